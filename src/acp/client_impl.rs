@@ -8,24 +8,79 @@
 //! `!Send` and everything runs on a single thread via `tokio::task::LocalSet`.
 
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsString;
 use std::io;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::sync::OnceLock;
 
 use agent_client_protocol::{
     Client, ContentBlock, CreateTerminalRequest, CreateTerminalResponse,
     KillTerminalCommandRequest, KillTerminalCommandResponse, PermissionOptionKind,
     ReadTextFileRequest, ReadTextFileResponse, ReleaseTerminalRequest, ReleaseTerminalResponse,
     RequestPermissionOutcome, RequestPermissionRequest, RequestPermissionResponse,
-    SelectedPermissionOutcome, SessionNotification, SessionUpdate, TerminalExitStatus, TerminalId,
-    TerminalOutputRequest, TerminalOutputResponse, ToolKind, WaitForTerminalExitRequest,
-    WaitForTerminalExitResponse, WriteTextFileRequest, WriteTextFileResponse,
+    SelectedPermissionOutcome, SessionId, SessionNotification, SessionUpdate, TerminalExitStatus,
+    TerminalId, TerminalOutputRequest, TerminalOutputResponse, ToolCallStatus, ToolKind,
+    WaitForTerminalExitRequest, WaitForTerminalExitResponse, WriteTextFileRequest,
+    WriteTextFileResponse,
 };
 
 use crate::acp::streaming::{self, RenderState};
 use crate::acp::tools::{self, SessionUpdateMsg, TerminalSession};
+use crate::acp::types::HarnessCommandResult;
+use crate::guardrails::{self, LineScanner};
+use crate::offline;
+use crate::output::formatter;
+use crate::project::{Permission, PermissionsConfig, SecurityConfig};
+use crate::terminal_env;
+use crate::transcripts::{Recorder, TerminalRecord};
+
+static SECURITY: OnceLock<SecurityConfig> = OnceLock::new();
+
+/// Install the project's `[security]` settings for every client created
+/// afterwards. Later calls are ignored.
+pub fn configure_security(config: &SecurityConfig) {
+    let _ = SECURITY.set(config.clone());
+}
+
+static PERMISSIONS: OnceLock<PermissionsConfig> = OnceLock::new();
+
+/// Install the project's `[permissions]` for every client created
+/// afterwards. Later calls are ignored.
+pub fn configure_permissions(config: &PermissionsConfig) {
+    let _ = PERMISSIONS.set(*config);
+}
+
+/// Limits on tool use within one session. Zero disables a limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ToolLimits {
+    /// Tool calls allowed before the turn is cancelled.
+    pub max_tool_calls: u32,
+    /// Back-to-back runs of the same terminal command, with no file write in
+    /// between, before further runs are refused. The turn is cancelled at
+    /// twice this many.
+    pub max_repeated_commands: u32,
+}
+
+/// Tool use observed so far in the session.
+#[derive(Debug, Default)]
+struct ToolUsage {
+    tool_calls: u32,
+    last_command: Option<String>,
+    repeats: u32,
+    /// Why the turn should be cancelled, once a limit is exceeded.
+    stop: Option<String>,
+}
+
+/// A terminal being recorded for the task transcript.
+struct TerminalMeta {
+    command: String,
+    cwd: String,
+    started_at: chrono::DateTime<chrono::Utc>,
+    started: std::time::Instant,
+    exit_code: Option<i32>,
+}
 
 /// Ralph's implementation of the ACP [`Client`] trait.
 ///
@@ -46,13 +101,25 @@ struct PendingToolCall {
 pub struct RalphClient {
     /// Project root directory; paths are resolved relative to this.
     project_root: PathBuf,
+    /// Task working directory under the project root. When set, relative
+    /// request paths and terminal commands resolve against it instead.
+    working_dir: Option<PathBuf>,
     /// Active terminal sessions, keyed by terminal ID string.
     terminals: Rc<RefCell<HashMap<String, TerminalSession>>>,
     /// Accumulated agent message text for post-session sigil extraction.
     text_accumulator: Rc<RefCell<String>>,
+    /// Accumulated agent thought text, kept apart from the answer.
+    thought_accumulator: Rc<RefCell<String>>,
     /// File paths modified via `write_text_file`, normalized to be
     /// project-relative.
     files_modified: Rc<RefCell<Vec<String>>>,
+    /// File paths read via `read_text_file`, normalized like
+    /// `files_modified`, each listed once.
+    files_read: Rc<RefCell<Vec<String>>>,
+    /// Content of each written file before its first write this session
+    /// (`None` if it did not exist), keyed like `files_modified`. Only
+    /// filled while the TUI is active, for the Diff panel.
+    original_contents: Rc<RefCell<HashMap<String, Option<String>>>>,
     /// If `true`, file write requests are rejected.
     read_only: bool,
     /// If set, only these absolute paths may be written. All other writes
@@ -71,6 +138,31 @@ pub struct RalphClient {
     pending_tool_calls: Rc<RefCell<HashMap<String, PendingToolCall>>>,
     /// Tracks whether we are inside a multi-line sigil tag.
     in_sigil: Rc<RefCell<Option<String>>>,
+    /// Table rows held back until the table ends, for column alignment.
+    table_rows: Rc<RefCell<Vec<String>>>,
+    /// Latest cumulative session cost in USD from `UsageUpdate` notifications.
+    reported_cost_usd: Rc<RefCell<Option<f64>>>,
+    /// Set while `session/load` replays an earlier conversation; updates are
+    /// dropped so old output is neither rendered nor scanned for sigils.
+    replaying: Rc<RefCell<bool>>,
+    /// File access outside the project root.
+    security: SecurityConfig,
+    /// Allow/deny/ask per tool kind for permission requests.
+    permissions: PermissionsConfig,
+    /// Tool-call budget and repeated-command threshold for this session.
+    tool_limits: ToolLimits,
+    tool_usage: Rc<RefCell<ToolUsage>>,
+    /// Tool calls started and not yet completed or failed.
+    tools_running: Rc<RefCell<HashSet<String>>>,
+    /// `[guardrails]` state over the agent's message text.
+    guard_scanner: Rc<RefCell<LineScanner>>,
+    /// `true` while a guardrail match waits for the user's answer. ACP serves
+    /// each request on its own task, so tool handlers wait on this instead.
+    guard_paused: Rc<tokio::sync::watch::Sender<bool>>,
+    /// Where released terminals are recorded; `None` records nothing.
+    transcript: Option<Recorder>,
+    /// Command details of the terminals being recorded, keyed like `terminals`.
+    terminal_meta: Rc<RefCell<HashMap<String, TerminalMeta>>>,
 }
 
 impl RalphClient {
@@ -134,9 +226,46 @@ impl RalphClient {
         path.starts_with(self.canonical_project_root())
     }
 
+    /// Return true when `[security]` lets the agent touch `path`: inside the
+    /// project root, under an `allowed_paths` entry, or unrestricted.
+    fn path_permitted(&self, path: &Path, write: bool) -> bool {
+        if self.security.allow_outside_root
+            || (!write && self.security.allow_reads_outside_root)
+            || self.is_within_project_root(path)
+        {
+            return true;
+        }
+        self.security.allowed_paths.iter().any(|entry| {
+            let entry = match entry.strip_prefix("~/") {
+                Some(rest) => match std::env::var_os("HOME") {
+                    Some(home) => PathBuf::from(home).join(rest),
+                    None => return false,
+                },
+                None => PathBuf::from(entry),
+            };
+            Self::canonicalize_allow_missing(&self.resolve_request_path(&entry))
+                .map(|allowed| path.starts_with(allowed))
+                .unwrap_or(false)
+        })
+    }
+
+    /// Directory the agent's relative paths and commands start from.
+    fn working_dir(&self) -> &Path {
+        self.working_dir.as_deref().unwrap_or(&self.project_root)
+    }
+
+    /// Resolve a path sent by the agent relative to the working directory.
+    fn resolve_agent_path(&self, path: &Path) -> PathBuf {
+        if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            self.working_dir().join(path)
+        }
+    }
+
     /// Resolve and validate an ACP filesystem path against project boundaries.
     fn resolve_path_for_fs(&self, req_path: &Path) -> io::Result<(PathBuf, PathBuf)> {
-        let resolved = self.resolve_request_path(req_path);
+        let resolved = self.resolve_agent_path(req_path);
         let canonical = Self::canonicalize_allow_missing(&resolved)?;
         Ok((resolved, canonical))
     }
@@ -194,9 +323,13 @@ impl RalphClient {
     pub fn new(project_root: PathBuf, read_only: bool, model_name: String) -> Self {
         Self {
             project_root,
+            working_dir: None,
             terminals: Rc::new(RefCell::new(HashMap::new())),
             text_accumulator: Rc::new(RefCell::new(String::new())),
+            thought_accumulator: Rc::new(RefCell::new(String::new())),
             files_modified: Rc::new(RefCell::new(Vec::new())),
+            files_read: Rc::new(RefCell::new(Vec::new())),
+            original_contents: Rc::new(RefCell::new(HashMap::new())),
             read_only,
             allowed_write_paths: None,
             model_name,
@@ -205,6 +338,18 @@ impl RalphClient {
             in_code_block: Rc::new(RefCell::new(false)),
             pending_tool_calls: Rc::new(RefCell::new(HashMap::new())),
             in_sigil: Rc::new(RefCell::new(None)),
+            table_rows: Rc::new(RefCell::new(Vec::new())),
+            reported_cost_usd: Rc::new(RefCell::new(None)),
+            replaying: Rc::new(RefCell::new(false)),
+            security: SECURITY.get().cloned().unwrap_or_default(),
+            permissions: PERMISSIONS.get().copied().unwrap_or_default(),
+            tool_limits: ToolLimits::default(),
+            tool_usage: Rc::new(RefCell::new(ToolUsage::default())),
+            tools_running: Rc::new(RefCell::new(HashSet::new())),
+            guard_scanner: Rc::new(RefCell::new(LineScanner::default())),
+            guard_paused: Rc::new(tokio::sync::watch::Sender::new(false)),
+            transcript: None,
+            terminal_meta: Rc::new(RefCell::new(HashMap::new())),
         }
     }
 
@@ -216,6 +361,7 @@ impl RalphClient {
             line_buffer: Rc::clone(&self.line_buffer),
             in_code_block: Rc::clone(&self.in_code_block),
             in_sigil: Rc::clone(&self.in_sigil),
+            table_rows: Rc::clone(&self.table_rows),
         }
     }
 
@@ -250,12 +396,128 @@ impl RalphClient {
         self
     }
 
+    /// Resolve relative paths and run terminal commands in `dir` (a task's
+    /// working directory) instead of the project root.
+    pub fn with_working_dir(mut self, dir: PathBuf) -> Self {
+        self.working_dir = Some(dir);
+        self
+    }
+
+    /// Record every terminal into a task's transcript when it is released.
+    pub fn with_transcript(mut self, recorder: Recorder) -> Self {
+        self.transcript = Some(recorder);
+        self
+    }
+
+    /// Enforce a tool-call budget and repeated-command threshold.
+    pub fn with_tool_limits(mut self, limits: ToolLimits) -> Self {
+        self.tool_limits = limits;
+        self
+    }
+
+    /// Why the current turn should be cancelled, once a tool limit is exceeded.
+    pub fn stop_requested(&self) -> Option<String> {
+        self.tool_usage.borrow().stop.clone()
+    }
+
+    /// Whether no tool call is running, so the turn can be cancelled without
+    /// cutting a command or edit off halfway.
+    pub fn at_safe_point(&self) -> bool {
+        self.tools_running.borrow().is_empty()
+    }
+
+    /// Count one tool call against the budget.
+    fn record_tool_call(&self) {
+        let max = self.tool_limits.max_tool_calls;
+        let mut usage = self.tool_usage.borrow_mut();
+        usage.tool_calls += 1;
+        if max > 0 && usage.tool_calls > max && usage.stop.is_none() {
+            usage.stop = Some(format!("tool-call budget of {max} exceeded"));
+        }
+    }
+
+    /// Track a terminal command, returning a steering notice for the agent
+    /// when the same command keeps being re-run with no edits in between.
+    fn check_repeated_command(&self, command: &str) -> Result<(), String> {
+        let max = self.tool_limits.max_repeated_commands;
+        let mut usage = self.tool_usage.borrow_mut();
+        if usage.last_command.as_deref() == Some(command) {
+            usage.repeats += 1;
+        } else {
+            usage.last_command = Some(command.to_string());
+            usage.repeats = 1;
+        }
+        if max == 0 || usage.repeats <= max {
+            return Ok(());
+        }
+        if usage.repeats > max * 2 && usage.stop.is_none() {
+            usage.stop = Some(format!(
+                "`{command}` repeated {} times without file changes",
+                usage.repeats
+            ));
+        }
+        Err(format!(
+            "ralph: `{command}` has already run {max} times in a row with no file changes in \
+             between, so it will not run again. Stop retrying it: change your approach, or \
+             emit <task-failed> explaining what is blocking you."
+        ))
+    }
+
+    /// Scan message text against `[guardrails]`. On a match, wait for the
+    /// user's answer; a refusal stops the turn like an exceeded tool limit.
+    async fn check_guardrails(&self, text: &str) {
+        let guards = guardrails::guards();
+        if guards.is_empty() || self.read_only {
+            return;
+        }
+        // One prompt at a time: later text waits for the open one.
+        let _ = self.wait_for_guardrail().await;
+        if self.tool_usage.borrow().stop.is_some() {
+            return;
+        }
+        let hit = self.guard_scanner.borrow_mut().push(guards, text);
+        if let Some(hit) = hit {
+            self.guard_paused.send_replace(true);
+            if !guardrails::approve(&hit).await {
+                self.tool_usage.borrow_mut().stop = Some(format!(
+                    "guardrail `{}` matched and was not approved: {}",
+                    hit.pattern, hit.excerpt
+                ));
+            }
+            self.guard_paused.send_replace(false);
+        }
+    }
+
+    /// Hold a tool request while a guardrail approval is open. Once the user
+    /// declines, returns the stop reason so the request is refused.
+    async fn wait_for_guardrail(&self) -> Result<(), String> {
+        let mut paused = self.guard_paused.subscribe();
+        if !*paused.borrow_and_update() {
+            return Ok(());
+        }
+        let _ = paused.wait_for(|paused| !paused).await;
+        match self.stop_requested() {
+            Some(reason) => Err(format!("ralph: turn stopped: {reason}")),
+            None => Ok(()),
+        }
+    }
+
+    /// Ignore session updates while an earlier session is being loaded.
+    pub fn set_replaying(&self, replaying: bool) {
+        *self.replaying.borrow_mut() = replaying;
+    }
+
     /// Take and return all accumulated agent text, leaving the accumulator empty.
     pub fn take_accumulated_text(&self) -> String {
         let mut acc = self.text_accumulator.borrow_mut();
         std::mem::take(&mut *acc)
     }
 
+    /// Take and return all accumulated thought text, leaving the accumulator empty.
+    pub fn take_accumulated_thoughts(&self) -> String {
+        std::mem::take(&mut *self.thought_accumulator.borrow_mut())
+    }
+
     /// Read accumulated agent text without draining the accumulator.
     ///
     /// Used by interactive sessions to check for exit sigils after each
@@ -264,12 +526,84 @@ impl RalphClient {
         self.text_accumulator.borrow().clone()
     }
 
+    /// Take the session cost reported by the agent, if any.
+    pub fn take_reported_cost_usd(&self) -> Option<f64> {
+        self.reported_cost_usd.borrow_mut().take()
+    }
+
     /// Take and return the list of files modified, leaving it empty.
     pub fn take_files_modified(&self) -> Vec<String> {
         let mut files = self.files_modified.borrow_mut();
         std::mem::take(&mut *files)
     }
 
+    /// Take and return the list of files read, leaving it empty.
+    pub fn take_files_read(&self) -> Vec<String> {
+        let mut files = self.files_read.borrow_mut();
+        std::mem::take(&mut *files)
+    }
+
+    /// Run a command an agent without terminal support asked for with
+    /// `<run-command>`. It goes through [`Client::create_terminal`] like a
+    /// terminal the agent opens itself, so the repeat limit, offline mode,
+    /// command approval, secret masking and the transcript all apply.
+    pub async fn run_harness_command(
+        &self,
+        session_id: &SessionId,
+        command: &str,
+    ) -> HarnessCommandResult {
+        self.record_tool_call();
+        let request = CreateTerminalRequest::new(session_id.clone(), "sh")
+            .args(vec!["-c".to_string(), command.to_string()]);
+        let terminal_id = match Client::create_terminal(self, request).await {
+            Ok(resp) => resp.terminal_id,
+            Err(e) => {
+                return HarnessCommandResult {
+                    command: command.to_string(),
+                    exit_code: None,
+                    output: e
+                        .data
+                        .as_ref()
+                        .and_then(|d| d.as_str())
+                        .map(str::to_string)
+                        .unwrap_or(e.message),
+                }
+            }
+        };
+        let exit_code = Client::wait_for_terminal_exit(
+            self,
+            WaitForTerminalExitRequest::new(session_id.clone(), terminal_id.clone()),
+        )
+        .await
+        .ok()
+        .and_then(|resp| resp.exit_status.exit_code);
+        let output = Client::terminal_output(
+            self,
+            TerminalOutputRequest::new(session_id.clone(), terminal_id.clone()),
+        )
+        .await
+        .map(|resp| resp.output)
+        .unwrap_or_default();
+        let _ = Client::release_terminal(
+            self,
+            ReleaseTerminalRequest::new(session_id.clone(), terminal_id),
+        )
+        .await;
+        formatter::emit_event_info(
+            "agent",
+            &format!(
+                "ran `{}` for the agent ({})",
+                terminal_env::current().mask(command),
+                exit_code.map_or("no exit code".to_string(), |c| format!("exit {c}"))
+            ),
+        );
+        HarnessCommandResult {
+            command: command.to_string(),
+            exit_code,
+            output,
+        }
+    }
+
     /// Kill all active terminal sessions and remove them from the map.
     ///
     /// Called during cleanup to prevent orphaned subprocesses after an
@@ -277,15 +611,41 @@ impl RalphClient {
     /// is killed and its reader tasks are aborted.
     pub async fn cleanup_all_terminals(&self) {
         // Drain the map first so we don't hold the borrow across await points.
-        let sessions: Vec<tools::TerminalSession> = {
+        let sessions: Vec<(String, tools::TerminalSession)> = {
             let mut terminals = self.terminals.borrow_mut();
-            terminals.drain().map(|(_, v)| v).collect()
+            terminals.drain().collect()
         };
-        for session in sessions {
+        for (terminal_id, mut session) in sessions {
+            self.record_terminal(&terminal_id, &mut session).await;
             tools::release_terminal(session).await;
         }
     }
 
+    /// Append a terminal about to be released to the task transcript.
+    async fn record_terminal(&self, terminal_id: &str, session: &mut TerminalSession) {
+        let Some(recorder) = &self.transcript else {
+            return;
+        };
+        let Some(meta) = self.terminal_meta.borrow_mut().remove(terminal_id) else {
+            return;
+        };
+        let settled = tools::settle(session).await;
+        let (stdout, stderr) = tools::recorded_output(session);
+        let record = TerminalRecord {
+            iteration: recorder.iteration(),
+            command: meta.command,
+            cwd: meta.cwd,
+            started_at: meta.started_at.to_rfc3339(),
+            duration_ms: meta.started.elapsed().as_millis() as u64,
+            exit_code: meta.exit_code.or(settled),
+            stdout,
+            stderr,
+        };
+        if let Err(e) = recorder.append(&record) {
+            formatter::print_warning(&format!("Failed to record terminal transcript: {e:#}"));
+        }
+    }
+
     /// Normalize `path` to be project-relative.
     ///
     /// If `path` is under `project_root`, strips the prefix and returns the
@@ -313,6 +673,37 @@ impl RalphClient {
     fn is_write_kind(kind: &ToolKind) -> bool {
         matches!(kind, ToolKind::Edit | ToolKind::Delete | ToolKind::Move)
     }
+
+    /// The configured `[permissions]` entry for `kind`; kinds without an
+    /// entry (reads, searches, ...) are allowed.
+    fn permission_for(&self, kind: &ToolKind) -> Permission {
+        match kind {
+            ToolKind::Edit => self.permissions.edit,
+            ToolKind::Delete => self.permissions.delete,
+            ToolKind::Move => self.permissions.r#move,
+            ToolKind::Execute => self.permissions.execute,
+            ToolKind::Fetch => self.permissions.fetch,
+            _ => Permission::Allow,
+        }
+    }
+
+    /// Answer a permission request with its reject option, or `Cancelled`
+    /// when the agent offered none.
+    fn reject(req: &RequestPermissionRequest) -> RequestPermissionResponse {
+        let reject_option = req.options.iter().find(|opt| {
+            matches!(
+                opt.kind,
+                PermissionOptionKind::RejectOnce | PermissionOptionKind::RejectAlways
+            )
+        });
+        let outcome = match reject_option {
+            Some(opt) => RequestPermissionOutcome::Selected(SelectedPermissionOutcome::new(
+                opt.option_id.clone(),
+            )),
+            None => RequestPermissionOutcome::Cancelled,
+        };
+        RequestPermissionResponse::new(outcome)
+    }
 }
 
 #[async_trait::async_trait(?Send)]
@@ -327,33 +718,40 @@ impl Client for RalphClient {
     ) -> agent_client_protocol::Result<RequestPermissionResponse> {
         use agent_client_protocol::Error;
 
-        // In read-only mode, deny write-typed tool calls.
-        if self.read_only {
-            if let Some(kind) = req.tool_call.fields.kind.as_ref() {
-                if Self::is_write_kind(kind) {
-                    // Find a reject option to return, or fall back to Cancelled.
-                    let reject_option = req.options.iter().find(|opt| {
-                        matches!(
-                            opt.kind,
-                            PermissionOptionKind::RejectOnce | PermissionOptionKind::RejectAlways
-                        )
-                    });
-
-                    let outcome = if let Some(opt) = reject_option {
-                        RequestPermissionOutcome::Selected(SelectedPermissionOutcome::new(
-                            opt.option_id.clone(),
-                        ))
-                    } else {
-                        // No explicit reject option — use Cancelled to deny.
-                        RequestPermissionOutcome::Cancelled
-                    };
-
-                    return Ok(RequestPermissionResponse::new(outcome));
+        if self.wait_for_guardrail().await.is_err() {
+            return Ok(Self::reject(&req));
+        }
+        if let Some(kind) = req.tool_call.fields.kind.as_ref() {
+            // In read-only mode, deny write-typed tool calls.
+            if self.read_only && Self::is_write_kind(kind) {
+                return Ok(Self::reject(&req));
+            }
+            let what = req
+                .tool_call
+                .fields
+                .title
+                .clone()
+                .unwrap_or_else(|| req.tool_call.tool_call_id.0.to_string());
+            let kind_name = format!("{kind:?}").to_lowercase();
+            match self.permission_for(kind) {
+                Permission::Allow => {}
+                Permission::Deny => {
+                    formatter::emit_event(
+                        "guard",
+                        &format!("{kind_name} denied by [permissions]: {what}"),
+                        true,
+                    );
+                    return Ok(Self::reject(&req));
+                }
+                Permission::Ask => {
+                    if !guardrails::approve_tool_call(&kind_name, &what).await {
+                        return Ok(Self::reject(&req));
+                    }
                 }
             }
         }
 
-        // Normal mode (or read-only mode for non-write operations): auto-approve.
+        // Allowed by the read-only and [permissions] checks: auto-approve.
         // Pick the first AllowOnce option, then any allow option, or fail if none.
         let allow_option = req
             .options
@@ -392,6 +790,9 @@ impl Client for RalphClient {
         &self,
         notification: SessionNotification,
     ) -> agent_client_protocol::Result<()> {
+        if *self.replaying.borrow() {
+            return Ok(());
+        }
         let state = self.render_state();
 
         match notification.update {
@@ -406,10 +807,12 @@ impl Client for RalphClient {
                         &SessionUpdateMsg::AgentText(text.to_owned()),
                         &state,
                     );
+                    self.check_guardrails(text).await;
                 }
             }
             SessionUpdate::AgentThoughtChunk(chunk) => {
                 if let Some(text) = Self::content_block_text(&chunk.content) {
+                    self.thought_accumulator.borrow_mut().push_str(text);
                     streaming::render_session_update(
                         &SessionUpdateMsg::AgentThought(text.to_owned()),
                         &state,
@@ -417,6 +820,15 @@ impl Client for RalphClient {
                 }
             }
             SessionUpdate::ToolCall(tool_call) => {
+                self.record_tool_call();
+                if !matches!(
+                    tool_call.status,
+                    ToolCallStatus::Completed | ToolCallStatus::Failed
+                ) {
+                    self.tools_running
+                        .borrow_mut()
+                        .insert(tool_call.tool_call_id.0.as_ref().to_owned());
+                }
                 let name = tool_call.title.clone();
                 let input = tool_call
                     .raw_input
@@ -461,6 +873,12 @@ impl Client for RalphClient {
             }
             SessionUpdate::ToolCallUpdate(update) => {
                 let tool_call_id = update.tool_call_id.0.as_ref().to_owned();
+                if matches!(
+                    update.fields.status,
+                    Some(ToolCallStatus::Completed | ToolCallStatus::Failed)
+                ) {
+                    self.tools_running.borrow_mut().remove(&tool_call_id);
+                }
 
                 if let Some(ref raw_input) = update.fields.raw_input {
                     let mut pending = self.pending_tool_calls.borrow_mut();
@@ -500,6 +918,15 @@ impl Client for RalphClient {
             SessionUpdate::Plan(_) => {
                 // Plan updates are internal agent state; visible work shows via text + tool calls.
             }
+            SessionUpdate::UsageUpdate(update) => {
+                // The cost is cumulative for the session; keep the latest USD figure.
+                if let Some(cost) = update
+                    .cost
+                    .filter(|c| c.currency.eq_ignore_ascii_case("USD"))
+                {
+                    *self.reported_cost_usd.borrow_mut() = Some(cost.amount);
+                }
+            }
             // CurrentModeUpdate, ConfigOptionUpdate, etc. are
             // silently accepted — no rendering needed for these.
             _ => {}
@@ -534,9 +961,15 @@ impl Client for RalphClient {
             }
         };
 
-        if !self.is_within_project_root(&canonical) {
+        if !self.path_permitted(&canonical, false) {
             return Err(Error::invalid_params().data(serde_json::json!(format!(
-                "file path is outside project root: {}",
+                "file path is outside project root: {} (see security.allowed_paths)",
+                req.path.display()
+            ))));
+        }
+        if terminal_env::current().is_secrets_file(&canonical) {
+            return Err(Error::invalid_params().data(serde_json::json!(format!(
+                "reading the secrets file is not allowed: {}",
                 req.path.display()
             ))));
         }
@@ -573,6 +1006,13 @@ impl Client for RalphClient {
                 .join("\n")
         };
 
+        // Track the path for knowledge matching, once per session.
+        let normalized = self.normalize_path(&canonical);
+        let mut files_read = self.files_read.borrow_mut();
+        if !files_read.contains(&normalized) {
+            files_read.push(normalized);
+        }
+
         Ok(ReadTextFileResponse::new(result))
     }
 
@@ -587,6 +1027,9 @@ impl Client for RalphClient {
                 "write_text_file is not allowed in read-only mode"
             )));
         }
+        if let Err(reason) = self.wait_for_guardrail().await {
+            return Err(Error::invalid_params().data(serde_json::json!(reason)));
+        }
 
         let (resolved, canonical) = match self.resolve_path_for_fs(&req.path) {
             Ok(p) => p,
@@ -598,9 +1041,9 @@ impl Client for RalphClient {
             }
         };
 
-        if !self.is_within_project_root(&canonical) {
+        if !self.path_permitted(&canonical, true) {
             return Err(Error::invalid_params().data(serde_json::json!(format!(
-                "write not allowed outside project root: {}",
+                "write not allowed outside project root: {} (see security.allowed_paths)",
                 req.path.display()
             ))));
         }
@@ -630,6 +1073,15 @@ impl Client for RalphClient {
             }
         }
 
+        let normalized = self.normalize_path(&canonical);
+        let track_diff = crate::ui::is_active();
+        if track_diff {
+            self.original_contents
+                .borrow_mut()
+                .entry(normalized.clone())
+                .or_insert_with(|| std::fs::read_to_string(&canonical).ok());
+        }
+
         // Write the file.
         if let Err(e) = std::fs::write(&canonical, &req.content) {
             return Err(Error::internal_error().data(serde_json::json!(format!(
@@ -638,9 +1090,16 @@ impl Client for RalphClient {
             ))));
         }
 
+        if track_diff {
+            let originals = self.original_contents.borrow();
+            let before = originals.get(&normalized).and_then(|c| c.as_deref());
+            streaming::emit_file_diff(&normalized, before, &req.content);
+        }
+
         // Track the path (normalized to project-relative).
-        let normalized = self.normalize_path(&canonical);
         self.files_modified.borrow_mut().push(normalized);
+        // An edit makes re-running the previous command legitimate again.
+        self.tool_usage.borrow_mut().last_command = None;
 
         Ok(WriteTextFileResponse::new())
     }
@@ -655,14 +1114,69 @@ impl Client for RalphClient {
     ) -> agent_client_protocol::Result<CreateTerminalResponse> {
         use agent_client_protocol::Error;
 
+        if let Err(reason) = self.wait_for_guardrail().await {
+            return Err(Error::invalid_params().data(serde_json::json!(reason)));
+        }
         let (program, args) = Self::parse_terminal_request(&req)?;
-        let (terminal_id, session) = tools::create_terminal(&program, &args, &self.project_root)
-            .map_err(|e| {
-                Error::internal_error().data(serde_json::json!(format!(
-                    "failed to spawn terminal command '{}': {e}",
-                    program
-                )))
-            })?;
+        let command = std::iter::once(program.as_str())
+            .chain(args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if let Err(notice) = self.check_repeated_command(&command) {
+            return Err(Error::invalid_params().data(serde_json::json!(notice)));
+        }
+        let cwd = match &req.cwd {
+            Some(dir) => self.resolve_agent_path(dir),
+            None => self.working_dir().to_path_buf(),
+        };
+        let relative_cwd = match self.normalize_path(&cwd) {
+            rel if rel.is_empty() => ".".to_string(),
+            rel => rel,
+        };
+        if let Some(pattern) = offline::network_access(&command) {
+            formatter::emit_event(
+                "offline",
+                &format!(
+                    "refused network command `{}` (matched `{pattern}`)",
+                    terminal_env::current().mask(&command)
+                ),
+                true,
+            );
+            return Err(Error::invalid_params().data(serde_json::json!(
+                "Ralph is running offline and this command looks like network access. \
+                 Do not retry it; work with what is available locally."
+            )));
+        }
+        if guardrails::commands_need_approval()
+            && !guardrails::approve_command(&command, &relative_cwd).await
+        {
+            return Err(Error::invalid_params().data(serde_json::json!(
+                "The user declined to run this command. Do not retry it; use another \
+                 approach or explain in your reply why it is needed."
+            )));
+        }
+        let (terminal_id, session) =
+            tools::create_terminal(&program, &args, &cwd, terminal_env::current().vars()).map_err(
+                |e| {
+                    Error::internal_error().data(serde_json::json!(format!(
+                        "failed to spawn terminal command '{}': {e}",
+                        program
+                    )))
+                },
+            )?;
+
+        if self.transcript.is_some() {
+            self.terminal_meta.borrow_mut().insert(
+                terminal_id.clone(),
+                TerminalMeta {
+                    command,
+                    cwd: relative_cwd,
+                    started_at: chrono::Utc::now(),
+                    started: std::time::Instant::now(),
+                    exit_code: None,
+                },
+            );
+        }
 
         // Store the session in the map.
         self.terminals
@@ -686,7 +1200,8 @@ impl Client for RalphClient {
             )))
         })?;
 
-        let output = tools::read_terminal_output(session);
+        // Secret values injected into the command never reach the agent
+        let output = terminal_env::current().mask(&tools::read_terminal_output(session));
         // `truncated` is false because we drain the full buffer.
         Ok(TerminalOutputResponse::new(output, false))
     }
@@ -709,6 +1224,9 @@ impl Client for RalphClient {
             })?;
 
         let exit_code = tools::wait_for_exit(&mut session).await;
+        if let Some(meta) = self.terminal_meta.borrow_mut().get_mut(&terminal_id) {
+            meta.exit_code = (exit_code >= 0).then_some(exit_code);
+        }
         self.terminals.borrow_mut().insert(terminal_id, session);
 
         // Build exit status; exit_code is i32 from tools.rs (-1 = signal killed).
@@ -751,7 +1269,7 @@ impl Client for RalphClient {
         use agent_client_protocol::Error;
 
         let terminal_id = req.terminal_id.0.as_ref().to_owned();
-        let session = self
+        let mut session = self
             .terminals
             .borrow_mut()
             .remove(&terminal_id)
@@ -761,6 +1279,7 @@ impl Client for RalphClient {
                 )))
             })?;
 
+        self.record_terminal(&terminal_id, &mut session).await;
         tools::release_terminal(session).await;
 
         Ok(ReleaseTerminalResponse::new())
@@ -997,6 +1516,48 @@ mod tests {
         assert!(!outside_file.exists(), "outside file should not be created");
     }
 
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_security_allowed_paths_and_read_opt_in() {
+        let tmp = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        let listed = outside.path().join("config");
+        let unlisted = outside.path().join("other.txt");
+        std::fs::write(&unlisted, "secret").unwrap();
+
+        let mut client = make_client(&tmp, false);
+        client.security = SecurityConfig {
+            allowed_paths: vec![listed.display().to_string()],
+            ..Default::default()
+        };
+        let req = WriteTextFileRequest::new(SessionId::new("s"), listed.join("app.toml"), "ok");
+        client.write_text_file(req).await.unwrap();
+        assert!(listed.join("app.toml").exists());
+
+        let req = WriteTextFileRequest::new(SessionId::new("s"), &unlisted, "nope");
+        assert!(client.write_text_file(req).await.is_err());
+        let req = ReadTextFileRequest::new(SessionId::new("s"), &unlisted);
+        assert!(client.read_text_file(req).await.is_err());
+
+        let mut client = make_client(&tmp, false);
+        client.security = SecurityConfig {
+            allow_reads_outside_root: true,
+            ..Default::default()
+        };
+        let req = ReadTextFileRequest::new(SessionId::new("s"), &unlisted);
+        assert_eq!(client.read_text_file(req).await.unwrap().content, "secret");
+        let req = WriteTextFileRequest::new(SessionId::new("s"), &unlisted, "nope");
+        assert!(client.write_text_file(req).await.is_err());
+
+        let mut client = make_client(&tmp, false);
+        client.security = SecurityConfig {
+            allow_outside_root: true,
+            ..Default::default()
+        };
+        let req = WriteTextFileRequest::new(SessionId::new("s"), &unlisted, "changed");
+        client.write_text_file(req).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&unlisted).unwrap(), "changed");
+    }
+
     #[tokio::test(flavor = "current_thread")]
     async fn test_write_text_file_parent_traversal_rejected() {
         let tmp = TempDir::new().unwrap();
@@ -1099,10 +1660,135 @@ mod tests {
         }
     }
 
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_request_permission_follows_configured_permissions() {
+        let tmp = TempDir::new().unwrap();
+        let mut client = make_client(&tmp, false);
+        client.permissions.delete = Permission::Deny;
+
+        let options = || vec![make_allow_option("allow-1"), make_reject_option("reject-1")];
+        let selected = |resp: RequestPermissionResponse| match resp.outcome {
+            RequestPermissionOutcome::Selected(sel) => sel.option_id.0.to_string(),
+            other => panic!("expected Selected outcome, got: {other:?}"),
+        };
+
+        let req = make_permission_request(options(), Some(ToolKind::Delete));
+        let resp = client.request_permission(req).await.unwrap();
+        assert_eq!(selected(resp), "reject-1");
+
+        let req = make_permission_request(options(), Some(ToolKind::Edit));
+        let resp = client.request_permission(req).await.unwrap();
+        assert_eq!(selected(resp), "allow-1");
+
+        // A denied kind with no reject option is cancelled
+        let req =
+            make_permission_request(vec![make_allow_option("allow-1")], Some(ToolKind::Delete));
+        let resp = client.request_permission(req).await.unwrap();
+        assert!(matches!(resp.outcome, RequestPermissionOutcome::Cancelled));
+    }
+
     // ------------------------------------------------------------------ //
     // Terminal tests                                                        //
     // ------------------------------------------------------------------ //
 
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_repeated_command_is_refused_then_stops_turn() {
+        let tmp = TempDir::new().unwrap();
+        with_local_set!(async {
+            let client = make_client(&tmp, false).with_tool_limits(ToolLimits {
+                max_tool_calls: 0,
+                max_repeated_commands: 2,
+            });
+            let run = |cmd: &str| {
+                client.create_terminal(CreateTerminalRequest::new(SessionId::new("s"), cmd))
+            };
+
+            assert!(run("true").await.is_ok());
+            assert!(run("true").await.is_ok());
+            let err = run("true").await.unwrap_err();
+            assert!(format!("{err:?}").contains("will not run again"));
+            assert_eq!(client.stop_requested(), None);
+
+            // A file write in between resets the streak.
+            let path = tmp.path().join("fix.rs");
+            client
+                .write_text_file(WriteTextFileRequest::new(SessionId::new("s"), &path, "x"))
+                .await
+                .unwrap();
+            assert!(run("true").await.is_ok());
+            assert!(run("true").await.is_ok());
+            for _ in 0..3 {
+                assert!(run("true").await.is_err());
+            }
+            assert!(client
+                .stop_requested()
+                .unwrap()
+                .contains("repeated 5 times"));
+            client.cleanup_all_terminals().await;
+        });
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_guardrail_pause_holds_tool_requests() {
+        let tmp = TempDir::new().unwrap();
+        let client = make_client(&tmp, false);
+        let path = tmp.path().join("held.txt");
+
+        // Approved: the held write goes through once the pause clears.
+        client.guard_paused.send_replace(true);
+        let write =
+            client.write_text_file(WriteTextFileRequest::new(SessionId::new("s"), &path, "x"));
+        let answer = async {
+            tokio::task::yield_now().await;
+            assert!(!path.exists(), "write must wait for the answer");
+            client.guard_paused.send_replace(false);
+        };
+        let (written, ()) = tokio::join!(write, answer);
+        assert!(written.is_ok());
+        assert!(path.exists());
+
+        // Declined: held requests are refused.
+        client.guard_paused.send_replace(true);
+        let write = client.write_text_file(WriteTextFileRequest::new(
+            SessionId::new("s"),
+            tmp.path().join("refused.txt"),
+            "x",
+        ));
+        let permission = client.request_permission(make_permission_request(
+            vec![make_allow_option("allow"), make_reject_option("reject")],
+            Some(ToolKind::Edit),
+        ));
+        let answer = async {
+            tokio::task::yield_now().await;
+            client.tool_usage.borrow_mut().stop = Some("guardrail declined".into());
+            client.guard_paused.send_replace(false);
+        };
+        let (written, permission, ()) = tokio::join!(write, permission, answer);
+        assert!(format!("{:?}", written.unwrap_err()).contains("guardrail declined"));
+        assert!(!tmp.path().join("refused.txt").exists());
+        assert!(matches!(
+            permission.unwrap().outcome,
+            RequestPermissionOutcome::Selected(ref s) if s.option_id.0.as_ref() == "reject"
+        ));
+    }
+
+    #[test]
+    fn test_tool_call_budget() {
+        let tmp = TempDir::new().unwrap();
+        let client = make_client(&tmp, false).with_tool_limits(ToolLimits {
+            max_tool_calls: 2,
+            max_repeated_commands: 0,
+        });
+        client.record_tool_call();
+        client.record_tool_call();
+        assert_eq!(client.stop_requested(), None);
+        client.record_tool_call();
+        assert_eq!(
+            client.stop_requested().as_deref(),
+            Some("tool-call budget of 2 exceeded")
+        );
+    }
+
     #[tokio::test(flavor = "current_thread")]
     async fn test_create_terminal_and_output() {
         let tmp = TempDir::new().unwrap();
@@ -1133,6 +1819,41 @@ mod tests {
         });
     }
 
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_working_dir_resolves_paths_and_terminal_cwd() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::create_dir_all(tmp.path().join("packages/api")).unwrap();
+        std::fs::write(tmp.path().join("packages/api/Cargo.toml"), "[package]\n").unwrap();
+        with_local_set!(async {
+            let client = make_client(&tmp, false).with_working_dir(tmp.path().join("packages/api"));
+
+            let req = ReadTextFileRequest::new(SessionId::new("s"), "Cargo.toml");
+            let resp = client.read_text_file(req).await.unwrap();
+            assert_eq!(resp.content, "[package]\n");
+            let req = ReadTextFileRequest::new(SessionId::new("s"), "Cargo.toml");
+            client.read_text_file(req).await.unwrap();
+            assert_eq!(
+                client.take_files_read(),
+                vec!["packages/api/Cargo.toml".to_string()]
+            );
+
+            let req = WriteTextFileRequest::new(SessionId::new("s"), "src/lib.rs", "");
+            client.write_text_file(req).await.unwrap();
+            assert!(tmp.path().join("packages/api/src/lib.rs").exists());
+            assert_eq!(
+                client.take_files_modified(),
+                vec!["packages/api/src/lib.rs".to_string()]
+            );
+
+            let req = CreateTerminalRequest::new(SessionId::new("s"), "ls");
+            let terminal_id = client.create_terminal(req).await.unwrap().terminal_id;
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            let req = TerminalOutputRequest::new(SessionId::new("s"), terminal_id);
+            let output = client.terminal_output(req).await.unwrap().output;
+            assert!(output.contains("Cargo.toml"), "{output:?}");
+        });
+    }
+
     #[tokio::test(flavor = "current_thread")]
     async fn test_kill_terminal() {
         let tmp = TempDir::new().unwrap();
@@ -1182,6 +1903,44 @@ mod tests {
         });
     }
 
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_released_terminals_are_recorded() {
+        let tmp = TempDir::new().unwrap();
+        with_local_set!(async {
+            let client =
+                make_client(&tmp, false).with_transcript(Recorder::for_task(tmp.path(), "t-1", 3));
+
+            let req = CreateTerminalRequest::new(
+                SessionId::new("s"),
+                "sh -c 'echo out; echo err >&2; exit 3'",
+            );
+            let terminal_id = client.create_terminal(req).await.unwrap().terminal_id;
+            let req = WaitForTerminalExitRequest::new(SessionId::new("s"), terminal_id.clone());
+            client.wait_for_terminal_exit(req).await.unwrap();
+            // The agent reading the output does not drain the recording
+            let req = TerminalOutputRequest::new(SessionId::new("s"), terminal_id.clone());
+            client.terminal_output(req).await.unwrap();
+            let req = ReleaseTerminalRequest::new(SessionId::new("s"), terminal_id);
+            client.release_terminal(req).await.unwrap();
+
+            // Terminals left running are recorded at cleanup, without an exit code
+            let req = CreateTerminalRequest::new(SessionId::new("s"), "sleep 60");
+            client.create_terminal(req).await.unwrap();
+            client.cleanup_all_terminals().await;
+        });
+
+        let records = crate::transcripts::load(tmp.path(), "t-1").unwrap();
+        assert_eq!(records.len(), 2, "{records:?}");
+        assert_eq!(records[0].iteration, 3);
+        assert_eq!(records[0].command, "sh -c echo out; echo err >&2; exit 3");
+        assert_eq!(records[0].cwd, ".");
+        assert_eq!(records[0].exit_code, Some(3));
+        assert_eq!(records[0].stdout, "out\n");
+        assert_eq!(records[0].stderr, "err\n");
+        assert_eq!(records[1].command, "sleep 60");
+        assert_eq!(records[1].exit_code, None);
+    }
+
     #[tokio::test(flavor = "current_thread")]
     async fn test_terminal_not_found_returns_error() {
         let tmp = TempDir::new().unwrap();
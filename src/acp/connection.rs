@@ -7,9 +7,10 @@
 //!
 //! ACP futures are `!Send`; all connection logic runs inside a `tokio::task::LocalSet`.
 
+use std::ops::ControlFlow;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 /// Options for restricting an ACP session's capabilities.
 ///
@@ -25,26 +26,35 @@ pub struct SessionRestrictions {
 }
 
 use agent_client_protocol::{
-    Agent, AuthenticateRequest, CancelNotification, ClientCapabilities, ClientSideConnection,
-    ContentBlock, FileSystemCapability, Implementation, InitializeRequest, NewSessionRequest,
-    PromptRequest, ProtocolVersion, StopReason, TextContent,
+    Agent, AgentCapabilities, AuthenticateRequest, CancelNotification, ClientCapabilities,
+    ClientSideConnection, ContentBlock, FileSystemCapability, Implementation, InitializeRequest,
+    LoadSessionRequest, NewSessionRequest, PromptRequest, PromptResponse, ProtocolVersion,
+    SessionId, StopReason, TextContent,
 };
 use anyhow::{anyhow, Result};
 use tokio::task::LocalSet;
 use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
 
-use crate::acp::client_impl::RalphClient;
+use crate::acp::client_impl::{RalphClient, ToolLimits};
 use crate::acp::prompt;
-use crate::acp::streaming::flush_stdout;
-use crate::acp::types::{IterationContext, RunResult, StreamingResult};
+use crate::acp::sigils;
+use crate::acp::streaming::{self, flush_stdout};
+use crate::acp::types::{
+    HarnessCommandResult, IterationContext, RunResult, StreamingResult, TokenUsage,
+};
 use crate::config::Config;
 use crate::interrupt;
 use crate::output::formatter;
+use crate::steering;
+use crate::transcripts::Recorder;
 
 /// Inputs for executing one ACP session lifecycle.
 struct RunAcpSessionParams {
     agent_command: String,
     project_root: PathBuf,
+    /// Task working directory; the session, terminals and relative paths
+    /// start here instead of the project root.
+    working_dir: Option<PathBuf>,
     iteration: u32,
     total: u32,
     model: String,
@@ -52,6 +62,28 @@ struct RunAcpSessionParams {
     read_only: bool,
     model_override: Option<String>,
     restrictions: SessionRestrictions,
+    /// Hard limit for the whole session; `None` waits indefinitely.
+    timeout: Option<Duration>,
+    /// Earlier session to load instead of starting a new one.
+    resume_session: Option<String>,
+    tool_limits: ToolLimits,
+    /// Record terminal sessions into the task's transcript.
+    transcript: Option<Recorder>,
+}
+
+/// How long to wait for the agent to acknowledge a timeout cancel before
+/// tearing the process down.
+const CANCEL_GRACE: Duration = Duration::from_secs(5);
+
+/// Most rounds of `<run-command>` requests one session may make.
+const MAX_HARNESS_ROUNDS: usize = 50;
+
+/// How the prompt request ended.
+enum PromptOutcome {
+    Responded(Result<PromptResponse>),
+    Interrupted,
+    TimedOut,
+    ToolLimitHit(String),
 }
 
 /// Check if an ACP error looks like an authentication failure and return
@@ -73,6 +105,63 @@ pub(crate) fn auth_hint(err: &dyn std::fmt::Display) -> Option<String> {
     }
 }
 
+/// Substrings (lowercase) of agent errors that another attempt may not hit:
+/// the agent process died, its pipes broke, or the model API was briefly
+/// unavailable.
+const TRANSIENT_PATTERNS: &[&str] = &[
+    "server shut down unexpectedly",
+    "broken pipe",
+    "connection reset",
+    "connection closed",
+    "unexpected eof",
+    "timed out",
+    "overloaded",
+    "rate limit",
+    "temporarily unavailable",
+];
+
+/// An agent failure worth retrying, see [`TRANSIENT_PATTERNS`].
+#[derive(Debug)]
+pub struct TransientAgentError {
+    pub message: String,
+    /// ACP session the failed attempt was running, so a retry can resume it.
+    pub session_id: Option<String>,
+}
+
+impl std::fmt::Display for TransientAgentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for TransientAgentError {}
+
+/// Whether an agent error looks transient. Authentication errors never are.
+pub(crate) fn is_transient(err: &dyn std::fmt::Display) -> bool {
+    let msg = err.to_string().to_lowercase();
+    auth_hint(err).is_none() && TRANSIENT_PATTERNS.iter().any(|p| msg.contains(p))
+}
+
+/// Turn an ACP request error into the error `run_acp_session` returns:
+/// an auth hint, a [`TransientAgentError`], or `ACP <request> failed`.
+fn request_error(
+    request: &str,
+    err: agent_client_protocol::Error,
+    session_id: Option<&SessionId>,
+) -> anyhow::Error {
+    if let Some(hint) = auth_hint(&err) {
+        anyhow!("{hint}")
+    } else if is_transient(&err) {
+        TransientAgentError {
+            message: format!("ACP {request} failed: {err}"),
+            session_id: session_id.map(|id| id.to_string()),
+        }
+        .into()
+    } else {
+        anyhow!("ACP {request} failed: {err}")
+    }
+}
+
 // ============================================================================
 // Public API
 // ============================================================================
@@ -92,10 +181,16 @@ pub async fn run_iteration(config: &Config, context: &IterationContext) -> Resul
     // Build the full prompt text (system instructions + task context).
     // ACP has no separate system-prompt channel — everything goes in one TextContent block.
     let prompt_text = prompt::build_prompt_text(config, context);
+    streaming::start_meter(
+        config.iteration_timeout(),
+        &prompt_text,
+        crate::pricing::price_for(&current_model, &config.ralph_config.pricing),
+    );
 
     let local = LocalSet::new();
-    local
+    let result = local
         .run_until(run_acp_session(RunAcpSessionParams {
+            working_dir: context.task.cwd.as_ref().map(|dir| project_root.join(dir)),
             agent_command,
             project_root,
             iteration,
@@ -108,8 +203,18 @@ pub async fn run_iteration(config: &Config, context: &IterationContext) -> Resul
                 allow_terminal: true,
                 ..Default::default()
             },
+            timeout: config.iteration_timeout(),
+            resume_session: context.resume_session_id.clone(),
+            tool_limits: config.tool_limits(),
+            transcript: Some(Recorder::for_task(
+                &config.project_root,
+                &context.task.task_id,
+                iteration,
+            )),
         }))
-        .await
+        .await;
+    streaming::finish_meter();
+    result
 }
 
 /// Run a single autonomous prompt (for verification, review, and feature build).
@@ -141,6 +246,7 @@ pub async fn run_autonomous(
         .run_until(run_acp_session(RunAcpSessionParams {
             agent_command,
             project_root,
+            working_dir: None,
             iteration: 0,
             total: 0,
             model: model.clone().unwrap_or_else(|| "claude".to_owned()),
@@ -148,12 +254,18 @@ pub async fn run_autonomous(
             read_only,
             model_override: model,
             restrictions,
+            timeout: None,
+            resume_session: None,
+            tool_limits: ToolLimits::default(),
+            transcript: None,
         }))
         .await?;
 
     match result {
         RunResult::Completed(streaming_result) => Ok(streaming_result),
         RunResult::Interrupted => Err(anyhow!("autonomous session was interrupted")),
+        RunResult::TimedOut(_) => Err(anyhow!("autonomous session timed out")),
+        RunResult::ToolLimitHit(_, reason) => Err(anyhow!("autonomous session stopped: {reason}")),
     }
 }
 
@@ -189,6 +301,63 @@ async fn poll_interrupt() {
     }
 }
 
+/// Poll the client every 100 ms until it asks for the turn to be cancelled.
+async fn poll_tool_limits(client: &RalphClient) -> String {
+    loop {
+        if let Some(reason) = client.stop_requested() {
+            return reason;
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    }
+}
+
+/// Poll every 100 ms until a steering message is queued and no tool call is
+/// running, then take the queued messages.
+async fn poll_steering(client: &RalphClient) -> Vec<String> {
+    loop {
+        if steering::has_pending() && client.at_safe_point() {
+            return steering::deliver();
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    }
+}
+
+/// Whether the agent can use the client's terminals. ACP has no standard
+/// field for this, so an agent opts out with `"terminal": false` in the
+/// `_meta` of its initialize capabilities.
+fn agent_supports_terminal(caps: &AgentCapabilities) -> bool {
+    caps.meta
+        .as_ref()
+        .and_then(|meta| meta.get("terminal"))
+        .and_then(|v| v.as_bool())
+        != Some(false)
+}
+
+/// Run a turn's `<run-command>` requests in order, stopping early once a
+/// tool limit is hit.
+async fn run_harness_commands(
+    client: &RalphClient,
+    session_id: &SessionId,
+    commands: &[String],
+) -> Vec<HarnessCommandResult> {
+    let mut results = Vec::new();
+    for command in commands {
+        results.push(client.run_harness_command(session_id, command).await);
+        if client.stop_requested().is_some() {
+            break;
+        }
+    }
+    results
+}
+
+/// Resolve at `deadline`, or never when there is none.
+async fn wait_for_deadline(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+        None => std::future::pending().await,
+    }
+}
+
 /// Inner async function that runs the full ACP session lifecycle inside a LocalSet.
 ///
 /// This is `async` (not `async fn spawn_local(...)`) so it can be driven directly
@@ -197,6 +366,7 @@ async fn run_acp_session(params: RunAcpSessionParams) -> Result<RunResult> {
     let RunAcpSessionParams {
         agent_command,
         project_root,
+        working_dir,
         iteration,
         total,
         model,
@@ -204,6 +374,10 @@ async fn run_acp_session(params: RunAcpSessionParams) -> Result<RunResult> {
         read_only,
         model_override,
         restrictions,
+        timeout,
+        resume_session,
+        tool_limits,
+        transcript,
     } = params;
 
     let start = Instant::now();
@@ -211,7 +385,8 @@ async fn run_acp_session(params: RunAcpSessionParams) -> Result<RunResult> {
     // ── 1. Parse + spawn agent process ────────────────────────────────────
     let (program, args) = parse_agent_command(&agent_command)?;
 
-    let ralph_model = model_override.as_deref().unwrap_or(&model);
+    let ralph_model =
+        crate::agent_models::resolve(&agent_command, model_override.as_deref().unwrap_or(&model));
 
     let mut child = tokio::process::Command::new(&program)
         .args(&args)
@@ -219,9 +394,10 @@ async fn run_acp_session(params: RunAcpSessionParams) -> Result<RunResult> {
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
         .current_dir(&project_root)
-        .env("RALPH_MODEL", ralph_model)
+        .env("RALPH_MODEL", &ralph_model)
         .env("RALPH_ITERATION", iteration.to_string())
         .env("RALPH_TOTAL", total.to_string())
+        .kill_on_drop(true)
         .spawn()
         .map_err(|e| anyhow!("failed to spawn agent '{program}': {e}"))?;
 
@@ -271,10 +447,18 @@ async fn run_acp_session(params: RunAcpSessionParams) -> Result<RunResult> {
     });
 
     // ── 3. Create RalphClient and wire up the ACP connection ──────────────
-    let mut ralph_client = RalphClient::new(project_root.clone(), read_only, model.clone());
+    let mut ralph_client = RalphClient::new(project_root.clone(), read_only, model.clone())
+        .with_tool_limits(tool_limits);
     if let Some(paths) = restrictions.allowed_write_paths {
         ralph_client = ralph_client.with_allowed_write_paths(paths);
     }
+    if let Some(dir) = &working_dir {
+        ralph_client = ralph_client.with_working_dir(dir.clone());
+    }
+    if let Some(recorder) = transcript {
+        ralph_client = ralph_client.with_transcript(recorder);
+    }
+    let session_cwd = working_dir.unwrap_or_else(|| project_root.clone());
     let client = Rc::new(ralph_client);
     let client_ref = Rc::clone(&client);
 
@@ -313,10 +497,7 @@ async fn run_acp_session(params: RunAcpSessionParams) -> Result<RunResult> {
     let init_resp = conn
         .initialize(init_req)
         .await
-        .map_err(|e| match auth_hint(&e) {
-            Some(hint) => anyhow!("{hint}"),
-            None => anyhow!("ACP initialize failed: {e}"),
-        })?;
+        .map_err(|e| request_error("initialize", e, None))?;
 
     formatter::print_info("  Initialized ACP client.");
     flush_stdout();
@@ -330,40 +511,187 @@ async fn run_acp_session(params: RunAcpSessionParams) -> Result<RunResult> {
             .await;
     }
 
-    // ── 5. Create session ─────────────────────────────────────────────────
-    let session_resp = conn
-        .new_session(NewSessionRequest::new(project_root.clone()))
-        .await
-        .map_err(|e| match auth_hint(&e) {
-            Some(hint) => anyhow!("{hint}"),
-            None => anyhow!("ACP new_session failed: {e}"),
-        })?;
+    // ── 5. Resume or create session ───────────────────────────────────────
+    let resumed = match resume_session {
+        Some(id) if init_resp.agent_capabilities.load_session => {
+            // The agent replays the old conversation as session updates; keep
+            // it out of the stream and the sigil accumulator.
+            client.set_replaying(true);
+            let loaded = conn
+                .load_session(LoadSessionRequest::new(id.clone(), session_cwd.clone()))
+                .await;
+            client.set_replaying(false);
+            match loaded {
+                Ok(_) => Some(SessionId::new(id)),
+                Err(e) => {
+                    formatter::print_warning(&format!(
+                        "ralph: could not resume session {id}, starting a new one: {e}"
+                    ));
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
 
-    let session_id = session_resp.session_id;
-    formatter::print_info("  ACP session ready.");
+    let session_id = match resumed {
+        Some(id) => {
+            formatter::print_info("  Resumed previous ACP session.");
+            id
+        }
+        None => {
+            let session_resp = conn
+                .new_session(NewSessionRequest::new(session_cwd.clone()))
+                .await
+                .map_err(|e| request_error("new_session", e, None))?;
+            formatter::print_info("  ACP session ready.");
+            session_resp.session_id
+        }
+    };
 
     // ── 6. Send prompt (racing against interrupt) ─────────────────────────
-    let prompt_req = PromptRequest::new(
-        session_id.clone(),
-        vec![ContentBlock::Text(TextContent::new(prompt_text))],
-    );
-
-    let prompt_result = tokio::select! {
-        result = conn.prompt(prompt_req) => {
-            match result {
-                Ok(resp) => Ok(resp),
-                Err(e) => Err(match auth_hint(&e) {
-                    Some(hint) => anyhow!("{hint}"),
-                    None => anyhow!("ACP prompt failed: {e}"),
-                }),
+    let mut prompt_text = prompt_text;
+    // An agent that cannot run terminals asks Ralph to run its commands
+    let harness_commands =
+        restrictions.allow_terminal && !agent_supports_terminal(&init_resp.agent_capabilities);
+    if harness_commands {
+        formatter::emit_event_info(
+            "agent",
+            "agent has no terminal support; Ralph runs its <run-command> requests",
+        );
+        prompt_text = format!(
+            "{prompt_text}\n\n{}",
+            prompt::build_harness_commands_section()
+        );
+    }
+    // Steering queued since the last session goes out with the first prompt.
+    let queued = steering::deliver();
+    if !queued.is_empty() {
+        prompt_text = format!("{prompt_text}\n\n{}", steering::render(&queued));
+    }
+    // Tokens of earlier turns: cut short to deliver steering messages, or
+    // answered with the output of requested commands.
+    let mut earlier_usage = TokenUsage::default();
+    let mut harness_rounds = 0;
+
+    let outcome = loop {
+        let turn_start = client.peek_accumulated_text().len();
+        let prompt_req = PromptRequest::new(
+            session_id.clone(),
+            vec![ContentBlock::Text(TextContent::new(prompt_text.clone()))],
+        );
+        let prompt_fut = conn.prompt(prompt_req);
+        tokio::pin!(prompt_fut);
+        // `Continue` carries steering messages for another turn.
+        let step = tokio::select! {
+            result = &mut prompt_fut => {
+                ControlFlow::Break(PromptOutcome::Responded(
+                    result.map_err(|e| request_error("prompt", e, Some(&session_id))),
+                ))
+            }
+            _ = poll_interrupt() => {
+                // User pressed Ctrl+C — send cancellation notification.
+                let _ = conn.cancel(CancelNotification::new(session_id.clone())).await;
+                ControlFlow::Break(PromptOutcome::Interrupted)
+            }
+            _ = wait_for_deadline(timeout.map(|t| start + t)) => {
+                let _ = conn.cancel(CancelNotification::new(session_id.clone())).await;
+                // Let the agent wind down its tool calls before the process is killed.
+                let _ = tokio::time::timeout(CANCEL_GRACE, &mut prompt_fut).await;
+                ControlFlow::Break(PromptOutcome::TimedOut)
             }
+            reason = poll_tool_limits(&client) => {
+                let _ = conn.cancel(CancelNotification::new(session_id.clone())).await;
+                let _ = tokio::time::timeout(CANCEL_GRACE, &mut prompt_fut).await;
+                ControlFlow::Break(PromptOutcome::ToolLimitHit(reason))
+            }
+            messages = poll_steering(&client) => {
+                // Between tool calls: cut the turn short and continue the
+                // same session with the user's message.
+                let _ = conn.cancel(CancelNotification::new(session_id.clone())).await;
+                if let Ok(Ok(resp)) = tokio::time::timeout(CANCEL_GRACE, &mut prompt_fut).await {
+                    earlier_usage.add_tokens(&TokenUsage::from_prompt_response(&resp));
+                }
+                ControlFlow::Continue(messages)
+            }
+        };
+        // Commands the agent asked Ralph to run go back as the next prompt
+        let requests = match &step {
+            ControlFlow::Break(PromptOutcome::Responded(Ok(resp)))
+                if harness_commands
+                    && resp.stop_reason == StopReason::EndTurn
+                    && harness_rounds < MAX_HARNESS_ROUNDS =>
+            {
+                let requests = sigils::parse_run_command_requests(
+                    &client.peek_accumulated_text()[turn_start..],
+                );
+                // Only a turn that is followed by another round is counted
+                // here; the final turn's usage is added once after the loop
+                if !requests.is_empty() {
+                    earlier_usage.add_tokens(&TokenUsage::from_prompt_response(resp));
+                }
+                requests
+            }
+            _ => Vec::new(),
+        };
+        if !requests.is_empty() {
+            harness_rounds += 1;
+            let results = tokio::select! {
+                results = run_harness_commands(&client, &session_id, &requests) => Some(results),
+                _ = poll_interrupt() => None,
+                _ = wait_for_deadline(timeout.map(|t| start + t)) => Some(Vec::new()),
+            };
+            match results {
+                None => break PromptOutcome::Interrupted,
+                Some(results) if results.is_empty() => break PromptOutcome::TimedOut,
+                Some(results) => prompt_text = prompt::render_harness_results(&results),
+            }
+            continue;
         }
-        _ = poll_interrupt() => {
-            // User pressed Ctrl+C — send cancellation notification.
-            let _ = conn.cancel(CancelNotification::new(session_id.clone())).await;
+        let messages = match step {
+            ControlFlow::Continue(messages) => messages,
+            // The agent finished its turn with a message still queued
+            ControlFlow::Break(PromptOutcome::Responded(Ok(ref resp)))
+                if resp.stop_reason == StopReason::EndTurn && steering::has_pending() =>
+            {
+                earlier_usage.add_tokens(&TokenUsage::from_prompt_response(resp));
+                steering::deliver()
+            }
+            ControlFlow::Break(outcome) => break outcome,
+        };
+        formatter::emit_event_info(
+            "steer",
+            &format!("delivered {} message(s) to the agent", messages.len()),
+        );
+        prompt_text = steering::render(&messages);
+    };
+
+    let prompt_result = match outcome {
+        PromptOutcome::Responded(result) => result,
+        PromptOutcome::Interrupted => {
             cleanup(conn, io_handle, stderr_handle, &client, child).await;
             return Ok(RunResult::Interrupted);
         }
+        PromptOutcome::TimedOut | PromptOutcome::ToolLimitHit(_) => {
+            let partial = StreamingResult {
+                full_text: client.take_accumulated_text(),
+                thoughts: client.take_accumulated_thoughts(),
+                files_modified: client.take_files_modified(),
+                files_read: client.take_files_read(),
+                duration_ms: start.elapsed().as_millis() as u64,
+                stop_reason: StopReason::Cancelled,
+                usage: TokenUsage {
+                    reported_cost_usd: client.take_reported_cost_usd(),
+                    ..Default::default()
+                },
+                session_id: Some(session_id.to_string()),
+            };
+            cleanup(conn, io_handle, stderr_handle, &client, child).await;
+            return Ok(match outcome {
+                PromptOutcome::ToolLimitHit(reason) => RunResult::ToolLimitHit(partial, reason),
+                _ => RunResult::TimedOut(partial),
+            });
+        }
     };
 
     // ── 7. Map stop reason → RunResult ────────────────────────────────────
@@ -371,14 +699,25 @@ async fn run_acp_session(params: RunAcpSessionParams) -> Result<RunResult> {
     let duration_ms = start.elapsed().as_millis() as u64;
 
     let full_text = client.take_accumulated_text();
+    let thoughts = client.take_accumulated_thoughts();
     let files_modified = client.take_files_modified();
+    let files_read = client.take_files_read();
+    let mut usage = TokenUsage {
+        reported_cost_usd: client.take_reported_cost_usd(),
+        ..TokenUsage::from_prompt_response(&prompt_resp)
+    };
+    usage.add_tokens(&earlier_usage);
 
     let run_result = match prompt_resp.stop_reason {
         StopReason::EndTurn => RunResult::Completed(StreamingResult {
             full_text,
+            thoughts,
             files_modified,
+            files_read,
             duration_ms,
             stop_reason: StopReason::EndTurn,
+            usage,
+            session_id: Some(session_id.to_string()),
         }),
         StopReason::Cancelled => {
             // The agent responded with Cancelled (e.g. from a prior cancel notification).
@@ -392,9 +731,13 @@ async fn run_acp_session(params: RunAcpSessionParams) -> Result<RunResult> {
             ));
             RunResult::Completed(StreamingResult {
                 full_text,
+                thoughts,
                 files_modified,
+                files_read,
                 duration_ms,
                 stop_reason: other,
+                usage,
+                session_id: Some(session_id.to_string()),
             })
         }
     };
@@ -605,4 +948,32 @@ mod tests {
         let err = "timeout waiting for response";
         assert!(auth_hint(&err).is_none());
     }
+
+    // ---- transient error tests ----------------------------------------------
+
+    #[test]
+    fn test_is_transient_matches_crashes_and_overload() {
+        assert!(is_transient(
+            &"Internal error: \"server shut down unexpectedly\""
+        ));
+        assert!(is_transient(&"Broken pipe (os error 32)"));
+        assert!(is_transient(&"API Error: 529 Overloaded"));
+        assert!(!is_transient(&"Invalid params: unknown field `foo`"));
+        assert!(!is_transient(&"auth failed: connection reset"));
+    }
+
+    #[test]
+    fn test_request_error_keeps_session_of_transient_failures() {
+        let session = SessionId::new("sess-1");
+        let crashed =
+            agent_client_protocol::Error::internal_error().data("server shut down unexpectedly");
+        let err = request_error("prompt", crashed, Some(&session));
+        let transient = err.downcast_ref::<TransientAgentError>().unwrap();
+        assert_eq!(transient.session_id.as_deref(), Some("sess-1"));
+        assert!(transient.message.starts_with("ACP prompt failed"));
+
+        let invalid = agent_client_protocol::Error::invalid_params();
+        let err = request_error("prompt", invalid, Some(&session));
+        assert!(err.downcast_ref::<TransientAgentError>().is_none());
+    }
 }
@@ -96,7 +96,9 @@ async fn read_multiline_input(stdin: &mut BufReader<tokio::io::Stdin>) -> Multil
         let hint = "Type your message. Empty line submits. Empty buffer exits. Ctrl+C interrupts.";
         return match ui::prompt_multiline("Interactive Prompt", hint) {
             Some(ui::UiPromptResult::Input(text)) => MultilineResult::Input(text),
-            Some(ui::UiPromptResult::Exit) => MultilineResult::Exit,
+            Some(ui::UiPromptResult::Exit | ui::UiPromptResult::Selected(_)) => {
+                MultilineResult::Exit
+            }
             Some(ui::UiPromptResult::Interrupted) => MultilineResult::Interrupted,
             None => MultilineResult::Exit,
         };
@@ -5,8 +5,9 @@
 //! - build_task_context()
 //! - build_prompt_text() (new: concatenates system + task context)
 
-use crate::acp::types::{IterationContext, TaskInfo};
+use crate::acp::types::{HarnessCommandResult, IterationContext, TaskInfo};
 use crate::config::Config;
+use crate::prompt_templates;
 
 /// Build the system instructions portion of a prompt.
 ///
@@ -49,6 +50,24 @@ After completing your work, signal the result:
 
 Emit one of these sigils every iteration with the task ID you were assigned.
 
+## Follow-up Work
+
+If you discover work outside your assigned task, queue it instead of doing it:
+
+- `<task-add title="Short title" priority="2" depends-on="t-abc123">Details</task-add>` — add a task (`priority` and `depends-on` are optional; without `title` the first line of the body is the title)
+- `<task-block reason="why">t-abc123</task-block>` — block a pending task that cannot proceed yet
+- `<dep-add blocker="t-abc123" blocked="t-def456"/>` — make one existing task wait for another
+
+Ralph validates each one against the task DAG after your turn and skips any that are invalid.
+
+## Artifacts
+
+If your task produces a file that later tasks build on (a generated schema, an API contract), register it:
+
+- `<artifact path="db/schema.sql">Orders schema</artifact>` — the path is relative to your working directory and must be a file inside the project; the description is optional
+
+Tasks that depend on yours see the path and the start of the file.
+
 ## Project Completion
 
 When the ENTIRE project/DAG is complete (not just your assigned task), output:
@@ -94,10 +113,39 @@ pub fn build_task_context(task: &TaskInfo) -> String {
     output.push_str("## Assigned Task\n\n");
     output.push_str(&format!("**ID:** {}\n", task.task_id));
     output.push_str(&format!("**Title:** {}\n", task.title));
+    if let Some(ref cwd) = task.cwd {
+        output.push_str(&format!(
+            "**Working Directory:** `{cwd}` (relative to the project root). Terminal \
+             commands run there and relative file paths resolve against it.\n"
+        ));
+    }
     output.push_str("\n### Description\n");
     output.push_str(&task.description);
     output.push('\n');
 
+    let meta = &task.frontmatter;
+    if !meta.criteria.is_empty() {
+        output.push_str("\n### Acceptance Criteria\n");
+        for criterion in &meta.criteria {
+            output.push_str(&format!("- {criterion}\n"));
+        }
+    }
+    if !meta.files.is_empty() {
+        output.push_str("\n### Affected Files\n");
+        for file in &meta.files {
+            output.push_str(&format!("- `{file}`\n"));
+        }
+    }
+    if !meta.links.is_empty() {
+        output.push_str("\n### Links\n");
+        for link in &meta.links {
+            output.push_str(&format!("- {link}\n"));
+        }
+    }
+    if !meta.labels.is_empty() {
+        output.push_str(&format!("\n**Labels:** {}\n", meta.labels.join(", ")));
+    }
+
     // Add parent context if present
     if let Some(ref parent) = task.parent {
         output.push_str("\n### Parent Context\n");
@@ -114,27 +162,113 @@ pub fn build_task_context(task: &TaskInfo) -> String {
                 "- [{}] {}: {}\n",
                 blocker.task_id, blocker.title, blocker.summary
             ));
+            for artifact in &blocker.artifacts {
+                output.push_str(&format!("  - Artifact `{}`", artifact.path));
+                if !artifact.description.is_empty() {
+                    output.push_str(&format!(": {}", artifact.description));
+                }
+                output.push('\n');
+                if !artifact.excerpt.is_empty() {
+                    output.push_str("    ```\n");
+                    for line in artifact.excerpt.lines() {
+                        output.push_str(&format!("    {line}\n"));
+                    }
+                    output.push_str("    ```\n");
+                }
+            }
         }
     }
 
     output
 }
 
+/// Most characters of one command's output sent back to an agent without
+/// terminal support; longer output keeps its end, where errors usually are.
+const HARNESS_OUTPUT_CHARS: usize = 16_000;
+
+/// Appended to the prompt of a session whose agent cannot run terminal
+/// commands, so it asks Ralph to run them instead of failing a tool call.
+pub fn build_harness_commands_section() -> String {
+    r#"## Running Commands
+
+You cannot run terminal commands in this session. Ralph runs them for you:
+emit one `<run-command>COMMAND</run-command>` per command, for example
+`<run-command>cargo test</run-command>`, then end your turn. Ralph runs them
+in order with `sh -c` in the working directory and replies with each exit code
+and output. Wherever these instructions say to run tests or other commands,
+request them this way. Emit no completion sigil in a turn that requests
+commands; wait for their results first."#
+        .to_string()
+}
+
+/// The reply to a turn's `<run-command>` requests.
+pub fn render_harness_results(results: &[HarnessCommandResult]) -> String {
+    let mut out = String::from("## Command Results\n");
+    for result in results {
+        let status = match result.exit_code {
+            Some(code) => format!("exit {code}"),
+            None => "did not finish".to_string(),
+        };
+        let output = result.output.trim_end();
+        let chars = output.chars().count();
+        let output: String = if chars > HARNESS_OUTPUT_CHARS {
+            let tail: String = output.chars().skip(chars - HARNESS_OUTPUT_CHARS).collect();
+            format!(
+                "[... {} earlier characters omitted]\n{tail}",
+                chars - HARNESS_OUTPUT_CHARS
+            )
+        } else {
+            output.to_string()
+        };
+        out.push_str(&format!(
+            "\n### `{}` ({status})\n\n```\n{output}\n```\n",
+            result.command
+        ));
+    }
+    out
+}
+
+/// Context window of the supported Claude models (opus, sonnet, haiku), in tokens.
+pub const CONTEXT_WINDOW_TOKENS: usize = 200_000;
+
+/// Rough token count for `text`: one token per four characters.
+///
+/// Good enough to notice a prompt that will not fit; not a tokenizer.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
 /// Build the full prompt text for an ACP iteration.
 ///
 /// Concatenates the system prompt instructions and task context into a single string,
 /// since ACP has no separate system prompt channel. The system prompt portion is
 /// placed first (Ralph loop instructions, sigil definitions, etc.), followed by
 /// task assignment, spec/plan, retry info, journal/knowledge context.
+///
+/// The system, task and memory sections can be overridden by the project's
+/// `.ralph/prompts/{system,task,memory}.md` templates.
 pub fn build_prompt_text(config: &Config, context: &IterationContext) -> String {
+    let task = &context.task;
+    let vars = [
+        ("task_id", task.task_id.as_str()),
+        ("task_title", task.title.as_str()),
+        ("task_description", task.description.as_str()),
+        ("spec", context.spec_content.as_deref().unwrap_or("")),
+        ("plan", context.plan_content.as_deref().unwrap_or("")),
+        ("journal", context.journal_context.as_str()),
+        ("knowledge", context.knowledge_context.as_str()),
+    ];
+    let section = |name: &str, default: String| {
+        prompt_templates::apply(&config.project_root, name, default, &vars)
+    };
     let mut prompt = String::new();
 
     // Start with system instructions
-    prompt.push_str(&build_system_instructions(config));
+    prompt.push_str(&section("system", build_system_instructions(config)));
 
     // Append iteration context
     prompt.push_str("\n\n");
-    prompt.push_str(&build_task_context(&context.task));
+    prompt.push_str(&section("task", build_task_context(task)));
 
     if let Some(ref spec) = context.spec_content {
         prompt.push_str("\n## Feature Specification\n\n");
@@ -156,15 +290,46 @@ pub fn build_prompt_text(config: &Config, context: &IterationContext) -> String
         ));
         prompt.push_str("The previous attempt failed verification with the following reason:\n\n");
         prompt.push_str(&format!("> {}\n\n", retry.previous_failure_reason));
+        if let Some(ref output) = retry.previous_output {
+            prompt.push_str("The previous attempt ended with this output:\n\n");
+            prompt.push_str(&format!("```\n{}\n```\n\n", output.trim_end()));
+            prompt.push_str("Build on that work instead of starting over.\n");
+        }
+        if let Some(ref thoughts) = retry.previous_thoughts {
+            prompt.push_str("\nIts reasoning, condensed (most recent last):\n\n");
+            prompt.push_str(&format!("```text\n{}\n```\n\n", thoughts.trim_end()));
+        }
         prompt.push_str("Fix the issues identified above before marking the task as done.\n");
     }
 
+    if let Some(ref analysis) = context.speculative_analysis {
+        prompt.push_str("\n## Prior Analysis\n\n");
+        prompt.push_str(
+            "A read-only session explored this task while the previous one was being verified. \
+             Files may have changed since; confirm what it says before relying on it.\n\n",
+        );
+        prompt.push_str(analysis.trim_end());
+        prompt.push('\n');
+    }
+
     // Run Journal section (pre-rendered markdown from journal::render_journal_context)
     if !context.journal_context.is_empty() {
         prompt.push('\n');
         prompt.push_str(&context.journal_context);
     }
 
+    // Scratchpad section (pre-rendered markdown from scratchpad::render_scratchpad_context)
+    if !context.scratchpad_context.is_empty() {
+        prompt.push('\n');
+        prompt.push_str(&context.scratchpad_context);
+    }
+
+    // Changes So Far section (pre-rendered markdown from diff_context::render_diff_context)
+    if !context.diff_context.is_empty() {
+        prompt.push('\n');
+        prompt.push_str(&context.diff_context);
+    }
+
     // Project Knowledge section (pre-rendered markdown from knowledge::render_knowledge_context)
     if !context.knowledge_context.is_empty() {
         prompt.push('\n');
@@ -172,6 +337,15 @@ pub fn build_prompt_text(config: &Config, context: &IterationContext) -> String
     }
 
     // Memory Instructions section — always included
+    prompt.push_str(&section("memory", build_memory_instructions(config)));
+
+    prompt
+}
+
+/// The memory section of the iteration prompt: journal, knowledge and
+/// custom sigil instructions.
+fn build_memory_instructions(config: &Config) -> String {
+    let mut prompt = String::new();
     prompt.push_str("\n## Memory\n\n");
     prompt.push_str(
         "You have access to a persistent memory system. Use these sigils to record knowledge:\n\n",
@@ -188,6 +362,9 @@ pub fn build_prompt_text(config: &Config, context: &IterationContext) -> String
     prompt.push_str("```\n<knowledge tags=\"tag1,tag2\" title=\"Short descriptive title\">\nDetailed explanation of the knowledge. Maximum ~500 words.\n</knowledge>\n```\n\n");
     prompt
         .push_str("Tags should be lowercase, relevant keywords. At least one tag is required.\n\n");
+    prompt.push_str(&crate::sigil_actions::render_prompt_section(
+        &config.ralph_config.sigils.custom,
+    ));
     prompt.push_str("#### Bidirectional Linking (Roam protocol)\n\n");
     prompt.push_str(
         "Knowledge entries support `[[Title]]` links to other entries. Use these to create\n",
@@ -217,7 +394,7 @@ pub fn build_prompt_text(config: &Config, context: &IterationContext) -> String
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::acp::types::{BlockerContext, ParentContext};
+    use crate::acp::types::{ArtifactContext, BlockerContext, ParentContext, RetryInfo};
     use crate::project::{ProjectConfig, RalphConfig};
     use std::path::PathBuf;
 
@@ -235,6 +412,7 @@ mod tests {
             None,
             false,
             None,
+            None,
         )
         .unwrap()
     }
@@ -358,6 +536,8 @@ mod tests {
             task_id: "t-abc123".to_string(),
             title: "Implement feature X".to_string(),
             description: "Add the new feature X to the codebase.".to_string(),
+            frontmatter: Default::default(),
+            cwd: None,
             parent: Some(ParentContext {
                 title: "Epic Y".to_string(),
                 description: "The larger epic Y that encompasses this task.".to_string(),
@@ -367,11 +547,17 @@ mod tests {
                     task_id: "t-prereq1".to_string(),
                     title: "Setup foundation".to_string(),
                     summary: "Created the base structure".to_string(),
+                    artifacts: vec![ArtifactContext {
+                        path: "db/schema.sql".to_string(),
+                        description: "Orders schema".to_string(),
+                        excerpt: "CREATE TABLE orders (\n  id INTEGER\n);".to_string(),
+                    }],
                 },
                 BlockerContext {
                     task_id: "t-prereq2".to_string(),
                     title: "Add dependencies".to_string(),
                     summary: "Installed required packages".to_string(),
+                    artifacts: vec![],
                 },
             ],
         };
@@ -387,16 +573,53 @@ mod tests {
         assert!(output.contains("**Parent:** Epic Y"));
         assert!(output.contains("The larger epic Y that encompasses this task."));
         assert!(output.contains("### Completed Prerequisites"));
-        assert!(output.contains("- [t-prereq1] Setup foundation: Created the base structure"));
+        assert!(output.contains(
+            "- [t-prereq1] Setup foundation: Created the base structure\n  \
+             - Artifact `db/schema.sql`: Orders schema\n    ```\n    CREATE TABLE orders (\n"
+        ));
         assert!(output.contains("- [t-prereq2] Add dependencies: Installed required packages"));
     }
 
+    #[test]
+    fn task_context_renders_frontmatter_fields() {
+        let task = TaskInfo {
+            task_id: "t-fm0001".to_string(),
+            title: "Orders endpoint".to_string(),
+            description: "Add GET /orders/{id}.".to_string(),
+            frontmatter: crate::dag::TaskFrontmatter {
+                criteria: vec!["Returns 404 for unknown ids".to_string()],
+                files: vec!["src/api/orders.rs".to_string()],
+                links: vec!["https://example.com/42".to_string()],
+                labels: vec!["api".to_string(), "backend".to_string()],
+            },
+            cwd: None,
+            parent: None,
+            completed_blockers: vec![],
+        };
+
+        let output = build_task_context(&task);
+        assert!(output.contains(
+            "Add GET /orders/{id}.\n\n### Acceptance Criteria\n- Returns 404 for unknown ids\n\n\
+             ### Affected Files\n- `src/api/orders.rs`\n\n### Links\n- https://example.com/42\n\n\
+             **Labels:** api, backend\n"
+        ));
+
+        let plain = build_task_context(&TaskInfo {
+            frontmatter: Default::default(),
+            ..task
+        });
+        assert!(!plain.contains("### Affected Files"));
+        assert!(!plain.contains("**Labels:**"));
+    }
+
     #[test]
     fn task_context_no_parent_omits_parent_section() {
         let task = TaskInfo {
             task_id: "t-xyz789".to_string(),
             title: "Standalone task".to_string(),
             description: "A task with no parent.".to_string(),
+            frontmatter: Default::default(),
+            cwd: None,
             parent: None,
             completed_blockers: vec![],
         };
@@ -422,6 +645,8 @@ mod tests {
             task_id: "t-def456".to_string(),
             title: "Initial task".to_string(),
             description: "A task with no prerequisites.".to_string(),
+            frontmatter: Default::default(),
+            cwd: None,
             parent: Some(ParentContext {
                 title: "Parent task".to_string(),
                 description: "Parent description.".to_string(),
@@ -446,17 +671,21 @@ mod tests {
             task_id: "t-multi".to_string(),
             title: "Task with multiple blockers".to_string(),
             description: "Depends on two tasks.".to_string(),
+            frontmatter: Default::default(),
+            cwd: None,
             parent: None,
             completed_blockers: vec![
                 BlockerContext {
                     task_id: "t-blocker1".to_string(),
                     title: "First blocker".to_string(),
                     summary: "Completed first".to_string(),
+                    artifacts: vec![],
                 },
                 BlockerContext {
                     task_id: "t-blocker2".to_string(),
                     title: "Second blocker".to_string(),
                     summary: "Completed second".to_string(),
+                    artifacts: vec![],
                 },
             ],
         };
@@ -468,12 +697,31 @@ mod tests {
         assert!(output.contains("- [t-blocker2] Second blocker: Completed second"));
     }
 
+    #[test]
+    fn task_context_names_the_working_directory() {
+        let task = TaskInfo {
+            task_id: "t-api".to_string(),
+            title: "Add endpoint".to_string(),
+            description: "In the API package.".to_string(),
+            frontmatter: Default::default(),
+            cwd: Some("packages/api".to_string()),
+            parent: None,
+            completed_blockers: vec![],
+        };
+
+        let output = build_task_context(&task);
+
+        assert!(output.contains("**Working Directory:** `packages/api`"));
+    }
+
     #[test]
     fn task_context_verbatim_fields() {
         let task = TaskInfo {
             task_id: "t-verbatim-123".to_string(),
             title: "Special chars: <>&\"'".to_string(),
             description: "Description with\nnewlines and\ttabs.".to_string(),
+            frontmatter: Default::default(),
+            cwd: None,
             parent: None,
             completed_blockers: vec![],
         };
@@ -485,6 +733,14 @@ mod tests {
         assert!(output.contains("Description with\nnewlines and\ttabs."));
     }
 
+    #[test]
+    fn estimate_tokens_counts_characters() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+        assert_eq!(estimate_tokens(&"é".repeat(8)), 2);
+    }
+
     // --- Memory / Journal / Knowledge system prompt tests ---
 
     fn test_iteration_context(journal_context: &str, knowledge_context: &str) -> IterationContext {
@@ -493,6 +749,8 @@ mod tests {
                 task_id: "t-test01".to_string(),
                 title: "Test task".to_string(),
                 description: "Test description".to_string(),
+                frontmatter: Default::default(),
+                cwd: None,
                 parent: None,
                 completed_blockers: vec![],
             },
@@ -502,9 +760,52 @@ mod tests {
             run_id: "run-00000001".to_string(),
             journal_context: journal_context.to_string(),
             knowledge_context: knowledge_context.to_string(),
+            scratchpad_context: String::new(),
+            diff_context: String::new(),
+            speculative_analysis: None,
+            resume_session_id: None,
         }
     }
 
+    #[test]
+    fn test_retry_prompt_carries_previous_output() {
+        let config = test_config();
+        let mut ctx = test_iteration_context("", "");
+        ctx.retry_info = Some(RetryInfo {
+            attempt: 2,
+            max_retries: 3,
+            previous_failure_reason: "tests fail".to_string(),
+            previous_output: Some("Added the parser; lexer still TODO.\n".to_string()),
+            previous_thoughts: None,
+        });
+        let prompt = build_prompt_text(&config, &ctx);
+        assert!(prompt.contains("> tests fail"));
+        assert!(prompt.contains("```\nAdded the parser; lexer still TODO.\n```"));
+        assert!(!prompt.contains("reasoning, condensed"));
+
+        ctx.retry_info.as_mut().unwrap().previous_thoughts =
+            Some("The lexer needs a peek method.".to_string());
+        let prompt = build_prompt_text(&config, &ctx);
+        assert!(prompt.contains("```text\nThe lexer needs a peek method.\n```"));
+
+        ctx.retry_info.as_mut().unwrap().previous_output = None;
+        let prompt = build_prompt_text(&config, &ctx);
+        assert!(!prompt.contains("ended with this output"));
+    }
+
+    #[test]
+    fn test_prompt_includes_speculative_analysis() {
+        let config = test_config();
+        let mut ctx = test_iteration_context("", "");
+        let prompt = build_prompt_text(&config, &ctx);
+        assert!(!prompt.contains("## Prior Analysis"));
+
+        ctx.speculative_analysis = Some("Edit src/api.rs; reuse Router.\n".to_string());
+        let prompt = build_prompt_text(&config, &ctx);
+        assert!(prompt.contains("## Prior Analysis"));
+        assert!(prompt.contains("Edit src/api.rs; reuse Router.\n"));
+    }
+
     #[test]
     fn test_system_prompt_includes_journal() {
         let config = test_config();
@@ -532,6 +833,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_system_prompt_includes_scratchpad_after_journal() {
+        let config = test_config();
+        let mut ctx = test_iteration_context("## Run Journal\n\nentries\n", "");
+        ctx.scratchpad_context = crate::scratchpad::render_scratchpad_context(
+            ".ralph/scratch/auth.md",
+            "- retry flaky test\n",
+        );
+        let prompt = build_prompt_text(&config, &ctx);
+        let journal = prompt.find("## Run Journal").unwrap();
+        let scratch = prompt.find("## Scratchpad").unwrap();
+        assert!(journal < scratch);
+        assert!(prompt.contains("- retry flaky test"));
+
+        ctx.diff_context = "## Changes So Far\n\n src/auth.rs | 2 +-\n".to_string();
+        let prompt = build_prompt_text(&config, &ctx);
+        assert!(prompt.find("## Scratchpad").unwrap() < prompt.find("## Changes So Far").unwrap());
+
+        ctx.scratchpad_context = String::new();
+        assert!(!build_prompt_text(&config, &ctx).contains("## Scratchpad"));
+    }
+
     #[test]
     fn test_system_prompt_includes_knowledge() {
         let config = test_config();
@@ -635,4 +958,47 @@ mod tests {
         assert_eq!(ctx.journal_context, "journal content");
         assert_eq!(ctx.knowledge_context, "knowledge content");
     }
+
+    #[test]
+    fn test_project_templates_override_prompt_sections() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let prompts = dir.path().join(crate::prompt_templates::PROMPTS_DIR);
+        std::fs::create_dir_all(&prompts).unwrap();
+        std::fs::write(
+            prompts.join("task.md"),
+            "{{default}}\n### Team Standards\nWrite `{{task_title}}` in idiomatic Go.\n",
+        )
+        .unwrap();
+        std::fs::write(prompts.join("memory.md"), "\n## Memory\n\n{{knowledge}}\n").unwrap();
+        let mut config = test_config();
+        config.project_root = dir.path().to_path_buf();
+
+        let prompt = build_prompt_text(&config, &test_iteration_context("", "Use sqlx."));
+        assert!(prompt.contains("**Title:** Test task\n"));
+        assert!(prompt.contains("### Team Standards\nWrite `Test task` in idiomatic Go.\n"));
+        assert!(prompt.contains("ONE TASK PER LOOP"));
+        assert!(prompt.ends_with("\n## Memory\n\nUse sqlx.\n"));
+        assert!(!prompt.contains("<journal>"));
+    }
+
+    #[test]
+    fn test_render_harness_results_keeps_output_tail() {
+        let results = vec![
+            HarnessCommandResult {
+                command: "cargo test".to_string(),
+                exit_code: Some(101),
+                output: format!("{}error: tests failed\n", "x".repeat(HARNESS_OUTPUT_CHARS)),
+            },
+            HarnessCommandResult {
+                command: "sleep 999".to_string(),
+                exit_code: None,
+                output: String::new(),
+            },
+        ];
+        let text = render_harness_results(&results);
+        assert!(text.contains("### `cargo test` (exit 101)"));
+        assert!(text.contains("[... 19 earlier characters omitted]"));
+        assert!(text.contains("error: tests failed\n```"));
+        assert!(text.contains("### `sleep 999` (did not finish)"));
+    }
 }
@@ -3,7 +3,7 @@
 //! Migrated from `src/claude/events.rs` with a new `extract_sigils()` combinator
 //! that calls all individual parsers and assembles a `SigilResult`.
 
-use crate::acp::types::{KnowledgeSigil, SigilResult};
+use crate::acp::types::{ArtifactSigil, CustomSigilMatch, KnowledgeSigil, QueueSigil, SigilResult};
 
 /// Sigil for the COMPLETE promise.
 #[allow(dead_code)]
@@ -12,16 +12,37 @@ pub const COMPLETE_SIGIL: &str = "<promise>COMPLETE</promise>";
 /// Sigil for the FAILURE promise.
 pub const FAILURE_SIGIL: &str = "<promise>FAILURE</promise>";
 
-/// Valid model names for the `<next-model>` sigil.
-const VALID_MODELS: &[&str] = &["opus", "sonnet", "haiku"];
-
 /// Valid phase names for the `<phase-complete>` sigil.
 const VALID_PHASES: &[&str] = &["spec", "plan", "build"];
 
+/// Tags ralph parses itself; `[sigils.custom]` entries cannot reuse them.
+pub const BUILTIN_TAGS: &[&str] = &[
+    "promise",
+    "task-done",
+    "task-failed",
+    "next-model",
+    "journal",
+    "knowledge",
+    "phase-complete",
+    "tasks-created",
+    "task-dag",
+    "rubric-scores",
+    "review-blocking",
+    "review-notes",
+    "verify-pass",
+    "verify-fail",
+    "task-add",
+    "task-block",
+    "dep-add",
+    "artifact",
+    "run-command",
+];
+
 /// Parse the `<next-model>...</next-model>` sigil from result text.
 ///
-/// Returns `Some(model)` if a valid model name is found between the tags,
-/// `None` if the sigil is absent or contains an invalid model name.
+/// Returns `Some(model)` if a valid model name or alias (`strong`, `balanced`,
+/// `fast`) is found between the tags, as its tier name; `None` if the sigil
+/// is absent or contains an invalid model name.
 pub fn parse_next_model_hint(text: &str) -> Option<String> {
     let start_tag = "<next-model>";
     let end_tag = "</next-model>";
@@ -31,11 +52,7 @@ pub fn parse_next_model_hint(text: &str) -> Option<String> {
     let end_idx = text[content_start..].find(end_tag)?;
     let model = text[content_start..content_start + end_idx].trim();
 
-    if VALID_MODELS.contains(&model) {
-        Some(model.to_string())
-    } else {
-        None
-    }
+    crate::agent_models::tier(model).map(str::to_string)
 }
 
 /// Parse the `<task-done>...</task-done>` sigil from result text.
@@ -58,6 +75,30 @@ pub fn parse_task_done(text: &str) -> Option<String> {
     }
 }
 
+/// Parse every `<run-command>...</run-command>` sigil, in order.
+///
+/// Agents without terminal support use these to have Ralph run commands for
+/// them. Empty commands are skipped; whitespace is trimmed.
+pub fn parse_run_command_requests(text: &str) -> Vec<String> {
+    let start_tag = "<run-command>";
+    let end_tag = "</run-command>";
+
+    let mut commands = Vec::new();
+    let mut rest = text;
+    while let Some(start_idx) = rest.find(start_tag) {
+        let content_start = start_idx + start_tag.len();
+        let Some(end_idx) = rest[content_start..].find(end_tag) else {
+            break;
+        };
+        let command = rest[content_start..content_start + end_idx].trim();
+        if !command.is_empty() {
+            commands.push(command.to_string());
+        }
+        rest = &rest[content_start + end_idx + end_tag.len()..];
+    }
+    commands
+}
+
 /// Parse the `<task-failed>...</task-failed>` sigil from result text.
 ///
 /// Returns `Some(task_id)` if a task ID is found between the tags,
@@ -195,7 +236,7 @@ pub fn extract_interactive_sigils(text: &str) -> InteractiveSigils {
 ///
 /// Looks for `attr_name="value"` in the tag content. Returns `None` if not found.
 /// Handles attributes appearing in any order.
-fn extract_attribute(tag_content: &str, attr_name: &str) -> Option<String> {
+pub(crate) fn extract_attribute(tag_content: &str, attr_name: &str) -> Option<String> {
     let pattern = format!("{}=\"", attr_name);
     let start = tag_content.find(&pattern)?;
     let value_start = start + pattern.len();
@@ -203,10 +244,176 @@ fn extract_attribute(tag_content: &str, attr_name: &str) -> Option<String> {
     Some(tag_content[value_start..value_start + end].to_string())
 }
 
+/// Parse every `<tag>body</tag>` occurrence of the given project-defined tags.
+///
+/// Matches are returned in the order they appear in the text. Built-in tags,
+/// names that are not `[a-z0-9_-]+`, and empty bodies are ignored.
+pub fn parse_custom_sigils(text: &str, tags: &[&str]) -> Vec<CustomSigilMatch> {
+    let mut found: Vec<(usize, CustomSigilMatch)> = Vec::new();
+    for tag in tags.iter().filter(|t| is_valid_custom_tag(t)) {
+        let start_tag = format!("<{tag}>");
+        let end_tag = format!("</{tag}>");
+        let mut search_from = 0;
+        while let Some(idx) = text[search_from..].find(&start_tag) {
+            let content_start = search_from + idx + start_tag.len();
+            let Some(end_idx) = text[content_start..].find(&end_tag) else {
+                break;
+            };
+            let body = text[content_start..content_start + end_idx].trim();
+            if !body.is_empty() {
+                found.push((
+                    search_from + idx,
+                    CustomSigilMatch {
+                        tag: tag.to_string(),
+                        body: body.to_string(),
+                    },
+                ));
+            }
+            search_from = content_start + end_idx + end_tag.len();
+        }
+    }
+    found.sort_by_key(|(pos, _)| *pos);
+    found.into_iter().map(|(_, m)| m).collect()
+}
+
+/// Find every `<tag attrs>body</tag>` or self-closing `<tag attrs/>` occurrence.
+///
+/// Returns `(position, attributes, body)` triples; the body of a self-closing
+/// tag is empty. An opening tag without its closing tag ends the scan.
+fn tag_occurrences<'a>(text: &'a str, tag: &str) -> Vec<(usize, &'a str, &'a str)> {
+    let open = format!("<{tag}");
+    let close = format!("</{tag}>");
+    let mut found = Vec::new();
+    let mut search_from = 0;
+    while let Some(idx) = text[search_from..].find(&open) {
+        let start = search_from + idx;
+        let after_name = start + open.len();
+        // Skip longer names sharing the prefix (`<task-added>`).
+        if !text[after_name..].starts_with(['>', '/', ' ', '\n', '\t']) {
+            search_from = after_name;
+            continue;
+        }
+        let Some(gt) = text[after_name..].find('>') else {
+            break;
+        };
+        let tag_end = after_name + gt;
+        let attrs = &text[after_name..tag_end];
+        if let Some(attrs) = attrs.strip_suffix('/') {
+            found.push((start, attrs, ""));
+            search_from = tag_end + 1;
+            continue;
+        }
+        let Some(end_idx) = text[tag_end + 1..].find(&close) else {
+            break;
+        };
+        found.push((start, attrs, &text[tag_end + 1..tag_end + 1 + end_idx]));
+        search_from = tag_end + 1 + end_idx + close.len();
+    }
+    found
+}
+
+/// Parse `<task-add>`, `<task-block>` and `<dep-add>` sigils, in output order.
+///
+/// A `<task-add>` without a `title` attribute takes its title from the first
+/// body line. Occurrences missing a title, task ID or either dependency end
+/// are skipped; validation against the DAG happens when they are applied.
+pub fn parse_queue_sigils(text: &str) -> Vec<QueueSigil> {
+    let mut found: Vec<(usize, QueueSigil)> = Vec::new();
+
+    for (pos, attrs, body) in tag_occurrences(text, "task-add") {
+        let body = body.trim();
+        let (title, description) = match extract_attribute(attrs, "title") {
+            Some(title) => (title.trim().to_string(), body.to_string()),
+            None => match body.split_once('\n') {
+                Some((first, rest)) => (first.trim().to_string(), rest.trim().to_string()),
+                None => (body.to_string(), String::new()),
+            },
+        };
+        if title.is_empty() {
+            continue;
+        }
+        let priority = extract_attribute(attrs, "priority").and_then(|p| p.trim().parse().ok());
+        let depends_on = extract_attribute(attrs, "depends-on")
+            .map(|ids| {
+                ids.split(',')
+                    .map(|id| id.trim().to_string())
+                    .filter(|id| !id.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        found.push((
+            pos,
+            QueueSigil::AddTask {
+                title,
+                description,
+                priority,
+                depends_on,
+            },
+        ));
+    }
+
+    for (pos, attrs, body) in tag_occurrences(text, "task-block") {
+        let task_id = body.trim();
+        if task_id.is_empty() {
+            continue;
+        }
+        found.push((
+            pos,
+            QueueSigil::BlockTask {
+                task_id: task_id.to_string(),
+                reason: extract_attribute(attrs, "reason").filter(|r| !r.trim().is_empty()),
+            },
+        ));
+    }
+
+    for (pos, attrs, _) in tag_occurrences(text, "dep-add") {
+        let blocker = extract_attribute(attrs, "blocker").unwrap_or_default();
+        let blocked = extract_attribute(attrs, "blocked").unwrap_or_default();
+        if blocker.trim().is_empty() || blocked.trim().is_empty() {
+            continue;
+        }
+        found.push((
+            pos,
+            QueueSigil::AddDependency {
+                blocker: blocker.trim().to_string(),
+                blocked: blocked.trim().to_string(),
+            },
+        ));
+    }
+
+    found.sort_by_key(|(pos, _)| *pos);
+    found.into_iter().map(|(_, q)| q).collect()
+}
+
+/// Parse `<artifact path="...">description</artifact>` (or self-closing)
+/// sigils, in output order. Occurrences without a `path` are skipped.
+pub fn parse_artifact_sigils(text: &str) -> Vec<ArtifactSigil> {
+    tag_occurrences(text, "artifact")
+        .into_iter()
+        .filter_map(|(_, attrs, body)| {
+            let path = extract_attribute(attrs, "path")?.trim().to_string();
+            (!path.is_empty()).then(|| ArtifactSigil {
+                path,
+                description: body.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Whether `tag` can be used as a custom sigil name.
+pub fn is_valid_custom_tag(tag: &str) -> bool {
+    !tag.is_empty()
+        && !BUILTIN_TAGS.contains(&tag)
+        && tag
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
 /// Extract all sigils from accumulated agent output text.
 ///
 /// Calls all individual sigil parsers and assembles the results into a `SigilResult`.
-pub fn extract_sigils(text: &str) -> SigilResult {
+/// `custom_tags` are the `[sigils.custom]` names configured for the project.
+pub fn extract_sigils(text: &str, custom_tags: &[&str]) -> SigilResult {
     SigilResult {
         task_done: parse_task_done(text),
         task_failed: parse_task_failed(text),
@@ -215,6 +422,9 @@ pub fn extract_sigils(text: &str) -> SigilResult {
         knowledge_entries: parse_knowledge_sigils(text),
         is_complete: text.contains(COMPLETE_SIGIL),
         is_failure: text.contains(FAILURE_SIGIL),
+        custom: parse_custom_sigils(text, custom_tags),
+        queue: parse_queue_sigils(text),
+        artifacts: parse_artifact_sigils(text),
     }
 }
 
@@ -248,6 +458,12 @@ mod tests {
         assert_eq!(parse_next_model_hint(text), Some("opus".to_string()));
     }
 
+    #[test]
+    fn parse_hint_alias_maps_to_tier() {
+        let text = "<next-model>fast</next-model>";
+        assert_eq!(parse_next_model_hint(text), Some("haiku".to_string()));
+    }
+
     #[test]
     fn parse_hint_absent_returns_none() {
         let text = "No sigil here, just regular output.";
@@ -301,19 +517,32 @@ mod tests {
     #[test]
     fn result_event_default_has_no_hint() {
         // Equivalent: extract_sigils on empty text has no model hint
-        let result = extract_sigils("");
+        let result = extract_sigils("", &[]);
         assert!(result.next_model_hint.is_none());
     }
 
     #[test]
     fn result_event_with_hint() {
         // Equivalent: extract_sigils with next-model sigil populates the hint
-        let result = extract_sigils("done <next-model>opus</next-model>");
+        let result = extract_sigils("done <next-model>opus</next-model>", &[]);
         assert_eq!(result.next_model_hint, Some("opus".to_string()));
         assert!(!result.is_complete);
         assert!(!result.is_failure);
     }
 
+    // --- parse_run_command_requests tests ---
+
+    #[test]
+    fn parse_run_command_requests_in_order() {
+        let text = "First <run-command> cargo test </run-command>, then \
+                    <run-command></run-command><run-command>ls src</run-command> <run-command>oops";
+        assert_eq!(
+            parse_run_command_requests(text),
+            vec!["cargo test", "ls src"]
+        );
+        assert!(parse_run_command_requests("no commands").is_empty());
+    }
+
     // --- parse_task_done tests ---
 
     #[test]
@@ -555,7 +784,7 @@ mod tests {
             "\n",
             "<promise>COMPLETE</promise>",
         );
-        let result = extract_sigils(text);
+        let result = extract_sigils(text, &[]);
         assert_eq!(result.task_done, Some("t-abc123".to_string()));
         assert_eq!(result.task_failed, None);
         assert_eq!(result.next_model_hint, Some("sonnet".to_string()));
@@ -572,7 +801,7 @@ mod tests {
     #[test]
     fn test_extract_sigils_none_present() {
         let text = "Just some plain agent output with no sigils at all.";
-        let result = extract_sigils(text);
+        let result = extract_sigils(text, &[]);
         assert_eq!(result.task_done, None);
         assert_eq!(result.task_failed, None);
         assert_eq!(result.next_model_hint, None);
@@ -586,7 +815,7 @@ mod tests {
     fn test_extract_sigils_partial() {
         // Only task-done and journal sigils present
         let text = "Work done.\n<task-done>t-xyz789</task-done>\n<journal>Key decision: used HashMap.</journal>";
-        let result = extract_sigils(text);
+        let result = extract_sigils(text, &[]);
         assert_eq!(result.task_done, Some("t-xyz789".to_string()));
         assert_eq!(result.task_failed, None);
         assert_eq!(result.next_model_hint, None);
@@ -599,6 +828,86 @@ mod tests {
         assert!(!result.is_failure);
     }
 
+    #[test]
+    fn test_extract_custom_sigils_in_output_order() {
+        let text = "<todo>write docs</todo> then <migration>add users.email</migration>\n\
+                    <todo> </todo><todo>bump version</todo><journal>notes</journal>";
+        let result = extract_sigils(text, &["migration", "todo", "journal", "Bad Tag"]);
+        let found: Vec<(&str, &str)> = result
+            .custom
+            .iter()
+            .map(|m| (m.tag.as_str(), m.body.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("todo", "write docs"),
+                ("migration", "add users.email"),
+                ("todo", "bump version"),
+            ]
+        );
+        assert_eq!(result.journal_notes, Some("notes".to_string()));
+    }
+
+    #[test]
+    fn test_parse_queue_sigils_in_output_order() {
+        let text = "<dep-add blocker=\"t-aaa\" blocked=\"t-bbb\"/>\n\
+                    <task-add title=\"Add index\" priority=\"1\" depends-on=\"t-aaa, t-ccc\">\n\
+                    Lookups by email are slow.\n</task-add>\n\
+                    <task-block reason=\"needs API key\">t-ddd</task-block>\n\
+                    <task-add>Write docs\nCover the flag.</task-add>\n\
+                    <task-added>not a sigil</task-added><task-add>  </task-add>\n\
+                    <dep-add blocker=\"t-aaa\" />";
+        assert_eq!(
+            parse_queue_sigils(text),
+            vec![
+                QueueSigil::AddDependency {
+                    blocker: "t-aaa".to_string(),
+                    blocked: "t-bbb".to_string(),
+                },
+                QueueSigil::AddTask {
+                    title: "Add index".to_string(),
+                    description: "Lookups by email are slow.".to_string(),
+                    priority: Some(1),
+                    depends_on: vec!["t-aaa".to_string(), "t-ccc".to_string()],
+                },
+                QueueSigil::BlockTask {
+                    task_id: "t-ddd".to_string(),
+                    reason: Some("needs API key".to_string()),
+                },
+                QueueSigil::AddTask {
+                    title: "Write docs".to_string(),
+                    description: "Cover the flag.".to_string(),
+                    priority: None,
+                    depends_on: vec![],
+                },
+            ]
+        );
+        assert!(extract_sigils("no sigils", &[]).queue.is_empty());
+        assert!(!is_valid_custom_tag("task-add"));
+    }
+
+    #[test]
+    fn test_parse_artifact_sigils() {
+        let text = "<artifact path=\"db/schema.sql\">\nOrders schema\n</artifact>\n\
+                    <artifact path=\"api/openapi.yaml\"/>\n\
+                    <artifact>no path</artifact><artifacts>not a sigil</artifacts>";
+        assert_eq!(
+            extract_sigils(text, &[]).artifacts,
+            vec![
+                ArtifactSigil {
+                    path: "db/schema.sql".to_string(),
+                    description: "Orders schema".to_string(),
+                },
+                ArtifactSigil {
+                    path: "api/openapi.yaml".to_string(),
+                    description: String::new(),
+                },
+            ]
+        );
+        assert!(!is_valid_custom_tag("artifact"));
+    }
+
     // --- parse_phase_complete tests ---
 
     #[test]
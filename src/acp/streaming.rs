@@ -11,13 +11,27 @@
 use std::cell::RefCell;
 use std::io::Write;
 use std::rc::Rc;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use colored::Colorize;
 
+use crate::acp::prompt::estimate_tokens;
 use crate::acp::tools::SessionUpdateMsg;
-use crate::ui::event::ToolLine;
+use crate::output::markdown_table::{self, Table};
+use crate::pricing::ModelPrice;
+use crate::ui::event::{FileDiff, StreamMeter, ToolLine};
 use crate::ui::{self, UiEvent};
 
+/// Counters behind the TUI's Run header meter for the current iteration.
+struct Meter {
+    meter: StreamMeter,
+    prompt_tokens: u64,
+    price: Option<ModelPrice>,
+}
+
+static METER: Mutex<Option<Meter>> = Mutex::new(None);
+
 /// State carried across render calls within a single session.
 ///
 /// Tracks the model name (for the `model ->` prefix), whether the current
@@ -30,6 +44,9 @@ pub struct RenderState {
     pub in_code_block: Rc<RefCell<bool>>,
     /// Tracks whether we are inside a multi-line sigil tag (e.g. `<journal>`).
     pub in_sigil: Rc<RefCell<Option<String>>>,
+    /// Markdown table rows held back until the table ends, so its columns
+    /// can be aligned.
+    pub table_rows: Rc<RefCell<Vec<String>>>,
 }
 
 /// Truncate a string to at most one line and `max_chars` characters.
@@ -49,14 +66,64 @@ pub fn truncate_to_line(s: &str, max_chars: usize) -> String {
 ///
 /// Called before tool call lines and at session end to ensure no text is lost.
 fn flush_line_buffer(state: &RenderState) {
-    let mut buf = state.line_buffer.borrow_mut();
-    if !buf.is_empty() {
-        let line = std::mem::take(&mut *buf);
+    let mut line = std::mem::take(&mut *state.line_buffer.borrow_mut());
+    // An unterminated last row still belongs to the table being held
+    if !state.table_rows.borrow().is_empty() && markdown_table::is_row(&line) {
+        state
+            .table_rows
+            .borrow_mut()
+            .push(std::mem::take(&mut line));
+    }
+    flush_table_rows(state);
+    if !line.is_empty() {
         let mut in_code = state.in_code_block.borrow_mut();
         let mut in_sigil = state.in_sigil.borrow_mut();
         let formatted = format_markdown_line(&line, &mut in_code, &mut in_sigil);
         print!("{formatted}");
-        flush_stdout();
+    }
+    flush_stdout();
+}
+
+/// Print one complete line of agent text. Table rows outside code blocks
+/// and sigils are held back until the table ends.
+fn print_text_line(line: &str, state: &RenderState) {
+    if markdown_table::is_row(line)
+        && !*state.in_code_block.borrow()
+        && state.in_sigil.borrow().is_none()
+    {
+        state.table_rows.borrow_mut().push(line.to_string());
+        return;
+    }
+    flush_table_rows(state);
+    let mut in_code = state.in_code_block.borrow_mut();
+    let mut in_sigil = state.in_sigil.borrow_mut();
+    println!(
+        "{}",
+        format_markdown_line(line, &mut in_code, &mut in_sigil)
+    );
+}
+
+/// Print the held-back table rows: column-aligned when they form a table,
+/// otherwise as ordinary lines.
+fn flush_table_rows(state: &RenderState) {
+    let rows = std::mem::take(&mut *state.table_rows.borrow_mut());
+    let lines: Vec<&str> = rows.iter().map(String::as_str).collect();
+    match markdown_table::parse(&lines) {
+        Some(table) => {
+            for line in format_table(&table) {
+                println!("{line}");
+            }
+        }
+        None => {
+            let mut in_code = state.in_code_block.borrow_mut();
+            let mut in_sigil = state.in_sigil.borrow_mut();
+            for line in lines {
+                println!(
+                    "{}",
+                    format_markdown_line(line, &mut in_code, &mut in_sigil)
+                );
+            }
+        }
     }
 }
 
@@ -71,6 +138,87 @@ pub fn has_useful_summary(input: &str, locations: &[String]) -> bool {
     !trimmed.is_empty() && trimmed != "{}" && trimmed != "null"
 }
 
+/// Send a file's diff for this session to the TUI Diff panel.
+///
+/// `before` is the content prior to the session's first write (`None` for a
+/// new file), so repeated writes show one cumulative diff. No-op without the TUI.
+pub fn emit_file_diff(path: &str, before: Option<&str>, after: &str) {
+    if !ui::is_active() {
+        return;
+    }
+    let old_label = match before {
+        Some(_) => format!("a/{path}"),
+        None => "/dev/null".to_string(),
+    };
+    let diff = similar::TextDiff::from_lines(before.unwrap_or(""), after)
+        .unified_diff()
+        .context_radius(3)
+        .header(&old_label, &format!("b/{path}"))
+        .to_string();
+    ui::emit(UiEvent::FileDiff(FileDiff {
+        path: path.to_string(),
+        diff,
+    }));
+}
+
+/// Reset the Run header meter for an iteration's agent session (TUI only).
+///
+/// The cost estimate prices `prompt` as input and the streamed text as
+/// output; `price` is `None` for a model without one.
+pub fn start_meter(timeout: Option<Duration>, prompt: &str, price: Option<ModelPrice>) {
+    if !ui::is_active() {
+        return;
+    }
+    let meter = Meter {
+        meter: StreamMeter {
+            started: Instant::now(),
+            finished: None,
+            timeout,
+            chars: 0,
+            cost_micro_usd: None,
+        },
+        prompt_tokens: estimate_tokens(prompt) as u64,
+        price,
+    };
+    update_meter(|m| *m = Some(meter));
+}
+
+/// Stop the meter's clock when the iteration's session ends. Later sessions
+/// (verification, review) do not count towards it.
+pub fn finish_meter() {
+    update_meter(|m| {
+        if let Some(m) = m {
+            m.meter.finished.get_or_insert_with(Instant::now);
+        }
+    });
+}
+
+/// Add streamed characters to a running meter.
+fn count_streamed(chars: usize) {
+    update_meter(|m| {
+        if let Some(m) = m.as_mut().filter(|m| m.meter.finished.is_none()) {
+            m.meter.chars += chars as u64;
+        }
+    });
+}
+
+/// Apply `change` to the meter and send the result to the TUI.
+fn update_meter(change: impl FnOnce(&mut Option<Meter>)) {
+    let mut guard = METER.lock().unwrap_or_else(|e| e.into_inner());
+    change(&mut guard);
+    if let Some(m) = guard.as_mut() {
+        m.meter.cost_micro_usd = m.price.map(|price| {
+            let usage = crate::acp::types::TokenUsage {
+                input_tokens: m.prompt_tokens,
+                output_tokens: m.meter.tokens(),
+                ..Default::default()
+            };
+            (price.cost(&usage) * 1_000_000.0).round() as u64
+        });
+        ui::emit(UiEvent::Meter(m.meter.clone()));
+    }
+}
+
 /// Render a single ACP session update to the terminal.
 ///
 /// Output style per variant:
@@ -83,6 +231,13 @@ pub fn has_useful_summary(input: &str, locations: &[String]) -> bool {
 /// - `Finished`          — flush buffer + newline
 pub fn render_session_update(update: &SessionUpdateMsg, state: &RenderState) {
     if ui::is_active() {
+        match update {
+            SessionUpdateMsg::AgentText(text) | SessionUpdateMsg::AgentThought(text) => {
+                count_streamed(text.chars().count());
+            }
+            SessionUpdateMsg::ToolCall { input, .. } => count_streamed(input.chars().count()),
+            _ => {}
+        }
         match update {
             SessionUpdateMsg::AgentText(text) => {
                 ui::emit(UiEvent::AgentText(text.to_owned()));
@@ -135,6 +290,11 @@ pub fn render_session_update(update: &SessionUpdateMsg, state: &RenderState) {
         return;
     }
 
+    // CI mode keeps stdout for the JSONL event stream; the transcript is dropped.
+    if crate::ci::is_enabled() {
+        return;
+    }
+
     match update {
         SessionUpdateMsg::AgentText(text) => {
             let mut is_first = state.is_first_chunk.borrow_mut();
@@ -162,13 +322,7 @@ pub fn render_session_update(update: &SessionUpdateMsg, state: &RenderState) {
             while let Some(newline_pos) = buf.find('\n') {
                 let line: String = buf.drain(..=newline_pos).collect();
                 // Strip the trailing \n for formatting, then println.
-                let trimmed = &line[..line.len() - 1];
-                let mut in_code = state.in_code_block.borrow_mut();
-                let mut in_sigil = state.in_sigil.borrow_mut();
-                let formatted = format_markdown_line(trimmed, &mut in_code, &mut in_sigil);
-                drop(in_sigil);
-                drop(in_code);
-                println!("{formatted}");
+                print_text_line(&line[..line.len() - 1], state);
             }
 
             flush_stdout();
@@ -612,6 +766,37 @@ pub fn format_markdown_line(
     format_inline_markdown(line)
 }
 
+/// Lay out a markdown table as column-aligned plain text: a bold header, a
+/// dashed rule, then the rows with inline markdown formatting.
+pub fn format_table(table: &Table) -> Vec<String> {
+    let widths = table.widths();
+    let row = |cells: &[String], header: bool| {
+        let cells: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .zip(&table.align)
+            .map(|((cell, width), align)| {
+                let (before, after) = markdown_table::padding(cell, *width, *align);
+                let text = if header {
+                    cell.bold().to_string()
+                } else {
+                    format_inline_markdown(cell)
+                };
+                format!("{}{text}{}", " ".repeat(before), " ".repeat(after))
+            })
+            .collect();
+        cells.join(" | ").trim_end().to_string()
+    };
+    let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+
+    let mut lines = vec![
+        row(&table.header, true),
+        rule.join("-+-").dimmed().to_string(),
+    ];
+    lines.extend(table.rows.iter().map(|cells| row(cells, false)));
+    lines
+}
+
 /// Apply inline markdown formatting to a line of text.
 ///
 /// Recognizes:
@@ -969,4 +1154,40 @@ mod tests {
     fn test_useful_summary_whitespace_only() {
         assert!(!has_useful_summary("   ", &[]));
     }
+
+    // ---- format_table tests -------------------------------------------------
+
+    fn strip_ansi(text: &str) -> String {
+        let mut out = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                chars.by_ref().find(|c| *c == 'm');
+            } else {
+                out.push(c);
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_format_table_aligns_columns() {
+        let table = markdown_table::parse(&[
+            "| File | Lines |",
+            "|:-----|------:|",
+            "| **src/main.rs** | 12 |",
+            "| a.rs | 1200 |",
+        ])
+        .unwrap();
+        let lines: Vec<String> = format_table(&table).iter().map(|l| strip_ansi(l)).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "File            | Lines",
+                "----------------+------",
+                "**src/main.rs** |    12",
+                "a.rs            |  1200",
+            ]
+        );
+    }
 }
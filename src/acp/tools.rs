@@ -27,6 +27,10 @@ pub struct TerminalSession {
     pub(crate) child: tokio::process::Child,
     pub(crate) stdout_buf: Rc<RefCell<Vec<u8>>>,
     pub(crate) stderr_buf: Rc<RefCell<Vec<u8>>>,
+    /// Everything the child wrote, kept after the agent reads it, for the
+    /// task's terminal transcript. Capped like the read buffers.
+    pub(crate) stdout_log: Rc<RefCell<Vec<u8>>>,
+    pub(crate) stderr_log: Rc<RefCell<Vec<u8>>>,
     pub(crate) stdout_reader: tokio::task::JoinHandle<()>,
     pub(crate) stderr_reader: tokio::task::JoinHandle<()>,
 }
@@ -36,6 +40,7 @@ pub struct TerminalSession {
 /// This executes a program with explicit arguments (no intermediate shell),
 /// which prevents shell-injection bugs from argument concatenation.
 /// Two `spawn_local` tasks continuously drain stdout and stderr into 1 MB ring buffers.
+/// `env` is added to the inherited environment.
 ///
 /// Must be called from within a `tokio::task::LocalSet` context because
 /// it calls `spawn_local` internally.
@@ -43,10 +48,12 @@ pub fn create_terminal(
     program: &str,
     args: &[String],
     cwd: &Path,
+    env: &[(String, String)],
 ) -> io::Result<(String, TerminalSession)> {
     let mut child = tokio::process::Command::new(program)
         .args(args)
         .current_dir(cwd)
+        .envs(env.iter().map(|(k, v)| (k, v)))
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
         .spawn()?;
@@ -62,47 +69,11 @@ pub fn create_terminal(
 
     let stdout_buf: Rc<RefCell<Vec<u8>>> = Rc::new(RefCell::new(Vec::new()));
     let stderr_buf: Rc<RefCell<Vec<u8>>> = Rc::new(RefCell::new(Vec::new()));
+    let stdout_log: Rc<RefCell<Vec<u8>>> = Rc::new(RefCell::new(Vec::new()));
+    let stderr_log: Rc<RefCell<Vec<u8>>> = Rc::new(RefCell::new(Vec::new()));
 
-    let stdout_buf_clone = Rc::clone(&stdout_buf);
-    let stderr_buf_clone = Rc::clone(&stderr_buf);
-
-    let stdout_reader = tokio::task::spawn_local(async move {
-        let mut stdout = stdout;
-        let mut buf = [0u8; 4096];
-        loop {
-            match stdout.read(&mut buf).await {
-                Ok(0) => break, // EOF
-                Ok(n) => {
-                    let mut b = stdout_buf_clone.borrow_mut();
-                    b.extend_from_slice(&buf[..n]);
-                    if b.len() > MAX_BUF_SIZE {
-                        let excess = b.len() - MAX_BUF_SIZE;
-                        b.drain(..excess);
-                    }
-                }
-                Err(_) => break,
-            }
-        }
-    });
-
-    let stderr_reader = tokio::task::spawn_local(async move {
-        let mut stderr = stderr;
-        let mut buf = [0u8; 4096];
-        loop {
-            match stderr.read(&mut buf).await {
-                Ok(0) => break, // EOF
-                Ok(n) => {
-                    let mut b = stderr_buf_clone.borrow_mut();
-                    b.extend_from_slice(&buf[..n]);
-                    if b.len() > MAX_BUF_SIZE {
-                        let excess = b.len() - MAX_BUF_SIZE;
-                        b.drain(..excess);
-                    }
-                }
-                Err(_) => break,
-            }
-        }
-    });
+    let stdout_reader = spawn_reader(stdout, Rc::clone(&stdout_buf), Rc::clone(&stdout_log));
+    let stderr_reader = spawn_reader(stderr, Rc::clone(&stderr_buf), Rc::clone(&stderr_log));
 
     let id = TERMINAL_COUNTER.fetch_add(1, Ordering::SeqCst);
     let terminal_id = format!("terminal-{id}");
@@ -111,6 +82,8 @@ pub fn create_terminal(
         child,
         stdout_buf,
         stderr_buf,
+        stdout_log,
+        stderr_log,
         stdout_reader,
         stderr_reader,
     };
@@ -118,6 +91,56 @@ pub fn create_terminal(
     Ok((terminal_id, session))
 }
 
+/// Copy `pipe` into both `buf` (drained by the agent) and `log` (kept for the
+/// transcript) until EOF, dropping the oldest bytes past 1 MB.
+fn spawn_reader(
+    mut pipe: impl tokio::io::AsyncRead + Unpin + 'static,
+    buf: Rc<RefCell<Vec<u8>>>,
+    log: Rc<RefCell<Vec<u8>>>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::task::spawn_local(async move {
+        let mut chunk = [0u8; 4096];
+        loop {
+            match pipe.read(&mut chunk).await {
+                Ok(0) => break, // EOF
+                Ok(n) => {
+                    for target in [&buf, &log] {
+                        let mut b = target.borrow_mut();
+                        b.extend_from_slice(&chunk[..n]);
+                        if b.len() > MAX_BUF_SIZE {
+                            let excess = b.len() - MAX_BUF_SIZE;
+                            b.drain(..excess);
+                        }
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+    })
+}
+
+/// Everything the session has written so far, as `(stdout, stderr)`. Unlike
+/// [`read_terminal_output`] this does not drain anything.
+pub fn recorded_output(session: &TerminalSession) -> (String, String) {
+    let stdout = String::from_utf8_lossy(&session.stdout_log.borrow()).into_owned();
+    let stderr = String::from_utf8_lossy(&session.stderr_log.borrow()).into_owned();
+    (stdout, stderr)
+}
+
+/// Exit code if the child has exited, letting its readers catch up first so
+/// the recorded output is complete. `None` while it is still running, or if
+/// it was killed by a signal.
+pub async fn settle(session: &mut TerminalSession) -> Option<i32> {
+    let status = session.child.try_wait().ok().flatten()?;
+    let grace = std::time::Duration::from_millis(500);
+    for reader in [&mut session.stdout_reader, &mut session.stderr_reader] {
+        if !reader.is_finished() {
+            let _ = tokio::time::timeout(grace, reader).await;
+        }
+    }
+    status.code()
+}
+
 /// Drain all buffered output (stdout then stderr) from the session and return
 /// it as a UTF-8 string. Buffers are cleared after reading.
 pub fn read_terminal_output(session: &TerminalSession) -> String {
@@ -216,7 +239,7 @@ mod tests {
     async fn test_terminal_create_and_output() {
         with_local_set!(async {
             let cwd = std::env::current_dir().unwrap();
-            let (id, session) = create_terminal("echo", &as_args(&["hello"]), &cwd).unwrap();
+            let (id, session) = create_terminal("echo", &as_args(&["hello"]), &cwd, &[]).unwrap();
 
             // Terminal ID must be non-empty and contain the counter.
             assert!(!id.is_empty(), "terminal ID should not be empty");
@@ -246,11 +269,32 @@ mod tests {
         });
     }
 
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_terminal_env_is_injected() {
+        with_local_set!(async {
+            let cwd = std::env::current_dir().unwrap();
+            let env = [("RALPH_TEST_TOKEN".to_string(), "abc123".to_string())];
+            let (_, session) = create_terminal(
+                "sh",
+                &as_args(&["-c", "echo token=$RALPH_TEST_TOKEN"]),
+                &cwd,
+                &env,
+            )
+            .unwrap();
+
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            let output = read_terminal_output(&session);
+            assert!(output.contains("token=abc123"), "got: {output:?}");
+
+            release_terminal(session).await;
+        });
+    }
+
     #[tokio::test(flavor = "current_thread")]
     async fn test_terminal_kill() {
         with_local_set!(async {
             let cwd = std::env::current_dir().unwrap();
-            let (_, mut session) = create_terminal("sleep", &as_args(&["60"]), &cwd).unwrap();
+            let (_, mut session) = create_terminal("sleep", &as_args(&["60"]), &cwd, &[]).unwrap();
 
             // Kill the long-running process.
             kill_terminal(&mut session).await;
@@ -267,7 +311,7 @@ mod tests {
     async fn test_terminal_release_cleanup() {
         with_local_set!(async {
             let cwd = std::env::current_dir().unwrap();
-            let (_, session) = create_terminal("sleep", &as_args(&["10"]), &cwd).unwrap();
+            let (_, session) = create_terminal("sleep", &as_args(&["10"]), &cwd, &[]).unwrap();
 
             // Allow a brief moment for output to be buffered.
             tokio::time::sleep(Duration::from_millis(50)).await;
@@ -6,6 +6,8 @@
 
 use agent_client_protocol::StopReason;
 
+use crate::dag::TaskFrontmatter;
+
 // ---- Types copied from src/claude/client.rs ----
 // Originals remain in claude/client.rs and will be removed in Phase 6.
 
@@ -13,7 +15,13 @@ use agent_client_protocol::StopReason;
 pub struct TaskInfo {
     pub task_id: String,
     pub title: String,
+    /// Free-text description, without its frontmatter block.
     pub description: String,
+    /// Criteria, files, links and labels from the description's frontmatter.
+    pub frontmatter: TaskFrontmatter,
+    /// Working directory relative to the project root, for tasks that run
+    /// in a subdirectory (e.g. one package of a monorepo).
+    pub cwd: Option<String>,
     pub parent: Option<ParentContext>,
     pub completed_blockers: Vec<BlockerContext>,
 }
@@ -29,6 +37,17 @@ pub struct BlockerContext {
     pub task_id: String,
     pub title: String,
     pub summary: String,
+    /// Files the blocker registered with `<artifact>` sigils.
+    pub artifacts: Vec<ArtifactContext>,
+}
+
+/// A blocker's artifact as shown to the dependent task.
+pub struct ArtifactContext {
+    /// Path relative to the project root.
+    pub path: String,
+    pub description: String,
+    /// Opening lines of the file; empty when it can no longer be read.
+    pub excerpt: String,
 }
 
 /// Information about a retry attempt.
@@ -36,6 +55,10 @@ pub struct RetryInfo {
     pub attempt: i32,
     pub max_retries: i32,
     pub previous_failure_reason: String,
+    /// Closing output of the previous attempt, when it ran in this process.
+    pub previous_output: Option<String>,
+    /// Condensed thoughts of the previous attempt (`thoughts_in_retry`).
+    pub previous_thoughts: Option<String>,
 }
 
 /// Full iteration context passed to the system prompt.
@@ -52,6 +75,18 @@ pub struct IterationContext {
     pub journal_context: String,
     /// Pre-rendered markdown from knowledge::render_knowledge_context().
     pub knowledge_context: String,
+    /// Pre-rendered markdown from scratchpad::render_scratchpad_context();
+    /// empty when the scratchpad is disabled.
+    pub scratchpad_context: String,
+    /// Pre-rendered markdown from diff_context::render_diff_context();
+    /// empty when `diff_context` is off or nothing changed.
+    pub diff_context: String,
+    /// What a read-only session found exploring this task while the previous
+    /// one was verified (`speculative_exploration`).
+    pub speculative_analysis: Option<String>,
+    /// ACP session of the task's previous attempt. Loaded instead of a fresh
+    /// session when the agent advertises `loadSession`.
+    pub resume_session_id: Option<String>,
 }
 
 // ---- New ACP-specific types ----
@@ -73,18 +108,99 @@ pub enum RunResult {
     Completed(StreamingResult),
     /// The user interrupted the iteration with Ctrl+C.
     Interrupted,
+    /// The iteration hit its hard timeout and was cancelled; carries the partial output.
+    TimedOut(StreamingResult),
+    /// The iteration exceeded a tool limit and was cancelled; carries the
+    /// partial output and which limit was hit.
+    ToolLimitHit(StreamingResult, String),
+}
+
+/// A command Ralph ran for an agent without terminal support.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HarnessCommandResult {
+    pub command: String,
+    /// `None` if the command was refused or killed by a signal.
+    pub exit_code: Option<u32>,
+    /// Combined output, masked, or why the command did not run.
+    pub output: String,
 }
 
 /// Data collected from a streaming ACP session.
 pub struct StreamingResult {
     /// Accumulated full agent text output (for sigil extraction).
     pub full_text: String,
+    /// Accumulated agent thought text (reasoning, not part of the answer).
+    pub thoughts: String,
     /// File paths written during the session (from write_text_file calls).
     pub files_modified: Vec<String>,
+    /// File paths read during the session (from read_text_file calls).
+    pub files_read: Vec<String>,
     /// Iteration duration in milliseconds (wall clock, tracked by Ralph).
     pub duration_ms: u64,
     /// Why the agent stopped (EndTurn, MaxTokens, Refusal, etc.).
     pub stop_reason: StopReason,
+    /// Token counts and cost reported by the agent (zero when not reported).
+    pub usage: TokenUsage,
+    /// ACP session the output came from.
+    pub session_id: Option<String>,
+}
+
+/// Token usage for one session, as reported by the agent.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cached_read_tokens: u64,
+    pub cached_write_tokens: u64,
+    /// Session cost in USD when the agent reports one directly.
+    pub reported_cost_usd: Option<f64>,
+}
+
+impl TokenUsage {
+    /// Read usage from a prompt response's `usage` field, falling back to a
+    /// `usage` object in `_meta` for agents that only report it there.
+    pub fn from_prompt_response(resp: &agent_client_protocol::PromptResponse) -> Self {
+        if let Some(ref usage) = resp.usage {
+            return TokenUsage {
+                input_tokens: usage.input_tokens,
+                output_tokens: usage.output_tokens,
+                cached_read_tokens: usage.cached_read_tokens.unwrap_or(0),
+                cached_write_tokens: usage.cached_write_tokens.unwrap_or(0),
+                reported_cost_usd: None,
+            };
+        }
+        resp.meta
+            .as_ref()
+            .and_then(|meta| meta.get("usage"))
+            .map(Self::from_json)
+            .unwrap_or_default()
+    }
+
+    /// Add another turn's token counts (not its cost) to these.
+    pub fn add_tokens(&mut self, other: &TokenUsage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cached_read_tokens += other.cached_read_tokens;
+        self.cached_write_tokens += other.cached_write_tokens;
+    }
+
+    /// Parse a loosely-shaped usage object (camelCase or snake_case keys).
+    fn from_json(value: &serde_json::Value) -> Self {
+        let field = |camel: &str, snake: &str| {
+            value
+                .get(camel)
+                .or_else(|| value.get(snake))
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0)
+        };
+        TokenUsage {
+            input_tokens: field("inputTokens", "input_tokens"),
+            output_tokens: field("outputTokens", "output_tokens"),
+            cached_read_tokens: field("cachedReadTokens", "cache_read_input_tokens"),
+            cached_write_tokens: field("cachedWriteTokens", "cache_creation_input_tokens"),
+            reported_cost_usd: None,
+        }
+    }
 }
 
 /// All sigils extracted from a session's text output.
@@ -104,4 +220,44 @@ pub struct SigilResult {
     pub is_complete: bool,
     /// True if `<promise>FAILURE</promise>` was found.
     pub is_failure: bool,
+    /// Project-defined sigils from `[sigils.custom]`, in output order.
+    pub custom: Vec<CustomSigilMatch>,
+    /// Queue changes from `<task-add>`, `<task-block>` and `<dep-add>`, in output order.
+    pub queue: Vec<QueueSigil>,
+    /// Files registered with `<artifact>` sigils, in output order.
+    pub artifacts: Vec<ArtifactSigil>,
+}
+
+/// `<artifact path="db/schema.sql">description</artifact>`: a file the task
+/// produced for the tasks that depend on it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArtifactSigil {
+    pub path: String,
+    pub description: String,
+}
+
+/// A change to the task queue requested by the agent mid-iteration.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueueSigil {
+    /// `<task-add title="..." priority="N" depends-on="t-a,t-b">description</task-add>`
+    AddTask {
+        title: String,
+        description: String,
+        priority: Option<i32>,
+        depends_on: Vec<String>,
+    },
+    /// `<task-block reason="...">t-abc123</task-block>`
+    BlockTask {
+        task_id: String,
+        reason: Option<String>,
+    },
+    /// `<dep-add blocker="t-a" blocked="t-b"/>`
+    AddDependency { blocker: String, blocked: String },
+}
+
+/// One occurrence of a project-defined sigil (`<tag>body</tag>`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomSigilMatch {
+    pub tag: String,
+    pub body: String,
 }
@@ -0,0 +1,107 @@
+//! Per-agent model names.
+//!
+//! Strategies and `--model` work in three tiers named after Claude's models
+//! (`opus`, `sonnet`, `haiku`), with the agent-neutral aliases `strong`,
+//! `balanced` and `fast`. An agent that names its models differently maps
+//! the tiers in its profile:
+//!
+//! ```toml
+//! [agents.gemini.models]
+//! strong = "gemini-2.0-pro"
+//! fast = "gemini-2.0-flash"
+//! ```
+//!
+//! The agent then receives `gemini-2.0-pro` in `RALPH_MODEL` wherever Ralph
+//! picked the strong tier. A tier the map leaves out falls back to the
+//! nearest one it has, stronger first. `[agent.models]` does the same for
+//! the default agent. Agents without a map get the tier names.
+
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use crate::project::RalphConfig;
+
+/// Model tiers, strongest first, with their agent-neutral aliases.
+pub const TIERS: &[(&str, &str)] = &[
+    ("opus", "strong"),
+    ("sonnet", "balanced"),
+    ("haiku", "fast"),
+];
+
+/// Model maps by agent command, installed by [`configure`].
+static MODELS: OnceLock<Vec<(String, BTreeMap<String, String>)>> = OnceLock::new();
+
+/// The tier `name` refers to, by tier name or alias.
+pub fn tier(name: &str) -> Option<&'static str> {
+    TIERS
+        .iter()
+        .find(|(tier, alias)| *tier == name || *alias == name)
+        .map(|(tier, _)| *tier)
+}
+
+/// Install the model maps of `[agent]` and every `[agents.<name>]` profile
+/// for sessions started afterwards. Later calls are ignored.
+pub fn configure(config: &RalphConfig) {
+    let mut maps = vec![(config.agent.command.clone(), config.agent.models.clone())];
+    for (name, profile) in &config.agents {
+        let command = profile.command.clone().unwrap_or_else(|| name.clone());
+        maps.push((command, profile.models.clone()));
+        // `--agent <name>` resolves to the command, but keep the name too.
+        maps.push((name.clone(), profile.models.clone()));
+    }
+    maps.retain(|(_, models)| !models.is_empty());
+    let _ = MODELS.set(maps);
+}
+
+/// The model name to hand `agent_command` for `model`: the ID its profile
+/// maps the tier to, or `model` unchanged.
+pub fn resolve(agent_command: &str, model: &str) -> String {
+    MODELS
+        .get()
+        .and_then(|maps| maps.iter().find(|(command, _)| command == agent_command))
+        .and_then(|(_, models)| resolve_in(models, model))
+        .unwrap_or_else(|| model.to_string())
+}
+
+/// Look `model` up in `models` by its tier name or alias, then by the
+/// nearest other tier (stronger first). `None` for names outside the tiers.
+fn resolve_in(models: &BTreeMap<String, String>, model: &str) -> Option<String> {
+    if let Some(id) = models.get(model) {
+        return Some(id.clone());
+    }
+    let wanted = tier(model)?;
+    let wanted = TIERS.iter().position(|(name, _)| *name == wanted)?;
+    let mut order: Vec<usize> = (0..TIERS.len()).collect();
+    order.sort_by_key(|&i| (i.abs_diff(wanted), i));
+    order.into_iter().find_map(|i| {
+        let (tier, alias) = TIERS[i];
+        models.get(tier).or_else(|| models.get(alias)).cloned()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiers_resolve_through_the_agent_map() {
+        assert_eq!(tier("strong"), Some("opus"));
+        assert_eq!(tier("haiku"), Some("haiku"));
+        assert_eq!(tier("gpt-4o"), None);
+
+        let models: BTreeMap<String, String> =
+            [("strong", "gemini-2.0-pro"), ("fast", "gemini-2.0-flash")]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+        assert_eq!(resolve_in(&models, "opus").unwrap(), "gemini-2.0-pro");
+        assert_eq!(resolve_in(&models, "fast").unwrap(), "gemini-2.0-flash");
+        // No balanced model: the stronger neighbour wins the tie.
+        assert_eq!(resolve_in(&models, "sonnet").unwrap(), "gemini-2.0-pro");
+        assert_eq!(resolve_in(&models, "gpt-4o"), None);
+        assert_eq!(resolve_in(&BTreeMap::new(), "opus"), None);
+
+        // Without configure() every name passes through.
+        assert_eq!(resolve("claude-agent-acp", "sonnet"), "sonnet");
+    }
+}
@@ -0,0 +1,623 @@
+//! Local HTTP API for editor integrations (`ralph api`).
+//!
+//! Serves HTTP/1.1 with JSON bodies on a Unix socket (default
+//! `.ralph/ralph.sock`), so only users who can open the socket file reach it:
+//!
+//! | Request                      | Does                                                 |
+//! |------------------------------|------------------------------------------------------|
+//! | `GET /tasks`                 | Tasks; `?status=` and `?feature=` filter             |
+//! | `GET /tasks/<id>`            | One task with its criteria, blockers and log         |
+//! | `POST /tasks/<id>/claim`     | Claim for manual work, like `ralph task next --claim` |
+//! | `POST /tasks/<id>/complete`  | Mark done, like `ralph task done`                    |
+//! | `GET /knowledge`             | Knowledge entries; `?tag=` filters                   |
+//! | `POST /runs`                 | Start `ralph run <target> --ci` in the background    |
+//! | `GET /events`                | Server-sent events of the run's JSONL event stream   |
+//!
+//! Every response but `/events` closes the connection. One run at a time can
+//! be started; its events go to every `/events` subscriber (stderr lines as
+//! `stderr` events), followed by an `exit` event with the run's exit code.
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::broadcast;
+
+use crate::dag::{self, Db};
+use crate::feature;
+use crate::knowledge;
+use crate::output::formatter;
+use crate::post_feature;
+use crate::project::ProjectConfig;
+
+/// Default socket path, relative to the project root.
+pub const DEFAULT_SOCKET: &str = ".ralph/ralph.sock";
+
+/// Largest request body accepted.
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Interval of the comment lines that keep idle `/events` streams open.
+const KEEPALIVE: Duration = Duration::from_secs(15);
+
+/// A parsed HTTP request.
+#[derive(Debug, PartialEq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: BTreeMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// The body as a JSON object; an empty body is `{}`.
+    fn json(&self) -> Result<Value> {
+        if self.body.iter().all(u8::is_ascii_whitespace) {
+            return Ok(json!({}));
+        }
+        serde_json::from_slice(&self.body).context("Request body is not valid JSON")
+    }
+}
+
+/// A JSON response.
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl std::fmt::Display) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message.to_string() }),
+        }
+    }
+
+    fn to_http(&self) -> String {
+        let body = self.body.to_string();
+        format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n{}",
+            self.status,
+            reason_phrase(self.status),
+            body.len(),
+            body
+        )
+    }
+}
+
+/// Shared state of a running API server.
+pub struct Api {
+    project: ProjectConfig,
+    db_path: String,
+    /// Server-sent event frames of the current run.
+    events: broadcast::Sender<String>,
+    /// Process ID of the run started through the API, while it runs.
+    run: Arc<Mutex<Option<u32>>>,
+}
+
+impl Api {
+    pub fn new(project: ProjectConfig) -> Result<Self> {
+        let db_path = project.db_path().to_string_lossy().to_string();
+        let (events, _) = broadcast::channel(1024);
+        Ok(Self {
+            project,
+            db_path,
+            events,
+            run: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// Answer every request but `/events`.
+    pub fn handle(&self, request: &Request) -> Response {
+        let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+        let result = match (request.method.as_str(), segments.as_slice()) {
+            ("GET", ["tasks"]) => self.list_tasks(request),
+            ("GET", ["tasks", id]) => self.show_task(id),
+            ("POST", ["tasks", id, "claim"]) => self.claim_task(id, request),
+            ("POST", ["tasks", id, "complete"]) => self.complete_task(id, request),
+            ("GET", ["knowledge"]) => Ok(self.list_knowledge(request)),
+            ("POST", ["runs"]) => self.start_run(request),
+            (_, ["tasks"] | ["tasks", _] | ["tasks", _, _] | ["knowledge"] | ["runs"]) => {
+                Ok(Response::error(405, "Method not allowed"))
+            }
+            _ => Ok(Response::error(
+                404,
+                format!("No route for {}", request.path),
+            )),
+        };
+        result.unwrap_or_else(|e| Response::error(400, format!("{e:#}")))
+    }
+
+    fn db(&self) -> Result<Db> {
+        dag::open_db(&self.db_path)
+    }
+
+    fn list_tasks(&self, request: &Request) -> Result<Response> {
+        let db = self.db()?;
+        let feature_id = match request.query.get("feature") {
+            Some(name) => Some(feature::get_feature(&db, name)?.id),
+            None => None,
+        };
+        let status = request.query.get("status").map(String::as_str);
+        let tasks = dag::select_tasks(&db, status, feature_id.as_deref())?;
+        Ok(Response::ok(json!(tasks)))
+    }
+
+    fn show_task(&self, id: &str) -> Result<Response> {
+        let db = self.db()?;
+        let Ok(task) = dag::get_task(&db, id) else {
+            return Ok(Response::error(404, format!("No such task: {id}")));
+        };
+        let blockers: Vec<String> = dag::get_task_blockers(&db, id)?
+            .into_iter()
+            .map(|t| t.id)
+            .collect();
+        Ok(Response::ok(json!({
+            "task": task,
+            "criteria": dag::get_task_criteria(&db, id)?,
+            "blockers": blockers,
+            "log": dag::get_task_logs(&db, id)?,
+        })))
+    }
+
+    fn claim_task(&self, id: &str, request: &Request) -> Result<Response> {
+        let db = self.db()?;
+        if dag::get_task(&db, id).is_err() {
+            return Ok(Response::error(404, format!("No such task: {id}")));
+        }
+        let user = request.json()?["by"]
+            .as_str()
+            .map(str::to_string)
+            .or_else(|| self.project.user_name());
+        let who = format!("human:{}", user.as_deref().unwrap_or("unknown"));
+        if !dag::claim_task_manually(&db, id, &who)? {
+            return Ok(Response::error(409, format!("{id} is not claimable")));
+        }
+        dag::add_log(&db, id, &format!("Claimed by {who} for manual work"))?;
+        Ok(Response::ok(json!({ "task": dag::get_task(&db, id)? })))
+    }
+
+    fn complete_task(&self, id: &str, request: &Request) -> Result<Response> {
+        let db = self.db()?;
+        if dag::get_task(&db, id).is_err() {
+            return Ok(Response::error(404, format!("No such task: {id}")));
+        }
+        let body = request.json()?;
+        let transitions = dag::force_complete_task(db.conn(), id)?;
+        let user = self.project.user_name();
+        dag::set_completed_by(&db, id, user.as_deref())?;
+        if let Some(message) = body["message"].as_str() {
+            dag::add_log(&db, id, message)?;
+        }
+        let follow_ups: Vec<String> = post_feature::after_transitions(
+            &db,
+            &self.project.config,
+            &transitions,
+            user.as_deref(),
+        )?
+        .into_iter()
+        .map(|t| t.id)
+        .collect();
+        Ok(Response::ok(json!({
+            "task": dag::get_task(&db, id)?,
+            "follow_ups": follow_ups,
+        })))
+    }
+
+    fn list_knowledge(&self, request: &Request) -> Response {
+        let tag = request.query.get("tag");
+        let entries: Vec<Value> = knowledge::discover_knowledge(&self.project.root)
+            .into_iter()
+            .filter(|e| tag.is_none_or(|tag| e.tags.contains(tag)))
+            .map(|e| {
+                json!({
+                    "title": e.title,
+                    "tags": e.tags,
+                    "feature": e.feature,
+                    "scope": e.scope.as_str(),
+                    "body": e.body,
+                    "path": e.file_path,
+                })
+            })
+            .collect();
+        Response::ok(json!(entries))
+    }
+
+    /// Start `ralph run --ci` for the body's `target` (a string or a list),
+    /// with optional `limit` and `timeout` (seconds).
+    fn start_run(&self, request: &Request) -> Result<Response> {
+        let body = request.json()?;
+        let targets: Vec<String> = match &body["target"] {
+            Value::String(target) => vec![target.clone()],
+            Value::Array(targets) => targets
+                .iter()
+                .filter_map(|t| t.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
+        };
+        if targets.is_empty() {
+            bail!("\"target\" is required: a feature name, task ID or list of them");
+        }
+        let mut run = self.run.lock().unwrap();
+        if let Some(pid) = *run {
+            return Ok(Response::error(
+                409,
+                format!("A run is already active (pid {pid})"),
+            ));
+        }
+
+        let mut command = tokio::process::Command::new(std::env::current_exe()?);
+        command.arg("run").arg("--ci");
+        for key in ["limit", "timeout"] {
+            match &body[key] {
+                Value::Null => {}
+                Value::Number(n) => {
+                    command.arg(format!("--{key}")).arg(n.to_string());
+                }
+                Value::String(s) => {
+                    command.arg(format!("--{key}")).arg(s);
+                }
+                _ => bail!("\"{key}\" must be a number"),
+            }
+        }
+        // Targets are never read as flags, whatever the client sends
+        command.arg("--").args(&targets);
+        let mut child = command
+            .current_dir(&self.project.root)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to start ralph run")?;
+        let pid = child.id().unwrap_or_default();
+        *run = Some(pid);
+        formatter::emit_event_info(
+            "api",
+            &format!("run {} started (pid {pid})", targets.join(" ")),
+        );
+
+        // Errors before the run starts (a bad target) only reach stderr
+        if let Some(stderr) = child.stderr.take() {
+            let events = self.events.clone();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let _ = events.send(sse_frame(Some("stderr"), &line));
+                }
+            });
+        }
+        let stdout = child.stdout.take();
+        let events = self.events.clone();
+        let finished = Arc::clone(&self.run);
+        tokio::spawn(async move {
+            if let Some(stdout) = stdout {
+                let mut lines = BufReader::new(stdout).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let _ = events.send(sse_frame(None, &line));
+                }
+            }
+            let code = child.wait().await.ok().and_then(|s| s.code());
+            let _ = events.send(sse_frame(
+                Some("exit"),
+                &json!({ "code": code }).to_string(),
+            ));
+            *finished.lock().unwrap() = None;
+            formatter::emit_event_info("api", &format!("run exited with {code:?}"));
+        });
+        Ok(Response {
+            status: 202,
+            body: json!({ "pid": pid, "targets": targets }),
+        })
+    }
+}
+
+/// Serve the API on `socket` until interrupted.
+pub async fn serve(project: ProjectConfig, socket: &Path) -> Result<()> {
+    if socket.exists() {
+        if UnixStream::connect(socket).await.is_ok() {
+            bail!("ralph api is already serving {}", socket.display());
+        }
+        // Left behind by a server that did not shut down cleanly
+        std::fs::remove_file(socket)
+            .with_context(|| format!("Failed to remove stale {}", socket.display()))?;
+    }
+    let listener = UnixListener::bind(socket)
+        .with_context(|| format!("Failed to listen on {}", socket.display()))?;
+    restrict_permissions(socket)?;
+    let api = Arc::new(Api::new(project)?);
+    formatter::print_info(&format!("Serving the ralph API on {}", socket.display()));
+
+    let result = tokio::select! {
+        result = accept_loop(&listener, &api) => result,
+        _ = tokio::signal::ctrl_c() => Ok(()),
+    };
+    let _ = std::fs::remove_file(socket);
+    result
+}
+
+async fn accept_loop(listener: &UnixListener, api: &Arc<Api>) -> Result<()> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let api = Arc::clone(api);
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, &api).await {
+                formatter::emit_event("api", &format!("{e:#}"), true);
+            }
+        });
+    }
+}
+
+async fn handle_connection(stream: UnixStream, api: &Api) -> Result<()> {
+    let (read, mut write) = stream.into_split();
+    let mut reader = BufReader::new(read);
+    let request = match read_request(&mut reader).await {
+        Ok(Some(request)) => request,
+        Ok(None) => return Ok(()),
+        Err(e) => {
+            let response = Response::error(400, format!("{e:#}"));
+            write.write_all(response.to_http().as_bytes()).await?;
+            return Ok(());
+        }
+    };
+
+    if request.method == "GET" && request.path == "/events" {
+        let mut events = api.events.subscribe();
+        write
+            .write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\
+                  Cache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n",
+            )
+            .await?;
+        let mut keepalive = tokio::time::interval(KEEPALIVE);
+        loop {
+            let frame = tokio::select! {
+                event = events.recv() => match event {
+                    Ok(frame) => frame,
+                    Err(broadcast::error::RecvError::Lagged(n)) => format!(": {n} events dropped\n\n"),
+                    Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                },
+                _ = keepalive.tick() => ": keepalive\n\n".to_string(),
+            };
+            // The client went away
+            if write.write_all(frame.as_bytes()).await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+
+    let response = api.handle(&request);
+    write.write_all(response.to_http().as_bytes()).await?;
+    Ok(())
+}
+
+/// Read one request. `None` when the client closed the connection first.
+pub async fn read_request<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Option<Request>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).await? == 0 {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        bail!("Malformed request line");
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('=').or(Some((pair, ""))))
+        .filter(|(key, _)| !key.is_empty())
+        .map(|(key, value)| (percent_decode(key), percent_decode(value)))
+        .collect();
+    let method = method.to_string();
+    let path = percent_decode(path);
+
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            bail!("Connection closed inside the headers");
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().context("Invalid Content-Length")?;
+            }
+        }
+    }
+    if content_length > MAX_BODY_BYTES {
+        bail!("Request body over {MAX_BODY_BYTES} bytes");
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(Request {
+        method,
+        path,
+        query,
+        body,
+    }))
+}
+
+/// One server-sent event carrying `data`.
+fn sse_frame(event: Option<&str>, data: &str) -> String {
+    let mut frame = String::new();
+    if let Some(event) = event {
+        frame.push_str(&format!("event: {event}\n"));
+    }
+    for line in data.lines() {
+        frame.push_str(&format!("data: {line}\n"));
+    }
+    frame.push('\n');
+    frame
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' => match text
+                .get(i + 1..i + 3)
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+            {
+                Some(byte) => {
+                    out.push(byte);
+                    i += 2;
+                }
+                None => out.push(b'%'),
+            },
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        _ => "Error",
+    }
+}
+
+/// Only the owner may connect.
+fn restrict_permissions(socket: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(socket, std::fs::Permissions::from_mode(0o600))
+        .with_context(|| format!("Failed to restrict {}", socket.display()))
+}
+
+/// The socket path for `--socket`, relative to the project root by default.
+pub fn socket_path(project_root: &Path, socket: Option<PathBuf>) -> PathBuf {
+    socket.unwrap_or_else(|| project_root.join(DEFAULT_SOCKET))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::RalphConfig;
+
+    fn setup() -> (tempfile::TempDir, Api) {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join(".ralph/knowledge")).unwrap();
+        let project = ProjectConfig {
+            root: dir.path().to_path_buf(),
+            config: RalphConfig::default(),
+        };
+        dag::init_db(project.db_path().to_str().unwrap()).unwrap();
+        (dir, Api::new(project).unwrap())
+    }
+
+    fn request(method: &str, path: &str, body: &str) -> Request {
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            query: BTreeMap::new(),
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_request() {
+        let raw = "POST /tasks/t-1/complete?tag=a%20b&x HTTP/1.1\r\nHost: ralph\r\n\
+                   content-length: 16\r\n\r\n{\"message\":\"ok\"}";
+        let request = read_request(&mut raw.as_bytes()).await.unwrap().unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/tasks/t-1/complete");
+        assert_eq!(request.query.get("tag").map(String::as_str), Some("a b"));
+        assert_eq!(request.query.get("x").map(String::as_str), Some(""));
+        assert_eq!(request.json().unwrap()["message"], "ok");
+
+        assert!(read_request(&mut "".as_bytes()).await.unwrap().is_none());
+        assert!(read_request(&mut "GET\r\n\r\n".as_bytes()).await.is_err());
+        assert_eq!(
+            sse_frame(Some("exit"), "a\nb"),
+            "event: exit\ndata: a\ndata: b\n\n"
+        );
+    }
+
+    #[test]
+    fn test_task_routes() {
+        let (_dir, api) = setup();
+        let db = api.db().unwrap();
+        let task = dag::create_task(&db, "Wire the API", None, None, 0).unwrap();
+
+        let mut list = request("GET", "/tasks", "");
+        list.query
+            .insert("status".to_string(), "pending".to_string());
+        let response = api.handle(&list);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body[0]["id"], task.id.as_str());
+
+        let claim = format!("/tasks/{}/claim", task.id);
+        let response = api.handle(&request("POST", &claim, r#"{"by": "nvim"}"#));
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body["task"]["claimed_by"], "human:nvim");
+        assert_eq!(api.handle(&request("POST", &claim, "")).status, 409);
+
+        let complete = format!("/tasks/{}/complete", task.id);
+        let response = api.handle(&request("POST", &complete, r#"{"message": "done"}"#));
+        assert_eq!(response.body["task"]["status"], "done");
+        let shown = api.handle(&request("GET", &format!("/tasks/{}", task.id), ""));
+        assert!(shown.body["log"].to_string().contains("done"));
+
+        assert_eq!(
+            api.handle(&request("GET", "/tasks/t-missing", "")).status,
+            404
+        );
+        assert_eq!(api.handle(&request("DELETE", "/tasks", "")).status, 405);
+        assert_eq!(api.handle(&request("GET", "/nope", "")).status, 404);
+        assert_eq!(api.handle(&request("POST", "/runs", "{}")).status, 400);
+        assert_eq!(
+            api.handle(&request("GET", "/knowledge", "")).body,
+            json!([])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_serve_over_socket() {
+        let (dir, api) = setup();
+        let socket = dir.path().join(DEFAULT_SOCKET);
+        let server = tokio::spawn({
+            let (project, socket) = (api.project.clone(), socket.clone());
+            async move { serve(project, &socket).await }
+        });
+        for _ in 0..100 {
+            if socket.exists() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let mut stream = UnixStream::connect(&socket).await.unwrap();
+        stream
+            .write_all(b"GET /tasks HTTP/1.1\r\nHost: ralph\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("\r\n\r\n[]"));
+
+        assert!(serve(api.project.clone(), &socket).await.is_err());
+        server.abort();
+    }
+}
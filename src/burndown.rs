@@ -0,0 +1,239 @@
+//! Burn-down and velocity from task and journal timestamps.
+//!
+//! A leaf task counts as created on its `created_at` day and as completed on
+//! the day of its first `done` journal entry; tasks marked done by hand have
+//! no entry and fall back to `updated_at`. Each day of the window gets the
+//! tasks completed that day (throughput) and the tasks still open at its end
+//! (burn-down). [`sparkline`] draws either series as one line of text, which
+//! the TUI's Progress panel and `ralph stats` both use.
+
+use anyhow::Result;
+use chrono::{Days, NaiveDate, Utc};
+use serde::Serialize;
+
+use crate::dag::Db;
+
+/// Days shown by the TUI panel and `ralph stats`.
+pub const WINDOW_DAYS: u32 = 14;
+
+/// Days of throughput averaged into the velocity.
+const VELOCITY_DAYS: usize = 7;
+
+/// Bar heights, lowest first; days with nothing use the baseline `_`.
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One day of a [`Burndown`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DayPoint {
+    pub date: String,
+    /// Tasks completed that day.
+    pub completed: u32,
+    /// Tasks open at the end of the day.
+    pub remaining: u32,
+}
+
+/// Throughput and remaining work per day for a feature, or for all tasks.
+#[derive(Debug, Clone, Serialize)]
+pub struct Burndown {
+    /// Feature name; `None` covers every task.
+    pub feature: Option<String>,
+    pub days: Vec<DayPoint>,
+}
+
+impl Burndown {
+    /// Tasks open now.
+    pub fn remaining(&self) -> u32 {
+        self.days.last().map_or(0, |d| d.remaining)
+    }
+
+    /// Average tasks completed per day over the last week of the window.
+    pub fn velocity(&self) -> f64 {
+        let recent: Vec<u32> = self
+            .days
+            .iter()
+            .rev()
+            .take(VELOCITY_DAYS)
+            .map(|d| d.completed)
+            .collect();
+        if recent.is_empty() {
+            return 0.0;
+        }
+        recent.iter().sum::<u32>() as f64 / recent.len() as f64
+    }
+
+    /// One-line summary: burn-down sparkline, tasks left, velocity and the
+    /// days left at that pace.
+    pub fn summary(&self) -> String {
+        let remaining: Vec<u32> = self.days.iter().map(|d| d.remaining).collect();
+        let velocity = self.velocity();
+        let mut line = format!(
+            "{}  {} left · {velocity:.1}/day",
+            sparkline(&remaining),
+            self.remaining()
+        );
+        if self.remaining() > 0 && velocity > 0.0 {
+            let eta = (self.remaining() as f64 / velocity).ceil();
+            line.push_str(&format!(" · ~{eta:.0}d to go"));
+        }
+        line
+    }
+}
+
+/// Burn-down over the last `days` days (ending today, UTC) for the leaf
+/// tasks of `feature_id`, or of every feature when `None`.
+pub fn compute(db: &Db, feature_id: Option<&str>, days: u32) -> Result<Vec<DayPoint>> {
+    let mut stmt = db.conn().prepare(
+        "SELECT substr(t.created_at, 1, 10),
+                CASE WHEN t.status = 'done' THEN COALESCE(
+                    (SELECT substr(MIN(j.created_at), 1, 10) FROM journal j
+                     WHERE j.task_id = t.id AND j.outcome = 'done'),
+                    substr(t.updated_at, 1, 10)
+                ) END
+         FROM tasks t
+         WHERE t.archived_at IS NULL
+           AND NOT EXISTS (SELECT 1 FROM tasks c WHERE c.parent_id = t.id)
+           AND (?1 IS NULL OR t.feature_id = ?1)",
+    )?;
+    let tasks = stmt
+        .query_map([feature_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<(String, Option<String>)>, _>>()?;
+    Ok(series(&tasks, Utc::now().date_naive(), days))
+}
+
+/// Fold `(created, completed)` dates into one point per day of the window
+/// ending on `end`.
+fn series(tasks: &[(String, Option<String>)], end: NaiveDate, days: u32) -> Vec<DayPoint> {
+    let start = end
+        .checked_sub_days(Days::new(days.saturating_sub(1) as u64))
+        .unwrap_or(end);
+    start
+        .iter_days()
+        .take_while(|day| *day <= end)
+        .map(|day| {
+            let date = day.format("%Y-%m-%d").to_string();
+            let created = tasks.iter().filter(|(c, _)| *c <= date).count();
+            let done = tasks
+                .iter()
+                .filter(|(_, d)| d.as_ref().is_some_and(|d| *d <= date))
+                .count();
+            let completed = tasks
+                .iter()
+                .filter(|(_, d)| d.as_deref() == Some(date.as_str()))
+                .count();
+            DayPoint {
+                date,
+                completed: completed as u32,
+                remaining: created.saturating_sub(done) as u32,
+            }
+        })
+        .collect()
+}
+
+/// One line per burn-down, labelled with its feature (`all` for every
+/// task), then a `done/day` line charting `throughput`.
+pub fn render(burndowns: &[Burndown], throughput: &[u32]) -> Vec<String> {
+    let label = |b: &Burndown| b.feature.clone().unwrap_or_else(|| "all".to_string());
+    let width = burndowns
+        .iter()
+        .map(|b| label(b).len())
+        .chain(["done/day".len()])
+        .max()
+        .unwrap_or(0);
+    let mut lines: Vec<String> = burndowns
+        .iter()
+        .map(|b| format!("{:<width$}  {}", label(b), b.summary()))
+        .collect();
+    lines.push(format!("{:<width$}  {}", "done/day", sparkline(throughput)));
+    lines
+}
+
+/// `values` as bars scaled to the largest; zero is drawn as `_`.
+pub fn sparkline(values: &[u32]) -> String {
+    let max = values.iter().copied().max().unwrap_or(0);
+    values
+        .iter()
+        .map(|&v| {
+            if v == 0 {
+                '_'
+            } else {
+                let level = (v as usize * BARS.len()).div_ceil(max as usize);
+                BARS[level.clamp(1, BARS.len()) - 1]
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(created: &str, done: Option<&str>) -> (String, Option<String>) {
+        (created.to_string(), done.map(str::to_string))
+    }
+
+    #[test]
+    fn series_tracks_throughput_and_remaining_work() {
+        let end = NaiveDate::from_ymd_opt(2026, 3, 5).unwrap();
+        let tasks = [
+            task("2026-03-01", Some("2026-03-02")),
+            task("2026-03-01", Some("2026-03-04")),
+            task("2026-03-01", Some("2026-03-04")),
+            task("2026-03-03", None),
+            task("2026-03-06", None), // after the window
+        ];
+        let days = series(&tasks, end, 4);
+        let dates: Vec<&str> = days.iter().map(|d| d.date.as_str()).collect();
+        assert_eq!(
+            dates,
+            ["2026-03-02", "2026-03-03", "2026-03-04", "2026-03-05"]
+        );
+        let completed: Vec<u32> = days.iter().map(|d| d.completed).collect();
+        assert_eq!(completed, [1, 0, 2, 0]);
+        let remaining: Vec<u32> = days.iter().map(|d| d.remaining).collect();
+        assert_eq!(remaining, [2, 3, 1, 1]);
+
+        let burndown = Burndown {
+            feature: None,
+            days,
+        };
+        assert_eq!(burndown.velocity(), 0.75);
+        assert_eq!(burndown.summary(), "▆█▃▃  1 left · 0.8/day · ~2d to go");
+        assert_eq!(
+            render(&[burndown], &completed),
+            [
+                "all       ▆█▃▃  1 left · 0.8/day · ~2d to go",
+                "done/day  ▄_█_"
+            ]
+        );
+    }
+
+    #[test]
+    fn compute_ignores_done_journal_entries_of_reset_tasks() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let db = crate::dag::init_db(temp_file.path().to_str().unwrap()).unwrap();
+        let done = crate::dag::create_task(&db, "Done", None, None, 0).unwrap();
+        let reset = crate::dag::create_task(&db, "Reset", None, None, 0).unwrap();
+        crate::dag::claim_task(&db, &done.id, "agent").unwrap();
+        crate::dag::complete_task(&db, &done.id).unwrap();
+        // Journaled done, then reset to pending by hand
+        db.conn()
+            .execute(
+                "INSERT INTO journal (run_id, iteration, task_id, outcome)
+                 VALUES ('run-1', 1, ?1, 'done')",
+                [&reset.id],
+            )
+            .unwrap();
+
+        let days = compute(&db, None, 1).unwrap();
+        assert_eq!(days.len(), 1);
+        assert_eq!(days[0].completed, 1);
+        assert_eq!(days[0].remaining, 1);
+    }
+
+    #[test]
+    fn sparkline_scales_to_the_largest_value() {
+        assert_eq!(sparkline(&[0, 1, 4, 8]), "_▁▄█");
+        assert_eq!(sparkline(&[3, 3]), "██");
+        assert_eq!(sparkline(&[]), "");
+    }
+}
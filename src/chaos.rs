@@ -0,0 +1,257 @@
+//! `ralph run --chaos`: failure injection for robustness testing.
+//!
+//! A hidden developer mode that makes the run loop meet the failures real
+//! runs meet, at chosen probabilities, so hooks, retries and recovery can be
+//! exercised without waiting for a flaky agent:
+//!
+//! - `crash`: the agent process dies before answering (a transient agent
+//!   error, so `execution.agent_retries` applies)
+//! - `sigil`: a closing sigil tag in the agent's output loses its `>`
+//! - `truncate`: the agent's output is cut off at a random point
+//! - `db`: claiming or completing a task fails with SQLite's "database is
+//!   locked"
+//!
+//! The spec is a comma-separated list of `kind=probability`, plus `all=` for
+//! every kind and `seed=` for a reproducible sequence:
+//! `--chaos=crash=0.2,db=0.05,seed=7`. Kinds not named stay off; a bare `--chaos`
+//! (or only a seed) sets every kind to 0.1.
+//! Every injection is announced as a `chaos` event.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+
+use crate::acp::connection::TransientAgentError;
+use crate::acp::types::RunResult;
+use crate::output::formatter;
+
+/// Probability of every kind under a bare `--chaos`.
+const DEFAULT_PROBABILITY: f64 = 0.1;
+
+/// Injection probabilities and the random sequence deciding them.
+#[derive(Debug, Clone)]
+pub struct Chaos {
+    pub crash: f64,
+    pub sigil: f64,
+    pub truncate: f64,
+    pub db: f64,
+    /// splitmix64 state, shared by clones so every site draws from one sequence.
+    state: Arc<AtomicU64>,
+}
+
+impl Chaos {
+    /// Parse a `--chaos` spec; one without probabilities sets every kind to 0.1.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut chaos = Chaos {
+            crash: 0.0,
+            sigil: 0.0,
+            truncate: 0.0,
+            db: 0.0,
+            state: Arc::new(AtomicU64::new(time_seed())),
+        };
+        let entries: Vec<&str> = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|e| !e.is_empty())
+            .collect();
+        if entries.iter().all(|e| e.starts_with("seed=")) {
+            chaos.set_all(DEFAULT_PROBABILITY);
+        }
+        for entry in entries {
+            let (key, value) = entry
+                .split_once('=')
+                .with_context(|| format!("chaos: expected kind=probability, got '{entry}'"))?;
+            let value = value.trim();
+            if key.trim() == "seed" {
+                let seed = value
+                    .parse()
+                    .with_context(|| format!("chaos: invalid seed '{value}'"))?;
+                chaos.state.store(seed, Ordering::Relaxed);
+                continue;
+            }
+            let p: f64 = value
+                .parse()
+                .ok()
+                .filter(|p| (0.0..=1.0).contains(p))
+                .with_context(|| format!("chaos: {key} must be a probability in 0..=1"))?;
+            match key.trim() {
+                "crash" => chaos.crash = p,
+                "sigil" => chaos.sigil = p,
+                "truncate" => chaos.truncate = p,
+                "db" => chaos.db = p,
+                "all" => chaos.set_all(p),
+                other => bail!(
+                    "chaos: unknown kind '{other}' (expected crash, sigil, truncate, db, all or seed)"
+                ),
+            }
+        }
+        Ok(chaos)
+    }
+
+    fn set_all(&mut self, p: f64) {
+        self.crash = p;
+        self.sigil = p;
+        self.truncate = p;
+        self.db = p;
+    }
+
+    /// One line for the run header.
+    pub fn describe(&self) -> String {
+        format!(
+            "crash={} sigil={} truncate={} db={}",
+            self.crash, self.sigil, self.truncate, self.db
+        )
+    }
+
+    /// Next value of the splitmix64 sequence.
+    fn next(&self) -> u64 {
+        let mut z = self
+            .state
+            .fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed)
+            .wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn roll(&self, p: f64) -> bool {
+        p > 0.0 && ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+
+    /// A crashed agent in place of this attempt, or `None` to run it.
+    pub fn agent_crash(&self) -> Option<anyhow::Error> {
+        if !self.roll(self.crash) {
+            return None;
+        }
+        announce("agent crash");
+        Some(
+            TransientAgentError {
+                message: "chaos: agent server shut down unexpectedly".to_string(),
+                session_id: None,
+            }
+            .into(),
+        )
+    }
+
+    /// Truncate the agent's output or break one of its sigils.
+    pub fn mangle(&self, result: &mut RunResult) {
+        let RunResult::Completed(result) = result else {
+            return;
+        };
+        let text = &mut result.full_text;
+        if self.roll(self.truncate) && !text.is_empty() {
+            let mut cut = (self.next() % text.len() as u64) as usize;
+            while !text.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            announce(&format!(
+                "stream truncated at {cut} of {} bytes",
+                text.len()
+            ));
+            text.truncate(cut);
+        }
+        if self.roll(self.sigil) {
+            if let Some(at) = break_closing_tag(text) {
+                announce(&format!("malformed sigil at byte {at}"));
+            }
+        }
+    }
+
+    /// "database is locked" at `site`, or `Ok`.
+    pub fn db_lock(&self, site: &str) -> Result<()> {
+        if !self.roll(self.db) {
+            return Ok(());
+        }
+        announce(&format!("database lock while {site}"));
+        Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error {
+                code: rusqlite::ErrorCode::DatabaseBusy,
+                extended_code: 5,
+            },
+            Some("database is locked".to_string()),
+        ))
+        .with_context(|| format!("chaos: {site}"))
+    }
+}
+
+/// Drop the `>` of the last closing tag in `text`; its byte offset.
+fn break_closing_tag(text: &mut String) -> Option<usize> {
+    let open = text.rfind("</")?;
+    let close = open + text[open..].find('>')?;
+    text.remove(close);
+    Some(open)
+}
+
+fn announce(what: &str) {
+    let message = format!("injected {what}");
+    formatter::print_warning(&format!("ralph: chaos: {message}"));
+    formatter::emit_event("chaos", &message, true);
+}
+
+fn time_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64
+        ^ u64::from(std::process::id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::acp::types::StreamingResult;
+
+    #[test]
+    fn spec_sets_probabilities_and_seed() {
+        let chaos = Chaos::parse("").unwrap();
+        assert_eq!(chaos.describe(), "crash=0.1 sigil=0.1 truncate=0.1 db=0.1");
+        let chaos = Chaos::parse("all=0.5, db=0, seed=7").unwrap();
+        assert_eq!(chaos.describe(), "crash=0.5 sigil=0.5 truncate=0.5 db=0");
+        assert!(Chaos::parse("crash=2").is_err());
+        assert!(Chaos::parse("fire=0.1").is_err());
+        assert!(Chaos::parse("crash").is_err());
+
+        // The same seed gives the same sequence; zero never fires.
+        let a = Chaos::parse("seed=42,all=0").unwrap();
+        let b = Chaos::parse("seed=42").unwrap();
+        assert_eq!(b.crash, 0.1);
+        assert_eq!(
+            (0..8).map(|_| a.next()).collect::<Vec<_>>(),
+            (0..8).map(|_| b.next()).collect::<Vec<_>>()
+        );
+        assert!((0..100).all(|_| a.db_lock("claiming").is_ok()));
+        assert!(a.agent_crash().is_none());
+    }
+
+    #[test]
+    fn certain_injections_always_fire() {
+        let chaos = Chaos::parse("all=1,seed=3").unwrap();
+        let err = chaos.agent_crash().unwrap();
+        assert!(err.downcast_ref::<TransientAgentError>().is_some());
+        let err = chaos.db_lock("claiming t-1").unwrap_err();
+        assert!(format!("{err:#}").contains("database is locked"));
+
+        let full = "Done.\n<task-done>t-1</task-done>";
+        let mut result = RunResult::Completed(StreamingResult {
+            full_text: full.to_string(),
+            thoughts: String::new(),
+            files_modified: Vec::new(),
+            files_read: Vec::new(),
+            duration_ms: 0,
+            stop_reason: agent_client_protocol::StopReason::EndTurn,
+            usage: Default::default(),
+            session_id: None,
+        });
+        chaos.mangle(&mut result);
+        let RunResult::Completed(result) = result else {
+            unreachable!()
+        };
+        assert!(result.full_text.len() < full.len());
+        assert!(!result.full_text.ends_with("</task-done>"));
+
+        let mut text = "<task-done>t-1</task-done>".to_string();
+        assert_eq!(break_closing_tag(&mut text), Some(14));
+        assert_eq!(text, "<task-done>t-1</task-done");
+    }
+}
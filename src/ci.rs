@@ -0,0 +1,234 @@
+//! Headless CI mode: JSONL event stream and the final `result.json` contract.
+//!
+//! When enabled (`ralph run <target> --ci`), the TUI and all interactive
+//! prompts are disabled, stdout carries one JSON object per event, and the run
+//! ends by writing `.ralph/result.json` for pipeline consumption.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::dag::Db;
+use crate::journal;
+
+/// Wall-clock timeout applied in CI mode when `--timeout` is not given (60 minutes).
+pub const DEFAULT_TIMEOUT_SECS: u64 = 3600;
+
+/// Exit code reported when the run hits its wall-clock timeout.
+pub const EXIT_TIMEOUT: u8 = 4;
+
+static CI_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Turn on CI mode for the rest of the process.
+pub fn enable() {
+    CI_MODE.store(true, Ordering::SeqCst);
+}
+
+/// Check whether CI mode is active.
+pub fn is_enabled() -> bool {
+    CI_MODE.load(Ordering::SeqCst)
+}
+
+/// One line of the JSONL event stream.
+#[derive(Debug, Serialize)]
+struct EventRecord<'a> {
+    ts: String,
+    category: &'a str,
+    message: &'a str,
+    error: bool,
+}
+
+/// Render a single event as a JSON line (without trailing newline).
+pub fn event_json(category: &str, message: &str, is_error: bool) -> String {
+    let record = EventRecord {
+        ts: chrono::Utc::now().to_rfc3339(),
+        category,
+        message,
+        error: is_error,
+    };
+    serde_json::to_string(&record).unwrap_or_default()
+}
+
+/// Write an event to stdout as one JSON line.
+pub fn emit_event(category: &str, message: &str, is_error: bool) {
+    let line = event_json(category, message, is_error);
+    let mut stdout = std::io::stdout().lock();
+    let _ = writeln!(stdout, "{line}");
+    let _ = stdout.flush();
+}
+
+/// Final machine-readable summary of a CI run.
+#[derive(Debug, Serialize)]
+pub struct CiResult {
+    pub outcome: String,
+    pub exit_code: u8,
+    pub run_id: String,
+    pub target: String,
+    pub duration_secs: f64,
+    pub iterations: usize,
+    pub tasks_completed: Vec<String>,
+    pub tasks_failed: Vec<String>,
+    pub files_modified: Vec<String>,
+    pub journal_ids: Vec<i64>,
+    /// Suggested follow-up commands from the end-of-run summary.
+    pub next_steps: Vec<String>,
+}
+
+impl CiResult {
+    /// Build the result from the journal entries recorded under `run_id`.
+    pub fn collect(
+        db: &Db,
+        run_id: &str,
+        target: &str,
+        outcome: &str,
+        exit_code: u8,
+        duration_secs: f64,
+    ) -> Result<Self> {
+        let entries = journal::query_journal_recent(db, run_id, u32::MAX)?;
+
+        let mut completed = Vec::new();
+        let mut failed = Vec::new();
+        let mut files = BTreeSet::new();
+        for entry in &entries {
+            if let Some(ref task_id) = entry.task_id {
+                let bucket = match entry.outcome.as_str() {
+                    "done" => &mut completed,
+                    "failed" => &mut failed,
+                    _ => continue,
+                };
+                if !bucket.contains(task_id) {
+                    bucket.push(task_id.clone());
+                }
+            }
+            files.extend(entry.files_modified.iter().cloned());
+        }
+
+        Ok(CiResult {
+            outcome: outcome.to_string(),
+            exit_code,
+            run_id: run_id.to_string(),
+            target: target.to_string(),
+            duration_secs,
+            iterations: entries.len(),
+            tasks_completed: completed,
+            tasks_failed: failed,
+            files_modified: files.into_iter().collect(),
+            journal_ids: entries.iter().map(|e| e.id).collect(),
+            next_steps: Vec::new(),
+        })
+    }
+}
+
+/// Path of the result file for a project.
+pub fn result_path(project_root: &Path) -> PathBuf {
+    project_root.join(".ralph").join("result.json")
+}
+
+/// Write `result.json` atomically (temp file + rename).
+pub fn write_result(project_root: &Path, result: &CiResult) -> Result<PathBuf> {
+    let path = result_path(project_root);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    let body = serde_json::to_string_pretty(result)?;
+    std::fs::write(&tmp, body).with_context(|| format!("Failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, &path)
+        .with_context(|| format!("Failed to move result into {}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag;
+    use crate::journal::JournalEntry;
+    use tempfile::{NamedTempFile, TempDir};
+
+    fn entry(
+        run_id: &str,
+        iteration: u32,
+        task_id: &str,
+        outcome: &str,
+        files: &[&str],
+    ) -> JournalEntry {
+        JournalEntry {
+            id: 0,
+            run_id: run_id.to_string(),
+            iteration,
+            task_id: Some(task_id.to_string()),
+            feature_id: None,
+            outcome: outcome.to_string(),
+            model: Some("sonnet".to_string()),
+            duration_secs: 1.0,
+            cost_usd: 0.0,
+            input_tokens: 0,
+            output_tokens: 0,
+            files_modified: files.iter().map(|f| f.to_string()).collect(),
+            files_read: Vec::new(),
+            notes: None,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    #[test]
+    fn event_json_is_single_line_object() {
+        let line = event_json("task", "t-abc done\nsecond line", false);
+        assert!(!line.contains('\n'));
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["category"], "task");
+        assert_eq!(value["message"], "t-abc done\nsecond line");
+        assert_eq!(value["error"], false);
+        assert!(value["ts"].is_string());
+    }
+
+    #[test]
+    fn collect_summarizes_journal_for_run() -> Result<()> {
+        let temp = NamedTempFile::new()?;
+        let db = dag::init_db(temp.path().to_str().unwrap())?;
+        let a = dag::create_task(&db, "A", None, None, 0)?;
+        let b = dag::create_task(&db, "B", None, None, 0)?;
+
+        journal::insert_journal_entry(&db, &entry("run-1", 1, &a.id, "retried", &["src/a.rs"]))?;
+        journal::insert_journal_entry(&db, &entry("run-1", 2, &a.id, "done", &["src/a.rs"]))?;
+        journal::insert_journal_entry(&db, &entry("run-1", 3, &b.id, "failed", &["src/b.rs"]))?;
+        journal::insert_journal_entry(&db, &entry("run-other", 1, &b.id, "done", &["x.rs"]))?;
+
+        let result = CiResult::collect(&db, "run-1", "feat", "failure", 1, 12.5)?;
+        assert_eq!(result.iterations, 3);
+        assert_eq!(result.tasks_completed, vec![a.id.clone()]);
+        assert_eq!(result.tasks_failed, vec![b.id.clone()]);
+        assert_eq!(result.files_modified, vec!["src/a.rs", "src/b.rs"]);
+        assert_eq!(result.journal_ids.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn write_result_creates_file() -> Result<()> {
+        let dir = TempDir::new()?;
+        let result = CiResult {
+            outcome: "complete".to_string(),
+            exit_code: 0,
+            run_id: "run-1".to_string(),
+            target: "feat".to_string(),
+            duration_secs: 1.0,
+            iterations: 0,
+            tasks_completed: vec![],
+            tasks_failed: vec![],
+            files_modified: vec![],
+            journal_ids: vec![],
+            next_steps: vec!["ralph stats".to_string()],
+        };
+        let path = write_result(dir.path(), &result)?;
+        assert_eq!(path, dir.path().join(".ralph/result.json"));
+        let value: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+        assert_eq!(value["outcome"], "complete");
+        assert_eq!(value["next_steps"][0], "ralph stats");
+        assert!(!path.with_extension("json.tmp").exists());
+        Ok(())
+    }
+}
@@ -1,6 +1,9 @@
 //! CLI argument parsing using clap.
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use clap_complete::{ArgValueCandidates, Shell};
+
+use crate::completions;
 
 /// Looping harness for hands-off AI agent workflows.
 ///
@@ -10,16 +13,46 @@ use clap::{Parser, Subcommand};
 /// according to the whims of the LLM. Use --limit=1 to test before unleashing
 /// unattended loops.
 #[derive(Parser, Debug)]
-#[command(name = "ralph", version, about, long_about = None)]
+#[command(name = "ralph", version, about, long_about = None, after_long_help = EXAMPLES)]
 pub struct Args {
     /// Disable ratatui UI and force plain terminal output
     #[arg(long, global = true)]
     pub no_ui: bool,
 
+    /// Answer yes to every confirmation (e.g. `ralph feature delete x --yes`)
+    #[arg(long, short = 'y', global = true)]
+    pub yes: bool,
+
+    /// Never read stdin: confirmations are declined and interrupts stop the run
+    #[arg(long, global = true, env = "RALPH_NO_INPUT", value_parser = clap::builder::BoolishValueParser::new())]
+    pub no_input: bool,
+
     #[command(subcommand)]
     pub command: Option<Command>,
 }
 
+/// Shown after `ralph --help`.
+const EXAMPLES: &str = "\
+Examples:
+  ralph init                                  Set up .ralph.toml and .ralph/ here
+  ralph feature create auth                   Interview, spec, plan and task DAG for 'auth'
+  ralph run auth --limit 1                    One iteration, to check the setup
+  ralph run auth                              Loop until every task is done
+  ralph task add 'Fix flaky test' -c 'cargo test passes 5 times in a row'
+  ralph run t-1a2b3c4d                        Work on a single task
+  ralph task list --ready                     What the next iteration can pick up
+  source <(ralph completions bash)            TAB completion, incl. feature names and task IDs";
+
+/// Shown after `ralph completions --help`.
+const COMPLETIONS_HELP: &str = "\
+Load completions on shell startup so they always match the installed ralph:
+  bash        echo 'source <(ralph completions bash)' >> ~/.bashrc
+  zsh         echo 'source <(ralph completions zsh)' >> ~/.zshrc
+  fish        echo 'ralph completions fish | source' >> ~/.config/fish/config.fish
+  powershell  Add 'ralph completions powershell | Out-String | Invoke-Expression' to $PROFILE
+
+Feature names and task IDs are completed from .ralph/progress.db when run inside a project.";
+
 /// Available subcommands.
 #[derive(Subcommand, Debug)]
 pub enum Command {
@@ -35,27 +68,107 @@ pub enum Command {
         #[command(subcommand)]
         action: TaskAction,
     },
+    /// Maintain the knowledge base (export, import, lint)
+    Knowledge {
+        #[command(subcommand)]
+        action: KnowledgeAction,
+    },
+    /// Search the iteration journal
+    Journal {
+        #[command(subcommand)]
+        action: JournalAction,
+    },
+    /// Inspect and upgrade the progress database schema
+    Db {
+        #[command(subcommand)]
+        action: DbAction,
+    },
+    /// Two-way sync of standalone tasks with an issue tracker (linear, jira)
+    Sync {
+        #[command(subcommand)]
+        action: SyncAction,
+    },
+    /// Inspect and edit .ralph.toml (check, get, set)
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Show task counts and verification statistics, including flaky checks
+    Stats {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Dashboard appearance (themes)
+    Ui {
+        #[command(subcommand)]
+        action: UiAction,
+    },
+    /// Compare runs recorded in the journal (diff)
+    Runs {
+        #[command(subcommand)]
+        action: RunsAction,
+    },
+    /// List run logs, or page, tail or follow one run's log
+    Logs {
+        /// Run ID (default: list runs with logs)
+        #[arg(value_name = "RUN_ID", add = ArgValueCandidates::new(completions::run_ids))]
+        run_id: Option<String>,
+
+        /// Task whose recorded output to show (with --terminals)
+        #[arg(long, value_name = "TASK_ID", conflicts_with = "run_id", requires = "terminals", add = ArgValueCandidates::new(completions::task_ids))]
+        task: Option<String>,
+
+        /// Show the terminal commands the agent ran for --task: exit codes and full output
+        #[arg(long, requires = "task", conflicts_with = "run_id")]
+        terminals: bool,
+
+        /// Keep printing as the run writes, until it finishes
+        #[arg(short, long, requires = "run_id")]
+        follow: bool,
+
+        /// Print only the last N lines instead of paging
+        #[arg(short = 'n', long, value_name = "N", requires = "run_id")]
+        lines: Option<usize>,
+    },
+    /// Serve a local HTTP API on a Unix socket for editor integrations
+    Api {
+        /// Socket to listen on (default: .ralph/ralph.sock in the project)
+        #[arg(long, value_name = "PATH")]
+        socket: Option<std::path::PathBuf>,
+    },
+    /// Print a shell completion script (bash, zsh, fish, powershell, elvish)
+    #[command(after_long_help = COMPLETIONS_HELP)]
+    Completions {
+        /// Shell to complete for
+        #[arg(value_name = "SHELL")]
+        shell: Shell,
+    },
     /// Authenticate with the agent (e.g. claude auth login)
     Auth {
-        /// Agent command to authenticate
+        /// Agent profile ([agents.<name>] in .ralph.toml) whose auth_command to run
         #[arg(long, env = "RALPH_AGENT")]
         agent: Option<String>,
     },
     /// Run the agent loop on a feature or task
     Run {
-        /// Feature name or task ID (t-...) to run
-        #[arg(value_name = "TARGET")]
-        target: String,
+        /// Feature name(s) or one task ID (t-...) to run
+        #[arg(value_name = "TARGET", required = true, num_args = 1.., add = ArgValueCandidates::new(completions::run_targets))]
+        targets: Vec<String>,
 
-        /// Maximum iterations; 0 = forever
-        #[arg(long, value_name = "N", env = "RALPH_LIMIT")]
-        limit: Option<u32>,
+        /// How to schedule several features: finish each in turn, or alternate between them
+        #[arg(long, value_enum, default_value_t = FeatureOrder::Sequential)]
+        order: FeatureOrder,
 
-        /// Model strategy: fixed, cost-optimized, escalate, plan-then-execute
+        /// Maximum iterations; 0 = forever, auto = unresolved leaf tasks x execution.auto_limit_factor
+        #[arg(long, value_name = "N|auto", env = "RALPH_LIMIT")]
+        limit: Option<IterationLimit>,
+
+        /// Model strategy: fixed, cost-optimized, escalate, plan-then-execute, adaptive
         #[arg(long, value_name = "STRATEGY", env = "RALPH_MODEL_STRATEGY")]
         model_strategy: Option<String>,
 
-        /// Model for fixed strategy: opus (4.6), sonnet (4.6), haiku (4.5). Implies --model-strategy=fixed when used alone.
+        /// Model for fixed strategy: opus (4.6), sonnet (4.6), haiku (4.5), or the aliases strong, balanced, fast, mapped per agent by `[agents.<name>.models]`. Implies --model-strategy=fixed when used alone.
         #[arg(long, value_name = "MODEL", env = "RALPH_MODEL")]
         model: Option<String>,
 
@@ -70,9 +183,270 @@ pub enum Command {
         /// Agent command to spawn
         #[arg(long, env = "RALPH_AGENT")]
         agent: Option<String>,
+
+        /// Headless CI mode: no TUI or prompts, JSONL events on stdout, writes .ralph/result.json
+        #[arg(long, env = "RALPH_CI", value_parser = clap::builder::BoolishValueParser::new())]
+        ci: bool,
+
+        /// Hard wall-clock timeout for the whole run in seconds (CI default: 3600)
+        #[arg(long, value_name = "SECS", env = "RALPH_TIMEOUT")]
+        timeout: Option<u64>,
+
+        /// Ready-task ordering: fifo, priority, aged (default: [execution].scheduler or priority)
+        #[arg(long, value_name = "SCHEDULER", env = "RALPH_SCHEDULER")]
+        scheduler: Option<String>,
+
+        /// When failed tasks block the run, start a planning session to propose fixes without asking
+        #[arg(long)]
+        auto_replan: bool,
+
+        /// Ask before every terminal command the agent runs; approved commands are not asked again this run
+        #[arg(long, conflicts_with = "ci")]
+        approve_commands: bool,
+
+        /// Only run these task IDs (comma-separated); dependencies among them still apply
+        #[arg(long, value_name = "ID,...", value_delimiter = ',', add = ArgValueCandidates::new(completions::task_ids))]
+        only: Vec<String>,
+
+        /// Never pick these task IDs (comma-separated)
+        #[arg(long, value_name = "ID,...", value_delimiter = ',', add = ArgValueCandidates::new(completions::task_ids))]
+        skip: Vec<String>,
+
+        /// Air-gapped run: only agents marked `local = true`, no webhooks, no network commands
+        #[arg(long, env = "RALPH_OFFLINE", value_parser = clap::builder::BoolishValueParser::new())]
+        offline: bool,
+
+        /// Developer mode: inject agent crashes, malformed sigils, truncated output and
+        /// database lock errors (`crash=0.2,sigil=0.1,truncate=0.1,db=0.05,seed=7`; bare: all 0.1)
+        #[arg(long, hide = true, value_name = "SPEC", env = "RALPH_CHAOS", num_args = 0..=1, require_equals = true, default_missing_value = "")]
+        chaos: Option<String>,
+    },
+}
+
+/// Knowledge subcommands.
+#[derive(Subcommand, Debug)]
+pub enum KnowledgeAction {
+    /// List knowledge entries with their tags and scope
+    List {
+        /// Only entries scoped to this feature (by name)
+        #[arg(long, value_name = "NAME", add = ArgValueCandidates::new(completions::feature_names))]
+        feature: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Write knowledge entries to a .tar.gz bundle
+    Export {
+        /// Bundle file to write
+        #[arg(short, long, value_name = "PATH")]
+        output: std::path::PathBuf,
+
+        /// Only export entries with at least one of these tags (comma-separated)
+        #[arg(long, value_name = "TAGS", value_delimiter = ',')]
+        tags: Vec<String>,
+    },
+    /// Import a bundle, merging duplicates into existing entries
+    Import {
+        /// Bundle file to read
+        #[arg(value_name = "BUNDLE")]
+        bundle: std::path::PathBuf,
+    },
+    /// Check entries for broken links, missing tags, near-duplicate titles,
+    /// overlong bodies and stale feature references
+    Lint {
+        /// Repair what can be repaired automatically
+        #[arg(long)]
+        fix: bool,
     },
 }
 
+/// Journal subcommands.
+#[derive(Subcommand, Debug)]
+pub enum JournalAction {
+    /// Full-text search over journal notes, ranked by relevance and recency
+    Search {
+        /// Words to search for
+        #[arg(value_name = "QUERY", required = true, num_args = 1..)]
+        query: Vec<String>,
+
+        /// Only entries for this task ID
+        #[arg(long, value_name = "ID", add = ArgValueCandidates::new(completions::task_ids))]
+        task: Option<String>,
+
+        /// Only entries for this feature (by name)
+        #[arg(long, value_name = "NAME", add = ArgValueCandidates::new(completions::feature_names))]
+        feature: Option<String>,
+
+        /// Maximum number of results
+        #[arg(long, value_name = "N", default_value_t = 20)]
+        limit: u32,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// Database subcommands.
+#[derive(Subcommand, Debug)]
+pub enum DbAction {
+    /// Apply pending schema migrations (the database is backed up first)
+    Migrate {
+        /// Show pending migrations without applying them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Archive old journal entries and task logs to .ralph/archive/, delete them, and VACUUM
+    Prune {
+        /// Keep history newer than this many days
+        #[arg(long, value_name = "DAYS", default_value_t = 90)]
+        keep_days: u32,
+
+        /// Keep the journal of this many most recent runs regardless of age
+        #[arg(long, value_name = "N", default_value_t = 50)]
+        keep_runs: u32,
+
+        /// Show what would be pruned without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// Config subcommands.
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Strictly validate .ralph.toml: unknown keys, invalid values and models,
+    /// missing agent binaries. Exits non-zero when a problem is found
+    Check,
+    /// Print a value set in .ralph.toml. Exits non-zero when it is unset
+    Get {
+        /// Dotted key, e.g. execution.max_retries
+        #[arg(value_name = "KEY")]
+        key: String,
+    },
+    /// Set a value in .ralph.toml, keeping comments and formatting. The value
+    /// is validated like `ralph config check` before the file is written
+    Set {
+        /// Dotted key, e.g. ui.theme
+        #[arg(value_name = "KEY")]
+        key: String,
+
+        /// TOML value (5, true, ["a", "b"]); anything else is a string
+        #[arg(value_name = "VALUE", allow_hyphen_values = true)]
+        value: String,
+    },
+}
+
+/// Run comparison subcommands.
+#[derive(Subcommand, Debug)]
+pub enum RunsAction {
+    /// Side-by-side summary of two runs: iterations, outcomes per task,
+    /// durations, cost, models and files modified
+    Diff {
+        /// Baseline run ID
+        #[arg(value_name = "RUN_A", add = ArgValueCandidates::new(completions::run_ids))]
+        run_a: String,
+
+        /// Run ID to compare against the baseline
+        #[arg(value_name = "RUN_B", add = ArgValueCandidates::new(completions::run_ids))]
+        run_b: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// UI subcommands.
+#[derive(Subcommand, Debug)]
+pub enum UiAction {
+    /// List built-in themes and theme files (.ralph/themes/, ~/.config/ralph/themes/)
+    Themes,
+}
+
+/// Sync subcommands, one per tracker.
+#[derive(Subcommand, Debug)]
+pub enum SyncAction {
+    /// Sync with Linear ([sync.linear] in .ralph.toml, token in LINEAR_API_KEY)
+    Linear {
+        #[command(flatten)]
+        args: SyncArgs,
+    },
+    /// Sync with Jira Cloud ([sync.jira] in .ralph.toml, token in JIRA_API_TOKEN)
+    Jira {
+        #[command(flatten)]
+        args: SyncArgs,
+    },
+}
+
+/// Options shared by every `ralph sync` tracker.
+#[derive(clap::Args, Debug)]
+pub struct SyncArgs {
+    /// Show what would be imported and pushed without changing anything
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Skip importing issues as tasks
+    #[arg(long)]
+    pub no_import: bool,
+
+    /// Skip pushing task status changes to the tracker
+    #[arg(long)]
+    pub no_push: bool,
+}
+
+/// `ralph run --limit`: a fixed iteration count, or `auto` to derive it from
+/// the remaining work.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IterationLimit {
+    Fixed(u32),
+    Auto,
+}
+
+impl IterationLimit {
+    /// The fixed count, if any.
+    pub fn fixed(self) -> Option<u32> {
+        match self {
+            IterationLimit::Fixed(n) => Some(n),
+            IterationLimit::Auto => None,
+        }
+    }
+}
+
+impl std::str::FromStr for IterationLimit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("auto") {
+            return Ok(IterationLimit::Auto);
+        }
+        s.parse()
+            .map(IterationLimit::Fixed)
+            .map_err(|_| format!("invalid limit '{s}' (expected a number or auto)"))
+    }
+}
+
+/// How `ralph run` schedules tasks across several features.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeatureOrder {
+    /// Tasks of earlier features first; later ones only when earlier ones have none ready
+    Sequential,
+    /// Alternate between features, one task each
+    Interleaved,
+}
+
+/// A phase of `feature create`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeaturePhase {
+    /// Interview and write the spec
+    Spec,
+    /// Write the implementation plan from the spec
+    Plan,
+    /// Decompose the plan into the task DAG
+    Build,
+}
+
 /// Feature subcommands.
 #[derive(Subcommand, Debug)]
 pub enum FeatureAction {
@@ -82,6 +456,30 @@ pub enum FeatureAction {
         #[arg(value_name = "NAME")]
         name: String,
 
+        /// Use an existing markdown document as the spec (skips the interview)
+        #[arg(long, value_name = "PATH")]
+        from_doc: Option<std::path::PathBuf>,
+
+        /// Run only this phase, then stop for manual review. `build` rebuilds
+        /// the task DAG from the current plan, archiving the previous one.
+        #[arg(long, value_enum, value_name = "PHASE")]
+        phase: Option<FeaturePhase>,
+
+        /// Model to use: opus 4.6 (default), sonnet 4.6, haiku 4.5
+        #[arg(long, value_name = "MODEL")]
+        model: Option<String>,
+
+        /// Agent command to spawn
+        #[arg(long, env = "RALPH_AGENT")]
+        agent: Option<String>,
+    },
+    /// Update the plan after code drift and reconcile the task DAG
+    /// (done tasks are kept, pending ones updated)
+    Replan {
+        /// Feature name
+        #[arg(value_name = "NAME", add = ArgValueCandidates::new(completions::feature_names))]
+        name: String,
+
         /// Model to use: opus 4.6 (default), sonnet 4.6, haiku 4.5
         #[arg(long, value_name = "MODEL")]
         model: Option<String>,
@@ -91,16 +489,84 @@ pub enum FeatureAction {
         agent: Option<String>,
     },
     /// List all features and their status
-    List,
+    List {
+        /// List archived features instead
+        #[arg(long)]
+        archived: bool,
+
+        /// Write the list to FILE instead of showing it
+        #[arg(long, value_name = "FILE")]
+        out: Option<std::path::PathBuf>,
+    },
+    /// Show a feature's spec (or plan) rendered as markdown
+    Show {
+        /// Feature name
+        #[arg(value_name = "NAME", add = ArgValueCandidates::new(completions::feature_names))]
+        name: String,
+
+        /// Show the plan instead of the spec
+        #[arg(long)]
+        plan: bool,
+
+        /// Write the document to FILE instead of showing it
+        #[arg(long, value_name = "FILE")]
+        out: Option<std::path::PathBuf>,
+    },
+    /// Show what changed between revisions of a feature's spec or plan
+    Diff {
+        /// Feature name
+        #[arg(value_name = "NAME", add = ArgValueCandidates::new(completions::feature_names))]
+        name: String,
+
+        /// Diff the plan instead of the spec
+        #[arg(long)]
+        plan: bool,
+
+        /// Older revision (default: the one before --to)
+        #[arg(long, value_name = "REV")]
+        from: Option<i32>,
+
+        /// Newer revision (default: the working file)
+        #[arg(long, value_name = "REV")]
+        to: Option<i32>,
+    },
+    /// List plan sections no task was derived from and tasks with no plan section
+    Coverage {
+        /// Feature name
+        #[arg(value_name = "NAME", add = ArgValueCandidates::new(completions::feature_names))]
+        name: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
     /// Delete a feature and all its tasks
     Delete {
         /// Feature name
-        #[arg(value_name = "NAME")]
+        #[arg(value_name = "NAME", add = ArgValueCandidates::new(completions::feature_names))]
+        name: String,
+    },
+    /// Move a feature to .ralph/archive/ and hide it and its tasks
+    Archive {
+        /// Feature name
+        #[arg(value_name = "NAME", add = ArgValueCandidates::new(completions::feature_names))]
+        name: String,
+    },
+    /// Bring an archived feature back
+    Restore {
+        /// Feature name
+        #[arg(value_name = "NAME", add = ArgValueCandidates::new(completions::archived_feature_names))]
+        name: String,
+    },
+    /// Copy a feature's spec, plan and unresolved tasks into a new feature
+    Clone {
+        /// Feature to copy
+        #[arg(value_name = "NAME", add = ArgValueCandidates::new(completions::feature_names))]
         name: String,
 
-        /// Skip confirmation prompt
-        #[arg(long, short)]
-        yes: bool,
+        /// Name of the new feature
+        #[arg(value_name = "NEW_NAME")]
+        new_name: String,
     },
 }
 
@@ -113,12 +579,13 @@ pub enum TaskAction {
         #[arg(value_name = "TITLE")]
         title: String,
 
-        /// Task description
-        #[arg(short, long, value_name = "DESC")]
+        /// Task description; may open with a `---` frontmatter block (criteria,
+        /// files, links, labels)
+        #[arg(short, long, value_name = "DESC", allow_hyphen_values = true)]
         description: Option<String>,
 
         /// Parent task ID
-        #[arg(long, value_name = "ID")]
+        #[arg(long, value_name = "ID", add = ArgValueCandidates::new(completions::task_ids))]
         parent: Option<String>,
 
         /// Feature ID to associate with
@@ -132,6 +599,18 @@ pub enum TaskAction {
         /// Maximum retries for this task
         #[arg(long, value_name = "N", default_value = "3")]
         max_retries: i32,
+
+        /// Acceptance criterion (repeatable, one checklist item per flag)
+        #[arg(long = "criterion", short = 'c', value_name = "TEXT")]
+        criteria: Vec<String>,
+
+        /// Working directory for the task, relative to the project root
+        #[arg(long, value_name = "DIR")]
+        cwd: Option<String>,
+
+        /// Label the task (repeatable, e.g. --label backend --label risky)
+        #[arg(long = "label", short = 'l', value_name = "LABEL", add = ArgValueCandidates::new(completions::labels))]
+        labels: Vec<String>,
     },
     /// Interactively create a new standalone task (Claude-assisted)
     Create {
@@ -146,17 +625,21 @@ pub enum TaskAction {
     /// Show full task details
     Show {
         /// Task ID
-        #[arg(value_name = "ID")]
+        #[arg(value_name = "ID", add = ArgValueCandidates::new(completions::task_ids))]
         id: String,
 
         /// Output as JSON
         #[arg(long)]
         json: bool,
+
+        /// Write the details to FILE instead of showing it
+        #[arg(long, value_name = "FILE", conflicts_with = "json")]
+        out: Option<std::path::PathBuf>,
     },
     /// List tasks
     List {
         /// Filter by feature name
-        #[arg(long, value_name = "NAME")]
+        #[arg(long, value_name = "NAME", add = ArgValueCandidates::new(completions::feature_names))]
         feature: Option<String>,
 
         /// Filter by status
@@ -171,14 +654,24 @@ pub enum TaskAction {
         #[arg(long)]
         all: bool,
 
+        /// Filter by label, from --label or the description's frontmatter
+        /// (repeatable: tasks must carry every label; searches all tasks
+        /// unless --feature or --ready is given)
+        #[arg(long = "label", short = 'l', value_name = "LABEL", add = ArgValueCandidates::new(completions::labels))]
+        labels: Vec<String>,
+
         /// Output as JSON
         #[arg(long)]
         json: bool,
+
+        /// Write the list to FILE instead of showing it
+        #[arg(long, value_name = "FILE", conflicts_with = "json")]
+        out: Option<std::path::PathBuf>,
     },
     /// Update task fields
     Update {
         /// Task ID
-        #[arg(value_name = "ID")]
+        #[arg(value_name = "ID", add = ArgValueCandidates::new(completions::task_ids))]
         id: String,
 
         /// New title
@@ -186,81 +679,162 @@ pub enum TaskAction {
         title: Option<String>,
 
         /// New description
-        #[arg(short, long, value_name = "DESC")]
+        #[arg(short, long, value_name = "DESC", allow_hyphen_values = true)]
         description: Option<String>,
 
         /// New priority
         #[arg(long, value_name = "N")]
         priority: Option<i32>,
+
+        /// Add a label (repeatable)
+        #[arg(long = "label", short = 'l', value_name = "LABEL", add = ArgValueCandidates::new(completions::labels))]
+        labels: Vec<String>,
+
+        /// Remove a label added with --label (repeatable)
+        #[arg(long = "unlabel", value_name = "LABEL", add = ArgValueCandidates::new(completions::labels))]
+        unlabel: Vec<String>,
     },
     /// Delete a task
     Delete {
         /// Task ID
-        #[arg(value_name = "ID")]
+        #[arg(value_name = "ID", add = ArgValueCandidates::new(completions::task_ids))]
         id: String,
+    },
+    /// Mark done, delete, reset or reprioritize many tasks in one transaction
+    Bulk {
+        /// Select tasks with this status
+        #[arg(long, value_name = "STATUS", required_unless_present_any = ["feature", "stdin"])]
+        status: Option<String>,
 
-        /// Skip confirmation prompt in UI mode
-        #[arg(long, short)]
-        yes: bool,
+        /// Select this feature's tasks
+        #[arg(long, value_name = "NAME", add = ArgValueCandidates::new(completions::feature_names))]
+        feature: Option<String>,
+
+        /// Read newline-separated task IDs from stdin instead (needs --yes)
+        #[arg(long, conflicts_with_all = ["status", "feature"])]
+        stdin: bool,
+
+        #[command(subcommand)]
+        action: BulkAction,
+    },
+    /// Show the next ready task with the context a run would give the agent
+    Next {
+        /// Only consider this feature's tasks
+        #[arg(long, value_name = "NAME", add = ArgValueCandidates::new(completions::feature_names))]
+        feature: Option<String>,
+
+        /// Claim the task so runs leave it to you until it is done, failed or reset
+        #[arg(long)]
+        claim: bool,
     },
     /// Mark a task as done
     Done {
         /// Task ID
-        #[arg(value_name = "ID")]
+        #[arg(value_name = "ID", add = ArgValueCandidates::new(completions::task_ids))]
         id: String,
 
-        /// Skip confirmation prompt in UI mode
-        #[arg(long, short)]
-        yes: bool,
+        /// Note for the task log; runs show it to the tasks this one unblocks
+        #[arg(short = 'm', long, value_name = "MSG")]
+        message: Option<String>,
     },
     /// Mark a task as failed
     Fail {
         /// Task ID
-        #[arg(value_name = "ID")]
+        #[arg(value_name = "ID", add = ArgValueCandidates::new(completions::task_ids))]
         id: String,
 
         /// Failure reason
         #[arg(short = 'r', long, value_name = "REASON")]
         reason: Option<String>,
-
-        /// Skip confirmation prompt in UI mode
-        #[arg(long, short)]
-        yes: bool,
     },
     /// Reset a task to pending
     Reset {
         /// Task ID
-        #[arg(value_name = "ID")]
+        #[arg(value_name = "ID", add = ArgValueCandidates::new(completions::task_ids))]
+        id: String,
+    },
+    /// Revert the working-tree changes a task made and reset it to pending
+    Rollback {
+        /// Task ID
+        #[arg(value_name = "ID", add = ArgValueCandidates::new(completions::task_ids))]
         id: String,
 
-        /// Skip confirmation prompt in UI mode
-        #[arg(long, short)]
-        yes: bool,
+        /// List what would be reverted without changing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Choose which of the task's files to revert
+        #[arg(long)]
+        pick: bool,
     },
     /// Add or view task log entries
     Log {
         /// Task ID
-        #[arg(value_name = "ID")]
+        #[arg(value_name = "ID", add = ArgValueCandidates::new(completions::task_ids))]
         id: String,
 
         /// Log message to add
         #[arg(short = 'm', long, value_name = "MSG")]
         message: Option<String>,
+
+        /// Write the log entries to FILE instead of showing it
+        #[arg(long, value_name = "FILE", conflicts_with = "message")]
+        out: Option<std::path::PathBuf>,
     },
     /// Manage task dependencies
     Deps {
         #[command(subcommand)]
         action: DepsAction,
     },
+    /// Check the task DAG for dependency cycles and tasks stuck behind failed blockers
+    Validate {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show the longest dependency chain among unresolved tasks and the blockers holding up the most work
+    CriticalPath {
+        /// Only consider this feature's tasks
+        #[arg(long, value_name = "NAME", add = ArgValueCandidates::new(completions::feature_names))]
+        feature: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
     /// Show task tree with status colors
     Tree {
         /// Root task ID
-        #[arg(value_name = "ID")]
+        #[arg(value_name = "ID", add = ArgValueCandidates::new(completions::task_ids))]
         id: String,
 
         /// Output as JSON
         #[arg(long)]
         json: bool,
+
+        /// Write the tree to FILE instead of showing it
+        #[arg(long, value_name = "FILE", conflicts_with = "json")]
+        out: Option<std::path::PathBuf>,
+    },
+}
+
+/// What `task bulk` does to the selected tasks.
+#[derive(Subcommand, Debug)]
+pub enum BulkAction {
+    /// Mark the tasks done
+    Done {
+        /// Note for each task's log
+        #[arg(short = 'm', long, value_name = "MSG")]
+        message: Option<String>,
+    },
+    /// Delete the tasks and their subtasks
+    Delete,
+    /// Reset the tasks to pending
+    Reset,
+    /// Set the tasks' priority (lower = higher priority)
+    Priority {
+        #[arg(value_name = "N", allow_negative_numbers = true)]
+        priority: i32,
     },
 }
 
@@ -270,28 +844,32 @@ pub enum DepsAction {
     /// Add a dependency: A must complete before B
     Add {
         /// Blocker task ID (must complete first)
-        #[arg(value_name = "BLOCKER")]
+        #[arg(value_name = "BLOCKER", add = ArgValueCandidates::new(completions::task_ids))]
         blocker: String,
 
         /// Blocked task ID (depends on blocker)
-        #[arg(value_name = "BLOCKED")]
+        #[arg(value_name = "BLOCKED", add = ArgValueCandidates::new(completions::task_ids))]
         blocked: String,
     },
     /// Remove a dependency
     Rm {
         /// Blocker task ID
-        #[arg(value_name = "BLOCKER")]
+        #[arg(value_name = "BLOCKER", add = ArgValueCandidates::new(completions::task_ids))]
         blocker: String,
 
         /// Blocked task ID
-        #[arg(value_name = "BLOCKED")]
+        #[arg(value_name = "BLOCKED", add = ArgValueCandidates::new(completions::task_ids))]
         blocked: String,
     },
     /// List dependencies for a task
     List {
         /// Task ID
-        #[arg(value_name = "ID")]
+        #[arg(value_name = "ID", add = ArgValueCandidates::new(completions::task_ids))]
         id: String,
+
+        /// Write the dependencies to FILE instead of showing it
+        #[arg(long, value_name = "FILE")]
+        out: Option<std::path::PathBuf>,
     },
 }
 
@@ -299,7 +877,13 @@ pub enum DepsAction {
 pub const VALID_MODELS: &[&str] = &["opus", "sonnet", "haiku"];
 
 /// Valid strategy names.
-pub const VALID_STRATEGIES: &[&str] = &["fixed", "cost-optimized", "escalate", "plan-then-execute"];
+pub const VALID_STRATEGIES: &[&str] = &[
+    "fixed",
+    "cost-optimized",
+    "escalate",
+    "plan-then-execute",
+    "adaptive",
+];
 
 impl Args {
     pub fn parse_args() -> Self {
@@ -313,16 +897,20 @@ pub fn resolve_model_strategy(
     model: &Option<String>,
     model_strategy: &Option<String>,
 ) -> Result<(String, Option<String>), String> {
-    // Validate model name if provided
-    if let Some(ref m) = model {
-        if !VALID_MODELS.contains(&m.as_str()) {
-            return Err(format!(
-                "invalid model '{}': must be one of {}",
-                m,
-                VALID_MODELS.join(", ")
-            ));
-        }
-    }
+    // Validate model name if provided; aliases (strong, balanced, fast) become tiers
+    let model = &match model {
+        Some(m) => match crate::agent_models::tier(m) {
+            Some(tier) => Some(tier.to_string()),
+            None => {
+                return Err(format!(
+                    "invalid model '{}': must be one of {} (or strong, balanced, fast)",
+                    m,
+                    VALID_MODELS.join(", ")
+                ))
+            }
+        },
+        None => None,
+    };
 
     // Validate strategy name if provided
     if let Some(ref strategy) = model_strategy {
@@ -358,14 +946,453 @@ mod tests {
     fn test_agent_flag_parsed_on_run() {
         let args = Args::try_parse_from(["ralph", "run", "feat", "--agent", "gemini-cli"]).unwrap();
         match args.command {
-            Some(Command::Run { agent, target, .. }) => {
+            Some(Command::Run { agent, targets, .. }) => {
                 assert_eq!(agent, Some("gemini-cli".to_string()));
-                assert_eq!(target, "feat");
+                assert_eq!(targets, vec!["feat"]);
             }
             _ => panic!("expected Run command"),
         }
     }
 
+    #[test]
+    fn test_run_accepts_several_features_and_an_order() {
+        let args =
+            Args::try_parse_from(["ralph", "run", "auth", "billing", "--order", "interleaved"])
+                .unwrap();
+        match args.command {
+            Some(Command::Run { targets, order, .. }) => {
+                assert_eq!(targets, vec!["auth", "billing"]);
+                assert_eq!(order, FeatureOrder::Interleaved);
+            }
+            _ => panic!("expected Run command"),
+        }
+        assert!(Args::try_parse_from(["ralph", "run"]).is_err());
+    }
+
+    #[test]
+    fn test_approve_commands_flag_parsed_on_run() {
+        let args = Args::try_parse_from(["ralph", "run", "auth", "--approve-commands"]).unwrap();
+        match args.command {
+            Some(Command::Run {
+                approve_commands, ..
+            }) => assert!(approve_commands),
+            _ => panic!("expected Run command"),
+        }
+        assert!(
+            Args::try_parse_from(["ralph", "run", "auth", "--approve-commands", "--ci"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_only_and_skip_parsed_on_run() {
+        let args = Args::try_parse_from([
+            "ralph", "run", "auth", "--only", "t-1,t-2", "--skip", "t-2", "--only", "t-3",
+        ])
+        .unwrap();
+        match args.command {
+            Some(Command::Run { only, skip, .. }) => {
+                assert_eq!(only, ["t-1", "t-2", "t-3"]);
+                assert_eq!(skip, ["t-2"]);
+            }
+            _ => panic!("expected Run command"),
+        }
+    }
+
+    #[test]
+    fn test_offline_flag_parsed_on_run() {
+        let args = Args::try_parse_from(["ralph", "run", "auth", "--offline", "--ci"]).unwrap();
+        match args.command {
+            Some(Command::Run { offline, ci, .. }) => assert!(offline && ci),
+            _ => panic!("expected Run command"),
+        }
+    }
+
+    #[test]
+    fn test_auto_replan_flag_parsed_on_run() {
+        let args = Args::try_parse_from(["ralph", "run", "feat", "--auto-replan"]).unwrap();
+        match args.command {
+            Some(Command::Run { auto_replan, .. }) => assert!(auto_replan),
+            _ => panic!("expected Run command"),
+        }
+    }
+
+    #[test]
+    fn test_ci_and_timeout_flags_parsed_on_run() {
+        let args =
+            Args::try_parse_from(["ralph", "run", "feat", "--ci", "--timeout", "900"]).unwrap();
+        match args.command {
+            Some(Command::Run { ci, timeout, .. }) => {
+                assert!(ci);
+                assert_eq!(timeout, Some(900));
+            }
+            _ => panic!("expected Run command"),
+        }
+    }
+
+    #[test]
+    fn test_knowledge_export_tags_split_on_comma() {
+        let args = Args::try_parse_from([
+            "ralph",
+            "knowledge",
+            "export",
+            "-o",
+            "kb.tar.gz",
+            "--tags",
+            "sqlite,acp",
+        ])
+        .unwrap();
+        match args.command {
+            Some(Command::Knowledge {
+                action: KnowledgeAction::Export { output, tags },
+            }) => {
+                assert_eq!(output, std::path::PathBuf::from("kb.tar.gz"));
+                assert_eq!(tags, vec!["sqlite", "acp"]);
+            }
+            _ => panic!("expected Knowledge Export command"),
+        }
+    }
+
+    #[test]
+    fn test_feature_create_from_doc() {
+        let args = Args::try_parse_from([
+            "ralph",
+            "feature",
+            "create",
+            "auth",
+            "--from-doc",
+            "docs/auth.md",
+        ])
+        .unwrap();
+        match args.command {
+            Some(Command::Feature {
+                action: FeatureAction::Create { name, from_doc, .. },
+            }) => {
+                assert_eq!(name, "auth");
+                assert_eq!(from_doc, Some(std::path::PathBuf::from("docs/auth.md")));
+            }
+            _ => panic!("expected Feature Create command"),
+        }
+    }
+
+    #[test]
+    fn test_feature_create_phase() {
+        let args = Args::try_parse_from(["ralph", "feature", "create", "auth", "--phase", "plan"])
+            .unwrap();
+        match args.command {
+            Some(Command::Feature {
+                action: FeatureAction::Create { phase, .. },
+            }) => assert_eq!(phase, Some(FeaturePhase::Plan)),
+            _ => panic!("expected Feature Create command"),
+        }
+        assert!(
+            Args::try_parse_from(["ralph", "feature", "create", "auth", "--phase", "tasks"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_db_migrate_dry_run() {
+        let args = Args::try_parse_from(["ralph", "db", "migrate", "--dry-run"]).unwrap();
+        match args.command {
+            Some(Command::Db {
+                action: DbAction::Migrate { dry_run },
+            }) => assert!(dry_run),
+            _ => panic!("expected Db Migrate command"),
+        }
+    }
+
+    #[test]
+    fn test_config_check_args() {
+        let args = Args::try_parse_from(["ralph", "config", "check"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Config {
+                action: ConfigAction::Check
+            })
+        ));
+
+        let args = Args::try_parse_from(["ralph", "config", "set", "execution.max_retries", "-1"])
+            .unwrap();
+        match args.command {
+            Some(Command::Config {
+                action: ConfigAction::Set { key, value },
+            }) => {
+                assert_eq!(key, "execution.max_retries");
+                assert_eq!(value, "-1");
+            }
+            _ => panic!("expected Config Set command"),
+        }
+    }
+
+    #[test]
+    fn test_task_labels_are_repeatable() {
+        let args = Args::try_parse_from([
+            "ralph", "task", "add", "Orders", "--label", "backend", "-l", "risky",
+        ])
+        .unwrap();
+        match args.command {
+            Some(Command::Task {
+                action: TaskAction::Add { labels, .. },
+            }) => assert_eq!(labels, ["backend", "risky"]),
+            _ => panic!("expected Task Add command"),
+        }
+
+        let args = Args::try_parse_from([
+            "ralph", "task", "list", "--label", "api", "--label", "backend",
+        ])
+        .unwrap();
+        match args.command {
+            Some(Command::Task {
+                action: TaskAction::List { labels, .. },
+            }) => assert_eq!(labels, ["api", "backend"]),
+            _ => panic!("expected Task List command"),
+        }
+    }
+
+    #[test]
+    fn test_task_description_may_start_with_frontmatter() {
+        let args = Args::try_parse_from([
+            "ralph",
+            "task",
+            "add",
+            "Orders",
+            "-d",
+            "---\nlabels: [api]\n---\nBody",
+        ])
+        .unwrap();
+        match args.command {
+            Some(Command::Task {
+                action: TaskAction::Add { description, .. },
+            }) => assert_eq!(
+                description.as_deref(),
+                Some("---\nlabels: [api]\n---\nBody")
+            ),
+            _ => panic!("expected Task Add command"),
+        }
+    }
+
+    #[test]
+    fn test_stats_args() {
+        let args = Args::try_parse_from(["ralph", "stats", "--json"]).unwrap();
+        assert!(matches!(args.command, Some(Command::Stats { json: true })));
+    }
+
+    #[test]
+    fn test_runs_diff_args() {
+        let args =
+            Args::try_parse_from(["ralph", "runs", "diff", "run-aaaa", "run-bbbb", "--json"])
+                .unwrap();
+        match args.command {
+            Some(Command::Runs {
+                action: RunsAction::Diff { run_a, run_b, json },
+            }) => {
+                assert_eq!((run_a.as_str(), run_b.as_str()), ("run-aaaa", "run-bbbb"));
+                assert!(json);
+            }
+            _ => panic!("expected Runs Diff command"),
+        }
+    }
+
+    #[test]
+    fn test_ui_themes_args() {
+        let args = Args::try_parse_from(["ralph", "ui", "themes"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Ui {
+                action: UiAction::Themes
+            })
+        ));
+    }
+
+    #[test]
+    fn test_sync_args() {
+        let args =
+            Args::try_parse_from(["ralph", "sync", "jira", "--dry-run", "--no-push"]).unwrap();
+        match args.command {
+            Some(Command::Sync {
+                action: SyncAction::Jira { args },
+            }) => {
+                assert!(args.dry_run && args.no_push && !args.no_import);
+            }
+            _ => panic!("expected Sync Jira command"),
+        }
+    }
+
+    #[test]
+    fn test_feature_diff_args() {
+        let args =
+            Args::try_parse_from(["ralph", "feature", "diff", "auth", "--plan", "--from", "1"])
+                .unwrap();
+        match args.command {
+            Some(Command::Feature {
+                action:
+                    FeatureAction::Diff {
+                        name,
+                        plan,
+                        from,
+                        to,
+                    },
+            }) => {
+                assert_eq!(name, "auth");
+                assert!(plan);
+                assert_eq!(from, Some(1));
+                assert_eq!(to, None);
+            }
+            _ => panic!("expected Feature Diff command"),
+        }
+    }
+
+    #[test]
+    fn test_feature_coverage_args() {
+        let args =
+            Args::try_parse_from(["ralph", "feature", "coverage", "auth", "--json"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Feature {
+                action: FeatureAction::Coverage { ref name, json: true },
+            }) if name == "auth"
+        ));
+    }
+
+    #[test]
+    fn test_feature_show_args() {
+        let args = Args::try_parse_from(["ralph", "feature", "show", "auth", "--plan"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Feature {
+                action: FeatureAction::Show {
+                    ref name,
+                    plan: true,
+                    out: None,
+                },
+            }) if name == "auth"
+        ));
+    }
+
+    #[test]
+    fn test_task_bulk_args() {
+        let args = Args::try_parse_from([
+            "ralph",
+            "task",
+            "bulk",
+            "--status",
+            "pending",
+            "--feature",
+            "auth",
+            "priority",
+            "2",
+        ])
+        .unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Task {
+                action: TaskAction::Bulk {
+                    status: Some(ref status),
+                    feature: Some(_),
+                    stdin: false,
+                    action: BulkAction::Priority { priority: 2 },
+                    ..
+                },
+            }) if status == "pending"
+        ));
+
+        let args =
+            Args::try_parse_from(["ralph", "task", "bulk", "--stdin", "-y", "delete"]).unwrap();
+        assert!(args.yes);
+        assert!(matches!(
+            args.command,
+            Some(Command::Task {
+                action: TaskAction::Bulk {
+                    stdin: true,
+                    action: BulkAction::Delete,
+                    ..
+                },
+            })
+        ));
+
+        // Tasks must be selected, one way only
+        assert!(Args::try_parse_from(["ralph", "task", "bulk", "reset"]).is_err());
+        assert!(Args::try_parse_from([
+            "ralph", "task", "bulk", "--stdin", "--status", "x", "reset"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_feature_archive_and_list_archived_args() {
+        let args = Args::try_parse_from(["ralph", "feature", "archive", "auth", "-y"]).unwrap();
+        assert!(args.yes);
+        assert!(matches!(
+            args.command,
+            Some(Command::Feature {
+                action: FeatureAction::Archive { ref name },
+            }) if name == "auth"
+        ));
+
+        let args = Args::try_parse_from(["ralph", "feature", "list", "--archived"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Feature {
+                action: FeatureAction::List {
+                    archived: true,
+                    out: None
+                },
+            })
+        ));
+    }
+
+    #[test]
+    fn test_hidden_chaos_flag() {
+        let args = Args::try_parse_from(["ralph", "run", "--chaos", "feat"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Run { chaos: Some(ref spec), ref targets, .. })
+                if spec.is_empty() && targets == &["feat"]
+        ));
+        let args = Args::try_parse_from(["ralph", "run", "feat", "--chaos=db=0.5"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Run { chaos: Some(ref spec), .. }) if spec == "db=0.5"
+        ));
+    }
+
+    #[test]
+    fn test_out_flag_on_explorer_commands() {
+        let args =
+            Args::try_parse_from(["ralph", "task", "tree", "t-1", "--out", "tree.txt"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Task {
+                action: TaskAction::Tree { out: Some(ref path), json: false, .. },
+            }) if path.to_str() == Some("tree.txt")
+        ));
+        let args = Args::try_parse_from(["ralph", "task", "deps", "list", "t-1", "--out", "d.txt"])
+            .unwrap();
+        assert!(matches!(
+            args.command,
+            Some(Command::Task {
+                action: TaskAction::Deps {
+                    action: DepsAction::List { out: Some(_), .. }
+                },
+            })
+        ));
+        assert!(Args::try_parse_from(["ralph", "task", "list", "--json", "--out", "x"]).is_err());
+        assert!(
+            Args::try_parse_from(["ralph", "task", "log", "t-1", "-m", "hi", "--out", "x"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_yes_and_no_input_flags() {
+        let args = Args::try_parse_from(["ralph", "--yes", "task", "list", "--no-input"]).unwrap();
+        assert!(args.yes);
+        assert!(args.no_input);
+
+        let args = Args::try_parse_from(["ralph", "task", "list"]).unwrap();
+        assert!(!args.yes);
+    }
+
     #[test]
     fn test_global_no_ui_flag() {
         let args = Args::try_parse_from(["ralph", "--no-ui", "run", "feat"]).unwrap();
@@ -394,15 +1421,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn task_add_cwd_flag_parsed() {
+        let args =
+            Args::try_parse_from(["ralph", "task", "add", "Endpoint", "--cwd", "packages/api"])
+                .unwrap();
+        match args.command {
+            Some(Command::Task {
+                action: TaskAction::Add { title, cwd, .. },
+            }) => {
+                assert_eq!(title, "Endpoint");
+                assert_eq!(cwd.as_deref(), Some("packages/api"));
+            }
+            _ => panic!("expected task add command"),
+        }
+    }
+
     #[test]
     fn task_delete_yes_flag_parsed() {
         let args = Args::try_parse_from(["ralph", "task", "delete", "t-123", "--yes"]).unwrap();
         match args.command {
             Some(Command::Task {
-                action: TaskAction::Delete { id, yes },
+                action: TaskAction::Delete { id },
             }) => {
                 assert_eq!(id, "t-123");
-                assert!(yes);
+                assert!(args.yes);
             }
             _ => panic!("expected task delete command"),
         }
@@ -410,18 +1453,67 @@ mod tests {
 
     #[test]
     fn task_done_yes_flag_parsed() {
-        let args = Args::try_parse_from(["ralph", "task", "done", "t-123", "--yes"]).unwrap();
+        let args = Args::try_parse_from(["ralph", "task", "done", "t-123", "--yes", "-m", "Fixed"])
+            .unwrap();
         match args.command {
             Some(Command::Task {
-                action: TaskAction::Done { id, yes },
+                action: TaskAction::Done { id, message },
             }) => {
                 assert_eq!(id, "t-123");
-                assert!(yes);
+                assert_eq!(message.as_deref(), Some("Fixed"));
+                assert!(args.yes);
             }
             _ => panic!("expected task done command"),
         }
     }
 
+    #[test]
+    fn logs_task_terminals_parsed() {
+        let args =
+            Args::try_parse_from(["ralph", "logs", "--task", "t-123", "--terminals"]).unwrap();
+        match args.command {
+            Some(Command::Logs {
+                run_id,
+                task,
+                terminals,
+                ..
+            }) => {
+                assert_eq!(run_id, None);
+                assert_eq!(task.as_deref(), Some("t-123"));
+                assert!(terminals);
+            }
+            _ => panic!("expected logs command"),
+        }
+        assert!(Args::try_parse_from(["ralph", "logs", "--task", "t-123"]).is_err());
+        assert!(Args::try_parse_from(["ralph", "logs", "run-1", "--terminals"]).is_err());
+    }
+
+    #[test]
+    fn knowledge_list_feature_parsed() {
+        let args =
+            Args::try_parse_from(["ralph", "knowledge", "list", "--feature", "auth"]).unwrap();
+        match args.command {
+            Some(Command::Knowledge {
+                action: KnowledgeAction::List { feature, json },
+            }) => {
+                assert_eq!(feature.as_deref(), Some("auth"));
+                assert!(!json);
+            }
+            _ => panic!("expected Knowledge List command"),
+        }
+    }
+
+    #[test]
+    fn api_socket_parsed() {
+        let args = Args::try_parse_from(["ralph", "api", "--socket", "/tmp/r.sock"]).unwrap();
+        match args.command {
+            Some(Command::Api { socket }) => {
+                assert_eq!(socket, Some(std::path::PathBuf::from("/tmp/r.sock")));
+            }
+            _ => panic!("expected api command"),
+        }
+    }
+
     #[test]
     fn task_fail_yes_flag_parsed() {
         let args =
@@ -429,11 +1521,11 @@ mod tests {
                 .unwrap();
         match args.command {
             Some(Command::Task {
-                action: TaskAction::Fail { id, reason, yes },
+                action: TaskAction::Fail { id, reason },
             }) => {
                 assert_eq!(id, "t-123");
                 assert_eq!(reason, Some("why".to_string()));
-                assert!(yes);
+                assert!(args.yes);
             }
             _ => panic!("expected task fail command"),
         }
@@ -444,15 +1536,32 @@ mod tests {
         let args = Args::try_parse_from(["ralph", "task", "reset", "t-123", "--yes"]).unwrap();
         match args.command {
             Some(Command::Task {
-                action: TaskAction::Reset { id, yes },
+                action: TaskAction::Reset { id },
             }) => {
                 assert_eq!(id, "t-123");
-                assert!(yes);
+                assert!(args.yes);
             }
             _ => panic!("expected task reset command"),
         }
     }
 
+    #[test]
+    fn task_rollback_flags_parsed() {
+        let args =
+            Args::try_parse_from(["ralph", "task", "rollback", "t-123", "--dry-run"]).unwrap();
+        match args.command {
+            Some(Command::Task {
+                action: TaskAction::Rollback { id, dry_run, pick },
+            }) => {
+                assert_eq!(id, "t-123");
+                assert!(dry_run);
+                assert!(!pick);
+                assert!(!args.yes);
+            }
+            _ => panic!("expected task rollback command"),
+        }
+    }
+
     #[test]
     fn test_no_sandbox_flag_absent() {
         let result = Args::try_parse_from(["ralph", "run", "feat", "--no-sandbox"]);
@@ -503,6 +1612,14 @@ mod tests {
         assert!(result.unwrap_err().contains("invalid model"));
     }
 
+    #[test]
+    fn model_alias_resolves_to_tier() {
+        let model = Some("strong".to_string());
+        let (strategy, model) = resolve_model_strategy(&model, &None).unwrap();
+        assert_eq!(strategy, "fixed");
+        assert_eq!(model.as_deref(), Some("opus"));
+    }
+
     #[test]
     fn invalid_strategy_name_errors() {
         let model = None;
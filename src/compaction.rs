@@ -0,0 +1,230 @@
+//! Prompt compaction for iterations whose prompt would overflow the model's
+//! context window.
+//!
+//! The run loop estimates the size of the assembled prompt before each
+//! iteration. When it is over [`Config::prompt_token_budget`], the spec and
+//! plan (largest first) are replaced by summaries written by the cheap
+//! `journal_summary_model`.
+//! Summaries are cached under `.ralph/cache/summaries/`, keyed by a hash of the
+//! original text, so later iterations and runs reuse them until the document
+//! changes.
+
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+use crate::acp;
+use crate::acp::prompt::{build_prompt_text, estimate_tokens};
+use crate::acp::types::IterationContext;
+use crate::config::Config;
+use crate::output::formatter;
+use crate::review::DocumentKind;
+
+/// Shrink `context` until its prompt fits the token budget.
+///
+/// Never fails: a summary that cannot be produced leaves the original text in
+/// place with a warning, and the iteration goes ahead.
+pub async fn fit_to_budget(config: &Config, context: &mut IterationContext) {
+    let budget = config.prompt_token_budget();
+    let before = estimate_tokens(&build_prompt_text(config, context));
+    if before <= budget {
+        return;
+    }
+    formatter::print_warning(&format!(
+        "ralph: prompt is ~{before} tokens, over the {budget}-token budget; summarizing spec/plan"
+    ));
+
+    let mut order = [DocumentKind::Spec, DocumentKind::Plan];
+    order.sort_by_key(|kind| std::cmp::Reverse(document(context, *kind).map_or(0, str::len)));
+    let mut estimate = before;
+    for kind in order {
+        if estimate <= budget {
+            break;
+        }
+        let Some(original) = document(context, kind).map(str::to_string) else {
+            continue;
+        };
+        match summarize(config, kind, &original).await {
+            Ok(summary) => {
+                *document_mut(context, kind) = Some(summary);
+                estimate = estimate_tokens(&build_prompt_text(config, context));
+            }
+            Err(e) => formatter::print_warning(&format!(
+                "ralph: could not summarize the {}: {e:#}",
+                kind.label()
+            )),
+        }
+    }
+
+    if estimate <= budget {
+        formatter::emit_event_info(
+            "prompt",
+            &format!("compacted ~{before} \u{2192} ~{estimate} tokens"),
+        );
+    } else {
+        formatter::emit_event(
+            "prompt",
+            &format!("~{estimate} tokens after compaction, budget {budget}"),
+            true,
+        );
+    }
+}
+
+fn document(context: &IterationContext, kind: DocumentKind) -> Option<&str> {
+    match kind {
+        DocumentKind::Spec => context.spec_content.as_deref(),
+        DocumentKind::Plan => context.plan_content.as_deref(),
+    }
+}
+
+fn document_mut(context: &mut IterationContext, kind: DocumentKind) -> &mut Option<String> {
+    match kind {
+        DocumentKind::Spec => &mut context.spec_content,
+        DocumentKind::Plan => &mut context.plan_content,
+    }
+}
+
+/// Cached or freshly generated summary of `original`, with a note pointing the
+/// agent at the full document.
+async fn summarize(config: &Config, kind: DocumentKind, original: &str) -> Result<String> {
+    let path = cache_path(&config.project_root, kind, original);
+    let model = config.ralph_config.execution.journal_summary_model.as_str();
+    let summary = match std::fs::read_to_string(&path) {
+        Ok(cached) => cached,
+        Err(_) => {
+            formatter::emit_event_info(
+                "prompt",
+                &format!("summarizing {} with {}", kind.label(), model),
+            );
+            let result = acp::connection::run_autonomous(
+                &config.agent_command,
+                &config.project_root,
+                &summary_instructions(kind, original),
+                "Summarize the document.",
+                true,
+                Some(model),
+                Default::default(),
+            )
+            .await?;
+            let Some(summary) = parse_summary(&result.full_text) else {
+                bail!("summary agent did not emit a <summary> sigil");
+            };
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            std::fs::write(&path, &summary)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            summary
+        }
+    };
+    Ok(format!(
+        "_Summarized to fit the context window. The full {} is `{}.md` in the \
+         feature directory under `.ralph/features/`; read it when a detail is missing._\n\n{}",
+        kind.label(),
+        kind.label(),
+        summary.trim()
+    ))
+}
+
+/// `.ralph/cache/summaries/<kind>-<hash>.md`, keyed by the original text.
+fn cache_path(project_root: &Path, kind: DocumentKind, original: &str) -> PathBuf {
+    let hash = format!("{:x}", Sha256::digest(original.as_bytes()));
+    project_root
+        .join(".ralph/cache/summaries")
+        .join(format!("{}-{}.md", kind.label(), &hash[..16]))
+}
+
+fn summary_instructions(kind: DocumentKind, original: &str) -> String {
+    format!(
+        "You condense a feature {label} for a coding agent whose context window is too \
+         small for the full text.\n\n\
+         Keep every requirement, acceptance criterion, interface, data shape, file path \
+         and decision, and any constraint the implementation must respect. Drop \
+         background, motivation, examples and repetition. Aim for a quarter of the \
+         original length. Use markdown.\n\n\
+         Reply with the summary only, inside `<summary>...</summary>`.\n\n\
+         ## Original {label}\n\n{original}\n",
+        label = kind.label(),
+    )
+}
+
+fn parse_summary(text: &str) -> Option<String> {
+    let start = text.find("<summary>")? + "<summary>".len();
+    let end = text[start..].find("</summary>")?;
+    let summary = text[start..start + end].trim();
+    (!summary.is_empty()).then(|| summary.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::acp::types::TaskInfo;
+    use crate::project::{ProjectConfig, RalphConfig};
+    use tempfile::TempDir;
+
+    #[test]
+    fn summary_sigil_and_cache_key() {
+        assert_eq!(
+            parse_summary("ok\n<summary>\n- R1\n</summary>").as_deref(),
+            Some("- R1")
+        );
+        assert_eq!(parse_summary("<summary> </summary>"), None);
+        assert_eq!(parse_summary("no sigil"), None);
+
+        let root = Path::new("/p");
+        let a = cache_path(root, DocumentKind::Spec, "text");
+        assert_eq!(a, cache_path(root, DocumentKind::Spec, "text"));
+        assert_ne!(a, cache_path(root, DocumentKind::Spec, "text v2"));
+        assert_ne!(a, cache_path(root, DocumentKind::Plan, "text"));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn cached_summary_replaces_oversized_spec() {
+        let dir = TempDir::new().unwrap();
+        let mut ralph_config = RalphConfig::default();
+        ralph_config.execution.prompt_token_budget = 4_000;
+        let project = ProjectConfig {
+            root: dir.path().to_path_buf(),
+            config: ralph_config,
+        };
+        let config =
+            Config::from_run_args(None, None, None, project, None, None, false, None, None)
+                .unwrap();
+        let spec = "Requirement. ".repeat(2_000);
+        std::fs::create_dir_all(dir.path().join(".ralph/cache/summaries")).unwrap();
+        std::fs::write(
+            cache_path(dir.path(), DocumentKind::Spec, &spec),
+            "- Requirement",
+        )
+        .unwrap();
+
+        let mut context = IterationContext {
+            task: TaskInfo {
+                task_id: "t-1".to_string(),
+                title: "Task".to_string(),
+                description: String::new(),
+                frontmatter: Default::default(),
+                cwd: None,
+                parent: None,
+                completed_blockers: Vec::new(),
+            },
+            spec_content: Some(spec),
+            plan_content: Some("Short plan.".to_string()),
+            retry_info: None,
+            run_id: "run-00000001".to_string(),
+            journal_context: String::new(),
+            knowledge_context: String::new(),
+            scratchpad_context: String::new(),
+            diff_context: String::new(),
+            speculative_analysis: None,
+            resume_session_id: None,
+        };
+        fit_to_budget(&config, &mut context).await;
+
+        let spec = context.spec_content.unwrap();
+        assert!(spec.starts_with("_Summarized to fit the context window."));
+        assert!(spec.ends_with("- Requirement"));
+        assert_eq!(context.plan_content.as_deref(), Some("Short plan."));
+    }
+}
@@ -0,0 +1,171 @@
+//! Shell completion (`ralph completions <shell>`).
+//!
+//! The printed script does not contain the command tree. On every TAB it
+//! calls back into ralph with `COMPLETE=<shell>`, and ralph answers from the
+//! clap definitions in [`crate::cli`]. Feature names and task IDs are read
+//! from the project's progress database, opened read-only, so they are always
+//! current; outside a project they are simply not offered.
+
+use anyhow::{Context, Result};
+use clap::CommandFactory;
+use clap_complete::env::Shells;
+use clap_complete::{CompleteEnv, CompletionCandidate, Shell};
+use rusqlite::{Connection, OpenFlags};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::cli;
+use crate::project;
+
+/// Environment variable the scripts set when calling back into ralph.
+const COMPLETE_VAR: &str = "COMPLETE";
+
+const FEATURES_SQL: &str =
+    "SELECT name, status FROM features WHERE archived_at IS NULL ORDER BY name";
+const ARCHIVED_FEATURES_SQL: &str =
+    "SELECT name, status FROM features WHERE archived_at IS NOT NULL ORDER BY name";
+const TASKS_SQL: &str = "SELECT id, status || ': ' || title FROM tasks
+     WHERE archived_at IS NULL ORDER BY created_at DESC";
+const LABELS_SQL: &str = "SELECT label, count(*) || ' tasks' FROM task_labels
+     GROUP BY label ORDER BY label";
+const RUNS_SQL: &str = "SELECT run_id, count(*) || ' iterations' FROM journal
+     GROUP BY run_id ORDER BY MAX(id) DESC";
+
+/// If this process was started by a completion script, print the candidates
+/// for the words on the command line and exit. Must run before anything is
+/// written to stdout.
+pub fn handle_request() {
+    CompleteEnv::with_factory(cli::Args::command)
+        .var(COMPLETE_VAR)
+        .complete();
+}
+
+/// Write the script that registers ralph's completions with `shell`.
+pub fn write_script(shell: Shell, out: &mut dyn Write) -> Result<()> {
+    let completer = std::env::current_exe()
+        .ok()
+        .and_then(|path| path.to_str().map(str::to_string))
+        .unwrap_or_else(|| "ralph".to_string());
+    let name = shell.to_string();
+    Shells::builtins()
+        .completer(&name)
+        .with_context(|| format!("No completion support for {name}"))?
+        .write_registration(COMPLETE_VAR, "ralph", "ralph", &completer, out)
+        .context("Failed to write completion script")
+}
+
+/// Active feature names.
+pub fn feature_names() -> Vec<CompletionCandidate> {
+    project_candidates(FEATURES_SQL)
+}
+
+/// Archived feature names, for `feature restore`.
+pub fn archived_feature_names() -> Vec<CompletionCandidate> {
+    project_candidates(ARCHIVED_FEATURES_SQL)
+}
+
+/// Task IDs, newest first, with status and title as help text.
+pub fn task_ids() -> Vec<CompletionCandidate> {
+    project_candidates(TASKS_SQL)
+}
+
+/// Labels added with `--label`, with how many tasks carry each.
+pub fn labels() -> Vec<CompletionCandidate> {
+    project_candidates(LABELS_SQL)
+}
+
+/// Run IDs from the journal, newest first, for `ralph logs`.
+pub fn run_ids() -> Vec<CompletionCandidate> {
+    project_candidates(RUNS_SQL)
+}
+
+/// Anything `ralph run` accepts: feature names, then task IDs.
+pub fn run_targets() -> Vec<CompletionCandidate> {
+    let mut targets = feature_names();
+    targets.extend(task_ids());
+    targets
+}
+
+fn project_candidates(sql: &str) -> Vec<CompletionCandidate> {
+    progress_db()
+        .map(|path| candidates(&path, sql))
+        .unwrap_or_default()
+}
+
+fn progress_db() -> Option<PathBuf> {
+    let project = project::discover().ok()?;
+    let path = project.db_path();
+    path.exists().then_some(path)
+}
+
+/// `(value, help)` rows of `sql`. Completion must never print errors, so any
+/// failure yields no candidates.
+fn candidates(db_path: &Path, sql: &str) -> Vec<CompletionCandidate> {
+    let rows = || -> rusqlite::Result<Vec<(String, String)>> {
+        let conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    };
+    rows()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(value, help)| CompletionCandidate::new(value).help(Some(help.into())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag::{self, init_db};
+    use crate::feature;
+    use tempfile::TempDir;
+
+    fn values(candidates: &[CompletionCandidate]) -> Vec<String> {
+        candidates
+            .iter()
+            .map(|c| c.get_value().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn candidates_come_from_the_progress_db() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("progress.db");
+        let db = init_db(path.to_str().unwrap()).unwrap();
+        let feature = feature::create_feature(&db, "auth").unwrap();
+        let task = dag::create_task(&db, "Add login", None, None, 0).unwrap();
+        feature::create_feature(&db, "old").unwrap();
+        db.conn()
+            .execute(
+                "UPDATE features SET archived_at = '2026-01-01T00:00:00Z' WHERE name = 'old'",
+                [],
+            )
+            .unwrap();
+
+        assert_eq!(values(&candidates(&path, FEATURES_SQL)), vec![feature.name]);
+        assert_eq!(
+            values(&candidates(&path, ARCHIVED_FEATURES_SQL)),
+            vec!["old"]
+        );
+        dag::add_labels(&db, &task.id, &["backend".to_string()]).unwrap();
+        assert_eq!(values(&candidates(&path, LABELS_SQL)), vec!["backend"]);
+        let tasks = candidates(&path, TASKS_SQL);
+        assert_eq!(values(&tasks), vec![task.id]);
+        assert_eq!(
+            tasks[0].get_help().map(ToString::to_string).as_deref(),
+            Some("pending: Add login")
+        );
+        assert!(candidates(&dir.path().join("missing.db"), TASKS_SQL).is_empty());
+    }
+
+    #[test]
+    fn script_calls_back_into_ralph() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+            let mut out = Vec::new();
+            write_script(shell, &mut out).unwrap();
+            let script = String::from_utf8(out).unwrap();
+            assert!(script.contains(COMPLETE_VAR), "{shell}: {script}");
+        }
+    }
+}
@@ -2,21 +2,28 @@
 
 use anyhow::{bail, Result};
 use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
 use std::env;
 use std::fmt;
 use std::hash::{Hash, Hasher};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::chaos::Chaos;
 use crate::cli;
+use crate::dag::Scheduler;
+use crate::exit_when::ExitCondition;
 use crate::project::{ProjectConfig, RalphConfig};
 
 /// Target for the `ralph run` command.
 #[derive(Debug, Clone)]
 pub enum RunTarget {
-    /// Run a feature by name.
-    Feature(String),
+    /// Run one or more features by name, scheduled by `order`.
+    Features {
+        names: Vec<String>,
+        order: cli::FeatureOrder,
+    },
     /// Run a standalone task by ID (t-...).
     Task(String),
 }
@@ -32,6 +39,9 @@ pub enum ModelStrategy {
     Escalate,
     /// Use opus for planning (iteration 1), sonnet for execution.
     PlanThenExecute,
+    /// Weighted round-robin by each model's journal success rate, adjusted
+    /// per task type.
+    Adaptive,
 }
 
 impl fmt::Display for ModelStrategy {
@@ -41,6 +51,7 @@ impl fmt::Display for ModelStrategy {
             ModelStrategy::CostOptimized => write!(f, "cost-optimized"),
             ModelStrategy::Escalate => write!(f, "escalate"),
             ModelStrategy::PlanThenExecute => write!(f, "plan-then-execute"),
+            ModelStrategy::Adaptive => write!(f, "adaptive"),
         }
     }
 }
@@ -55,6 +66,7 @@ impl FromStr for ModelStrategy {
             "cost-optimized" => Ok(ModelStrategy::CostOptimized),
             "escalate" => Ok(ModelStrategy::Escalate),
             "plan-then-execute" => Ok(ModelStrategy::PlanThenExecute),
+            "adaptive" => Ok(ModelStrategy::Adaptive),
             _ => bail!("invalid model strategy '{}'", s),
         }
     }
@@ -89,9 +101,21 @@ fn generate_run_id() -> String {
     format!("run-{:08x}", hash as u32)
 }
 
+/// `--limit auto`: the iteration limit follows the remaining work.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoLimit {
+    /// Iterations budgeted per unresolved leaf task (`execution.auto_limit_factor`).
+    pub factor: f64,
+    /// Tasks in scope when the limit was last computed. It is recomputed
+    /// only once the DAG grows past this.
+    pub tasks_seen: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub limit: u32,
+    /// Set under `--limit auto`; `limit` is then raised as work is added.
+    pub auto_limit: Option<AutoLimit>,
     pub iteration: u32,
     pub total: u32,
     /// The active model strategy for this run.
@@ -103,6 +127,11 @@ pub struct Config {
     /// Escalation level for the `Escalate` strategy (0=haiku, 1=sonnet, 2=opus).
     /// Tracks the minimum model tier; the strategy never auto-de-escalates below this.
     pub escalation_level: u8,
+    /// Smooth weighted round-robin credit per model for the `Adaptive` strategy.
+    pub adaptive_credits: BTreeMap<String, f64>,
+    /// True when `current_model` came from a `<next-model>` hint, which the
+    /// `Adaptive` strategy must not replace.
+    pub model_hinted: bool,
     /// Project root directory (directory containing .ralph.toml).
     pub project_root: PathBuf,
     /// Parsed project configuration.
@@ -120,6 +149,20 @@ pub struct Config {
     pub run_target: Option<RunTarget>,
     /// The ACP agent command (program + optional args), e.g. "claude" or "gemini-cli --flag".
     pub agent_command: String,
+    /// Ordering policy for picking the next ready task.
+    pub scheduler: Scheduler,
+    /// The person running ralph (`[user] name` or git `user.name`).
+    pub user: Option<String>,
+    /// `--only`: the task IDs the run is limited to; empty runs every task.
+    pub only: Vec<String>,
+    /// `--skip`: task IDs the run never picks.
+    pub skip: Vec<String>,
+    /// HEAD when the run started; the base of `[execution] diff_context`.
+    pub run_base: Option<String>,
+    /// `[run] exit_when`, checked before each iteration.
+    pub exit_when: Option<ExitCondition>,
+    /// `--chaos`: failures to inject into this run.
+    pub chaos: Option<Chaos>,
 }
 
 impl Config {
@@ -134,6 +177,7 @@ impl Config {
         max_retries_override: Option<u32>,
         no_verify: bool,
         agent: Option<String>,
+        scheduler: Option<String>,
     ) -> Result<Self> {
         // Resolve model strategy early
         let (strategy_str, model) =
@@ -152,6 +196,7 @@ impl Config {
             ModelStrategy::CostOptimized => "sonnet".to_string(),
             ModelStrategy::Escalate => "haiku".to_string(),
             ModelStrategy::PlanThenExecute => "opus".to_string(),
+            ModelStrategy::Adaptive => "sonnet".to_string(),
         };
 
         let limit = limit.unwrap_or(0);
@@ -174,6 +219,8 @@ impl Config {
         let command = agent
             .or_else(|| env::var("RALPH_AGENT").ok())
             .unwrap_or_else(|| project.config.agent.command.clone());
+        // A profile name (`[agents.<name>]`) stands in for that profile's command.
+        let command = resolve_agent_profile(&project.config, command);
 
         // Validate with shlex::split() — error on malformed input (e.g., unclosed quotes)
         let parts = shlex::split(&command).ok_or_else(|| {
@@ -184,14 +231,33 @@ impl Config {
         }
         let agent_command = command;
 
+        // Resolve scheduler: --scheduler flag (or RALPH_SCHEDULER) > execution.scheduler
+        let scheduler = scheduler
+            .as_deref()
+            .unwrap_or(&execution.scheduler)
+            .parse::<Scheduler>()?
+            .with_aging(execution.aging_secs);
+
+        let user = project.user_name();
+        let exit_when = project
+            .config
+            .run
+            .exit_when
+            .as_deref()
+            .map(ExitCondition::parse)
+            .transpose()?;
+
         Ok(Config {
             limit,
+            auto_limit: None,
             iteration,
             total,
             model_strategy,
             model,
             current_model,
             escalation_level: 0,
+            adaptive_credits: BTreeMap::new(),
+            model_hinted: false,
             project_root: project.root,
             ralph_config: project.config,
             agent_id: generate_agent_id(),
@@ -200,9 +266,22 @@ impl Config {
             run_id: generate_run_id(),
             run_target,
             agent_command,
+            scheduler,
+            user,
+            only: Vec::new(),
+            skip: Vec::new(),
+            run_base: None,
+            exit_when,
+            chaos: None,
         })
     }
 
+    /// Whether `--only` and `--skip` let the run pick `task_id`.
+    pub fn selects(&self, task_id: &str) -> bool {
+        (self.only.is_empty() || self.only.iter().any(|id| id == task_id))
+            && !self.skip.iter().any(|id| id == task_id)
+    }
+
     /// Create config for next iteration.
     pub fn next_iteration(&self) -> Self {
         Config {
@@ -211,10 +290,133 @@ impl Config {
         }
     }
 
+    /// How this run is recorded on the tasks it creates and completes:
+    /// `user via agent`, or the agent program alone when no user is known.
+    pub fn attribution(&self) -> String {
+        let agent = shlex::split(&self.agent_command)
+            .and_then(|parts| parts.into_iter().next())
+            .map(|program| {
+                Path::new(&program)
+                    .file_name()
+                    .map_or(program.clone(), |name| name.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| self.agent_command.clone());
+        match &self.user {
+            Some(user) => format!("{user} via {agent}"),
+            None => agent,
+        }
+    }
+
+    /// Derive the limit from the remaining work (`--limit auto`) instead of a
+    /// fixed count; see [`Config::update_auto_limit`].
+    pub fn with_auto_limit(mut self) -> Self {
+        self.auto_limit = Some(AutoLimit {
+            factor: self.ralph_config.execution.auto_limit_factor,
+            tasks_seen: 0,
+        });
+        self
+    }
+
+    /// Recompute an auto limit when the scope holds more than the `tasks`
+    /// seen last time: iterations used so far plus `leaves` (unresolved leaf
+    /// tasks) times the factor, rounded up. The limit never shrinks. Returns
+    /// the new limit when it changed.
+    pub fn update_auto_limit(&mut self, tasks: usize, leaves: usize) -> Option<u32> {
+        let auto = self.auto_limit.as_mut()?;
+        if tasks <= auto.tasks_seen {
+            return None;
+        }
+        auto.tasks_seen = tasks;
+        let needed = (leaves as f64 * auto.factor).ceil().max(1.0) as u32;
+        let limit = (self.iteration - 1 + needed).max(self.limit);
+        if limit == self.limit {
+            return None;
+        }
+        self.limit = limit;
+        self.total = limit;
+        Some(limit)
+    }
+
     /// Check if iteration limit has been reached.
     pub fn limit_reached(&self) -> bool {
         self.limit > 0 && self.iteration > self.limit
     }
+
+    /// Wall-clock limit for one agent iteration (`execution.iteration_timeout_secs`).
+    pub fn iteration_timeout(&self) -> Option<std::time::Duration> {
+        match self.ralph_config.execution.iteration_timeout_secs {
+            0 => None,
+            secs => Some(std::time::Duration::from_secs(secs)),
+        }
+    }
+
+    /// Wait before agent retry `attempt` (1-based): `agent_retry_backoff_secs`
+    /// doubled for each earlier retry, capped at five minutes.
+    pub fn agent_retry_delay(&self, attempt: u32) -> std::time::Duration {
+        let base = self.ralph_config.execution.agent_retry_backoff_secs;
+        let secs = base.saturating_mul(1 << attempt.saturating_sub(1).min(16));
+        std::time::Duration::from_secs(secs.min(300))
+    }
+
+    /// Estimated prompt size above which the spec and plan are compacted.
+    ///
+    /// Defaults to half the context window, leaving the rest for the agent's
+    /// own tool calls and output.
+    pub fn prompt_token_budget(&self) -> usize {
+        match self.ralph_config.execution.prompt_token_budget {
+            0 => crate::acp::prompt::CONTEXT_WINDOW_TOKENS / 2,
+            tokens => tokens as usize,
+        }
+    }
+
+    /// Tool-use limits for one agent iteration (`execution.max_tool_calls`,
+    /// `execution.max_repeated_commands`).
+    pub fn tool_limits(&self) -> crate::acp::client_impl::ToolLimits {
+        crate::acp::client_impl::ToolLimits {
+            max_tool_calls: self.ralph_config.execution.max_tool_calls,
+            max_repeated_commands: self.ralph_config.execution.max_repeated_commands,
+        }
+    }
+}
+
+/// Login command used when no profile or `[agent].auth_command` applies.
+const DEFAULT_AUTH_COMMAND: &str = "claude auth login";
+
+/// Map an agent profile name to its configured command; other values pass through.
+pub fn resolve_agent_profile(config: &RalphConfig, agent: String) -> String {
+    match config.agents.get(&agent).and_then(|p| p.command.clone()) {
+        Some(command) => command,
+        None => agent,
+    }
+}
+
+/// Resolve the command `ralph auth` should run, split into program and args.
+///
+/// `--agent <name>` selects `[agents.<name>].auth_command`. Values that are not
+/// profile names fall back to `[agent].auth_command`, then `claude auth login`.
+pub fn resolve_auth_command(config: &RalphConfig, agent: Option<&str>) -> Result<Vec<String>> {
+    let command = match agent.and_then(|name| config.agents.get(name).map(|p| (name, p))) {
+        Some((name, profile)) => match &profile.auth_command {
+            Some(command) => command.clone(),
+            None => bail!(
+                "agent profile '{}' has no auth_command; add one under [agents.{}] in .ralph.toml",
+                name,
+                name
+            ),
+        },
+        None => config
+            .agent
+            .auth_command
+            .clone()
+            .unwrap_or_else(|| DEFAULT_AUTH_COMMAND.to_string()),
+    };
+
+    let parts = shlex::split(&command)
+        .ok_or_else(|| anyhow::anyhow!("invalid auth command: failed to parse \"{}\"", command))?;
+    if parts.is_empty() {
+        bail!("auth command is empty");
+    }
+    Ok(parts)
 }
 
 #[cfg(test)]
@@ -241,6 +443,7 @@ mod tests {
             None,
             false,
             None,
+            None,
         )
     }
 
@@ -326,6 +529,10 @@ mod tests {
             "plan-then-execute".parse::<ModelStrategy>().unwrap(),
             ModelStrategy::PlanThenExecute
         );
+        assert_eq!(
+            "adaptive".parse::<ModelStrategy>().unwrap(),
+            ModelStrategy::Adaptive
+        );
     }
 
     #[test]
@@ -371,6 +578,28 @@ mod tests {
         assert_eq!(next.agent_id, agent_id_1);
     }
 
+    #[test]
+    fn auto_limit_grows_with_the_dag() {
+        let mut config = config_from_run(None, None).unwrap();
+        assert_eq!(
+            config.update_auto_limit(4, 4),
+            None,
+            "off without --limit auto"
+        );
+
+        let mut config = config.with_auto_limit();
+        assert_eq!(config.update_auto_limit(4, 3), Some(6));
+        assert_eq!((config.limit, config.total), (6, 6));
+        // Unchanged DAG: no re-evaluation even though leaves were resolved
+        config.iteration = 3;
+        assert_eq!(config.update_auto_limit(4, 1), None);
+        // Two tasks added at iteration 3: two iterations used plus 3 leaves x 2
+        assert_eq!(config.update_auto_limit(6, 3), Some(8));
+        // Growth that needs fewer iterations than are left never lowers it
+        assert_eq!(config.update_auto_limit(7, 1), None);
+        assert_eq!(config.limit, 8);
+    }
+
     #[test]
     fn test_config_has_run_id() {
         let config = config_from_run(None, None).unwrap();
@@ -416,11 +645,34 @@ verify = true
             None,
             false,
             Some("gemini-cli".to_string()),
+            None,
         )
         .unwrap();
         assert_eq!(config.agent_command, "gemini-cli");
     }
 
+    #[test]
+    fn test_attribution_pairs_user_with_agent_program() {
+        let mut project = test_project();
+        project.config.user.name = Some("Alice".to_string());
+        let mut config = Config::from_run_args(
+            None,
+            None,
+            None,
+            project,
+            None,
+            None,
+            false,
+            Some("/opt/bin/gemini --experimental-acp".to_string()),
+            None,
+        )
+        .unwrap();
+        assert_eq!(config.user.as_deref(), Some("Alice"));
+        assert_eq!(config.attribution(), "Alice via gemini");
+        config.user = None;
+        assert_eq!(config.attribution(), "gemini");
+    }
+
     #[test]
     fn test_agent_config_backward_compat() {
         use crate::project::RalphConfig;
@@ -469,6 +721,7 @@ command = "gemini-cli"
             None,
             false,
             Some("claude 'unclosed quote".to_string()),
+            None,
         );
         assert!(result.is_err());
         let err = result.unwrap_err().to_string();
@@ -478,4 +731,149 @@ command = "gemini-cli"
             err
         );
     }
+
+    fn profiles_config() -> RalphConfig {
+        toml::from_str(
+            r#"
+[agents.gemini]
+command = "gemini --experimental-acp"
+auth_command = "gemini auth login --browser"
+
+[agents.local]
+command = "my-agent"
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_agent_profile_name_resolves_to_command() {
+        let project = ProjectConfig {
+            root: PathBuf::from("/test"),
+            config: profiles_config(),
+        };
+        let config = Config::from_run_args(
+            None,
+            None,
+            None,
+            project,
+            None,
+            None,
+            false,
+            Some("gemini".to_string()),
+            None,
+        )
+        .unwrap();
+        assert_eq!(config.agent_command, "gemini --experimental-acp");
+    }
+
+    #[test]
+    fn test_resolve_auth_command_uses_profile() {
+        let config = profiles_config();
+        assert_eq!(
+            resolve_auth_command(&config, Some("gemini")).unwrap(),
+            vec!["gemini", "auth", "login", "--browser"]
+        );
+        assert!(resolve_auth_command(&config, Some("local")).is_err());
+    }
+
+    #[test]
+    fn test_resolve_auth_command_falls_back_to_claude() {
+        let config = profiles_config();
+        assert_eq!(
+            resolve_auth_command(&config, None).unwrap(),
+            vec!["claude", "auth", "login"]
+        );
+        // An agent command that is not a profile name keeps the default flow.
+        assert_eq!(
+            resolve_auth_command(&config, Some("claude-agent-acp")).unwrap(),
+            vec!["claude", "auth", "login"]
+        );
+
+        let custom: RalphConfig = toml::from_str(
+            r#"
+[agent]
+auth_command = "acme login"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            resolve_auth_command(&custom, None).unwrap(),
+            vec!["acme", "login"]
+        );
+    }
+
+    #[test]
+    fn test_iteration_timeout_zero_disables() {
+        let mut project = test_project();
+        let config = Config::from_run_args(
+            None,
+            None,
+            None,
+            project.clone(),
+            None,
+            None,
+            false,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(config.iteration_timeout(), None);
+
+        project.config.execution.iteration_timeout_secs = 900;
+        let config =
+            Config::from_run_args(None, None, None, project, None, None, false, None, None)
+                .unwrap();
+        assert_eq!(
+            config.iteration_timeout(),
+            Some(std::time::Duration::from_secs(900))
+        );
+    }
+
+    #[test]
+    fn test_agent_retry_delay_doubles_up_to_cap() {
+        let mut config = config_from_run(None, None).unwrap();
+        let secs = |attempt| config.agent_retry_delay(attempt).as_secs();
+        assert_eq!((secs(1), secs(2), secs(3)), (2, 4, 8));
+        assert_eq!(secs(40), 300);
+        config.ralph_config.execution.agent_retry_backoff_secs = 0;
+        assert_eq!(config.agent_retry_delay(3).as_secs(), 0);
+    }
+
+    #[test]
+    fn test_scheduler_flag_overrides_config_aging() {
+        let mut project = test_project();
+        project.config.execution.scheduler = "fifo".to_string();
+        project.config.execution.aging_secs = 600;
+
+        let config = Config::from_run_args(
+            None,
+            None,
+            None,
+            project.clone(),
+            None,
+            None,
+            false,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(config.scheduler, Scheduler::Fifo);
+
+        let config = Config::from_run_args(
+            None,
+            None,
+            None,
+            project,
+            None,
+            None,
+            false,
+            None,
+            Some("aged".to_string()),
+        )
+        .unwrap();
+        assert_eq!(config.scheduler, Scheduler::Aged { aging_secs: 600 });
+
+        assert!(config_from_run(None, None).unwrap().scheduler == Scheduler::Priority);
+    }
 }
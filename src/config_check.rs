@@ -0,0 +1,425 @@
+//! Strict checking of `.ralph.toml` (`ralph config check`).
+//!
+//! Normal loading is lenient: every section has serde defaults, so a typo like
+//! `max_retires` is silently ignored and the default applies. The check parses
+//! the same file strictly and reports, with line numbers:
+//!
+//! - keys no config section knows about,
+//! - values of the wrong type or out of range (including negative numbers for
+//!   counts, which the lenient load rejects with a less helpful message),
+//! - model names outside [`cli::VALID_MODELS`], and `models` keys of agent
+//!   profiles that name no tier,
+//! - agent commands whose binary cannot be found,
+//! - settings the run would reject later (scheduler, exit_when, theme,
+//!   colors, review, guardrail patterns),
+//! - `.ralph/prompts/` templates that override nothing or use unknown
+//!   variables.
+
+use anyhow::{Context, Result};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use toml_edit::{ImDocument, Item};
+
+use crate::agent_models;
+use crate::cli;
+use crate::dag::Scheduler;
+use crate::project::RalphConfig;
+use crate::prompt_templates;
+use crate::ui::theme;
+
+/// One problem found in the config file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    /// 1-based line the problem is on, when it can be located.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {line}: {}", self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// Check the config file at `path`. An empty result means it is clean.
+pub fn check(path: &Path) -> Result<Vec<Finding>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let root = path.parent().unwrap_or(Path::new("."));
+    Ok(check_str(&text, root))
+}
+
+/// Check config `text` belonging to the project at `root`.
+pub(crate) fn check_str(text: &str, root: &Path) -> Vec<Finding> {
+    let doc = match ImDocument::parse(text) {
+        Ok(doc) => doc,
+        Err(e) => {
+            return vec![Finding {
+                line: e.span().map(|span| line_at(text, span.start)),
+                message: e.message().to_string(),
+            }]
+        }
+    };
+    let locate = |path: &[&str]| line_of(&doc, text, path);
+
+    let mut unknown: Vec<Vec<String>> = Vec::new();
+    let config: RalphConfig =
+        match serde_ignored::deserialize(toml::Deserializer::new(text), |path| {
+            unknown.push(segments(&path))
+        }) {
+            Ok(config) => config,
+            Err(e) => {
+                return vec![Finding {
+                    line: e.span().map(|span| line_at(text, span.start)),
+                    message: e.message().to_string(),
+                }]
+            }
+        };
+
+    let mut findings: Vec<Finding> = unknown
+        .iter()
+        .map(|path| {
+            let path: Vec<&str> = path.iter().map(String::as_str).collect();
+            Finding {
+                line: locate(&path),
+                message: format!("unknown key `{}`", path.join(".")),
+            }
+        })
+        .collect();
+    let mut report = |path: &[&str], message: String| {
+        findings.push(Finding {
+            line: locate(path),
+            message,
+        })
+    };
+
+    let adaptive = &config.strategy.adaptive;
+    for (i, model) in adaptive.models.iter().enumerate() {
+        if let Some(message) = invalid_model(model) {
+            report(&["strategy", "adaptive", "models", &i.to_string()], message);
+        }
+    }
+    for (kind, weights) in &adaptive.task_weights {
+        for (model, weight) in weights {
+            let path = ["strategy", "adaptive", "task_weights", kind, model];
+            if let Some(message) = invalid_model(model) {
+                report(&path, message);
+            } else if !non_negative(*weight) {
+                report(
+                    &path,
+                    format!("task weight must be zero or more, got {weight}"),
+                );
+            }
+        }
+    }
+    for (i, model) in config.review.models.iter().enumerate() {
+        if let Some(message) = invalid_model(model) {
+            report(&["review", "models", &i.to_string()], message);
+        }
+    }
+
+    if let Some(message) = missing_agent_binary(&config.agent.command, &config, root) {
+        report(&["agent", "command"], message);
+    }
+    for key in config.agent.models.keys() {
+        if let Some(message) = invalid_model_tier(key) {
+            report(&["agent", "models", key], message);
+        }
+    }
+    for (name, profile) in &config.agents {
+        if let Some(command) = &profile.command {
+            if let Some(message) = missing_agent_binary(command, &config, root) {
+                report(&["agents", name, "command"], message);
+            }
+        }
+        for key in profile.models.keys() {
+            if let Some(message) = invalid_model_tier(key) {
+                report(&["agents", name, "models", key], message);
+            }
+        }
+    }
+
+    if let Err(e) = config.execution.scheduler.parse::<Scheduler>() {
+        report(&["execution", "scheduler"], e.to_string());
+    }
+    if let Some(source) = &config.run.exit_when {
+        if let Err(e) = crate::exit_when::ExitCondition::parse(source) {
+            report(&["run", "exit_when"], e.to_string());
+        }
+    }
+    let themes = theme::available(root);
+    match themes
+        .iter()
+        .find(|t| t.name.eq_ignore_ascii_case(config.ui.theme.trim()))
+    {
+        None => report(
+            &["ui", "theme"],
+            format!(
+                "invalid theme '{}' (expected light, dark or a theme file; see `ralph ui themes`)",
+                config.ui.theme
+            ),
+        ),
+        Some(entry) => {
+            if let Err(e) = entry.load() {
+                report(&["ui", "theme"], format!("{e:#}"));
+            }
+        }
+    }
+    if let Err(e) = config.ui.colors.validate() {
+        report(&["ui", "colors"], e.to_string());
+    }
+    if let Err(e) = config.review.validate() {
+        let key = if config.review.reviewers == 0 {
+            "reviewers"
+        } else {
+            "quorum"
+        };
+        report(&["review", key], e.to_string());
+    }
+    if let Err(e) = config.verification.validate() {
+        report(&["verification"], format!("{e:#}"));
+    }
+    if let Err(e) = crate::guardrails::compile(&config.guardrails) {
+        report(&["guardrails", "patterns"], e.to_string());
+    }
+    if let Err(e) = crate::offline::compile(&config.offline) {
+        report(&["offline", "network_patterns"], e.to_string());
+    }
+    for (model, price) in &config.pricing {
+        let prices = [
+            ("input_per_mtok", Some(price.input_per_mtok)),
+            ("output_per_mtok", Some(price.output_per_mtok)),
+            ("cache_read_per_mtok", price.cache_read_per_mtok),
+            ("cache_write_per_mtok", price.cache_write_per_mtok),
+        ];
+        for (key, value) in prices {
+            if let Some(value) = value.filter(|v| !non_negative(*v)) {
+                report(
+                    &["pricing", model, key],
+                    format!("price must be zero or more, got {value}"),
+                );
+            }
+        }
+    }
+
+    findings.extend(
+        prompt_templates::check(root)
+            .into_iter()
+            .map(|message| Finding {
+                line: None,
+                message,
+            }),
+    );
+
+    findings.sort_by_key(|f| f.line.unwrap_or(usize::MAX));
+    findings
+}
+
+fn non_negative(value: f64) -> bool {
+    value.is_finite() && value >= 0.0
+}
+
+fn invalid_model(model: &str) -> Option<String> {
+    (!cli::VALID_MODELS.contains(&model)).then(|| {
+        format!(
+            "invalid model '{model}' (expected one of: {})",
+            cli::VALID_MODELS.join(", ")
+        )
+    })
+}
+
+/// A `models` key must name a tier, by tier name or alias.
+fn invalid_model_tier(key: &str) -> Option<String> {
+    agent_models::tier(key).is_none().then(|| {
+        format!(
+            "unknown model tier '{key}' (expected one of: {})",
+            agent_models::TIERS
+                .iter()
+                .flat_map(|(tier, alias)| [*tier, *alias])
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    })
+}
+
+/// Why `command` cannot start, if its program is not found. A command that
+/// names an `[agents.<name>]` profile is checked through that profile.
+fn missing_agent_binary(command: &str, config: &RalphConfig, root: &Path) -> Option<String> {
+    if config.agents.contains_key(command) {
+        return None;
+    }
+    let Some(parts) = shlex::split(command) else {
+        return Some(format!("cannot parse agent command \"{command}\""));
+    };
+    let Some(program) = parts.first() else {
+        return Some("agent command is empty".to_string());
+    };
+    if find_program(program, root).is_some() {
+        return None;
+    }
+    Some(if program.contains('/') {
+        format!("agent binary '{program}' does not exist")
+    } else {
+        format!("agent binary '{program}' not found on PATH")
+    })
+}
+
+/// The file `program` runs: a path (relative to `root`) when it contains a
+/// slash, otherwise the first match on `PATH`.
+fn find_program(program: &str, root: &Path) -> Option<PathBuf> {
+    if program.contains('/') {
+        let path = root.join(program);
+        return path.is_file().then_some(path);
+    }
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths)
+        .map(|dir| dir.join(program))
+        .find(|path| path.is_file())
+}
+
+/// Key path of an ignored field, as the segments of the TOML document.
+fn segments(path: &serde_ignored::Path) -> Vec<String> {
+    use serde_ignored::Path;
+    match path {
+        Path::Root => Vec::new(),
+        Path::Seq { parent, index } => {
+            let mut segments = segments(parent);
+            segments.push(index.to_string());
+            segments
+        }
+        Path::Map { parent, key } => {
+            let mut segments = segments(parent);
+            segments.push(key.clone());
+            segments
+        }
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => segments(parent),
+    }
+}
+
+/// Line of the deepest part of `path` present in the document: the key
+/// itself, or the table or array that should contain it.
+fn line_of(doc: &ImDocument<&str>, text: &str, path: &[&str]) -> Option<usize> {
+    let mut item: &Item = doc.as_item();
+    let mut span = None;
+    for segment in path {
+        if let Some(array) = item.as_array() {
+            let value = segment.parse().ok().and_then(|i: usize| array.get(i));
+            span = value.and_then(|v| v.span()).or(span);
+            break;
+        }
+        let Some(table) = item.as_table_like() else {
+            break;
+        };
+        let Some(next) = table.get(segment) else {
+            break;
+        };
+        span = table
+            .key(segment)
+            .and_then(|key| key.span())
+            .or_else(|| next.span())
+            .or(span);
+        item = next;
+    }
+    span.map(|span| line_at(text, span.start))
+}
+
+fn line_at(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn check(text: &str) -> Vec<String> {
+        let dir = TempDir::new().unwrap();
+        check_str(text, dir.path())
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn reports_unknown_keys_with_line_numbers() {
+        let findings = check(
+            "[agent]\ncommand = \"sh\"\n\n[execution]\nmax_retires = 5\nverify = true\n\n\
+             [executoin]\nscheduler = \"fifo\"\n\n[agents.fast]\ncommand = \"sh\"\nmodel = \"x\"\n",
+        );
+        assert_eq!(
+            findings,
+            vec![
+                "line 5: unknown key `execution.max_retires`",
+                "line 8: unknown key `executoin`",
+                "line 13: unknown key `agents.fast.model`",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_type_and_range_errors() {
+        let findings = check("[agent]\ncommand = \"sh\"\n\n[execution]\nmax_retries = -1\n");
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].starts_with("line 5: "), "{findings:?}");
+
+        let findings = check(
+            "[agent]\ncommand = \"sh\"\n[review]\nreviewers = 3\nquorum = 4\n\
+             [pricing.opus]\ninput_per_mtok = -1.0\noutput_per_mtok = 75.0\n\
+             [execution]\nscheduler = \"lifo\"\n[ui]\ntheme = \"solarized\"\n\
+             [guardrails]\npatterns = [\"(x\"]\n",
+        );
+        assert_eq!(findings.len(), 5, "{findings:?}");
+        assert!(findings[0].starts_with("line 5: review.quorum must be between 1 and"));
+        assert_eq!(findings[1], "line 7: price must be zero or more, got -1");
+        assert!(findings[2].starts_with("line 10: invalid scheduler 'lifo'"));
+        assert!(findings[3].starts_with("line 12: invalid theme 'solarized'"));
+        assert_eq!(findings[4], "line 14: Invalid guardrail pattern '(x'");
+
+        let findings = check(
+            "[agent]\ncommand = \"sh\"\n[run]\nexit_when = \"budget.cost > 5 or run.ratio > 1\"\n",
+        );
+        assert_eq!(findings.len(), 1, "{findings:?}");
+        assert!(findings[0].starts_with("line 4: unknown variable 'run.ratio'"));
+    }
+
+    #[test]
+    fn reports_invalid_models_and_missing_agent_binaries() {
+        let findings = check(
+            "[agent]\ncommand = \"no-such-agent-binary --acp\"\n\n\
+             [agents.local]\ncommand = \"./bin/agent\"\n\n\
+             [agents.local.models]\nstrong = \"big-1\"\nsmart = \"big-2\"\n\n\
+             [strategy.adaptive]\nmodels = [\"haiku\", \"gpt-4\"]\n\n\
+             [review]\nmodels = [\"opus\", \"sonet\"]\n",
+        );
+        assert_eq!(
+            findings,
+            vec![
+                "line 2: agent binary 'no-such-agent-binary' not found on PATH",
+                "line 5: agent binary './bin/agent' does not exist",
+                "line 9: unknown model tier 'smart' (expected one of: opus, strong, sonnet, balanced, haiku, fast)",
+                "line 12: invalid model 'gpt-4' (expected one of: opus, sonnet, haiku)",
+                "line 15: invalid model 'sonet' (expected one of: opus, sonnet, haiku)",
+            ]
+        );
+    }
+
+    #[test]
+    fn clean_config_has_no_findings() {
+        assert!(check("[agent]\ncommand = \"sh -c true\"\n").is_empty());
+        // An agent command may name a profile
+        assert!(check(
+            "[agent]\ncommand = \"fast\"\n[agents.fast]\ncommand = \"sh\"\n\
+             [env]\nANY_NAME = \"1\"\n[sigils.custom.note]\naction = \"create-task\"\n"
+        )
+        .is_empty());
+
+        let findings = check("[execution\nverify = true\n");
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].starts_with("line 1: "), "{findings:?}");
+    }
+}
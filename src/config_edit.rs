@@ -0,0 +1,240 @@
+//! `ralph config get` and `ralph config set`: scripted access to `.ralph.toml`.
+//!
+//! Keys are dotted paths into the file (`execution.max_retries`,
+//! `agents.fast.model`). Edits go through `toml_edit`, so comments, key
+//! order and formatting elsewhere in the file are kept. A new value is
+//! checked with the same strict pass as `ralph config check`; the file is
+//! only written when the edit introduces no new problem.
+
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+use toml_edit::{DocumentMut, Item, Table, Value};
+
+use crate::config_check;
+use crate::project::RalphConfig;
+
+/// Value of `key` as written in the config file at `path`, or `None` when
+/// the file leaves it unset (the built-in default applies). Strings print
+/// without quotes; arrays and tables print as TOML.
+pub fn get(path: &Path, key: &str) -> Result<Option<String>> {
+    let segments = parse_key(key)?;
+    let text = read(path)?;
+    let doc: DocumentMut = text
+        .parse()
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    let mut item = doc.as_item();
+    for segment in &segments {
+        match item.as_table_like().and_then(|t| t.get(segment)) {
+            Some(next) => item = next,
+            None => {
+                if !is_known_key(&segments) {
+                    bail!("unknown config key `{key}`");
+                }
+                return Ok(None);
+            }
+        }
+    }
+    Ok(Some(render(item)))
+}
+
+/// Set `key` to `value` in the config file at `path`.
+///
+/// `value` is read as a TOML value (`5`, `true`, `["a", "b"]`) and falls
+/// back to a plain string, so `ralph config set ui.theme dark` needs no
+/// quotes. A value the schema rejects leaves the file untouched.
+pub fn set(path: &Path, key: &str, value: &str) -> Result<()> {
+    let segments = parse_key(key)?;
+    let text = read(path)?;
+    let root = path.parent().unwrap_or(Path::new("."));
+    let updated = set_str(&text, root, &segments, value)?;
+    std::fs::write(path, updated).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn set_str(text: &str, root: &Path, segments: &[String], value: &str) -> Result<String> {
+    let key = segments.join(".");
+    let before = messages(text, root);
+
+    let typed = value.trim().parse::<Value>().ok();
+    let mut attempts: Vec<Value> = typed.into_iter().collect();
+    if !attempts.iter().any(Value::is_str) {
+        attempts.push(Value::from(value));
+    }
+
+    let mut first_problems = None;
+    for candidate in attempts {
+        let updated = with_value(text, segments, candidate)?;
+        let problems: Vec<String> = messages(&updated, root)
+            .into_iter()
+            .filter(|m| !before.contains(m))
+            .collect();
+        if problems.is_empty() {
+            return Ok(updated);
+        }
+        first_problems.get_or_insert(problems);
+    }
+    bail!(
+        "invalid value for `{key}`: {}",
+        first_problems.unwrap_or_default().join("; ")
+    )
+}
+
+/// `text` with the value at `segments` replaced, creating missing tables.
+/// The old value's comments and spacing carry over to the new one.
+fn with_value(text: &str, segments: &[String], mut value: Value) -> Result<String> {
+    let mut doc: DocumentMut = text.parse().context("Failed to parse config")?;
+    let (last, parents) = segments.split_last().context("empty config key")?;
+
+    let mut table = doc.as_table_mut() as &mut dyn toml_edit::TableLike;
+    for segment in parents {
+        if table.get(segment).is_none() {
+            let mut new = Table::new();
+            new.set_implicit(true);
+            table.insert(segment, Item::Table(new));
+        }
+        table = table
+            .get_mut(segment)
+            .and_then(Item::as_table_like_mut)
+            .with_context(|| format!("`{segment}` in `{}` is not a table", segments.join(".")))?;
+    }
+
+    match table.get_mut(last) {
+        Some(item) => {
+            if let Some(old) = item.as_value() {
+                *value.decor_mut() = old.decor().clone();
+            }
+            *item = Item::Value(value);
+        }
+        None => {
+            table.insert(last, Item::Value(value));
+        }
+    }
+    Ok(doc.to_string())
+}
+
+/// Whether the schema has a field at `segments`. Unknown keys are the ones
+/// the strict parse ignores; a type error means the key exists.
+fn is_known_key(segments: &[String]) -> bool {
+    let Ok(text) = with_value("", segments, Value::from(false)) else {
+        return false;
+    };
+    let mut ignored = false;
+    let parsed: Result<RalphConfig, _> =
+        serde_ignored::deserialize(toml::Deserializer::new(&text), |_| ignored = true);
+    parsed.is_err() || !ignored
+}
+
+/// Problems `ralph config check` reports for `text`, without line numbers,
+/// so an edit can be judged by the problems it adds.
+fn messages(text: &str, root: &Path) -> Vec<String> {
+    config_check::check_str(text, root)
+        .into_iter()
+        .map(|finding| finding.message)
+        .collect()
+}
+
+fn parse_key(key: &str) -> Result<Vec<String>> {
+    let segments: Vec<String> = key.split('.').map(|s| s.trim().to_string()).collect();
+    if segments.iter().any(String::is_empty) {
+        bail!("invalid config key `{key}` (expected a dotted path like execution.max_retries)");
+    }
+    Ok(segments)
+}
+
+fn read(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+fn render(item: &Item) -> String {
+    match item {
+        Item::Value(Value::String(s)) => s.value().clone(),
+        Item::Value(value) => value.clone().decorated("", "").to_string(),
+        Item::Table(table) => {
+            let mut doc = DocumentMut::new();
+            for (key, item) in table.iter() {
+                doc.insert(key, item.clone());
+            }
+            doc.to_string().trim_end().to_string()
+        }
+        Item::ArrayOfTables(array) => {
+            let mut doc = DocumentMut::new();
+            doc.insert("_", Item::ArrayOfTables(array.clone()));
+            doc.to_string().trim_end().to_string()
+        }
+        Item::None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const CONFIG: &str = "# Project config\n[agent]\ncommand = \"sh\"\n\n\
+                          [execution]\n# how often to retry\nmax_retries = 3 # default\n";
+
+    fn set_in(text: &str, key: &str, value: &str) -> Result<String> {
+        let dir = TempDir::new().unwrap();
+        set_str(text, dir.path(), &parse_key(key).unwrap(), value)
+    }
+
+    #[test]
+    fn set_keeps_comments_and_infers_types() {
+        let updated = set_in(CONFIG, "execution.max_retries", "5").unwrap();
+        assert_eq!(
+            updated,
+            CONFIG.replace("max_retries = 3 # default", "max_retries = 5 # default")
+        );
+
+        let updated = set_in(CONFIG, "ui.theme", "dark").unwrap();
+        assert!(updated.starts_with(CONFIG), "{updated}");
+        assert!(updated.ends_with("[ui]\ntheme = \"dark\"\n"), "{updated}");
+
+        let updated = set_in(CONFIG, "strategy.adaptive.models", "[\"haiku\", \"opus\"]").unwrap();
+        assert!(updated.contains("[strategy.adaptive]\nmodels = [\"haiku\", \"opus\"]"));
+        assert!(!updated.contains("[strategy]\n"), "{updated}");
+
+        // A number where the schema wants a string is kept as a string
+        let updated = set_in(CONFIG, "user.name", "42").unwrap();
+        assert!(updated.contains("name = \"42\""), "{updated}");
+    }
+
+    #[test]
+    fn set_rejects_unknown_keys_and_invalid_values() {
+        let err = set_in(CONFIG, "execution.max_retires", "5").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("unknown key `execution.max_retires`"));
+
+        let err = set_in(CONFIG, "execution.max_retries", "-1").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("invalid value for `execution.max_retries`"));
+
+        let err = set_in(CONFIG, "ui.theme", "solarized").unwrap_err();
+        assert!(
+            err.to_string().contains("invalid theme 'solarized'"),
+            "{err}"
+        );
+
+        assert!(parse_key("execution..max_retries").is_err());
+    }
+
+    #[test]
+    fn get_reads_values_and_distinguishes_unset_from_unknown() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(".ralph.toml");
+        std::fs::write(&path, CONFIG).unwrap();
+
+        assert_eq!(get(&path, "execution.max_retries").unwrap().unwrap(), "3");
+        assert_eq!(get(&path, "agent.command").unwrap().unwrap(), "sh");
+        assert_eq!(get(&path, "agent").unwrap().unwrap(), "command = \"sh\"");
+        assert_eq!(get(&path, "execution.verify").unwrap(), None);
+        assert_eq!(get(&path, "ui.theme").unwrap(), None);
+        assert!(get(&path, "execution.max_retires").is_err());
+        assert!(get(&path, "nope").is_err());
+
+        set(&path, "ui.theme", "light").unwrap();
+        assert_eq!(get(&path, "ui.theme").unwrap().unwrap(), "light");
+    }
+}
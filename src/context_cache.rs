@@ -0,0 +1,339 @@
+//! Caching the project context that prompts are built from.
+//!
+//! Every iteration used to re-read each knowledge file, rebuild the link
+//! graph and re-run the journal's full-text search, and every interactive
+//! session re-read CLAUDE.md and `.ralph.toml`. Files are now re-read only
+//! when their modification time or size changes, and related journal entries
+//! are searched again only when another connection has written to the
+//! database (`PRAGMA data_version`). The search skips the current run's
+//! entries, which are the only ones this run writes.
+//!
+//! [`ContextCache`] lives as long as one `ralph run` and counts how often
+//! each source was served from memory; the run ends with that summary as a
+//! `cache` event.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::dag::Db;
+use crate::journal::{self, JournalEntry};
+use crate::knowledge::{self, KnowledgeEntry, LinkGraph};
+
+/// What identifies a version of a file: modification time and size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl Stamp {
+    fn of(path: &Path) -> Option<Self> {
+        let meta = std::fs::metadata(path).ok()?;
+        Some(Self {
+            modified: meta.modified().ok(),
+            len: meta.len(),
+        })
+    }
+}
+
+/// How often one source was served from memory, and the time spent.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SourceStats {
+    pub lookups: u32,
+    pub hits: u32,
+    /// Time spent on lookups that read the source.
+    pub miss_time: Duration,
+    /// Time spent on lookups served from memory, checks included.
+    pub hit_time: Duration,
+}
+
+impl SourceStats {
+    fn record(&mut self, hit: bool, elapsed: Duration) {
+        self.lookups += 1;
+        if hit {
+            self.hits += 1;
+            self.hit_time += elapsed;
+        } else {
+            self.miss_time += elapsed;
+        }
+    }
+
+    /// Estimated time saved: each hit against the average miss.
+    pub fn saved(&self) -> Duration {
+        let misses = self.lookups - self.hits;
+        if misses == 0 {
+            return Duration::ZERO;
+        }
+        (self.miss_time / misses * self.hits).saturating_sub(self.hit_time)
+    }
+}
+
+/// The knowledge base as last read.
+struct KnowledgeSnapshot {
+    /// Files in directory order, with the stamp they were read at.
+    files: Vec<(PathBuf, Stamp)>,
+    /// Parsed entry per file; `None` for malformed files.
+    parsed: HashMap<PathBuf, Option<KnowledgeEntry>>,
+    entries: Vec<KnowledgeEntry>,
+    graph: LinkGraph,
+}
+
+/// Context sources of one run, re-read only when they change.
+#[derive(Default)]
+pub struct ContextCache {
+    knowledge: Option<KnowledgeSnapshot>,
+    /// `PRAGMA data_version` the related journal entries were searched at.
+    journal_version: Option<i64>,
+    related: HashMap<(String, u32), Vec<JournalEntry>>,
+    pub knowledge_stats: SourceStats,
+    pub journal_stats: SourceStats,
+}
+
+impl ContextCache {
+    /// The knowledge entries under `project_root` and their link graph, as
+    /// [`knowledge::discover_knowledge`] and [`knowledge::build_link_graph`]
+    /// would return them. Only new or changed files are read.
+    pub fn knowledge(&mut self, project_root: &Path) -> (&[KnowledgeEntry], &LinkGraph) {
+        let started = Instant::now();
+        let files = knowledge_files(project_root);
+        let hit = self.knowledge.as_ref().is_some_and(|k| k.files == files);
+        if !hit {
+            let mut previous = self
+                .knowledge
+                .take()
+                .map(|k| (k.files, k.parsed))
+                .unwrap_or_default();
+            let old_stamps: HashMap<PathBuf, Stamp> = previous.0.drain(..).collect();
+            let mut parsed = HashMap::new();
+            let mut entries = Vec::new();
+            for (path, stamp) in &files {
+                let entry = match previous.1.remove(path) {
+                    Some(entry) if old_stamps.get(path) == Some(stamp) => entry,
+                    _ => knowledge::read_knowledge_file(path),
+                };
+                entries.extend(entry.clone());
+                parsed.insert(path.clone(), entry);
+            }
+            let graph = knowledge::build_link_graph(&entries);
+            self.knowledge = Some(KnowledgeSnapshot {
+                files,
+                parsed,
+                entries,
+                graph,
+            });
+        }
+        self.knowledge_stats.record(hit, started.elapsed());
+        let snapshot = self
+            .knowledge
+            .as_ref()
+            .expect("knowledge snapshot was just built");
+        (&snapshot.entries, &snapshot.graph)
+    }
+
+    /// Smart-select journal entries for system prompt injection: up to
+    /// `recent_limit` entries of the current run (chronological), then up to
+    /// `fts_limit` entries of other runs matching the task title and
+    /// description (FTS5). Other runs are searched again only when another
+    /// connection wrote to the database.
+    pub fn select_journal_entries(
+        &mut self,
+        db: &Db,
+        run_id: &str,
+        task_title: &str,
+        task_description: &str,
+        recent_limit: u32,
+        fts_limit: u32,
+    ) -> Result<Vec<JournalEntry>> {
+        let mut entries = journal::query_journal_recent(db, run_id, recent_limit)?;
+
+        let started = Instant::now();
+        let version: i64 = db
+            .conn()
+            .query_row("PRAGMA data_version", [], |row| row.get(0))?;
+        if self.journal_version != Some(version) {
+            self.related.clear();
+            self.journal_version = Some(version);
+        }
+        let key = (format!("{} {}", task_title, task_description), fts_limit);
+        let hit = self.related.contains_key(&key);
+        if !hit {
+            let related = journal::query_journal_fts(db, &key.0, run_id, fts_limit)?;
+            self.related.insert(key.clone(), related);
+        }
+        entries.extend(self.related[&key].iter().cloned());
+        self.journal_stats.record(hit, started.elapsed());
+        Ok(entries)
+    }
+
+    /// One line on what the cache served, or `None` before any lookup.
+    pub fn summary(&self) -> Option<String> {
+        if self.knowledge_stats.lookups == 0 && self.journal_stats.lookups == 0 {
+            return None;
+        }
+        let saved = self.knowledge_stats.saved() + self.journal_stats.saved();
+        Some(format!(
+            "context cache: knowledge {}/{} cached, journal search {}/{} cached, ~{:.1} ms saved",
+            self.knowledge_stats.hits,
+            self.knowledge_stats.lookups,
+            self.journal_stats.hits,
+            self.journal_stats.lookups,
+            saved.as_secs_f64() * 1000.0
+        ))
+    }
+}
+
+/// The `.md` files of the knowledge base in directory order, with stamps.
+fn knowledge_files(project_root: &Path) -> Vec<(PathBuf, Stamp)> {
+    let Ok(dir) = std::fs::read_dir(project_root.join(".ralph/knowledge")) else {
+        return Vec::new();
+    };
+    dir.flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("md"))
+        .filter_map(|path| Some((path.clone(), Stamp::of(&path)?)))
+        .collect()
+}
+
+/// `path` as a string, read again only when its stamp changed since the last
+/// call in this process. `None` if it cannot be read.
+pub fn read_to_string(path: &Path) -> Option<String> {
+    static FILES: OnceLock<Mutex<HashMap<PathBuf, (Stamp, String)>>> = OnceLock::new();
+    let stamp = Stamp::of(path)?;
+    let mut files = FILES
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if let Some((cached, content)) = files.get(path) {
+        if *cached == stamp {
+            return Some(content.clone());
+        }
+    }
+    let content = std::fs::read_to_string(path).ok()?;
+    files.insert(path.to_path_buf(), (stamp, content.clone()));
+    Some(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag::init_db;
+    use crate::journal::{insert_journal_entry, JournalEntry};
+    use tempfile::TempDir;
+
+    fn write_entry(root: &Path, file: &str, title: &str, body: &str) {
+        let dir = root.join(".ralph/knowledge");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join(file),
+            format!("---\ntitle: {title}\ntags: [auth]\n---\n\n{body}\n"),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn knowledge_is_reread_only_when_files_change() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write_entry(root, "login.md", "Login", "See [[Sessions]].");
+        write_entry(root, "sessions.md", "Sessions", "Cookies.");
+        let mut cache = ContextCache::default();
+
+        let (entries, graph) = cache.knowledge(root);
+        assert_eq!(entries.len(), 2);
+        assert!(graph.outlinks["login"].contains("sessions"));
+        assert_eq!(cache.knowledge(root).0.len(), 2);
+        assert_eq!(cache.knowledge_stats.hits, 1);
+
+        // A changed size changes the stamp even within one mtime tick
+        write_entry(root, "sessions.md", "Sessions", "Cookies, renewed hourly.");
+        write_entry(root, "tokens.md", "Tokens", "JWT.");
+        std::fs::write(root.join(".ralph/knowledge/notes.txt"), "ignored").unwrap();
+        let (entries, _) = cache.knowledge(root);
+        let mut titles: Vec<&str> = entries.iter().map(|e| e.title.as_str()).collect();
+        titles.sort();
+        assert_eq!(titles, ["Login", "Sessions", "Tokens"]);
+        assert!(entries
+            .iter()
+            .any(|e| e.body.contains("renewed hourly") && e.file_path.ends_with("sessions.md")));
+
+        std::fs::remove_file(root.join(".ralph/knowledge/login.md")).unwrap();
+        assert_eq!(cache.knowledge(root).0.len(), 2);
+        assert_eq!(
+            (cache.knowledge_stats.lookups, cache.knowledge_stats.hits),
+            (4, 1)
+        );
+    }
+
+    #[test]
+    fn related_journal_entries_are_searched_again_after_outside_writes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("progress.db");
+        let db = init_db(path.to_str().unwrap()).unwrap();
+        let entry = |run_id: &str, notes: &str| JournalEntry {
+            id: 0,
+            run_id: run_id.to_string(),
+            iteration: 1,
+            task_id: None,
+            feature_id: None,
+            outcome: "done".to_string(),
+            model: None,
+            duration_secs: 1.0,
+            cost_usd: 0.0,
+            input_tokens: 0,
+            output_tokens: 0,
+            files_modified: Vec::new(),
+            files_read: Vec::new(),
+            notes: Some(notes.to_string()),
+            created_at: String::new(),
+        };
+        insert_journal_entry(&db, &entry("run-old", "Login needs CSRF tokens")).unwrap();
+        let mut cache = ContextCache::default();
+        let select = |cache: &mut ContextCache| {
+            cache
+                .select_journal_entries(&db, "run-now", "Login form", "", 5, 5)
+                .unwrap()
+        };
+
+        assert_eq!(select(&mut cache).len(), 1);
+        // This run's own entries are not part of the search
+        insert_journal_entry(&db, &entry("run-now", "Login form started")).unwrap();
+        assert_eq!(select(&mut cache).len(), 2);
+        assert_eq!(cache.journal_stats.hits, 1);
+
+        let other = init_db(path.to_str().unwrap()).unwrap();
+        insert_journal_entry(&other, &entry("run-other", "Login rate limits")).unwrap();
+        let selected = select(&mut cache);
+        assert_eq!(selected.len(), 3);
+        assert_eq!(cache.journal_stats.hits, 1);
+        assert!(cache
+            .summary()
+            .unwrap()
+            .starts_with("context cache: knowledge 0/0 cached, journal search 1/3 cached"));
+    }
+
+    #[test]
+    fn saved_time_weighs_hits_against_the_average_miss() {
+        let stats = SourceStats {
+            lookups: 4,
+            hits: 2,
+            miss_time: Duration::from_millis(20),
+            hit_time: Duration::from_millis(2),
+        };
+        assert_eq!(stats.saved(), Duration::from_millis(18));
+        assert_eq!(SourceStats::default().saved(), Duration::ZERO);
+    }
+
+    #[test]
+    fn files_are_reread_after_they_change() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("CLAUDE.md");
+        assert_eq!(read_to_string(&path), None);
+        std::fs::write(&path, "Use tabs.").unwrap();
+        assert_eq!(read_to_string(&path).as_deref(), Some("Use tabs."));
+        std::fs::write(&path, "Use spaces.").unwrap();
+        assert_eq!(read_to_string(&path).as_deref(), Some("Use spaces."));
+    }
+}
@@ -0,0 +1,86 @@
+//! Files a task produced for the tasks that depend on it.
+//!
+//! The agent registers an output with an `<artifact path="...">` sigil; the
+//! run loop stores the project-relative path here. When a dependent task
+//! runs, each completed blocker's artifacts are listed in its prompt with an
+//! excerpt of the file.
+
+use anyhow::{Context, Result};
+use rusqlite::params;
+use serde::Serialize;
+
+use crate::dag::Db;
+
+/// A file registered by a task.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Artifact {
+    /// Path relative to the project root.
+    pub path: String,
+    pub description: String,
+}
+
+/// Register `path` as an output of `task_id`. Registering the same path again
+/// replaces its description.
+pub fn add_artifact(db: &Db, task_id: &str, path: &str, description: &str) -> Result<()> {
+    db.conn()
+        .execute(
+            "INSERT INTO artifacts (task_id, path, description) VALUES (?, ?, ?)
+             ON CONFLICT (task_id, path) DO UPDATE SET
+                 description = excluded.description, created_at = datetime('now')",
+            params![task_id, path, description],
+        )
+        .with_context(|| format!("Failed to register artifact '{path}' for task '{task_id}'"))?;
+    Ok(())
+}
+
+/// A task's artifacts, in the order they were first registered.
+pub fn get_task_artifacts(db: &Db, task_id: &str) -> Result<Vec<Artifact>> {
+    let mut stmt = db
+        .conn()
+        .prepare("SELECT path, description FROM artifacts WHERE task_id = ? ORDER BY id")?;
+    let artifacts = stmt
+        .query_map([task_id], |row| {
+            Ok(Artifact {
+                path: row.get(0)?,
+                description: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to get task artifacts")?;
+    Ok(artifacts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag;
+
+    #[test]
+    fn artifacts_are_upserted_per_path() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let db = dag::open_db(temp_file.path().to_str().unwrap()).unwrap();
+        let task = dag::create_task(&db, "schema", None, None, 0).unwrap();
+
+        add_artifact(&db, &task.id, "db/schema.sql", "Orders schema").unwrap();
+        add_artifact(&db, &task.id, "api/openapi.yaml", "").unwrap();
+        add_artifact(&db, &task.id, "db/schema.sql", "Orders and refunds schema").unwrap();
+
+        let artifacts = get_task_artifacts(&db, &task.id).unwrap();
+        assert_eq!(
+            artifacts,
+            vec![
+                Artifact {
+                    path: "db/schema.sql".to_string(),
+                    description: "Orders and refunds schema".to_string(),
+                },
+                Artifact {
+                    path: "api/openapi.yaml".to_string(),
+                    description: String::new(),
+                },
+            ]
+        );
+
+        dag::delete_task(&db, &task.id).unwrap();
+        assert!(get_task_artifacts(&db, &task.id).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,190 @@
+//! Batch operations on many tasks at once (`ralph task bulk`).
+//!
+//! A batch commits in one transaction: if any task cannot be changed, for
+//! example a blocker of a task outside the batch being deleted, nothing is.
+
+use anyhow::{anyhow, Result};
+use std::collections::HashSet;
+
+use super::{crud, transitions, AutoTransition, Db, Task, TaskUpdate};
+
+/// What to do to every task in a batch.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BulkOp {
+    /// Force to done, like `ralph task done`.
+    Done {
+        completed_by: Option<String>,
+        /// Added to each task's log.
+        message: Option<String>,
+    },
+    /// Delete with subtasks, like `ralph task delete`.
+    Delete,
+    /// Reset to pending, like `ralph task reset`.
+    Reset,
+    /// Set the priority (lower = higher priority).
+    Priority(i32),
+}
+
+/// Unarchived tasks with `status`, in feature `feature_id`, or both.
+pub fn select_tasks(db: &Db, status: Option<&str>, feature_id: Option<&str>) -> Result<Vec<Task>> {
+    let tasks = match feature_id {
+        Some(id) => crud::get_all_tasks_for_feature(db, id)?,
+        None => crud::get_all_tasks(db)?,
+    };
+    Ok(tasks
+        .into_iter()
+        .filter(|t| status.is_none_or(|s| t.status == s))
+        .collect())
+}
+
+/// Task IDs from newline-separated input, in order and without repeats.
+///
+/// Only the first word of a line counts, so `ralph task list` output can be
+/// piped in after filtering; blank lines and `#` comments are skipped.
+pub fn parse_task_ids(text: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    text.lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .filter(|id| !id.starts_with('#'))
+        .filter(|id| seen.insert(*id))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Apply `op` to every task in `ids` in one transaction. Returns the
+/// auto-transitions the changes triggered, for the caller to report.
+pub fn apply_bulk(db: &Db, ids: &[String], op: &BulkOp) -> Result<Vec<AutoTransition>> {
+    let missing: Vec<&str> = ids
+        .iter()
+        .filter(|id| crud::get_task(db, id).is_err())
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        return Err(anyhow!("No such task(s): {}", missing.join(", ")));
+    }
+
+    let tx = db.conn().unchecked_transaction()?;
+    let mut all = Vec::new();
+    if *op == BulkOp::Delete {
+        // Dependencies between tasks of the batch go with it; only a
+        // dependent outside the batch keeps a blocker from being deleted.
+        let batch: HashSet<&str> = ids.iter().map(String::as_str).collect();
+        for id in ids {
+            for blocked in crud::get_tasks_blocked_by(db, id)? {
+                if batch.contains(blocked.id.as_str()) {
+                    db.conn().execute(
+                        "DELETE FROM dependencies WHERE blocker_id = ? AND blocked_id = ?",
+                        [id, &blocked.id],
+                    )?;
+                }
+            }
+        }
+    }
+    for id in ids {
+        match op {
+            BulkOp::Done {
+                completed_by,
+                message,
+            } => {
+                all.extend(transitions::force_complete_task(db.conn(), id)?);
+                crud::set_completed_by(db, id, completed_by.as_deref())?;
+                if let Some(message) = message {
+                    crud::add_log(db, id, message)?;
+                }
+            }
+            BulkOp::Delete => {
+                // Already gone if a parent earlier in the batch took it along
+                if crud::get_task(db, id).is_ok() {
+                    crud::delete_task(db, id)?;
+                }
+            }
+            BulkOp::Reset => all.extend(transitions::force_reset_task(db.conn(), id)?),
+            BulkOp::Priority(priority) => {
+                crud::update_task(
+                    db,
+                    id,
+                    TaskUpdate {
+                        title: None,
+                        description: None,
+                        priority: Some(*priority),
+                    },
+                )?;
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(all)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag::{add_dependency, create_task, get_task, init_db};
+    use tempfile::NamedTempFile;
+
+    fn setup() -> (NamedTempFile, Db) {
+        let file = NamedTempFile::new().unwrap();
+        let db = init_db(file.path().to_str().unwrap()).unwrap();
+        (file, db)
+    }
+
+    #[test]
+    fn test_parse_task_ids() {
+        let text = "t-aaa111\n\n# stale\nt-bbb222  [pending] Title\nt-aaa111\n";
+        assert_eq!(parse_task_ids(text), vec!["t-aaa111", "t-bbb222"]);
+    }
+
+    #[test]
+    fn test_bulk_done_priority_and_reset() {
+        let (_file, db) = setup();
+        let parent = create_task(&db, "Parent", None, None, 0).unwrap();
+        let a = create_task(&db, "A", None, Some(&parent.id), 0).unwrap();
+        let b = create_task(&db, "B", None, Some(&parent.id), 0).unwrap();
+        let ids = vec![a.id.clone(), b.id.clone()];
+
+        apply_bulk(&db, &ids, &BulkOp::Priority(2)).unwrap();
+        assert_eq!(get_task(&db, &b.id).unwrap().priority, 2);
+
+        let op = BulkOp::Done {
+            completed_by: Some("alice".to_string()),
+            message: Some("cleanup".to_string()),
+        };
+        let transitions = apply_bulk(&db, &ids, &op).unwrap();
+        assert!(transitions.iter().any(|t| matches!(
+            t,
+            AutoTransition::ParentCompleted { parent_id } if *parent_id == parent.id
+        )));
+        let done = get_task(&db, &a.id).unwrap();
+        assert_eq!(done.status, "done");
+        assert_eq!(done.completed_by.as_deref(), Some("alice"));
+
+        apply_bulk(&db, &ids, &BulkOp::Reset).unwrap();
+        let pending = select_tasks(&db, Some("pending"), None).unwrap();
+        assert!(ids.iter().all(|id| pending.iter().any(|t| t.id == *id)));
+    }
+
+    #[test]
+    fn test_bulk_delete_is_all_or_nothing() {
+        let (_file, db) = setup();
+        let a = create_task(&db, "A", None, None, 0).unwrap();
+        let b = create_task(&db, "B", None, None, 0).unwrap();
+        let child = create_task(&db, "Child", None, Some(&a.id), 0).unwrap();
+        let outside = create_task(&db, "Outside", None, None, 0).unwrap();
+        add_dependency(&db, &a.id, &b.id).unwrap();
+        add_dependency(&db, &b.id, &outside.id).unwrap();
+
+        // `b` blocks a task outside the batch, so nothing is deleted
+        let ids = vec![a.id.clone(), b.id.clone(), child.id.clone()];
+        assert!(apply_bulk(&db, &ids, &BulkOp::Delete).is_err());
+        assert!(get_task(&db, &a.id).is_ok());
+        assert_eq!(crud::get_tasks_blocked_by(&db, &a.id).unwrap().len(), 1);
+
+        let ids = vec![a.id.clone(), b.id.clone(), child.id.clone(), outside.id];
+        apply_bulk(&db, &ids, &BulkOp::Delete).unwrap();
+        assert!(get_task(&db, &child.id).is_err());
+        assert!(select_tasks(&db, None, None).unwrap().is_empty());
+
+        let err = apply_bulk(&db, std::slice::from_ref(&a.id), &BulkOp::Reset).unwrap_err();
+        assert!(err.to_string().contains(&a.id));
+    }
+}
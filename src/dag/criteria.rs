@@ -0,0 +1,193 @@
+//! Acceptance criteria checklists attached to tasks.
+//!
+//! Each task owns an ordered list of criteria. The verification agent marks
+//! each one `passed` or `failed`, so `ralph task show` can report per-criterion
+//! state rather than a single verification verdict.
+
+#![allow(dead_code)]
+
+use anyhow::{anyhow, Context, Result};
+use rusqlite::params;
+use serde::Serialize;
+
+use crate::dag::Db;
+
+/// A single acceptance criterion on a task.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Criterion {
+    /// 1-based position within the task's checklist.
+    pub position: i32,
+    pub description: String,
+    /// One of `pending`, `passed`, `failed`.
+    pub status: String,
+    /// Optional explanation from the verification agent.
+    pub note: Option<String>,
+}
+
+/// Append acceptance criteria to a task's checklist.
+///
+/// Blank entries are skipped. Positions continue from the current end of the list.
+pub fn add_criteria(db: &Db, task_id: &str, criteria: &[String]) -> Result<()> {
+    let next: i32 = db
+        .conn()
+        .query_row(
+            "SELECT COALESCE(MAX(position), 0) FROM task_criteria WHERE task_id = ?",
+            [task_id],
+            |row| row.get(0),
+        )
+        .context("Failed to read criteria positions")?;
+
+    let mut position = next;
+    for text in criteria {
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        position += 1;
+        db.conn()
+            .execute(
+                "INSERT INTO task_criteria (task_id, position, description) VALUES (?, ?, ?)",
+                params![task_id, position, text],
+            )
+            .with_context(|| format!("Failed to add criterion to task '{}'", task_id))?;
+    }
+
+    Ok(())
+}
+
+/// Replace a task's whole checklist, e.g. when re-planning rewrites the task.
+pub fn replace_criteria(db: &Db, task_id: &str, criteria: &[String]) -> Result<()> {
+    db.conn()
+        .execute("DELETE FROM task_criteria WHERE task_id = ?", [task_id])
+        .context("Failed to clear task criteria")?;
+    add_criteria(db, task_id, criteria)
+}
+
+/// Get a task's acceptance criteria in checklist order.
+pub fn get_task_criteria(db: &Db, task_id: &str) -> Result<Vec<Criterion>> {
+    let mut stmt = db.conn().prepare(
+        "SELECT position, description, status, note FROM task_criteria WHERE task_id = ? ORDER BY position",
+    )?;
+    let criteria = stmt
+        .query_map([task_id], |row| {
+            Ok(Criterion {
+                position: row.get(0)?,
+                description: row.get(1)?,
+                status: row.get(2)?,
+                note: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to get task criteria")?;
+    Ok(criteria)
+}
+
+/// Record the verification verdict for one criterion.
+pub fn set_criterion_status(
+    db: &Db,
+    task_id: &str,
+    position: i32,
+    status: &str,
+    note: Option<&str>,
+) -> Result<()> {
+    if !matches!(status, "pending" | "passed" | "failed") {
+        return Err(anyhow!("Invalid criterion status '{}'", status));
+    }
+
+    let updated = db
+        .conn()
+        .execute(
+            "UPDATE task_criteria SET status = ?, note = ?, updated_at = datetime('now') WHERE task_id = ? AND position = ?",
+            params![status, note, task_id, position],
+        )
+        .context("Failed to update criterion status")?;
+    if updated == 0 {
+        return Err(anyhow!("Task '{}' has no criterion #{}", task_id, position));
+    }
+
+    Ok(())
+}
+
+/// Reset every criterion on a task back to `pending`.
+pub fn reset_criteria(db: &Db, task_id: &str) -> Result<()> {
+    db.conn()
+        .execute(
+            "UPDATE task_criteria SET status = 'pending', note = NULL, updated_at = datetime('now') WHERE task_id = ?",
+            [task_id],
+        )
+        .context("Failed to reset task criteria")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag::{create_task, init_db};
+    use tempfile::NamedTempFile;
+
+    fn setup() -> (NamedTempFile, Db) {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db = init_db(temp_file.path().to_str().unwrap()).unwrap();
+        (temp_file, db)
+    }
+
+    #[test]
+    fn test_add_and_get_criteria_in_order() -> Result<()> {
+        let (_tmp, db) = setup();
+        let task = create_task(&db, "Task", None, None, 0)?;
+
+        add_criteria(
+            &db,
+            &task.id,
+            &[
+                "tests pass".to_string(),
+                "  ".to_string(),
+                "docs updated".to_string(),
+            ],
+        )?;
+        add_criteria(&db, &task.id, &["clippy clean".to_string()])?;
+
+        let criteria = get_task_criteria(&db, &task.id)?;
+        let descs: Vec<&str> = criteria.iter().map(|c| c.description.as_str()).collect();
+        assert_eq!(descs, vec!["tests pass", "docs updated", "clippy clean"]);
+        assert_eq!(
+            criteria.iter().map(|c| c.position).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert!(criteria.iter().all(|c| c.status == "pending"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_criterion_status_and_reset() -> Result<()> {
+        let (_tmp, db) = setup();
+        let task = create_task(&db, "Task", None, None, 0)?;
+        add_criteria(&db, &task.id, &["a".to_string(), "b".to_string()])?;
+
+        set_criterion_status(&db, &task.id, 1, "passed", None)?;
+        set_criterion_status(&db, &task.id, 2, "failed", Some("missing test"))?;
+
+        let criteria = get_task_criteria(&db, &task.id)?;
+        assert_eq!(criteria[0].status, "passed");
+        assert_eq!(criteria[1].status, "failed");
+        assert_eq!(criteria[1].note.as_deref(), Some("missing test"));
+
+        reset_criteria(&db, &task.id)?;
+        let criteria = get_task_criteria(&db, &task.id)?;
+        assert!(criteria
+            .iter()
+            .all(|c| c.status == "pending" && c.note.is_none()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_criterion_status_rejects_unknown() -> Result<()> {
+        let (_tmp, db) = setup();
+        let task = create_task(&db, "Task", None, None, 0)?;
+        add_criteria(&db, &task.id, &["a".to_string()])?;
+
+        assert!(set_criterion_status(&db, &task.id, 1, "maybe", None).is_err());
+        assert!(set_criterion_status(&db, &task.id, 7, "passed", None).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,352 @@
+//! Critical path and bottleneck analysis over unresolved tasks
+//! (`ralph task critical-path`).
+//!
+//! The graph holds every unresolved (not done or failed) task, with an edge
+//! from each blocker to the task it blocks and from each child to its parent,
+//! since a parent only completes once its children do. Leaf tasks weigh their
+//! estimated duration; parents weigh nothing.
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+use crate::dag::{get_all_tasks, get_ready_tasks, Db, Task};
+
+/// How many bottlenecks to report.
+const MAX_BOTTLENECKS: usize = 5;
+
+/// Result of [`critical_path`].
+#[derive(Debug, Clone, Serialize)]
+pub struct CriticalPathReport {
+    /// Longest chain of unresolved tasks, first blocker first.
+    pub path: Vec<PathStep>,
+    /// Summed estimate of `path`; `None` when no task has journal history, in
+    /// which case every task counted as one unit.
+    pub estimated_secs: Option<f64>,
+    /// Leaf tasks with the most unresolved tasks downstream, most first.
+    pub bottlenecks: Vec<Bottleneck>,
+    /// Ready tasks on the path or among the bottlenecks that other ready
+    /// tasks would be scheduled ahead of.
+    pub suggestions: Vec<PriorityBump>,
+}
+
+/// One task on the critical path.
+#[derive(Debug, Clone, Serialize)]
+pub struct PathStep {
+    pub id: String,
+    pub title: String,
+    pub status: String,
+    /// Estimated duration, `None` for parents and when there is no history.
+    pub estimate_secs: Option<f64>,
+}
+
+/// A task many others are waiting on.
+#[derive(Debug, Clone, Serialize)]
+pub struct Bottleneck {
+    pub id: String,
+    pub title: String,
+    /// Unresolved leaf tasks that cannot finish before this one.
+    pub dependents: usize,
+}
+
+/// A suggested `ralph task update <id> --priority <to>`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PriorityBump {
+    pub id: String,
+    pub title: String,
+    pub from: i32,
+    pub to: i32,
+}
+
+/// Compute the critical path, bottlenecks and priority suggestions, limited
+/// to one feature's tasks when `feature_id` is set.
+///
+/// A task's estimate is the mean duration of its earlier journaled attempts,
+/// falling back to the mean duration of all completed tasks. Fails on a
+/// dependency cycle, which `ralph task validate` reports in detail.
+pub fn critical_path(db: &Db, feature_id: Option<&str>) -> Result<CriticalPathReport> {
+    let tasks: BTreeMap<String, Task> = get_all_tasks(db)?
+        .into_iter()
+        .filter(|t| t.status != "done" && t.status != "failed")
+        .filter(|t| feature_id.is_none() || t.feature_id.as_deref() == feature_id)
+        .map(|t| (t.id.clone(), t))
+        .collect();
+    let parents: HashSet<&str> = tasks
+        .values()
+        .filter_map(|t| t.parent_id.as_deref())
+        .collect();
+
+    let mut successors: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    let mut stmt = db
+        .conn()
+        .prepare("SELECT blocker_id, blocked_id FROM dependencies")?;
+    let edges = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for (blocker, blocked) in &edges {
+        if let (Some((blocker, _)), Some((blocked, _))) =
+            (tasks.get_key_value(blocker), tasks.get_key_value(blocked))
+        {
+            successors
+                .entry(blocker.as_str())
+                .or_default()
+                .insert(blocked.as_str());
+        }
+    }
+    for task in tasks.values() {
+        if let Some((parent, _)) = task.parent_id.as_ref().and_then(|p| tasks.get_key_value(p)) {
+            successors
+                .entry(task.id.as_str())
+                .or_default()
+                .insert(parent.as_str());
+        }
+    }
+
+    let (own, fallback) = journal_estimates(db)?;
+    let weight = |id: &str| -> f64 {
+        if parents.contains(id) {
+            0.0
+        } else {
+            own.get(id).copied().or(fallback).unwrap_or(1.0)
+        }
+    };
+
+    // Longest weighted path by dynamic programming over a topological order.
+    let mut in_degree: HashMap<&str, usize> = tasks.keys().map(|id| (id.as_str(), 0)).collect();
+    for targets in successors.values() {
+        for target in targets {
+            *in_degree.entry(target).or_default() += 1;
+        }
+    }
+    let mut queue: VecDeque<&str> = tasks
+        .keys()
+        .map(String::as_str)
+        .filter(|id| in_degree[id] == 0)
+        .collect();
+    let mut order = Vec::with_capacity(tasks.len());
+    while let Some(id) = queue.pop_front() {
+        order.push(id);
+        for target in successors.get(id).into_iter().flatten() {
+            let degree = in_degree.get_mut(target).expect("edge target is a node");
+            *degree -= 1;
+            if *degree == 0 {
+                queue.push_back(target);
+            }
+        }
+    }
+    if order.len() < tasks.len() {
+        bail!("The task DAG has a dependency cycle; run `ralph task validate` to find it");
+    }
+
+    let mut distance: HashMap<&str, f64> = HashMap::new();
+    let mut previous: HashMap<&str, &str> = HashMap::new();
+    for &id in &order {
+        let total = distance.get(id).copied().unwrap_or(0.0) + weight(id);
+        distance.insert(id, total);
+        for &target in successors.get(id).into_iter().flatten() {
+            if distance.get(target).is_none_or(|&d| total > d) {
+                distance.insert(target, total);
+                previous.insert(target, id);
+            }
+        }
+    }
+    let end = order
+        .iter()
+        .copied()
+        .max_by(|a, b| distance[a].total_cmp(&distance[b]).then_with(|| b.cmp(a)));
+    let mut path_ids: Vec<&str> = Vec::new();
+    let mut cursor = end;
+    while let Some(id) = cursor {
+        path_ids.push(id);
+        cursor = previous.get(id).copied();
+    }
+    path_ids.reverse();
+
+    let has_history = !own.is_empty() || fallback.is_some();
+    let path: Vec<PathStep> = path_ids
+        .iter()
+        .map(|&id| PathStep {
+            id: id.to_string(),
+            title: tasks[id].title.clone(),
+            status: tasks[id].status.clone(),
+            estimate_secs: (has_history && !parents.contains(id)).then(|| weight(id)),
+        })
+        .collect();
+    let estimated_secs = has_history.then(|| path_ids.iter().map(|&id| weight(id)).sum());
+
+    let mut bottlenecks: Vec<Bottleneck> = tasks
+        .values()
+        .filter(|t| !parents.contains(t.id.as_str()))
+        .map(|t| {
+            let mut seen: HashSet<&str> = HashSet::new();
+            let mut stack = vec![t.id.as_str()];
+            while let Some(id) = stack.pop() {
+                for &target in successors.get(id).into_iter().flatten() {
+                    if seen.insert(target) {
+                        stack.push(target);
+                    }
+                }
+            }
+            Bottleneck {
+                id: t.id.clone(),
+                title: t.title.clone(),
+                dependents: seen.iter().filter(|id| !parents.contains(*id)).count(),
+            }
+        })
+        .filter(|b| b.dependents > 0)
+        .collect();
+    bottlenecks.sort_by(|a, b| b.dependents.cmp(&a.dependents).then(a.id.cmp(&b.id)));
+    bottlenecks.truncate(MAX_BOTTLENECKS);
+
+    let important: HashSet<&str> = path_ids
+        .iter()
+        .copied()
+        .chain(bottlenecks.iter().map(|b| b.id.as_str()))
+        .collect();
+    let ready: Vec<Task> = get_ready_tasks(db)?
+        .into_iter()
+        .filter(|t| tasks.contains_key(&t.id))
+        .collect();
+    let best_other = ready
+        .iter()
+        .filter(|t| !important.contains(t.id.as_str()))
+        .map(|t| t.priority)
+        .min();
+    let suggestions = match best_other {
+        Some(best) => {
+            let to = (best - 1).max(0);
+            ready
+                .iter()
+                .filter(|t| important.contains(t.id.as_str()) && t.priority > to)
+                .filter(|t| t.priority >= best)
+                .map(|t| PriorityBump {
+                    id: t.id.clone(),
+                    title: t.title.clone(),
+                    from: t.priority,
+                    to,
+                })
+                .collect()
+        }
+        None => Vec::new(),
+    };
+
+    Ok(CriticalPathReport {
+        path,
+        estimated_secs,
+        bottlenecks,
+        suggestions,
+    })
+}
+
+/// Mean journaled duration per task, and the mean over completed attempts.
+fn journal_estimates(db: &Db) -> Result<(HashMap<String, f64>, Option<f64>)> {
+    let mut stmt = db.conn().prepare(
+        "SELECT task_id, AVG(duration_secs) FROM journal
+         WHERE task_id IS NOT NULL AND duration_secs > 0
+         GROUP BY task_id",
+    )?;
+    let own = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<HashMap<String, f64>>>()?;
+    let fallback: Option<f64> = db.conn().query_row(
+        "SELECT AVG(duration_secs) FROM journal WHERE outcome = 'done' AND duration_secs > 0",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok((own, fallback))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag::{add_dependency, create_task, force_complete_task, init_db};
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, Db) {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        (dir, db)
+    }
+
+    fn ids(report: &CriticalPathReport) -> Vec<&str> {
+        report.path.iter().map(|s| s.id.as_str()).collect()
+    }
+
+    #[test]
+    fn longest_chain_wins_and_counts_tasks_without_history() {
+        let (_dir, db) = setup();
+        let a = create_task(&db, "A", None, None, 5).unwrap();
+        let b = create_task(&db, "B", None, None, 5).unwrap();
+        let c = create_task(&db, "C", None, None, 5).unwrap();
+        create_task(&db, "Unrelated", None, None, 0).unwrap();
+        let done = create_task(&db, "Done", None, None, 5).unwrap();
+        add_dependency(&db, &done.id, &a.id).unwrap();
+        add_dependency(&db, &a.id, &b.id).unwrap();
+        add_dependency(&db, &b.id, &c.id).unwrap();
+        force_complete_task(db.conn(), &done.id).unwrap();
+
+        let report = critical_path(&db, None).unwrap();
+        assert_eq!(
+            ids(&report),
+            vec![a.id.as_str(), b.id.as_str(), c.id.as_str()]
+        );
+        assert_eq!(report.estimated_secs, None);
+        assert_eq!(report.bottlenecks[0].id, a.id);
+        assert_eq!(report.bottlenecks[0].dependents, 2);
+
+        // A is ready but the unrelated task (priority 0) would run first.
+        assert_eq!(report.suggestions.len(), 1);
+        assert_eq!(report.suggestions[0].id, a.id);
+        assert_eq!(
+            (report.suggestions[0].from, report.suggestions[0].to),
+            (5, 0)
+        );
+    }
+
+    #[test]
+    fn journal_durations_weight_the_path() {
+        let (_dir, db) = setup();
+        let slow = create_task(&db, "Slow", None, None, 1).unwrap();
+        let quick1 = create_task(&db, "Quick 1", None, None, 1).unwrap();
+        let quick2 = create_task(&db, "Quick 2", None, None, 1).unwrap();
+        add_dependency(&db, &quick1.id, &quick2.id).unwrap();
+        db.conn()
+            .execute(
+                "INSERT INTO journal (run_id, iteration, task_id, outcome, duration_secs)
+                 VALUES ('r', 1, ?, 'retried', 600), ('r', 2, ?, 'retried', 10)",
+                [&slow.id, &quick1.id],
+            )
+            .unwrap();
+
+        let report = critical_path(&db, None).unwrap();
+        assert_eq!(ids(&report), vec![slow.id.as_str()]);
+        assert_eq!(report.estimated_secs, Some(600.0));
+    }
+
+    #[test]
+    fn parents_wait_for_children_and_cycles_fail() {
+        let (_dir, db) = setup();
+        let parent = create_task(&db, "Parent", None, None, 1).unwrap();
+        let child = create_task(&db, "Child", None, Some(&parent.id), 1).unwrap();
+        let after = create_task(&db, "After", None, None, 1).unwrap();
+        add_dependency(&db, &parent.id, &after.id).unwrap();
+
+        let report = critical_path(&db, None).unwrap();
+        assert_eq!(
+            ids(&report),
+            vec![child.id.as_str(), parent.id.as_str(), after.id.as_str()]
+        );
+        assert_eq!(report.bottlenecks.len(), 1);
+        assert_eq!(report.bottlenecks[0].dependents, 1);
+
+        db.conn()
+            .execute(
+                "INSERT INTO dependencies (blocker_id, blocked_id) VALUES (?, ?)",
+                [&after.id, &child.id],
+            )
+            .unwrap();
+        assert!(critical_path(&db, None).is_err());
+    }
+}
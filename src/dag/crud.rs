@@ -24,6 +24,14 @@ pub struct CreateTaskParams<'a> {
     pub feature_id: Option<&'a str>,
     pub task_type: &'a str,
     pub max_retries: i32,
+    /// Acceptance criteria checklist, stored in `task_criteria`.
+    pub criteria: &'a [String],
+    /// Task whose agent queued this one with a `<task-add>` sigil.
+    pub discovered_by: Option<&'a str>,
+    /// Working directory relative to the project root.
+    pub cwd: Option<&'a str>,
+    /// Who is creating the task (see [`Task::created_by`]).
+    pub created_by: Option<&'a str>,
 }
 
 /// Create a new task.
@@ -44,10 +52,37 @@ pub fn create_task(
             feature_id: None,
             task_type: "feature",
             max_retries: 3,
+            criteria: &[],
+            discovered_by: None,
+            cwd: None,
+            created_by: None,
         },
     )
 }
 
+/// Create a pending task of `feature_id` under `parent_id` for tests:
+/// priority 0, three retries, no description or criteria.
+#[cfg(test)]
+pub fn create_test_task(db: &Db, feature_id: &str, title: &str, parent_id: Option<&str>) -> Task {
+    create_task_with_feature(
+        db,
+        CreateTaskParams {
+            title,
+            description: None,
+            parent_id,
+            priority: 0,
+            feature_id: Some(feature_id),
+            task_type: "feature",
+            max_retries: 3,
+            criteria: &[],
+            discovered_by: None,
+            cwd: None,
+            created_by: None,
+        },
+    )
+    .unwrap()
+}
+
 /// Create a new task with feature association and task type.
 pub fn create_task_with_feature(db: &Db, params: CreateTaskParams<'_>) -> Result<Task> {
     let CreateTaskParams {
@@ -58,6 +93,10 @@ pub fn create_task_with_feature(db: &Db, params: CreateTaskParams<'_>) -> Result
         feature_id,
         task_type,
         max_retries,
+        criteria,
+        discovered_by,
+        cwd,
+        created_by,
     } = params;
 
     // Validate parent exists if specified
@@ -75,6 +114,8 @@ pub fn create_task_with_feature(db: &Db, params: CreateTaskParams<'_>) -> Result
         }
     }
 
+    let cwd = cwd.map(normalize_cwd).transpose()?.flatten();
+    let cwd = cwd.as_deref();
     let timestamp = chrono::Utc::now().to_rfc3339();
     let desc = description.unwrap_or("");
 
@@ -82,14 +123,25 @@ pub fn create_task_with_feature(db: &Db, params: CreateTaskParams<'_>) -> Result
     let id = generate_and_insert_task_id(
         |id| {
             db.conn().execute(
-                "INSERT INTO tasks (id, title, description, parent_id, priority, feature_id, task_type, max_retries, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-                rusqlite::params![id, title, desc, parent_id, priority, feature_id, task_type, max_retries, &timestamp, &timestamp],
+                "INSERT INTO tasks (id, title, description, parent_id, priority, feature_id, task_type, max_retries, discovered_by, cwd, created_by, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                rusqlite::params![id, title, desc, parent_id, priority, feature_id, task_type, max_retries, discovered_by, cwd, created_by, &timestamp, &timestamp],
             )?;
             Ok(())
         },
         10, // max retries
     )?;
 
+    // Frontmatter criteria join the checklist, so verification checks them too.
+    let mut criteria = criteria.to_vec();
+    for c in crate::dag::parse_frontmatter(desc).0.criteria {
+        if !criteria.contains(&c) {
+            criteria.push(c);
+        }
+    }
+    if !criteria.is_empty() {
+        crate::dag::add_criteria(db, &id, &criteria)?;
+    }
+
     Ok(Task {
         id: id.clone(),
         title: title.to_string(),
@@ -105,6 +157,12 @@ pub fn create_task_with_feature(db: &Db, params: CreateTaskParams<'_>) -> Result
         created_at: timestamp.clone(),
         updated_at: timestamp,
         claimed_by: None,
+        discovered_by: discovered_by.map(|s| s.to_string()),
+        source_ref: None,
+        cwd: cwd.map(|s| s.to_string()),
+        created_by: created_by.map(|s| s.to_string()),
+        completed_by: None,
+        plan_ref: None,
     })
 }
 
@@ -164,13 +222,95 @@ pub fn update_task(db: &Db, id: &str, fields: TaskUpdate) -> Result<Task> {
     let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
     db.conn().execute(&query, param_refs.as_slice())?;
 
+    // A task without a checklist picks up criteria added to its frontmatter.
+    if let Some(description) = &fields.description {
+        let criteria = crate::dag::parse_frontmatter(description).0.criteria;
+        if !criteria.is_empty() && crate::dag::get_task_criteria(db, id)?.is_empty() {
+            crate::dag::add_criteria(db, id, &criteria)?;
+        }
+    }
+
     get_task(db, id)
 }
 
+/// Set or clear a task's working directory.
+pub fn set_task_cwd(db: &Db, id: &str, cwd: Option<&str>) -> Result<()> {
+    let cwd = cwd.map(normalize_cwd).transpose()?.flatten();
+    let changed = db.conn().execute(
+        "UPDATE tasks SET cwd = ?, updated_at = ? WHERE id = ?",
+        rusqlite::params![cwd, chrono::Utc::now().to_rfc3339(), id],
+    )?;
+    if changed == 0 {
+        return Err(anyhow!("Task '{}' does not exist", id));
+    }
+    Ok(())
+}
+
+/// Move a task under another parent.
+pub fn set_task_parent(db: &Db, id: &str, parent_id: &str) -> Result<()> {
+    let changed = db.conn().execute(
+        "UPDATE tasks SET parent_id = ?, updated_at = ? WHERE id = ?",
+        rusqlite::params![parent_id, chrono::Utc::now().to_rfc3339(), id],
+    )?;
+    if changed == 0 {
+        return Err(anyhow!("Task '{}' does not exist", id));
+    }
+    Ok(())
+}
+
+/// Set or clear the plan section a task was derived from.
+pub fn set_task_plan_ref(db: &Db, id: &str, plan_ref: Option<&str>) -> Result<()> {
+    let plan_ref = plan_ref.map(str::trim).filter(|r| !r.is_empty());
+    let changed = db.conn().execute(
+        "UPDATE tasks SET plan_ref = ?, updated_at = ? WHERE id = ?",
+        rusqlite::params![plan_ref, chrono::Utc::now().to_rfc3339(), id],
+    )?;
+    if changed == 0 {
+        return Err(anyhow!("Task '{}' does not exist", id));
+    }
+    Ok(())
+}
+
+/// Record who completed a task. Shown only while the task is `done`, so a
+/// task that is reset and completed again is attributed to the last completer.
+pub fn set_completed_by(db: &Db, id: &str, completed_by: Option<&str>) -> Result<()> {
+    let changed = db.conn().execute(
+        "UPDATE tasks SET completed_by = ? WHERE id = ?",
+        rusqlite::params![completed_by, id],
+    )?;
+    if changed == 0 {
+        return Err(anyhow!("Task '{}' does not exist", id));
+    }
+    Ok(())
+}
+
+/// Normalize a task working directory to a plain relative path
+/// (`./packages/api/` becomes `packages/api`). Absolute paths and paths that
+/// climb out of the project root are rejected; the root itself is `None`.
+pub fn normalize_cwd(dir: &str) -> Result<Option<String>> {
+    use std::path::{Component, Path};
+
+    let mut parts = Vec::new();
+    for component in Path::new(dir.trim()).components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(anyhow!(
+                    "Task directory '{}' must be relative to the project root and stay inside it",
+                    dir
+                ))
+            }
+        }
+    }
+    Ok((!parts.is_empty()).then(|| parts.join("/")))
+}
+
 /// Delete a task.
 ///
 /// Rejects if other tasks depend on it (blocker in dependencies table).
-/// Cascade deletes children.
+/// Cascade deletes children. Journal entries of its attempts are kept with
+/// the task reference cleared.
 pub fn delete_task(db: &Db, id: &str) -> Result<()> {
     // Check if task exists
     let exists: bool = db
@@ -222,6 +362,31 @@ pub fn delete_task(db: &Db, id: &str) -> Result<()> {
     db.conn()
         .execute("DELETE FROM task_logs WHERE task_id = ?", [id])?;
 
+    // Delete acceptance criteria
+    db.conn()
+        .execute("DELETE FROM task_criteria WHERE task_id = ?", [id])?;
+
+    // Delete cached verification results
+    db.conn()
+        .execute("DELETE FROM verifications WHERE task_id = ?", [id])?;
+
+    // Delete registered artifacts (the files themselves stay)
+    db.conn()
+        .execute("DELETE FROM artifacts WHERE task_id = ?", [id])?;
+
+    db.conn()
+        .execute("DELETE FROM task_labels WHERE task_id = ?", [id])?;
+
+    // Tasks this one queued outlive it
+    db.conn().execute(
+        "UPDATE tasks SET discovered_by = NULL WHERE discovered_by = ?",
+        [id],
+    )?;
+
+    // Journal entries of its attempts stay as run history
+    db.conn()
+        .execute("UPDATE journal SET task_id = NULL WHERE task_id = ?", [id])?;
+
     // Delete the task itself
     db.conn().execute("DELETE FROM tasks WHERE id = ?", [id])?;
 
@@ -273,6 +438,45 @@ pub fn delete_tasks_for_feature(db: &Db, feature_id: &str) -> Result<usize> {
         .collect();
     stmt.execute(params.as_slice())?;
 
+    // Delete acceptance criteria
+    let sql = format!(
+        "DELETE FROM task_criteria WHERE task_id IN ({})",
+        placeholder_str
+    );
+    let mut stmt = db.conn().prepare(&sql)?;
+    stmt.execute(params.as_slice())?;
+
+    // Delete cached verification results
+    let sql = format!(
+        "DELETE FROM verifications WHERE task_id IN ({})",
+        placeholder_str
+    );
+    let mut stmt = db.conn().prepare(&sql)?;
+    stmt.execute(params.as_slice())?;
+
+    // Delete registered artifacts
+    let sql = format!(
+        "DELETE FROM artifacts WHERE task_id IN ({})",
+        placeholder_str
+    );
+    let mut stmt = db.conn().prepare(&sql)?;
+    stmt.execute(params.as_slice())?;
+
+    let sql = format!(
+        "DELETE FROM task_labels WHERE task_id IN ({})",
+        placeholder_str
+    );
+    let mut stmt = db.conn().prepare(&sql)?;
+    stmt.execute(params.as_slice())?;
+
+    // Tasks these ones queued outlive them
+    let sql = format!(
+        "UPDATE tasks SET discovered_by = NULL WHERE discovered_by IN ({})",
+        placeholder_str
+    );
+    let mut stmt = db.conn().prepare(&sql)?;
+    stmt.execute(params.as_slice())?;
+
     // Delete journal entries referencing this feature
     db.conn()
         .execute("DELETE FROM journal WHERE feature_id = ?", [feature_id])?;
@@ -408,10 +612,10 @@ pub fn get_all_tasks_for_feature(db: &Db, feature_id: &str) -> Result<Vec<Task>>
     Ok(tasks)
 }
 
-/// Get all tasks in the database.
+/// Get all tasks in the database, excluding archived ones.
 pub fn get_all_tasks(db: &Db) -> Result<Vec<Task>> {
     let query = format!(
-        "SELECT {} FROM tasks ORDER BY priority ASC, created_at ASC",
+        "SELECT {} FROM tasks WHERE archived_at IS NULL ORDER BY priority ASC, created_at ASC",
         TASK_COLUMNS,
     );
     let mut stmt = db.conn().prepare(&query)?;
@@ -542,6 +746,107 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_task_cwd_is_normalized_and_validated() {
+        let temp = NamedTempFile::new().unwrap();
+        let db = init_db(temp.path().to_str().unwrap()).unwrap();
+
+        let params = |cwd| CreateTaskParams {
+            title: "Endpoint",
+            description: None,
+            parent_id: None,
+            priority: 0,
+            feature_id: None,
+            task_type: "standalone",
+            max_retries: 3,
+            criteria: &[],
+            discovered_by: None,
+            cwd,
+            created_by: None,
+        };
+        let task = create_task_with_feature(&db, params(Some("./packages//api/"))).unwrap();
+        assert_eq!(task.cwd.as_deref(), Some("packages/api"));
+        assert_eq!(get_task(&db, &task.id).unwrap().cwd, task.cwd);
+
+        for bad in ["/etc", "../sibling", "packages/../../x"] {
+            let err = create_task_with_feature(&db, params(Some(bad))).unwrap_err();
+            assert!(err.to_string().contains("relative to the project root"));
+        }
+
+        set_task_cwd(&db, &task.id, Some(".")).unwrap();
+        assert_eq!(get_task(&db, &task.id).unwrap().cwd, None);
+        assert!(set_task_cwd(&db, "t-missing", Some("x")).is_err());
+    }
+
+    #[test]
+    fn test_frontmatter_criteria_seed_the_checklist() {
+        let temp = NamedTempFile::new().unwrap();
+        let db = init_db(temp.path().to_str().unwrap()).unwrap();
+        let descriptions = |c: &[crate::dag::Criterion]| {
+            c.iter().map(|c| c.description.clone()).collect::<Vec<_>>()
+        };
+
+        let explicit = ["Builds".to_string()];
+        let task = create_task_with_feature(
+            &db,
+            CreateTaskParams {
+                title: "Endpoint",
+                description: Some("---\ncriteria: [Builds, Returns 404]\n---\nAdd it"),
+                parent_id: None,
+                priority: 0,
+                feature_id: None,
+                task_type: "standalone",
+                max_retries: 3,
+                criteria: &explicit,
+                discovered_by: None,
+                cwd: None,
+                created_by: None,
+            },
+        )
+        .unwrap();
+        let criteria = crate::dag::get_task_criteria(&db, &task.id).unwrap();
+        assert_eq!(descriptions(&criteria), ["Builds", "Returns 404"]);
+
+        let plain = create_task(&db, "Plain", Some("No checklist"), None, 0).unwrap();
+        let update = |description: &str| TaskUpdate {
+            title: None,
+            description: Some(description.to_string()),
+            priority: None,
+        };
+        update_task(&db, &plain.id, update("---\ncriteria:\n  - Logs\n---\n")).unwrap();
+        update_task(&db, &plain.id, update("---\ncriteria:\n  - Other\n---\n")).unwrap();
+        let criteria = crate::dag::get_task_criteria(&db, &plain.id).unwrap();
+        assert_eq!(descriptions(&criteria), ["Logs"]);
+    }
+
+    #[test]
+    fn test_delete_task_keeps_tasks_it_discovered() {
+        let temp = NamedTempFile::new().unwrap();
+        let db = init_db(temp.path().to_str().unwrap()).unwrap();
+
+        let origin = create_task(&db, "Origin", None, None, 0).unwrap();
+        let found = create_task_with_feature(
+            &db,
+            CreateTaskParams {
+                title: "Follow-up",
+                description: None,
+                parent_id: None,
+                priority: 0,
+                feature_id: None,
+                task_type: "standalone",
+                max_retries: 3,
+                criteria: &[],
+                discovered_by: Some(&origin.id),
+                cwd: None,
+                created_by: None,
+            },
+        )
+        .unwrap();
+
+        delete_task(&db, &origin.id).unwrap();
+        assert_eq!(get_task(&db, &found.id).unwrap().discovered_by, None);
+    }
+
     #[test]
     fn test_delete_task_cascade_children() {
         let temp = NamedTempFile::new().unwrap();
@@ -678,6 +983,10 @@ mod tests {
                 feature_id: Some(&feat.id),
                 task_type: "feature",
                 max_retries: 3,
+                criteria: &[],
+                discovered_by: None,
+                cwd: None,
+                created_by: None,
             },
         )
         .unwrap();
@@ -691,6 +1000,10 @@ mod tests {
                 feature_id: Some(&feat.id),
                 task_type: "feature",
                 max_retries: 3,
+                criteria: &[],
+                discovered_by: None,
+                cwd: None,
+                created_by: None,
             },
         )
         .unwrap();
@@ -761,4 +1074,39 @@ mod tests {
         assert_eq!(ready.len(), 1);
         assert_eq!(ready[0].id, c.id);
     }
+
+    #[test]
+    fn test_created_by_and_completed_by_are_stored() {
+        let temp = NamedTempFile::new().unwrap();
+        let db = init_db(temp.path().to_str().unwrap()).unwrap();
+        let task = create_task_with_feature(
+            &db,
+            CreateTaskParams {
+                title: "Attributed",
+                description: None,
+                parent_id: None,
+                priority: 0,
+                feature_id: None,
+                task_type: "standalone",
+                max_retries: 3,
+                criteria: &[],
+                discovered_by: None,
+                cwd: None,
+                created_by: Some("alice"),
+            },
+        )
+        .unwrap();
+        assert_eq!(task.created_by.as_deref(), Some("alice"));
+
+        crate::dag::claim_task(&db, &task.id, "agent-test").unwrap();
+        crate::dag::complete_task(&db, &task.id).unwrap();
+        set_completed_by(&db, &task.id, Some("bob via claude-agent-acp")).unwrap();
+        let task = get_task(&db, &task.id).unwrap();
+        assert_eq!(task.created_by.as_deref(), Some("alice"));
+        assert_eq!(
+            task.completed_by.as_deref(),
+            Some("bob via claude-agent-acp")
+        );
+        assert!(set_completed_by(&db, "t-missing", None).is_err());
+    }
 }
@@ -1,11 +1,14 @@
 //! Database connection and initialization.
 
-use anyhow::{Context, Result};
-use rusqlite::Connection;
-use std::path::Path;
+use anyhow::{bail, Context, Result};
+use rusqlite::{params, Connection, OpenFlags};
+use std::path::{Path, PathBuf};
 
 /// Current schema version.
-const SCHEMA_VERSION: i32 = 5;
+const SCHEMA_VERSION: i32 = 25;
+
+/// How long a write waits for another connection's lock before failing.
+const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 
 /// SQLite database wrapper.
 pub struct Db {
@@ -17,47 +20,42 @@ impl Db {
     pub fn conn(&self) -> &Connection {
         &self.conn
     }
-}
-
-/// Open or initialize the database at the given path.
-pub fn init_db(path: &str) -> Result<Db> {
-    // Create parent directories if needed
-    if let Some(parent) = Path::new(path).parent() {
-        std::fs::create_dir_all(parent)
-            .with_context(|| format!("Failed to create parent directory for {}", path))?;
-    }
-
-    // Open or create database
-    let conn =
-        Connection::open(path).with_context(|| format!("Failed to open database at {}", path))?;
-
-    // Enable WAL mode
-    conn.pragma_update(None, "journal_mode", "WAL")
-        .context("Failed to enable WAL mode")?;
-
-    // Enable foreign keys
-    conn.pragma_update(None, "foreign_keys", "ON")
-        .context("Failed to enable foreign keys")?;
-
-    // Check schema version
-    let version: i32 = conn
-        .pragma_query_value(None, "user_version", |row| row.get(0))
-        .context("Failed to read schema version")?;
 
-    if version < SCHEMA_VERSION {
-        // Run migrations
-        migrate(&conn, version, SCHEMA_VERSION)?;
+    /// Run `f` so that its writes apply together or not at all. Uses a
+    /// savepoint, so it also nests inside an open transaction.
+    pub fn atomically<T>(&self, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        self.conn.execute_batch("SAVEPOINT ralph_atomic")?;
+        match f() {
+            Ok(value) => {
+                self.conn.execute_batch("RELEASE ralph_atomic")?;
+                Ok(value)
+            }
+            Err(e) => {
+                let _ = self
+                    .conn
+                    .execute_batch("ROLLBACK TO ralph_atomic; RELEASE ralph_atomic");
+                Err(e)
+            }
+        }
     }
+}
 
-    Ok(Db { conn })
+/// A single ordered schema migration.
+struct Migration {
+    version: i32,
+    description: &'static str,
+    sql: &'static str,
 }
 
-/// Run migrations from `from_version` to `to_version`.
-fn migrate(conn: &Connection, from_version: i32, to_version: i32) -> Result<()> {
-    if from_version < 1 && to_version >= 1 {
-        // Initial schema
-        conn.execute_batch(
-            r#"
+/// Every schema migration, in the order it must be applied.
+///
+/// Append new migrations to the end and bump `SCHEMA_VERSION`; never edit a
+/// migration that has already shipped.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial task, dependency and log tables",
+        sql: r#"
             CREATE TABLE tasks (
                 id TEXT PRIMARY KEY,
                 parent_id TEXT REFERENCES tasks(id),
@@ -85,13 +83,11 @@ fn migrate(conn: &Connection, from_version: i32, to_version: i32) -> Result<()>
                 timestamp TEXT NOT NULL
             );
             "#,
-        )
-        .context("Failed to create schema v1")?;
-    }
-
-    if from_version < 2 && to_version >= 2 {
-        conn.execute_batch(
-            r#"
+    },
+    Migration {
+        version: 2,
+        description: "features and task metadata columns",
+        sql: r#"
             CREATE TABLE IF NOT EXISTS features (
                 id TEXT PRIMARY KEY,
                 name TEXT NOT NULL UNIQUE,
@@ -111,13 +107,11 @@ fn migrate(conn: &Connection, from_version: i32, to_version: i32) -> Result<()>
             ALTER TABLE tasks ADD COLUMN verification_status TEXT
                 CHECK (verification_status IN ('pending','passed','failed'));
             "#,
-        )
-        .context("Failed to create schema v2")?;
-    }
-
-    if from_version < 3 && to_version >= 3 {
-        conn.execute_batch(
-            r#"
+    },
+    Migration {
+        version: 3,
+        description: "journal table with FTS5 index",
+        sql: r#"
             CREATE TABLE journal (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
                 run_id TEXT NOT NULL,
@@ -158,13 +152,11 @@ fn migrate(conn: &Connection, from_version: i32, to_version: i32) -> Result<()>
                 INSERT INTO journal_fts(rowid, notes) VALUES (new.id, new.notes);
             END;
             "#,
-        )
-        .context("Failed to create schema v3")?;
-    }
-
-    if from_version < 4 && to_version >= 4 {
-        conn.execute_batch(
-            r#"
+    },
+    Migration {
+        version: 4,
+        description: "task lookup indexes",
+        sql: r#"
             CREATE INDEX IF NOT EXISTS idx_tasks_status_priority_created
                 ON tasks(status, priority, created_at);
             CREATE INDEX IF NOT EXISTS idx_tasks_parent_id
@@ -176,13 +168,11 @@ fn migrate(conn: &Connection, from_version: i32, to_version: i32) -> Result<()>
             CREATE INDEX IF NOT EXISTS idx_task_logs_task_id_timestamp
                 ON task_logs(task_id, timestamp);
             "#,
-        )
-        .context("Failed to create schema v4 indexes")?;
-    }
-
-    if from_version < 5 && to_version >= 5 {
-        conn.execute_batch(
-            r#"
+    },
+    Migration {
+        version: 5,
+        description: "model_overrides table",
+        sql: r#"
             CREATE TABLE IF NOT EXISTS model_overrides (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
                 iteration INTEGER NOT NULL,
@@ -194,13 +184,514 @@ fn migrate(conn: &Connection, from_version: i32, to_version: i32) -> Result<()>
             CREATE INDEX IF NOT EXISTS idx_model_overrides_iteration
                 ON model_overrides(iteration);
             "#,
+    },
+    Migration {
+        version: 6,
+        description: "task_criteria table",
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS task_criteria (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                task_id TEXT NOT NULL REFERENCES tasks(id),
+                position INTEGER NOT NULL,
+                description TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'pending'
+                    CHECK (status IN ('pending','passed','failed')),
+                note TEXT,
+                updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+                UNIQUE (task_id, position)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_task_criteria_task_id
+                ON task_criteria(task_id, position);
+            "#,
+    },
+    Migration {
+        version: 7,
+        description: "spec/plan revision numbers on features",
+        sql: r#"
+            ALTER TABLE features ADD COLUMN spec_revision INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE features ADD COLUMN plan_revision INTEGER NOT NULL DEFAULT 0;
+            "#,
+    },
+    Migration {
+        version: 8,
+        description: "token counts on journal entries",
+        sql: r#"
+            ALTER TABLE journal ADD COLUMN input_tokens INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE journal ADD COLUMN output_tokens INTEGER NOT NULL DEFAULT 0;
+            "#,
+    },
+    Migration {
+        version: 9,
+        description: "interrupted and rolled_back journal outcomes",
+        // SQLite cannot alter a CHECK constraint, so the table is rebuilt.
+        // Row ids are preserved, which keeps the external-content FTS index valid.
+        sql: r#"
+            CREATE TABLE journal_new (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                run_id TEXT NOT NULL,
+                iteration INTEGER NOT NULL,
+                task_id TEXT REFERENCES tasks(id),
+                feature_id TEXT REFERENCES features(id),
+                outcome TEXT NOT NULL
+                    CHECK (outcome IN ('done','failed','retried','blocked','interrupted','rolled_back')),
+                model TEXT,
+                duration_secs REAL,
+                cost_usd REAL,
+                files_modified TEXT,
+                notes TEXT,
+                created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                input_tokens INTEGER NOT NULL DEFAULT 0,
+                output_tokens INTEGER NOT NULL DEFAULT 0
+            );
+
+            INSERT INTO journal_new
+                SELECT id, run_id, iteration, task_id, feature_id, outcome, model,
+                       duration_secs, cost_usd, files_modified, notes, created_at,
+                       input_tokens, output_tokens
+                FROM journal;
+
+            DROP TABLE journal;
+            ALTER TABLE journal_new RENAME TO journal;
+
+            CREATE INDEX idx_journal_run_id ON journal(run_id, iteration);
+            CREATE INDEX idx_journal_feature_id ON journal(feature_id);
+
+            CREATE TRIGGER journal_ai AFTER INSERT ON journal BEGIN
+                INSERT INTO journal_fts(rowid, notes) VALUES (new.id, new.notes);
+            END;
+
+            CREATE TRIGGER journal_ad AFTER DELETE ON journal BEGIN
+                INSERT INTO journal_fts(journal_fts, rowid, notes)
+                    VALUES('delete', old.id, old.notes);
+            END;
+
+            CREATE TRIGGER journal_au AFTER UPDATE ON journal BEGIN
+                INSERT INTO journal_fts(journal_fts, rowid, notes)
+                    VALUES('delete', old.id, old.notes);
+                INSERT INTO journal_fts(rowid, notes) VALUES (new.id, new.notes);
+            END;
+            "#,
+    },
+    Migration {
+        version: 10,
+        description: "archived_at on features and tasks",
+        sql: r#"
+            ALTER TABLE features ADD COLUMN archived_at TEXT;
+            ALTER TABLE tasks ADD COLUMN archived_at TEXT;
+            "#,
+    },
+    Migration {
+        version: 11,
+        description: "timeout journal outcome",
+        // Same rebuild as v9, widening the outcome CHECK again.
+        sql: r#"
+            CREATE TABLE journal_new (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                run_id TEXT NOT NULL,
+                iteration INTEGER NOT NULL,
+                task_id TEXT REFERENCES tasks(id),
+                feature_id TEXT REFERENCES features(id),
+                outcome TEXT NOT NULL
+                    CHECK (outcome IN ('done','failed','retried','blocked','interrupted','rolled_back','timeout')),
+                model TEXT,
+                duration_secs REAL,
+                cost_usd REAL,
+                files_modified TEXT,
+                notes TEXT,
+                created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                input_tokens INTEGER NOT NULL DEFAULT 0,
+                output_tokens INTEGER NOT NULL DEFAULT 0
+            );
+
+            INSERT INTO journal_new
+                SELECT id, run_id, iteration, task_id, feature_id, outcome, model,
+                       duration_secs, cost_usd, files_modified, notes, created_at,
+                       input_tokens, output_tokens
+                FROM journal;
+
+            DROP TABLE journal;
+            ALTER TABLE journal_new RENAME TO journal;
+
+            CREATE INDEX idx_journal_run_id ON journal(run_id, iteration);
+            CREATE INDEX idx_journal_feature_id ON journal(feature_id);
+
+            CREATE TRIGGER journal_ai AFTER INSERT ON journal BEGIN
+                INSERT INTO journal_fts(rowid, notes) VALUES (new.id, new.notes);
+            END;
+
+            CREATE TRIGGER journal_ad AFTER DELETE ON journal BEGIN
+                INSERT INTO journal_fts(journal_fts, rowid, notes)
+                    VALUES('delete', old.id, old.notes);
+            END;
+
+            CREATE TRIGGER journal_au AFTER UPDATE ON journal BEGIN
+                INSERT INTO journal_fts(journal_fts, rowid, notes)
+                    VALUES('delete', old.id, old.notes);
+                INSERT INTO journal_fts(rowid, notes) VALUES (new.id, new.notes);
+            END;
+            "#,
+    },
+    Migration {
+        version: 12,
+        description: "discovered_by on tasks",
+        sql: r#"
+            ALTER TABLE tasks ADD COLUMN discovered_by TEXT REFERENCES tasks(id);
+            "#,
+    },
+    Migration {
+        version: 13,
+        description: "verifications cache table",
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS verifications (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                task_id TEXT NOT NULL REFERENCES tasks(id),
+                fingerprint TEXT NOT NULL,
+                passed INTEGER NOT NULL,
+                reason TEXT NOT NULL,
+                criteria TEXT NOT NULL DEFAULT '[]',
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_verifications_task_fingerprint
+                ON verifications(task_id, fingerprint);
+            "#,
+    },
+    Migration {
+        version: 14,
+        description: "source_ref and source_synced_status on tasks",
+        sql: r#"
+            ALTER TABLE tasks ADD COLUMN source_ref TEXT;
+            ALTER TABLE tasks ADD COLUMN source_synced_status TEXT;
+
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_tasks_source_ref
+                ON tasks(source_ref) WHERE source_ref IS NOT NULL;
+            "#,
+    },
+    Migration {
+        version: 15,
+        description: "claim lease expiry on tasks",
+        sql: r#"
+            ALTER TABLE tasks ADD COLUMN lease_expires_at TEXT;
+
+            -- Agent claims from before leases expire now, so a crashed run's
+            -- task is reclaimable; human claims keep their NULL lease.
+            UPDATE tasks SET lease_expires_at = datetime('now')
+                WHERE status = 'in_progress' AND claimed_by IS NOT NULL
+                AND claimed_by NOT LIKE 'human:%';
+            "#,
+    },
+    Migration {
+        version: 16,
+        description: "commit the latest plan revision was written at",
+        sql: r#"
+            ALTER TABLE features ADD COLUMN plan_commit TEXT;
+            "#,
+    },
+    Migration {
+        version: 17,
+        description: "working directory on tasks",
+        sql: r#"
+            ALTER TABLE tasks ADD COLUMN cwd TEXT;
+            "#,
+    },
+    Migration {
+        version: 18,
+        description: "when a feature's follow-up tasks were created",
+        sql: r#"
+            ALTER TABLE features ADD COLUMN post_feature_at TEXT;
+            "#,
+    },
+    Migration {
+        version: 19,
+        description: "created_by and completed_by on tasks",
+        sql: r#"
+            ALTER TABLE tasks ADD COLUMN created_by TEXT;
+            ALTER TABLE tasks ADD COLUMN completed_by TEXT;
+            "#,
+    },
+    Migration {
+        version: 20,
+        description: "artifacts table",
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS artifacts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                task_id TEXT NOT NULL REFERENCES tasks(id),
+                path TEXT NOT NULL,
+                description TEXT NOT NULL DEFAULT '',
+                created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                UNIQUE (task_id, path)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_artifacts_task_id
+                ON artifacts(task_id);
+            "#,
+    },
+    Migration {
+        version: 21,
+        description: "verification_history table",
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS verification_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                task_id TEXT NOT NULL,
+                fingerprint TEXT NOT NULL,
+                passed INTEGER NOT NULL,
+                flaky INTEGER NOT NULL DEFAULT 0,
+                files TEXT NOT NULL DEFAULT '[]',
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_verification_history_task_id
+                ON verification_history(task_id);
+            "#,
+    },
+    Migration {
+        version: 22,
+        description: "task_labels table",
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS task_labels (
+                task_id TEXT NOT NULL REFERENCES tasks(id),
+                label TEXT NOT NULL,
+                PRIMARY KEY (task_id, label)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_task_labels_label
+                ON task_labels(label);
+            "#,
+    },
+    Migration {
+        version: 23,
+        description: "journal files_read column",
+        sql: r#"
+            ALTER TABLE journal ADD COLUMN files_read TEXT;
+            "#,
+    },
+    Migration {
+        version: 24,
+        description: "run_summaries table",
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS run_summaries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                run_id TEXT NOT NULL,
+                iteration INTEGER NOT NULL,
+                summary TEXT NOT NULL,
+                model TEXT,
+                cost_usd REAL NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_run_summaries_run_id
+                ON run_summaries(run_id);
+            "#,
+    },
+    Migration {
+        version: 25,
+        description: "plan_ref on tasks",
+        sql: r#"
+            ALTER TABLE tasks ADD COLUMN plan_ref TEXT;
+            "#,
+    },
+];
+
+/// Schema state of a database file, as reported by `ralph db migrate --dry-run`.
+#[derive(Debug, Clone)]
+pub struct MigrationPlan {
+    pub current: i32,
+    pub target: i32,
+    /// `(version, description)` of each migration that would run.
+    pub pending: Vec<(i32, &'static str)>,
+}
+
+/// What happened when a database was opened and brought up to date.
+#[derive(Debug, Clone)]
+pub struct MigrationReport {
+    pub from: i32,
+    pub to: i32,
+    pub applied: Vec<(i32, &'static str)>,
+    /// Copy of the database taken before the first migration ran.
+    pub backup: Option<PathBuf>,
+}
+
+/// Open or initialize the database at the given path.
+///
+/// Pending migrations are applied automatically; an existing database is
+/// backed up before it is touched.
+pub fn init_db(path: &str) -> Result<Db> {
+    open_and_migrate(path).map(|(db, _)| db)
+}
+
+/// Open the database and apply pending migrations, reporting what was done.
+pub fn migrate_db(path: &str) -> Result<MigrationReport> {
+    open_and_migrate(path).map(|(_, report)| report)
+}
+
+/// Describe the migrations `init_db` would apply, without modifying the file.
+pub fn plan_migrations(path: &str) -> Result<MigrationPlan> {
+    let current = if Path::new(path).exists() {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open database at {}", path))?;
+        read_version(&conn)?
+    } else {
+        0
+    };
+    check_supported(current)?;
+
+    Ok(MigrationPlan {
+        current,
+        target: SCHEMA_VERSION,
+        pending: pending_after(current),
+    })
+}
+
+fn open_and_migrate(path: &str) -> Result<(Db, MigrationReport)> {
+    // Create parent directories if needed
+    if let Some(parent) = Path::new(path).parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create parent directory for {}", path))?;
+    }
+
+    // Open or create database
+    let conn =
+        Connection::open(path).with_context(|| format!("Failed to open database at {}", path))?;
+
+    // Enable WAL mode
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .context("Failed to enable WAL mode")?;
+
+    // Enable foreign keys
+    conn.pragma_update(None, "foreign_keys", "ON")
+        .context("Failed to enable foreign keys")?;
+
+    // Wait on locks held by other ralph instances sharing this file
+    conn.busy_timeout(BUSY_TIMEOUT)
+        .context("Failed to set busy timeout")?;
+
+    // Check schema version
+    let version = read_version(&conn)?;
+    check_supported(version)?;
+
+    let mut report = MigrationReport {
+        from: version,
+        to: version,
+        applied: Vec::new(),
+        backup: None,
+    };
+
+    if version < SCHEMA_VERSION {
+        // Never migrate a populated database without a copy to fall back on.
+        if version > 0 {
+            report.backup = Some(backup_db(&conn, path, version)?);
+        }
+        migrate(&conn, version, SCHEMA_VERSION)?;
+        report.applied = pending_after(version);
+        report.to = SCHEMA_VERSION;
+    }
+
+    Ok((Db { conn }, report))
+}
+
+/// Read the schema version of an open database.
+///
+/// Databases created before the `schema_version` table existed only carry
+/// `PRAGMA user_version`, so the higher of the two wins.
+fn read_version(conn: &Connection) -> Result<i32> {
+    let user_version: i32 = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .context("Failed to read schema version")?;
+
+    let has_table: bool = conn
+        .query_row(
+            "SELECT count(*) > 0 FROM sqlite_master WHERE type='table' AND name='schema_version'",
+            [],
+            |row| row.get(0),
         )
-        .context("Failed to create schema v5 model_overrides table")?;
+        .context("Failed to inspect schema_version table")?;
+    if !has_table {
+        return Ok(user_version);
     }
 
-    // Set schema version
-    conn.pragma_update(None, "user_version", to_version)
-        .context("Failed to update schema version")?;
+    let recorded: i32 = conn
+        .query_row(
+            "SELECT COALESCE(MAX(version), 0) FROM schema_version",
+            [],
+            |row| row.get(0),
+        )
+        .context("Failed to read schema_version table")?;
+    Ok(recorded.max(user_version))
+}
+
+fn check_supported(version: i32) -> Result<()> {
+    if version > SCHEMA_VERSION {
+        bail!(
+            "Database schema v{} is newer than this ralph supports (v{}); upgrade ralph",
+            version,
+            SCHEMA_VERSION
+        );
+    }
+    Ok(())
+}
+
+fn pending_after(version: i32) -> Vec<(i32, &'static str)> {
+    MIGRATIONS
+        .iter()
+        .filter(|m| m.version > version && m.version <= SCHEMA_VERSION)
+        .map(|m| (m.version, m.description))
+        .collect()
+}
+
+/// Snapshot the database next to the original as `<file>.v<N>-<timestamp>.bak`.
+///
+/// Uses `VACUUM INTO` so the copy includes anything still sitting in the WAL.
+fn backup_db(conn: &Connection, path: &str, version: i32) -> Result<PathBuf> {
+    let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%S");
+    let backup = PathBuf::from(format!("{}.v{}-{}.bak", path, version, stamp));
+    conn.execute("VACUUM INTO ?", [backup.to_string_lossy()])
+        .with_context(|| format!("Failed to back up database to {}", backup.display()))?;
+    Ok(backup)
+}
+
+/// Run migrations from `from_version` to `to_version`.
+///
+/// Each migration runs in its own transaction together with its
+/// `schema_version` row, so an interrupted upgrade resumes where it stopped.
+fn migrate(conn: &Connection, from_version: i32, to_version: i32) -> Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        "#,
+    )
+    .context("Failed to create schema_version table")?;
+
+    // Record migrations applied before the table existed.
+    for m in MIGRATIONS.iter().filter(|m| m.version <= from_version) {
+        conn.execute(
+            "INSERT OR IGNORE INTO schema_version (version, description) VALUES (?, ?)",
+            params![m.version, m.description],
+        )
+        .context("Failed to backfill schema_version")?;
+    }
+
+    for m in MIGRATIONS
+        .iter()
+        .filter(|m| m.version > from_version && m.version <= to_version)
+    {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch(m.sql).with_context(|| {
+            format!("Failed to create schema v{} ({})", m.version, m.description)
+        })?;
+        tx.execute(
+            "INSERT OR REPLACE INTO schema_version (version, description) VALUES (?, ?)",
+            params![m.version, m.description],
+        )?;
+        tx.pragma_update(None, "user_version", m.version)
+            .context("Failed to update schema version")?;
+        tx.commit()
+            .with_context(|| format!("Failed to commit schema v{}", m.version))?;
+    }
 
     Ok(())
 }
@@ -210,6 +701,39 @@ mod tests {
     use super::*;
     use tempfile::NamedTempFile;
 
+    #[test]
+    fn atomically_rolls_back_and_nests() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let db = init_db(temp_file.path().to_str().unwrap())?;
+        let count = |db: &Db| -> Result<i64> {
+            Ok(db
+                .conn()
+                .query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0))?)
+        };
+        let insert = |db: &Db, id: &str| -> Result<()> {
+            db.conn().execute(
+                "INSERT INTO tasks (id, title, created_at, updated_at)
+                 VALUES (?, 'x', datetime('now'), datetime('now'))",
+                [id],
+            )?;
+            Ok(())
+        };
+
+        let failed: Result<()> = db.atomically(|| {
+            insert(&db, "t-1")?;
+            bail!("crash")
+        });
+        assert!(failed.is_err());
+        assert_eq!(count(&db)?, 0);
+
+        // Inside a transaction, the savepoint's writes go with the transaction
+        let tx = db.conn().unchecked_transaction()?;
+        db.atomically(|| insert(&db, "t-2"))?;
+        drop(tx);
+        assert_eq!(count(&db)?, 0);
+        Ok(())
+    }
+
     #[test]
     fn test_fresh_db_creates_tables() -> Result<()> {
         let temp_file = NamedTempFile::new()?;
@@ -227,6 +751,7 @@ mod tests {
         assert!(tables.contains(&"dependencies".to_string()));
         assert!(tables.contains(&"task_logs".to_string()));
         assert!(tables.contains(&"model_overrides".to_string()));
+        assert!(tables.contains(&"task_criteria".to_string()));
 
         Ok(())
     }
@@ -523,4 +1048,173 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_schema_v9_rebuild_keeps_journal_rows_and_fts() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let path = temp_file.path().to_str().unwrap();
+        {
+            let conn = Connection::open(path)?;
+            conn.pragma_update(None, "foreign_keys", "ON")?;
+            migrate(&conn, 0, 8)?;
+            conn.execute(
+                "INSERT INTO journal (run_id, iteration, outcome, notes) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params!["run-old", 1, "done", "rebuilt the parser"],
+            )?;
+            let rejected = conn.execute(
+                "INSERT INTO journal (run_id, iteration, outcome) VALUES ('run-old', 2, 'interrupted')",
+                [],
+            );
+            assert!(rejected.is_err(), "v8 CHECK should reject 'interrupted'");
+        }
+
+        let db = init_db(path)?;
+        let matches: i32 = db.conn().query_row(
+            "SELECT count(*) FROM journal_fts WHERE journal_fts MATCH 'parser'",
+            [],
+            |r| r.get(0),
+        )?;
+        assert_eq!(matches, 1, "FTS index should survive the rebuild");
+
+        for outcome in ["interrupted", "rolled_back", "timeout"] {
+            db.conn().execute(
+                "INSERT INTO journal (run_id, iteration, outcome) VALUES ('run-new', 1, ?1)",
+                [outcome],
+            )?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_schema_v15_backfills_leases_of_agent_claims() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let path = temp_file.path().to_str().unwrap();
+        {
+            let conn = Connection::open(path)?;
+            conn.pragma_update(None, "foreign_keys", "ON")?;
+            migrate(&conn, 0, 14)?;
+            conn.execute_batch(
+                "INSERT INTO tasks (id, title, status, claimed_by, created_at, updated_at) VALUES
+                    ('t-agent', 'Agent', 'in_progress', 'agent-1', '', ''),
+                    ('t-human', 'Human', 'in_progress', 'human:alice', '', ''),
+                    ('t-pending', 'Pending', 'pending', NULL, '', '');",
+            )?;
+        }
+
+        let db = init_db(path)?;
+        let lease = |id: &str| -> Result<Option<String>> {
+            Ok(db.conn().query_row(
+                "SELECT lease_expires_at FROM tasks WHERE id = ?",
+                [id],
+                |r| r.get(0),
+            )?)
+        };
+        assert!(lease("t-agent")?.is_some());
+        assert_eq!(lease("t-human")?, None);
+        assert_eq!(lease("t-pending")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrations_are_ordered_and_end_at_schema_version() {
+        let versions: Vec<i32> = MIGRATIONS.iter().map(|m| m.version).collect();
+        let expected: Vec<i32> = (1..=SCHEMA_VERSION).collect();
+        assert_eq!(versions, expected);
+    }
+
+    #[test]
+    fn test_fresh_db_records_schema_version_rows() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let db = init_db(temp_file.path().to_str().unwrap())?;
+
+        let versions: Vec<i32> = db
+            .conn()
+            .prepare("SELECT version FROM schema_version ORDER BY version")?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        assert_eq!(versions, (1..=SCHEMA_VERSION).collect::<Vec<_>>());
+        Ok(())
+    }
+
+    #[test]
+    fn test_legacy_db_is_backed_up_and_backfilled() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("progress.db");
+        let path = path.to_str().unwrap();
+
+        // A pre-schema_version database only carries PRAGMA user_version.
+        {
+            let conn = Connection::open(path)?;
+            migrate(&conn, 0, 3)?;
+            conn.execute_batch("DROP TABLE schema_version")?;
+            conn.pragma_update(None, "user_version", 3)?;
+        }
+
+        let report = migrate_db(path)?;
+        assert_eq!(report.from, 3);
+        assert_eq!(report.to, SCHEMA_VERSION);
+        assert_eq!(report.applied.first().map(|m| m.0), Some(4));
+
+        let backup = report.backup.expect("backup should be taken");
+        assert!(backup.exists());
+        let old = Connection::open(&backup)?;
+        let old_version: i32 = old.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        assert_eq!(old_version, 3);
+
+        let db = init_db(path)?;
+        let rows: i32 = db
+            .conn()
+            .query_row("SELECT count(*) FROM schema_version", [], |r| r.get(0))?;
+        assert_eq!(rows, SCHEMA_VERSION);
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_migrations_does_not_modify_db() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let path = temp_file.path().to_str().unwrap();
+        {
+            let conn = Connection::open(path)?;
+            migrate(&conn, 0, 2)?;
+        }
+
+        let plan = plan_migrations(path)?;
+        assert_eq!(plan.current, 2);
+        assert_eq!(plan.target, SCHEMA_VERSION);
+        assert_eq!(
+            plan.pending.iter().map(|m| m.0).collect::<Vec<_>>(),
+            (3..=SCHEMA_VERSION).collect::<Vec<_>>()
+        );
+        assert_eq!(plan_migrations(path)?.current, 2);
+
+        let missing = temp_file.path().with_extension("missing");
+        let plan = plan_migrations(missing.to_str().unwrap())?;
+        assert_eq!(plan.current, 0);
+        assert!(!missing.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_newer_schema_is_rejected() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let path = temp_file.path().to_str().unwrap();
+        {
+            let conn = Connection::open(path)?;
+            conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1)?;
+        }
+        assert!(init_db(path).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_up_to_date_db_is_not_backed_up() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let path = temp_file.path().to_str().unwrap();
+        init_db(path)?;
+
+        let report = migrate_db(path)?;
+        assert!(report.applied.is_empty());
+        assert!(report.backup.is_none());
+        Ok(())
+    }
 }
@@ -4,7 +4,9 @@
 
 use anyhow::{bail, Context, Result};
 use rusqlite::params;
-use std::collections::{HashSet, VecDeque};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
 
 use crate::dag::Db;
 
@@ -13,16 +15,25 @@ use crate::dag::Db;
 /// A dependency `(blocker_id, blocked_id)` means `blocked_id` cannot start
 /// until `blocker_id` is done.
 ///
-/// Rejects the dependency if it would create a cycle.
+/// Rejects the dependency if it would create a cycle; the error names the loop.
 pub fn add_dependency(db: &Db, blocker_id: &str, blocked_id: &str) -> Result<()> {
-    // Check if adding this edge would create a cycle
-    if would_create_cycle(db, blocker_id, blocked_id)? {
+    if blocker_id == blocked_id {
         bail!(
-            "Adding dependency {} -> {} would create a cycle",
+            "Adding dependency {} -> {} would create a cycle: a task cannot depend on itself",
             blocker_id,
             blocked_id
         );
     }
+    // An existing path blocked -> ... -> blocker closes a loop with the new edge.
+    if let Some(path) = dependency_path(db, blocked_id, blocker_id)? {
+        bail!(
+            "Adding dependency {} -> {} would create a cycle: {} -> {}",
+            blocker_id,
+            blocked_id,
+            path.join(" -> "),
+            blocked_id
+        );
+    }
 
     // Insert the dependency
     db.conn()
@@ -52,46 +63,145 @@ pub fn remove_dependency(db: &Db, blocker_id: &str, blocked_id: &str) -> Result<
     Ok(())
 }
 
-/// Check if adding a dependency would create a cycle.
+/// Shortest chain of dependency edges from `from` to `to`, both included.
 ///
-/// Returns true if `blocker_id` is reachable from `blocked_id` through existing edges.
-/// This uses BFS to traverse the dependency graph.
-fn would_create_cycle(db: &Db, blocker_id: &str, blocked_id: &str) -> Result<bool> {
-    // If blocker_id == blocked_id, it's a self-loop (caught by CHECK constraint, but we check here too)
-    if blocker_id == blocked_id {
-        return Ok(true);
-    }
-
-    // BFS from blocked_id to see if we can reach blocker_id
+/// Uses BFS over forward edges (blocker -> blocked), remembering each node's
+/// predecessor so the path can be rebuilt.
+fn dependency_path(db: &Db, from: &str, to: &str) -> Result<Option<Vec<String>>> {
+    let mut previous: HashMap<String, String> = HashMap::new();
     let mut visited = HashSet::new();
     let mut queue = VecDeque::new();
-    queue.push_back(blocked_id.to_string());
-    visited.insert(blocked_id.to_string());
+    queue.push_back(from.to_string());
+    visited.insert(from.to_string());
 
+    let mut stmt = db
+        .conn()
+        .prepare("SELECT blocked_id FROM dependencies WHERE blocker_id = ? ORDER BY blocked_id")?;
     while let Some(current) = queue.pop_front() {
-        // Get all tasks that current blocks (i.e., tasks that depend on current)
-        let mut stmt = db
-            .conn()
-            .prepare("SELECT blocked_id FROM dependencies WHERE blocker_id = ?")?;
         let dependents: Vec<String> = stmt
             .query_map([&current], |row| row.get(0))?
             .collect::<Result<_, _>>()?;
 
         for dependent in dependents {
-            // If we found blocker_id, adding the edge would create a cycle
-            if dependent == blocker_id {
-                return Ok(true);
+            if !visited.insert(dependent.clone()) {
+                continue;
+            }
+            previous.insert(dependent.clone(), current.clone());
+            if dependent == to {
+                let mut path = vec![dependent];
+                while let Some(prev) = previous.get(path.last().unwrap()) {
+                    path.push(prev.clone());
+                }
+                path.reverse();
+                return Ok(Some(path));
             }
+            queue.push_back(dependent);
+        }
+    }
+
+    Ok(None)
+}
+
+/// A problem found by [`validate_dag`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DagIssue {
+    /// Tasks that block each other in a loop; the first ID is repeated at the end.
+    Cycle { path: Vec<String> },
+    /// A pending task waiting on a failed blocker, so it can never become ready.
+    BlockedByFailed { task_id: String, blocker_id: String },
+}
+
+impl DagIssue {
+    /// Cycles deadlock the ready query; failed blockers only need attention.
+    pub fn is_error(&self) -> bool {
+        matches!(self, DagIssue::Cycle { .. })
+    }
+}
+
+impl fmt::Display for DagIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DagIssue::Cycle { path } => write!(f, "dependency cycle: {}", path.join(" -> ")),
+            DagIssue::BlockedByFailed {
+                task_id,
+                blocker_id,
+            } => write!(
+                f,
+                "{} is pending but blocked by failed task {}",
+                task_id, blocker_id
+            ),
+        }
+    }
+}
 
-            if !visited.contains(&dependent) {
-                visited.insert(dependent.clone());
-                queue.push_back(dependent);
+/// Audit the whole dependency graph.
+///
+/// Cycles can only exist in databases edited by hand or written by older
+/// versions; they are found by DFS, one report per loop.
+pub fn validate_dag(db: &Db) -> Result<Vec<DagIssue>> {
+    let mut edges: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut stmt = db.conn().prepare(
+        "SELECT blocker_id, blocked_id FROM dependencies ORDER BY blocker_id, blocked_id",
+    )?;
+    for row in stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))? {
+        let (blocker, blocked) = row?;
+        edges.entry(blocker).or_default().push(blocked);
+    }
+
+    let mut issues = Vec::new();
+    let mut done: HashSet<&str> = HashSet::new();
+    let mut seen_cycles: HashSet<Vec<String>> = HashSet::new();
+    for start in edges.keys() {
+        if done.contains(start.as_str()) {
+            continue;
+        }
+        // Iterative DFS; `stack` holds the current path with each node's next edge index.
+        let mut stack: Vec<(&str, usize)> = vec![(start.as_str(), 0)];
+        while let Some((node, next)) = stack.last_mut() {
+            let targets = edges.get(*node).map(Vec::as_slice).unwrap_or_default();
+            let Some(target) = targets.get(*next) else {
+                done.insert(node);
+                stack.pop();
+                continue;
+            };
+            *next += 1;
+            if done.contains(target.as_str()) {
+                continue;
             }
+            if let Some(pos) = stack.iter().position(|(n, _)| *n == target.as_str()) {
+                let mut path: Vec<String> =
+                    stack[pos..].iter().map(|(n, _)| n.to_string()).collect();
+                // Rotate to the smallest ID so the same loop is only reported once.
+                let min = (0..path.len()).min_by_key(|&i| &path[i]).unwrap_or(0);
+                path.rotate_left(min);
+                if seen_cycles.insert(path.clone()) {
+                    path.push(path[0].clone());
+                    issues.push(DagIssue::Cycle { path });
+                }
+                continue;
+            }
+            stack.push((target.as_str(), 0));
         }
     }
 
-    // blocker_id is not reachable from blocked_id, so no cycle
-    Ok(false)
+    let mut stmt = db.conn().prepare(
+        "SELECT t.id, b.id FROM dependencies d
+         JOIN tasks t ON t.id = d.blocked_id
+         JOIN tasks b ON b.id = d.blocker_id
+         WHERE t.status = 'pending' AND b.status = 'failed'
+           AND t.archived_at IS NULL
+         ORDER BY t.id, b.id",
+    )?;
+    for row in stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))? {
+        let (task_id, blocker_id) = row?;
+        issues.push(DagIssue::BlockedByFailed {
+            task_id,
+            blocker_id,
+        });
+    }
+
+    Ok(issues)
 }
 
 #[cfg(test)]
@@ -279,4 +389,72 @@ mod tests {
         assert_eq!(count, 4);
         Ok(())
     }
+
+    #[test]
+    fn test_cycle_error_names_path() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let db = init_db(temp_file.path().to_str().unwrap())?;
+        for id in ["t-aaa111", "t-bbb222", "t-ccc333"] {
+            create_task(&db, id, id)?;
+        }
+        add_dependency(&db, "t-aaa111", "t-bbb222")?;
+        add_dependency(&db, "t-bbb222", "t-ccc333")?;
+
+        let err = add_dependency(&db, "t-ccc333", "t-aaa111").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Adding dependency t-ccc333 -> t-aaa111 would create a cycle: \
+             t-aaa111 -> t-bbb222 -> t-ccc333 -> t-aaa111"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_dag_reports_cycles_and_failed_blockers() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let db = init_db(temp_file.path().to_str().unwrap())?;
+        for id in ["t-aaa111", "t-bbb222", "t-ccc333", "t-ddd444"] {
+            create_task(&db, id, id)?;
+        }
+        assert!(validate_dag(&db)?.is_empty());
+
+        // Bypass add_dependency to plant a cycle, as a hand-edited database might.
+        for (blocker, blocked) in [
+            ("t-aaa111", "t-bbb222"),
+            ("t-bbb222", "t-ccc333"),
+            ("t-ccc333", "t-aaa111"),
+            ("t-ddd444", "t-aaa111"),
+        ] {
+            db.conn().execute(
+                "INSERT INTO dependencies (blocker_id, blocked_id) VALUES (?, ?)",
+                params![blocker, blocked],
+            )?;
+        }
+        db.conn().execute(
+            "UPDATE tasks SET status = 'failed' WHERE id = 't-ddd444'",
+            [],
+        )?;
+
+        let issues = validate_dag(&db)?;
+        assert_eq!(
+            issues,
+            vec![
+                DagIssue::Cycle {
+                    path: vec![
+                        "t-aaa111".to_string(),
+                        "t-bbb222".to_string(),
+                        "t-ccc333".to_string(),
+                        "t-aaa111".to_string(),
+                    ],
+                },
+                DagIssue::BlockedByFailed {
+                    task_id: "t-aaa111".to_string(),
+                    blocker_id: "t-ddd444".to_string(),
+                },
+            ]
+        );
+        assert!(issues[0].is_error());
+        assert!(!issues[1].is_error());
+        Ok(())
+    }
 }
@@ -0,0 +1,160 @@
+//! Optional frontmatter at the top of a task description.
+//!
+//! A description may open with a `---` block of simple YAML: keys with an
+//! inline list (`[a, b]`), a block list (`- a` lines) or a single value.
+//!
+//! ```text
+//! ---
+//! criteria:
+//!   - GET /orders/{id} returns 404 for unknown ids
+//! files: [src/api/orders.rs, src/db/orders.rs]
+//! links: https://example.com/issues/42
+//! labels: [api, backend]
+//! ---
+//! Free-text description.
+//! ```
+//!
+//! The description is stored verbatim; the block is parsed when read.
+//! Unknown keys are ignored. A block containing anything but keys, list
+//! items, blank lines and `#` comments is not frontmatter, and the whole
+//! description is treated as free text.
+
+use serde::Serialize;
+
+/// Structured fields from a description's frontmatter.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TaskFrontmatter {
+    /// Acceptance criteria (`criteria` or `acceptance_criteria`).
+    pub criteria: Vec<String>,
+    /// Files the task is expected to touch (`files` or `affected_files`).
+    pub files: Vec<String>,
+    pub links: Vec<String>,
+    /// Lowercased labels; merged with `task add --label` ones by
+    /// [`crate::dag::task_labels`].
+    pub labels: Vec<String>,
+}
+
+impl TaskFrontmatter {
+    fn field(&mut self, key: &str) -> Option<&mut Vec<String>> {
+        match key {
+            "criteria" | "acceptance_criteria" => Some(&mut self.criteria),
+            "files" | "affected_files" => Some(&mut self.files),
+            "links" => Some(&mut self.links),
+            "labels" => Some(&mut self.labels),
+            _ => None,
+        }
+    }
+}
+
+/// Split `description` into its frontmatter and the free-text body. Without
+/// a valid frontmatter block the fields are empty and the body is the whole
+/// description.
+pub fn parse_frontmatter(description: &str) -> (TaskFrontmatter, &str) {
+    parse_block(description).unwrap_or_else(|| (TaskFrontmatter::default(), description))
+}
+
+fn parse_block(description: &str) -> Option<(TaskFrontmatter, &str)> {
+    let text = description.trim_start();
+    let rest = text.strip_prefix("---")?;
+    let rest = rest
+        .strip_prefix("\r\n")
+        .or_else(|| rest.strip_prefix('\n'))?;
+
+    let mut block_end = None;
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            block_end = Some((offset, offset + line.len()));
+            break;
+        }
+        offset += line.len();
+    }
+    let (end, body_start) = block_end?;
+
+    let mut meta = TaskFrontmatter::default();
+    let mut current: Option<String> = None;
+    for line in rest[..end].lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some(item) = trimmed.strip_prefix('-') {
+            let key = current.as_deref()?;
+            push_value(&mut meta, key, item);
+            continue;
+        }
+        let (key, value) = trimmed.split_once(':')?;
+        let key = key.trim().to_ascii_lowercase();
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return None;
+        }
+        let value = value.trim();
+        if let Some(list) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            for item in list.split(',') {
+                push_value(&mut meta, &key, item);
+            }
+        } else if !value.is_empty() {
+            push_value(&mut meta, &key, value);
+        }
+        current = Some(key);
+    }
+
+    let body = rest[body_start..].trim_start_matches(['\r', '\n']);
+    Some((meta, body))
+}
+
+fn push_value(meta: &mut TaskFrontmatter, key: &str, raw: &str) {
+    let value = raw.trim().trim_matches(|c| c == '"' || c == '\'').trim();
+    if value.is_empty() {
+        return;
+    }
+    let value = if key == "labels" {
+        value.to_lowercase()
+    } else {
+        value.to_string()
+    };
+    if let Some(field) = meta.field(key) {
+        if !field.contains(&value) {
+            field.push(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_inline_block_and_scalar_values() {
+        let description = "---\ncriteria:\n  - Returns 404 for unknown ids\n  - \"Logs the request\"\n\
+                           files: [src/api.rs, 'src/db.rs']\nlinks: https://example.com/42\n\
+                           labels: [API, backend]\nowner: sam # ignored\n---\n\nAdd the endpoint.\n";
+        let (meta, body) = parse_frontmatter(description);
+        assert_eq!(
+            meta.criteria,
+            ["Returns 404 for unknown ids", "Logs the request"]
+        );
+        assert_eq!(meta.files, ["src/api.rs", "src/db.rs"]);
+        assert_eq!(meta.links, ["https://example.com/42"]);
+        assert_eq!(meta.labels, ["api", "backend"]);
+        assert_eq!(body, "Add the endpoint.\n");
+    }
+
+    #[test]
+    fn text_without_a_valid_block_is_all_body() {
+        for text in [
+            "Plain description",
+            "---\nlabels: [a]\nno closing line",
+            "---\nThis is prose, not: frontmatter at all\n---\nbody",
+            "Intro\n---\nlabels: [a]\n---\n",
+        ] {
+            let (meta, body) = parse_frontmatter(text);
+            assert_eq!(meta, TaskFrontmatter::default(), "{text:?}");
+            assert_eq!(body, text);
+        }
+    }
+}
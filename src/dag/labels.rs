@@ -0,0 +1,135 @@
+//! Task labels: a lightweight taxonomy orthogonal to features.
+//!
+//! Labels come from two places: the `task_labels` table (`task add --label`,
+//! `task update --label/--unlabel`) and the `labels:` key of a description's
+//! frontmatter. Both are lowercased; [`task_labels`] merges them, and that
+//! union is what `task list --label` filters on and what knowledge matching
+//! and the prompt see.
+
+use anyhow::{Context, Result};
+use rusqlite::params;
+use std::collections::HashMap;
+
+use crate::dag::{Db, Task};
+
+/// Trim and lowercase `labels`, dropping empty ones and duplicates.
+pub fn normalize_labels(labels: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = labels
+        .iter()
+        .map(|l| l.trim().to_lowercase())
+        .filter(|l| !l.is_empty())
+        .collect();
+    normalized.sort();
+    normalized.dedup();
+    normalized
+}
+
+/// Attach `labels` to `task_id`; labels it already has are kept once.
+pub fn add_labels(db: &Db, task_id: &str, labels: &[String]) -> Result<()> {
+    for label in normalize_labels(labels) {
+        db.conn()
+            .execute(
+                "INSERT OR IGNORE INTO task_labels (task_id, label) VALUES (?, ?)",
+                params![task_id, label],
+            )
+            .with_context(|| format!("Failed to label task '{task_id}' with '{label}'"))?;
+    }
+    Ok(())
+}
+
+/// Detach `labels` from `task_id`. Frontmatter labels live in the
+/// description and are not touched.
+pub fn remove_labels(db: &Db, task_id: &str, labels: &[String]) -> Result<()> {
+    for label in normalize_labels(labels) {
+        db.conn()
+            .execute(
+                "DELETE FROM task_labels WHERE task_id = ? AND label = ?",
+                params![task_id, label],
+            )
+            .with_context(|| format!("Failed to remove label '{label}' from '{task_id}'"))?;
+    }
+    Ok(())
+}
+
+/// Labels stored for `task_id` in the table, sorted.
+pub fn get_task_labels(db: &Db, task_id: &str) -> Result<Vec<String>> {
+    let mut stmt = db
+        .conn()
+        .prepare("SELECT label FROM task_labels WHERE task_id = ? ORDER BY label")?;
+    let labels = stmt
+        .query_map([task_id], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()
+        .context("Failed to get task labels")?;
+    Ok(labels)
+}
+
+/// Stored labels of every task, for listing many tasks with one query.
+pub fn labels_by_task(db: &Db) -> Result<HashMap<String, Vec<String>>> {
+    let mut stmt = db
+        .conn()
+        .prepare("SELECT task_id, label FROM task_labels ORDER BY task_id, label")?;
+    let mut labels: HashMap<String, Vec<String>> = HashMap::new();
+    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    for row in rows {
+        let (task_id, label): (String, String) = row.context("Failed to get task labels")?;
+        labels.entry(task_id).or_default().push(label);
+    }
+    Ok(labels)
+}
+
+/// All of `task`'s labels: `stored` (from the table) and its frontmatter's,
+/// sorted and deduplicated.
+pub fn task_labels(task: &Task, stored: &[String]) -> Vec<String> {
+    let mut labels = stored.to_vec();
+    labels.extend(task.frontmatter().0.labels);
+    normalize_labels(&labels)
+}
+
+/// All labels of each of `tasks`, keyed by task ID.
+pub fn labels_for(db: &Db, tasks: &[Task]) -> Result<HashMap<String, Vec<String>>> {
+    let mut stored = labels_by_task(db)?;
+    Ok(tasks
+        .iter()
+        .map(|t| {
+            let labels = task_labels(t, &stored.remove(&t.id).unwrap_or_default());
+            (t.id.clone(), labels)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag;
+
+    #[test]
+    fn labels_merge_table_and_frontmatter() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let db = dag::open_db(temp_file.path().to_str().unwrap()).unwrap();
+        let task = dag::create_task(
+            &db,
+            "orders endpoint",
+            Some("---\nlabels: [api, Backend]\n---\nAdd it."),
+            None,
+            0,
+        )
+        .unwrap();
+
+        let labels = ["Risky".to_string(), " backend ".to_string(), String::new()];
+        add_labels(&db, &task.id, &labels).unwrap();
+        add_labels(&db, &task.id, &["risky".to_string()]).unwrap();
+        assert_eq!(
+            get_task_labels(&db, &task.id).unwrap(),
+            ["backend", "risky"]
+        );
+
+        let all = labels_for(&db, std::slice::from_ref(&task)).unwrap();
+        assert_eq!(all[&task.id], ["api", "backend", "risky"]);
+
+        remove_labels(&db, &task.id, &["RISKY".to_string()]).unwrap();
+        assert_eq!(get_task_labels(&db, &task.id).unwrap(), ["backend"]);
+
+        dag::delete_task(&db, &task.id).unwrap();
+        assert!(labels_by_task(&db).unwrap().is_empty());
+    }
+}
@@ -2,34 +2,63 @@
 //!
 //! Manages task dependencies and execution state using SQLite backend.
 
+mod artifacts;
+mod bulk;
+mod criteria;
+mod critical_path;
 mod crud;
 mod db;
 mod dependencies;
+mod frontmatter;
 mod ids;
+mod labels;
+mod recovery;
+mod scheduler;
 mod tasks;
 mod transitions;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde::Serialize;
 
+#[allow(unused_imports)]
+pub use artifacts::{add_artifact, get_task_artifacts, Artifact};
+pub use bulk::{apply_bulk, parse_task_ids, select_tasks, BulkOp};
+#[allow(unused_imports)]
+pub use criteria::{
+    add_criteria, get_task_criteria, replace_criteria, reset_criteria, set_criterion_status,
+    Criterion,
+};
+pub use critical_path::{critical_path, CriticalPathReport};
+#[cfg(test)]
+pub use crud::create_test_task;
 #[allow(unused_imports)]
 pub use crud::{
     add_log, create_task, create_task_with_feature, delete_task, delete_tasks_for_feature,
-    get_task, get_task_tree, update_task, CreateTaskParams, TaskUpdate,
+    get_task, get_task_tree, normalize_cwd, set_completed_by, set_task_cwd, set_task_parent,
+    set_task_plan_ref, update_task, CreateTaskParams, TaskUpdate,
 };
 #[allow(unused_imports)]
 pub use crud::{
     get_all_tasks, get_all_tasks_for_feature, get_task_blockers, get_task_logs,
     get_tasks_blocked_by, LogEntry,
 };
-pub use db::{init_db, Db};
 #[allow(unused_imports)]
-pub use dependencies::{add_dependency, remove_dependency};
+pub use db::{init_db, migrate_db, plan_migrations, Db, MigrationPlan, MigrationReport};
+#[allow(unused_imports)]
+pub use dependencies::{add_dependency, remove_dependency, validate_dag, DagIssue};
+pub use frontmatter::{parse_frontmatter, TaskFrontmatter};
 #[allow(unused_imports)]
 pub use ids::{generate_and_insert_task_id, generate_feature_id, generate_task_id};
+pub use labels::{
+    add_labels, get_task_labels, labels_for, normalize_labels, remove_labels, task_labels,
+};
+pub use recovery::recover_partial_writes;
+pub use scheduler::{Scheduler, DEFAULT_AGING_SECS};
 #[allow(unused_imports)]
 pub use tasks::{compute_parent_status, get_task_status};
-pub use transitions::{force_complete_task, force_fail_task, force_reset_task, AutoTransition};
+pub use transitions::{
+    force_complete_task, force_fail_task, force_reset_task, set_task_status, AutoTransition,
+};
 
 /// A task in the DAG.
 #[derive(Debug, Clone, Serialize)]
@@ -49,6 +78,26 @@ pub struct Task {
     pub created_at: String,
     pub updated_at: String,
     pub claimed_by: Option<String>,
+    /// Task whose agent queued this one with a `<task-add>` sigil.
+    pub discovered_by: Option<String>,
+    /// External issue this task was imported from, e.g. `linear:ENG-42`.
+    pub source_ref: Option<String>,
+    /// Working directory relative to the project root (monorepo packages).
+    pub cwd: Option<String>,
+    /// Who created the task: a user name, or `user via agent` for tasks a run created.
+    pub created_by: Option<String>,
+    /// Who completed the task, in the same form as `created_by`.
+    pub completed_by: Option<String>,
+    /// Anchor of the plan section the task was derived from.
+    pub plan_ref: Option<String>,
+}
+
+impl Task {
+    /// Structured fields from the description's frontmatter, and the
+    /// free-text rest of the description.
+    pub fn frontmatter(&self) -> (TaskFrontmatter, &str) {
+        parse_frontmatter(&self.description)
+    }
 }
 
 /// Task counts summary.
@@ -69,7 +118,8 @@ pub fn open_db(path: &str) -> Result<Db> {
 ///
 /// Expects columns in order: id, title, description, status, parent_id, feature_id,
 /// task_type, priority, retry_count, max_retries, verification_status, created_at,
-/// updated_at, claimed_by
+/// updated_at, claimed_by, discovered_by, source_ref, cwd, created_by, completed_by,
+/// plan_ref
 pub(crate) fn task_from_row(row: &rusqlite::Row) -> rusqlite::Result<Task> {
     Ok(Task {
         id: row.get(0)?,
@@ -88,16 +138,35 @@ pub(crate) fn task_from_row(row: &rusqlite::Row) -> rusqlite::Result<Task> {
         created_at: row.get(11)?,
         updated_at: row.get(12)?,
         claimed_by: row.get(13)?,
+        discovered_by: row.get(14)?,
+        source_ref: row.get(15)?,
+        cwd: row.get(16)?,
+        created_by: row.get(17)?,
+        completed_by: row.get(18)?,
+        plan_ref: row.get(19)?,
     })
 }
 
 /// The standard column list for task queries.
-const TASK_COLUMNS: &str = "id, title, description, status, parent_id, feature_id, task_type, priority, retry_count, max_retries, verification_status, created_at, updated_at, claimed_by";
+const TASK_COLUMNS: &str = "id, title, description, status, parent_id, feature_id, task_type, priority, retry_count, max_retries, verification_status, created_at, updated_at, claimed_by, discovered_by, source_ref, cwd, created_by, completed_by, plan_ref";
+
+/// How long a claim holds without renewal. A running ralph renews its claims
+/// every [`CLAIM_RENEW_INTERVAL`], so only the claims of a process that died
+/// expire.
+pub const CLAIM_LEASE_SECS: u64 = 300;
+
+/// How often a running ralph renews its claim leases.
+pub const CLAIM_RENEW_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Tasks (aliased `t`) that may be claimed: pending ones, and in-progress ones
+/// whose claim lease has expired.
+const CLAIMABLE: &str = "(t.status = 'pending'
+    OR (t.status = 'in_progress' AND t.lease_expires_at < datetime('now')))";
 
 /// Get all tasks that are ready to execute.
 pub fn get_ready_tasks(db: &Db) -> Result<Vec<Task>> {
     // A task is ready when:
-    // 1. Status is 'pending'
+    // 1. Status is 'pending', or 'in_progress' with an expired claim lease
     // 2. All blockers have status 'done'
     // 3. Parent (if any) is not 'failed'
     // 4. Task is a leaf node (no children)
@@ -107,7 +176,8 @@ pub fn get_ready_tasks(db: &Db) -> Result<Vec<Task>> {
         r#"
         SELECT DISTINCT t.{cols}
         FROM tasks t
-        WHERE t.status = 'pending'
+        WHERE {claimable}
+          AND t.archived_at IS NULL
           -- Must be a leaf node (no children)
           AND NOT EXISTS (
               SELECT 1 FROM tasks c WHERE c.parent_id = t.id
@@ -130,6 +200,7 @@ pub fn get_ready_tasks(db: &Db) -> Result<Vec<Task>> {
         ORDER BY t.priority ASC, t.created_at ASC
         "#,
         cols = TASK_COLUMNS.replace(", ", ", t."),
+        claimable = CLAIMABLE,
     );
     let mut stmt = db.conn().prepare(&query)?;
 
@@ -142,15 +213,19 @@ pub fn get_ready_tasks(db: &Db) -> Result<Vec<Task>> {
 
 /// Get task counts (total, ready, done, blocked).
 pub fn get_task_counts(db: &Db) -> Result<TaskCounts> {
-    let total: usize = db
-        .conn()
-        .query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0))?;
+    let total: usize = db.conn().query_row(
+        "SELECT COUNT(*) FROM tasks WHERE archived_at IS NULL",
+        [],
+        |row| row.get(0),
+    )?;
 
     let ready: usize = db.conn().query_row(
-        r#"
+        &format!(
+            r#"
         SELECT COUNT(*)
         FROM tasks t
-        WHERE t.status = 'pending'
+        WHERE {claimable}
+          AND t.archived_at IS NULL
           AND NOT EXISTS (
               SELECT 1 FROM tasks c WHERE c.parent_id = t.id
           )
@@ -168,18 +243,20 @@ pub fn get_task_counts(db: &Db) -> Result<TaskCounts> {
                 AND b.status != 'done'
           )
         "#,
+            claimable = CLAIMABLE,
+        ),
         [],
         |row| row.get(0),
     )?;
 
     let done: usize = db.conn().query_row(
-        "SELECT COUNT(*) FROM tasks WHERE status = 'done'",
+        "SELECT COUNT(*) FROM tasks WHERE status = 'done' AND archived_at IS NULL",
         [],
         |row| row.get(0),
     )?;
 
     let blocked: usize = db.conn().query_row(
-        "SELECT COUNT(*) FROM tasks WHERE status = 'blocked'",
+        "SELECT COUNT(*) FROM tasks WHERE status = 'blocked' AND archived_at IS NULL",
         [],
         |row| row.get(0),
     )?;
@@ -192,48 +269,104 @@ pub fn get_task_counts(db: &Db) -> Result<TaskCounts> {
     })
 }
 
-/// Claim a task for execution by an agent.
-pub fn claim_task(db: &Db, task_id: &str, agent_id: &str) -> Result<()> {
-    // Transition to in_progress and set claimed_by atomically
-    // pending→in_progress produces no auto-transitions, so discard the vec
-    let _transitions = transitions::set_task_status(db.conn(), task_id, "in_progress")?;
-    db.conn().execute(
-        "UPDATE tasks SET claimed_by = ? WHERE id = ?",
+/// Claim a pending task for execution by an agent.
+///
+/// The claim is a single conditional `UPDATE`, so when several ralph instances
+/// share one database only one of them gets the task. It holds for
+/// [`CLAIM_LEASE_SECS`] unless renewed with [`renew_claims`]; an expired claim
+/// can be taken over by any agent. Returns `false` when the task was already
+/// claimed or is no longer pending.
+pub fn claim_task(db: &Db, task_id: &str, agent_id: &str) -> Result<bool> {
+    let claimed = db.conn().execute(
+        "UPDATE tasks SET status = 'in_progress', claimed_by = ?1, updated_at = datetime('now'),
+                lease_expires_at = datetime('now', ?3)
+         WHERE id = ?2
+           AND ((status = 'pending' AND claimed_by IS NULL)
+                OR (status = 'in_progress' AND lease_expires_at < datetime('now')))",
+        rusqlite::params![agent_id, task_id, lease_modifier()],
+    )?;
+    if claimed == 1 {
+        return Ok(true);
+    }
+    // Distinguish a lost race from a bad request.
+    let status: String = db
+        .conn()
+        .query_row("SELECT status FROM tasks WHERE id = ?", [task_id], |row| {
+            row.get(0)
+        })
+        .with_context(|| format!("Task {} not found", task_id))?;
+    match status.as_str() {
+        "pending" | "in_progress" => Ok(false),
+        other => Err(anyhow::anyhow!(
+            "Invalid status transition from '{}' to 'in_progress'",
+            other
+        )),
+    }
+}
+
+/// Claim a pending task for a person working it by hand (`ralph task next
+/// --claim`). Unlike [`claim_task`] the claim has no lease, so runs leave the
+/// task alone until it is marked done, failed or reset. Returns `false` when
+/// someone else holds the task.
+pub fn claim_task_manually(db: &Db, task_id: &str, agent_id: &str) -> Result<bool> {
+    let claimed = db.conn().execute(
+        "UPDATE tasks SET status = 'in_progress', claimed_by = ?1, updated_at = datetime('now'),
+                lease_expires_at = NULL
+         WHERE id = ?2
+           AND ((status = 'pending' AND claimed_by IS NULL)
+                OR (status = 'in_progress' AND lease_expires_at < datetime('now')))",
         rusqlite::params![agent_id, task_id],
     )?;
-    Ok(())
+    Ok(claimed == 1)
+}
+
+/// Extend the lease of every task `agent_id` holds. Returns how many were renewed.
+pub fn renew_claims(db: &Db, agent_id: &str) -> Result<usize> {
+    Ok(db.conn().execute(
+        "UPDATE tasks SET lease_expires_at = datetime('now', ?1)
+         WHERE claimed_by = ?2 AND status = 'in_progress'",
+        rusqlite::params![lease_modifier(), agent_id],
+    )?)
+}
+
+fn lease_modifier() -> String {
+    format!("+{CLAIM_LEASE_SECS} seconds")
 }
 
 /// Mark a task as completed.
 pub fn complete_task(db: &Db, task_id: &str) -> Result<Vec<AutoTransition>> {
-    // Transition to done (auto-transitions handled in set_task_status)
-    let transitions = transitions::set_task_status(db.conn(), task_id, "done")?;
-    // Clear claimed_by
-    db.conn()
-        .execute("UPDATE tasks SET claimed_by = NULL WHERE id = ?", [task_id])?;
-    Ok(transitions)
+    db.atomically(|| {
+        // Transition to done (auto-transitions handled in set_task_status)
+        let transitions = transitions::set_task_status(db.conn(), task_id, "done")?;
+        // Clear claimed_by
+        db.conn()
+            .execute("UPDATE tasks SET claimed_by = NULL WHERE id = ?", [task_id])?;
+        Ok(transitions)
+    })
 }
 
 /// Mark a task as failed.
 pub fn fail_task(db: &Db, task_id: &str, reason: &str) -> Result<Vec<AutoTransition>> {
-    // Transition to failed (auto-transitions handled in set_task_status)
-    let transitions = transitions::set_task_status(db.conn(), task_id, "failed")?;
-    // Clear claimed_by
-    db.conn()
-        .execute("UPDATE tasks SET claimed_by = NULL WHERE id = ?", [task_id])?;
-    // Log the failure reason
-    let timestamp = chrono::Utc::now().to_rfc3339();
-    db.conn().execute(
-        "INSERT INTO task_logs (task_id, message, timestamp) VALUES (?, ?, ?)",
-        rusqlite::params![task_id, reason, timestamp],
-    )?;
-    Ok(transitions)
+    db.atomically(|| {
+        // Transition to failed (auto-transitions handled in set_task_status)
+        let transitions = transitions::set_task_status(db.conn(), task_id, "failed")?;
+        // Clear claimed_by
+        db.conn()
+            .execute("UPDATE tasks SET claimed_by = NULL WHERE id = ?", [task_id])?;
+        // Log the failure reason
+        let timestamp = chrono::Utc::now().to_rfc3339();
+        db.conn().execute(
+            "INSERT INTO task_logs (task_id, message, timestamp) VALUES (?, ?, ?)",
+            rusqlite::params![task_id, reason, timestamp],
+        )?;
+        Ok(transitions)
+    })
 }
 
 /// Check if all DAG tasks are resolved (done or failed).
 pub fn all_resolved(db: &Db) -> Result<bool> {
     let unresolved: i64 = db.conn().query_row(
-        "SELECT COUNT(*) FROM tasks WHERE status NOT IN ('done', 'failed')",
+        "SELECT COUNT(*) FROM tasks WHERE status NOT IN ('done', 'failed') AND archived_at IS NULL",
         [],
         |row| row.get(0),
     )?;
@@ -246,8 +379,9 @@ pub fn get_ready_tasks_for_feature(db: &Db, feature_id: &str) -> Result<Vec<Task
         r#"
         SELECT DISTINCT t.{cols}
         FROM tasks t
-        WHERE t.status = 'pending'
+        WHERE {claimable}
           AND t.feature_id = ?
+          AND t.archived_at IS NULL
           AND NOT EXISTS (
               SELECT 1 FROM tasks c WHERE c.parent_id = t.id
           )
@@ -267,6 +401,7 @@ pub fn get_ready_tasks_for_feature(db: &Db, feature_id: &str) -> Result<Vec<Task
         ORDER BY t.priority ASC, t.created_at ASC
         "#,
         cols = TASK_COLUMNS.replace(", ", ", t."),
+        claimable = CLAIMABLE,
     );
     let mut stmt = db.conn().prepare(&query)?;
 
@@ -324,14 +459,46 @@ pub fn get_feature_task_counts(db: &Db, feature_id: &str) -> Result<TaskCounts>
 
 /// Retry a failed task: transition back to pending and increment retry_count.
 pub fn retry_task(db: &Db, task_id: &str) -> Result<Vec<AutoTransition>> {
-    // Transition failed -> pending (typically produces no auto-transitions)
-    let transitions = transitions::set_task_status(db.conn(), task_id, "pending")?;
-    // Increment retry_count and set verification_status to failed
-    db.conn().execute(
-        "UPDATE tasks SET retry_count = retry_count + 1, verification_status = 'failed', claimed_by = NULL WHERE id = ?",
-        [task_id],
+    db.atomically(|| {
+        // Transition failed -> pending (typically produces no auto-transitions)
+        let transitions = transitions::set_task_status(db.conn(), task_id, "pending")?;
+        // Increment retry_count and set verification_status to failed
+        db.conn().execute(
+            "UPDATE tasks SET retry_count = retry_count + 1, verification_status = 'failed', claimed_by = NULL WHERE id = ?",
+            [task_id],
+        )?;
+        Ok(transitions)
+    })
+}
+
+/// Return an in-progress task to pending after an attempt that did not finish
+/// (e.g. an iteration timeout), counting it against `retry_count`.
+pub fn requeue_task(db: &Db, task_id: &str) -> Result<()> {
+    db.atomically(|| {
+        release_claim(db, task_id)?;
+        db.conn().execute(
+            "UPDATE tasks SET retry_count = retry_count + 1 WHERE id = ?",
+            [task_id],
+        )?;
+        Ok(())
+    })
+}
+
+/// Requeue every in-progress task `agent_id` holds, as [`requeue_task`]
+/// does for one. For a run cut short (e.g. by `--timeout`), whose claims
+/// would otherwise stay until their lease expires. Returns the task IDs.
+pub fn requeue_claimed(db: &Db, agent_id: &str) -> Result<Vec<String>> {
+    let mut stmt = db.conn().prepare(
+        "SELECT id FROM tasks WHERE claimed_by = ? AND status = 'in_progress' ORDER BY id",
     )?;
-    Ok(transitions)
+    let ids: Vec<String> = stmt
+        .query_map([agent_id], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    drop(stmt);
+    for id in &ids {
+        requeue_task(db, id)?;
+    }
+    Ok(ids)
 }
 
 /// Release the claim on a task (set to pending if in_progress).
@@ -344,10 +511,13 @@ pub fn release_claim(db: &Db, task_id: &str) -> Result<()> {
             })?;
 
     if status == "in_progress" {
-        // in_progress→pending produces no auto-transitions, so discard the vec
-        let _transitions = transitions::set_task_status(db.conn(), task_id, "pending")?;
-        db.conn()
-            .execute("UPDATE tasks SET claimed_by = NULL WHERE id = ?", [task_id])?;
+        db.atomically(|| {
+            // in_progress→pending produces no auto-transitions, so discard the vec
+            let _transitions = transitions::set_task_status(db.conn(), task_id, "pending")?;
+            db.conn()
+                .execute("UPDATE tasks SET claimed_by = NULL WHERE id = ?", [task_id])?;
+            Ok(())
+        })?;
     }
 
     Ok(())
@@ -374,6 +544,12 @@ mod tests {
             created_at: "2024-01-01T00:00:00Z".to_string(),
             updated_at: "2024-01-01T00:00:00Z".to_string(),
             claimed_by: None,
+            discovered_by: None,
+            source_ref: None,
+            cwd: None,
+            created_by: None,
+            completed_by: None,
+            plan_ref: None,
         };
         assert_eq!(task.id, "t-abc123");
         assert_eq!(task.title, "Test task");
@@ -530,4 +706,106 @@ mod tests {
         assert_eq!(ready[0].id, "t-older");
         assert_eq!(ready[1].id, "t-newer");
     }
+
+    #[test]
+    fn claim_is_exclusive_across_connections() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let path = temp_file.path().to_str().unwrap();
+        let db = init_db(path).unwrap();
+        let other = open_db(path).unwrap();
+
+        create_task(&db, "t-shared", "Shared", None, 0);
+
+        assert!(claim_task(&db, "t-shared", "agent-a").unwrap());
+        assert!(!claim_task(&other, "t-shared", "agent-b").unwrap());
+        assert_eq!(
+            crud::get_task(&other, "t-shared")
+                .unwrap()
+                .claimed_by
+                .as_deref(),
+            Some("agent-a")
+        );
+
+        complete_task(&db, "t-shared").unwrap();
+        assert!(claim_task(&other, "t-shared", "agent-b").is_err());
+        assert!(claim_task(&other, "t-missing", "agent-b").is_err());
+    }
+
+    #[test]
+    fn expired_claim_lease_is_reclaimable() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let db = init_db(temp_file.path().to_str().unwrap()).unwrap();
+        create_task(&db, "t-crashed", "Crashed", None, 0);
+        let expire = || {
+            db.conn()
+                .execute(
+                    "UPDATE tasks SET lease_expires_at = datetime('now', '-1 seconds')",
+                    [],
+                )
+                .unwrap()
+        };
+
+        assert!(claim_task(&db, "t-crashed", "agent-a").unwrap());
+        assert!(get_ready_tasks(&db).unwrap().is_empty());
+        assert!(!claim_task(&db, "t-crashed", "agent-b").unwrap());
+
+        // Renewal keeps a live claim
+        expire();
+        assert_eq!(renew_claims(&db, "agent-a").unwrap(), 1);
+        assert!(get_ready_tasks(&db).unwrap().is_empty());
+
+        expire();
+        let ready = get_ready_tasks(&db).unwrap();
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].claimed_by.as_deref(), Some("agent-a"));
+        assert_eq!(get_task_counts(&db).unwrap().ready, 1);
+        assert!(claim_task(&db, "t-crashed", "agent-b").unwrap());
+        let task = crud::get_task(&db, "t-crashed").unwrap();
+        assert_eq!(task.status, "in_progress");
+        assert_eq!(task.claimed_by.as_deref(), Some("agent-b"));
+        assert_eq!(renew_claims(&db, "agent-a").unwrap(), 0);
+    }
+
+    #[test]
+    fn manual_claim_has_no_lease() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let db = init_db(temp_file.path().to_str().unwrap()).unwrap();
+        create_task(&db, "t-manual", "Manual", None, 0);
+
+        assert!(claim_task_manually(&db, "t-manual", "human:alice").unwrap());
+        db.conn()
+            .execute(
+                "UPDATE tasks SET updated_at = datetime('now', '-1 days')",
+                [],
+            )
+            .unwrap();
+        assert!(get_ready_tasks(&db).unwrap().is_empty());
+        assert!(!claim_task(&db, "t-manual", "agent-a").unwrap());
+        assert!(!claim_task_manually(&db, "t-manual", "human:bob").unwrap());
+        assert_eq!(
+            crud::get_task(&db, "t-manual")
+                .unwrap()
+                .claimed_by
+                .as_deref(),
+            Some("human:alice")
+        );
+    }
+
+    #[test]
+    fn requeue_claimed_releases_only_own_tasks() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let db = init_db(temp_file.path().to_str().unwrap()).unwrap();
+        create_task(&db, "t-mine", "Mine", None, 0);
+        create_task(&db, "t-theirs", "Theirs", None, 0);
+        assert!(claim_task(&db, "t-mine", "agent-a").unwrap());
+        assert!(claim_task(&db, "t-theirs", "agent-b").unwrap());
+
+        assert_eq!(requeue_claimed(&db, "agent-a").unwrap(), vec!["t-mine"]);
+        let mine = crud::get_task(&db, "t-mine").unwrap();
+        assert_eq!(mine.status, "pending");
+        assert_eq!(mine.claimed_by, None);
+        assert_eq!(mine.retry_count, 1);
+        let theirs = crud::get_task(&db, "t-theirs").unwrap();
+        assert_eq!(theirs.claimed_by.as_deref(), Some("agent-b"));
+    }
 }
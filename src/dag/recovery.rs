@@ -0,0 +1,111 @@
+//! Startup repair of task state a crash left half-written.
+//!
+//! Completing, failing and releasing a task each take several writes. They
+//! now commit together, but a database written by an older Ralph, or by a
+//! process killed between statements, can still hold the pieces:
+//!
+//! - a done or failed task still claimed: its auto-transitions (unblocking
+//!   dependents, finishing parents and features) may never have run
+//! - a pending task still claimed, which no agent can claim again
+//! - an in-progress leaf task with no owner, which no lease will ever expire
+
+use anyhow::Result;
+
+use super::{transitions, Db};
+
+/// Repair half-applied completions, failures and releases in one
+/// transaction. Returns a line per repaired task.
+pub fn recover_partial_writes(db: &Db) -> Result<Vec<String>> {
+    let tx = db.conn().unchecked_transaction()?;
+    let mut repaired = Vec::new();
+
+    let mut stmt = db.conn().prepare(
+        "SELECT id, status FROM tasks
+         WHERE claimed_by IS NOT NULL AND status IN ('done', 'failed', 'pending')
+         ORDER BY id",
+    )?;
+    let claimed: Vec<(String, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+    drop(stmt);
+    for (id, status) in claimed {
+        db.conn()
+            .execute("UPDATE tasks SET claimed_by = NULL WHERE id = ?", [&id])?;
+        let transitions = transitions::auto_transitions(db.conn(), &id, &status)?;
+        repaired.push(if transitions.is_empty() {
+            format!("{id}: cleared the claim left on a {status} task")
+        } else {
+            format!(
+                "{id}: cleared the claim left on a {status} task, applied {} missed auto-transition(s)",
+                transitions.len()
+            )
+        });
+    }
+
+    let mut stmt = db.conn().prepare(
+        "SELECT id FROM tasks t
+         WHERE status = 'in_progress' AND claimed_by IS NULL
+           AND NOT EXISTS (SELECT 1 FROM tasks c WHERE c.parent_id = t.id)
+         ORDER BY id",
+    )?;
+    let orphaned: Vec<String> = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    drop(stmt);
+    for id in orphaned {
+        db.conn().execute(
+            "UPDATE tasks SET status = 'pending', lease_expires_at = NULL,
+                    updated_at = datetime('now')
+             WHERE id = ?",
+            [&id],
+        )?;
+        repaired.push(format!(
+            "{id}: reset an in-progress task with no owner to pending"
+        ));
+    }
+
+    tx.commit()?;
+    Ok(repaired)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag::{self, init_db};
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn repairs_completions_a_crash_cut_short() -> Result<()> {
+        let temp_file = NamedTempFile::new()?;
+        let db = init_db(temp_file.path().to_str().unwrap())?;
+        let parent = dag::create_task(&db, "Parent", None, None, 0)?;
+        let child = dag::create_task(&db, "Child", None, Some(&parent.id), 0)?;
+        let stuck = dag::create_task(&db, "Stuck", None, None, 0)?;
+        let orphan = dag::create_task(&db, "Orphan", None, None, 0)?;
+
+        // The status write landed, the claim and auto-transitions did not
+        db.conn().execute(
+            "UPDATE tasks SET status = 'done', claimed_by = 'agent-1' WHERE id = ?",
+            [&child.id],
+        )?;
+        db.conn().execute(
+            "UPDATE tasks SET claimed_by = 'agent-1' WHERE id = ?",
+            [&stuck.id],
+        )?;
+        db.conn().execute(
+            "UPDATE tasks SET status = 'in_progress' WHERE id = ?",
+            [&orphan.id],
+        )?;
+
+        let repaired = recover_partial_writes(&db)?;
+        assert_eq!(repaired.len(), 3);
+        assert_eq!(dag::get_task(&db, &parent.id)?.status, "done");
+        assert_eq!(dag::get_task(&db, &child.id)?.claimed_by, None);
+        assert_eq!(dag::get_task(&db, &stuck.id)?.claimed_by, None);
+        assert_eq!(dag::get_task(&db, &orphan.id)?.status, "pending");
+
+        // A consistent database needs nothing
+        assert!(recover_partial_writes(&db)?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+//! Ready-queue ordering policies.
+//!
+//! `get_ready_tasks` returns tasks sorted by priority then age. The scheduler
+//! re-orders that list for the run loop so a steady stream of high-priority
+//! work cannot starve old low-priority tasks indefinitely.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::dag::Task;
+
+/// Default wait after which an aged task gains one priority level (60 minutes).
+pub const DEFAULT_AGING_SECS: u64 = 3600;
+
+/// How the run loop picks the next task from the ready set.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Scheduler {
+    /// Oldest task first, ignoring priority.
+    Fifo,
+    /// Lowest priority number first, oldest first within a priority.
+    #[default]
+    Priority,
+    /// Like `Priority`, but each `aging_secs` of waiting lowers the effective
+    /// priority number by one.
+    Aged { aging_secs: u64 },
+}
+
+impl fmt::Display for Scheduler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scheduler::Fifo => write!(f, "fifo"),
+            Scheduler::Priority => write!(f, "priority"),
+            Scheduler::Aged { .. } => write!(f, "aged"),
+        }
+    }
+}
+
+impl FromStr for Scheduler {
+    type Err = anyhow::Error;
+
+    /// Parse a scheduler name. `aged` uses [`DEFAULT_AGING_SECS`]; see
+    /// [`Scheduler::with_aging`] to override it.
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "fifo" => Ok(Scheduler::Fifo),
+            "priority" => Ok(Scheduler::Priority),
+            "aged" => Ok(Scheduler::Aged {
+                aging_secs: DEFAULT_AGING_SECS,
+            }),
+            _ => bail!(
+                "invalid scheduler '{}' (expected fifo, priority, or aged)",
+                s
+            ),
+        }
+    }
+}
+
+impl Scheduler {
+    /// Set the aging interval; no effect on non-aged schedulers.
+    pub fn with_aging(self, aging_secs: u64) -> Self {
+        match self {
+            Scheduler::Aged { .. } => Scheduler::Aged {
+                aging_secs: aging_secs.max(1),
+            },
+            other => other,
+        }
+    }
+
+    /// Effective priority of a task at `now` (lower runs first).
+    pub fn effective_priority(&self, task: &Task, now: DateTime<Utc>) -> i64 {
+        match self {
+            Scheduler::Fifo => 0,
+            Scheduler::Priority => task.priority as i64,
+            Scheduler::Aged { aging_secs } => {
+                let waited = created_at(task)
+                    .map(|t| (now - t).num_seconds().max(0) as u64)
+                    .unwrap_or(0);
+                task.priority as i64 - (waited / aging_secs) as i64
+            }
+        }
+    }
+
+    /// Re-order a ready list in place. Ties fall back to creation time.
+    pub fn order(&self, tasks: &mut [Task], now: DateTime<Utc>) {
+        tasks.sort_by(|a, b| {
+            self.effective_priority(a, now)
+                .cmp(&self.effective_priority(b, now))
+                .then_with(|| a.created_at.cmp(&b.created_at))
+        });
+    }
+}
+
+fn created_at(task: &Task) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&task.created_at)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn task(id: &str, priority: i32, created_at: DateTime<Utc>) -> Task {
+        Task {
+            id: id.to_string(),
+            title: id.to_string(),
+            description: String::new(),
+            status: "pending".to_string(),
+            parent_id: None,
+            feature_id: None,
+            task_type: "standalone".to_string(),
+            priority,
+            retry_count: 0,
+            max_retries: 3,
+            verification_status: None,
+            created_at: created_at.to_rfc3339(),
+            updated_at: created_at.to_rfc3339(),
+            claimed_by: None,
+            discovered_by: None,
+            source_ref: None,
+            cwd: None,
+            created_by: None,
+            completed_by: None,
+            plan_ref: None,
+        }
+    }
+
+    fn ids(tasks: &[Task]) -> Vec<&str> {
+        tasks.iter().map(|t| t.id.as_str()).collect()
+    }
+
+    #[test]
+    fn parse_and_display_round_trip() {
+        for name in ["fifo", "priority", "aged"] {
+            assert_eq!(name.parse::<Scheduler>().unwrap().to_string(), name);
+        }
+        assert!("lifo".parse::<Scheduler>().is_err());
+    }
+
+    #[test]
+    fn priority_and_fifo_ordering() {
+        let now = Utc::now();
+        let mut tasks = vec![
+            task("new-urgent", 0, now - Duration::minutes(1)),
+            task("old-low", 5, now - Duration::hours(10)),
+        ];
+
+        Scheduler::Priority.order(&mut tasks, now);
+        assert_eq!(ids(&tasks), vec!["new-urgent", "old-low"]);
+
+        Scheduler::Fifo.order(&mut tasks, now);
+        assert_eq!(ids(&tasks), vec!["old-low", "new-urgent"]);
+    }
+
+    #[test]
+    fn aged_scheduler_promotes_waiting_tasks() {
+        let now = Utc::now();
+        let mut tasks = vec![
+            task("new-urgent", 0, now - Duration::minutes(1)),
+            task("old-low", 5, now - Duration::hours(10)),
+        ];
+
+        // One level per hour: after 10h the old task's effective priority is -5.
+        let aged = "aged".parse::<Scheduler>().unwrap();
+        aged.order(&mut tasks, now);
+        assert_eq!(ids(&tasks), vec!["old-low", "new-urgent"]);
+
+        // With a slow aging interval the urgent task still wins.
+        let slow = aged.with_aging(24 * 3600);
+        slow.order(&mut tasks, now);
+        assert_eq!(ids(&tasks), vec!["new-urgent", "old-low"]);
+    }
+}
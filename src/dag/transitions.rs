@@ -84,8 +84,18 @@ pub fn set_task_status(
     .context("Failed to update task status")?;
 
     // Run auto-transitions based on new status
+    auto_transitions(conn, task_id, new_status)
+}
+
+/// The auto-transitions that follow `task_id` reaching `status`. Each one
+/// checks the state it changes, so running them again is harmless.
+pub(super) fn auto_transitions(
+    conn: &Connection,
+    task_id: &str,
+    status: &str,
+) -> Result<Vec<AutoTransition>> {
     let mut transitions = Vec::new();
-    match new_status {
+    match status {
         "done" => {
             transitions.extend(auto_unblock_tasks(conn, task_id)?);
             transitions.extend(auto_complete_parent(conn, task_id)?);
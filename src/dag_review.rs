@@ -0,0 +1,331 @@
+//! Review of a freshly built task DAG.
+//!
+//! Specs and plans go through review loops; the DAG `feature create` builds
+//! from the plan gets one read-only pass. The reviewer checks the tasks
+//! against the plan (coverage, granularity, missing test and docs work,
+//! dependency sanity) and answers with a `<dag-review>` sigil of
+//! suggestions. Each names an issue and may carry the [`DagEdits`] that fix
+//! it, in the format blocked-run planning sessions use. Ralph lists the
+//! suggestions and applies the edits once the user accepts.
+//!
+//! `[review] dag = false` skips the review; `[review] dag_auto_apply`
+//! applies the edits without asking.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::acp;
+use crate::acp::connection::SessionRestrictions;
+use crate::dag::{self, Db};
+use crate::feature_prompts::build_dag_review_system_prompt;
+use crate::input;
+use crate::output::formatter;
+use crate::project::RalphConfig;
+use crate::prompt_templates;
+use crate::remediation::{self, ApplyReport, DagEdits};
+use crate::replan;
+
+const START_TAG: &str = "<dag-review>";
+const END_TAG: &str = "</dag-review>";
+
+/// Model for the review session; it only reads.
+const REVIEW_MODEL: &str = "sonnet";
+
+/// What a suggestion is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    Coverage,
+    Granularity,
+    Tests,
+    Docs,
+    Dependencies,
+}
+
+impl Category {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Category::Coverage => "coverage",
+            Category::Granularity => "granularity",
+            Category::Tests => "tests",
+            Category::Docs => "docs",
+            Category::Dependencies => "dependencies",
+        }
+    }
+}
+
+/// One issue the reviewer found, with the edits that fix it, if any.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Suggestion {
+    pub category: Category,
+    pub issue: String,
+    /// Empty when the fix needs a person.
+    #[serde(default)]
+    pub edits: DagEdits,
+}
+
+#[derive(Debug, Deserialize)]
+struct DagReview {
+    #[serde(default)]
+    suggestions: Vec<Suggestion>,
+}
+
+/// Extract the last `<dag-review>` sigil in `text`.
+pub fn parse(text: &str) -> Result<Vec<Suggestion>> {
+    let start = text
+        .rfind(START_TAG)
+        .context("The reviewer did not emit a <dag-review> sigil")?
+        + START_TAG.len();
+    let end = text[start..]
+        .find(END_TAG)
+        .context("The <dag-review> sigil is not closed")?;
+    let body = text[start..start + end].trim();
+    let body = body
+        .strip_prefix("```json")
+        .or_else(|| body.strip_prefix("```"))
+        .and_then(|b| b.trim_end().strip_suffix("```"))
+        .unwrap_or(body);
+    let review: DagReview =
+        serde_json::from_str(body).context("The <dag-review> sigil is not valid JSON")?;
+    Ok(review.suggestions)
+}
+
+/// The tasks under `root_id` with their criteria, then their dependencies,
+/// for the review prompt.
+pub fn render_tasks(db: &Db, root_id: &str) -> Result<String> {
+    let mut out = replan::render_existing_tasks(db, root_id)?;
+    let mut edges = Vec::new();
+    for task in dag::get_task_tree(db, root_id)? {
+        for blocker in dag::get_task_blockers(db, &task.id)? {
+            edges.push(format!("- `{}` \u{2192} `{}`\n", blocker.id, task.id));
+        }
+    }
+    out.push_str("\nDependencies (blocker \u{2192} blocked):\n\n");
+    if edges.is_empty() {
+        out.push_str("(none)\n");
+    }
+    edges.iter().for_each(|e| out.push_str(e));
+    Ok(out)
+}
+
+/// Drop the edits of suggestions that do not validate against the DAG,
+/// keeping their issue as advice.
+fn validate(db: &Db, root_id: &str, suggestions: &mut [Suggestion]) {
+    for suggestion in suggestions {
+        if suggestion.edits.is_empty() {
+            continue;
+        }
+        if let Err(e) = suggestion.edits.validate(db, Some(root_id)) {
+            formatter::print_warning(&format!(
+                "Dropping the edits of \"{}\": {e:#}",
+                suggestion.issue
+            ));
+            suggestion.edits = DagEdits::default();
+        }
+    }
+}
+
+/// One line per suggestion, followed by its edits.
+fn render_lines(suggestions: &[Suggestion]) -> Vec<String> {
+    let mut lines = vec![format!(
+        "Task DAG review: {} suggestion(s)",
+        suggestions.len()
+    )];
+    for suggestion in suggestions {
+        lines.push(format!(
+            "[{}] {}",
+            suggestion.category.label(),
+            suggestion.issue
+        ));
+        if suggestion.edits.is_empty() {
+            lines.push("    (no edits; needs a person)".to_string());
+        }
+        for line in suggestion.edits.render_lines().into_iter().skip(1) {
+            lines.push(format!("    {line}"));
+        }
+    }
+    lines
+}
+
+/// Apply each suggestion's edits in its own transaction. A suggestion an
+/// earlier one invalidated is skipped with a warning.
+fn apply_suggestions(
+    db: &Db,
+    root_id: &str,
+    suggestions: &[Suggestion],
+    max_retries: i32,
+    user: Option<&str>,
+) -> ApplyReport {
+    let mut total = ApplyReport::default();
+    for suggestion in suggestions.iter().filter(|s| !s.edits.is_empty()) {
+        let applied = suggestion.edits.validate(db, Some(root_id)).and_then(|_| {
+            remediation::apply(db, &suggestion.edits, Some(root_id), max_retries, user)
+        });
+        match applied {
+            Ok(report) => {
+                total.created.extend(report.created);
+                total.added += report.added;
+                total.removed += report.removed;
+            }
+            Err(e) => formatter::print_warning(&format!(
+                "Skipped the edits of \"{}\": {e:#}",
+                suggestion.issue
+            )),
+        }
+    }
+    total
+}
+
+/// Review the DAG under `root_id` against the feature's plan and apply the
+/// suggested edits the user accepts. A failed review session only warns:
+/// the DAG stands as built.
+#[allow(clippy::too_many_arguments)]
+pub async fn review(
+    db: &Db,
+    project_root: &Path,
+    config: &RalphConfig,
+    root_id: &str,
+    spec: &str,
+    plan: &str,
+    agent_command: &str,
+    user: Option<&str>,
+) -> Result<()> {
+    if !config.review.dag {
+        return Ok(());
+    }
+    let tasks = render_tasks(db, root_id)?;
+    let system_prompt = prompt_templates::apply(
+        project_root,
+        "dag_review",
+        build_dag_review_system_prompt(spec, plan, &tasks),
+        &[("spec", spec), ("plan", plan), ("tasks", &tasks)],
+    );
+    formatter::print_info("Reviewing the task DAG against the plan");
+    let result = acp::connection::run_autonomous(
+        agent_command,
+        project_root,
+        &system_prompt,
+        "Review the task DAG against the plan, emit the <dag-review> sigil and stop.",
+        true,
+        Some(REVIEW_MODEL),
+        SessionRestrictions {
+            allow_terminal: false,
+            ..Default::default()
+        },
+    )
+    .await;
+    let mut suggestions = match result.and_then(|r| parse(&r.full_text)) {
+        Ok(suggestions) => suggestions,
+        Err(e) => {
+            formatter::print_warning(&format!("DAG review skipped: {e:#}"));
+            return Ok(());
+        }
+    };
+    if suggestions.is_empty() {
+        formatter::print_info("DAG review: no issues found");
+        return Ok(());
+    }
+    validate(db, root_id, &mut suggestions);
+
+    let lines = render_lines(&suggestions);
+    if !crate::ui::is_active() || !crate::ui::show_explorer("DAG Review", lines.clone()) {
+        println!();
+        for line in &lines {
+            println!("{line}");
+        }
+    }
+    let fixable = suggestions.iter().filter(|s| !s.edits.is_empty()).count();
+    if fixable == 0 {
+        formatter::print_info(
+            "None of the suggestions come with edits; change the tasks with `ralph task` if needed.",
+        );
+        return Ok(());
+    }
+    let question = format!("Apply the edits of {fixable} suggestion(s)?");
+    let accepted = if config.review.dag_auto_apply || input::assume_yes() {
+        true
+    } else if input::no_input() {
+        false
+    } else if crate::ui::is_active() {
+        crate::ui::prompt_confirm("DAG Review", &question, true).unwrap_or(false)
+    } else {
+        input::confirm(&question, true)
+    };
+    if !accepted {
+        formatter::print_info("Suggested DAG edits not applied.");
+        return Ok(());
+    }
+
+    let max_retries = config.execution.max_retries as i32;
+    let report = apply_suggestions(db, root_id, &suggestions, max_retries, user);
+    formatter::print_info(&format!(
+        "DAG review applied: {} task(s) created, {} dependency(ies) added, {} removed",
+        report.created.len(),
+        report.added,
+        report.removed
+    ));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag::init_db;
+    use crate::feature;
+    use tempfile::TempDir;
+
+    #[test]
+    fn suggestions_are_checked_and_applied() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let feat = feature::create_feature(&db, "users").unwrap();
+        let task =
+            |title: &str, parent: Option<&str>| dag::create_test_task(&db, &feat.id, title, parent);
+        let root = task("Feature: users", None);
+        let schema = task("Users table", Some(&root.id));
+        let api = task("User endpoint", Some(&root.id));
+        assert!(render_tasks(&db, &root.id)
+            .unwrap()
+            .ends_with("blocked):\n\n(none)\n"));
+
+        let text = format!(
+            "Looks mostly fine.\n<dag-review>\n```json\n{{\"suggestions\": [\n\
+             {{\"category\": \"dependencies\", \"issue\": \"The endpoint needs the table.\",\n\
+              \"edits\": {{\"add_dependencies\": [{{\"blocker\": \"{s}\", \"blocked\": \"{a}\"}}]}}}},\n\
+             {{\"category\": \"tests\", \"issue\": \"Nothing tests lookups.\",\n\
+              \"edits\": {{\"tasks\": [{{\"key\": \"t\", \"title\": \"Test lookups\", \"depends_on\": [\"{a}\"]}}]}}}},\n\
+             {{\"category\": \"docs\", \"issue\": \"Bad reference.\",\n\
+              \"edits\": {{\"add_dependencies\": [{{\"blocker\": \"t-nope\", \"blocked\": \"{a}\"}}]}}}},\n\
+             {{\"category\": \"granularity\", \"issue\": \"Split the endpoint.\"}}\n\
+             ]}}\n```\n</dag-review>",
+            s = schema.id,
+            a = api.id
+        );
+        let mut suggestions = parse(&text).unwrap();
+        assert_eq!(suggestions.len(), 4);
+        assert_eq!(suggestions[1].category, Category::Tests);
+        validate(&db, &root.id, &mut suggestions);
+        assert!(suggestions[2].edits.is_empty());
+
+        let lines = render_lines(&suggestions);
+        assert_eq!(lines[0], "Task DAG review: 4 suggestion(s)");
+        assert!(lines.contains(&"[granularity] Split the endpoint.".to_string()));
+        assert!(lines.contains(&"    (no edits; needs a person)".to_string()));
+
+        let report = apply_suggestions(&db, &root.id, &suggestions, 3, None);
+        assert_eq!((report.created.len(), report.added), (1, 1));
+        let created = dag::get_task(&db, &report.created[0]).unwrap();
+        assert_eq!(created.parent_id.as_deref(), Some(root.id.as_str()));
+        assert_eq!(
+            dag::get_task_blockers(&db, &created.id).unwrap()[0].id,
+            api.id
+        );
+        assert!(render_tasks(&db, &root.id)
+            .unwrap()
+            .contains(&format!("- `{}` \u{2192} `{}`", schema.id, api.id)));
+
+        assert!(parse("<dag-review>{\"suggestions\": [{\"category\": \"style\", \"issue\": \"x\"}]}</dag-review>").is_err());
+        assert!(parse("no sigil").is_err());
+    }
+}
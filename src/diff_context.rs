@@ -0,0 +1,297 @@
+//! `[execution] diff_context`: the changes made so far, in each prompt.
+//!
+//! Agents otherwise rediscover earlier iterations' edits by reading files.
+//! With `diff_context = "run"` the prompt carries a summary of `git diff`
+//! against HEAD at the start of the run; with `"feature"` against the commit
+//! the feature's plan was written on. The summary is the `--stat`, the new
+//! untracked files, and as many hunks as fit in `diff_context_chars`: files
+//! the task lists under `files` first, then the smallest. Files whose hunks
+//! did not fit are named so the agent knows to read them.
+
+use std::path::Path;
+use std::process::Command;
+
+use serde::Deserialize;
+
+/// Changes under `.ralph/` (progress database, scratchpads) are not code.
+const EXCLUDE_RALPH: &str = ":(exclude).ralph";
+
+/// Most files listed by the stat and the untracked list.
+const MAX_LISTED_FILES: usize = 50;
+
+/// Which changes `[execution] diff_context` shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffScope {
+    /// No section (the default).
+    #[default]
+    Off,
+    /// Changes since the run started.
+    Run,
+    /// Changes since the feature's plan was written; the run's start for
+    /// standalone tasks and features without a recorded plan commit.
+    Feature,
+}
+
+/// The commit HEAD points at, or `None` outside a git repository.
+pub fn head(project_root: &Path) -> Option<String> {
+    git(project_root, &["rev-parse", "HEAD"])
+}
+
+/// The "Changes So Far" prompt section for the changes since `base`, or an
+/// empty string when nothing changed.
+pub fn render_diff_context(
+    project_root: &Path,
+    base: &str,
+    since: &str,
+    focus: &[String],
+    budget: usize,
+) -> String {
+    let stat_count = format!("--stat-count={MAX_LISTED_FILES}");
+    let stat = git(
+        project_root,
+        &[
+            "diff",
+            "--stat",
+            &stat_count,
+            base,
+            "--",
+            ".",
+            EXCLUDE_RALPH,
+        ],
+    )
+    .unwrap_or_default();
+    let untracked = git(
+        project_root,
+        &[
+            "ls-files",
+            "--others",
+            "--exclude-standard",
+            "--",
+            ".",
+            EXCLUDE_RALPH,
+        ],
+    )
+    .unwrap_or_default();
+    if stat.is_empty() && untracked.is_empty() {
+        return String::new();
+    }
+
+    let short = &base[..base.len().min(12)];
+    let mut out = String::from("## Changes So Far\n\n");
+    out.push_str(&format!(
+        "These changes since `{short}` ({since}) are already in the working tree. \
+         Build on them instead of re-reading files to find out what was done.\n"
+    ));
+    if !stat.is_empty() {
+        out.push_str(&format!("\n```text\n{stat}\n```\n"));
+    }
+    let new_files: Vec<&str> = untracked.lines().collect();
+    if !new_files.is_empty() {
+        out.push_str("\nNew files not yet in git:\n\n");
+        for file in new_files.iter().take(MAX_LISTED_FILES) {
+            out.push_str(&format!("- `{file}`\n"));
+        }
+        if new_files.len() > MAX_LISTED_FILES {
+            out.push_str(&format!(
+                "- ...and {} more\n",
+                new_files.len() - MAX_LISTED_FILES
+            ));
+        }
+    }
+
+    let diff = git(project_root, &["diff", base, "--", ".", EXCLUDE_RALPH]).unwrap_or_default();
+    let (hunks, omitted) = select_hunks(&format!("{diff}\n"), focus, budget);
+    if !hunks.is_empty() {
+        out.push_str(&format!("\n```diff\n{}\n```\n", hunks.trim_end()));
+    }
+    if !omitted.is_empty() {
+        out.push_str(&format!(
+            "\nNot shown (over the {budget}-character budget): {}\n",
+            omitted
+                .iter()
+                .map(|f| format!("`{f}`"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    out
+}
+
+/// One file of a unified diff: its header lines and its hunks.
+struct FileDiff<'a> {
+    path: &'a str,
+    header: &'a str,
+    hunks: Vec<&'a str>,
+}
+
+impl FileDiff<'_> {
+    fn len(&self) -> usize {
+        self.header.len() + self.hunks.iter().map(|h| h.len()).sum::<usize>()
+    }
+}
+
+/// Split `git diff` output into files, each cut into its `@@` hunks.
+fn split_files(diff: &str) -> Vec<FileDiff<'_>> {
+    let mut starts: Vec<usize> = diff
+        .match_indices("\ndiff --git ")
+        .map(|(i, _)| i + 1)
+        .collect();
+    if diff.starts_with("diff --git ") {
+        starts.insert(0, 0);
+    }
+    let ends = starts.iter().skip(1).copied().chain([diff.len()]);
+    starts
+        .iter()
+        .zip(ends)
+        .map(|(&start, end)| {
+            let chunk = &diff[start..end];
+            let mut cuts: Vec<usize> = chunk.match_indices("\n@@ ").map(|(i, _)| i + 1).collect();
+            cuts.push(chunk.len());
+            let header = &chunk[..cuts[0]];
+            let hunks = cuts.windows(2).map(|w| &chunk[w[0]..w[1]]).collect();
+            let path = header
+                .lines()
+                .next()
+                .and_then(|line| line.rsplit_once(" b/"))
+                .map_or("", |(_, path)| path);
+            FileDiff {
+                path,
+                header,
+                hunks,
+            }
+        })
+        .collect()
+}
+
+/// The hunks of `diff` that fit in `budget` characters, and the files left
+/// out entirely. Files in `focus` come first, then the smallest; a file that
+/// does not fit whole contributes the leading hunks that do.
+fn select_hunks(diff: &str, focus: &[String], budget: usize) -> (String, Vec<String>) {
+    let mut files = split_files(diff);
+    files.sort_by_key(|f| (!focus.iter().any(|p| p == f.path), f.len()));
+
+    let mut out = String::new();
+    let mut omitted = Vec::new();
+    for file in files {
+        let left = budget.saturating_sub(out.len());
+        if file.len() <= left {
+            out.push_str(file.header);
+            file.hunks.iter().for_each(|h| out.push_str(h));
+            continue;
+        }
+        let mut used = file.header.len();
+        let taken: Vec<&str> = file
+            .hunks
+            .iter()
+            .take_while(|h| {
+                used += h.len();
+                used <= left
+            })
+            .copied()
+            .collect();
+        if taken.is_empty() {
+            omitted.push(file.path.to_string());
+        } else {
+            out.push_str(file.header);
+            taken.iter().for_each(|h| out.push_str(h));
+            out.push_str(&format!(
+                "# ... {} more hunk(s) in {}\n",
+                file.hunks.len() - taken.len(),
+                file.path
+            ));
+        }
+    }
+    (out, omitted)
+}
+
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    out.status
+        .success()
+        .then(|| String::from_utf8_lossy(&out.stdout).trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn run_git(dir: &Path, args: &[&str]) {
+        let ok = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status
+            .success();
+        assert!(ok, "git {:?} failed", args);
+    }
+
+    #[test]
+    fn summarizes_changes_since_the_base() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        run_git(root, &["init", "-q"]);
+        let long: String = (0..200).map(|i| format!("line {i}\n")).collect();
+        std::fs::write(root.join("big.rs"), &long).unwrap();
+        std::fs::write(root.join("small.rs"), "fn a() {}\n").unwrap();
+        run_git(root, &["add", "."]);
+        run_git(root, &["commit", "-q", "-m", "init"]);
+        let base = head(root).unwrap();
+        assert_eq!(
+            render_diff_context(root, &base, "start of this run", &[], 4000),
+            ""
+        );
+
+        std::fs::write(root.join("small.rs"), "fn b() {}\n").unwrap();
+        std::fs::write(root.join("big.rs"), long.replace("line", "row")).unwrap();
+        std::fs::write(root.join("new.rs"), "fn c() {}\n").unwrap();
+        std::fs::create_dir_all(root.join(".ralph")).unwrap();
+        std::fs::write(root.join(".ralph/notes.md"), "ignored\n").unwrap();
+
+        let section = render_diff_context(root, &base, "start of this run", &[], 500);
+        assert!(section.starts_with("## Changes So Far\n"));
+        assert!(section.contains("start of this run"));
+        assert!(section.contains("small.rs |"));
+        assert!(section.contains("- `new.rs`"));
+        assert!(!section.contains(".ralph"));
+        assert!(section.contains("+fn b() {}"));
+        assert!(section.contains("Not shown (over the 500-character budget): `big.rs`"));
+
+        // A file the task lists goes ahead of smaller ones.
+        let section = render_diff_context(root, &base, "x", &["big.rs".to_string()], 10_000);
+        assert!(section.find("+row 0").unwrap() < section.find("+fn b() {}").unwrap());
+    }
+
+    #[test]
+    fn oversized_files_contribute_leading_hunks() {
+        let diff = "diff --git a/a.rs b/a.rs\n--- a/a.rs\n+++ b/a.rs\n\
+                    @@ -1 +1 @@\n-a\n+b\n@@ -9 +9 @@\n-c\n+d\n\
+                    diff --git a/b.rs b/b.rs\n--- a/b.rs\n+++ b/b.rs\n@@ -1 +1 @@\n-x\n+y\n";
+        let files = split_files(diff);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "a.rs");
+        assert_eq!(
+            files[0].hunks,
+            ["@@ -1 +1 @@\n-a\n+b\n", "@@ -9 +9 @@\n-c\n+d\n"]
+        );
+
+        let (hunks, omitted) = select_hunks(diff, &["a.rs".to_string()], 75);
+        assert!(hunks.contains("+b") && !hunks.contains("+d"));
+        assert!(hunks.contains("# ... 1 more hunk(s) in a.rs"));
+        assert_eq!(omitted, ["b.rs"]);
+
+        let (hunks, omitted) = select_hunks(diff, &[], 0);
+        assert_eq!(hunks, "");
+        assert_eq!(omitted, ["b.rs", "a.rs"]);
+    }
+}
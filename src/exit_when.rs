@@ -0,0 +1,441 @@
+//! `[run] exit_when`: stop a run when a user-defined condition holds.
+//!
+//! The condition is a small expression over the run's stats, checked before
+//! each iteration:
+//!
+//! ```text
+//! feature.done_ratio >= 0.8 or budget.cost > 5
+//! not (run.failed > 3) and run.elapsed_mins < 90
+//! ```
+//!
+//! Comparisons (`<`, `<=`, `>`, `>=`, `==`, `!=`) take a variable or a
+//! number on either side; `and`, `or`, `not` (or `&&`, `||`, `!`) and
+//! parentheses combine them. Variables are listed in [`VARIABLES`]; an
+//! unknown one is an error when the run starts, not when it is evaluated.
+
+use anyhow::{bail, Result};
+
+/// Variables an expression can use, with what they measure.
+pub const VARIABLES: &[(&str, &str)] = &[
+    ("feature.total", "leaf tasks in the run's scope"),
+    ("feature.done", "done leaf tasks in scope"),
+    ("feature.failed", "failed leaf tasks in scope"),
+    (
+        "feature.remaining",
+        "leaf tasks in scope neither done nor failed",
+    ),
+    (
+        "feature.done_ratio",
+        "feature.done / feature.total (0 to 1)",
+    ),
+    ("budget.cost", "USD spent by this run's iterations"),
+    (
+        "budget.tokens",
+        "input and output tokens of this run's iterations",
+    ),
+    ("run.iterations", "iterations this run has finished"),
+    (
+        "run.failed",
+        "iterations of this run that failed or timed out",
+    ),
+    ("run.elapsed_mins", "minutes since the run started"),
+];
+
+/// The values of [`VARIABLES`] at one point of a run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunStats {
+    pub total: usize,
+    pub done: usize,
+    pub failed: usize,
+    pub cost: f64,
+    pub tokens: u64,
+    pub iterations: u32,
+    pub failed_iterations: u32,
+    pub elapsed_mins: f64,
+}
+
+impl RunStats {
+    fn value(&self, name: &str) -> f64 {
+        match name {
+            "feature.total" => self.total as f64,
+            "feature.done" => self.done as f64,
+            "feature.failed" => self.failed as f64,
+            "feature.remaining" => self.total.saturating_sub(self.done + self.failed) as f64,
+            "feature.done_ratio" if self.total == 0 => 0.0,
+            "feature.done_ratio" => self.done as f64 / self.total as f64,
+            "budget.cost" => self.cost,
+            "budget.tokens" => self.tokens as f64,
+            "run.iterations" => self.iterations as f64,
+            "run.failed" => self.failed_iterations as f64,
+            "run.elapsed_mins" => self.elapsed_mins,
+            _ => unreachable!("variables are checked when parsing"),
+        }
+    }
+}
+
+/// A parsed `exit_when` expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExitCondition {
+    source: String,
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Operand, Cmp, Operand),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Number(f64),
+    Variable(&'static str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Cmp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Cmp(Cmp),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl ExitCondition {
+    /// Parse `source`, checking every variable name.
+    pub fn parse(source: &str) -> Result<Self> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            bail!("unexpected {} in exit_when '{source}'", describe(token));
+        }
+        Ok(Self {
+            source: source.trim().to_string(),
+            expr,
+        })
+    }
+
+    /// The expression as written.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Whether the condition holds for `stats`.
+    pub fn is_met(&self, stats: &RunStats) -> bool {
+        eval(&self.expr, stats)
+    }
+
+    /// The variables the expression uses with their values, e.g.
+    /// `feature.done_ratio=0.83, budget.cost=1.2`, for the stop message.
+    pub fn explain(&self, stats: &RunStats) -> String {
+        let mut names = Vec::new();
+        collect_variables(&self.expr, &mut names);
+        names
+            .iter()
+            .map(|name| {
+                let value = stats.value(name);
+                format!("{name}={}", (value * 100.0).round() / 100.0)
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+fn eval(expr: &Expr, stats: &RunStats) -> bool {
+    match expr {
+        Expr::Or(a, b) => eval(a, stats) || eval(b, stats),
+        Expr::And(a, b) => eval(a, stats) && eval(b, stats),
+        Expr::Not(a) => !eval(a, stats),
+        Expr::Compare(left, cmp, right) => {
+            let value = |operand: &Operand| match operand {
+                Operand::Number(n) => *n,
+                Operand::Variable(name) => stats.value(name),
+            };
+            let (l, r) = (value(left), value(right));
+            match cmp {
+                Cmp::Lt => l < r,
+                Cmp::Le => l <= r,
+                Cmp::Gt => l > r,
+                Cmp::Ge => l >= r,
+                Cmp::Eq => l == r,
+                Cmp::Ne => l != r,
+            }
+        }
+    }
+}
+
+fn collect_variables(expr: &Expr, names: &mut Vec<&'static str>) {
+    match expr {
+        Expr::Or(a, b) | Expr::And(a, b) => {
+            collect_variables(a, names);
+            collect_variables(b, names);
+        }
+        Expr::Not(a) => collect_variables(a, names),
+        Expr::Compare(left, _, right) => {
+            for operand in [left, right] {
+                if let Operand::Variable(name) = operand {
+                    if !names.contains(name) {
+                        names.push(name);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let two = source.get(start..start + 2).unwrap_or_default();
+        let token = match two {
+            "<=" => Some(Token::Cmp(Cmp::Le)),
+            ">=" => Some(Token::Cmp(Cmp::Ge)),
+            "==" => Some(Token::Cmp(Cmp::Eq)),
+            "!=" => Some(Token::Cmp(Cmp::Ne)),
+            "&&" => Some(Token::And),
+            "||" => Some(Token::Or),
+            _ => None,
+        };
+        if let Some(token) = token {
+            chars.next();
+            chars.next();
+            tokens.push(token);
+            continue;
+        }
+        let single = match c {
+            '<' => Some(Token::Cmp(Cmp::Lt)),
+            '>' => Some(Token::Cmp(Cmp::Gt)),
+            '!' => Some(Token::Not),
+            '(' => Some(Token::Open),
+            ')' => Some(Token::Close),
+            _ => None,
+        };
+        if let Some(token) = single {
+            chars.next();
+            tokens.push(token);
+            continue;
+        }
+        if c.is_ascii_digit() || c == '.' {
+            let mut end = start;
+            while let Some(&(i, d)) = chars.peek() {
+                if !(d.is_ascii_digit() || d == '.') {
+                    break;
+                }
+                end = i + d.len_utf8();
+                chars.next();
+            }
+            let text = &source[start..end];
+            match text.parse() {
+                Ok(n) => tokens.push(Token::Number(n)),
+                Err(_) => bail!("invalid number '{text}' in exit_when '{source}'"),
+            }
+            continue;
+        }
+        if c.is_ascii_alphabetic() || c == '_' {
+            let mut end = start;
+            while let Some(&(i, d)) = chars.peek() {
+                if !(d.is_ascii_alphanumeric() || d == '_' || d == '.') {
+                    break;
+                }
+                end = i + d.len_utf8();
+                chars.next();
+            }
+            tokens.push(match &source[start..end] {
+                "and" => Token::And,
+                "or" => Token::Or,
+                "not" => Token::Not,
+                word => Token::Ident(word.to_string()),
+            });
+            continue;
+        }
+        bail!("unexpected '{c}' in exit_when '{source}'");
+    }
+    if tokens.is_empty() {
+        bail!("exit_when is empty");
+    }
+    Ok(tokens)
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Number(n) => format!("number {n}"),
+        Token::Ident(name) => format!("'{name}'"),
+        Token::Cmp(_) => "comparison".to_string(),
+        Token::And => "'and'".to_string(),
+        Token::Or => "'or'".to_string(),
+        Token::Not => "'not'".to_string(),
+        Token::Open => "'('".to_string(),
+        Token::Close => "')'".to_string(),
+    }
+}
+
+/// Recursive descent: `or` binds loosest, then `and`, then `not`.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.tokens.get(self.pos) == Some(token);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.eat(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.not()?;
+        while self.eat(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr> {
+        if self.eat(&Token::Not) {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        if self.eat(&Token::Open) {
+            let expr = self.or()?;
+            if !self.eat(&Token::Close) {
+                bail!("missing ')' in exit_when");
+            }
+            return Ok(expr);
+        }
+        let left = self.operand()?;
+        let cmp = match self.next() {
+            Some(Token::Cmp(cmp)) => cmp,
+            Some(token) => bail!("expected a comparison, found {}", describe(&token)),
+            None => bail!("expected a comparison at the end of exit_when"),
+        };
+        let right = self.operand()?;
+        Ok(Expr::Compare(left, cmp, right))
+    }
+
+    fn operand(&mut self) -> Result<Operand> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Operand::Number(n)),
+            Some(Token::Ident(name)) => match VARIABLES.iter().find(|(v, _)| *v == name) {
+                Some((variable, _)) => Ok(Operand::Variable(variable)),
+                None => bail!(
+                    "unknown variable '{name}' in exit_when (expected one of {})",
+                    VARIABLES
+                        .iter()
+                        .map(|(v, _)| *v)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            },
+            Some(token) => bail!("expected a number or variable, found {}", describe(&token)),
+            None => bail!("expected a number or variable at the end of exit_when"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats() -> RunStats {
+        RunStats {
+            total: 10,
+            done: 8,
+            failed: 1,
+            cost: 2.5,
+            tokens: 120_000,
+            iterations: 12,
+            failed_iterations: 4,
+            elapsed_mins: 42.0,
+        }
+    }
+
+    #[test]
+    fn evaluates_with_precedence_and_parentheses() {
+        let met = |source: &str| ExitCondition::parse(source).unwrap().is_met(&stats());
+        assert!(met("feature.done_ratio >= 0.8 or budget.cost > 5"));
+        assert!(!met("feature.done_ratio > 0.8 || budget.cost > 5"));
+        assert!(met("feature.remaining == 1 and run.iterations >= 12"));
+        // `and` binds tighter than `or`
+        assert!(met(
+            "run.failed > 3 or budget.cost > 5 and run.elapsed_mins > 60"
+        ));
+        assert!(!met(
+            "(run.failed > 3 or budget.cost > 5) and run.elapsed_mins > 60"
+        ));
+        assert!(met("not (budget.tokens < 100000) && !(5 < budget.cost)"));
+        assert!(ExitCondition::parse("feature.done_ratio >= 1")
+            .unwrap()
+            .is_met(&RunStats {
+                total: 3,
+                done: 3,
+                ..RunStats::default()
+            }));
+
+        let condition =
+            ExitCondition::parse(" feature.done_ratio >= 0.8 or budget.cost > 5 ").unwrap();
+        assert_eq!(
+            condition.source(),
+            "feature.done_ratio >= 0.8 or budget.cost > 5"
+        );
+        assert_eq!(
+            condition.explain(&stats()),
+            "feature.done_ratio=0.8, budget.cost=2.5"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        let error = |source: &str| ExitCondition::parse(source).unwrap_err().to_string();
+        assert!(error("feature.ratio > 1").starts_with("unknown variable 'feature.ratio'"));
+        assert_eq!(
+            error("budget.cost"),
+            "expected a comparison at the end of exit_when"
+        );
+        assert_eq!(
+            error("budget.cost > 5 run.failed > 1"),
+            "unexpected 'run.failed' in exit_when 'budget.cost > 5 run.failed > 1'"
+        );
+        assert_eq!(error("(budget.cost > 5"), "missing ')' in exit_when");
+        assert_eq!(
+            error("budget.cost > $5"),
+            "unexpected '$' in exit_when 'budget.cost > $5'"
+        );
+        assert_eq!(error("  "), "exit_when is empty");
+    }
+}
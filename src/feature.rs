@@ -1,9 +1,11 @@
 //! Feature management: CRUD operations and file management.
 
-use anyhow::{anyhow, Context, Result};
-use std::path::Path;
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
-use crate::dag::{generate_feature_id, Db};
+use crate::dag::{self, generate_feature_id, Db};
+use crate::review::DocumentKind;
 
 /// A feature in the DAG.
 #[derive(Debug, Clone)]
@@ -13,6 +15,33 @@ pub struct Feature {
     pub spec_path: Option<String>,
     pub plan_path: Option<String>,
     pub status: String,
+    /// Latest spec revision stored under `history/` (0 = none yet).
+    pub spec_revision: i32,
+    /// Latest plan revision stored under `history/` (0 = none yet).
+    pub plan_revision: i32,
+    /// When the feature was archived; archived features are hidden from listings.
+    pub archived_at: Option<String>,
+    /// Git HEAD when the latest plan revision was recorded; the baseline for
+    /// `ralph feature replan`.
+    pub plan_commit: Option<String>,
+}
+
+/// The standard column list for feature queries.
+const FEATURE_COLUMNS: &str =
+    "id, name, spec_path, plan_path, status, spec_revision, plan_revision, archived_at, plan_commit";
+
+fn feature_from_row(row: &rusqlite::Row) -> rusqlite::Result<Feature> {
+    Ok(Feature {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        spec_path: row.get(2)?,
+        plan_path: row.get(3)?,
+        status: row.get(4)?,
+        spec_revision: row.get(5)?,
+        plan_revision: row.get(6)?,
+        archived_at: row.get(7)?,
+        plan_commit: row.get(8)?,
+    })
 }
 
 /// Create a new feature in the database.
@@ -44,6 +73,10 @@ pub fn create_feature(db: &Db, name: &str) -> Result<Feature> {
         spec_path: None,
         plan_path: None,
         status: "draft".to_string(),
+        spec_revision: 0,
+        plan_revision: 0,
+        archived_at: None,
+        plan_commit: None,
     })
 }
 
@@ -51,17 +84,9 @@ pub fn create_feature(db: &Db, name: &str) -> Result<Feature> {
 pub fn get_feature(db: &Db, name: &str) -> Result<Feature> {
     db.conn()
         .query_row(
-            "SELECT id, name, spec_path, plan_path, status FROM features WHERE name = ?",
+            &format!("SELECT {FEATURE_COLUMNS} FROM features WHERE name = ?"),
             [name],
-            |row| {
-                Ok(Feature {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    spec_path: row.get(2)?,
-                    plan_path: row.get(3)?,
-                    status: row.get(4)?,
-                })
-            },
+            feature_from_row,
         )
         .context(format!("Feature '{}' not found", name))
 }
@@ -70,37 +95,34 @@ pub fn get_feature(db: &Db, name: &str) -> Result<Feature> {
 pub fn get_feature_by_id(db: &Db, id: &str) -> Result<Feature> {
     db.conn()
         .query_row(
-            "SELECT id, name, spec_path, plan_path, status FROM features WHERE id = ?",
+            &format!("SELECT {FEATURE_COLUMNS} FROM features WHERE id = ?"),
             [id],
-            |row| {
-                Ok(Feature {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    spec_path: row.get(2)?,
-                    plan_path: row.get(3)?,
-                    status: row.get(4)?,
-                })
-            },
+            feature_from_row,
         )
         .context(format!("Feature with id '{}' not found", id))
 }
 
-/// List all features.
+/// List all features that are not archived.
 pub fn list_features(db: &Db) -> Result<Vec<Feature>> {
-    let mut stmt = db.conn().prepare(
-        "SELECT id, name, spec_path, plan_path, status FROM features ORDER BY created_at ASC",
-    )?;
+    let mut stmt = db.conn().prepare(&format!(
+        "SELECT {FEATURE_COLUMNS} FROM features WHERE archived_at IS NULL ORDER BY created_at ASC"
+    ))?;
 
     let features = stmt
-        .query_map([], |row| {
-            Ok(Feature {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                spec_path: row.get(2)?,
-                plan_path: row.get(3)?,
-                status: row.get(4)?,
-            })
-        })?
+        .query_map([], feature_from_row)?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(features)
+}
+
+/// List archived features, most recently archived first.
+pub fn list_archived_features(db: &Db) -> Result<Vec<Feature>> {
+    let mut stmt = db.conn().prepare(&format!(
+        "SELECT {FEATURE_COLUMNS} FROM features WHERE archived_at IS NOT NULL ORDER BY archived_at DESC"
+    ))?;
+
+    let features = stmt
+        .query_map([], feature_from_row)?
         .collect::<Result<Vec<_>, _>>()?;
 
     Ok(features)
@@ -173,6 +195,38 @@ pub fn read_plan(project_root: &Path, name: &str) -> Result<String> {
         .with_context(|| format!("Failed to read plan: {}", plan_path.display()))
 }
 
+/// Line the authoring prompts ask the agent to end spec.md and plan.md with.
+/// A document without it after the session was cut off mid-write.
+pub const COMPLETE_MARKER: &str = "<!-- ralph:complete -->";
+
+/// A spec or plan as an authoring session left it.
+#[derive(Debug, PartialEq)]
+pub enum AuthoredDocument {
+    /// Never written.
+    Missing,
+    /// Ended with [`COMPLETE_MARKER`], which has been removed from the file.
+    Complete,
+    /// Written without the marker; holds the partial content.
+    Truncated(String),
+}
+
+/// Check a document an authoring session wrote for [`COMPLETE_MARKER`] and
+/// strip the marker, so review and later phases see only the document.
+pub fn finish_authored_document(path: &Path) -> Result<AuthoredDocument> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(AuthoredDocument::Missing),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let body = content.trim_end();
+    let Some(body) = body.strip_suffix(COMPLETE_MARKER) else {
+        return Ok(AuthoredDocument::Truncated(content));
+    };
+    std::fs::write(path, format!("{}\n", body.trim_end()))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(AuthoredDocument::Complete)
+}
+
 /// Delete a feature from the database.
 pub fn delete_feature(db: &Db, id: &str) -> Result<()> {
     let updated = db
@@ -194,6 +248,455 @@ pub fn feature_exists(db: &Db, name: &str) -> Result<bool> {
     Ok(exists)
 }
 
+/// Archive a feature instead of deleting it.
+///
+/// Moves `.ralph/features/<name>/` to `.ralph/archive/<name>/` and stamps
+/// `archived_at` on the feature and its tasks, which hides them from listings
+/// and ready queries. Returns the number of tasks archived.
+pub fn archive_feature(db: &Db, project_root: &Path, name: &str) -> Result<usize> {
+    let feature = get_feature(db, name)?;
+    if feature.archived_at.is_some() {
+        bail!("Feature '{}' is already archived", name);
+    }
+    ensure_nothing_in_progress(db, &feature)?;
+
+    let timestamp = chrono::Utc::now().to_rfc3339();
+    let tx = db.conn().unchecked_transaction()?;
+    tx.execute(
+        "UPDATE features SET archived_at = ?1, updated_at = ?1 WHERE id = ?2",
+        rusqlite::params![timestamp, feature.id],
+    )?;
+    let tasks = tx.execute(
+        "UPDATE tasks SET archived_at = ?1, updated_at = ?1
+         WHERE feature_id = ?2 AND archived_at IS NULL",
+        rusqlite::params![timestamp, feature.id],
+    )?;
+    move_feature_dir(
+        &project_root.join(".ralph/features").join(name),
+        &project_root.join(".ralph/archive").join(name),
+    )?;
+    tx.commit().context("Failed to archive feature")?;
+    Ok(tasks)
+}
+
+/// Archive a feature's active tasks, leaving the feature itself active, so
+/// its task DAG can be rebuilt from an edited plan. Returns the number of
+/// tasks archived.
+pub fn archive_tasks(db: &Db, feature_id: &str) -> Result<usize> {
+    ensure_nothing_in_progress(db, &get_feature_by_id(db, feature_id)?)?;
+    let timestamp = chrono::Utc::now().to_rfc3339();
+    Ok(db.conn().execute(
+        "UPDATE tasks SET archived_at = ?1, updated_at = ?1
+         WHERE feature_id = ?2 AND archived_at IS NULL",
+        rusqlite::params![timestamp, feature_id],
+    )?)
+}
+
+/// Fail if any of the feature's active tasks is in progress.
+pub fn ensure_nothing_in_progress(db: &Db, feature: &Feature) -> Result<()> {
+    let in_progress: i64 = db.conn().query_row(
+        "SELECT COUNT(*) FROM tasks
+         WHERE feature_id = ? AND status = 'in_progress' AND archived_at IS NULL",
+        [&feature.id],
+        |row| row.get(0),
+    )?;
+    if in_progress > 0 {
+        bail!(
+            "Feature '{}' has {} task(s) in progress; stop the run or reset them first",
+            feature.name,
+            in_progress
+        );
+    }
+    Ok(())
+}
+
+/// Bring an archived feature back: move its directory out of `.ralph/archive/`
+/// and clear `archived_at` on the feature and the tasks archived with it.
+/// Tasks superseded earlier by a DAG rebuild stay archived.
+pub fn restore_feature(db: &Db, project_root: &Path, name: &str) -> Result<usize> {
+    let feature = get_feature(db, name)?;
+    if feature.archived_at.is_none() {
+        bail!("Feature '{}' is not archived", name);
+    }
+
+    let timestamp = chrono::Utc::now().to_rfc3339();
+    let tx = db.conn().unchecked_transaction()?;
+    tx.execute(
+        "UPDATE features SET archived_at = NULL, updated_at = ?1 WHERE id = ?2",
+        rusqlite::params![timestamp, feature.id],
+    )?;
+    let tasks = tx.execute(
+        "UPDATE tasks SET archived_at = NULL, updated_at = ?1
+         WHERE feature_id = ?2 AND archived_at = ?3",
+        rusqlite::params![timestamp, feature.id, feature.archived_at],
+    )?;
+    move_feature_dir(
+        &project_root.join(".ralph/archive").join(name),
+        &project_root.join(".ralph/features").join(name),
+    )?;
+    tx.commit().context("Failed to restore feature")?;
+    Ok(tasks)
+}
+
+/// What [`clone_feature`] created.
+#[derive(Debug)]
+pub struct CloneSummary {
+    pub feature: Feature,
+    /// Tasks copied (as pending) into the new feature.
+    pub tasks: usize,
+    /// Dependency edges recreated between the copies.
+    pub dependencies: usize,
+}
+
+/// Copy a feature's spec, plan and unresolved tasks into a new feature, so an
+/// alternative plan can be tried without touching the original or its history.
+///
+/// Unresolved tasks are copied as pending with fresh IDs, keeping their
+/// parents, criteria and the dependencies between them. A blocker outside the
+/// copy is kept while it is unresolved and dropped once it is done or failed.
+/// The clone's spec and plan start a revision history of their own.
+pub fn clone_feature(
+    db: &Db,
+    project_root: &Path,
+    name: &str,
+    new_name: &str,
+    created_by: Option<&str>,
+) -> Result<CloneSummary> {
+    let source = get_feature(db, name)?;
+    if source.archived_at.is_some() {
+        bail!("Feature '{}' is archived; restore it before cloning", name);
+    }
+
+    let clone_dir = project_root.join(".ralph/features").join(new_name);
+    if clone_dir.exists() {
+        bail!("{} already exists", clone_dir.display());
+    }
+
+    let tx = db.conn().unchecked_transaction()?;
+    let clone = create_feature(db, new_name)?;
+    ensure_feature_dirs(project_root, new_name)?;
+    let mut has_plan = false;
+    for kind in [DocumentKind::Spec, DocumentKind::Plan] {
+        let from = document_path(project_root, name, kind);
+        if !from.exists() {
+            continue;
+        }
+        let to = document_path(project_root, new_name, kind);
+        std::fs::copy(&from, &to)
+            .with_context(|| format!("Failed to copy {} to {}", from.display(), to.display()))?;
+        let to = to.to_string_lossy();
+        match kind {
+            DocumentKind::Spec => update_feature_spec_path(db, &clone.id, &to)?,
+            DocumentKind::Plan => {
+                update_feature_plan_path(db, &clone.id, &to)?;
+                has_plan = true;
+            }
+        }
+        record_revision(db, project_root, &get_feature_by_id(db, &clone.id)?, kind)?;
+    }
+
+    let (mut pending, others): (Vec<dag::Task>, Vec<dag::Task>) = dag::get_all_tasks(db)?
+        .into_iter()
+        .filter(|t| t.status != "done" && t.status != "failed")
+        .partition(|t| t.feature_id.as_deref() == Some(source.id.as_str()));
+    let unresolved: HashSet<String> = pending
+        .iter()
+        .chain(&others)
+        .map(|t| t.id.clone())
+        .collect();
+    // Copy parents before their children so each child's parent has its new ID.
+    let mut ids: HashMap<String, String> = HashMap::new();
+    while !pending.is_empty() {
+        let (ready, rest): (Vec<_>, Vec<_>) = pending.into_iter().partition(|t| {
+            t.parent_id
+                .as_ref()
+                .is_none_or(|p| ids.contains_key(p) || !unresolved.contains(p))
+        });
+        if ready.is_empty() {
+            bail!("Feature '{}' has a parent cycle among its tasks", name);
+        }
+        for task in ready {
+            let criteria: Vec<String> = dag::get_task_criteria(db, &task.id)?
+                .into_iter()
+                .map(|c| c.description)
+                .collect();
+            let copy = dag::create_task_with_feature(
+                db,
+                dag::CreateTaskParams {
+                    title: &task.title,
+                    description: Some(task.description.as_str()).filter(|d| !d.is_empty()),
+                    parent_id: task
+                        .parent_id
+                        .as_ref()
+                        .and_then(|p| ids.get(p))
+                        .map(String::as_str),
+                    priority: task.priority,
+                    feature_id: Some(&clone.id),
+                    task_type: &task.task_type,
+                    max_retries: task.max_retries,
+                    criteria: &criteria,
+                    discovered_by: task
+                        .discovered_by
+                        .as_ref()
+                        .and_then(|d| ids.get(d))
+                        .map(String::as_str),
+                    cwd: task.cwd.as_deref(),
+                    created_by,
+                },
+            )?;
+            if task.plan_ref.is_some() {
+                dag::set_task_plan_ref(db, &copy.id, task.plan_ref.as_deref())?;
+            }
+            dag::add_labels(db, &copy.id, &dag::get_task_labels(db, &task.id)?)?;
+            dag::add_log(db, &copy.id, &format!("Cloned from {} ({})", task.id, name))?;
+            ids.insert(task.id, copy.id);
+        }
+        pending = rest;
+    }
+
+    let mut dependencies = 0;
+    for (old, new) in &ids {
+        for blocker in dag::get_task_blockers(db, old)? {
+            let blocker_id = match ids.get(&blocker.id) {
+                Some(copy) => copy.as_str(),
+                None if unresolved.contains(&blocker.id) => blocker.id.as_str(),
+                None => continue,
+            };
+            dag::add_dependency(db, blocker_id, new)?;
+            dependencies += 1;
+        }
+    }
+
+    let status = if !ids.is_empty() {
+        "ready"
+    } else if has_plan {
+        "planned"
+    } else {
+        "draft"
+    };
+    update_feature_status(db, &clone.id, status)?;
+    tx.commit().context("Failed to clone feature")?;
+
+    Ok(CloneSummary {
+        feature: get_feature_by_id(db, &clone.id)?,
+        tasks: ids.len(),
+        dependencies,
+    })
+}
+
+/// Move a feature directory, refusing to overwrite. A missing source is fine:
+/// features created with `task add --feature` may have no files yet.
+fn move_feature_dir(from: &Path, to: &Path) -> Result<()> {
+    if !from.exists() {
+        return Ok(());
+    }
+    if to.exists() {
+        bail!("{} already exists", to.display());
+    }
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::rename(from, to)
+        .with_context(|| format!("Failed to move {} to {}", from.display(), to.display()))
+}
+
+/// Path of a stored revision: `.ralph/features/<name>/history/<kind>.v<N>.md`.
+pub fn revision_path(
+    project_root: &Path,
+    name: &str,
+    kind: DocumentKind,
+    revision: i32,
+) -> PathBuf {
+    project_root
+        .join(".ralph/features")
+        .join(name)
+        .join("history")
+        .join(format!("{}.v{}.md", kind.label(), revision))
+}
+
+fn document_path(project_root: &Path, name: &str, kind: DocumentKind) -> PathBuf {
+    project_root
+        .join(".ralph/features")
+        .join(name)
+        .join(format!("{}.md", kind.label()))
+}
+
+fn current_revision(feature: &Feature, kind: DocumentKind) -> i32 {
+    match kind {
+        DocumentKind::Spec => feature.spec_revision,
+        DocumentKind::Plan => feature.plan_revision,
+    }
+}
+
+/// Store the current spec or plan as a new revision if it changed.
+///
+/// Copies the working document into `history/` and bumps the revision number
+/// on the feature row. A plan revision also records the git HEAD, which
+/// `ralph feature replan` diffs against. Returns the new revision, or `None`
+/// when the document is missing or identical to the latest stored revision.
+pub fn record_revision(
+    db: &Db,
+    project_root: &Path,
+    feature: &Feature,
+    kind: DocumentKind,
+) -> Result<Option<i32>> {
+    let doc = document_path(project_root, &feature.name, kind);
+    if !doc.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&doc)
+        .with_context(|| format!("Failed to read {}", doc.display()))?;
+
+    // Re-read the revision from the DB so repeated calls with a stale
+    // `Feature` don't overwrite history.
+    let latest = current_revision(&get_feature_by_id(db, &feature.id)?, kind);
+    if latest > 0 {
+        let previous = read_revision(project_root, &feature.name, kind, latest)?;
+        if previous == content {
+            return Ok(None);
+        }
+    }
+
+    let next = latest + 1;
+    let path = revision_path(project_root, &feature.name, kind, next);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::write(&path, &content)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    let timestamp = chrono::Utc::now().to_rfc3339();
+    match kind {
+        DocumentKind::Spec => db.conn().execute(
+            "UPDATE features SET spec_revision = ?, updated_at = ? WHERE id = ?",
+            rusqlite::params![next, timestamp, feature.id],
+        ),
+        DocumentKind::Plan => db.conn().execute(
+            "UPDATE features SET plan_revision = ?, plan_commit = ?, updated_at = ? WHERE id = ?",
+            rusqlite::params![next, head_commit(project_root), timestamp, feature.id],
+        ),
+    }
+    .with_context(|| format!("Failed to record {} revision", kind.label()))?;
+
+    Ok(Some(next))
+}
+
+/// Mark the feature's plan as matching the code at the current git HEAD, so
+/// the next `ralph feature replan` only sees later changes.
+pub fn update_plan_commit(db: &Db, project_root: &Path, feature_id: &str) -> Result<()> {
+    db.conn()
+        .execute(
+            "UPDATE features SET plan_commit = ?, updated_at = ? WHERE id = ?",
+            rusqlite::params![
+                head_commit(project_root),
+                chrono::Utc::now().to_rfc3339(),
+                feature_id
+            ],
+        )
+        .context("Failed to record the plan commit")?;
+    Ok(())
+}
+
+/// The commit checked out in `project_root`, if it is a git repository.
+fn head_commit(project_root: &Path) -> Option<String> {
+    let out = std::process::Command::new("git")
+        .arg("-C")
+        .arg(project_root)
+        .args(["rev-parse", "HEAD"])
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    out.status
+        .success()
+        .then(|| String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
+/// Read a stored revision of a feature's spec or plan.
+pub fn read_revision(
+    project_root: &Path,
+    name: &str,
+    kind: DocumentKind,
+    revision: i32,
+) -> Result<String> {
+    let path = revision_path(project_root, name, kind, revision);
+    std::fs::read_to_string(&path).with_context(|| {
+        format!(
+            "No {} revision {} for feature '{}' ({})",
+            kind.label(),
+            revision,
+            name,
+            path.display()
+        )
+    })
+}
+
+/// Render a unified diff between two revisions of a feature document.
+///
+/// `from` defaults to the revision before the working copy: the latest stored
+/// revision if the working file has changed since, otherwise the one before it.
+/// `to` defaults to the working file. Returns an empty string when identical.
+pub fn diff_revisions(
+    project_root: &Path,
+    feature: &Feature,
+    kind: DocumentKind,
+    from: Option<i32>,
+    to: Option<i32>,
+) -> Result<String> {
+    let latest = current_revision(feature, kind);
+    if latest == 0 {
+        bail!(
+            "Feature '{}' has no recorded {} revisions",
+            feature.name,
+            kind.label()
+        );
+    }
+
+    let (new_label, new_text) = match to {
+        Some(rev) => (
+            format!("{}.v{}", kind.label(), rev),
+            read_revision(project_root, &feature.name, kind, rev)?,
+        ),
+        None => {
+            let doc = document_path(project_root, &feature.name, kind);
+            let text = std::fs::read_to_string(&doc)
+                .with_context(|| format!("Failed to read {}", doc.display()))?;
+            (format!("{}.md", kind.label()), text)
+        }
+    };
+
+    let from = match from {
+        Some(rev) => rev,
+        None => {
+            let stored = read_revision(project_root, &feature.name, kind, latest)?;
+            if to.is_none() && stored != new_text {
+                latest
+            } else {
+                to.unwrap_or(latest) - 1
+            }
+        }
+    };
+    if from < 1 {
+        bail!(
+            "Feature '{}' has only one {} revision; nothing to compare",
+            feature.name,
+            kind.label()
+        );
+    }
+    let old_text = read_revision(project_root, &feature.name, kind, from)?;
+    let old_label = format!("{}.v{}", kind.label(), from);
+
+    if old_text == new_text {
+        return Ok(String::new());
+    }
+    Ok(similar::TextDiff::from_lines(&old_text, &new_text)
+        .unified_diff()
+        .context_radius(3)
+        .header(&old_label, &new_label)
+        .to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -213,6 +716,29 @@ mod tests {
         assert!(feature.plan_path.is_none());
     }
 
+    #[test]
+    fn test_finish_authored_document() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("spec.md");
+        assert_eq!(
+            finish_authored_document(&path).unwrap(),
+            AuthoredDocument::Missing
+        );
+
+        std::fs::write(&path, "# Spec\n\n## Overview\n\nLogin with pass").unwrap();
+        assert_eq!(
+            finish_authored_document(&path).unwrap(),
+            AuthoredDocument::Truncated("# Spec\n\n## Overview\n\nLogin with pass".to_string())
+        );
+
+        std::fs::write(&path, format!("# Spec\n\nDone.\n\n{COMPLETE_MARKER}\n\n")).unwrap();
+        assert_eq!(
+            finish_authored_document(&path).unwrap(),
+            AuthoredDocument::Complete
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "# Spec\n\nDone.\n");
+    }
+
     #[test]
     fn test_create_duplicate_feature_fails() {
         let temp = NamedTempFile::new().unwrap();
@@ -224,6 +750,151 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("already exists"));
     }
 
+    #[test]
+    fn test_archive_and_restore_feature() {
+        let temp = NamedTempFile::new().unwrap();
+        let db = init_db(temp.path().to_str().unwrap()).unwrap();
+        let root = tempfile::TempDir::new().unwrap();
+        let root = root.path();
+
+        let feat = create_feature(&db, "auth").unwrap();
+        ensure_feature_dirs(root, "auth").unwrap();
+        std::fs::write(root.join(".ralph/features/auth/spec.md"), "spec").unwrap();
+        db.conn()
+            .execute(
+                "INSERT INTO tasks (id, title, status, feature_id, created_at, updated_at)
+                 VALUES ('t-arch0001', 'Task', 'pending', ?1, '2026-01-01', '2026-01-01')",
+                [&feat.id],
+            )
+            .unwrap();
+
+        assert_eq!(archive_feature(&db, root, "auth").unwrap(), 1);
+        assert!(root.join(".ralph/archive/auth/spec.md").exists());
+        assert!(!root.join(".ralph/features/auth").exists());
+        assert!(list_features(&db).unwrap().is_empty());
+        assert_eq!(list_archived_features(&db).unwrap().len(), 1);
+        assert!(crate::dag::get_ready_tasks(&db).unwrap().is_empty());
+        assert!(crate::dag::get_all_tasks(&db).unwrap().is_empty());
+        assert!(archive_feature(&db, root, "auth").is_err());
+
+        assert_eq!(restore_feature(&db, root, "auth").unwrap(), 1);
+        assert!(root.join(".ralph/features/auth/spec.md").exists());
+        assert_eq!(list_features(&db).unwrap().len(), 1);
+        assert_eq!(crate::dag::get_ready_tasks(&db).unwrap().len(), 1);
+        assert!(restore_feature(&db, root, "auth").is_err());
+    }
+
+    #[test]
+    fn test_archive_tasks_for_dag_rebuild() {
+        let temp = NamedTempFile::new().unwrap();
+        let db = init_db(temp.path().to_str().unwrap()).unwrap();
+        let root = tempfile::TempDir::new().unwrap();
+        let root = root.path();
+        let feat = create_feature(&db, "auth").unwrap();
+        for (id, status) in [("t-old00001", "in_progress"), ("t-old00002", "done")] {
+            db.conn()
+                .execute(
+                    "INSERT INTO tasks (id, title, status, feature_id, created_at, updated_at)
+                     VALUES (?1, 'Old', ?2, ?3, '2026-01-01', '2026-01-01')",
+                    rusqlite::params![id, status, feat.id],
+                )
+                .unwrap();
+        }
+
+        assert!(archive_tasks(&db, &feat.id).is_err());
+        db.conn()
+            .execute(
+                "UPDATE tasks SET status = 'pending' WHERE id = 't-old00001'",
+                [],
+            )
+            .unwrap();
+        assert_eq!(archive_tasks(&db, &feat.id).unwrap(), 2);
+        assert!(crate::dag::get_all_tasks(&db).unwrap().is_empty());
+        assert_eq!(list_features(&db).unwrap().len(), 1);
+        assert_eq!(archive_tasks(&db, &feat.id).unwrap(), 0);
+
+        // Restoring the feature later does not bring the superseded DAG back
+        archive_feature(&db, root, "auth").unwrap();
+        assert_eq!(restore_feature(&db, root, "auth").unwrap(), 0);
+        assert!(crate::dag::get_all_tasks(&db).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_clone_feature_copies_documents_and_unresolved_tasks() {
+        let temp = NamedTempFile::new().unwrap();
+        let db = init_db(temp.path().to_str().unwrap()).unwrap();
+        let root = tempfile::TempDir::new().unwrap();
+        let root = root.path();
+        let feat = create_feature(&db, "auth").unwrap();
+        ensure_feature_dirs(root, "auth").unwrap();
+        std::fs::write(root.join(".ralph/features/auth/spec.md"), "spec").unwrap();
+        std::fs::write(root.join(".ralph/features/auth/plan.md"), "plan").unwrap();
+        let task = |title: &str, parent: Option<&str>| {
+            let task = dag::create_test_task(&db, &feat.id, title, parent);
+            dag::add_criteria(&db, &task.id, &["works".to_string()]).unwrap();
+            task
+        };
+        let done = task("Done", None);
+        let parent = task("Parent", None);
+        let first = task("First", Some(&parent.id));
+        let second = task("Second", Some(&parent.id));
+        dag::add_labels(&db, &first.id, &["backend".to_string()]).unwrap();
+        let outside = dag::create_task(&db, "Outside", None, None, 1).unwrap();
+        dag::add_dependency(&db, &done.id, &first.id).unwrap();
+        dag::add_dependency(&db, &first.id, &second.id).unwrap();
+        dag::add_dependency(&db, &outside.id, &second.id).unwrap();
+        dag::force_complete_task(db.conn(), &done.id).unwrap();
+
+        let summary = clone_feature(&db, root, "auth", "auth-alt", Some("alice")).unwrap();
+        assert_eq!(summary.tasks, 3);
+        assert_eq!(summary.dependencies, 2);
+        assert_eq!(summary.feature.status, "ready");
+        assert_eq!(summary.feature.spec_revision, 1);
+        assert_eq!(summary.feature.plan_revision, 1);
+        assert_eq!(
+            std::fs::read_to_string(root.join(".ralph/features/auth-alt/plan.md")).unwrap(),
+            "plan"
+        );
+
+        let copies = dag::get_all_tasks_for_feature(&db, &summary.feature.id).unwrap();
+        let by_title = |t: &str| copies.iter().find(|c| c.title == t).unwrap();
+        assert!(copies.iter().all(|c| c.status == "pending"));
+        assert_eq!(
+            by_title("First").parent_id,
+            Some(by_title("Parent").id.clone())
+        );
+        assert_eq!(by_title("Second").created_by.as_deref(), Some("alice"));
+        assert_eq!(
+            dag::get_task_criteria(&db, &by_title("First").id)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            dag::get_task_labels(&db, &by_title("First").id).unwrap(),
+            ["backend"]
+        );
+        assert!(dag::get_task_blockers(&db, &by_title("First").id)
+            .unwrap()
+            .is_empty());
+        let mut blockers: Vec<String> = dag::get_task_blockers(&db, &by_title("Second").id)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.id)
+            .collect();
+        blockers.sort();
+        let mut expected = vec![by_title("First").id.clone(), outside.id.clone()];
+        expected.sort();
+        assert_eq!(blockers, expected);
+
+        // The original is untouched.
+        assert_eq!(
+            dag::get_all_tasks_for_feature(&db, &feat.id).unwrap().len(),
+            4
+        );
+        assert!(clone_feature(&db, root, "auth", "auth-alt", None).is_err());
+    }
+
     #[test]
     fn test_get_feature() {
         let temp = NamedTempFile::new().unwrap();
@@ -310,4 +981,82 @@ mod tests {
         ensure_feature_dirs(root, "auth").unwrap();
         assert!(root.join(".ralph/features/auth").is_dir());
     }
+
+    fn write_doc(root: &Path, name: &str, kind: DocumentKind, text: &str) {
+        let path = document_path(root, name, kind);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, text).unwrap();
+    }
+
+    #[test]
+    fn test_record_revision_only_on_change() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path();
+        let db = init_db(root.join("progress.db").to_str().unwrap()).unwrap();
+        let feat = create_feature(&db, "auth").unwrap();
+
+        // No spec yet.
+        assert_eq!(
+            record_revision(&db, root, &feat, DocumentKind::Spec).unwrap(),
+            None
+        );
+
+        write_doc(root, "auth", DocumentKind::Spec, "v1\n");
+        assert_eq!(
+            record_revision(&db, root, &feat, DocumentKind::Spec).unwrap(),
+            Some(1)
+        );
+        // Unchanged content does not create a new revision, even with a stale Feature.
+        assert_eq!(
+            record_revision(&db, root, &feat, DocumentKind::Spec).unwrap(),
+            None
+        );
+
+        write_doc(root, "auth", DocumentKind::Spec, "v2\n");
+        assert_eq!(
+            record_revision(&db, root, &feat, DocumentKind::Spec).unwrap(),
+            Some(2)
+        );
+
+        let feat = get_feature(&db, "auth").unwrap();
+        assert_eq!(feat.spec_revision, 2);
+        assert_eq!(feat.plan_revision, 0);
+        assert_eq!(
+            read_revision(root, "auth", DocumentKind::Spec, 1).unwrap(),
+            "v1\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_revisions_defaults() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path();
+        let db = init_db(root.join("progress.db").to_str().unwrap()).unwrap();
+        let feat = create_feature(&db, "auth").unwrap();
+
+        write_doc(root, "auth", DocumentKind::Plan, "step one\nstep two\n");
+        record_revision(&db, root, &feat, DocumentKind::Plan).unwrap();
+        let feat = get_feature(&db, "auth").unwrap();
+
+        // Single revision and unchanged working copy: nothing to compare.
+        assert!(diff_revisions(root, &feat, DocumentKind::Plan, None, None).is_err());
+
+        // Working copy edited since v1: compare v1 → working file.
+        write_doc(root, "auth", DocumentKind::Plan, "step one\nstep 2\n");
+        let diff = diff_revisions(root, &feat, DocumentKind::Plan, None, None).unwrap();
+        assert!(diff.contains("--- plan.v1"));
+        assert!(diff.contains("+++ plan.md"));
+        assert!(diff.contains("-step two"));
+        assert!(diff.contains("+step 2"));
+
+        // Once recorded, the default compares the two latest revisions.
+        record_revision(&db, root, &feat, DocumentKind::Plan).unwrap();
+        let feat = get_feature(&db, "auth").unwrap();
+        let diff = diff_revisions(root, &feat, DocumentKind::Plan, None, None).unwrap();
+        assert!(diff.contains("--- plan.v1"));
+        assert!(diff.contains("+step 2"));
+
+        let same = diff_revisions(root, &feat, DocumentKind::Plan, Some(2), Some(2)).unwrap();
+        assert!(same.is_empty());
+    }
 }
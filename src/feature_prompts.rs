@@ -1,6 +1,6 @@
 //! Prompt and context builders used by `feature create` and task-creation flows.
 
-use crate::{dag, feature, project};
+use crate::{context_cache, dag, feature, plan_coverage, project};
 
 pub const MAX_CONTEXT_FILE_CHARS: usize = 10_000;
 
@@ -160,16 +160,11 @@ After writing the plan file, STOP. Do not implement anything."#,
     )
 }
 
-pub fn build_feature_build_system_prompt(
-    spec_content: &str,
-    plan_content: &str,
-    root_id: &str,
-    feature_id: &str,
-) -> String {
+pub fn build_feature_build_system_prompt(spec_content: &str, plan_content: &str) -> String {
     format!(
         r#"You are a planning agent for Ralph, an autonomous AI agent loop that drives Claude Code.
 
-Decompose the feature's plan into a task DAG by creating tasks using the `ralph` CLI.
+Decompose the feature's plan into a task DAG and propose it as JSON. Ralph shows the proposal to the user, who accepts it, asks for changes, or rejects it. Ralph creates the tasks only after the user accepts.
 
 ## How Ralph Executes Tasks
 
@@ -185,68 +180,278 @@ Decompose the feature's plan into a task DAG by creating tasks using the `ralph`
 
 {plan_content}
 
-## Root Task
+## Plan Sections
 
-A root task has already been created for this feature:
-- **Root Task ID:** `{root_id}`
-- **Feature ID:** `{feature_id}`
+The anchors of the plan's sections, for each task's `plan_ref`:
 
-All tasks you create should be children of this root task (or children of other tasks you create under it).
+{plan_sections}
 
-## CLI Commands
+## Proposal Format
 
-Use these `ralph` commands via the Bash tool to create the task DAG:
+Emit the whole DAG as one JSON object inside a `<task-dag>` sigil. Top-level tasks go directly under the feature's root task:
 
-### Create a task
-```bash
-ralph task add "Short imperative title" \
-  -d "Detailed description: what to do, which files to touch, how to verify." \
-  --parent {root_id} \
-  --feature {feature_id}
 ```
-
-This prints the new task ID (e.g., `t-a1b2c3`) to stdout. Capture it:
-```bash
-ID=$(ralph task add "Title" -d "Description" --parent {root_id} --feature {feature_id})
+<task-dag>
+{{"tasks": [
+  {{"key": "models", "title": "Add data models", "plan_ref": "2-data-model", "children": [
+    {{"key": "schema",
+     "title": "Add users table migration",
+     "plan_ref": "21-users-table",
+     "description": "Plan §2.1: create the users table in migrations/. Verify with cargo test.",
+     "criteria": ["Migration applies to an empty database", "cargo test passes"]}}
+  ]}},
+  {{"title": "Add user lookup endpoint",
+   "plan_ref": "3-api",
+   "description": "Plan §3: GET /users/:id in src/api/users.rs.",
+   "criteria": ["GET /users/:id returns 404 for unknown ids"],
+   "depends_on": ["schema"]}}
+]}}
+</task-dag>
 ```
 
-### Create a child task under another task
-```bash
-CHILD=$(ralph task add "Child task" -d "Description" --parent $PARENT_ID --feature {feature_id})
-```
+Fields:
+- `title` (required): short imperative title
+- `description`: what to do, which files to touch, how to verify
+- `criteria`: acceptance criteria; the verification agent checks each one individually, so keep them concrete and testable
+- `children`: subtasks; a task with children is a parent that groups them and never executes
+- `key`: short name, needed only if another task lists it in `depends_on`
+- `depends_on`: keys of tasks that must complete first; never a task's own parent or child
+- `cwd`: directory the task works in, relative to the project root (e.g. `packages/api` in a monorepo); children inherit it. Omit it for the project root.
+- `plan_ref`: anchor of the plan section the task implements, from the list above; children without one inherit it
 
-### Add a dependency (A must complete before B)
-```bash
-ralph task deps add $BLOCKER_ID $BLOCKED_ID
-```
+Do NOT create tasks with `ralph task add` — Ralph inserts the accepted proposal itself.
 
 ## Decomposition Rules
 
 1. **Right-size tasks**: One coherent unit of work per task. Good tasks touch 1-3 files.
-2. **Reference spec/plan sections**: Each task description must reference which spec/plan section it implements
-3. **Include acceptance criteria**: Each task must include how to verify it's done
+2. **Reference spec/plan sections**: Each task description must reference which spec/plan section it implements, and each task sets `plan_ref` to that section's anchor (on the task or its parent). Every plan section should end up with a task; `ralph feature coverage` reports the ones that do not
+3. **Include acceptance criteria**: Each leaf task needs `criteria` describing how to verify it's done
 4. **Parent tasks for grouping**: Parents organize related children, they never execute
 5. **Dependencies for ordering**: Only when task B needs artifacts from task A
 6. **Foundation first**: Schemas and types before the code that uses them
 
-## Completion Signal
+## Instructions
+
+1. Read the spec and plan carefully, and the code where needed
+2. Group related work under parent tasks, with leaf tasks under each parent
+3. Add dependencies where order matters
+4. Emit the `<task-dag>` sigil, then `<phase-complete>build</phase-complete>`, and stop"#,
+        spec_content = spec_content,
+        plan_content = plan_content,
+        plan_sections = plan_coverage::render_sections(&plan_coverage::plan_sections(plan_content)),
+    )
+}
+
+pub fn build_feature_replan_system_prompt(
+    name: &str,
+    spec_content: &str,
+    plan_content: &str,
+    plan_path: &str,
+    drift: &str,
+    context: &str,
+) -> String {
+    format!(
+        r#"You are helping the user update the implementation plan for feature "{name}".
+
+## Your Role
+
+The plan below was written against an older state of the codebase. Since then the code has changed — by hand, by other features, or by ralph's own tasks. Work out where the plan no longer matches the code and rewrite it so it does.
+
+## Scope — PLANNING DOCUMENT ONLY
+
+Your ONLY deliverable is the updated plan at `{plan_path}`. You must NOT write or modify source code, tests, or configuration, run build or test commands, or create tasks. Ralph reconciles the task DAG with the new plan after you finish.
+
+## Workflow
+
+1. **Compare** — Read the code changes below and, where needed, the current code. List the parts of the plan that are already done differently, obsolete, or now missing.
+2. **Confirm** — Summarize the drift for the user and ask ONE question at a time about anything that needs a decision (e.g. keep a manual change or restore the planned design).
+3. **Rewrite** — Update `{plan_path}` in place. Keep sections that still hold unchanged, so the tasks built from them keep their meaning; mark work that the changes already completed as done rather than deleting it.
+4. **Signal completion** — Tell the user the plan is updated and emit `<phase-complete>plan</phase-complete>`. Do NOT continue working.
+
+{context}
+
+## Specification
+
+{spec_content}
+
+## Current Plan
+
+{plan_content}
+
+## Code Changes Since the Plan Was Written
+
+{drift}
+
+After updating the plan file, STOP. Do not implement anything."#,
+    )
+}
+
+pub fn build_feature_reconcile_system_prompt(
+    spec_content: &str,
+    plan_content: &str,
+    existing_tasks: &str,
+) -> String {
+    format!(
+        r#"You are a planning agent for Ralph, an autonomous AI agent loop that drives Claude Code.
+
+The feature's plan was just updated. Propose the feature's task DAG as it should be under the new plan, starting from the tasks it already has. Ralph shows the proposal to the user and applies it only after they accept.
+
+## Specification
+
+{spec_content}
+
+## Updated Plan
+
+{plan_content}
+
+## Existing Tasks
+
+Each line shows the task's ID, status and title, then its description and acceptance criteria:
+
+{existing_tasks}
+
+## Proposal Format
+
+Emit the whole DAG as one JSON object inside a `<task-dag>` sigil, in the same format as the original decomposition, plus an `id` field for tasks that already exist:
+
+```
+<task-dag>
+{{"tasks": [
+  {{"id": "t-1a2b3c", "key": "schema", "title": "Add users table migration",
+   "plan_ref": "21-users-table",
+   "description": "Plan §2.1 (updated): the table now lives in db/migrations/.",
+   "criteria": ["Migration applies to an empty database"]}},
+  {{"title": "Add user lookup endpoint",
+   "plan_ref": "3-api",
+   "description": "Plan §3: GET /users/:id in src/api/users.rs.",
+   "criteria": ["GET /users/:id returns 404 for unknown ids"],
+   "depends_on": ["schema"]}}
+]}}
+</task-dag>
+```
+
+`plan_ref` is the anchor of the plan section a task implements, the heading lowercased with spaces as hyphens and punctuation dropped (`## 2.1 Users table` is `21-users-table`).
 
-When you are done creating all tasks and dependencies, emit the completion sigil:
-`<phase-complete>build</phase-complete>`
+Reconciliation rules:
+- **Done tasks are kept as they are.** Leave them out, or list one with its `id` and a `key` only when a new task must depend on it; its other fields are ignored and nothing new may go under it.
+- **Pending, blocked or failed tasks** the plan still needs: list them with their `id` and the updated title, description, criteria, `cwd` and `plan_ref`. Failed tasks go back to pending.
+- **In-progress tasks** are being worked on and are kept even if you leave them out.
+- **Tasks the plan no longer needs**: leave them out and Ralph deletes them.
+- **New work**: tasks without an `id`. They go under the task they are nested in.
+- An existing unfinished task moves under the task you nest it in, so nest it where it already is unless it should move. Nothing can move under a done task.
+- `key` and `depends_on` work as before; existing dependencies are kept.
 
-This tells Ralph the task DAG is complete and it can print a summary.
+Do NOT create tasks with `ralph task add` — Ralph applies the accepted proposal itself.
 
 ## Instructions
 
-1. Read the spec and plan carefully
-2. Create parent tasks for logical groupings (as children of `{root_id}`)
-3. Create leaf tasks under each parent
-4. Add dependencies between tasks where order matters
-5. Emit `<phase-complete>build</phase-complete>` when done"#,
-        spec_content = spec_content,
-        plan_content = plan_content,
-        root_id = root_id,
-        feature_id = feature_id,
+1. Compare the updated plan with the existing tasks, reading the code where needed
+2. Emit the `<task-dag>` sigil, then `<phase-complete>build</phase-complete>`, and stop"#,
+    )
+}
+
+pub fn build_unblock_system_prompt(failures: &str) -> String {
+    format!(
+        r#"You are a planning agent for Ralph, an autonomous AI agent loop that drives Claude Code.
+
+Ralph's run stopped: the tasks below cannot start because tasks they depend on failed. Work out why those tasks failed and propose edits to the task DAG that let the work continue. Ralph shows the edits to the user and applies them only after they accept.
+
+## Scope — DIAGNOSIS AND PROPOSAL ONLY
+
+Read the failure details, the code, and run read-only commands (e.g. the failing tests) to find the cause. Do NOT fix the code yourself and do NOT create tasks with `ralph task add` — the tasks you propose do the work.
+
+{failures}
+
+## Proposal Format
+
+Emit the edits as one JSON object inside a `<dag-edits>` sigil. Every field is optional, but propose at least one edit:
+
+```
+<dag-edits>
+{{"tasks": [
+  {{"key": "pin-sqlx",
+   "title": "Pin sqlx to 0.7 so the migration compiles",
+   "description": "The schema task failed on the sqlx 0.8 API change (see its log). Pin sqlx in Cargo.toml.",
+   "criteria": ["cargo build succeeds"],
+   "parent": "t-abc123"}}
+ ],
+ "add_dependencies": [{{"blocker": "pin-sqlx", "blocked": "t-def456"}}],
+ "remove_dependencies": [{{"blocker": "t-0a1b2c", "blocked": "t-def456"}}],
+ "retry": ["t-0a1b2c"]}}
+</dag-edits>
+```
+
+- `tasks`: remediation tasks to create. `key` names the task in dependencies; `parent` is an existing task ID to nest it under (default: beside the first blocked task); `depends_on` lists keys or task IDs that must finish first.
+- `add_dependencies` / `remove_dependencies`: edges between existing task IDs or new task keys. Remove a dependency only if the blocked task really does not need the failed one.
+- `retry`: failed task IDs to reset to pending, typically after a remediation task fixes what made them fail. Make each retried task depend on its fix.
+
+## Instructions
+
+1. Diagnose each failure from its log, journal notes and criteria, reading the code where needed
+2. Emit the `<dag-edits>` sigil with the smallest set of edits that unblocks the work, and stop"#,
+    )
+}
+
+pub fn build_dag_review_system_prompt(spec: &str, plan: &str, tasks: &str) -> String {
+    format!(
+        r#"You are a reviewer for Ralph, an autonomous AI agent loop that drives Claude Code.
+
+Ralph just created the task DAG below from the feature's plan. Each leaf task is handed to a fresh session that sees only the task, its parent, its finished prerequisites, the spec and the plan. Check the DAG against the plan before any task runs.
+
+## Scope — REVIEW ONLY
+
+Read the code where needed, but do not change files and do not create tasks with `ralph task add`. Ralph shows your suggestions to the user and applies the edits they accept.
+
+## Specification
+
+{spec}
+
+## Plan
+
+{plan}
+
+## Task DAG
+
+{tasks}
+
+## What to Check
+
+- **coverage**: every part of the plan is done by some task
+- **granularity**: each leaf fits one session; no task is a trivial fragment of another
+- **tests**: behaviour the plan introduces is tested by some task's criteria or a dedicated task
+- **docs**: user-facing changes come with documentation work
+- **dependencies**: a task that needs another's output depends on it; no needless ordering
+
+## Suggestion Format
+
+Emit one JSON object inside a `<dag-review>` sigil. An empty `suggestions` list means the DAG is fine:
+
+```
+<dag-review>
+{{"suggestions": [
+  {{"category": "tests",
+   "issue": "Nothing tests the 404 path of GET /users/:id (plan §3).",
+   "edits": {{"tasks": [{{"key": "lookup-tests",
+                         "title": "Test user lookup errors",
+                         "description": "Plan §3: cover unknown and malformed ids.",
+                         "criteria": ["cargo test users:: passes"],
+                         "depends_on": ["t-def456"]}}]}}}},
+  {{"category": "dependencies",
+   "issue": "The endpoint reads the users table but does not wait for its migration.",
+   "edits": {{"add_dependencies": [{{"blocker": "t-abc123", "blocked": "t-def456"}}]}}}},
+  {{"category": "granularity",
+   "issue": "t-0a1b2c covers the whole API layer; split it by endpoint."}}
+]}}
+</dag-review>
+```
+
+- `category`: one of `coverage`, `granularity`, `tests`, `docs`, `dependencies`.
+- `edits` (optional): the same edits as a blocked-run planning session — `tasks` to create (`parent` defaults to the feature's root task), `add_dependencies` and `remove_dependencies` between task IDs or new task keys. Keys are local to one suggestion. Leave `edits` out when the fix needs a person.
+
+## Instructions
+
+1. Compare the DAG with the plan, reading the code where needed
+2. Emit the `<dag-review>` sigil with only the suggestions worth acting on, and stop"#,
     )
 }
 
@@ -280,11 +485,13 @@ Interview the user about what they want done, then create a standalone task in t
 
 After gathering requirements, create the task by running the `ralph task add` command via the terminal.
 
-Usage: `ralph task add <TITLE> -d <DESCRIPTION> --priority <N>`
+Usage: `ralph task add <TITLE> -d <DESCRIPTION> --priority <N> [--criterion <TEXT>]... [--cwd <DIR>]`
+
+Pass each acceptance criterion as its own `--criterion` flag; the verification agent checks them one by one. In a monorepo, pass `--cwd` with the package directory (relative to the project root) the task works in.
 
 Example:
 ```
-ralph task add "Refactor auth module" -d "Extract token validation into a separate function. Acceptance criteria: all existing tests pass, new unit test for the extracted function." --priority 0
+ralph task add "Refactor auth module" -d "Extract token validation into a separate function." --criterion "All existing tests pass" --criterion "New unit test covers the extracted function" --priority 0
 ```
 
 The command prints the new task ID on success. Confirm the task was created by showing the ID to the user.
@@ -326,13 +533,11 @@ pub fn gather_project_context(
     db: &dag::Db,
     include_tasks: bool,
 ) -> String {
-    use std::fs;
-
     let mut sections = Vec::new();
 
     // Read CLAUDE.md
     let claude_md_path = project.root.join("CLAUDE.md");
-    let claude_md = fs::read_to_string(&claude_md_path).ok();
+    let claude_md = context_cache::read_to_string(&claude_md_path);
     let claude_md_section = if let Some(content) = claude_md {
         let truncated = truncate_context(&content, MAX_CONTEXT_FILE_CHARS, "CLAUDE.md");
         format!("### CLAUDE.md\n\n{}", truncated)
@@ -343,7 +548,7 @@ pub fn gather_project_context(
 
     // Read .ralph.toml
     let config_path = project.root.join(".ralph.toml");
-    if let Ok(config_content) = fs::read_to_string(&config_path) {
+    if let Some(config_content) = context_cache::read_to_string(&config_path) {
         sections.push(format!(
             "### Configuration (.ralph.toml)\n\n```toml\n{}\n```",
             config_content
@@ -385,6 +590,31 @@ pub fn gather_project_context(
     format!("## Project Context\n\n{}", sections.join("\n\n"))
 }
 
+/// Section appended to spec and plan authoring prompts, after any template,
+/// asking for [`feature::COMPLETE_MARKER`] as the document's last line.
+pub fn build_completion_marker_section(doc_path: &str) -> String {
+    format!(
+        "## Completion Marker\n\n\
+         When the document is finished, end `{doc_path}` with this line on its own:\n\n\
+         {marker}\n\n\
+         Ralph removes it afterwards. A document without it is treated as cut off \
+         mid-write, and the session is resumed.",
+        marker = feature::COMPLETE_MARKER
+    )
+}
+
+/// Section handing a resumed authoring session the draft the previous one
+/// left without its completion marker.
+pub fn build_resume_draft_section(doc_label: &str, doc_path: &str, draft: &str) -> String {
+    format!(
+        "## Current {doc_label} Draft (incomplete)\n\n\
+         The previous session ended before finishing `{doc_path}`. This draft has no \
+         completion marker and may stop mid-sentence. Continue from it: ask the user only \
+         about what is still missing, then rewrite the whole file, ending with the marker.\n\n\
+         {draft}"
+    )
+}
+
 /// Build initial message for feature spec interview.
 pub fn build_initial_message_spec(name: &str, resuming: bool) -> String {
     if resuming {
@@ -412,6 +642,40 @@ pub fn build_initial_message_plan(name: &str, resuming: bool) -> String {
     }
 }
 
+/// Build initial message for task DAG decomposition: a first proposal, or a
+/// revision of `previous` (its JSON and the user's feedback).
+pub fn build_initial_message_build(previous: Option<(&str, &str)>) -> String {
+    match previous {
+        None => "Read the spec and plan, then propose the task DAG in a <task-dag> sigil and stop."
+            .to_string(),
+        Some((proposal, feedback)) => format!(
+            "Revise your proposed task DAG.\n\nPrevious proposal:\n\n{}\n\nRequested changes: {}\n\nEmit the complete revised DAG in a <task-dag> sigil and stop.",
+            proposal, feedback
+        ),
+    }
+}
+
+/// Build initial message for the plan update of `ralph feature replan`.
+pub fn build_initial_message_replan(name: &str) -> String {
+    format!(
+        "Compare the plan for feature \"{}\" with the code changes in your system prompt, tell me where they diverge, then update the plan.",
+        name
+    )
+}
+
+/// Build initial message for the blocked-run planning session, or for a
+/// revision of its `previous` proposal with the user's feedback.
+pub fn build_initial_message_unblock(previous: Option<(&str, &str)>) -> String {
+    match previous {
+        None => "Diagnose the failures, then propose DAG edits in a <dag-edits> sigil and stop."
+            .to_string(),
+        Some((proposal, feedback)) => format!(
+            "Revise your proposed DAG edits.\n\nPrevious proposal:\n\n{}\n\nRequested changes: {}\n\nEmit the complete revised edits in a <dag-edits> sigil and stop.",
+            proposal, feedback
+        ),
+    }
+}
+
 /// Build initial message for task creation interview.
 pub fn build_initial_message_task_new() -> String {
     "Start the task creation interview.".to_string()
@@ -0,0 +1,204 @@
+//! Flaky verification detection.
+//!
+//! Every fresh verdict is appended to `verification_history` together with
+//! the fingerprint it was reached under (see [`verification::fingerprint`])
+//! and the files the task touched. A pass whose fingerprint equals that of
+//! the task's previous failure means nothing changed between the two runs:
+//! the failure was flaky. The pass is flagged, and its files become a
+//! known-flaky area.
+//!
+//! A task touching a known-flaky area gets `[execution] flaky_retry_bonus`
+//! extra retries, never reuses a cached failed verdict, and its verifier is
+//! told to re-run a failing check before rejecting the work.
+//!
+//! History rows deliberately carry no foreign key: what they record is about
+//! files, and it stays useful after the task that surfaced it is deleted.
+//!
+//! [`verification::fingerprint`]: crate::verification::fingerprint
+
+use anyhow::{Context, Result};
+use rusqlite::OptionalExtension;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::dag::Db;
+
+/// Verification and flake counts for `ralph stats`.
+#[derive(Debug, Default, Serialize)]
+pub struct FlakeStats {
+    pub verifications: u32,
+    pub failed: u32,
+    pub flakes: u32,
+    /// `(path, flakes)`, most flaky first.
+    pub files: Vec<(String, u32)>,
+    /// `(task_id, flakes)`, most flaky first.
+    pub tasks: Vec<(String, u32)>,
+}
+
+/// Append a fresh verdict to the history. Returns whether it is a flake: a
+/// pass under the same fingerprint as the task's previous (failed) verdict.
+pub fn record(
+    db: &Db,
+    task_id: &str,
+    fingerprint: &str,
+    files: &[String],
+    passed: bool,
+) -> Result<bool> {
+    let previous: Option<(String, bool)> = db
+        .conn()
+        .query_row(
+            "SELECT fingerprint, passed FROM verification_history
+             WHERE task_id = ? ORDER BY id DESC LIMIT 1",
+            [task_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    let flaky = passed && matches!(previous, Some((ref fp, false)) if fp == fingerprint);
+    db.conn()
+        .execute(
+            "INSERT INTO verification_history (task_id, fingerprint, passed, flaky, files)
+             VALUES (?, ?, ?, ?, ?)",
+            rusqlite::params![
+                task_id,
+                fingerprint,
+                passed,
+                flaky,
+                serde_json::to_string(&area(files))?
+            ],
+        )
+        .context("Failed to record verification history")?;
+    Ok(flaky)
+}
+
+/// The known-flaky files among `files`, plus every file of a flake `task_id`
+/// itself was involved in. Empty when the task touches no flaky area.
+pub fn flaky_area(db: &Db, task_id: &str, files: &[String]) -> Result<Vec<String>> {
+    let mut stmt = db
+        .conn()
+        .prepare("SELECT task_id, files FROM verification_history WHERE flaky = 1")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let touched = area(files);
+    let mut flaky = Vec::new();
+    for (flake_task, json) in rows {
+        let flake_files: Vec<String> = serde_json::from_str(&json).unwrap_or_default();
+        for file in flake_files {
+            if (flake_task == task_id || touched.contains(&file)) && !flaky.contains(&file) {
+                flaky.push(file);
+            }
+        }
+    }
+    flaky.sort();
+    Ok(flaky)
+}
+
+/// Verification and flake counts across the whole history.
+pub fn stats(db: &Db) -> Result<FlakeStats> {
+    let (verifications, failed, flakes) = db.conn().query_row(
+        "SELECT COUNT(*), COALESCE(SUM(passed = 0), 0), COALESCE(SUM(flaky), 0)
+         FROM verification_history",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+
+    let mut stmt = db
+        .conn()
+        .prepare("SELECT task_id, files FROM verification_history WHERE flaky = 1")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut files: BTreeMap<String, u32> = BTreeMap::new();
+    let mut tasks: BTreeMap<String, u32> = BTreeMap::new();
+    for (task_id, json) in rows {
+        *tasks.entry(task_id).or_default() += 1;
+        for file in serde_json::from_str::<Vec<String>>(&json).unwrap_or_default() {
+            *files.entry(file).or_default() += 1;
+        }
+    }
+
+    Ok(FlakeStats {
+        verifications,
+        failed,
+        flakes,
+        files: most_first(files),
+        tasks: most_first(tasks),
+    })
+}
+
+/// `max_retries` for a task, raised by `bonus` when it touches a flaky area.
+pub fn max_retries(base: u32, bonus: u32, flaky_area: &[String]) -> u32 {
+    if flaky_area.is_empty() {
+        base
+    } else {
+        base.saturating_add(bonus)
+    }
+}
+
+/// Deduplicated project files, leaving out ralph's own `.ralph/` state.
+fn area(files: &[String]) -> Vec<String> {
+    let mut area: Vec<String> = Vec::new();
+    for file in files {
+        let file = file.trim_start_matches("./");
+        if !file.is_empty() && !file.starts_with(".ralph/") && !area.iter().any(|f| f == file) {
+            area.push(file.to_string());
+        }
+    }
+    area
+}
+
+fn most_first(counts: BTreeMap<String, u32>) -> Vec<(String, u32)> {
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn pass_after_fail_under_same_fingerprint_is_a_flake() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = crate::dag::init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let task = crate::dag::create_task(&db, "Fix net", None, None, 0).unwrap();
+        let other = crate::dag::create_task(&db, "Touch net", None, None, 0).unwrap();
+        let touched = files(&["src/net.rs", "./src/lib.rs", ".ralph/scratch/x.md"]);
+
+        // A fix between the failure and the pass is not a flake
+        assert!(!record(&db, &task.id, "a", &touched, false).unwrap());
+        assert!(!record(&db, &task.id, "b", &touched, true).unwrap());
+        assert!(flaky_area(&db, &other.id, &touched).unwrap().is_empty());
+
+        assert!(!record(&db, &task.id, "c", &touched, false).unwrap());
+        assert!(record(&db, &task.id, "c", &touched, true).unwrap());
+
+        assert_eq!(
+            flaky_area(&db, &other.id, &files(&["src/net.rs"])).unwrap(),
+            ["src/net.rs"]
+        );
+        assert!(flaky_area(&db, &other.id, &files(&["src/ui.rs"]))
+            .unwrap()
+            .is_empty());
+        assert_eq!(flaky_area(&db, &task.id, &[]).unwrap().len(), 2);
+
+        let stats = stats(&db).unwrap();
+        assert_eq!((stats.verifications, stats.failed, stats.flakes), (4, 2, 1));
+        assert_eq!(
+            stats.files,
+            [("src/lib.rs".to_string(), 1), ("src/net.rs".to_string(), 1)]
+        );
+        assert_eq!(stats.tasks, [(task.id.clone(), 1)]);
+
+        assert_eq!(max_retries(3, 2, &[]), 3);
+        assert_eq!(max_retries(3, 2, &files(&["src/net.rs"])), 5);
+    }
+}
@@ -0,0 +1,265 @@
+//! Content guardrails on streamed agent text (`[guardrails]`), and command
+//! approval for `ralph run --approve-commands`.
+//!
+//! Each pattern is a regex matched against the agent's message text, one line
+//! at a time, as it streams in. A match pauses the session while the excerpt
+//! is shown in a confirm modal (or a stdin prompt without the TUI):
+//! `RalphClient` holds file writes, terminals and permission requests until
+//! the answer arrives. Approval lets them through; anything else refuses them
+//! and cancels the turn the same way a tool limit does.
+//!
+//! With command approval on, every terminal the agent asks for waits the same
+//! way until the user approves the exact command line. Approved commands are
+//! remembered for the rest of the run; a declined one is refused to the agent.
+//! Tool kinds set to `ask` in `[permissions]` prompt the same way, every time.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use crate::input;
+use crate::output::formatter;
+
+/// Longest excerpt shown when a guardrail trips.
+const MAX_EXCERPT_CHARS: usize = 200;
+
+static GUARDS: OnceLock<Vec<Regex>> = OnceLock::new();
+
+/// `[guardrails]` section of `.ralph.toml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct GuardrailsConfig {
+    /// Regexes over agent message text, e.g. `(?i)force[- ]push`.
+    #[serde(default)]
+    pub patterns: Vec<String>,
+}
+
+/// Compile `[guardrails] patterns`, naming the first invalid one.
+pub fn compile(config: &GuardrailsConfig) -> Result<Vec<Regex>> {
+    config
+        .patterns
+        .iter()
+        .map(|p| Regex::new(p).with_context(|| format!("Invalid guardrail pattern '{p}'")))
+        .collect()
+}
+
+/// Install the project's guardrails for every client created afterwards.
+/// Later calls are ignored.
+pub fn configure(config: &GuardrailsConfig) -> Result<()> {
+    let _ = GUARDS.set(compile(config)?);
+    Ok(())
+}
+
+/// The configured guardrails (none until [`configure`] runs).
+pub fn guards() -> &'static [Regex] {
+    GUARDS.get().map(Vec::as_slice).unwrap_or_default()
+}
+
+/// A guardrail that matched agent output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuardMatch {
+    pub pattern: String,
+    /// The line the match is on, as streamed so far.
+    pub excerpt: String,
+}
+
+/// Scans streamed text line by line. Keeps the current partial line so a
+/// phrase split across chunks still matches.
+#[derive(Debug, Default)]
+pub struct LineScanner {
+    line: String,
+}
+
+impl LineScanner {
+    /// Add a chunk and report the first guardrail it completes a match for.
+    /// Text up to the end of a match is not scanned again.
+    pub fn push(&mut self, guards: &[Regex], chunk: &str) -> Option<GuardMatch> {
+        self.line.push_str(chunk);
+        let found = guards
+            .iter()
+            .find_map(|g| g.find(&self.line).map(|m| (g, m.start(), m.end())));
+        let result = found.map(|(guard, start, end)| {
+            let line_start = self.line[..start].rfind('\n').map_or(0, |i| i + 1);
+            let line_end = self.line[end..]
+                .find('\n')
+                .map_or(self.line.len(), |i| end + i);
+            let excerpt: String = self.line[line_start..line_end]
+                .trim()
+                .chars()
+                .take(MAX_EXCERPT_CHARS)
+                .collect();
+            self.line.drain(..end);
+            GuardMatch {
+                pattern: guard.as_str().to_string(),
+                excerpt,
+            }
+        });
+        if let Some(newline) = self.line.rfind('\n') {
+            self.line.drain(..=newline);
+        }
+        result
+    }
+}
+
+/// Ask the user whether the agent may continue after `hit`.
+///
+/// Only an explicit answer approves: `--yes`, `--no-input` and CI mode
+/// decline, since guardrails exist for the runs nobody is watching.
+pub async fn approve(hit: &GuardMatch) -> bool {
+    formatter::emit_event(
+        "guard",
+        &format!("output matched `{}`: {}", hit.pattern, hit.excerpt),
+        true,
+    );
+    let question = format!(
+        "Agent output matched guardrail `{}`:\n\n  {}\n\nLet the agent continue?",
+        hit.pattern, hit.excerpt
+    );
+    ask("Guardrail", &question).await
+}
+
+/// Confirm with an explicit answer only: modal with the TUI, else stdin.
+/// Either way the answer is awaited, so the runtime keeps serving the agent
+/// connection meanwhile.
+async fn ask(title: &str, question: &str) -> bool {
+    if crate::ui::is_active() {
+        return crate::ui::prompt_confirm_async(title, question, false)
+            .await
+            .unwrap_or(false);
+    }
+    if input::assume_yes() || input::no_input() {
+        formatter::print_warning(&format!("{title} declined: it needs an explicit answer."));
+        return false;
+    }
+    let question = question.to_string();
+    tokio::task::spawn_blocking(move || input::confirm(&question, false))
+        .await
+        .unwrap_or(false)
+}
+
+static APPROVE_COMMANDS: AtomicBool = AtomicBool::new(false);
+
+/// Require approval for every terminal command in sessions started
+/// afterwards (`ralph run --approve-commands`).
+pub fn require_command_approval() {
+    APPROVE_COMMANDS.store(true, Ordering::SeqCst);
+}
+
+/// Whether terminal commands need approval.
+pub fn commands_need_approval() -> bool {
+    APPROVE_COMMANDS.load(Ordering::SeqCst)
+}
+
+/// Command lines approved so far, with the directory they run in.
+#[derive(Debug, Default)]
+pub struct CommandApprovals {
+    approved: BTreeSet<(String, String)>,
+}
+
+impl CommandApprovals {
+    /// Whether `command` was already approved for `cwd`.
+    pub fn contains(&self, command: &str, cwd: &str) -> bool {
+        self.approved
+            .contains(&(command.to_string(), cwd.to_string()))
+    }
+
+    /// Remember that `command` may run in `cwd`.
+    pub fn insert(&mut self, command: &str, cwd: &str) {
+        self.approved.insert((command.to_string(), cwd.to_string()));
+    }
+}
+
+static COMMAND_APPROVALS: Mutex<CommandApprovals> = Mutex::new(CommandApprovals {
+    approved: BTreeSet::new(),
+});
+
+/// Ask the user whether the agent may run `command` in `cwd` (shown with
+/// secrets masked). Identical requests later in the run are approved without
+/// asking. Like [`approve`], only an explicit answer approves.
+pub async fn approve_command(command: &str, cwd: &str) -> bool {
+    // The lock is not held across the question, so a second request
+    // arriving meanwhile asks on its own instead of blocking the runtime
+    if approvals().contains(command, cwd) {
+        return true;
+    }
+    let shown = crate::terminal_env::current().mask(command);
+    let question = format!(
+        "The agent wants to run:\n\n  $ {shown}\n  (in {cwd})\n\nAllow it? \
+         Identical commands run without asking for the rest of this run."
+    );
+    let approved = ask("Approve command", &question).await;
+    let verdict = if approved { "approved" } else { "declined" };
+    formatter::emit_event("guard", &format!("command {verdict}: {shown}"), !approved);
+    if approved {
+        approvals().insert(command, cwd);
+    }
+    approved
+}
+
+fn approvals() -> std::sync::MutexGuard<'static, CommandApprovals> {
+    COMMAND_APPROVALS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Ask the user whether the agent may make a tool call of `kind`
+/// (`[permissions]` set to `ask`). Unlike commands, every call asks.
+pub async fn approve_tool_call(kind: &str, what: &str) -> bool {
+    let question = format!("The agent asks to {kind}:\n\n  {what}\n\nAllow it?");
+    let approved = ask("Tool permission", &question).await;
+    let verdict = if approved { "approved" } else { "declined" };
+    formatter::emit_event("guard", &format!("{kind} {verdict}: {what}"), !approved);
+    approved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guards(patterns: &[&str]) -> Vec<Regex> {
+        compile(&GuardrailsConfig {
+            patterns: patterns.iter().map(|p| p.to_string()).collect(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn matches_phrases_split_across_chunks_once() {
+        let guards = guards(&["(?i)force[- ]push"]);
+        let mut scanner = LineScanner::default();
+        assert_eq!(scanner.push(&guards, "Done.\nNow I will FORCE"), None);
+        let hit = scanner.push(&guards, "-push to main").unwrap();
+        assert_eq!(hit.pattern, "(?i)force[- ]push");
+        assert_eq!(hit.excerpt, "Now I will FORCE-push to main");
+        assert_eq!(scanner.push(&guards, " now.\n"), None);
+        assert!(scanner.push(&guards, "force push again").is_some());
+    }
+
+    #[test]
+    fn scanned_lines_are_dropped() {
+        let guards = guards(&["delete the database"]);
+        let mut scanner = LineScanner::default();
+        assert_eq!(scanner.push(&guards, "I will not delete\n"), None);
+        assert_eq!(scanner.push(&guards, "the database.\n"), None);
+        assert_eq!(scanner.line, "");
+    }
+
+    #[test]
+    fn approvals_are_per_command_and_directory() {
+        let mut approvals = CommandApprovals::default();
+        assert!(!approvals.contains("cargo test", "."));
+        approvals.insert("cargo test", ".");
+        assert!(approvals.contains("cargo test", "."));
+        assert!(!approvals.contains("cargo test", "packages/api"));
+        assert!(!approvals.contains("rm -rf build", "."));
+    }
+
+    #[test]
+    fn invalid_pattern_is_named() {
+        let config = GuardrailsConfig {
+            patterns: vec!["(unclosed".to_string()],
+        };
+        let err = compile(&config).unwrap_err();
+        assert!(err.to_string().contains("(unclosed"));
+    }
+}
@@ -0,0 +1,180 @@
+//! Plain-terminal input policy for confirmations and prompts.
+//!
+//! When the TUI is off, confirmations fall back to reading a line from stdin,
+//! so piped answers (`echo y | ralph task delete t-…`) work. `--yes` answers
+//! every confirmation up front; `--no-input` (and CI mode) never read stdin and
+//! decline anything that would need an answer.
+
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+static NO_INPUT: AtomicBool = AtomicBool::new(false);
+
+/// Set the process-wide input policy from the global CLI flags.
+pub fn configure(assume_yes: bool, no_input: bool) {
+    ASSUME_YES.store(assume_yes, Ordering::SeqCst);
+    NO_INPUT.store(no_input, Ordering::SeqCst);
+}
+
+/// Whether `--yes` was given.
+pub fn assume_yes() -> bool {
+    ASSUME_YES.load(Ordering::SeqCst)
+}
+
+/// Whether stdin must not be read (`--no-input` or CI mode).
+pub fn no_input() -> bool {
+    NO_INPUT.load(Ordering::SeqCst) || crate::ci::is_enabled()
+}
+
+/// Read one line from stdin without its trailing newline.
+///
+/// Returns `None` under `--no-input`, on EOF, or on a read error.
+pub fn read_line() -> Option<String> {
+    if no_input() {
+        return None;
+    }
+    let mut line = String::new();
+    match std::io::stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(
+            line.trim_end_matches('\n')
+                .trim_end_matches('\r')
+                .to_string(),
+        ),
+    }
+}
+
+/// Ask a yes/no question on the plain terminal.
+///
+/// The prompt goes to stderr so stdout stays clean for `--json` output. An
+/// empty answer takes `default_yes`; EOF and `--no-input` always decline.
+pub fn confirm(prompt: &str, default_yes: bool) -> bool {
+    if assume_yes() {
+        return true;
+    }
+    if no_input() {
+        crate::output::formatter::print_warning(&format!(
+            "{prompt} Declined: input is disabled (pass --yes to confirm)."
+        ));
+        return false;
+    }
+
+    let hint = if default_yes { "[Y/n]" } else { "[y/N]" };
+    eprint!("{prompt} {hint} ");
+    let _ = std::io::stderr().flush();
+
+    match read_line() {
+        Some(answer) => parse_answer(&answer, default_yes),
+        None => {
+            eprintln!();
+            false
+        }
+    }
+}
+
+/// Pick any number of `choices`, in the TUI when it is running and on the
+/// plain terminal otherwise.
+///
+/// `checked` holds the initial marks. Returns the chosen indices in list order,
+/// or `None` if the prompt was cancelled. `--yes` and `--no-input` keep the
+/// initial marks without asking.
+pub fn select_many(title: &str, choices: &[&str], checked: &[bool]) -> Option<Vec<usize>> {
+    let defaults: Vec<usize> = (0..choices.len())
+        .filter(|&i| checked.get(i) == Some(&true))
+        .collect();
+    if assume_yes() || no_input() {
+        return Some(defaults);
+    }
+    if crate::ui::is_active() {
+        return match crate::ui::prompt_multi_select(title, "Choose any number.", choices, checked) {
+            Some(crate::ui::UiPromptResult::Selected(indices)) => Some(indices),
+            _ => None,
+        };
+    }
+
+    eprintln!("{title}");
+    for (i, choice) in choices.iter().enumerate() {
+        let mark = if defaults.contains(&i) { "x" } else { " " };
+        eprintln!("  [{mark}] {}. {choice}", i + 1);
+    }
+    eprint!("Numbers or ranges (e.g. 1,3-4), all, none; empty keeps [x]: ");
+    let _ = std::io::stderr().flush();
+    loop {
+        let answer = read_line()?;
+        match parse_selection(&answer, choices.len(), &defaults) {
+            Some(indices) => return Some(indices),
+            None => {
+                eprint!("Not a valid selection, try again: ");
+                let _ = std::io::stderr().flush();
+            }
+        }
+    }
+}
+
+/// Interpret a typed selection such as `1,3-4`, `all` or `none` over `count`
+/// items. Numbers are 1-based; an empty answer keeps `defaults`.
+fn parse_selection(answer: &str, count: usize, defaults: &[usize]) -> Option<Vec<usize>> {
+    let answer = answer.trim().to_lowercase();
+    match answer.as_str() {
+        "" => return Some(defaults.to_vec()),
+        "all" | "a" => return Some((0..count).collect()),
+        "none" | "n" => return Some(Vec::new()),
+        _ => {}
+    }
+    let mut chosen = vec![false; count];
+    for part in answer.split([',', ' ']).filter(|p| !p.is_empty()) {
+        let (start, end) = match part.split_once('-') {
+            Some((a, b)) => (
+                a.trim().parse::<usize>().ok()?,
+                b.trim().parse::<usize>().ok()?,
+            ),
+            None => {
+                let n = part.parse::<usize>().ok()?;
+                (n, n)
+            }
+        };
+        if start == 0 || end > count || start > end {
+            return None;
+        }
+        chosen[start - 1..end].iter_mut().for_each(|c| *c = true);
+    }
+    Some((0..count).filter(|&i| chosen[i]).collect())
+}
+
+/// Interpret a typed yes/no answer.
+fn parse_answer(answer: &str, default_yes: bool) -> bool {
+    match answer.trim().to_lowercase().as_str() {
+        "" => default_yes,
+        "y" | "yes" => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_answer_handles_defaults_and_case() {
+        assert!(parse_answer("", true));
+        assert!(!parse_answer("", false));
+        assert!(parse_answer(" Y ", false));
+        assert!(parse_answer("yes", false));
+        assert!(!parse_answer("n", true));
+        assert!(!parse_answer("maybe", true));
+    }
+
+    #[test]
+    fn parse_selection_handles_lists_ranges_and_keywords() {
+        assert_eq!(parse_selection("", 4, &[1]), Some(vec![1]));
+        assert_eq!(parse_selection("all", 3, &[]), Some(vec![0, 1, 2]));
+        assert_eq!(parse_selection("none", 3, &[0]), Some(vec![]));
+        assert_eq!(parse_selection("3, 1", 4, &[]), Some(vec![0, 2]));
+        assert_eq!(parse_selection("2-4", 4, &[]), Some(vec![1, 2, 3]));
+        assert_eq!(parse_selection("5", 4, &[]), None);
+        assert_eq!(parse_selection("0", 4, &[]), None);
+        assert_eq!(parse_selection("3-1", 4, &[]), None);
+        assert_eq!(parse_selection("x", 4, &[]), None);
+    }
+}
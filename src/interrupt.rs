@@ -2,7 +2,6 @@
 //! for mid-loop interrupt support.
 
 use anyhow::Result;
-use std::io::IsTerminal;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, OnceLock};
 
@@ -63,8 +62,12 @@ pub fn request_interrupt() {
 /// Prompt the user for feedback on the interrupted task.
 ///
 /// Returns `Some(feedback)` if the user typed something, or `None` if they
-/// pressed Enter immediately or stdin is not a terminal.
+/// pressed Enter immediately, stdin hit EOF, or `--no-input`/CI mode is active.
 pub fn prompt_for_feedback(task: &Task) -> Result<Option<String>> {
+    if crate::ci::is_enabled() {
+        return Ok(None);
+    }
+
     if crate::ui::is_active() {
         let title = format!("Interrupted {}", task.id);
         let hint = format!(
@@ -79,12 +82,13 @@ pub fn prompt_for_feedback(task: &Task) -> Result<Option<String>> {
                     Some(text)
                 }
             }
-            Some(crate::ui::UiPromptResult::Exit) | None => None,
+            Some(crate::ui::UiPromptResult::Exit | crate::ui::UiPromptResult::Selected(_))
+            | None => None,
             Some(crate::ui::UiPromptResult::Interrupted) => None,
         });
     }
 
-    if !std::io::stdin().is_terminal() {
+    if crate::input::no_input() {
         return Ok(None);
     }
 
@@ -99,14 +103,10 @@ pub fn prompt_for_feedback(task: &Task) -> Result<Option<String>> {
         use std::io::Write;
         std::io::stdout().flush()?;
 
-        let mut line = String::new();
-        std::io::stdin().read_line(&mut line)?;
-        let trimmed = line.trim_end_matches('\n').trim_end_matches('\r');
-
-        if trimmed.is_empty() {
-            break;
+        match crate::input::read_line() {
+            Some(line) if !line.is_empty() => lines.push(line),
+            _ => break,
         }
-        lines.push(trimmed.to_string());
     }
 
     if lines.is_empty() {
@@ -116,6 +116,83 @@ pub fn prompt_for_feedback(task: &Task) -> Result<Option<String>> {
     }
 }
 
+/// Choice labels offered in the TUI input pane after an interrupt.
+pub const CHOICE_CONTINUE: &str = "Continue";
+pub const CHOICE_RETRY: &str = "Retry with feedback";
+pub const CHOICE_STOP: &str = "Stop";
+
+/// What the operator wants to happen after an interrupted iteration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterruptDecision {
+    /// Keep looping, optionally appending feedback to the interrupted task.
+    Continue { feedback: Option<String> },
+    /// Halt the run, recording any feedback that was given.
+    Stop { feedback: Option<String> },
+}
+
+/// Ask the operator how to proceed after an interrupt.
+///
+/// With the TUI active, a single choice prompt in the input pane offers
+/// continue / retry with feedback / stop; typing instead of picking a choice
+/// is treated as feedback. Otherwise falls back to `prompt_for_feedback`
+/// followed by `should_continue` on the terminal.
+pub fn resolve_interrupt(task: &Task) -> Result<InterruptDecision> {
+    if crate::ci::is_enabled() {
+        return Ok(InterruptDecision::Stop { feedback: None });
+    }
+
+    if crate::ui::is_active() {
+        let title = format!("Interrupted {}", task.id);
+        let hint = format!(
+            "{}\n{}\n\nPick an action, or start typing to give feedback and continue.",
+            task.id, task.title
+        );
+        let choice =
+            crate::ui::prompt_choice(&title, &hint, &[CHOICE_CONTINUE, CHOICE_RETRY, CHOICE_STOP]);
+        let decision = decision_from_choice(choice);
+        if decision
+            == (InterruptDecision::Continue {
+                feedback: Some(CHOICE_RETRY.to_string()),
+            })
+        {
+            // "Retry with feedback" opens the free-text pane for the actual guidance.
+            return Ok(InterruptDecision::Continue {
+                feedback: prompt_for_feedback(task)?,
+            });
+        }
+        return Ok(decision);
+    }
+
+    let feedback = prompt_for_feedback(task)?;
+    if should_continue()? {
+        Ok(InterruptDecision::Continue { feedback })
+    } else {
+        Ok(InterruptDecision::Stop { feedback })
+    }
+}
+
+/// Map a TUI choice-prompt reply to a decision.
+///
+/// `Retry with feedback` comes back as `Continue` carrying the choice label,
+/// which the caller replaces with the text from a follow-up prompt. Esc and
+/// Ctrl+C stop the run, matching the old "Continue?" confirm.
+fn decision_from_choice(choice: Option<crate::ui::UiPromptResult>) -> InterruptDecision {
+    use crate::ui::UiPromptResult;
+
+    match choice {
+        Some(UiPromptResult::Input(text)) => match text.as_str() {
+            CHOICE_CONTINUE => InterruptDecision::Continue { feedback: None },
+            CHOICE_STOP => InterruptDecision::Stop { feedback: None },
+            _ if text.trim().is_empty() => InterruptDecision::Continue { feedback: None },
+            _ => InterruptDecision::Continue {
+                feedback: Some(text),
+            },
+        },
+        Some(UiPromptResult::Exit | UiPromptResult::Interrupted | UiPromptResult::Selected(_))
+        | None => InterruptDecision::Stop { feedback: None },
+    }
+}
+
 /// Append user feedback to a task description with a clear delimiter.
 ///
 /// Multiple interventions stack at the end; the original description stays
@@ -129,9 +206,13 @@ pub fn append_feedback_to_description(description: &str, feedback: &str, iterati
 
 /// Ask the user whether to continue the run loop.
 ///
-/// Returns `true` for "Y" (default) or `false` for "n".
-/// Non-TTY defaults to `false`.
+/// Returns `true` for "Y" (default) or `false` for "n". `--yes` continues
+/// without asking; EOF, `--no-input` and CI mode return `false`.
 pub fn should_continue() -> Result<bool> {
+    if crate::ci::is_enabled() {
+        return Ok(false);
+    }
+
     if crate::ui::is_active() {
         return Ok(
             crate::ui::prompt_confirm("Continue Run", "Continue after interruption?", true)
@@ -139,7 +220,10 @@ pub fn should_continue() -> Result<bool> {
         );
     }
 
-    if !std::io::stdin().is_terminal() {
+    if crate::input::assume_yes() {
+        return Ok(true);
+    }
+    if crate::input::no_input() {
         return Ok(false);
     }
 
@@ -147,11 +231,14 @@ pub fn should_continue() -> Result<bool> {
     use std::io::Write;
     std::io::stdout().flush()?;
 
-    let mut input = String::new();
-    std::io::stdin().read_line(&mut input)?;
-    let trimmed = input.trim().to_lowercase();
-
-    Ok(trimmed.is_empty() || trimmed == "y" || trimmed == "yes")
+    // EOF on a piped stdin stops rather than looping unattended.
+    Ok(match crate::input::read_line() {
+        Some(answer) => {
+            let trimmed = answer.trim().to_lowercase();
+            trimmed.is_empty() || trimmed == "y" || trimmed == "yes"
+        }
+        None => false,
+    })
 }
 
 #[cfg(test)]
@@ -178,6 +265,50 @@ mod tests {
         assert!(after_second.contains("Second feedback"));
     }
 
+    #[test]
+    fn decision_from_choice_maps_labels() {
+        use crate::ui::UiPromptResult;
+
+        assert_eq!(
+            decision_from_choice(Some(UiPromptResult::Input(CHOICE_CONTINUE.to_string()))),
+            InterruptDecision::Continue { feedback: None }
+        );
+        assert_eq!(
+            decision_from_choice(Some(UiPromptResult::Input(CHOICE_STOP.to_string()))),
+            InterruptDecision::Stop { feedback: None }
+        );
+        assert_eq!(
+            decision_from_choice(Some(UiPromptResult::Input(CHOICE_RETRY.to_string()))),
+            InterruptDecision::Continue {
+                feedback: Some(CHOICE_RETRY.to_string())
+            }
+        );
+    }
+
+    #[test]
+    fn decision_from_choice_free_text_is_feedback() {
+        use crate::ui::UiPromptResult;
+
+        assert_eq!(
+            decision_from_choice(Some(UiPromptResult::Input("use the v2 API".to_string()))),
+            InterruptDecision::Continue {
+                feedback: Some("use the v2 API".to_string())
+            }
+        );
+        assert_eq!(
+            decision_from_choice(Some(UiPromptResult::Exit)),
+            InterruptDecision::Stop { feedback: None }
+        );
+        assert_eq!(
+            decision_from_choice(Some(UiPromptResult::Interrupted)),
+            InterruptDecision::Stop { feedback: None }
+        );
+        assert_eq!(
+            decision_from_choice(None),
+            InterruptDecision::Stop { feedback: None }
+        );
+    }
+
     #[test]
     fn is_interrupted_default_false() {
         // Before registration, should return false
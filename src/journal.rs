@@ -6,6 +6,8 @@
 
 use crate::dag::Db;
 use anyhow::Result;
+use rusqlite::OptionalExtension;
+use std::collections::HashMap;
 
 /// A single journal entry recording metadata about one agent loop iteration.
 #[derive(Debug, Clone)]
@@ -21,22 +23,42 @@ pub struct JournalEntry {
     pub model: Option<String>,
     pub duration_secs: f64,
     pub cost_usd: f64,
+    /// Input tokens reported by the agent (0 when not reported).
+    pub input_tokens: u64,
+    /// Output tokens reported by the agent (0 when not reported).
+    pub output_tokens: u64,
     pub files_modified: Vec<String>,
+    /// Files the agent read, for knowledge matching.
+    pub files_read: Vec<String>,
     pub notes: Option<String>,
     pub created_at: String,
 }
 
+/// A rolling summary of a run (`[execution] run_summary_every`), covering
+/// its journal up to `iteration`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RollingSummary {
+    pub run_id: String,
+    /// Last iteration the summary covers.
+    pub iteration: u32,
+    pub summary: String,
+    pub model: Option<String>,
+    pub cost_usd: f64,
+}
+
 /// Insert a journal entry into the database.
 ///
-/// The `files_modified` field is serialized as a JSON array.
+/// The `files_modified` and `files_read` fields are serialized as JSON arrays.
 /// The FTS5 index is updated automatically by the `journal_ai` trigger.
 /// Returns the `last_insert_rowid()` of the new row.
 pub fn insert_journal_entry(db: &Db, entry: &JournalEntry) -> Result<i64> {
     let files_json = serde_json::to_string(&entry.files_modified)?;
+    let read_json = serde_json::to_string(&entry.files_read)?;
     db.conn().execute(
         "INSERT INTO journal (run_id, iteration, task_id, feature_id, outcome,
-         model, duration_secs, cost_usd, files_modified, notes, created_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+         model, duration_secs, cost_usd, input_tokens, output_tokens,
+         files_modified, notes, created_at, files_read)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
         rusqlite::params![
             entry.run_id,
             entry.iteration,
@@ -46,24 +68,40 @@ pub fn insert_journal_entry(db: &Db, entry: &JournalEntry) -> Result<i64> {
             entry.model,
             entry.duration_secs,
             entry.cost_usd,
+            entry.input_tokens as i64,
+            entry.output_tokens as i64,
             files_json,
             entry.notes,
             entry.created_at,
+            read_json,
         ],
     )?;
     Ok(db.conn().last_insert_rowid())
 }
 
+/// Change the outcome of an existing entry, e.g. when a pipelined
+/// verification rejects work already journaled as done.
+pub fn set_outcome(db: &Db, id: i64, outcome: &str) -> Result<()> {
+    db.conn().execute(
+        "UPDATE journal SET outcome = ?1 WHERE id = ?2",
+        rusqlite::params![outcome, id],
+    )?;
+    Ok(())
+}
+
 /// Map a `rusqlite::Row` to a `JournalEntry`.
 ///
 /// Expects columns in order:
 /// id, run_id, iteration, task_id, feature_id, outcome,
-/// model, duration_secs, cost_usd, files_modified, notes, created_at
+/// model, duration_secs, cost_usd, files_modified, notes, created_at,
+/// input_tokens, output_tokens, files_read
 fn journal_from_row(row: &rusqlite::Row) -> rusqlite::Result<JournalEntry> {
-    let files_json: Option<String> = row.get(9)?;
-    let files_modified: Vec<String> = files_json
-        .and_then(|j| serde_json::from_str(&j).ok())
-        .unwrap_or_default();
+    let files = |index: usize| -> rusqlite::Result<Vec<String>> {
+        let json: Option<String> = row.get(index)?;
+        Ok(json
+            .and_then(|j| serde_json::from_str(&j).ok())
+            .unwrap_or_default())
+    };
 
     Ok(JournalEntry {
         id: row.get(0)?,
@@ -75,7 +113,10 @@ fn journal_from_row(row: &rusqlite::Row) -> rusqlite::Result<JournalEntry> {
         model: row.get(6)?,
         duration_secs: row.get::<_, Option<f64>>(7)?.unwrap_or(0.0),
         cost_usd: row.get::<_, Option<f64>>(8)?.unwrap_or(0.0),
-        files_modified,
+        input_tokens: row.get::<_, i64>(12)?.max(0) as u64,
+        output_tokens: row.get::<_, i64>(13)?.max(0) as u64,
+        files_modified: files(9)?,
+        files_read: files(14)?,
         notes: row.get(10)?,
         created_at: row.get(11)?,
     })
@@ -88,7 +129,8 @@ fn journal_from_row(row: &rusqlite::Row) -> rusqlite::Result<JournalEntry> {
 pub fn query_journal_recent(db: &Db, run_id: &str, limit: u32) -> Result<Vec<JournalEntry>> {
     let mut stmt = db.conn().prepare(
         "SELECT id, run_id, iteration, task_id, feature_id, outcome,
-                model, duration_secs, cost_usd, files_modified, notes, created_at
+                model, duration_secs, cost_usd, files_modified, notes, created_at,
+                input_tokens, output_tokens, files_read
          FROM journal
          WHERE run_id = ?1
          ORDER BY iteration DESC
@@ -100,6 +142,135 @@ pub fn query_journal_recent(db: &Db, run_id: &str, limit: u32) -> Result<Vec<Jou
     Ok(entries)
 }
 
+/// Journal entries of `run_id` after iteration `after`, in chronological order.
+pub fn query_journal_since(db: &Db, run_id: &str, after: u32) -> Result<Vec<JournalEntry>> {
+    let mut stmt = db.conn().prepare(
+        "SELECT id, run_id, iteration, task_id, feature_id, outcome,
+                model, duration_secs, cost_usd, files_modified, notes, created_at,
+                input_tokens, output_tokens, files_read
+         FROM journal
+         WHERE run_id = ?1 AND iteration > ?2
+         ORDER BY iteration ASC, id ASC",
+    )?;
+    let rows = stmt.query_map(rusqlite::params![run_id, after], journal_from_row)?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+/// Store a new rolling summary for its run; the latest one is used.
+pub fn insert_rolling_summary(db: &Db, summary: &RollingSummary) -> Result<()> {
+    db.conn().execute(
+        "INSERT INTO run_summaries (run_id, iteration, summary, model, cost_usd)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![
+            summary.run_id,
+            summary.iteration,
+            summary.summary,
+            summary.model,
+            summary.cost_usd,
+        ],
+    )?;
+    Ok(())
+}
+
+/// The most recent rolling summary of `run_id`, if one was written.
+pub fn latest_rolling_summary(db: &Db, run_id: &str) -> Result<Option<RollingSummary>> {
+    let summary = db
+        .conn()
+        .query_row(
+            "SELECT run_id, iteration, summary, model, cost_usd
+             FROM run_summaries
+             WHERE run_id = ?1
+             ORDER BY iteration DESC, id DESC
+             LIMIT 1",
+            [run_id],
+            |row| {
+                Ok(RollingSummary {
+                    run_id: row.get(0)?,
+                    iteration: row.get(1)?,
+                    summary: row.get(2)?,
+                    model: row.get(3)?,
+                    cost_usd: row.get(4)?,
+                })
+            },
+        )
+        .optional()?;
+    Ok(summary)
+}
+
+/// The last `limit` journal entries of a task across all runs, newest first.
+pub fn query_journal_for_task(db: &Db, task_id: &str, limit: u32) -> Result<Vec<JournalEntry>> {
+    let mut stmt = db.conn().prepare(
+        "SELECT id, run_id, iteration, task_id, feature_id, outcome,
+                model, duration_secs, cost_usd, files_modified, notes, created_at,
+                input_tokens, output_tokens, files_read
+         FROM journal
+         WHERE task_id = ?1
+         ORDER BY id DESC
+         LIMIT ?2",
+    )?;
+    let rows = stmt.query_map(rusqlite::params![task_id, limit], journal_from_row)?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+/// All files recorded as modified by a task, in first-seen order without duplicates.
+pub fn files_modified_for_task(db: &Db, task_id: &str) -> Result<Vec<String>> {
+    files_for_task(db, task_id, "files_modified")
+}
+
+/// All files a task's iterations read, in first-seen order without duplicates.
+pub fn files_read_for_task(db: &Db, task_id: &str) -> Result<Vec<String>> {
+    files_for_task(db, task_id, "files_read")
+}
+
+/// For each of `paths`, the other tasks whose iterations recorded modifying
+/// it, in first-seen order. Paths no other task touched are left out.
+pub fn other_tasks_modifying(
+    db: &Db,
+    task_id: &str,
+    paths: &[String],
+) -> Result<HashMap<String, Vec<String>>> {
+    let mut stmt = db.conn().prepare(
+        "SELECT task_id, files_modified FROM journal
+         WHERE task_id IS NOT NULL AND task_id != ?1 AND files_modified IS NOT NULL
+           AND outcome != 'rolled_back'
+         ORDER BY id",
+    )?;
+    let rows = stmt.query_map([task_id], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+    let mut shared: HashMap<String, Vec<String>> = HashMap::new();
+    for (other, json) in rows.filter_map(|r| r.ok()) {
+        for file in serde_json::from_str::<Vec<String>>(&json).unwrap_or_default() {
+            if paths.contains(&file) {
+                let tasks = shared.entry(file).or_default();
+                if !tasks.contains(&other) {
+                    tasks.push(other.clone());
+                }
+            }
+        }
+    }
+    Ok(shared)
+}
+
+/// The union of a task's JSON path lists in `column`.
+fn files_for_task(db: &Db, task_id: &str, column: &str) -> Result<Vec<String>> {
+    let mut stmt = db.conn().prepare(&format!(
+        "SELECT {column} FROM journal
+         WHERE task_id = ?1 AND {column} IS NOT NULL
+         ORDER BY id"
+    ))?;
+    let rows = stmt.query_map([task_id], |row| row.get::<_, String>(0))?;
+    let mut files: Vec<String> = Vec::new();
+    for json in rows.filter_map(|r| r.ok()) {
+        for file in serde_json::from_str::<Vec<String>>(&json).unwrap_or_default() {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+    Ok(files)
+}
+
 /// FTS5 full-text search across journal notes.
 ///
 /// Returns entries ranked by FTS5 relevance, excluding entries from `exclude_run_id`
@@ -117,7 +288,8 @@ pub fn query_journal_fts(
 
     let mut stmt = db.conn().prepare(
         "SELECT j.id, j.run_id, j.iteration, j.task_id, j.feature_id, j.outcome,
-                j.model, j.duration_secs, j.cost_usd, j.files_modified, j.notes, j.created_at
+                j.model, j.duration_secs, j.cost_usd, j.files_modified, j.notes, j.created_at,
+                j.input_tokens, j.output_tokens, j.files_read
          FROM journal j
          JOIN journal_fts ON journal_fts.rowid = j.id
          WHERE journal_fts MATCH ?1
@@ -133,6 +305,91 @@ pub fn query_journal_fts(
     Ok(rows.filter_map(|r| r.ok()).collect())
 }
 
+/// Marks the start of a matched term in [`JournalHit::snippet`].
+pub const HIGHLIGHT_START: char = '\u{2}';
+/// Marks the end of a matched term in [`JournalHit::snippet`].
+pub const HIGHLIGHT_END: char = '\u{3}';
+
+/// Days over which a search hit's relevance is halved.
+const SEARCH_RECENCY_HALF_LIFE_DAYS: f64 = 30.0;
+
+/// One `ralph journal search` result.
+#[derive(Debug, Clone)]
+pub struct JournalHit {
+    pub entry: JournalEntry,
+    /// Excerpt of the notes with matched terms wrapped in
+    /// [`HIGHLIGHT_START`] / [`HIGHLIGHT_END`].
+    pub snippet: String,
+}
+
+/// Optional filters for [`search_journal`].
+#[derive(Debug, Default)]
+pub struct SearchFilter<'a> {
+    pub task_id: Option<&'a str>,
+    pub feature_id: Option<&'a str>,
+}
+
+/// Full-text search over journal notes for humans.
+///
+/// Unlike [`query_journal_fts`], every term is quoted (so punctuation in the
+/// query is not FTS syntax) and hits are ranked by BM25 relevance damped by
+/// age, so a recent entry beats an equally relevant old one.
+pub fn search_journal(
+    db: &Db,
+    query: &str,
+    filter: &SearchFilter,
+    limit: u32,
+) -> Result<Vec<JournalHit>> {
+    let fts_query = build_search_query(query);
+    if fts_query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut stmt = db.conn().prepare(
+        "SELECT j.id, j.run_id, j.iteration, j.task_id, j.feature_id, j.outcome,
+                j.model, j.duration_secs, j.cost_usd, j.files_modified, j.notes, j.created_at,
+                j.input_tokens, j.output_tokens, j.files_read,
+                snippet(journal_fts, 0, ?2, ?3, '…', 16)
+         FROM journal j
+         JOIN journal_fts ON journal_fts.rowid = j.id
+         WHERE journal_fts MATCH ?1
+           AND (?4 IS NULL OR j.task_id = ?4)
+           AND (?5 IS NULL OR j.feature_id = ?5)
+         ORDER BY bm25(journal_fts)
+                  / (1.0 + MAX(julianday('now') - julianday(j.created_at), 0) / ?6),
+                  j.id DESC
+         LIMIT ?7",
+    )?;
+    let rows = stmt.query_map(
+        rusqlite::params![
+            fts_query,
+            HIGHLIGHT_START.to_string(),
+            HIGHLIGHT_END.to_string(),
+            filter.task_id,
+            filter.feature_id,
+            SEARCH_RECENCY_HALF_LIFE_DAYS,
+            limit
+        ],
+        |row| {
+            Ok(JournalHit {
+                entry: journal_from_row(row)?,
+                snippet: row.get(15)?,
+            })
+        },
+    )?;
+    Ok(rows.filter_map(|r| r.ok()).collect())
+}
+
+/// Build an FTS5 OR query from user input, quoting each term.
+fn build_search_query(text: &str) -> String {
+    text.split_whitespace()
+        .map(|w| w.replace('"', ""))
+        .filter(|w| !w.is_empty())
+        .map(|w| format!("\"{w}\""))
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
 /// Build an FTS5 query string from free text.
 ///
 /// Splits on whitespace, filters short words (<= 2 chars), and caps at 10 words.
@@ -149,41 +406,36 @@ pub fn build_fts_query(text: &str) -> String {
     words.join(" OR ")
 }
 
-/// Smart-select journal entries for system prompt injection.
-///
-/// Combines:
-/// - Up to `recent_limit` entries from the current `run_id` (chronological)
-/// - Up to `fts_limit` entries from other runs matching the task title/description via FTS5
-pub fn select_journal_entries(
-    db: &Db,
-    run_id: &str,
-    task_title: &str,
-    task_description: &str,
-    recent_limit: u32,
-    fts_limit: u32,
-) -> Result<Vec<JournalEntry>> {
-    let mut entries = query_journal_recent(db, run_id, recent_limit)?;
-    let query_text = format!("{} {}", task_title, task_description);
-    let fts_entries = query_journal_fts(db, &query_text, run_id, fts_limit)?;
-    entries.extend(fts_entries);
-    Ok(entries)
-}
-
 const JOURNAL_TOKEN_BUDGET: usize = 3000;
 const CHARS_PER_TOKEN: usize = 4;
 
 /// Render journal entries as markdown for the system prompt.
 ///
 /// Enforces a token budget (FR-5.3): stops adding entries once the budget
-/// (estimated at 4 chars/token) would be exceeded.
-pub fn render_journal_context(entries: &[JournalEntry]) -> String {
-    if entries.is_empty() {
+/// (estimated at 4 chars/token) would be exceeded. The run's rolling summary,
+/// when there is one, comes first and counts against the budget.
+pub fn render_journal_context(
+    entries: &[JournalEntry],
+    summary: Option<&RollingSummary>,
+) -> String {
+    if entries.is_empty() && summary.is_none() {
         return String::new();
     }
     let budget_chars = JOURNAL_TOKEN_BUDGET * CHARS_PER_TOKEN;
     let mut output = String::from("## Run Journal\n\n");
     let mut remaining = budget_chars;
 
+    if let Some(summary) = summary {
+        let rendered = format!(
+            "### Run So Far (iterations 1-{})\n{}\n",
+            summary.iteration,
+            summary.summary.trim()
+        );
+        output.push_str(&rendered);
+        output.push('\n');
+        remaining = remaining.saturating_sub(rendered.len());
+    }
+
     for entry in entries {
         let rendered = render_single_entry(entry);
         if rendered.len() > remaining {
@@ -197,23 +449,24 @@ pub fn render_journal_context(entries: &[JournalEntry]) -> String {
 }
 
 /// Render a single journal entry as markdown (FR-5.4 format).
-fn render_single_entry(entry: &JournalEntry) -> String {
+pub(crate) fn render_single_entry(entry: &JournalEntry) -> String {
     let files = if entry.files_modified.is_empty() {
         "none".to_string()
     } else {
         entry.files_modified.join(", ")
     };
     let notes = entry.notes.as_deref().unwrap_or("No notes recorded");
-    // NFR-5.2: omit cost when zero (ACP iterations don't report cost)
-    let duration_cost = if entry.cost_usd < f64::EPSILON {
-        format!("{dur:.1}s", dur = entry.duration_secs)
-    } else {
-        format!(
-            "{dur:.1}s | **Cost**: ${cost:.4}",
-            dur = entry.duration_secs,
-            cost = entry.cost_usd
-        )
-    };
+    // NFR-5.2: omit cost and tokens when zero (not every agent reports usage)
+    let mut duration_cost = format!("{dur:.1}s", dur = entry.duration_secs);
+    if entry.cost_usd >= f64::EPSILON {
+        duration_cost.push_str(&format!(" | **Cost**: ${cost:.4}", cost = entry.cost_usd));
+    }
+    if entry.input_tokens > 0 || entry.output_tokens > 0 {
+        duration_cost.push_str(&format!(
+            " | **Tokens**: {} in / {} out",
+            entry.input_tokens, entry.output_tokens
+        ));
+    }
     format!(
         "### Iteration {} [{outcome}]\n\
          - **Task**: {task_id}\n\
@@ -257,7 +510,10 @@ mod tests {
             model: Some("sonnet".to_string()),
             duration_secs: 12.5,
             cost_usd: 0.0042,
+            input_tokens: 1200,
+            output_tokens: 340,
             files_modified: vec!["src/main.rs".to_string(), "src/lib.rs".to_string()],
+            files_read: Vec::new(),
             notes: Some(format!(
                 "Notes for iteration {} in run {}",
                 iteration, run_id
@@ -287,6 +543,7 @@ mod tests {
         assert_eq!(r.model.as_deref(), Some("sonnet"));
         assert!((r.duration_secs - 12.5).abs() < f64::EPSILON);
         assert!((r.cost_usd - 0.0042).abs() < 1e-9);
+        assert_eq!((r.input_tokens, r.output_tokens), (1200, 340));
         assert_eq!(r.files_modified, vec!["src/main.rs", "src/lib.rs"]);
         assert_eq!(
             r.notes.as_deref(),
@@ -383,25 +640,29 @@ mod tests {
             model: Some("sonnet".to_string()),
             duration_secs: 30.0,
             cost_usd: 0.005,
+            input_tokens: 1500,
+            output_tokens: 200,
             files_modified: vec!["src/main.rs".to_string()],
+            files_read: Vec::new(),
             notes: Some("Fixed the bug in parser".to_string()),
             created_at: "2026-02-18T10:00:00Z".to_string(),
         }];
 
-        let rendered = render_journal_context(&entries);
+        let rendered = render_journal_context(&entries, None);
         assert!(rendered.contains("## Run Journal"));
         assert!(rendered.contains("### Iteration 1 [done]"));
         assert!(rendered.contains("**Task**: t-abc123"));
         assert!(rendered.contains("**Model**: sonnet"));
         assert!(rendered.contains("30.0s"));
         assert!(rendered.contains("$0.0050"));
+        assert!(rendered.contains("**Tokens**: 1500 in / 200 out"));
         assert!(rendered.contains("src/main.rs"));
         assert!(rendered.contains("Fixed the bug in parser"));
     }
 
     #[test]
     fn test_render_journal_context_empty() {
-        let rendered = render_journal_context(&[]);
+        let rendered = render_journal_context(&[], None);
         assert_eq!(rendered, "");
     }
 
@@ -417,12 +678,15 @@ mod tests {
             model: None,
             duration_secs: 5.0,
             cost_usd: 0.001,
+            input_tokens: 0,
+            output_tokens: 0,
             files_modified: vec![],
+            files_read: Vec::new(),
             notes: None,
             created_at: "2026-02-18T10:00:00Z".to_string(),
         }];
 
-        let rendered = render_journal_context(&entries);
+        let rendered = render_journal_context(&entries, None);
         assert!(rendered.contains("No notes recorded"));
         assert!(rendered.contains("none")); // task_id = none
         assert!(rendered.contains("unknown")); // model = unknown
@@ -442,12 +706,15 @@ mod tests {
             model: Some("opus".to_string()),
             duration_secs: 60.5,
             cost_usd: 0.0123,
+            input_tokens: 0,
+            output_tokens: 0,
             files_modified: vec!["src/lib.rs".to_string(), "tests/test.rs".to_string()],
+            files_read: Vec::new(),
             notes: Some("Implemented the core algorithm".to_string()),
             created_at: "2026-02-18T11:00:00Z".to_string(),
         }];
 
-        let rendered = render_journal_context(&entries);
+        let rendered = render_journal_context(&entries, None);
         // Must start with the section header
         assert!(rendered.starts_with("## Run Journal\n\n"));
         // Must contain the iteration header
@@ -461,6 +728,46 @@ mod tests {
         assert!(rendered.contains("Implemented the core algorithm"));
     }
 
+    #[test]
+    fn rolling_summary_is_stored_and_rendered_first() {
+        let (_tmp, db) = open_test_db();
+        for i in 1..=4 {
+            insert_journal_entry(&db, &make_entry("run-a", i, "done")).unwrap();
+        }
+        insert_journal_entry(&db, &make_entry("run-b", 3, "done")).unwrap();
+        assert_eq!(latest_rolling_summary(&db, "run-a").unwrap(), None);
+
+        for (iteration, text) in [(2, "Login form built."), (4, "Login and logout built.")] {
+            let summary = RollingSummary {
+                run_id: "run-a".to_string(),
+                iteration,
+                summary: text.to_string(),
+                model: Some("haiku".to_string()),
+                cost_usd: 0.001,
+            };
+            insert_rolling_summary(&db, &summary).unwrap();
+        }
+        let latest = latest_rolling_summary(&db, "run-a").unwrap().unwrap();
+        assert_eq!(
+            (latest.iteration, latest.summary.as_str()),
+            (4, "Login and logout built.")
+        );
+        let since: Vec<u32> = query_journal_since(&db, "run-a", 2)
+            .unwrap()
+            .iter()
+            .map(|e| e.iteration)
+            .collect();
+        assert_eq!(since, vec![3, 4]);
+
+        let rendered = render_journal_context(&[], Some(&latest));
+        assert!(rendered.starts_with(
+            "## Run Journal\n\n### Run So Far (iterations 1-4)\nLogin and logout built.\n"
+        ));
+        let entries = query_journal_recent(&db, "run-a", 1).unwrap();
+        let rendered = render_journal_context(&entries, Some(&latest));
+        assert!(rendered.find("Run So Far").unwrap() < rendered.find("### Iteration 4").unwrap());
+    }
+
     /// NFR-5.2: entries with cost_usd == 0.0 omit the Cost line entirely.
     #[test]
     fn test_render_single_entry_zero_cost_omits_cost_line() {
@@ -474,7 +781,10 @@ mod tests {
             model: Some("sonnet".to_string()),
             duration_secs: 42.0,
             cost_usd: 0.0,
+            input_tokens: 0,
+            output_tokens: 0,
             files_modified: vec!["src/acp/connection.rs".to_string()],
+            files_read: Vec::new(),
             notes: Some("ACP iteration".to_string()),
             created_at: "2026-02-22T09:00:00Z".to_string(),
         };
@@ -509,7 +819,10 @@ mod tests {
             model: Some("opus".to_string()),
             duration_secs: 198.3,
             cost_usd: 1.1155,
+            input_tokens: 0,
+            output_tokens: 0,
             files_modified: vec![],
+            files_read: Vec::new(),
             notes: None,
             created_at: "2026-02-22T09:30:00Z".to_string(),
         };
@@ -550,13 +863,16 @@ mod tests {
                 model: Some("sonnet".to_string()),
                 duration_secs: 10.0,
                 cost_usd: 0.001,
+                input_tokens: 0,
+                output_tokens: 0,
                 files_modified: vec![],
+                files_read: Vec::new(),
                 notes: Some(large_notes.clone()),
                 created_at: format!("2026-02-18T10:{:02}:00Z", i),
             });
         }
 
-        let rendered = render_journal_context(&entries);
+        let rendered = render_journal_context(&entries, None);
         // The output should be shorter than if all 6 entries were rendered
         // (6 * ~3200 chars = ~19200 chars >> 12000 char budget)
         assert!(
@@ -671,15 +987,16 @@ mod tests {
         insert_journal_entry(&db, &past3).unwrap();
 
         // select_journal_entries with task matching "database schema migrations"
-        let results = select_journal_entries(
-            &db,
-            current_run,
-            "database schema",
-            "implement migrations for the project",
-            5, // recent_limit
-            5, // fts_limit
-        )
-        .unwrap();
+        let results = crate::context_cache::ContextCache::default()
+            .select_journal_entries(
+                &db,
+                current_run,
+                "database schema",
+                "implement migrations for the project",
+                5, // recent_limit
+                5, // fts_limit
+            )
+            .unwrap();
 
         // Should include current run entries (up to recent_limit=5, but only 3 exist)
         let current_entries: Vec<_> = results.iter().filter(|e| e.run_id == current_run).collect();
@@ -743,4 +1060,40 @@ mod tests {
         let parts: Vec<&str> = q5.split(" OR ").collect();
         assert_eq!(parts, vec!["foo", "bar", "baz"]);
     }
+
+    #[test]
+    fn test_search_journal_ranks_recent_and_filters() {
+        let (_tmp, db) = open_test_db();
+        let task = crate::dag::create_task(&db, "Socket work", None, None, 0).unwrap();
+
+        let mut old = make_entry("run-a", 1, "failed");
+        old.notes = Some("flaky websocket test timed out on CI".to_string());
+        old.created_at = "2025-01-01T00:00:00Z".to_string();
+        let mut recent = make_entry("run-b", 1, "done");
+        recent.notes = Some("fixed the flaky websocket test with a retry".to_string());
+        recent.created_at = chrono::Utc::now().to_rfc3339();
+        recent.task_id = Some(task.id.clone());
+        let mut other = make_entry("run-b", 2, "done");
+        other.notes = Some("unrelated parser change".to_string());
+        for e in [&old, &recent, &other] {
+            insert_journal_entry(&db, e).unwrap();
+        }
+
+        let hits = search_journal(&db, "flaky websocket", &SearchFilter::default(), 10).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].entry.run_id, "run-b");
+        assert!(hits[0]
+            .snippet
+            .contains(&format!("{HIGHLIGHT_START}websocket{HIGHLIGHT_END}")));
+
+        let filter = SearchFilter {
+            task_id: Some(&task.id),
+            ..Default::default()
+        };
+        let hits = search_journal(&db, "flaky", &filter, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+
+        // FTS syntax characters in the query are treated as text.
+        assert!(search_journal(&db, "\"web-socket* AND", &SearchFilter::default(), 10).is_ok());
+    }
 }
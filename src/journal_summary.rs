@@ -0,0 +1,375 @@
+//! Journal notes and rolling run summaries written by a cheap model.
+//!
+//! The note an agent leaves in `<journal>` is whatever it thought worth
+//! saying, often nothing. With `summarize_journal = true`, each completed
+//! iteration's transcript goes to `journal_summary_model` (haiku by default),
+//! which writes a note with fixed sections: what changed, why, and gotchas.
+//! Later iterations and runs get those notes as journal context.
+//!
+//! Journal context only holds a handful of recent and matching entries, so
+//! after dozens of iterations the arc of the run is lost. Every
+//! `run_summary_every` entries the same model folds the entries since the last
+//! summary into it ([`refresh_run_summary`]); older iterations are compressed
+//! again each time, so the summary stays short however long the run gets.
+
+use anyhow::{bail, Result};
+
+use crate::acp;
+use crate::acp::types::StreamingResult;
+use crate::config::Config;
+use crate::dag::{Db, Task};
+use crate::journal::{self, JournalEntry, RollingSummary};
+use crate::output::formatter;
+
+/// Characters of transcript handed to the summarizer. The end of an
+/// iteration says the most about its result, so the start is dropped.
+const MAX_TRANSCRIPT_CHARS: usize = 40_000;
+
+/// The journal note for an iteration of `task` and what writing it cost.
+///
+/// Without `summarize_journal`, or when the summarizer fails (with a
+/// warning), that is the agent's own note at no cost.
+pub async fn notes(
+    config: &Config,
+    task: &Task,
+    result: &StreamingResult,
+    agent_notes: Option<&str>,
+    outcome: &str,
+) -> (Option<String>, f64) {
+    let execution = &config.ralph_config.execution;
+    if !execution.summarize_journal {
+        return (agent_notes.map(str::to_string), 0.0);
+    }
+    let model = execution.journal_summary_model.as_str();
+    match summarize(config, model, task, result, agent_notes, outcome).await {
+        Ok((note, cost)) => {
+            formatter::emit_event_info("journal", &format!("notes summarized with {model}"));
+            (Some(note), cost)
+        }
+        Err(e) => {
+            formatter::print_warning(&format!(
+                "ralph: could not summarize the iteration for the journal: {e:#}"
+            ));
+            (agent_notes.map(str::to_string), 0.0)
+        }
+    }
+}
+
+async fn summarize(
+    config: &Config,
+    model: &str,
+    task: &Task,
+    result: &StreamingResult,
+    agent_notes: Option<&str>,
+    outcome: &str,
+) -> Result<(String, f64)> {
+    let instructions = summary_instructions(task, result, agent_notes, outcome);
+    let summary = acp::connection::run_autonomous(
+        &config.agent_command,
+        &config.project_root,
+        &instructions,
+        "Write the journal note.",
+        true,
+        Some(model),
+        Default::default(),
+    )
+    .await?;
+    let Some(note) = parse_tag(&summary.full_text, "note") else {
+        bail!("summary agent did not emit a <note> sigil");
+    };
+    let cost = crate::pricing::cost_usd(model, &summary.usage, &config.ralph_config.pricing);
+    Ok((note, cost))
+}
+
+fn summary_instructions(
+    task: &Task,
+    result: &StreamingResult,
+    agent_notes: Option<&str>,
+    outcome: &str,
+) -> String {
+    let files = if result.files_modified.is_empty() {
+        "none".to_string()
+    } else {
+        result.files_modified.join(", ")
+    };
+    let agent_notes = agent_notes.unwrap_or("(none)");
+    format!(
+        "You write the project journal entry for one iteration of a coding agent. Later \
+         iterations read it instead of the transcript, so record what they need to carry \
+         on, and nothing else.\n\n\
+         Reply with the note only, inside `<note>...</note>`, using exactly these sections:\n\n\
+         ### Changed\n\
+         What was changed, with file paths and function or type names.\n\n\
+         ### Why\n\
+         The decisions made and the reasons for them.\n\n\
+         ### Gotchas\n\
+         Pitfalls, failed approaches and open problems, or \"None\".\n\n\
+         Keep the note under 200 words. Do not invent anything the transcript does not show.\n\n\
+         ## Iteration\n\n\
+         Task: {id} {title}\n\
+         Outcome: {outcome}\n\
+         Files modified: {files}\n\
+         Agent's own note: {agent_notes}\n\n\
+         ## Transcript\n\n{transcript}\n",
+        id = task.id,
+        title = task.title,
+        transcript = transcript_tail(&result.full_text),
+    )
+}
+
+/// The last [`MAX_TRANSCRIPT_CHARS`] characters of `transcript`.
+fn transcript_tail(transcript: &str) -> String {
+    let chars = transcript.chars().count();
+    if chars <= MAX_TRANSCRIPT_CHARS {
+        return transcript.to_string();
+    }
+    let tail: String = transcript
+        .chars()
+        .skip(chars - MAX_TRANSCRIPT_CHARS)
+        .collect();
+    format!("[Transcript start omitted]\n\n{tail}")
+}
+
+/// Regenerate the run's rolling summary once `run_summary_every` journal
+/// entries have accumulated since the last one. Failures only warn: the
+/// journal context then keeps the previous summary.
+pub async fn refresh_run_summary(db: &Db, config: &Config) {
+    let every = config.ralph_config.execution.run_summary_every;
+    if every == 0 {
+        return;
+    }
+    let previous = match journal::latest_rolling_summary(db, &config.run_id) {
+        Ok(previous) => previous,
+        Err(e) => {
+            formatter::print_warning(&format!("ralph: could not read the run summary: {e:#}"));
+            return;
+        }
+    };
+    let covered = previous.as_ref().map_or(0, |s| s.iteration);
+    let entries = journal::query_journal_since(db, &config.run_id, covered).unwrap_or_default();
+    let Some(last) = entries.last().map(|e| e.iteration) else {
+        return;
+    };
+    if entries.len() < every as usize {
+        return;
+    }
+
+    let model = config.ralph_config.execution.journal_summary_model.as_str();
+    let result = summarize_run(config, model, previous.as_ref(), &entries)
+        .await
+        .and_then(|(summary, cost)| {
+            journal::insert_rolling_summary(
+                db,
+                &RollingSummary {
+                    run_id: config.run_id.clone(),
+                    iteration: last,
+                    summary,
+                    model: Some(model.to_string()),
+                    cost_usd: cost,
+                },
+            )
+        });
+    match result {
+        Ok(()) => formatter::emit_event_info(
+            "journal",
+            &format!("run summary updated through iteration {last} with {model}"),
+        ),
+        Err(e) => {
+            formatter::print_warning(&format!("ralph: could not update the run summary: {e:#}"))
+        }
+    }
+}
+
+async fn summarize_run(
+    config: &Config,
+    model: &str,
+    previous: Option<&RollingSummary>,
+    entries: &[JournalEntry],
+) -> Result<(String, f64)> {
+    let instructions = run_summary_instructions(previous, entries);
+    let summary = acp::connection::run_autonomous(
+        &config.agent_command,
+        &config.project_root,
+        &instructions,
+        "Write the run summary.",
+        true,
+        Some(model),
+        Default::default(),
+    )
+    .await?;
+    let Some(text) = parse_tag(&summary.full_text, "summary") else {
+        bail!("summary agent did not emit a <summary> sigil");
+    };
+    let cost = crate::pricing::cost_usd(model, &summary.usage, &config.ralph_config.pricing);
+    Ok((text, cost))
+}
+
+fn run_summary_instructions(previous: Option<&RollingSummary>, entries: &[JournalEntry]) -> String {
+    let previous = match previous {
+        Some(s) => format!(
+            "## Summary So Far (iterations 1-{})\n\n{}\n\n",
+            s.iteration,
+            s.summary.trim()
+        ),
+        None => String::new(),
+    };
+    let entries: String = entries
+        .iter()
+        .map(journal::render_single_entry)
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!(
+        "You keep the running summary of a long coding-agent run. Each iteration's agent \
+         sees it before the few most recent journal entries, so it must carry the arc of \
+         the run: what is done, what was decided, and what keeps going wrong.\n\n\
+         Fold the new journal entries into the summary so far. Keep recent iterations in \
+         more detail than old ones, and compress older material further each time.\n\n\
+         Reply with the summary only, inside `<summary>...</summary>`, using exactly these \
+         sections:\n\n\
+         ### Progress\n\
+         Tasks finished and the state of the work, with file paths.\n\n\
+         ### Decisions\n\
+         Approaches chosen and why.\n\n\
+         ### Open Problems\n\
+         Repeated failures, retries and unresolved issues, or \"None\".\n\n\
+         Keep the summary under 300 words. Do not invent anything the entries do not show.\n\n\
+         {previous}## New Journal Entries\n\n{entries}"
+    )
+}
+
+fn parse_tag(text: &str, tag: &str) -> Option<String> {
+    let open = format!("<{tag}>");
+    let start = text.rfind(&open)? + open.len();
+    let end = text[start..].find(&format!("</{tag}>"))?;
+    let body = text[start..start + end].trim();
+    (!body.is_empty()).then(|| body.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag::{self, init_db};
+    use crate::project::{ProjectConfig, RalphConfig};
+    use tempfile::TempDir;
+
+    #[test]
+    fn note_sigil_and_transcript_tail() {
+        assert_eq!(
+            parse_tag(
+                "<note>draft</note>\n<note>\n### Changed\nsrc/a.rs\n</note>",
+                "note"
+            )
+            .as_deref(),
+            Some("### Changed\nsrc/a.rs")
+        );
+        assert_eq!(parse_tag("<note> </note>", "note"), None);
+        assert_eq!(parse_tag("no sigil", "note"), None);
+        assert_eq!(
+            parse_tag("<summary>arc</summary>", "summary").as_deref(),
+            Some("arc")
+        );
+
+        assert_eq!(transcript_tail("short"), "short");
+        let long = format!("{}end", "é".repeat(MAX_TRANSCRIPT_CHARS));
+        let tail = transcript_tail(&long);
+        assert!(tail.starts_with("[Transcript start omitted]"));
+        assert!(tail.ends_with("end"));
+        assert_eq!(
+            tail.chars().count(),
+            MAX_TRANSCRIPT_CHARS + "[Transcript start omitted]\n\n".len()
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn agent_notes_are_kept_when_disabled_or_failing() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let task = dag::create_task(&db, "Add login", None, None, 0).unwrap();
+        let project = ProjectConfig {
+            root: dir.path().to_path_buf(),
+            config: RalphConfig::default(),
+        };
+        let mut config =
+            Config::from_run_args(None, None, None, project, None, None, false, None, None)
+                .unwrap();
+        config.agent_command = "ralph-no-such-agent".to_string();
+        let result = StreamingResult {
+            full_text: "Added the login form.".to_string(),
+            thoughts: String::new(),
+            files_modified: vec!["src/login.rs".to_string()],
+            files_read: Vec::new(),
+            duration_ms: 0,
+            stop_reason: agent_client_protocol::StopReason::EndTurn,
+            usage: Default::default(),
+            session_id: None,
+        };
+
+        let kept = (Some("Form done".to_string()), 0.0);
+        assert_eq!(
+            notes(&config, &task, &result, Some("Form done"), "done").await,
+            kept
+        );
+        config.ralph_config.execution.summarize_journal = true;
+        assert_eq!(
+            notes(&config, &task, &result, Some("Form done"), "done").await,
+            kept
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn run_summary_waits_for_enough_entries_and_keeps_the_old_one_on_failure() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let project = ProjectConfig {
+            root: dir.path().to_path_buf(),
+            config: RalphConfig::default(),
+        };
+        let mut config =
+            Config::from_run_args(None, None, None, project, None, None, false, None, None)
+                .unwrap();
+        config.agent_command = "ralph-no-such-agent".to_string();
+        config.ralph_config.execution.run_summary_every = 2;
+        let entry = |iteration: u32| JournalEntry {
+            id: 0,
+            run_id: config.run_id.clone(),
+            iteration,
+            task_id: None,
+            feature_id: None,
+            outcome: "done".to_string(),
+            model: None,
+            duration_secs: 1.0,
+            cost_usd: 0.0,
+            input_tokens: 0,
+            output_tokens: 0,
+            files_modified: Vec::new(),
+            files_read: Vec::new(),
+            notes: Some(format!("Finished step {iteration}")),
+            created_at: "2026-02-18T10:00:00Z".to_string(),
+        };
+        let previous = RollingSummary {
+            run_id: config.run_id.clone(),
+            iteration: 1,
+            summary: "Step 1 done.".to_string(),
+            model: None,
+            cost_usd: 0.0,
+        };
+        journal::insert_journal_entry(&db, &entry(1)).unwrap();
+        journal::insert_rolling_summary(&db, &previous).unwrap();
+        journal::insert_journal_entry(&db, &entry(2)).unwrap();
+
+        let since = journal::query_journal_since(&db, &config.run_id, 1).unwrap();
+        let prompt = run_summary_instructions(Some(&previous), &since);
+        assert!(prompt.contains("## Summary So Far (iterations 1-1)\n\nStep 1 done."));
+        assert!(prompt.contains("Finished step 2"));
+        assert!(!prompt.contains("Finished step 1"));
+
+        // One entry since the summary is not enough; two are, but the agent fails
+        refresh_run_summary(&db, &config).await;
+        journal::insert_journal_entry(&db, &entry(3)).unwrap();
+        refresh_run_summary(&db, &config).await;
+        assert_eq!(
+            journal::latest_rolling_summary(&db, &config.run_id).unwrap(),
+            Some(previous)
+        );
+    }
+}
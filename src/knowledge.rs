@@ -13,17 +13,22 @@
 //! can incrementally build context by following links.
 
 use crate::acp::types::KnowledgeSigil;
-use anyhow::Result;
-use std::collections::{HashMap, HashSet};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
+/// Longest entry body, in words; longer `<knowledge>` bodies are truncated.
+pub const MAX_BODY_WORDS: usize = 500;
+
 /// A single knowledge base entry parsed from a `.ralph/knowledge/*.md` file.
 #[derive(Debug, Clone)]
 pub struct KnowledgeEntry {
     pub title: String,
     pub tags: Vec<String>,
-    #[allow(dead_code)]
     pub feature: Option<String>,
+    /// `feature` keeps the entry to tasks of `feature`.
+    pub scope: KnowledgeScope,
     pub body: String,
     #[allow(dead_code)]
     pub created_at: String,
@@ -31,6 +36,89 @@ pub struct KnowledgeEntry {
     pub file_path: PathBuf,
 }
 
+impl KnowledgeEntry {
+    /// The feature whose namespace the entry lives in, or `None` if global.
+    pub fn scoped_feature(&self) -> Option<&str> {
+        match self.scope {
+            KnowledgeScope::Feature => self.feature.as_deref(),
+            KnowledgeScope::Global => None,
+        }
+    }
+}
+
+/// Namespace of a knowledge entry (`scope` frontmatter, `[knowledge] scope`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KnowledgeScope {
+    /// Offered to every task.
+    #[default]
+    Global,
+    /// Offered only to tasks of the feature the entry was written in.
+    Feature,
+}
+
+impl KnowledgeScope {
+    /// The `scope` frontmatter value.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            KnowledgeScope::Global => "global",
+            KnowledgeScope::Feature => "feature",
+        }
+    }
+}
+
+/// Prompt knowledge settings (`[knowledge]`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct KnowledgeConfig {
+    /// Most entries injected into one prompt (0 = no limit beyond the token budget).
+    #[serde(default)]
+    pub max_entries_per_prompt: usize,
+    /// Task types (`[knowledge.task_types.<name>]`) scoping tagged entries to
+    /// the tasks they are relevant to.
+    #[serde(default)]
+    pub task_types: BTreeMap<String, TaskTypeFilter>,
+    /// Offer an entry when a verification fails with an actionable reason.
+    #[serde(default = "default_true")]
+    pub learn_from_failures: bool,
+    /// Scope of entries written during a feature.
+    #[serde(default)]
+    pub scope: KnowledgeScope,
+    /// Score added to matched entries scoped to the task's feature.
+    #[serde(default = "default_feature_bonus")]
+    pub feature_bonus: u32,
+}
+
+impl Default for KnowledgeConfig {
+    fn default() -> Self {
+        Self {
+            max_entries_per_prompt: 0,
+            task_types: BTreeMap::new(),
+            learn_from_failures: true,
+            scope: KnowledgeScope::Global,
+            feature_bonus: default_feature_bonus(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_feature_bonus() -> u32 {
+    3
+}
+
+/// One task type: which tasks it applies to and which entries belong to it.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TaskTypeFilter {
+    /// Words in a task's title or description that give it this type.
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Tags owned by this type: exact (`css`) or a namespace (`frontend:*`).
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
 /// Bidirectional link graph built from `[[Title]]` references in knowledge entry bodies.
 ///
 /// For each entry title, tracks:
@@ -197,7 +285,7 @@ pub fn expand_via_links(
         }
     }
 
-    expanded.sort_by(|a, b| b.1.cmp(&a.1));
+    expanded.sort_by_key(|e| std::cmp::Reverse(e.1));
     expanded
 }
 
@@ -256,7 +344,8 @@ pub fn slugify_title(title: &str) -> String {
 /// Parse YAML frontmatter from a knowledge `.md` file.
 ///
 /// Extracts `title`, `tags` (YAML array format: `[tag1, tag2]`), `feature`,
-/// and `created_at` from the frontmatter block delimited by `---`.
+/// `scope` (`feature` or `global`, the default) and `created_at` from the
+/// frontmatter block delimited by `---`.
 ///
 /// Returns `None` if:
 /// - No frontmatter block found (doesn't start with `---`)
@@ -266,6 +355,12 @@ pub fn slugify_title(title: &str) -> String {
 /// The `file_path` field of the returned entry is set to an empty `PathBuf`;
 /// callers should set it after calling this function.
 fn parse_knowledge_frontmatter(content: &str) -> Option<KnowledgeEntry> {
+    parse_entry_fields(content).filter(|entry| !entry.tags.is_empty())
+}
+
+/// Parse the frontmatter fields without requiring tags, so the linter can
+/// report entries that discovery skips. `None` without frontmatter or title.
+pub(crate) fn parse_entry_fields(content: &str) -> Option<KnowledgeEntry> {
     let trimmed = content.trim();
     if !trimmed.starts_with("---") {
         return None;
@@ -280,6 +375,7 @@ fn parse_knowledge_frontmatter(content: &str) -> Option<KnowledgeEntry> {
     let mut title: Option<String> = None;
     let mut tags: Vec<String> = Vec::new();
     let mut feature: Option<String> = None;
+    let mut scope = KnowledgeScope::Global;
     let mut created_at: Option<String> = None;
 
     for line in frontmatter.lines() {
@@ -296,20 +392,20 @@ fn parse_knowledge_frontmatter(content: &str) -> Option<KnowledgeEntry> {
                 .collect();
         } else if let Some(val) = line.strip_prefix("feature:") {
             feature = Some(val.trim().trim_matches('"').to_string());
+        } else if let Some(val) = line.strip_prefix("scope:") {
+            if val.trim().trim_matches('"') == "feature" {
+                scope = KnowledgeScope::Feature;
+            }
         } else if let Some(val) = line.strip_prefix("created_at:") {
             created_at = Some(val.trim().trim_matches('"').to_string());
         }
     }
 
-    let title = title?;
-    if tags.is_empty() {
-        return None;
-    }
-
     Some(KnowledgeEntry {
-        title,
+        title: title?,
         tags,
         feature,
+        scope,
         body,
         created_at: created_at.unwrap_or_default(),
         file_path: PathBuf::new(), // caller fills this in
@@ -336,24 +432,49 @@ pub fn discover_knowledge(project_root: &Path) -> Vec<KnowledgeEntry> {
             continue;
         }
 
-        if let Ok(content) = std::fs::read_to_string(&path) {
-            if let Some(mut kb_entry) = parse_knowledge_frontmatter(&content) {
-                kb_entry.file_path = path;
-                entries.push(kb_entry);
-            }
+        if let Some(kb_entry) = read_knowledge_file(&path) {
+            entries.push(kb_entry);
         }
     }
 
     entries
 }
 
+/// Entries sorted by title, only those scoped to `feature` when given.
+pub fn list_entries(project_root: &Path, feature: Option<&str>) -> Vec<KnowledgeEntry> {
+    let mut entries: Vec<KnowledgeEntry> = discover_knowledge(project_root)
+        .into_iter()
+        .filter(|e| {
+            feature.is_none_or(|name| {
+                e.scoped_feature()
+                    .is_some_and(|f| f.eq_ignore_ascii_case(name))
+            })
+        })
+        .collect();
+    entries.sort_by_key(|e| e.title.to_lowercase());
+    entries
+}
+
+/// Read and parse one knowledge file; `None` if it is unreadable or its
+/// frontmatter is malformed.
+pub(crate) fn read_knowledge_file(path: &Path) -> Option<KnowledgeEntry> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let mut entry = parse_knowledge_frontmatter(&content)?;
+    entry.file_path = path.to_path_buf();
+    Some(entry)
+}
+
 /// Score and filter knowledge entries by tag relevance to the current context.
 ///
 /// Scoring per FR-6.3:
 /// - +2 for each tag matching a word in the task title or description (lowercased)
 /// - +2 for each tag matching the current feature name (lowercased)
-/// - +1 for each tag matching a word in any file path from the last journal entry
-///   (file paths are split on `/`, `.`, `-`, `_`; words must be > 2 chars)
+/// - +1 for each tag matching a word in any file path from the last journal entry,
+///   modified or read, or read by earlier attempts at the task (`read_files`;
+///   paths are split on `/`, `.`, `-`, `_`; words must be > 2 chars)
+/// - +3 for each tag matching one of the task's labels (lowercased)
+///
+/// A namespaced tag (`test:fixtures`) scores on its last segment (`fixtures`).
 ///
 /// Returns entries with score > 0, sorted by score descending.
 pub fn match_knowledge_entries(
@@ -362,6 +483,8 @@ pub fn match_knowledge_entries(
     task_description: &str,
     feature_name: Option<&str>,
     recent_files: &[String],
+    read_files: &[String],
+    labels: &[String],
 ) -> Vec<(KnowledgeEntry, u32)> {
     // Build word set from task title + description (lowercased)
     let context_words: HashSet<String> = format!("{} {}", task_title, task_description)
@@ -372,6 +495,7 @@ pub fn match_knowledge_entries(
     // Build word set from file paths (split on /, ., -, _; filter words > 2 chars)
     let file_words: HashSet<String> = recent_files
         .iter()
+        .chain(read_files)
         .flat_map(|p| p.split(&['/', '.', '-', '_'][..]))
         .map(|w| w.to_lowercase())
         .filter(|w| w.len() > 2)
@@ -384,27 +508,128 @@ pub fn match_knowledge_entries(
         .map(|entry| {
             let mut score: u32 = 0;
             for tag in &entry.tags {
+                // Namespaced tags (`test:fixtures`) score on their last segment
+                let tag = tag.rsplit(':').next().unwrap_or(tag);
                 if context_words.contains(tag) {
                     score += 2;
                 }
                 if let Some(ref feat) = feature_lower {
-                    if tag == feat {
+                    if tag == feat.as_str() {
                         score += 2;
                     }
                 }
                 if file_words.contains(tag) {
                     score += 1;
                 }
+                if labels.iter().any(|l| l.eq_ignore_ascii_case(tag)) {
+                    score += 3;
+                }
             }
             (entry.clone(), score)
         })
         .filter(|(_, score)| *score > 0)
         .collect();
 
-    scored.sort_by(|a, b| b.1.cmp(&a.1));
+    scored.sort_by_key(|e| std::cmp::Reverse(e.1));
     scored
 }
 
+/// Apply `[knowledge]` task-type filters and the per-prompt entry cap.
+///
+/// A task has every type whose keywords appear as a word in its title or
+/// description. An entry with a tag owned by some type is kept only if the
+/// task has one of the types owning it; untyped entries always pass. `entries`
+/// must already be sorted by score, so the cap keeps the best matches.
+pub fn filter_for_task(
+    mut entries: Vec<(KnowledgeEntry, u32)>,
+    config: &KnowledgeConfig,
+    task_title: &str,
+    task_description: &str,
+) -> Vec<(KnowledgeEntry, u32)> {
+    if !config.task_types.is_empty() {
+        let task_types: HashSet<&str> = task_types(config, task_title, task_description)
+            .into_iter()
+            .collect();
+        entries.retain(|(entry, _)| {
+            let mut owners = config
+                .task_types
+                .iter()
+                .filter(|(_, f)| {
+                    entry
+                        .tags
+                        .iter()
+                        .any(|tag| f.tags.iter().any(|p| tag_matches(tag, p)))
+                })
+                .map(|(name, _)| name.as_str())
+                .peekable();
+            owners.peek().is_none() || owners.any(|name| task_types.contains(name))
+        });
+    }
+    if config.max_entries_per_prompt > 0 {
+        entries.truncate(config.max_entries_per_prompt);
+    }
+    entries
+}
+
+/// Apply knowledge namespaces to matched entries of a task in `feature_name`.
+///
+/// Entries scoped to another feature, or to any feature when the task has
+/// none, are dropped. Entries scoped to the task's feature gain
+/// `feature_bonus` and are re-sorted by score ahead of equal global ones.
+pub fn apply_scope(
+    mut entries: Vec<(KnowledgeEntry, u32)>,
+    config: &KnowledgeConfig,
+    feature_name: Option<&str>,
+) -> Vec<(KnowledgeEntry, u32)> {
+    entries.retain(|(entry, _)| {
+        entry
+            .scoped_feature()
+            .is_none_or(|f| feature_name.is_some_and(|name| f.eq_ignore_ascii_case(name)))
+    });
+    for (entry, score) in &mut entries {
+        if entry.scoped_feature().is_some() {
+            *score += config.feature_bonus;
+        }
+    }
+    entries.sort_by_key(|(entry, score)| {
+        (std::cmp::Reverse(*score), entry.scoped_feature().is_none())
+    });
+    entries
+}
+
+/// Names of the `[knowledge.task_types]` whose keywords appear as a word in
+/// the task's title or description, in name order.
+pub fn task_types<'a>(
+    config: &'a KnowledgeConfig,
+    task_title: &str,
+    task_description: &str,
+) -> Vec<&'a str> {
+    let lower = format!("{} {}", task_title, task_description).to_lowercase();
+    let words: HashSet<&str> = lower
+        .split(|c: char| !c.is_alphanumeric() && c != '-' && c != '_')
+        .collect();
+    config
+        .task_types
+        .iter()
+        .filter(|(_, f)| {
+            f.keywords
+                .iter()
+                .any(|k| words.contains(k.to_lowercase().as_str()))
+        })
+        .map(|(name, _)| name.as_str())
+        .collect()
+}
+
+/// Whether `tag` matches `pattern`: equal, or inside a `ns:*` namespace.
+fn tag_matches(tag: &str, pattern: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => tag
+            .to_lowercase()
+            .starts_with(prefix.to_lowercase().as_str()),
+        None => tag.eq_ignore_ascii_case(pattern),
+    }
+}
+
 /// Write a knowledge entry to disk, handling deduplication.
 ///
 /// Deduplication logic (FR-3.4):
@@ -412,12 +637,17 @@ pub fn match_knowledge_entries(
 /// - >50% tag overlap AND substring title match → update existing file with merged tags.
 /// - Otherwise → create new file at `{slug}.md`.
 ///
-/// The body is truncated to 500 words (FR-3.5).
+/// With `scope = Feature` and a `feature`, the entry lives in that feature's
+/// namespace: it only deduplicates against entries of the same namespace, and
+/// its file name is prefixed with the feature's slug.
+///
+/// The body is truncated to [`MAX_BODY_WORDS`] words (FR-3.5).
 /// Returns an error if `sigil.tags` is empty (FR-3.6).
 pub fn write_knowledge_entry(
     project_root: &Path,
     sigil: &KnowledgeSigil,
     feature: Option<&str>,
+    scope: KnowledgeScope,
 ) -> Result<PathBuf> {
     // FR-3.6: at least one tag required
     if sigil.tags.is_empty() {
@@ -425,16 +655,28 @@ pub fn write_knowledge_entry(
     }
 
     // FR-3.5: truncate body to 500 words
-    let body = truncate_to_words(&sigil.body, 500);
+    let body = truncate_to_words(&sigil.body, MAX_BODY_WORDS);
 
     let kb_dir = project_root.join(".ralph/knowledge");
     std::fs::create_dir_all(&kb_dir)?;
 
-    let slug = slugify_title(&sigil.title);
+    let scope = match feature {
+        Some(_) => scope,
+        None => KnowledgeScope::Global,
+    };
+    let namespace = match scope {
+        KnowledgeScope::Feature => feature,
+        KnowledgeScope::Global => None,
+    };
+    let slug = match namespace {
+        Some(f) => format!("{}-{}", slugify_title(f), slugify_title(&sigil.title)),
+        None => slugify_title(&sigil.title),
+    };
     let default_path = kb_dir.join(format!("{}.md", slug));
 
     // FR-3.4: deduplication check
-    let final_path = find_dedup_target(&kb_dir, &sigil.title, &sigil.tags).unwrap_or(default_path);
+    let final_path =
+        find_dedup_target(&kb_dir, &sigil.title, &sigil.tags, namespace).unwrap_or(default_path);
 
     // Merge tags if updating an existing file
     let merged_tags = if final_path.exists() {
@@ -453,21 +695,165 @@ pub fn write_knowledge_entry(
 
     // Write the file with YAML frontmatter
     let now = chrono::Utc::now().to_rfc3339();
-    let feature_line = feature
-        .map(|f| format!("feature: \"{}\"\n", f))
-        .unwrap_or_default();
-    let content = format!(
-        "---\ntitle: \"{}\"\ntags: [{}]\n{}created_at: \"{}\"\n---\n\n{}\n",
-        sigil.title,
-        merged_tags.join(", "),
-        feature_line,
-        now,
-        body,
-    );
+    let content = render_entry_file(&sigil.title, &merged_tags, feature, scope, &now, &body);
     std::fs::write(&final_path, content)?;
     Ok(final_path)
 }
 
+/// Counts reported by `import_bundle`.
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportSummary {
+    /// Entries written as new files.
+    pub created: usize,
+    /// Entries folded into an existing entry (tags merged, local body kept).
+    pub merged: usize,
+}
+
+/// Export knowledge entries to a gzipped tar bundle.
+///
+/// When `tags` is non-empty, only entries carrying at least one of the tags are
+/// included. Files are stored as `knowledge/<file>.md`. Returns the entry count.
+pub fn export_bundle(project_root: &Path, output: &Path, tags: &[String]) -> Result<usize> {
+    let wanted: HashSet<String> = tags.iter().map(|t| t.trim().to_lowercase()).collect();
+    let mut entries: Vec<KnowledgeEntry> = discover_knowledge(project_root)
+        .into_iter()
+        .filter(|e| wanted.is_empty() || e.tags.iter().any(|t| wanted.contains(t)))
+        .collect();
+    entries.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+
+    let file = std::fs::File::create(output)
+        .with_context(|| format!("Failed to create bundle {}", output.display()))?;
+    let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+    let mut archive = tar::Builder::new(encoder);
+
+    for entry in &entries {
+        let name = entry
+            .file_path
+            .file_name()
+            .context("Knowledge entry has no file name")?;
+        archive
+            .append_path_with_name(&entry.file_path, Path::new("knowledge").join(name))
+            .with_context(|| format!("Failed to add {} to bundle", entry.file_path.display()))?;
+    }
+
+    archive.into_inner()?.finish()?;
+    Ok(entries.len())
+}
+
+/// Import a bundle produced by `export_bundle` into `.ralph/knowledge/`.
+///
+/// Each entry is deduplicated with the same rules as `<knowledge>` sigils
+/// (exact title, or >50% tag overlap plus substring title). Duplicates keep the
+/// local body and gain the bundle's tags. `[[links]]` in new entries that point
+/// at a bundle entry merged under a different local title are rewritten to
+/// that local title so the link graph stays connected.
+pub fn import_bundle(project_root: &Path, bundle: &Path) -> Result<ImportSummary> {
+    let file = std::fs::File::open(bundle)
+        .with_context(|| format!("Failed to open bundle {}", bundle.display()))?;
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+
+    let mut incoming: Vec<KnowledgeEntry> = Vec::new();
+    for item in archive.entries().context("Failed to read bundle")? {
+        let mut item = item?;
+        let path = item.path()?.into_owned();
+        if path.extension().and_then(|e| e.to_str()) != Some("md") {
+            continue;
+        }
+        let mut content = String::new();
+        std::io::Read::read_to_string(&mut item, &mut content)
+            .with_context(|| format!("Failed to read {} from bundle", path.display()))?;
+        if let Some(entry) = parse_knowledge_frontmatter(&content) {
+            incoming.push(entry);
+        }
+    }
+
+    // An entry repeated in the bundle (same file name) folds into the first
+    let mut summary = ImportSummary::default();
+    let mut unique: Vec<KnowledgeEntry> = Vec::new();
+    for entry in incoming {
+        let slug = slugify_title(&entry.title);
+        match unique.iter_mut().find(|e| {
+            e.scoped_feature() == entry.scoped_feature() && slugify_title(&e.title) == slug
+        }) {
+            Some(first) => {
+                first.tags = merge_tags(&first.tags, &entry.tags);
+                summary.merged += 1;
+            }
+            None => unique.push(entry),
+        }
+    }
+    let incoming = unique;
+
+    let kb_dir = project_root.join(".ralph/knowledge");
+    std::fs::create_dir_all(&kb_dir)?;
+
+    // Resolve every bundle entry to its local target before writing anything,
+    // so link rewriting sees the final title of each entry.
+    let mut targets: Vec<Option<(PathBuf, KnowledgeEntry)>> = Vec::new();
+    let mut renames: HashMap<String, String> = HashMap::new();
+    for entry in &incoming {
+        let existing =
+            find_dedup_target(&kb_dir, &entry.title, &entry.tags, entry.scoped_feature()).and_then(
+                |path| {
+                    let content = std::fs::read_to_string(&path).ok()?;
+                    parse_knowledge_frontmatter(&content).map(|e| (path, e))
+                },
+            );
+        if let Some((_, ref local)) = existing {
+            if local.title.to_lowercase() != entry.title.to_lowercase() {
+                renames.insert(entry.title.to_lowercase(), local.title.clone());
+            }
+        }
+        targets.push(existing);
+    }
+
+    for (entry, target) in incoming.iter().zip(targets) {
+        match target {
+            Some((path, local)) => {
+                let tags = merge_tags(&local.tags, &entry.tags);
+                let content = render_entry_file(
+                    &local.title,
+                    &tags,
+                    local.feature.as_deref(),
+                    local.scope,
+                    &local.created_at,
+                    &local.body,
+                );
+                std::fs::write(&path, content)?;
+                summary.merged += 1;
+            }
+            None => {
+                let body = rewrite_links(&entry.body, &renames);
+                // A local entry too different to merge may still share the
+                // file name; never overwrite it
+                let slug = match entry.scoped_feature() {
+                    Some(f) => format!("{}-{}", slugify_title(f), slugify_title(&entry.title)),
+                    None => slugify_title(&entry.title),
+                };
+                let path = (1..)
+                    .map(|n| match n {
+                        1 => kb_dir.join(format!("{slug}.md")),
+                        n => kb_dir.join(format!("{slug}-{n}.md")),
+                    })
+                    .find(|path| !path.exists())
+                    .expect("unbounded range");
+                let content = render_entry_file(
+                    &entry.title,
+                    &entry.tags,
+                    entry.feature.as_deref(),
+                    entry.scope,
+                    &entry.created_at,
+                    &body,
+                );
+                std::fs::write(&path, content)?;
+                summary.created += 1;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
 /// Render knowledge entries as markdown for the system prompt (FR-6.5).
 ///
 /// Enforces a 2000-token budget (estimated at 4 chars/token, FR-6.4).
@@ -533,7 +919,14 @@ pub fn render_knowledge_context_with_graph(
 /// Returns `Some(path)` if:
 /// - A file with the exact same title (case-insensitive) exists, OR
 /// - A file with >50% tag overlap AND a substring title match exists.
-fn find_dedup_target(kb_dir: &Path, title: &str, tags: &[String]) -> Option<PathBuf> {
+///
+/// Only entries in the `namespace` feature (`None` = global) are considered.
+fn find_dedup_target(
+    kb_dir: &Path,
+    title: &str,
+    tags: &[String],
+    namespace: Option<&str>,
+) -> Option<PathBuf> {
     let title_lower = title.to_lowercase();
 
     let entries = std::fs::read_dir(kb_dir).ok()?;
@@ -545,6 +938,9 @@ fn find_dedup_target(kb_dir: &Path, title: &str, tags: &[String]) -> Option<Path
 
         if let Ok(content) = std::fs::read_to_string(&path) {
             if let Some(existing) = parse_knowledge_frontmatter(&content) {
+                if existing.scoped_feature() != namespace {
+                    continue;
+                }
                 let existing_lower = existing.title.to_lowercase();
 
                 // Exact title match
@@ -590,6 +986,58 @@ fn truncate_to_words(text: &str, max_words: usize) -> String {
     }
 }
 
+/// Render a knowledge file: YAML frontmatter followed by the body.
+pub(crate) fn render_entry_file(
+    title: &str,
+    tags: &[String],
+    feature: Option<&str>,
+    scope: KnowledgeScope,
+    created_at: &str,
+    body: &str,
+) -> String {
+    let feature_line = feature
+        .map(|f| format!("feature: \"{}\"\n", f))
+        .unwrap_or_default();
+    let scope_line = match scope {
+        KnowledgeScope::Feature => format!("scope: {}\n", scope.as_str()),
+        KnowledgeScope::Global => String::new(),
+    };
+    format!(
+        "---\ntitle: \"{}\"\ntags: [{}]\n{}{}created_at: \"{}\"\n---\n\n{}\n",
+        title,
+        tags.join(", "),
+        feature_line,
+        scope_line,
+        created_at,
+        body,
+    )
+}
+
+/// Rewrite `[[Title]]` links whose lowercase title appears in `renames`.
+pub(crate) fn rewrite_links(body: &str, renames: &HashMap<String, String>) -> String {
+    if renames.is_empty() {
+        return body.to_string();
+    }
+
+    let mut out = String::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(start) = rest.find("[[") {
+        let content_start = start + 2;
+        let Some(len) = rest[content_start..].find("]]") else {
+            break;
+        };
+        let title = &rest[content_start..content_start + len];
+        out.push_str(&rest[..start]);
+        match renames.get(&title.trim().to_lowercase()) {
+            Some(new_title) => out.push_str(&format!("[[{}]]", new_title)),
+            None => out.push_str(&rest[start..content_start + len + 2]),
+        }
+        rest = &rest[content_start + len + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
 /// Merge two tag lists, preserving order and deduplicating.
 ///
 /// Tags from `existing` come first; new tags not already present are appended.
@@ -869,6 +1317,7 @@ Body content.
             title: title.to_string(),
             tags: tags.iter().map(|s| s.to_string()).collect(),
             feature: None,
+            scope: KnowledgeScope::Global,
             body: "Test body.".to_string(),
             created_at: "2026-02-17T12:00:00Z".to_string(),
             file_path: PathBuf::new(),
@@ -888,6 +1337,8 @@ Body content.
             "implement rust features",
             None,
             &[],
+            &[],
+            &[],
         );
 
         // "rust" tag matches "rust" in title and description -> score 2 (title) + 2 (desc) ... actually:
@@ -910,6 +1361,106 @@ Body content.
         assert!(python_matched.is_empty(), "Python entry should not match");
     }
 
+    #[test]
+    fn test_match_knowledge_namespaced_tag_scores_on_suffix() {
+        let entries = vec![make_entry("Fixture Layout", &["test:fixtures"])];
+        let matched = match_knowledge_entries(&entries, "Add fixtures", "", None, &[], &[], &[]);
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].1, 2);
+    }
+
+    #[test]
+    fn test_match_knowledge_boosts_task_labels() {
+        let entries = vec![
+            make_entry("Migrations", &["risky", "database"]),
+            make_entry("Styling", &["css"]),
+        ];
+        let labels = vec!["risky".to_string()];
+        let matched =
+            match_knowledge_entries(&entries, "Add orders table", "", None, &[], &[], &labels);
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].0.title, "Migrations");
+        assert_eq!(matched[0].1, 3);
+    }
+
+    fn typed_config(max: usize) -> KnowledgeConfig {
+        toml::from_str(&format!(
+            r#"
+max_entries_per_prompt = {max}
+[task_types.test]
+keywords = ["test", "coverage"]
+tags = ["test:*"]
+[task_types.frontend]
+keywords = ["css", "ui"]
+tags = ["frontend:*", "styling"]
+"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_filter_for_task_scopes_typed_entries() {
+        let entries = vec![
+            (make_entry("Fixtures", &["test:fixtures"]), 4),
+            (make_entry("Colors", &["frontend:colors"]), 3),
+            (make_entry("Spacing", &["Styling"]), 2),
+            (make_entry("Build", &["cargo"]), 1),
+        ];
+        let titles = |kept: Vec<(KnowledgeEntry, u32)>| -> Vec<String> {
+            kept.into_iter().map(|(e, _)| e.title).collect()
+        };
+
+        let build = filter_for_task(entries.clone(), &typed_config(0), "Add endpoint", "");
+        assert_eq!(titles(build), vec!["Build"]);
+
+        let test = filter_for_task(entries.clone(), &typed_config(0), "Raise coverage", "");
+        assert_eq!(titles(test), vec!["Fixtures", "Build"]);
+
+        let both = filter_for_task(entries, &typed_config(0), "Test the UI", "");
+        assert_eq!(titles(both), vec!["Fixtures", "Colors", "Spacing", "Build"]);
+    }
+
+    #[test]
+    fn test_apply_scope_prefers_active_feature() {
+        let scoped = |title: &str, feature: &str| KnowledgeEntry {
+            feature: Some(feature.to_string()),
+            scope: KnowledgeScope::Feature,
+            ..make_entry(title, &["auth"])
+        };
+        let entries = vec![
+            (make_entry("Global", &["auth"]), 4),
+            (scoped("Billing only", "billing"), 4),
+            (scoped("Auth only", "auth"), 2),
+        ];
+        let titles = |kept: Vec<(KnowledgeEntry, u32)>| -> Vec<(String, u32)> {
+            kept.into_iter().map(|(e, s)| (e.title, s)).collect()
+        };
+        let config = KnowledgeConfig::default();
+
+        let auth = apply_scope(entries.clone(), &config, Some("auth"));
+        assert_eq!(
+            titles(auth),
+            vec![("Auth only".to_string(), 5), ("Global".to_string(), 4)]
+        );
+        let none = apply_scope(entries, &config, None);
+        assert_eq!(titles(none), vec![("Global".to_string(), 4)]);
+    }
+
+    #[test]
+    fn test_filter_for_task_caps_entries() {
+        let entries = vec![
+            (make_entry("A", &["cargo"]), 3),
+            (make_entry("B", &["rust"]), 2),
+            (make_entry("C", &["sqlite"]), 1),
+        ];
+        let kept = filter_for_task(entries.clone(), &typed_config(2), "Anything", "");
+        assert_eq!(kept.len(), 2);
+        assert_eq!(kept[1].0.title, "B");
+
+        let unlimited = filter_for_task(entries, &KnowledgeConfig::default(), "Anything", "");
+        assert_eq!(unlimited.len(), 3);
+    }
+
     #[test]
     fn test_match_knowledge_feature_bonus() {
         let entries = vec![
@@ -923,6 +1474,8 @@ Body content.
             "some description",
             Some("improved-memory"),
             &[],
+            &[],
+            &[],
         );
 
         // "improved-memory" tag matches feature name -> +2
@@ -946,6 +1499,8 @@ Body content.
             "modify configuration",
             None,
             &recent_files,
+            &[],
+            &[],
         );
 
         // "config" tag: matches "configuration" in description? No, it's an exact word match.
@@ -959,6 +1514,29 @@ Body content.
         assert_eq!(matched[0].1, 1, "file path match should give score 1");
     }
 
+    #[test]
+    fn test_match_knowledge_read_path_bonus() {
+        let entries = vec![make_entry("Retry Policy", &["backoff"])];
+        let read_files = vec!["src/net/backoff.rs".to_string()];
+
+        let matched =
+            match_knowledge_entries(&entries, "Investigate flaky sync", "", None, &[], &[], &[]);
+        assert!(matched.is_empty());
+
+        // A file the agent only read counts like a modified one, once
+        let recent_files = read_files.clone();
+        let matched = match_knowledge_entries(
+            &entries,
+            "Investigate flaky sync",
+            "",
+            None,
+            &recent_files,
+            &read_files,
+            &[],
+        );
+        assert_eq!(matched[0].1, 1);
+    }
+
     #[test]
     fn test_match_knowledge_no_match() {
         let entries = vec![
@@ -972,6 +1550,8 @@ Body content.
             "implement rust features",
             Some("rust-feature"),
             &[],
+            &[],
+            &[],
         );
 
         // None of the tags (python, patterns, css, frontend) match "rust" context
@@ -989,6 +1569,8 @@ Body content.
             "sqlite rust storage",
             None,
             &[],
+            &[],
+            &[],
         );
 
         // context_words = {"rust", "database", "implementation", "sqlite", "storage"}
@@ -1008,8 +1590,15 @@ Body content.
             make_entry("High Score", &["rust", "testing", "implementation"]),
         ];
 
-        let matched =
-            match_knowledge_entries(&entries, "rust implementation", "testing rust", None, &[]);
+        let matched = match_knowledge_entries(
+            &entries,
+            "rust implementation",
+            "testing rust",
+            None,
+            &[],
+            &[],
+            &[],
+        );
 
         assert_eq!(matched.len(), 2);
         // High Score should come first (higher score)
@@ -1039,7 +1628,8 @@ Body content.
             "Some body content here.",
         );
 
-        let path = write_knowledge_entry(temp.path(), &sigil, None).unwrap();
+        let path =
+            write_knowledge_entry(temp.path(), &sigil, None, KnowledgeScope::Global).unwrap();
         assert!(path.exists(), "file should be created");
 
         let content = fs::read_to_string(&path).unwrap();
@@ -1061,11 +1651,46 @@ Body content.
         let temp = TempDir::new().unwrap();
         let sigil = make_sigil("Feature Entry", &["rust"], "Body content.");
 
-        let path = write_knowledge_entry(temp.path(), &sigil, Some("my-feature")).unwrap();
+        let path = write_knowledge_entry(
+            temp.path(),
+            &sigil,
+            Some("my-feature"),
+            KnowledgeScope::Global,
+        )
+        .unwrap();
         let content = fs::read_to_string(&path).unwrap();
         assert!(content.contains("feature: \"my-feature\""));
     }
 
+    #[test]
+    fn test_write_knowledge_entry_feature_scope_is_a_namespace() {
+        let temp = TempDir::new().unwrap();
+        let sigil = make_sigil("Session Store", &["auth"], "Global body.");
+        write_knowledge_entry(temp.path(), &sigil, None, KnowledgeScope::Global).unwrap();
+
+        let sigil = make_sigil("Session Store", &["auth"], "Scoped body.");
+        let path =
+            write_knowledge_entry(temp.path(), &sigil, Some("Login"), KnowledgeScope::Feature)
+                .unwrap();
+        assert_eq!(path.file_name().unwrap(), "login-session-store.md");
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("scope: feature\n"));
+
+        // The scoped entry did not replace the global one of the same title
+        let global = list_entries(temp.path(), None);
+        assert_eq!(global.len(), 2);
+        let scoped = list_entries(temp.path(), Some("login"));
+        assert_eq!(scoped.len(), 1);
+        assert_eq!(scoped[0].body, "Scoped body.");
+        assert_eq!(scoped[0].scoped_feature(), Some("Login"));
+
+        // Without a feature, the scope setting has nothing to scope to
+        let sigil = make_sigil("Standalone", &["auth"], "Body.");
+        let path =
+            write_knowledge_entry(temp.path(), &sigil, None, KnowledgeScope::Feature).unwrap();
+        assert!(!fs::read_to_string(&path).unwrap().contains("scope:"));
+    }
+
     #[test]
     fn test_write_knowledge_entry_no_tags() {
         let temp = TempDir::new().unwrap();
@@ -1075,7 +1700,7 @@ Body content.
             body: "Body content.".to_string(),
         };
 
-        let result = write_knowledge_entry(temp.path(), &sigil, None);
+        let result = write_knowledge_entry(temp.path(), &sigil, None, KnowledgeScope::Global);
         assert!(result.is_err(), "should return error when no tags");
     }
 
@@ -1085,11 +1710,13 @@ Body content.
 
         // Write first entry
         let sigil1 = make_sigil("My Entry", &["rust"], "First content.");
-        let path1 = write_knowledge_entry(temp.path(), &sigil1, None).unwrap();
+        let path1 =
+            write_knowledge_entry(temp.path(), &sigil1, None, KnowledgeScope::Global).unwrap();
 
         // Write second entry with same title — should update, not create new file
         let sigil2 = make_sigil("My Entry", &["rust", "testing"], "Second content wins.");
-        let path2 = write_knowledge_entry(temp.path(), &sigil2, None).unwrap();
+        let path2 =
+            write_knowledge_entry(temp.path(), &sigil2, None, KnowledgeScope::Global).unwrap();
 
         // Should be the same file
         assert_eq!(path1, path2, "dedup should reuse existing file");
@@ -1114,7 +1741,7 @@ Body content.
             &["rust", "testing", "patterns"],
             "First.",
         );
-        write_knowledge_entry(temp.path(), &sigil1, None).unwrap();
+        write_knowledge_entry(temp.path(), &sigil1, None, KnowledgeScope::Global).unwrap();
 
         // Write similar entry with >50% tag overlap and substring title
         let sigil2 = make_sigil(
@@ -1122,7 +1749,8 @@ Body content.
             &["rust", "testing", "new-tag"],
             "Updated content.",
         );
-        let path2 = write_knowledge_entry(temp.path(), &sigil2, None).unwrap();
+        let path2 =
+            write_knowledge_entry(temp.path(), &sigil2, None, KnowledgeScope::Global).unwrap();
 
         // Should update the existing file (tag overlap > 0.5, title match)
         let kb_dir = temp.path().join(".ralph/knowledge");
@@ -1151,7 +1779,8 @@ Body content.
         let long_body = words.join(" ");
 
         let sigil = make_sigil("Long Entry", &["test"], &long_body);
-        let path = write_knowledge_entry(temp.path(), &sigil, None).unwrap();
+        let path =
+            write_knowledge_entry(temp.path(), &sigil, None, KnowledgeScope::Global).unwrap();
 
         let content = fs::read_to_string(&path).unwrap();
         assert!(
@@ -1182,6 +1811,7 @@ Body content.
                     title: "Rust Testing".to_string(),
                     tags: vec!["rust".to_string(), "testing".to_string()],
                     feature: None,
+                    scope: KnowledgeScope::Global,
                     body: "Use #[test] attribute.".to_string(),
                     created_at: "2026-02-17T12:00:00Z".to_string(),
                     file_path: PathBuf::new(),
@@ -1193,6 +1823,7 @@ Body content.
                     title: "SQLite Patterns".to_string(),
                     tags: vec!["database".to_string(), "sqlite".to_string()],
                     feature: None,
+                    scope: KnowledgeScope::Global,
                     body: "Use WAL mode for performance.".to_string(),
                     created_at: "2026-02-17T12:00:00Z".to_string(),
                     file_path: PathBuf::new(),
@@ -1227,6 +1858,7 @@ Body content.
                     title: format!("Entry {}", i),
                     tags: vec!["test".to_string()],
                     feature: None,
+                    scope: KnowledgeScope::Global,
                     body: large_body.clone(),
                     created_at: "2026-02-17T12:00:00Z".to_string(),
                     file_path: PathBuf::new(),
@@ -1318,6 +1950,7 @@ Body content.
             title: title.to_string(),
             tags: tags.iter().map(|s| s.to_string()).collect(),
             feature: None,
+            scope: KnowledgeScope::Global,
             body: body.to_string(),
             created_at: "2026-02-17T12:00:00Z".to_string(),
             file_path: PathBuf::new(),
@@ -1640,4 +2273,163 @@ Body content.
         assert!(!graph.outlinks.contains_key("python tips"));
         assert!(!graph.backlinks.contains_key("python tips"));
     }
+
+    // --- Bundle export/import ---
+
+    #[test]
+    fn test_export_bundle_filters_by_tags() {
+        let src = TempDir::new().unwrap();
+        let kb_dir = src.path().join(".ralph/knowledge");
+        fs::create_dir_all(&kb_dir).unwrap();
+        fs::write(
+            kb_dir.join("a.md"),
+            make_valid_md("SQLite WAL", &["sqlite"], "Enable WAL."),
+        )
+        .unwrap();
+        fs::write(
+            kb_dir.join("b.md"),
+            make_valid_md("Python Tips", &["python"], "Use venv."),
+        )
+        .unwrap();
+
+        let bundle = src.path().join("kb.tar.gz");
+        let count = export_bundle(src.path(), &bundle, &["sqlite".to_string()]).unwrap();
+        assert_eq!(count, 1);
+
+        let dst = TempDir::new().unwrap();
+        let summary = import_bundle(dst.path(), &bundle).unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                created: 1,
+                merged: 0
+            }
+        );
+        let imported = discover_knowledge(dst.path());
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].title, "SQLite WAL");
+        assert_eq!(imported[0].body, "Enable WAL.");
+    }
+
+    #[test]
+    fn test_import_bundle_dedups_and_rewrites_links() {
+        let src = TempDir::new().unwrap();
+        let src_kb = src.path().join(".ralph/knowledge");
+        fs::create_dir_all(&src_kb).unwrap();
+        fs::write(
+            src_kb.join("migrations.md"),
+            make_valid_md(
+                "Schema Migrations Guide",
+                &["sqlite", "schema"],
+                "Bundle body.",
+            ),
+        )
+        .unwrap();
+        fs::write(
+            src_kb.join("columns.md"),
+            make_valid_md(
+                "Task Columns",
+                &["tasks"],
+                "See [[Schema Migrations Guide]] and [[Elsewhere]].",
+            ),
+        )
+        .unwrap();
+        let bundle = src.path().join("kb.tar.gz");
+        assert_eq!(export_bundle(src.path(), &bundle, &[]).unwrap(), 2);
+
+        // Destination already has an overlapping entry under a shorter title
+        let dst = TempDir::new().unwrap();
+        let dst_kb = dst.path().join(".ralph/knowledge");
+        fs::create_dir_all(&dst_kb).unwrap();
+        fs::write(
+            dst_kb.join("schema-migrations.md"),
+            make_valid_md("Schema Migrations", &["sqlite", "schema"], "Local body."),
+        )
+        .unwrap();
+
+        let summary = import_bundle(dst.path(), &bundle).unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                created: 1,
+                merged: 1
+            }
+        );
+
+        let entries = discover_knowledge(dst.path());
+        assert_eq!(entries.len(), 2);
+        let local = entries
+            .iter()
+            .find(|e| e.title == "Schema Migrations")
+            .unwrap();
+        assert_eq!(local.body, "Local body.");
+        let columns = entries.iter().find(|e| e.title == "Task Columns").unwrap();
+        assert_eq!(columns.body, "See [[Schema Migrations]] and [[Elsewhere]].");
+    }
+
+    #[test]
+    fn test_import_bundle_never_overwrites_local_files() {
+        let src = TempDir::new().unwrap();
+        let src_kb = src.path().join(".ralph/knowledge");
+        fs::create_dir_all(&src_kb).unwrap();
+        fs::write(
+            src_kb.join("a.md"),
+            make_valid_md("Retry Policy", &["loop"], "First."),
+        )
+        .unwrap();
+        fs::write(
+            src_kb.join("b.md"),
+            make_valid_md("Retry policy", &["retries"], "Second."),
+        )
+        .unwrap();
+        fs::write(
+            src_kb.join("c.md"),
+            make_valid_md("Cache: Layout", &["cache"], "Bundle body."),
+        )
+        .unwrap();
+        let bundle = src.path().join("kb.tar.gz");
+        assert_eq!(export_bundle(src.path(), &bundle, &[]).unwrap(), 3);
+
+        // Same file name as "Cache: Layout", but a different title and tags
+        let dst = TempDir::new().unwrap();
+        let dst_kb = dst.path().join(".ralph/knowledge");
+        fs::create_dir_all(&dst_kb).unwrap();
+        fs::write(
+            dst_kb.join("cache-layout.md"),
+            make_valid_md("Cache Layout", &["disk"], "Local body."),
+        )
+        .unwrap();
+
+        let summary = import_bundle(dst.path(), &bundle).unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                created: 2,
+                merged: 1
+            }
+        );
+
+        let entries = discover_knowledge(dst.path());
+        assert_eq!(entries.len(), 3);
+        let local = entries.iter().find(|e| e.title == "Cache Layout").unwrap();
+        assert_eq!(local.body, "Local body.");
+        assert!(dst_kb.join("cache-layout-2.md").exists());
+        let retry = entries
+            .iter()
+            .find(|e| e.title.eq_ignore_ascii_case("retry policy"))
+            .unwrap();
+        assert_eq!(retry.body, "First.");
+        assert_eq!(retry.tags, vec!["loop", "retries"]);
+    }
+
+    #[test]
+    fn test_rewrite_links_leaves_unmapped_links() {
+        let mut renames = HashMap::new();
+        renames.insert("old".to_string(), "New".to_string());
+        assert_eq!(
+            rewrite_links("[[Old]] then [[Other]] then [[ old ]]", &renames),
+            "[[New]] then [[Other]] then [[New]]"
+        );
+        assert_eq!(rewrite_links("unclosed [[Old", &renames), "unclosed [[Old");
+    }
 }
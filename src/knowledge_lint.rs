@@ -0,0 +1,523 @@
+//! Project-wide lint of the knowledge base (`ralph knowledge lint`).
+//!
+//! Discovery silently skips entries it cannot use and dedup only runs when a
+//! `<knowledge>` sigil is written, so problems accumulate unnoticed in
+//! hand-edited or imported entries. The lint reports:
+//!
+//! - files without frontmatter or a title (ignored by discovery),
+//! - entries without tags (also ignored),
+//! - `[[links]]` whose target entry does not exist,
+//! - titles close enough to another entry's to be the same note,
+//! - bodies over [`knowledge::MAX_BODY_WORDS`] words,
+//! - `feature:` references to features that no longer exist.
+//!
+//! `--fix` repairs what needs no judgement: it derives tags from the title,
+//! re-points links whose slug matches an entry, truncates long bodies at a
+//! line boundary and drops stale feature references. Near duplicates and
+//! malformed files are left to the user.
+
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::knowledge::{self, KnowledgeEntry, KnowledgeScope};
+
+/// Title similarity (shared words over all words) at which two entries count
+/// as near duplicates.
+const NEAR_DUPLICATE_SIMILARITY: f64 = 0.75;
+
+/// Most tags `--fix` derives for an untagged entry.
+const MAX_DERIVED_TAGS: usize = 3;
+
+/// Words too common to say anything about a title.
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "for", "from", "how", "in", "into", "of", "on", "or", "the", "to", "via",
+    "vs", "when", "with",
+];
+
+/// What kind of problem a [`LintIssue`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintKind {
+    Malformed,
+    MissingTags,
+    BrokenLink,
+    NearDuplicate,
+    OverBudget,
+    StaleFeature,
+}
+
+impl LintKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            LintKind::Malformed => "malformed",
+            LintKind::MissingTags => "missing-tags",
+            LintKind::BrokenLink => "broken-link",
+            LintKind::NearDuplicate => "near-duplicate",
+            LintKind::OverBudget => "over-budget",
+            LintKind::StaleFeature => "stale-feature",
+        }
+    }
+}
+
+/// One problem in one knowledge file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintIssue {
+    pub path: PathBuf,
+    pub kind: LintKind,
+    pub message: String,
+    /// Whether `--fix` can repair it.
+    pub fixable: bool,
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind.label(), self.message)?;
+        if self.fixable {
+            f.write_str(" (fixable)")?;
+        }
+        Ok(())
+    }
+}
+
+/// A knowledge file as the linter sees it.
+struct LintFile {
+    path: PathBuf,
+    /// `None` when the frontmatter or title is missing.
+    entry: Option<KnowledgeEntry>,
+}
+
+/// Lint every entry in `.ralph/knowledge/`. `features` holds the names of
+/// features that exist (archived ones included). Issues come sorted by file.
+pub fn lint(project_root: &Path, features: &HashSet<String>) -> Vec<LintIssue> {
+    let files = load_files(project_root);
+    let titles = TitleIndex::new(&files);
+    let mut issues = Vec::new();
+
+    for file in &files {
+        let Some(entry) = &file.entry else {
+            issues.push(issue(
+                file,
+                LintKind::Malformed,
+                "no frontmatter with a title; the entry is ignored".to_string(),
+                false,
+            ));
+            continue;
+        };
+
+        if entry.tags.is_empty() {
+            let derived = derive_tags(entry);
+            let message = if derived.is_empty() {
+                "no tags; the entry is ignored until it has some".to_string()
+            } else {
+                format!(
+                    "no tags; the entry is ignored until it has some (fix adds [{}])",
+                    derived.join(", ")
+                )
+            };
+            issues.push(issue(
+                file,
+                LintKind::MissingTags,
+                message,
+                !derived.is_empty(),
+            ));
+        }
+
+        for link in knowledge::extract_links(&strip_code(&entry.body)) {
+            if titles.exists(&link) {
+                continue;
+            }
+            let message = match titles.by_slug(&link) {
+                Some(target) => format!("[[{link}]] links to no entry (fix: [[{target}]])"),
+                None => format!("[[{link}]] links to no entry"),
+            };
+            let fixable = titles.by_slug(&link).is_some();
+            issues.push(issue(file, LintKind::BrokenLink, message, fixable));
+        }
+
+        let words = entry.body.split_whitespace().count();
+        if words > knowledge::MAX_BODY_WORDS {
+            issues.push(issue(
+                file,
+                LintKind::OverBudget,
+                format!(
+                    "body is {words} words, over the {}-word budget",
+                    knowledge::MAX_BODY_WORDS
+                ),
+                true,
+            ));
+        }
+
+        if let Some(feature) = stale_feature(entry, features) {
+            issues.push(issue(
+                file,
+                LintKind::StaleFeature,
+                format!("feature `{feature}` no longer exists"),
+                true,
+            ));
+        }
+    }
+
+    // Each pair is reported once, on the file that sorts later.
+    for (i, later) in files.iter().enumerate() {
+        let Some(entry) = &later.entry else {
+            continue;
+        };
+        for earlier in &files[..i] {
+            let Some(other) = &earlier.entry else {
+                continue;
+            };
+            if near_duplicate(&entry.title, &other.title) {
+                issues.push(issue(
+                    later,
+                    LintKind::NearDuplicate,
+                    format!(
+                        "title is close to \"{}\" ({}); merge them by hand",
+                        other.title,
+                        file_name(&earlier.path)
+                    ),
+                    false,
+                ));
+            }
+        }
+    }
+
+    issues.sort_by(|a, b| a.path.cmp(&b.path));
+    issues
+}
+
+/// Repair every fixable issue in place. Returns the issues that were fixed.
+pub fn fix(project_root: &Path, features: &HashSet<String>) -> Result<Vec<LintIssue>> {
+    let fixable: Vec<LintIssue> = lint(project_root, features)
+        .into_iter()
+        .filter(|issue| issue.fixable)
+        .collect();
+    let dirty: HashSet<&Path> = fixable.iter().map(|issue| issue.path.as_path()).collect();
+
+    let files = load_files(project_root);
+    let titles = TitleIndex::new(&files);
+    for file in &files {
+        let Some(entry) = &file.entry else {
+            continue;
+        };
+        if !dirty.contains(file.path.as_path()) {
+            continue;
+        }
+
+        let tags = if entry.tags.is_empty() {
+            derive_tags(entry)
+        } else {
+            entry.tags.clone()
+        };
+        let renames: HashMap<String, String> = knowledge::extract_links(&strip_code(&entry.body))
+            .into_iter()
+            .filter(|link| !titles.exists(link))
+            .filter_map(|link| {
+                let target = titles.by_slug(&link)?.to_string();
+                Some((link.to_lowercase(), target))
+            })
+            .collect();
+        let body = truncate_lines(
+            &knowledge::rewrite_links(&entry.body, &renames),
+            knowledge::MAX_BODY_WORDS,
+        );
+        // An entry scoped to a deleted feature would never be offered again
+        let (feature, scope) = match stale_feature(entry, features) {
+            Some(_) => (None, KnowledgeScope::Global),
+            None => (entry.feature.as_deref(), entry.scope),
+        };
+
+        let content = knowledge::render_entry_file(
+            &entry.title,
+            &tags,
+            feature,
+            scope,
+            &entry.created_at,
+            &body,
+        );
+        std::fs::write(&file.path, content)?;
+    }
+
+    Ok(fixable)
+}
+
+fn issue(file: &LintFile, kind: LintKind, message: String, fixable: bool) -> LintIssue {
+    LintIssue {
+        path: file.path.clone(),
+        kind,
+        message,
+        fixable,
+    }
+}
+
+fn load_files(project_root: &Path) -> Vec<LintFile> {
+    let Ok(dir) = std::fs::read_dir(project_root.join(".ralph/knowledge")) else {
+        return Vec::new();
+    };
+    let mut files: Vec<LintFile> = dir
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("md"))
+        .map(|path| {
+            let entry = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| knowledge::parse_entry_fields(&content));
+            LintFile { path, entry }
+        })
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files
+}
+
+/// Titles of all entries, for resolving `[[links]]`.
+struct TitleIndex {
+    /// Lowercased titles.
+    lower: HashSet<String>,
+    /// Title slug → title, for slugs only one entry has.
+    slugs: HashMap<String, Option<String>>,
+}
+
+impl TitleIndex {
+    fn new(files: &[LintFile]) -> Self {
+        let mut lower = HashSet::new();
+        let mut slugs: HashMap<String, Option<String>> = HashMap::new();
+        for entry in files.iter().filter_map(|f| f.entry.as_ref()) {
+            lower.insert(entry.title.to_lowercase());
+            slugs
+                .entry(knowledge::slugify_title(&entry.title))
+                .and_modify(|title| *title = None)
+                .or_insert_with(|| Some(entry.title.clone()));
+        }
+        Self { lower, slugs }
+    }
+
+    fn exists(&self, link: &str) -> bool {
+        self.lower.contains(&link.to_lowercase())
+    }
+
+    /// The one entry whose title has the same slug as `link`
+    /// (`[[ui-runtime]]` → `UI Runtime`).
+    fn by_slug(&self, link: &str) -> Option<&str> {
+        self.slugs.get(&knowledge::slugify_title(link))?.as_deref()
+    }
+}
+
+fn stale_feature<'a>(entry: &'a KnowledgeEntry, features: &HashSet<String>) -> Option<&'a str> {
+    entry
+        .feature
+        .as_deref()
+        .filter(|f| !f.is_empty() && !features.contains(*f))
+}
+
+/// `body` without fenced code blocks and inline code spans, where `[[x]]` is
+/// an example (Cargo's `[[bin]]`) rather than a link.
+fn strip_code(body: &str) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut in_fence = false;
+    for line in body.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        for (i, part) in line.split('`').enumerate() {
+            if i % 2 == 0 {
+                out.push_str(part);
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Tags for an untagged entry: its feature, then the title's telling words.
+fn derive_tags(entry: &KnowledgeEntry) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    if let Some(feature) = entry.feature.as_deref().filter(|f| !f.is_empty()) {
+        tags.push(knowledge::slugify_title(feature));
+    }
+    for word in title_words(&entry.title) {
+        if tags.len() >= MAX_DERIVED_TAGS {
+            break;
+        }
+        if word.len() > 1 && !tags.contains(&word) {
+            tags.push(word);
+        }
+    }
+    tags
+}
+
+/// Lowercased title words without stopwords.
+fn title_words(title: &str) -> Vec<String> {
+    title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty() && !STOPWORDS.contains(w))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Whether two titles name the same note: the same words up to case,
+/// punctuation, stopwords and plurals, or nearly all words shared.
+fn near_duplicate(a: &str, b: &str) -> bool {
+    let stem = |w: String| match w.strip_suffix('s') {
+        Some(s) if s.len() > 2 => s.to_string(),
+        _ => w,
+    };
+    let a: HashSet<String> = title_words(a).into_iter().map(stem).collect();
+    let b: HashSet<String> = title_words(b).into_iter().map(stem).collect();
+    if a.is_empty() || b.is_empty() {
+        return false;
+    }
+    let shared = a.intersection(&b).count();
+    let all = a.union(&b).count();
+    shared as f64 / all as f64 >= NEAR_DUPLICATE_SIMILARITY
+}
+
+/// Cut `body` to `max_words` words, keeping whole lines so markdown survives;
+/// the line that crosses the budget is cut at a word and marked. The marker
+/// counts as a word so the result passes the lint.
+fn truncate_lines(body: &str, max_words: usize) -> String {
+    if body.split_whitespace().count() <= max_words {
+        return body.to_string();
+    }
+    let max_words = max_words.saturating_sub(1);
+    let mut kept: Vec<String> = Vec::new();
+    let mut words = 0;
+    for line in body.lines() {
+        let count = line.split_whitespace().count();
+        if words + count <= max_words {
+            kept.push(line.to_string());
+            words += count;
+            continue;
+        }
+        let rest: Vec<&str> = line.split_whitespace().take(max_words - words).collect();
+        kept.push(format!("{} [truncated]", rest.join(" ")).trim().to_string());
+        break;
+    }
+    kept.join("\n")
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, file: &str, content: &str) {
+        let dir = root.join(".ralph/knowledge");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(file), content).unwrap();
+    }
+
+    fn kinds(issues: &[LintIssue]) -> Vec<(String, LintKind)> {
+        issues
+            .iter()
+            .map(|i| (file_name(&i.path), i.kind))
+            .collect()
+    }
+
+    #[test]
+    fn reports_each_kind_and_fixes_the_repairable_ones() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        write(
+            root,
+            "a-wal.md",
+            "---\ntitle: \"SQLite WAL Mode\"\ntags: [sqlite]\nfeature: \"gone\"\n---\n\nSee [[ui-runtime]] and [[Nowhere]].\n",
+        );
+        write(
+            root,
+            "b-wal.md",
+            "---\ntitle: \"SQLite WAL modes\"\ntags: [db]\nfeature: \"auth\"\n---\n\nBody.\n",
+        );
+        write(
+            root,
+            "c-ui.md",
+            "---\ntitle: \"UI Runtime\"\ntags: []\n---\n\nBody.\n",
+        );
+        let long = format!("# Heading\n\n{}\n", "word ".repeat(600).trim());
+        write(
+            root,
+            "d-long.md",
+            &format!("---\ntitle: \"Long Note\"\ntags: [x]\n---\n\n{long}"),
+        );
+        write(root, "e-bad.md", "no frontmatter here\n");
+        let features: HashSet<String> = ["auth".to_string()].into();
+
+        let issues = lint(root, &features);
+        assert_eq!(
+            kinds(&issues),
+            vec![
+                ("a-wal.md".to_string(), LintKind::BrokenLink),
+                ("a-wal.md".to_string(), LintKind::BrokenLink),
+                ("a-wal.md".to_string(), LintKind::StaleFeature),
+                ("b-wal.md".to_string(), LintKind::NearDuplicate),
+                ("c-ui.md".to_string(), LintKind::MissingTags),
+                ("d-long.md".to_string(), LintKind::OverBudget),
+                ("e-bad.md".to_string(), LintKind::Malformed),
+            ]
+        );
+        assert!(issues[0].message.contains("fix: [[UI Runtime]]"));
+        assert!(!issues[1].fixable, "no entry resembles [[Nowhere]]");
+        assert!(issues[4].message.contains("[ui, runtime]"));
+
+        let fixed = fix(root, &features).unwrap();
+        assert_eq!(fixed.len(), 4);
+        assert_eq!(
+            kinds(&lint(root, &features)),
+            vec![
+                ("a-wal.md".to_string(), LintKind::BrokenLink),
+                ("b-wal.md".to_string(), LintKind::NearDuplicate),
+                ("e-bad.md".to_string(), LintKind::Malformed),
+            ]
+        );
+
+        let wal = knowledge::read_knowledge_file(&root.join(".ralph/knowledge/a-wal.md")).unwrap();
+        assert_eq!(wal.feature, None);
+        assert!(wal.body.contains("[[UI Runtime]]"));
+        let note =
+            knowledge::read_knowledge_file(&root.join(".ralph/knowledge/d-long.md")).unwrap();
+        assert!(note.body.starts_with("# Heading\n\n"), "lines are kept");
+        assert!(note.body.ends_with("[truncated]"));
+        assert_eq!(
+            note.body.split_whitespace().count(),
+            knowledge::MAX_BODY_WORDS
+        );
+    }
+
+    #[test]
+    fn near_duplicates_ignore_case_punctuation_and_plurals() {
+        assert!(near_duplicate("Feature Lifecycle", "feature-lifecycles"));
+        assert!(near_duplicate(
+            "Retry policy for the agent",
+            "Agent retry policy"
+        ));
+        assert!(!near_duplicate("Retry policy", "Retry budget"));
+        assert!(!near_duplicate("Knowledge lint", "Journal search"));
+    }
+
+    #[test]
+    fn clean_knowledge_base_has_no_issues() {
+        let tmp = TempDir::new().unwrap();
+        write(
+            tmp.path(),
+            "a.md",
+            "---\ntitle: \"Alpha\"\ntags: [a]\n---\n\nSee [[beta]].\n",
+        );
+        write(
+            tmp.path(),
+            "b.md",
+            "---\ntitle: \"Beta\"\ntags: [b]\n---\n\nBack to [[Alpha]], not `[[bin]]`.\n\n```toml\n[[bin]]\n```\n",
+        );
+        assert!(lint(tmp.path(), &HashSet::new()).is_empty());
+    }
+}
@@ -0,0 +1,201 @@
+//! Knowledge candidates from verification failures.
+//!
+//! When a verifier rejects a task with an actionable reason ("tests must be
+//! run with --features sqlite"), the run loop turns the first actionable
+//! sentence into a candidate `<knowledge>` entry tagged with the files the
+//! task touched, and asks whether to keep it. CI runs and `--yes` accept it,
+//! `--no-input` declines. Each lesson is offered once per run, however often
+//! the same failure repeats. `[knowledge] learn_from_failures = false` turns
+//! the offer off.
+
+use anyhow::Result;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::acp::types::KnowledgeSigil;
+use crate::input;
+use crate::knowledge::{self, KnowledgeScope};
+use crate::output::formatter;
+
+/// Words of the lesson kept in the entry title.
+const TITLE_WORDS: usize = 8;
+
+/// Most file-derived tags on one entry, besides `verification`.
+const MAX_FILE_TAGS: usize = 5;
+
+/// Path words too generic to be useful tags.
+const GENERIC_WORDS: [&str; 7] = ["src", "lib", "mod", "main", "index", "test", "tests"];
+
+/// Phrases that make a sentence an instruction rather than a description.
+const CUES: [&str; 13] = [
+    "must",
+    "need to",
+    "needs to",
+    "should",
+    "require",
+    "requires",
+    "required",
+    "run with",
+    "instead of",
+    "always",
+    "never",
+    "do not",
+    "don't",
+];
+
+/// Lesson titles already offered in this run.
+static OFFERED: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// A knowledge entry for the first actionable sentence of `reason`, or
+/// `None` when the reason only describes what went wrong.
+pub fn candidate(reason: &str, task_id: &str, files: &[String]) -> Option<KnowledgeSigil> {
+    let lesson = reason
+        .split(['\n', ';'])
+        .flat_map(|part| part.split(". "))
+        .map(|s| {
+            s.trim()
+                .trim_start_matches(['-', '*', ' '])
+                .trim_end_matches('.')
+        })
+        .find(|s| is_actionable(s))?;
+
+    let mut words = lesson.split_whitespace();
+    let mut title: String = words
+        .by_ref()
+        .take(TITLE_WORDS)
+        .collect::<Vec<_>>()
+        .join(" ");
+    if words.next().is_some() {
+        title.push_str("...");
+    }
+    let mut chars = title.chars();
+    let title = match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => return None,
+    };
+
+    let mut tags = vec!["verification".to_string()];
+    for tag in file_tags(files) {
+        if tags.len() > MAX_FILE_TAGS {
+            break;
+        }
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+
+    let mut body = format!("{lesson}.\n\nLearned from a failed verification of {task_id}.");
+    if !files.is_empty() {
+        body.push_str(&format!(" Files: {}.", files.join(", ")));
+    }
+    Some(KnowledgeSigil { title, tags, body })
+}
+
+fn is_actionable(sentence: &str) -> bool {
+    let words: String = sentence
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '\'' {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect();
+    let words = format!(
+        " {} ",
+        words.split_whitespace().collect::<Vec<_>>().join(" ")
+    );
+    CUES.iter().any(|cue| words.contains(&format!(" {cue} ")))
+}
+
+/// Tags from file paths, split the way knowledge matching splits them, so
+/// the entry surfaces for later tasks touching the same files.
+fn file_tags(files: &[String]) -> Vec<String> {
+    files
+        .iter()
+        .flat_map(|path| {
+            let stem = path.rsplit_once('.').map_or(path.as_str(), |(s, _)| s);
+            stem.split(['/', '-', '_'])
+                .map(str::to_lowercase)
+                .collect::<Vec<_>>()
+        })
+        .filter(|w| w.len() > 2 && !GENERIC_WORDS.contains(&w.as_str()) && !w.starts_with('.'))
+        .collect()
+}
+
+/// Offer `sigil` to the user and write it to `.ralph/knowledge/` when
+/// accepted, in `scope` when written during `feature`. Returns the written
+/// path.
+pub fn offer(
+    project_root: &Path,
+    sigil: &KnowledgeSigil,
+    feature: Option<&str>,
+    scope: KnowledgeScope,
+) -> Result<Option<std::path::PathBuf>> {
+    {
+        let mut offered = OFFERED.lock().unwrap_or_else(|e| e.into_inner());
+        if !offered.insert(sigil.title.clone()) {
+            return Ok(None);
+        }
+    }
+    let question = format!(
+        "Verification failed with a reason worth remembering:\n\n  {}\n\n\
+         Save it as a knowledge entry tagged {}?",
+        sigil.title,
+        sigil.tags.join(", ")
+    );
+    let accepted = if crate::ci::is_enabled() || input::assume_yes() {
+        true
+    } else if input::no_input() {
+        false
+    } else if crate::ui::is_active() {
+        crate::ui::prompt_confirm("Knowledge", &question, true).unwrap_or(false)
+    } else {
+        input::confirm(&question, true)
+    };
+    if !accepted {
+        formatter::emit_event_info("knowledge", &format!("skipped: {}", sigil.title));
+        return Ok(None);
+    }
+    knowledge::write_knowledge_entry(project_root, sigil, feature, scope).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn candidate_takes_first_actionable_sentence() {
+        let files = vec![
+            "src/db/sqlite_store.rs".to_string(),
+            "Cargo.toml".to_string(),
+        ];
+        let sigil = candidate(
+            "Build passes. Tests must be run with --features sqlite; otherwise the store tests are skipped.",
+            "t-1",
+            &files,
+        )
+        .unwrap();
+        assert_eq!(sigil.title, "Tests must be run with --features sqlite");
+        assert_eq!(sigil.tags, ["verification", "sqlite", "store", "cargo"]);
+        assert!(sigil
+            .body
+            .starts_with("Tests must be run with --features sqlite.\n"));
+        assert!(sigil.body.contains("t-1") && sigil.body.contains("src/db/sqlite_store.rs"));
+
+        let long = candidate(
+            "you should always check the return value of every call",
+            "t-2",
+            &[],
+        );
+        assert_eq!(
+            long.unwrap().title,
+            "You should always check the return value of..."
+        );
+
+        assert!(candidate("The function returns the wrong value.", "t-3", &files).is_none());
+    }
+}
@@ -10,17 +10,58 @@
 #![allow(dead_code)]
 
 pub mod acp;
+pub mod agent_models;
+pub mod api;
+pub mod base64;
+pub mod burndown;
+pub mod chaos;
+pub mod ci;
 pub mod cli;
+pub mod compaction;
+pub mod completions;
 pub mod config;
+pub mod config_check;
+pub mod config_edit;
+pub mod context_cache;
 pub mod dag;
+pub mod dag_review;
+pub mod diff_context;
+pub mod exit_when;
 pub mod feature;
+pub mod feature_prompts;
+pub mod flaky;
+pub mod guardrails;
+pub mod input;
 pub mod interrupt;
 pub mod journal;
+pub mod journal_summary;
 pub mod knowledge;
+pub mod knowledge_lint;
+pub mod lessons;
+pub mod offline;
 pub mod output;
+pub mod plan_coverage;
+pub mod post_feature;
+pub mod pricing;
 pub mod project;
+pub mod prompt_templates;
+pub mod prune;
+pub mod remediation;
+pub mod replan;
 pub mod review;
+pub mod rollback;
+pub mod run_diff;
 pub mod run_loop;
+pub mod run_summary;
+pub mod scratchpad;
+pub mod sigil_actions;
+pub mod speculation;
+pub mod steering;
 pub mod strategy;
+pub mod sync;
+pub mod task_proposal;
+pub mod terminal_env;
+pub mod test_impact;
+pub mod transcripts;
 pub mod ui;
 pub mod verification;
@@ -1,36 +1,78 @@
 //! Ralph - Autonomous agent loop harness for Claude Code
 
 mod acp;
+mod agent_models;
+mod api;
+mod base64;
+mod burndown;
+mod chaos;
+mod ci;
 mod cli;
+mod compaction;
+mod completions;
 mod config;
+mod config_check;
+mod config_edit;
+mod context_cache;
 mod dag;
+mod dag_review;
+mod diff_context;
+mod exit_when;
 mod feature;
 mod feature_prompts;
+mod flaky;
+mod guardrails;
+mod input;
 mod interrupt;
 mod journal;
+mod journal_summary;
 mod knowledge;
+mod knowledge_lint;
+mod lessons;
+mod offline;
 mod output;
+mod plan_coverage;
+mod post_feature;
+mod pricing;
 mod project;
+mod prompt_templates;
+mod prune;
+mod remediation;
+mod replan;
 mod review;
+mod rollback;
+mod run_diff;
 mod run_loop;
+mod run_summary;
+mod scratchpad;
+mod sigil_actions;
+mod speculation;
+mod steering;
 mod strategy;
+mod sync;
+mod task_proposal;
+mod terminal_env;
+mod test_impact;
+mod transcripts;
 mod ui;
 mod verification;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
 use colored::Colorize;
 use std::process::ExitCode;
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> ExitCode {
-    match run().await {
+    let code = match run().await {
         Ok(result) => result,
         Err(e) => {
             eprintln!("Error: {e:#}");
             ExitCode::FAILURE
         }
-    }
+    };
+    output::notify::flush();
+    code
 }
 
 #[cfg(test)]
@@ -46,6 +88,7 @@ mod tests {
         let _blocked = run_loop::Outcome::Blocked;
         let _noplan = run_loop::Outcome::NoPlan;
         let _interrupted = run_loop::Outcome::Interrupted;
+        let _exit_condition = run_loop::Outcome::ExitCondition;
     }
 
     #[test]
@@ -193,6 +236,21 @@ mod tests {
             assert!(msg.contains("current spec draft"));
         }
 
+        #[test]
+        fn test_authoring_prompt_sections_mention_marker_and_draft() {
+            let marker = feature_prompts::build_completion_marker_section("/p/spec.md");
+            assert!(marker.contains(feature::COMPLETE_MARKER));
+            assert!(marker.contains("/p/spec.md"));
+
+            let resume = feature_prompts::build_resume_draft_section(
+                "spec",
+                "/p/spec.md",
+                "# Auth\n\nUsers log",
+            );
+            assert!(resume.contains("Current spec Draft (incomplete)"));
+            assert!(resume.ends_with("# Auth\n\nUsers log"));
+        }
+
         #[test]
         fn test_initial_message_plan_start() {
             let msg = build_initial_message_plan("my-feature", false);
@@ -263,7 +321,9 @@ mod tests {
 }
 
 async fn run() -> Result<ExitCode> {
+    completions::handle_request();
     let args = cli::Args::parse_args();
+    input::configure(args.yes, args.no_input);
     let ui_mode = ui::UiMode::resolve(args.no_ui);
 
     match args.command {
@@ -271,47 +331,126 @@ async fn run() -> Result<ExitCode> {
             project::init()?;
             Ok(ExitCode::SUCCESS)
         }
+        Some(cli::Command::Completions { shell }) => {
+            completions::write_script(shell, &mut std::io::stdout())?;
+            Ok(ExitCode::SUCCESS)
+        }
         Some(cli::Command::Auth { agent }) => handle_auth(agent).await,
         Some(cli::Command::Feature { action }) => handle_feature(action, ui_mode).await,
         Some(cli::Command::Task { action }) => handle_task(action, ui_mode).await,
+        Some(cli::Command::Knowledge { action }) => handle_knowledge(action),
+        Some(cli::Command::Journal { action }) => handle_journal(action),
+        Some(cli::Command::Db { action }) => handle_db(action),
+        Some(cli::Command::Sync { action }) => handle_sync(action),
+        Some(cli::Command::Config { action }) => handle_config(action),
+        Some(cli::Command::Stats { json }) => handle_stats(json),
+        Some(cli::Command::Ui { action }) => handle_ui(action),
+        Some(cli::Command::Runs { action }) => handle_runs(action),
+        Some(cli::Command::Api { socket }) => {
+            let project = project::discover()?;
+            let socket = api::socket_path(&project.root, socket);
+            api::serve(project, &socket).await?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(cli::Command::Logs {
+            run_id,
+            task,
+            terminals: _,
+            follow,
+            lines,
+        }) => match task {
+            Some(task_id) => handle_terminal_logs(&task_id),
+            None => handle_logs(run_id, follow, lines),
+        },
         Some(cli::Command::Run {
-            target,
+            targets,
+            order,
             limit,
             model_strategy,
             model,
             max_retries,
             no_verify,
             agent,
+            ci,
+            timeout,
+            scheduler,
+            auto_replan,
+            approve_commands,
+            only,
+            skip,
+            offline,
+            chaos,
         }) => {
+            if ci {
+                ci::enable();
+            }
+            if approve_commands {
+                guardrails::require_command_approval();
+            }
             let project = project::discover()?;
-            ui::theme::init_with_overrides(
-                ui::theme::resolve_theme_name(&project.config.ui.theme),
-                Some(&project.config.ui.colors),
-            );
-            let ui_guard = ui::start(ui_mode);
+            ui::theme::init_for_project(
+                &project.root,
+                &project.config.ui.theme,
+                &project.config.ui.colors,
+            )?;
+            output::notify::configure(&project.config.notifications);
+            configure_session(&project)?;
+            if offline {
+                offline::enable(&project.config.offline)?;
+            }
+            let ui_guard = ui::start(if ci { ui::UiMode::Off } else { ui_mode });
 
             // Resolve target: check feature names first, then task IDs
-            let db_path = project.root.join(".ralph/progress.db");
+            let db_path = project.db_path();
             let db = dag::open_db(db_path.to_str().unwrap())?;
+            let target_label = targets.join(", ");
 
-            let run_target = if target.starts_with("t-") {
-                // Task ID
-                dag::get_task(&db, &target)?;
-                config::RunTarget::Task(target)
-            } else {
-                // Feature name
-                let feat = feature::get_feature(&db, &target)?;
-                if feat.status != "ready" && feat.status != "running" {
-                    anyhow::bail!(
-                        "Feature '{}' is not ready to run (status: {}). Run 'ralph feature create {}' first.",
-                        target, feat.status, target
-                    );
+            let run_target = match targets.as_slice() {
+                [target] if target.starts_with("t-") => {
+                    // Task ID
+                    dag::get_task(&db, target)?;
+                    config::RunTarget::Task(target.clone())
+                }
+                _ => {
+                    let mut names: Vec<String> = Vec::new();
+                    for target in targets {
+                        if target.starts_with("t-") {
+                            anyhow::bail!(
+                                "'{}' is a task ID; a task can only be run on its own",
+                                target
+                            );
+                        }
+                        if names.contains(&target) {
+                            continue;
+                        }
+                        // Feature name
+                        let feat = feature::get_feature(&db, &target)?;
+                        if feat.archived_at.is_some() {
+                            anyhow::bail!(
+                                "Feature '{}' is archived. Run 'ralph feature restore {}' first.",
+                                target,
+                                target
+                            );
+                        }
+                        if feat.status != "ready" && feat.status != "running" {
+                            anyhow::bail!(
+                                "Feature '{}' is not ready to run (status: {}). Run 'ralph feature create {}' first.",
+                                target, feat.status, target
+                            );
+                        }
+                        names.push(target);
+                    }
+                    config::RunTarget::Features { names, order }
                 }
-                config::RunTarget::Feature(target)
             };
+            let feature_names = match &run_target {
+                config::RunTarget::Features { names, .. } if names.len() > 1 => names.clone(),
+                _ => Vec::new(),
+            };
+            check_task_subset(&db, &run_target, &only, &skip)?;
 
-            let config = config::Config::from_run_args(
-                limit,
+            let mut config = config::Config::from_run_args(
+                limit.and_then(cli::IterationLimit::fixed),
                 model_strategy,
                 model,
                 project,
@@ -319,58 +458,245 @@ async fn run() -> Result<ExitCode> {
                 max_retries,
                 no_verify,
                 agent,
+                scheduler,
             )?;
+            if limit == Some(cli::IterationLimit::Auto) {
+                config = config.with_auto_limit();
+            }
+            if offline {
+                offline::check_agent(&config.ralph_config, &config.agent_command)?;
+            }
+            config.only = only;
+            config.skip = skip;
+            config.chaos = chaos.as_deref().map(chaos::Chaos::parse).transpose()?;
+            if let Some(ref chaos) = config.chaos {
+                output::formatter::print_warning(&format!(
+                    "ralph: chaos mode: injecting failures ({})",
+                    chaos.describe()
+                ));
+            }
+            let run_id = config.run_id.clone();
+            let project_root = config.project_root.clone();
+            let ralph_config = config.ralph_config.clone();
+            let agent_command = config.agent_command.clone();
+            let run_target = config.run_target.clone();
+            let user = config.user.clone();
 
             output::formatter::print_iteration_info(&config);
 
-            let (exit_code, summary) = match run_loop::run(config).await? {
-                run_loop::Outcome::Complete => {
+            // Hard wall-clock limit; CI runs always get one
+            let timeout = timeout.or(ci.then_some(ci::DEFAULT_TIMEOUT_SECS));
+            let started = std::time::Instant::now();
+            let started_at = chrono::Utc::now();
+            let agent_id = config.agent_id.clone();
+            let result = match timeout {
+                Some(secs) => tokio::time::timeout(
+                    std::time::Duration::from_secs(secs),
+                    run_loop::run(config),
+                )
+                .await
+                .ok(),
+                None => Some(run_loop::run(config).await),
+            };
+            let outcome = match result {
+                Some(Ok(outcome)) => Some(outcome),
+                Some(Err(e)) => {
+                    // Pipelines still get a result.json for a run that errored out
+                    if ci {
+                        let written = ci::CiResult::collect(
+                            &db,
+                            &run_id,
+                            &target_label,
+                            "error",
+                            1,
+                            started.elapsed().as_secs_f64(),
+                        )
+                        .and_then(|result| ci::write_result(&project_root, &result));
+                        match written {
+                            Ok(path) => {
+                                ci::emit_event("result", &path.display().to_string(), false)
+                            }
+                            Err(write_err) => output::formatter::print_warning(&format!(
+                                "ralph: could not write the CI result: {write_err:#}"
+                            )),
+                        }
+                    }
+                    return Err(e);
+                }
+                None => {
+                    // The dropped run left its task claimed; hand it back now
+                    // rather than when the lease expires
+                    for id in dag::requeue_claimed(&db, &agent_id)? {
+                        output::formatter::print_warning(&format!(
+                            "Returned {id} to pending after the timeout."
+                        ));
+                    }
+                    None
+                }
+            };
+
+            let (code, outcome_name, summary): (u8, &str, Option<String>) = match outcome {
+                Some(run_loop::Outcome::Complete) => {
                     output::formatter::print_complete();
-                    (ExitCode::SUCCESS, Some("Tasks complete.".to_string()))
+                    (0, "complete", Some("Tasks complete.".to_string()))
                 }
-                run_loop::Outcome::Failure => {
+                Some(run_loop::Outcome::Failure) => {
                     output::formatter::print_failure();
                     (
-                        ExitCode::FAILURE,
+                        1,
+                        "failure",
                         Some("Critical failure. See progress file for details.".to_string()),
                     )
                 }
-                run_loop::Outcome::LimitReached => {
+                Some(run_loop::Outcome::LimitReached) => {
                     output::formatter::print_limit_reached();
                     (
-                        ExitCode::SUCCESS,
+                        0,
+                        "limit_reached",
                         Some("Iteration limit reached.".to_string()),
                     )
                 }
-                run_loop::Outcome::Blocked => {
+                Some(run_loop::Outcome::Blocked) => {
                     output::formatter::print_warning(
                         "Loop blocked: no ready tasks, but incomplete tasks remain",
                     );
-                    (ExitCode::from(2), Some("Loop blocked.".to_string()))
+                    (2, "blocked", Some("Loop blocked.".to_string()))
                 }
-                run_loop::Outcome::NoPlan => {
+                Some(run_loop::Outcome::NoPlan) => {
                     output::formatter::print_warning(
                         "No plan: DAG is empty. Run 'ralph feature create <name>' to create tasks",
                     );
-                    (ExitCode::from(3), Some("No plan available.".to_string()))
+                    (3, "no_plan", Some("No plan available.".to_string()))
                 }
-                run_loop::Outcome::Interrupted => {
+                Some(run_loop::Outcome::Interrupted) => {
                     output::formatter::print_warning("Run interrupted by user.");
                     (
-                        ExitCode::SUCCESS,
+                        0,
+                        "interrupted",
                         Some("Run interrupted by user.".to_string()),
                     )
                 }
+                Some(run_loop::Outcome::ExitCondition) => {
+                    let line = format!(
+                        "Exit condition met: {}",
+                        ralph_config
+                            .run
+                            .exit_when
+                            .as_deref()
+                            .unwrap_or_default()
+                            .trim()
+                    );
+                    output::formatter::print_info(&line);
+                    (0, "exit_condition", Some(line))
+                }
+                None => {
+                    let line = format!("Run timed out after {}s.", timeout.unwrap_or_default());
+                    output::formatter::print_error(&line);
+                    (ci::EXIT_TIMEOUT, "timeout", Some(line))
+                }
+            };
+
+            // Failed tasks holding up the rest: offer a planning session
+            if !ci
+                && outcome == Some(run_loop::Outcome::Blocked)
+                && remediation::escalate(
+                    &db,
+                    &project_root,
+                    &ralph_config,
+                    run_target.as_ref(),
+                    &agent_command,
+                    user.as_deref(),
+                    auto_replan,
+                )
+                .await?
+            {
+                output::formatter::print_info(&format!(
+                    "DAG updated. Run 'ralph run {target_label}' to continue."
+                ));
+            }
+
+            // Per-feature completion when several features shared the run
+            let feature_lines: Vec<String> = run_loop::summarize_features(&db, &feature_names)?
+                .iter()
+                .map(|summary| format!("  {summary}"))
+                .collect();
+            for line in &feature_lines {
+                output::formatter::print_info(line);
+            }
+
+            // What the run did and what to run next
+            let run_summary = match run_summary::RunSummary::collect(
+                &db,
+                &project_root,
+                &run_id,
+                started_at,
+                run_target.as_ref(),
+                outcome_name,
+            ) {
+                Ok(run_summary) => Some(run_summary),
+                Err(e) => {
+                    output::formatter::print_warning(&format!(
+                        "ralph: could not summarize the run: {e:#}"
+                    ));
+                    None
+                }
+            };
+            let summary_lines = run_summary
+                .as_ref()
+                .map(run_summary::RunSummary::render_lines)
+                .unwrap_or_default();
+            if !ci && !ui_guard.is_active() {
+                output::formatter::print_info("");
+                for line in &summary_lines {
+                    output::formatter::print_info(line);
+                }
+            }
+
+            let event = match outcome_name {
+                "complete" => Some(output::notify::NotifyEvent::RunComplete),
+                "blocked" => Some(output::notify::NotifyEvent::Blocked),
+                "limit_reached" | "timeout" => Some(output::notify::NotifyEvent::BudgetExceeded),
+                _ => None,
             };
+            if let (Some(event), Some(line)) = (event, summary.as_deref()) {
+                output::notify::notify(event, &target_label, line);
+            }
+
+            if ci {
+                let mut result = ci::CiResult::collect(
+                    &db,
+                    &run_id,
+                    &target_label,
+                    outcome_name,
+                    code,
+                    started.elapsed().as_secs_f64(),
+                )?;
+                if let Some(run_summary) = &run_summary {
+                    result.next_steps = run_summary
+                        .next_steps
+                        .iter()
+                        .map(|(command, _)| command.clone())
+                        .collect();
+                }
+                let path = ci::write_result(&project_root, &result)?;
+                ci::emit_event("result", &path.display().to_string(), false);
+            }
 
             if ui_guard.is_active() {
                 drop(ui_guard);
                 if let Some(line) = summary {
                     println!("{line}");
                 }
+                for line in &feature_lines {
+                    println!("{line}");
+                }
+                println!();
+                for line in &summary_lines {
+                    println!("{line}");
+                }
             }
 
-            Ok(exit_code)
+            Ok(ExitCode::from(code))
         }
         None => {
             cli::Args::parse_from(["ralph", "--help"]);
@@ -379,29 +705,877 @@ async fn run() -> Result<ExitCode> {
     }
 }
 
-/// Handle `ralph auth` — run `claude auth login` for the underlying Claude CLI.
+/// Install the project's process-wide agent session settings: `[security]`,
+/// `[permissions]`, `[guardrails]`, model maps and the terminal environment.
+fn configure_session(project: &project::ProjectConfig) -> Result<()> {
+    acp::client_impl::configure_security(&project.config.security);
+    acp::client_impl::configure_permissions(&project.config.permissions);
+    guardrails::configure(&project.config.guardrails)?;
+    agent_models::configure(&project.config);
+    terminal_env::configure(&project.root, &project.config)
+}
+
+/// Handle `ralph knowledge` subcommands.
+fn handle_knowledge(action: cli::KnowledgeAction) -> Result<ExitCode> {
+    let project = project::discover()?;
+    match action {
+        cli::KnowledgeAction::List { feature, json } => {
+            let entries = knowledge::list_entries(&project.root, feature.as_deref());
+            if json {
+                let values: Vec<_> = entries
+                    .iter()
+                    .map(|e| {
+                        serde_json::json!({
+                            "title": e.title,
+                            "tags": e.tags,
+                            "feature": e.feature,
+                            "scope": e.scope.as_str(),
+                            "created_at": e.created_at,
+                            "path": e.file_path,
+                        })
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&values)?);
+                return Ok(ExitCode::SUCCESS);
+            }
+            if entries.is_empty() {
+                output::formatter::print_info("No knowledge entries.");
+                return Ok(ExitCode::SUCCESS);
+            }
+            for entry in &entries {
+                let scope = match entry.scoped_feature() {
+                    Some(feature) => format!("feature:{feature}"),
+                    None => "global".to_string(),
+                };
+                println!("{}  [{}]  {}", entry.title, scope, entry.tags.join(", "));
+            }
+        }
+        cli::KnowledgeAction::Export {
+            output: bundle,
+            tags,
+        } => {
+            let count = knowledge::export_bundle(&project.root, &bundle, &tags)?;
+            output::formatter::print_info(&format!(
+                "Exported {} knowledge entries to {}",
+                count,
+                bundle.display()
+            ));
+        }
+        cli::KnowledgeAction::Import { bundle } => {
+            let summary = knowledge::import_bundle(&project.root, &bundle)?;
+            output::formatter::print_info(&format!(
+                "Imported {}: {} created, {} merged into existing entries",
+                bundle.display(),
+                summary.created,
+                summary.merged
+            ));
+        }
+        cli::KnowledgeAction::Lint { fix } => {
+            let db_path = project.db_path();
+            let db = dag::open_db(db_path.to_str().unwrap())?;
+            let features: std::collections::HashSet<String> = feature::list_features(&db)?
+                .into_iter()
+                .chain(feature::list_archived_features(&db)?)
+                .map(|f| f.name)
+                .collect();
+            let display = |path: &std::path::Path| {
+                path.strip_prefix(&project.root)
+                    .unwrap_or(path)
+                    .display()
+                    .to_string()
+            };
+
+            if fix {
+                for issue in knowledge_lint::fix(&project.root, &features)? {
+                    println!("{}: fixed {}", display(&issue.path), issue.kind.label());
+                }
+            }
+            let issues = knowledge_lint::lint(&project.root, &features);
+            if issues.is_empty() {
+                output::formatter::print_info("Knowledge base: no problems found");
+                return Ok(ExitCode::SUCCESS);
+            }
+            for issue in &issues {
+                println!("{}: {issue}", display(&issue.path));
+            }
+            let fixable = issues.iter().filter(|i| i.fixable).count();
+            let hint = if fixable > 0 {
+                format!(" ({fixable} fixable with --fix)")
+            } else {
+                String::new()
+            };
+            output::formatter::print_warning(&format!(
+                "{} problem(s) in the knowledge base{hint}",
+                issues.len()
+            ));
+            return Ok(ExitCode::FAILURE);
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Handle `ralph journal` subcommands.
+fn handle_journal(action: cli::JournalAction) -> Result<ExitCode> {
+    let project = project::discover()?;
+    let db_path = project.db_path();
+    let db = dag::open_db(db_path.to_str().unwrap())?;
+
+    match action {
+        cli::JournalAction::Search {
+            query,
+            task,
+            feature,
+            limit,
+            json,
+        } => {
+            let feature_id = match feature {
+                Some(ref name) => Some(feature::get_feature(&db, name)?.id),
+                None => None,
+            };
+            let filter = journal::SearchFilter {
+                task_id: task.as_deref(),
+                feature_id: feature_id.as_deref(),
+            };
+            let hits = journal::search_journal(&db, &query.join(" "), &filter, limit)?;
+
+            if json {
+                let values: Vec<_> = hits
+                    .iter()
+                    .map(|hit| {
+                        serde_json::json!({
+                            "id": hit.entry.id,
+                            "run_id": hit.entry.run_id,
+                            "iteration": hit.entry.iteration,
+                            "task_id": hit.entry.task_id,
+                            "feature_id": hit.entry.feature_id,
+                            "outcome": hit.entry.outcome,
+                            "created_at": hit.entry.created_at,
+                            "snippet": hit.snippet.replace(
+                                [journal::HIGHLIGHT_START, journal::HIGHLIGHT_END],
+                                ""
+                            ),
+                            "notes": hit.entry.notes,
+                        })
+                    })
+                    .collect();
+                println!("{}", serde_json::to_string_pretty(&values)?);
+                return Ok(ExitCode::SUCCESS);
+            }
+
+            if hits.is_empty() {
+                output::formatter::print_info("No matching journal entries.");
+                return Ok(ExitCode::SUCCESS);
+            }
+            for hit in &hits {
+                let entry = &hit.entry;
+                println!(
+                    "{}  {} #{}  [{}]  {}",
+                    entry.created_at.get(..16).unwrap_or(&entry.created_at),
+                    entry.run_id,
+                    entry.iteration,
+                    entry.outcome,
+                    entry.task_id.as_deref().unwrap_or("-")
+                );
+                println!("    {}", highlight_snippet(&hit.snippet));
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Render a journal search snippet with matched terms in bold yellow.
+fn highlight_snippet(snippet: &str) -> String {
+    use colored::Colorize;
+
+    let mut out = String::new();
+    for (i, part) in snippet.split(journal::HIGHLIGHT_START).enumerate() {
+        match part.split_once(journal::HIGHLIGHT_END) {
+            Some((term, rest)) if i > 0 => {
+                out.push_str(&term.yellow().bold().to_string());
+                out.push_str(rest);
+            }
+            _ => out.push_str(part),
+        }
+    }
+    out.replace('\n', " ")
+}
+
+/// Snapshot a feature's spec or plan into `history/` if it changed, and say so.
+fn record_feature_revision(
+    db: &dag::Db,
+    root: &std::path::Path,
+    feat: &feature::Feature,
+    kind: review::DocumentKind,
+) -> Result<()> {
+    if let Some(revision) = feature::record_revision(db, root, feat, kind)? {
+        output::formatter::print_info(&format!(
+            "Recorded {} revision {} (see `ralph feature diff {}{}`)",
+            kind.label(),
+            revision,
+            feat.name,
+            match kind {
+                review::DocumentKind::Spec => "",
+                review::DocumentKind::Plan => " --plan",
+            }
+        ));
+    }
+    Ok(())
+}
+
+/// Ask the agent for a task DAG proposal and let the user review it, sending
+/// invalid proposals and requested changes back to the agent. `None` when the
+/// user rejects it.
+async fn propose_task_dag(
+    agent_command: &str,
+    system_prompt: &str,
+    root: &std::path::Path,
+    model_name: &str,
+) -> Result<Option<task_proposal::ProposedDag>> {
+    let mut message = build_initial_message_build(None);
+    let mut invalid_proposals = 0;
+    loop {
+        let agent_text = acp::interactive::run_streaming(
+            agent_command,
+            system_prompt,
+            &message,
+            root,
+            Some(model_name),
+        )
+        .await?;
+        let proposal = match task_proposal::parse(&agent_text) {
+            Ok(proposal) => proposal,
+            Err(e) if invalid_proposals < task_proposal::MAX_INVALID_PROPOSALS => {
+                invalid_proposals += 1;
+                output::formatter::print_warning(&format!(
+                    "Invalid task DAG proposal: {e:#}. Asking the agent to fix it."
+                ));
+                message = format!(
+                    "Your task DAG proposal was invalid: {e:#}. Emit a corrected <task-dag> sigil and stop."
+                );
+                continue;
+            }
+            Err(e) => return Err(e.context("The agent did not propose a valid task DAG")),
+        };
+        match task_proposal::review(&proposal) {
+            task_proposal::Review::Accept => return Ok(Some(proposal)),
+            task_proposal::Review::Revise(feedback) => {
+                message = build_initial_message_build(Some((&proposal.to_json(), &feedback)));
+            }
+            task_proposal::Review::Reject => return Ok(None),
+        }
+    }
+}
+
+/// Authoring sessions resumed for a document still missing its completion
+/// marker before `feature create` gives up.
+const MAX_AUTHORING_RESUMES: usize = 2;
+
+/// Run the interactive session that writes a spec or plan to `path`. While
+/// the document lacks its completion marker (the agent died mid-write), the
+/// session is re-entered in resume mode with the partial draft instead of
+/// moving on to review.
+async fn author_document(
+    kind: review::DocumentKind,
+    name: &str,
+    system_prompt: &str,
+    path: &std::path::Path,
+    agent_command: &str,
+    root: &std::path::Path,
+    model_name: &str,
+) -> Result<()> {
+    let path_str = path.to_string_lossy();
+    let system_prompt = format!(
+        "{system_prompt}\n\n{}",
+        feature_prompts::build_completion_marker_section(&path_str)
+    );
+    let mut draft: Option<String> = None;
+    for resumes in 0..=MAX_AUTHORING_RESUMES {
+        let (prompt, resuming) = match draft {
+            Some(ref draft) => (
+                format!(
+                    "{system_prompt}\n\n{}",
+                    feature_prompts::build_resume_draft_section(kind.label(), &path_str, draft)
+                ),
+                true,
+            ),
+            None => (system_prompt.clone(), false),
+        };
+        let message = match kind {
+            review::DocumentKind::Spec => build_initial_message_spec(name, resuming),
+            review::DocumentKind::Plan => build_initial_message_plan(name, resuming),
+        };
+        acp::interactive::run_interactive(
+            agent_command,
+            &prompt,
+            &message,
+            root,
+            Some(model_name),
+            false,
+            Some(vec![path.to_path_buf()]),
+        )
+        .await?;
+
+        match feature::finish_authored_document(path)? {
+            feature::AuthoredDocument::Missing | feature::AuthoredDocument::Complete => {
+                return Ok(())
+            }
+            feature::AuthoredDocument::Truncated(content) => {
+                if resumes < MAX_AUTHORING_RESUMES {
+                    output::formatter::print_warning(&format!(
+                        "The {} at {} has no completion marker and may be cut off; resuming the session ({}/{}).",
+                        kind.label(),
+                        path_str,
+                        resumes + 1,
+                        MAX_AUTHORING_RESUMES
+                    ));
+                }
+                draft = Some(content);
+            }
+        }
+    }
+    anyhow::bail!(
+        "The {kind} at {path_str} is still incomplete after {MAX_AUTHORING_RESUMES} resumed sessions. \
+         Finish it by hand and run 'ralph feature create {name}' again, or delete it to start over.",
+        kind = kind.label()
+    )
+}
+
+/// End a `feature create --phase` run, pointing at the document to review
+/// and the phase that comes next.
+fn stop_after_phase(ui_guard: ui::UiGuard, path: &str, name: &str, next: &str) -> Result<ExitCode> {
+    output::formatter::print_info(&format!(
+        "Review {}, then run 'ralph feature create {} --phase {}'.",
+        path, name, next
+    ));
+    drop(ui_guard);
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Handle `ralph logs`.
+fn handle_logs(run_id: Option<String>, follow: bool, lines: Option<usize>) -> Result<ExitCode> {
+    use output::logger;
+    let project = project::discover()?;
+
+    let Some(run_id) = run_id else {
+        let runs = logger::list_runs(&project.root);
+        if runs.is_empty() {
+            println!(
+                "No run logs in {}",
+                logger::log_directory(&project.root).display()
+            );
+        }
+        for run in runs {
+            let modified: chrono::DateTime<chrono::Local> = run.modified.into();
+            println!(
+                "{}  {}  {:>6} KB{}",
+                run.run_id,
+                modified.format("%Y-%m-%d %H:%M"),
+                run.bytes.div_ceil(1024),
+                if run.active {
+                    "  (running or interrupted)"
+                } else {
+                    ""
+                }
+            );
+        }
+        return Ok(ExitCode::SUCCESS);
+    };
+
+    let dir = logger::run_directory(&project.root, &run_id);
+    if !dir.is_dir() {
+        anyhow::bail!("No logs for run {run_id}; `ralph logs` lists the runs that have them");
+    }
+    let offset = logger::current_len(&dir);
+    let text = logger::read_run(&dir)?;
+    let mut stdout = std::io::stdout();
+    if let Some(n) = lines {
+        let all: Vec<&str> = text.lines().collect();
+        for line in &all[all.len().saturating_sub(n)..] {
+            println!("{line}");
+        }
+    } else if follow || !std::io::IsTerminal::is_terminal(&stdout) || !page(&text) {
+        print!("{text}");
+    }
+    if follow {
+        logger::follow(&dir, offset, &mut stdout)?;
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Print the terminal sessions recorded for a task (`logs --task --terminals`).
+fn handle_terminal_logs(task_id: &str) -> Result<ExitCode> {
+    let project = project::discover()?;
+    let records = transcripts::load(&project.root, task_id)?;
+    if records.is_empty() {
+        println!("No terminal sessions recorded for {task_id}");
+        return Ok(ExitCode::SUCCESS);
+    }
+    let text = transcripts::render(&records);
+    if !std::io::IsTerminal::is_terminal(&std::io::stdout()) || !page(&text) {
+        print!("{text}");
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Show `text` in `$PAGER` (default `less -R`). Returns `false` if no pager
+/// could be started, so the caller prints it instead.
+fn page(text: &str) -> bool {
+    use std::io::Write;
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| "less -R".to_string());
+    let Some((program, args)) = shlex::split(&pager).and_then(|parts| {
+        let (program, args) = parts.split_first()?;
+        Some((program.clone(), args.to_vec()))
+    }) else {
+        return false;
+    };
+    let Ok(mut child) = std::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+    else {
+        return false;
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // The user may quit the pager before reading everything
+        stdin.write_all(text.as_bytes()).ok();
+    }
+    child.wait().ok();
+    true
+}
+
+/// Handle `ralph db` subcommands.
+fn handle_config(action: cli::ConfigAction) -> Result<ExitCode> {
+    match action {
+        cli::ConfigAction::Check => {
+            let path = project::find_config()?;
+            let findings = config_check::check(&path)?;
+            if findings.is_empty() {
+                output::formatter::print_info(&format!("{}: no problems found", path.display()));
+                return Ok(ExitCode::SUCCESS);
+            }
+            for finding in &findings {
+                match finding.line {
+                    Some(line) => println!("{}:{line}: {}", path.display(), finding.message),
+                    None => println!("{}: {}", path.display(), finding.message),
+                }
+            }
+            output::formatter::print_warning(&format!(
+                "{} problem(s) in {}",
+                findings.len(),
+                path.display()
+            ));
+            Ok(ExitCode::FAILURE)
+        }
+        cli::ConfigAction::Get { key } => {
+            let path = project::find_config()?;
+            match config_edit::get(&path, &key)? {
+                Some(value) => {
+                    println!("{value}");
+                    Ok(ExitCode::SUCCESS)
+                }
+                None => {
+                    output::formatter::print_warning(&format!(
+                        "{key} is not set in {}; the default applies",
+                        path.display()
+                    ));
+                    Ok(ExitCode::FAILURE)
+                }
+            }
+        }
+        cli::ConfigAction::Set { key, value } => {
+            let path = project::find_config()?;
+            config_edit::set(&path, &key, &value)?;
+            output::formatter::print_info(&format!("Set {key} in {}", path.display()));
+            Ok(ExitCode::SUCCESS)
+        }
+    }
+}
+
+/// Handle `ralph runs` subcommands.
+fn handle_runs(action: cli::RunsAction) -> Result<ExitCode> {
+    let project = project::discover()?;
+    let db_path = project.db_path();
+    let db = dag::open_db(db_path.to_str().unwrap())?;
+
+    match action {
+        cli::RunsAction::Diff { run_a, run_b, json } => {
+            let a = run_diff::summarize(&db, &run_a)?;
+            let b = run_diff::summarize(&db, &run_b)?;
+            if json {
+                let value = serde_json::json!({ "a": a, "b": b });
+                println!("{}", serde_json::to_string_pretty(&value)?);
+            } else {
+                for line in run_diff::render(&a, &b) {
+                    println!("{line}");
+                }
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+    }
+}
+
+/// Handle `ralph stats`.
+/// Check `--only`/`--skip` IDs: each must be a task of a feature being run.
+/// Listed tasks that are already resolved only get a warning, since the run
+/// would never pick them.
+fn check_task_subset(
+    db: &dag::Db,
+    run_target: &config::RunTarget,
+    only: &[String],
+    skip: &[String],
+) -> Result<()> {
+    if only.is_empty() && skip.is_empty() {
+        return Ok(());
+    }
+    let config::RunTarget::Features { names, .. } = run_target else {
+        anyhow::bail!(
+            "--only and --skip pick tasks within features; run the task on its own instead"
+        );
+    };
+    let mut feature_ids = Vec::new();
+    for name in names {
+        feature_ids.push(feature::get_feature(db, name)?.id);
+    }
+    for id in only.iter().chain(skip) {
+        let task = dag::get_task(db, id)?;
+        if !task
+            .feature_id
+            .as_ref()
+            .is_some_and(|f| feature_ids.contains(f))
+        {
+            anyhow::bail!("{id} is not a task of {}", names.join(", "));
+        }
+        if only.contains(id) && matches!(task.status.as_str(), "done" | "failed") {
+            output::formatter::print_warning(&format!(
+                "{id} is already {}; run 'ralph task reset {id}' to redo it",
+                task.status
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn handle_stats(json: bool) -> Result<ExitCode> {
+    let project = project::discover()?;
+    let db_path = project.db_path();
+    let db = dag::open_db(db_path.to_str().unwrap())?;
+    let counts = dag::get_task_counts(&db)?;
+    let flakes = flaky::stats(&db)?;
+    let mut progress = vec![burndown::Burndown {
+        feature: None,
+        days: burndown::compute(&db, None, burndown::WINDOW_DAYS)?,
+    }];
+    for feat in feature::list_features(&db)? {
+        let days = burndown::compute(&db, Some(&feat.id), burndown::WINDOW_DAYS)?;
+        // Features with nothing open and nothing finished in the window add no chart
+        if days.iter().any(|d| d.remaining > 0 || d.completed > 0) {
+            progress.push(burndown::Burndown {
+                feature: Some(feat.name),
+                days,
+            });
+        }
+    }
+
+    if json {
+        let value = serde_json::json!({
+            "tasks": {
+                "total": counts.total,
+                "ready": counts.ready,
+                "done": counts.done,
+                "blocked": counts.blocked,
+            },
+            "verification": flakes,
+            "progress": progress,
+        });
+        println!("{}", serde_json::to_string_pretty(&value)?);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    println!(
+        "Tasks: {} total, {} done, {} ready, {} blocked",
+        counts.total, counts.done, counts.ready, counts.blocked
+    );
+    println!(
+        "Verifications: {} run, {} failed, {} flaky",
+        flakes.verifications, flakes.failed, flakes.flakes
+    );
+    println!();
+    println!(
+        "Progress, last {} days (burn-down, tasks left, done per day):",
+        burndown::WINDOW_DAYS
+    );
+    // The first entry covers every task, so its throughput is the total
+    let throughput: Vec<u32> = progress[0].days.iter().map(|d| d.completed).collect();
+    for line in burndown::render(&progress, &throughput) {
+        println!("  {line}");
+    }
+    if !flakes.files.is_empty() {
+        println!();
+        println!("Flaky files:");
+        for (path, count) in &flakes.files {
+            println!("  {count:>3}  {path}");
+        }
+    }
+    if !flakes.tasks.is_empty() {
+        println!();
+        println!("Flaky tasks:");
+        for (task_id, count) in &flakes.tasks {
+            let title = dag::get_task(&db, task_id)
+                .map(|t| t.title)
+                .unwrap_or_else(|_| "(deleted)".to_string());
+            println!("  {count:>3}  {task_id}  {title}");
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn handle_ui(action: cli::UiAction) -> Result<ExitCode> {
+    match action {
+        cli::UiAction::Themes => {
+            let project = project::discover()?;
+            let themes = ui::theme::available(&project.root);
+            let active = ui::theme::resolve_theme(&project.config.ui.theme, &themes);
+            for (i, theme) in themes.iter().enumerate() {
+                let marker = if i == active { "*" } else { " " };
+                let (source, detail) = match &theme.path {
+                    None => ("built-in".to_string(), String::new()),
+                    Some(path) => {
+                        let shown = path.strip_prefix(&project.root).unwrap_or(path);
+                        let detail = match ui::theme::ThemeFile::load(path) {
+                            Ok(file) => file.description.unwrap_or_default(),
+                            Err(e) => format!("{} {}", "invalid:".red(), e.root_cause()),
+                        };
+                        (shown.display().to_string(), detail)
+                    }
+                };
+                let line = format!("{marker} {:<16} {:<40} {detail}", theme.name, source);
+                println!("{}", line.trim_end());
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+    }
+}
+
+fn handle_db(action: cli::DbAction) -> Result<ExitCode> {
+    let project = project::discover()?;
+    let db_path = project.db_path();
+    let db_path = db_path.to_str().unwrap();
+
+    match action {
+        cli::DbAction::Migrate { dry_run: true } => {
+            let plan = dag::plan_migrations(db_path)?;
+            if plan.pending.is_empty() {
+                output::formatter::print_info(&format!("Schema is up to date (v{})", plan.current));
+            } else {
+                output::formatter::print_info(&format!(
+                    "Schema v{} → v{}; {} pending migration(s):",
+                    plan.current,
+                    plan.target,
+                    plan.pending.len()
+                ));
+                for (version, description) in &plan.pending {
+                    println!("  v{}: {}", version, description);
+                }
+            }
+        }
+        cli::DbAction::Migrate { dry_run: false } => {
+            let report = dag::migrate_db(db_path)?;
+            if report.applied.is_empty() {
+                output::formatter::print_info(&format!("Schema is up to date (v{})", report.to));
+            } else {
+                if let Some(ref backup) = report.backup {
+                    output::formatter::print_info(&format!(
+                        "Backed up database to {}",
+                        backup.display()
+                    ));
+                }
+                output::formatter::print_info(&format!(
+                    "Migrated schema v{} → v{} ({} migration(s))",
+                    report.from,
+                    report.to,
+                    report.applied.len()
+                ));
+            }
+        }
+        cli::DbAction::Prune {
+            keep_days,
+            keep_runs,
+            dry_run,
+        } => {
+            let db = dag::open_db(db_path)?;
+            let report = prune::prune(
+                &db,
+                std::path::Path::new(db_path),
+                &project.root,
+                prune::PruneOptions {
+                    keep_days,
+                    keep_runs,
+                    dry_run,
+                },
+            )?;
+            let summary = format!(
+                "{} journal entr{} from {} run(s) and {} task log(s)",
+                report.journal_entries,
+                if report.journal_entries == 1 {
+                    "y"
+                } else {
+                    "ies"
+                },
+                report.runs,
+                report.task_logs
+            );
+            if report.journal_entries + report.task_logs == 0 {
+                output::formatter::print_info(&format!(
+                    "Nothing older than {keep_days} days outside the last {keep_runs} runs"
+                ));
+            } else if dry_run {
+                output::formatter::print_info(&format!("Would archive and delete {summary}"));
+            } else if let Some(ref archive) = report.archive {
+                output::formatter::print_info(&format!(
+                    "Archived {summary} to {}",
+                    archive
+                        .strip_prefix(&project.root)
+                        .unwrap_or(archive)
+                        .display()
+                ));
+                output::formatter::print_info(&format!(
+                    "Vacuumed database: {} KB → {} KB",
+                    report.size_before / 1024,
+                    report.size_after / 1024
+                ));
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Handle `ralph sync <tracker>` — import open issues and push status changes.
+fn handle_sync(action: cli::SyncAction) -> Result<ExitCode> {
+    let project = project::discover()?;
+    let db_path = project.db_path();
+    let db = dag::open_db(db_path.to_str().unwrap())?;
+    let sync_config = &project.config.sync;
+
+    let (tracker, statuses, args): (Box<dyn sync::Tracker>, _, _) = match action {
+        cli::SyncAction::Linear { args } => {
+            let config = sync_config
+                .linear
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("No [sync.linear] section in .ralph.toml"))?;
+            (
+                Box::new(sync::linear::Linear::new(config)?),
+                &config.states,
+                args,
+            )
+        }
+        cli::SyncAction::Jira { args } => {
+            let config = sync_config
+                .jira
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("No [sync.jira] section in .ralph.toml"))?;
+            (
+                Box::new(sync::jira::Jira::new(config)?),
+                &config.transitions,
+                args,
+            )
+        }
+    };
+
+    let user = project.user_name();
+    let report = sync::sync(
+        &db,
+        tracker.as_ref(),
+        statuses,
+        sync::SyncOptions {
+            import: !args.no_import,
+            push: !args.no_push,
+            dry_run: args.dry_run,
+            max_retries: project.config.execution.max_retries as i32,
+            created_by: user.as_deref(),
+        },
+    )?;
+
+    let verb = if args.dry_run {
+        "Would import"
+    } else {
+        "Imported"
+    };
+    for (key, task_id) in &report.imported {
+        if task_id.is_empty() {
+            println!("  {} {}", verb, key);
+        } else {
+            println!("  {} {} as {}", verb, key, task_id);
+        }
+    }
+    let verb = if args.dry_run {
+        "Would archive"
+    } else {
+        "Archived"
+    };
+    for (key, task_id) in &report.closed {
+        println!("  {} {} ({} was closed)", verb, task_id, key);
+    }
+    let verb = if args.dry_run { "Would move" } else { "Moved" };
+    for (key, status, state) in &report.pushed {
+        println!("  {} {} to '{}' (task {})", verb, key, state, status);
+    }
+    for error in &report.errors {
+        output::formatter::print_warning(&format!("ralph: push failed: {error}"));
+    }
+    output::formatter::print_info(&format!(
+        "{} sync: {} imported, {} archived, {} pushed, {} failed",
+        tracker.name(),
+        report.imported.len(),
+        report.closed.len(),
+        report.pushed.len(),
+        report.errors.len()
+    ));
+    Ok(if report.errors.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Handle `ralph auth` — run the selected agent's login command.
 ///
-/// The ACP agent binary (e.g. `claude-agent-acp`) may not have its own auth command;
-/// authentication is managed by the `claude` CLI which the agent delegates to.
-async fn handle_auth(_agent: Option<String>) -> Result<ExitCode> {
+/// `--agent <name>` picks the `auth_command` of an `[agents.<name>]` profile.
+/// Without a profile, auth is delegated to `claude auth login`, since the ACP
+/// agent binary (e.g. `claude-agent-acp`) has no auth command of its own.
+async fn handle_auth(agent: Option<String>) -> Result<ExitCode> {
     if ui::is_active() {
         ui::stop();
     }
 
-    if let Some(agent) = _agent {
-        output::formatter::print_info(&format!(
-            "Auth is delegated to `claude auth login`; ignoring --agent={agent}."
-        ));
+    // Auth works outside a project too; fall back to the built-in defaults.
+    // A .ralph.toml that exists but does not load is still an error.
+    let ralph_config = match project::find_config() {
+        Ok(path) => {
+            project::discover()
+                .with_context(|| format!("Failed to load {}", path.display()))?
+                .config
+        }
+        Err(_) => project::RalphConfig::default(),
+    };
+    let argv = config::resolve_auth_command(&ralph_config, agent.as_deref())?;
+    let display = argv.join(" ");
+
+    if let Some(ref agent) = agent {
+        if !ralph_config.agents.contains_key(agent) {
+            output::formatter::print_info(&format!(
+                "No [agents.{agent}] profile; using default auth command."
+            ));
+        }
     }
-    output::formatter::print_info("Running: claude auth login");
+    output::formatter::print_info(&format!("Running: {display}"));
 
-    let status = std::process::Command::new("claude")
-        .args(["auth", "login"])
+    let status = std::process::Command::new(&argv[0])
+        .args(&argv[1..])
         .stdin(std::process::Stdio::inherit())
         .stdout(std::process::Stdio::inherit())
         .stderr(std::process::Stdio::inherit())
         .status()
-        .map_err(|e| anyhow::anyhow!("failed to run 'claude auth login': {e}"))?;
+        .map_err(|e| anyhow::anyhow!("failed to run '{display}': {e}"))?;
 
     if status.success() {
         output::formatter::print_info("Authentication successful.");
@@ -418,21 +1592,37 @@ async fn handle_auth(_agent: Option<String>) -> Result<ExitCode> {
 /// Handle `ralph feature <action>` subcommands.
 async fn handle_feature(action: cli::FeatureAction, ui_mode: ui::UiMode) -> Result<ExitCode> {
     let project = project::discover()?;
-    ui::theme::init_with_overrides(
-        ui::theme::resolve_theme_name(&project.config.ui.theme),
-        Some(&project.config.ui.colors),
-    );
-    let db_path = project.root.join(".ralph/progress.db");
+    ui::theme::init_for_project(
+        &project.root,
+        &project.config.ui.theme,
+        &project.config.ui.colors,
+    )?;
+    configure_session(&project)?;
+    let db_path = project.db_path();
     let db = dag::open_db(db_path.to_str().unwrap())?;
 
     match action {
-        cli::FeatureAction::Create { name, model, agent } => {
+        cli::FeatureAction::Create {
+            name,
+            from_doc,
+            phase,
+            model,
+            agent,
+        } => {
+            use cli::FeaturePhase;
+            let runs = |p: FeaturePhase| phase.is_none_or(|only| only == p);
+            if from_doc.is_some() && !runs(FeaturePhase::Spec) {
+                anyhow::bail!("--from-doc only applies to the spec phase");
+            }
             let ui_guard = ui::start(ui_mode);
 
             // Resolve agent command: --agent flag > RALPH_AGENT env > config > "claude"
-            let agent_command = agent
-                .or_else(|| std::env::var("RALPH_AGENT").ok())
-                .unwrap_or_else(|| project.config.agent.command.clone());
+            let agent_command = config::resolve_agent_profile(
+                &project.config,
+                agent
+                    .or_else(|| std::env::var("RALPH_AGENT").ok())
+                    .unwrap_or_else(|| project.config.agent.command.clone()),
+            );
 
             let model_name = model.as_deref().unwrap_or("opus");
 
@@ -462,7 +1652,48 @@ async fn handle_feature(action: cli::FeatureAction, ui_mode: ui::UiMode) -> Resu
             // ── Phase 1: Spec ────────────────────────────────────────────
             // Skip if feature already has a spec on disk
             let has_spec = spec_path.exists();
-            if has_spec {
+            if !runs(FeaturePhase::Spec) {
+                // A later phase on its own: the spec must already exist
+            } else if let Some(ref doc) = from_doc {
+                let doc_content = std::fs::read_to_string(doc)
+                    .map_err(|e| anyhow::anyhow!("failed to read {}: {e}", doc.display()))?;
+                if doc_content.trim().is_empty() {
+                    anyhow::bail!("{} is empty", doc.display());
+                }
+                if has_spec {
+                    // The review may have edited the imported copy, so an
+                    // existing spec always wins; resuming works as usual
+                    output::formatter::print_warning(&format!(
+                        "Spec already exists at {}, ignoring --from-doc {}. Delete it to re-import.",
+                        spec_path_str,
+                        doc.display()
+                    ));
+                } else {
+                    output::formatter::print_info("Phase 1: Specification");
+                    output::formatter::print_info(&format!(
+                        "Importing {} -> review",
+                        doc.display()
+                    ));
+                    std::fs::write(&spec_path, &doc_content)
+                        .map_err(|e| anyhow::anyhow!("failed to write {spec_path_str}: {e}"))?;
+
+                    let context = gather_project_context(&project, &db, false);
+                    review::review_document(
+                        &spec_path_str,
+                        review::DocumentKind::Spec,
+                        &name,
+                        None,
+                        &context,
+                        &agent_command,
+                        &project.root,
+                        &project.config.review,
+                    )
+                    .await?;
+
+                    feature::update_feature_spec_path(&db, &feat.id, &spec_path_str)?;
+                    output::formatter::print_info(&format!("Spec saved to {}", spec_path_str));
+                }
+            } else if has_spec {
                 output::formatter::print_info(&format!(
                     "Spec already exists at {}, skipping spec phase.",
                     spec_path_str
@@ -475,19 +1706,27 @@ async fn handle_feature(action: cli::FeatureAction, ui_mode: ui::UiMode) -> Resu
                 let context = gather_project_context(&project, &db, false);
 
                 // Build system prompt and initial message
-                let system_prompt =
-                    build_feature_spec_system_prompt(&name, &spec_path_str, &context);
-                let initial_message = build_initial_message_spec(&name, false);
+                let system_prompt = prompt_templates::apply(
+                    &project.root,
+                    "feature_spec",
+                    build_feature_spec_system_prompt(&name, &spec_path_str, &context),
+                    &[
+                        ("name", &name),
+                        ("spec_path", &spec_path_str),
+                        ("context", &context),
+                    ],
+                );
 
-                // Launch interactive session via ACP (no terminal — spec authoring only)
-                let _agent_text = acp::interactive::run_interactive(
-                    &agent_command,
+                // Interactive session via ACP (no terminal — spec authoring only),
+                // resumed while the spec lacks its completion marker
+                author_document(
+                    review::DocumentKind::Spec,
+                    &name,
                     &system_prompt,
-                    &initial_message,
+                    &spec_path,
+                    &agent_command,
                     &project.root,
-                    Some(model_name),
-                    false,
-                    Some(vec![spec_path.clone()]),
+                    model_name,
                 )
                 .await?;
 
@@ -501,6 +1740,7 @@ async fn handle_feature(action: cli::FeatureAction, ui_mode: ui::UiMode) -> Resu
                         &context,
                         &agent_command,
                         &project.root,
+                        &project.config.review,
                     )
                     .await?;
                 }
@@ -513,15 +1753,22 @@ async fn handle_feature(action: cli::FeatureAction, ui_mode: ui::UiMode) -> Resu
             // Bail if spec wasn't actually written
             if !spec_path.exists() {
                 anyhow::bail!(
-                    "Spec file was not created at {}. Cannot proceed to planning.",
-                    spec_path_str
+                    "No spec at {}. Cannot proceed to planning; run 'ralph feature create {} --phase spec' first.",
+                    spec_path_str,
+                    name
                 );
             }
+            record_feature_revision(&db, &project.root, &feat, review::DocumentKind::Spec)?;
+            if phase == Some(FeaturePhase::Spec) {
+                return stop_after_phase(ui_guard, &spec_path_str, &name, "plan");
+            }
 
             // ── Phase 2: Plan ────────────────────────────────────────────
             // Skip if feature already has a plan on disk
             let has_plan = plan_path.exists();
-            if has_plan {
+            if !runs(FeaturePhase::Plan) {
+                // Building on its own: the plan must already exist
+            } else if has_plan {
                 output::formatter::print_info(&format!(
                     "Plan already exists at {}, skipping plan phase.",
                     plan_path_str
@@ -536,23 +1783,33 @@ async fn handle_feature(action: cli::FeatureAction, ui_mode: ui::UiMode) -> Resu
                 let context = gather_project_context(&project, &db, false);
 
                 // Build system prompt and initial message
-                let system_prompt = build_feature_plan_system_prompt(
-                    &name,
-                    &spec_content,
-                    &plan_path_str,
-                    &context,
+                let system_prompt = prompt_templates::apply(
+                    &project.root,
+                    "feature_plan",
+                    build_feature_plan_system_prompt(
+                        &name,
+                        &spec_content,
+                        &plan_path_str,
+                        &context,
+                    ),
+                    &[
+                        ("name", &name),
+                        ("spec", &spec_content),
+                        ("plan_path", &plan_path_str),
+                        ("context", &context),
+                    ],
                 );
-                let initial_message = build_initial_message_plan(&name, false);
 
-                // Launch interactive session via ACP (no terminal — plan authoring only)
-                let _agent_text = acp::interactive::run_interactive(
-                    &agent_command,
+                // Interactive session via ACP (no terminal — plan authoring only),
+                // resumed while the plan lacks its completion marker
+                author_document(
+                    review::DocumentKind::Plan,
+                    &name,
                     &system_prompt,
-                    &initial_message,
+                    &plan_path,
+                    &agent_command,
                     &project.root,
-                    Some(model_name),
-                    false,
-                    Some(vec![plan_path.clone()]),
+                    model_name,
                 )
                 .await?;
 
@@ -567,6 +1824,7 @@ async fn handle_feature(action: cli::FeatureAction, ui_mode: ui::UiMode) -> Resu
                         &context,
                         &agent_command,
                         &project.root,
+                        &project.config.review,
                     )
                     .await?;
                 }
@@ -580,10 +1838,15 @@ async fn handle_feature(action: cli::FeatureAction, ui_mode: ui::UiMode) -> Resu
             // Bail if plan wasn't actually written
             if !plan_path.exists() {
                 anyhow::bail!(
-                    "Plan file was not created at {}. Cannot proceed to task creation.",
-                    plan_path_str
+                    "No plan at {}. Cannot proceed to task creation; run 'ralph feature create {} --phase plan' first.",
+                    plan_path_str,
+                    name
                 );
             }
+            record_feature_revision(&db, &project.root, &feat, review::DocumentKind::Plan)?;
+            if phase == Some(FeaturePhase::Plan) {
+                return stop_after_phase(ui_guard, &plan_path_str, &name, "build");
+            }
 
             // ── Phase 3: Task DAG ────────────────────────────────────────
             output::formatter::print_info("Phase 3: Task Decomposition");
@@ -601,8 +1864,44 @@ async fn handle_feature(action: cli::FeatureAction, ui_mode: ui::UiMode) -> Resu
                 feature::update_feature_status(&db, &feat.id, "planned")?;
             }
 
+            // Fail before the agent session rather than after the review
+            feature::ensure_nothing_in_progress(&db, &feat)?;
+
+            // The agent proposes the DAG; nothing is written until it is accepted
+            let plan_sections =
+                plan_coverage::render_sections(&plan_coverage::plan_sections(&plan_content));
+            let system_prompt = prompt_templates::apply(
+                &project.root,
+                "feature_build",
+                build_feature_build_system_prompt(&spec_content, &plan_content),
+                &[
+                    ("spec", &spec_content),
+                    ("plan", &plan_content),
+                    ("plan_sections", &plan_sections),
+                ],
+            );
+            let Some(proposal) =
+                propose_task_dag(&agent_command, &system_prompt, &project.root, model_name).await?
+            else {
+                output::formatter::print_info(&format!(
+                    "Task DAG rejected; no tasks were created. Run 'ralph feature create {} --phase build' to propose a new one.",
+                    name
+                ));
+                return Ok(ExitCode::SUCCESS);
+            };
+
+            // Rebuilding: the previous DAG is archived, not merged
+            let superseded = feature::archive_tasks(&db, &feat.id)?;
+            if superseded > 0 {
+                output::formatter::print_info(&format!(
+                    "Archived {} task(s) from the previous DAG",
+                    superseded
+                ));
+            }
+
             // Create root task for the feature
             let max_retries = project.config.execution.max_retries as i32;
+            let user = project.user_name();
             let root = dag::create_task_with_feature(
                 &db,
                 dag::CreateTaskParams {
@@ -613,6 +1912,10 @@ async fn handle_feature(action: cli::FeatureAction, ui_mode: ui::UiMode) -> Resu
                     feature_id: Some(&feat.id),
                     task_type: "feature",
                     max_retries,
+                    criteria: &[],
+                    discovered_by: None,
+                    cwd: None,
+                    created_by: user.as_deref(),
                 },
             )?;
             output::formatter::print_info(&format!(
@@ -620,19 +1923,14 @@ async fn handle_feature(action: cli::FeatureAction, ui_mode: ui::UiMode) -> Resu
                 root.id, name
             ));
 
-            // Build system prompt for non-interactive DAG creation
-            let system_prompt =
-                build_feature_build_system_prompt(&spec_content, &plan_content, &root.id, &feat.id);
-
-            // Launch ACP streaming session — agent autonomously creates the task DAG
-            let _agent_text = acp::interactive::run_streaming(
-                &agent_command,
-                &system_prompt,
-                "Read the spec and plan, then create the task DAG. When done, emit <phase-complete>build</phase-complete> and stop.",
-                &project.root,
-                Some(model_name),
-            )
-            .await?;
+            task_proposal::insert(
+                &db,
+                &proposal,
+                &root.id,
+                &feat.id,
+                max_retries,
+                user.as_deref(),
+            )?;
 
             // Read back from DB and print summary
             let tree = dag::get_task_tree(&db, &root.id)?;
@@ -648,12 +1946,25 @@ async fn handle_feature(action: cli::FeatureAction, ui_mode: ui::UiMode) -> Resu
                     "Created {} tasks for feature '{}'",
                     child_count, name
                 ));
+                let labels = dag::labels_for(&db, &tree)?;
                 if ui_guard.is_active() {
-                    let lines = render_task_tree_lines(&tree, &root.id);
+                    let lines = render_task_tree_lines(&tree, &root.id, &labels);
                     let _ = ui::show_explorer("Created Task DAG", lines);
                 } else {
-                    print_task_tree(&tree, &root.id, "", true);
+                    print_task_tree(&tree, &root.id, &labels, "", true);
                 }
+
+                dag_review::review(
+                    &db,
+                    &project.root,
+                    &project.config,
+                    &root.id,
+                    &spec_content,
+                    &plan_content,
+                    &agent_command,
+                    user.as_deref(),
+                )
+                .await?;
             }
 
             // Update feature status to ready
@@ -667,10 +1978,237 @@ async fn handle_feature(action: cli::FeatureAction, ui_mode: ui::UiMode) -> Resu
             if ui_guard.is_active() {
                 drop(ui_guard);
             }
-
+
+            Ok(ExitCode::SUCCESS)
+        }
+        cli::FeatureAction::Replan { name, model, agent } => {
+            let feat = feature::get_feature(&db, &name)?;
+            let plan_path = project
+                .root
+                .join(".ralph/features")
+                .join(&name)
+                .join("plan.md");
+            let plan_path_str = plan_path.to_string_lossy().to_string();
+            if !plan_path.exists() {
+                anyhow::bail!(
+                    "No plan at {}. Run 'ralph feature create {} --phase plan' first.",
+                    plan_path_str,
+                    name
+                );
+            }
+            feature::ensure_nothing_in_progress(&db, &feat)?;
+
+            let base = replan::plan_baseline(&project.root, &feat)?;
+            let short = &base[..base.len().min(12)];
+            let Some(drift) = replan::code_drift(&project.root, &base) else {
+                output::formatter::print_info(&format!(
+                    "No code changes since the plan was written ({}); nothing to replan.",
+                    short
+                ));
+                return Ok(ExitCode::SUCCESS);
+            };
+
+            let ui_guard = ui::start(ui_mode);
+            let agent_command = config::resolve_agent_profile(
+                &project.config,
+                agent
+                    .or_else(|| std::env::var("RALPH_AGENT").ok())
+                    .unwrap_or_else(|| project.config.agent.command.clone()),
+            );
+            let model_name = model.as_deref().unwrap_or("opus");
+
+            // ── Phase 1: Plan ────────────────────────────────────────────
+            output::formatter::print_info("Phase 1: Update Plan");
+            output::formatter::print_info(&format!(
+                "Comparing the plan with code changes since {}",
+                short
+            ));
+            let spec_content = feature::read_spec(&project.root, &name)?;
+            let plan_content = feature::read_plan(&project.root, &name)?;
+            let context = gather_project_context(&project, &db, false);
+            let system_prompt = prompt_templates::apply(
+                &project.root,
+                "feature_replan",
+                build_feature_replan_system_prompt(
+                    &name,
+                    &spec_content,
+                    &plan_content,
+                    &plan_path_str,
+                    &drift,
+                    &context,
+                ),
+                &[
+                    ("name", &name),
+                    ("spec", &spec_content),
+                    ("plan", &plan_content),
+                    ("plan_path", &plan_path_str),
+                    ("drift", &drift),
+                    ("context", &context),
+                ],
+            );
+            acp::interactive::run_interactive(
+                &agent_command,
+                &system_prompt,
+                &build_initial_message_replan(&name),
+                &project.root,
+                Some(model_name),
+                false,
+                Some(vec![plan_path.clone()]),
+            )
+            .await?;
+            review::review_document(
+                &plan_path_str,
+                review::DocumentKind::Plan,
+                &name,
+                Some(&spec_content),
+                &context,
+                &agent_command,
+                &project.root,
+                &project.config.review,
+            )
+            .await?;
+            record_feature_revision(&db, &project.root, &feat, review::DocumentKind::Plan)?;
+
+            // ── Phase 2: Task DAG ────────────────────────────────────────
+            let Some(root) = replan::root_task(&db, &feat)? else {
+                feature::update_plan_commit(&db, &project.root, &feat.id)?;
+                output::formatter::print_info(&format!(
+                    "Feature '{}' has no task DAG yet. Run 'ralph feature create {} --phase build' to create it.",
+                    name, name
+                ));
+                return Ok(ExitCode::SUCCESS);
+            };
+            output::formatter::print_info("Phase 2: Reconcile Task DAG");
+            let plan_content = feature::read_plan(&project.root, &name)?;
+            let existing = replan::render_existing_tasks(&db, &root.id)?;
+            let system_prompt = prompt_templates::apply(
+                &project.root,
+                "feature_reconcile",
+                build_feature_reconcile_system_prompt(&spec_content, &plan_content, &existing),
+                &[
+                    ("spec", &spec_content),
+                    ("plan", &plan_content),
+                    ("existing_tasks", &existing),
+                ],
+            );
+            let Some(proposal) =
+                propose_task_dag(&agent_command, &system_prompt, &project.root, model_name).await?
+            else {
+                output::formatter::print_info(&format!(
+                    "Task DAG rejected; the tasks are unchanged. Run 'ralph feature replan {}' to try again.",
+                    name
+                ));
+                return Ok(ExitCode::SUCCESS);
+            };
+
+            let max_retries = project.config.execution.max_retries as i32;
+            let report = task_proposal::reconcile(
+                &db,
+                &proposal,
+                &root.id,
+                &feat.id,
+                max_retries,
+                project.user_name().as_deref(),
+            )?;
+            feature::update_plan_commit(&db, &project.root, &feat.id)?;
+            if report.updated + report.created > 0
+                && matches!(feat.status.as_str(), "done" | "failed")
+            {
+                feature::update_feature_status(&db, &feat.id, "ready")?;
+            }
+            output::formatter::print_info(&format!(
+                "Reconciled the task DAG: {} done or in-progress task(s) kept, {} updated, {} created, {} removed",
+                report.kept, report.updated, report.created, report.removed
+            ));
+            let tree = dag::get_task_tree(&db, &root.id)?;
+            let labels = dag::labels_for(&db, &tree)?;
+            if ui_guard.is_active() {
+                let _ = ui::show_explorer(
+                    "Reconciled Task DAG",
+                    render_task_tree_lines(&tree, &root.id, &labels),
+                );
+                drop(ui_guard);
+            } else {
+                print_task_tree(&tree, &root.id, &labels, "", true);
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        cli::FeatureAction::Show { name, plan, out } => {
+            let feat = feature::get_feature(&db, &name)?;
+            let (label, text) = if plan {
+                ("Plan", feature::read_plan(&project.root, &feat.name)?)
+            } else {
+                ("Spec", feature::read_spec(&project.root, &feat.name)?)
+            };
+            let lines: Vec<String> = text.lines().map(str::to_string).collect();
+            if let Some(path) = out {
+                return export_lines(&path, &lines);
+            }
+            let ui_guard = ui::start(ui_mode);
+            if ui_guard.is_active() {
+                let _ = ui::show_markdown_explorer(&format!("{label} {}", feat.name), lines);
+            } else {
+                for line in lines {
+                    println!("{line}");
+                }
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        cli::FeatureAction::Diff {
+            name,
+            plan,
+            from,
+            to,
+        } => {
+            let feat = feature::get_feature(&db, &name)?;
+            let kind = if plan {
+                review::DocumentKind::Plan
+            } else {
+                review::DocumentKind::Spec
+            };
+            let diff = feature::diff_revisions(&project.root, &feat, kind, from, to)?;
+            if diff.is_empty() {
+                output::formatter::print_info(&format!("No {} changes.", kind.label()));
+            } else {
+                for line in diff.lines() {
+                    if line.starts_with("+++") || line.starts_with("---") {
+                        println!("{}", line.bold());
+                    } else if line.starts_with('+') {
+                        println!("{}", line.green());
+                    } else if line.starts_with('-') {
+                        println!("{}", line.red());
+                    } else if line.starts_with("@@") {
+                        println!("{}", line.cyan());
+                    } else {
+                        println!("{}", line);
+                    }
+                }
+            }
             Ok(ExitCode::SUCCESS)
         }
-        cli::FeatureAction::Delete { name, yes } => {
+        cli::FeatureAction::Coverage { name, json } => {
+            let feat = feature::get_feature(&db, &name)?;
+            let plan = feature::read_plan(&project.root, &feat.name)?;
+            let tasks = match replan::root_task(&db, &feat)? {
+                Some(root) => dag::get_task_tree(&db, &root.id)?
+                    .into_iter()
+                    .filter(|t| t.id != root.id)
+                    .collect(),
+                None => Vec::new(),
+            };
+            let report = plan_coverage::coverage(&plan, &tasks);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_plan_coverage(&feat.name, &report);
+            }
+            Ok(if report.is_complete() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            })
+        }
+        cli::FeatureAction::Delete { name } => {
             let ui_guard = ui::start(ui_mode);
             let feat = feature::get_feature(&db, &name)?;
             let counts = dag::get_feature_task_counts(&db, &feat.id)?;
@@ -708,7 +2246,6 @@ async fn handle_feature(action: cli::FeatureAction, ui_mode: ui::UiMode) -> Resu
 
             if !confirm_if_ui_active(
                 &ui_guard,
-                yes,
                 "Delete Feature",
                 &format!("Delete feature '{}' and all associated data?", name),
                 false,
@@ -734,7 +2271,82 @@ async fn handle_feature(action: cli::FeatureAction, ui_mode: ui::UiMode) -> Resu
             ));
             Ok(ExitCode::SUCCESS)
         }
-        cli::FeatureAction::List => {
+        cli::FeatureAction::Archive { name } => {
+            let ui_guard = ui::start(ui_mode);
+            let feat = feature::get_feature(&db, &name)?;
+            let counts = dag::get_feature_task_counts(&db, &feat.id)?;
+            if !confirm_if_ui_active(
+                &ui_guard,
+                "Archive Feature",
+                &format!(
+                    "Archive feature '{}' ({} tasks) to .ralph/archive/?",
+                    name, counts.total
+                ),
+                true,
+            ) {
+                output::formatter::print_info("Cancelled.");
+                return Ok(ExitCode::SUCCESS);
+            }
+            let tasks = feature::archive_feature(&db, &project.root, &name)?;
+            show_result_if_ui_active(
+                &ui_guard,
+                "Feature Archived",
+                vec![format!(
+                    "Archived feature '{name}' ({tasks} tasks). Restore with 'ralph feature restore {name}'."
+                )],
+            );
+            Ok(ExitCode::SUCCESS)
+        }
+        cli::FeatureAction::Clone { name, new_name } => {
+            let user = project.user_name();
+            let summary =
+                feature::clone_feature(&db, &project.root, &name, &new_name, user.as_deref())?;
+            output::formatter::print_info(&format!(
+                "Cloned feature '{}' to '{}' ({} tasks, {} dependencies, status {}).",
+                name, new_name, summary.tasks, summary.dependencies, summary.feature.status
+            ));
+            Ok(ExitCode::SUCCESS)
+        }
+        cli::FeatureAction::Restore { name } => {
+            let tasks = feature::restore_feature(&db, &project.root, &name)?;
+            output::formatter::print_info(&format!(
+                "Restored feature '{}' ({} tasks).",
+                name, tasks
+            ));
+            Ok(ExitCode::SUCCESS)
+        }
+        cli::FeatureAction::List {
+            archived: true,
+            out,
+        } => {
+            let features = feature::list_archived_features(&db)?;
+            if features.is_empty() {
+                output::formatter::print_info("No archived features.");
+                return Ok(ExitCode::SUCCESS);
+            }
+            let lines: Vec<String> = features
+                .iter()
+                .map(|feat| {
+                    format!(
+                        "  {:<16} [{}]  archived {}",
+                        feat.name,
+                        feat.status,
+                        feat.archived_at.as_deref().unwrap_or("")
+                    )
+                })
+                .collect();
+            if let Some(path) = out {
+                return export_lines(&path, &lines);
+            }
+            for line in lines {
+                println!("{line}");
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        cli::FeatureAction::List {
+            archived: false,
+            out,
+        } => {
             let features = feature::list_features(&db)?;
 
             if features.is_empty() {
@@ -774,6 +2386,9 @@ async fn handle_feature(action: cli::FeatureAction, ui_mode: ui::UiMode) -> Resu
                 }
             }
 
+            if let Some(path) = out {
+                return export_lines(&path, &lines);
+            }
             let ui_guard = ui::start(ui_mode);
             if ui_guard.is_active() {
                 let _ = ui::show_explorer("Feature Explorer", lines);
@@ -790,11 +2405,13 @@ async fn handle_feature(action: cli::FeatureAction, ui_mode: ui::UiMode) -> Resu
 /// Handle `ralph task <action>` subcommands.
 async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<ExitCode> {
     let project = project::discover()?;
-    ui::theme::init_with_overrides(
-        ui::theme::resolve_theme_name(&project.config.ui.theme),
-        Some(&project.config.ui.colors),
-    );
-    let db_path = project.root.join(".ralph/progress.db");
+    ui::theme::init_for_project(
+        &project.root,
+        &project.config.ui.theme,
+        &project.config.ui.colors,
+    )?;
+    configure_session(&project)?;
+    let db_path = project.db_path();
     let db = dag::open_db(db_path.to_str().unwrap())?;
 
     match action {
@@ -805,7 +2422,20 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
             feature,
             priority,
             max_retries,
+            criteria,
+            cwd,
+            labels,
         } => {
+            let cwd = cwd
+                .as_deref()
+                .map(dag::normalize_cwd)
+                .transpose()?
+                .flatten();
+            if let Some(dir) = &cwd {
+                if !project.root.join(dir).is_dir() {
+                    anyhow::bail!("Task directory '{}' does not exist in the project", dir);
+                }
+            }
             let task_type = if feature.is_some() {
                 "feature"
             } else {
@@ -821,8 +2451,13 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
                     feature_id: feature.as_deref(),
                     task_type,
                     max_retries,
+                    criteria: &criteria,
+                    discovered_by: None,
+                    cwd: cwd.as_deref(),
+                    created_by: project.user_name().as_deref(),
                 },
             )?;
+            dag::add_labels(&db, &task.id, &labels)?;
             // Print just the ID for scriptability
             println!("{}", task.id);
             Ok(ExitCode::SUCCESS)
@@ -830,9 +2465,12 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
         cli::TaskAction::Create { model, agent } => {
             let ui_guard = ui::start(ui_mode);
             // Resolve agent command: --agent flag > RALPH_AGENT env > config > "claude"
-            let agent_command = agent
-                .or_else(|| std::env::var("RALPH_AGENT").ok())
-                .unwrap_or_else(|| project.config.agent.command.clone());
+            let agent_command = config::resolve_agent_profile(
+                &project.config,
+                agent
+                    .or_else(|| std::env::var("RALPH_AGENT").ok())
+                    .unwrap_or_else(|| project.config.agent.command.clone()),
+            );
 
             // Snapshot existing task IDs before the session
             let before_ids: std::collections::HashSet<String> =
@@ -842,7 +2480,12 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
             let context = gather_project_context(&project, &db, true);
 
             // Build system prompt and initial message
-            let system_prompt = build_task_new_system_prompt(&context);
+            let system_prompt = prompt_templates::apply(
+                &project.root,
+                "task_new",
+                build_task_new_system_prompt(&context),
+                &[("context", &context)],
+            );
             let initial_message = build_initial_message_task_new();
 
             // Launch interactive session via ACP
@@ -875,15 +2518,26 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
             }
             Ok(ExitCode::SUCCESS)
         }
-        cli::TaskAction::Show { id, json } => {
+        cli::TaskAction::Show { id, json, out } => {
             let task = dag::get_task(&db, &id)?;
             if json {
-                println!("{}", serde_json::to_string_pretty(&task)?);
+                let mut value = serde_json::to_value(&task)?;
+                value["criteria"] = serde_json::to_value(dag::get_task_criteria(&db, &id)?)?;
+                value["artifacts"] = serde_json::to_value(dag::get_task_artifacts(&db, &id)?)?;
+                value["frontmatter"] = serde_json::to_value(task.frontmatter().0)?;
+                value["labels"] = serde_json::to_value(dag::task_labels(
+                    &task,
+                    &dag::get_task_labels(&db, &id)?,
+                ))?;
+                println!("{}", serde_json::to_string_pretty(&value)?);
             } else {
                 let lines = render_task_details_lines(&db, &task)?;
+                if let Some(path) = out {
+                    return export_lines(&path, &lines);
+                }
                 let ui_guard = ui::start(ui_mode);
                 if ui_guard.is_active() {
-                    let _ = ui::show_explorer(&format!("Task {}", id), lines);
+                    let _ = ui::show_markdown_explorer(&format!("Task {}", id), lines);
                 } else {
                     for line in lines {
                         println!("{line}");
@@ -897,7 +2551,9 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
             status,
             ready,
             all,
+            labels,
             json,
+            out,
         } => {
             let tasks = if ready {
                 dag::get_ready_tasks(&db)?
@@ -906,6 +2562,8 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
             } else if let Some(ref feat_name) = feature {
                 let feat = feature::get_feature(&db, feat_name)?;
                 dag::get_all_tasks_for_feature(&db, &feat.id)?
+            } else if !labels.is_empty() {
+                dag::get_all_tasks(&db)?
             } else {
                 dag::get_standalone_tasks(&db)?
             };
@@ -916,9 +2574,27 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
             } else {
                 tasks
             };
+            let mut task_labels = dag::labels_for(&db, &tasks)?;
+            let wanted = dag::normalize_labels(&labels);
+            let tasks: Vec<(dag::Task, Vec<String>)> = tasks
+                .into_iter()
+                .map(|t| {
+                    let labels = task_labels.remove(&t.id).unwrap_or_default();
+                    (t, labels)
+                })
+                .filter(|(_, labels)| wanted.iter().all(|l| labels.contains(l)))
+                .collect();
 
             if json {
-                println!("{}", serde_json::to_string_pretty(&tasks)?);
+                let values = tasks
+                    .iter()
+                    .map(|(task, labels)| {
+                        let mut value = serde_json::to_value(task)?;
+                        value["labels"] = serde_json::to_value(labels)?;
+                        Ok(value)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                println!("{}", serde_json::to_string_pretty(&values)?);
                 return Ok(ExitCode::SUCCESS);
             }
 
@@ -934,10 +2610,19 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
             }
 
             let mut lines: Vec<String> = Vec::new();
-            for task in &tasks {
-                lines.push(format!("  {}  [{}]  {}", task.id, task.status, task.title));
+            for (task, labels) in &tasks {
+                lines.push(format!(
+                    "  {}  [{}]  {}{}",
+                    task.id,
+                    task.status,
+                    task.title,
+                    format_labels(labels)
+                ));
             }
 
+            if let Some(path) = out {
+                return export_lines(&path, &lines);
+            }
             let ui_guard = ui::start(ui_mode);
             if ui_guard.is_active() {
                 let _ = ui::show_explorer("Task Explorer", lines);
@@ -953,6 +2638,8 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
             title,
             description,
             priority,
+            labels,
+            unlabel,
         } => {
             let ui_guard = ui::start(ui_mode);
             let fields = dag::TaskUpdate {
@@ -961,6 +2648,8 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
                 priority,
             };
             let updated = dag::update_task(&db, &id, fields)?;
+            dag::add_labels(&db, &id, &labels)?;
+            dag::remove_labels(&db, &id, &unlabel)?;
             show_result_if_ui_active(
                 &ui_guard,
                 "Task Updated",
@@ -968,11 +2657,10 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
             );
             Ok(ExitCode::SUCCESS)
         }
-        cli::TaskAction::Delete { id, yes } => {
+        cli::TaskAction::Delete { id } => {
             let ui_guard = ui::start(ui_mode);
             if !confirm_if_ui_active(
                 &ui_guard,
-                yes,
                 "Delete Task",
                 &format!("Delete task '{}'?", id),
                 false,
@@ -988,11 +2676,135 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
             );
             Ok(ExitCode::SUCCESS)
         }
-        cli::TaskAction::Done { id, yes } => {
+        cli::TaskAction::Bulk {
+            status,
+            feature,
+            stdin,
+            action,
+        } => {
+            let ids = if stdin {
+                if !input::assume_yes() {
+                    anyhow::bail!("--stdin reads task IDs from stdin; pass --yes to confirm");
+                }
+                let mut text = String::new();
+                std::io::Read::read_to_string(&mut std::io::stdin(), &mut text)?;
+                dag::parse_task_ids(&text)
+            } else {
+                let feature_id = match feature {
+                    Some(ref name) => Some(feature::get_feature(&db, name)?.id),
+                    None => None,
+                };
+                dag::select_tasks(&db, status.as_deref(), feature_id.as_deref())?
+                    .into_iter()
+                    .map(|t| t.id)
+                    .collect()
+            };
+            if ids.is_empty() {
+                output::formatter::print_info("No tasks selected.");
+                return Ok(ExitCode::SUCCESS);
+            }
+
+            let user = project.user_name();
+            let (op, verb, done) = match action {
+                cli::BulkAction::Done { message } => (
+                    dag::BulkOp::Done {
+                        completed_by: user.clone(),
+                        message,
+                    },
+                    "Mark done".to_string(),
+                    "Marked done",
+                ),
+                cli::BulkAction::Delete => (dag::BulkOp::Delete, "Delete".to_string(), "Deleted"),
+                cli::BulkAction::Reset => {
+                    (dag::BulkOp::Reset, "Reset to pending".to_string(), "Reset")
+                }
+                cli::BulkAction::Priority { priority } => (
+                    dag::BulkOp::Priority(priority),
+                    format!("Set priority {priority} on"),
+                    "Reprioritized",
+                ),
+            };
+            let mut shown = ids.iter().take(5).cloned().collect::<Vec<_>>().join(", ");
+            if ids.len() > 5 {
+                shown.push_str(&format!(", and {} more", ids.len() - 5));
+            }
+            let ui_guard = ui::start(ui_mode);
+            if !confirm_if_ui_active(
+                &ui_guard,
+                "Bulk Task Update",
+                &format!("{verb} {} task(s) ({shown})?", ids.len()),
+                false,
+            ) {
+                output::formatter::print_info("Cancelled.");
+                return Ok(ExitCode::SUCCESS);
+            }
+            let transitions = dag::apply_bulk(&db, &ids, &op)?;
+            emit_auto_transitions_cli(&transitions);
+            post_feature::after_transitions(&db, &project.config, &transitions, user.as_deref())?;
+            show_result_if_ui_active(
+                &ui_guard,
+                "Tasks Updated",
+                vec![format!("{done} {} task(s)", ids.len())],
+            );
+            Ok(ExitCode::SUCCESS)
+        }
+        cli::TaskAction::Next { feature, claim } => {
+            let config = config::Config::from_run_args(
+                None,
+                None,
+                None,
+                project.clone(),
+                None,
+                None,
+                false,
+                None,
+                None,
+            )?;
+            let mut ready = match feature {
+                Some(ref name) => {
+                    dag::get_ready_tasks_for_feature(&db, &feature::get_feature(&db, name)?.id)?
+                }
+                None => dag::get_ready_tasks(&db)?,
+            };
+            config.scheduler.order(&mut ready, chrono::Utc::now());
+            let Some(task) = ready.into_iter().next() else {
+                output::formatter::print_info("No ready tasks.");
+                return Ok(ExitCode::SUCCESS);
+            };
+            if claim {
+                let who = format!(
+                    "human:{}",
+                    project.user_name().as_deref().unwrap_or("unknown")
+                );
+                if !dag::claim_task_manually(&db, &task.id, &who)? {
+                    anyhow::bail!("{} was just claimed by another agent; run again", task.id);
+                }
+                dag::add_log(&db, &task.id, &format!("Claimed by {who} for manual work"))?;
+            }
+            let context = run_loop::task_context(&db, &config, &task)?;
+            for line in render_task_context_lines(&db, &task, &context)? {
+                println!("{line}");
+            }
+            println!();
+            if claim {
+                output::formatter::print_info(&format!(
+                    "Claimed {id}. When finished: ralph task done {id} -m \"what you did\"",
+                    id = task.id
+                ));
+            } else {
+                output::formatter::print_info(&format!(
+                    "Work it by hand with: ralph task next{} --claim",
+                    feature
+                        .map(|name| format!(" --feature {name}"))
+                        .unwrap_or_default()
+                ));
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        cli::TaskAction::Done { id, message } => {
             let ui_guard = ui::start(ui_mode);
             if !confirm_if_ui_active(
                 &ui_guard,
-                yes,
                 "Mark Task Done",
                 &format!("Mark task '{}' as done?", id),
                 false,
@@ -1001,7 +2813,13 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
                 return Ok(ExitCode::SUCCESS);
             }
             let transitions = dag::force_complete_task(db.conn(), &id)?;
+            let user = project.user_name();
+            dag::set_completed_by(&db, &id, user.as_deref())?;
+            if let Some(ref message) = message {
+                dag::add_log(&db, &id, message)?;
+            }
             emit_auto_transitions_cli(&transitions);
+            post_feature::after_transitions(&db, &project.config, &transitions, user.as_deref())?;
             show_result_if_ui_active(
                 &ui_guard,
                 "Task Updated",
@@ -1009,11 +2827,10 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
             );
             Ok(ExitCode::SUCCESS)
         }
-        cli::TaskAction::Fail { id, reason, yes } => {
+        cli::TaskAction::Fail { id, reason } => {
             let ui_guard = ui::start(ui_mode);
             if !confirm_if_ui_active(
                 &ui_guard,
-                yes,
                 "Mark Task Failed",
                 &format!("Mark task '{}' as failed?", id),
                 false,
@@ -1035,11 +2852,10 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
             );
             Ok(ExitCode::SUCCESS)
         }
-        cli::TaskAction::Reset { id, yes } => {
+        cli::TaskAction::Reset { id } => {
             let ui_guard = ui::start(ui_mode);
             if !confirm_if_ui_active(
                 &ui_guard,
-                yes,
                 "Reset Task",
                 &format!("Reset task '{}' to pending?", id),
                 false,
@@ -1056,7 +2872,92 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
             );
             Ok(ExitCode::SUCCESS)
         }
-        cli::TaskAction::Log { id, message } => {
+        cli::TaskAction::Rollback { id, dry_run, pick } => {
+            let reverts = rollback::plan_rollback(&db, &project.root, &id)?;
+            if reverts.is_empty() {
+                output::formatter::print_warning(&format!(
+                    "No files recorded for {id}; only the task status will be reset."
+                ));
+            }
+            for revert in &reverts {
+                let line = match &revert.action {
+                    rollback::FileAction::Restore if revert.is_shared_restore() => format!(
+                        "  keep    {} (also modified by {}; revert it with --pick)",
+                        revert.path,
+                        revert.shared_with.join(", ")
+                    ),
+                    rollback::FileAction::Restore => format!("  restore {}", revert.path),
+                    rollback::FileAction::Remove => format!("  remove  {}", revert.path),
+                    rollback::FileAction::Skip(reason) => {
+                        format!("  skip    {} ({reason})", revert.path)
+                    }
+                };
+                println!("{line}");
+            }
+            if dry_run {
+                return Ok(ExitCode::SUCCESS);
+            }
+
+            let ui_guard = ui::start(ui_mode);
+            let picked = if pick {
+                let candidates: Vec<&rollback::FileRevert> = reverts
+                    .iter()
+                    .filter(|r| !matches!(r.action, rollback::FileAction::Skip(_)))
+                    .collect();
+                let paths: Vec<&str> = candidates.iter().map(|r| r.path.as_str()).collect();
+                // Files other tasks also changed start unchecked
+                let checked: Vec<bool> =
+                    candidates.iter().map(|r| !r.is_shared_restore()).collect();
+                let Some(indices) = input::select_many("Files to revert", &paths, &checked) else {
+                    output::formatter::print_info("Cancelled.");
+                    return Ok(ExitCode::SUCCESS);
+                };
+                Some(
+                    indices
+                        .into_iter()
+                        .map(|i| paths[i].to_string())
+                        .collect::<Vec<_>>(),
+                )
+            } else {
+                None
+            };
+            let shared: Vec<&str> = reverts
+                .iter()
+                .filter(|r| r.is_shared_restore())
+                .filter(|r| picked.as_ref().is_some_and(|p| p.contains(&r.path)))
+                .map(|r| r.path.as_str())
+                .collect();
+            let mut prompt = format!(
+                "Discard uncommitted changes to these files and reset '{}' to pending?",
+                id
+            );
+            if !shared.is_empty() {
+                prompt.push_str(&format!(
+                    " This also discards other tasks' changes to: {}.",
+                    shared.join(", ")
+                ));
+            }
+            if !confirm_if_ui_active(&ui_guard, "Roll Back Task", &prompt, false) {
+                output::formatter::print_info("Cancelled.");
+                return Ok(ExitCode::SUCCESS);
+            }
+            let report = rollback::rollback_task(&db, &project.root, &id, picked.as_deref())?;
+            emit_auto_transitions_cli(&report.transitions);
+            let reverted = report
+                .reverts
+                .iter()
+                .filter(|r| !matches!(r.action, rollback::FileAction::Skip(_)))
+                .count();
+            show_result_if_ui_active(
+                &ui_guard,
+                "Task Rolled Back",
+                vec![format!(
+                    "Reverted {reverted} file(s) and reset {id} to pending"
+                )],
+            );
+            Ok(ExitCode::SUCCESS)
+        }
+        cli::TaskAction::Log { id, message, out } => {
             let ui_guard = ui::start(ui_mode);
             if let Some(msg) = message {
                 dag::add_log(&db, &id, &msg)?;
@@ -1078,6 +2979,9 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
                         .iter()
                         .map(|log| format!("  [{}]  {}", log.timestamp, log.message))
                         .collect();
+                    if let Some(path) = out {
+                        return export_lines(&path, &lines);
+                    }
                     if ui_guard.is_active() {
                         let _ = ui::show_explorer(&format!("Task Log {id}"), lines);
                     } else {
@@ -1112,7 +3016,7 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
                 );
                 Ok(ExitCode::SUCCESS)
             }
-            cli::DepsAction::List { id } => {
+            cli::DepsAction::List { id, out } => {
                 let blockers = dag::get_task_blockers(&db, &id)?;
                 let blocked_by_me = dag::get_tasks_blocked_by(&db, &id)?;
 
@@ -1135,6 +3039,9 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
                     }
                 }
 
+                if let Some(path) = out {
+                    return export_lines(&path, &lines);
+                }
                 let ui_guard = ui::start(ui_mode);
                 if ui_guard.is_active() {
                     let _ = ui::show_explorer(&format!("Dependencies for {}", id), lines);
@@ -1146,17 +3053,56 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
                 Ok(ExitCode::SUCCESS)
             }
         },
-        cli::TaskAction::Tree { id, json } => {
+        cli::TaskAction::Validate { json } => {
+            let issues = dag::validate_dag(&db)?;
+            let has_errors = issues.iter().any(|i| i.is_error());
+            if json {
+                println!("{}", serde_json::to_string_pretty(&issues)?);
+            } else if issues.is_empty() {
+                output::formatter::print_info("Task DAG is valid.");
+            } else {
+                for issue in &issues {
+                    if issue.is_error() {
+                        output::formatter::print_error(&format!("error: {issue}"));
+                    } else {
+                        output::formatter::print_warning(&format!("warning: {issue}"));
+                    }
+                }
+            }
+            Ok(if has_errors {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            })
+        }
+        cli::TaskAction::CriticalPath { feature, json } => {
+            let feature_id = match feature {
+                Some(ref name) => Some(feature::get_feature(&db, name)?.id),
+                None => None,
+            };
+            let report = dag::critical_path(&db, feature_id.as_deref())?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_critical_path(&report);
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        cli::TaskAction::Tree { id, json, out } => {
             let tree = dag::get_task_tree(&db, &id)?;
             if json {
                 println!("{}", serde_json::to_string_pretty(&tree)?);
             } else {
-                let lines = render_task_tree_lines(&tree, &id);
+                let labels = dag::labels_for(&db, &tree)?;
+                let lines = render_task_tree_lines(&tree, &id, &labels);
+                if let Some(path) = out {
+                    return export_lines(&path, &lines);
+                }
                 let ui_guard = ui::start(ui_mode);
                 if ui_guard.is_active() {
                     let _ = ui::show_explorer(&format!("Tree {}", id), lines);
                 } else {
-                    print_task_tree(&tree, &id, "", true);
+                    print_task_tree(&tree, &id, &labels, "", true);
                 }
             }
             Ok(ExitCode::SUCCESS)
@@ -1164,17 +3110,21 @@ async fn handle_task(action: cli::TaskAction, ui_mode: ui::UiMode) -> Result<Exi
     }
 }
 
+/// Confirm a destructive action: TUI modal when active, otherwise a plain stdin
+/// prompt governed by `--yes` / `--no-input`.
 fn confirm_if_ui_active(
     ui_guard: &ui::UiGuard,
-    bypass: bool,
     title: &str,
     prompt: &str,
     default_yes: bool,
 ) -> bool {
-    if bypass || !ui_guard.is_active() {
+    if input::assume_yes() {
         return true;
     }
-    ui::prompt_confirm(title, prompt, default_yes).unwrap_or(false)
+    if ui_guard.is_active() {
+        return ui::prompt_confirm(title, prompt, default_yes).unwrap_or(false);
+    }
+    input::confirm(prompt, default_yes)
 }
 
 fn emit_auto_transitions_cli(transitions: &[dag::AutoTransition]) {
@@ -1217,6 +3167,17 @@ fn emit_auto_transitions_cli(transitions: &[dag::AutoTransition]) {
     }
 }
 
+/// `--out FILE`: write an explorer view's lines instead of showing them.
+fn export_lines(path: &std::path::Path, lines: &[String]) -> Result<ExitCode> {
+    ui::export::write_lines(path, lines)?;
+    output::formatter::print_info(&format!(
+        "Wrote {} line(s) to {}",
+        lines.len(),
+        path.display()
+    ));
+    Ok(ExitCode::SUCCESS)
+}
+
 fn show_result_if_ui_active(ui_guard: &ui::UiGuard, title: &str, lines: Vec<String>) {
     if ui_guard.is_active() {
         let _ = ui::show_explorer(title, lines);
@@ -1239,6 +3200,86 @@ fn colorize_status(status: &str) -> String {
     }
 }
 
+/// `ralph task next` output: the task and the context a run would send.
+fn render_task_context_lines(
+    db: &dag::Db,
+    task: &dag::Task,
+    context: &acp::types::IterationContext,
+) -> Result<Vec<String>> {
+    /// Spec lines shown before the excerpt is cut.
+    const SPEC_EXCERPT_LINES: usize = 40;
+
+    let mut lines = vec![format!(
+        "{}  [{}]  {}  (priority {})",
+        task.id, task.status, task.title, task.priority
+    )];
+    if let Some(ref cwd) = context.task.cwd {
+        lines.push(format!("  cwd: {cwd}"));
+    }
+    let meta = &context.task.frontmatter;
+    if !meta.labels.is_empty() {
+        lines.push(format!("  labels: {}", meta.labels.join(", ")));
+    }
+    if !context.task.description.is_empty() {
+        lines.push(String::new());
+        lines.extend(context.task.description.lines().map(String::from));
+    }
+    for (heading, items) in [("Files:", &meta.files), ("Links:", &meta.links)] {
+        if !items.is_empty() {
+            lines.push(String::new());
+            lines.push(heading.to_string());
+            lines.extend(items.iter().map(|item| format!("  {item}")));
+        }
+    }
+    let criteria = dag::get_task_criteria(db, &task.id)?;
+    if !criteria.is_empty() {
+        lines.push(String::new());
+        lines.push("Acceptance criteria:".to_string());
+        lines.extend(
+            criteria
+                .iter()
+                .map(|c| format!("  - [ ] {}", c.description)),
+        );
+    }
+    if let Some(ref parent) = context.task.parent {
+        lines.push(String::new());
+        lines.push(format!("Parent: {}", parent.title));
+    }
+    if !context.task.completed_blockers.is_empty() {
+        lines.push(String::new());
+        lines.push("Completed blockers:".to_string());
+        for b in &context.task.completed_blockers {
+            lines.push(format!("  {}  {}: {}", b.task_id, b.title, b.summary));
+            for artifact in &b.artifacts {
+                lines.push(format!(
+                    "    artifact {}  {}",
+                    artifact.path, artifact.description
+                ));
+            }
+        }
+    }
+    if let Some(ref spec) = context.spec_content {
+        lines.push(String::new());
+        lines.push("Spec (excerpt):".to_string());
+        lines.extend(
+            spec.lines()
+                .take(SPEC_EXCERPT_LINES)
+                .map(|l| format!("  {l}")),
+        );
+        let rest = spec.lines().count().saturating_sub(SPEC_EXCERPT_LINES);
+        if rest > 0 {
+            lines.push(format!("  … {rest} more lines"));
+        }
+    }
+    for section in [&context.knowledge_context, &context.journal_context] {
+        if !section.trim().is_empty() {
+            lines.push(String::new());
+            lines.extend(section.trim_end().lines().map(String::from));
+        }
+    }
+    Ok(lines)
+}
+
 fn render_task_details_lines(db: &dag::Db, task: &dag::Task) -> Result<Vec<String>> {
     let mut lines = Vec::new();
     lines.push(format!("{}  [{}]  {}", task.id, task.status, task.title));
@@ -1253,25 +3294,85 @@ fn render_task_details_lines(db: &dag::Db, task: &dag::Task) -> Result<Vec<Strin
             lines.push(format!("  feature:      {}", fid));
         }
     }
+    if let Some(ref origin) = task.discovered_by {
+        lines.push(format!("  discovered by: {}", origin));
+    }
+    if let Some(ref source) = task.source_ref {
+        lines.push(format!("  source:       {}", source));
+    }
+    if let Some(ref cwd) = task.cwd {
+        lines.push(format!("  cwd:          {}", cwd));
+    }
+    if let Some(ref plan_ref) = task.plan_ref {
+        lines.push(format!("  plan ref:     {}", plan_ref));
+    }
     lines.push(format!("  priority:     {}", task.priority));
     lines.push(format!(
         "  retries:      {}/{}",
         task.retry_count, task.max_retries
     ));
-    lines.push(format!(
-        "  verification: {}",
-        task.verification_status.as_deref().unwrap_or("none")
-    ));
-    lines.push(format!("  created:      {}", task.created_at));
+    let criteria = dag::get_task_criteria(db, &task.id)?;
+    if criteria.is_empty() {
+        lines.push(format!(
+            "  verification: {}",
+            task.verification_status.as_deref().unwrap_or("none")
+        ));
+    } else {
+        let passed = criteria.iter().filter(|c| c.status == "passed").count();
+        lines.push(format!(
+            "  criteria:     {}/{} passed",
+            passed,
+            criteria.len()
+        ));
+    }
+    match task.created_by {
+        Some(ref who) => lines.push(format!("  created:      {} by {}", task.created_at, who)),
+        None => lines.push(format!("  created:      {}", task.created_at)),
+    }
+    if let (Some(ref who), "done") = (&task.completed_by, task.status.as_str()) {
+        lines.push(format!("  completed by: {}", who));
+    }
+
+    let labels = dag::task_labels(task, &dag::get_task_labels(db, &task.id)?);
+    if !labels.is_empty() {
+        lines.push(format!("  labels:       {}", labels.join(", ")));
+    }
+    let (meta, description) = task.frontmatter();
 
-    if !task.description.is_empty() {
+    if !description.is_empty() {
         lines.push(String::new());
         lines.push("  Description:".to_string());
-        for line in task.description.lines() {
+        for line in description.lines() {
             lines.push(format!("    {}", line));
         }
     }
 
+    for (heading, items) in [("Files:", &meta.files), ("Links:", &meta.links)] {
+        if !items.is_empty() {
+            lines.push(String::new());
+            lines.push(format!("  {heading}"));
+            for item in items {
+                lines.push(format!("    {}", item));
+            }
+        }
+    }
+
+    if !criteria.is_empty() {
+        lines.push(String::new());
+        lines.push("  Acceptance criteria:".to_string());
+        for c in &criteria {
+            let mark = match c.status.as_str() {
+                "passed" => "[x]",
+                "failed" => "[!]",
+                _ => "[ ]",
+            };
+            lines.push(format!("    {} {}. {}", mark, c.position, c.description));
+            if let Some(ref note) = c.note {
+                lines.push(format!("          {}", note));
+            }
+        }
+    }
+
     let blockers = dag::get_task_blockers(db, &task.id)?;
     if !blockers.is_empty() {
         lines.push(String::new());
@@ -1290,11 +3391,126 @@ fn render_task_details_lines(db: &dag::Db, task: &dag::Task) -> Result<Vec<Strin
         }
     }
 
+    let artifacts = dag::get_task_artifacts(db, &task.id)?;
+    if !artifacts.is_empty() {
+        lines.push(String::new());
+        lines.push("  Artifacts:".to_string());
+        for a in &artifacts {
+            lines.push(format!("    {}  {}", a.path, a.description));
+        }
+    }
+
     Ok(lines)
 }
 
 /// Print a task tree with Unicode box-drawing characters.
-fn print_task_tree(tree: &[dag::Task], current_id: &str, prefix: &str, is_last: bool) {
+fn print_plan_coverage(name: &str, report: &plan_coverage::Coverage) {
+    if report.is_complete() {
+        output::formatter::print_info(&format!(
+            "Every section of {name}'s plan has a task, and every task a plan section."
+        ));
+        return;
+    }
+    if !report.uncovered.is_empty() {
+        println!("Plan sections without tasks:");
+        for section in &report.uncovered {
+            println!(
+                "  {}{}  (plan.md:{})  {}",
+                "  ".repeat(section.level - 2),
+                section.anchor.yellow(),
+                section.line,
+                section.title
+            );
+        }
+    }
+    if !report.unanchored.is_empty() {
+        if !report.uncovered.is_empty() {
+            println!();
+        }
+        println!("Tasks without a plan section:");
+        for task in &report.unanchored {
+            println!("  {}  {}", task.id, task.title);
+        }
+    }
+    if !report.unknown.is_empty() {
+        if !report.uncovered.is_empty() || !report.unanchored.is_empty() {
+            println!();
+        }
+        println!("Tasks referencing sections the plan no longer has:");
+        for task in &report.unknown {
+            println!(
+                "  {}  {}  (plan_ref: {})",
+                task.id,
+                task.title,
+                task.plan_ref.as_deref().unwrap_or_default().red()
+            );
+        }
+    }
+}
+
+fn print_critical_path(report: &dag::CriticalPathReport) {
+    if report.path.is_empty() {
+        output::formatter::print_info("No unresolved tasks.");
+        return;
+    }
+    let length = match report.estimated_secs {
+        Some(secs) => format!("~{}", format_estimate(secs)),
+        None => format!("{} tasks", report.path.len()),
+    };
+    println!("Critical path ({length}):");
+    for step in &report.path {
+        let estimate = step
+            .estimate_secs
+            .map(|s| format!("  ~{}", format_estimate(s)))
+            .unwrap_or_default();
+        println!(
+            "  {}  [{}]  {}{}",
+            step.id,
+            colorize_status(&step.status),
+            step.title,
+            estimate
+        );
+    }
+    if !report.bottlenecks.is_empty() {
+        println!();
+        println!("Bottlenecks:");
+        for b in &report.bottlenecks {
+            println!("  {}  {}  ({} waiting)", b.id, b.title, b.dependents);
+        }
+    }
+    if !report.suggestions.is_empty() {
+        println!();
+        println!("Suggested priority bumps:");
+        for s in &report.suggestions {
+            println!(
+                "  ralph task update {} --priority {}   # {}, now {}",
+                s.id, s.to, s.title, s.from
+            );
+        }
+    }
+}
+
+/// Whole minutes from a minute up, seconds below.
+fn format_estimate(secs: f64) -> String {
+    if secs >= 60.0 {
+        format!("{:.0}m", secs / 60.0)
+    } else {
+        format!("{secs:.0}s")
+    }
+}
+
+/// ` #a #b` for a task list or tree line; empty without labels.
+fn format_labels(labels: &[String]) -> String {
+    labels.iter().map(|l| format!(" #{l}")).collect()
+}
+
+fn print_task_tree(
+    tree: &[dag::Task],
+    current_id: &str,
+    labels: &std::collections::HashMap<String, Vec<String>>,
+    prefix: &str,
+    is_last: bool,
+) {
     // Find the current task
     let task = match tree.iter().find(|t| t.id == current_id) {
         Some(t) => t,
@@ -1302,15 +3518,22 @@ fn print_task_tree(tree: &[dag::Task], current_id: &str, prefix: &str, is_last:
     };
 
     let status_display = colorize_status(&task.status);
+    let label_display = labels
+        .get(&task.id)
+        .map(|l| format_labels(l).dimmed().to_string())
+        .unwrap_or_default();
 
     if prefix.is_empty() {
         // Root node
-        println!("{}  [{}]  {}", task.id, status_display, task.title);
+        println!(
+            "{}  [{}]  {}{}",
+            task.id, status_display, task.title, label_display
+        );
     } else {
         let connector = if is_last { "└─" } else { "├─" };
         println!(
-            "{}{} {}  [{}]  {}",
-            prefix, connector, task.id, status_display, task.title
+            "{}{} {}  [{}]  {}{}",
+            prefix, connector, task.id, status_display, task.title, label_display
         );
     }
 
@@ -1337,19 +3560,24 @@ fn print_task_tree(tree: &[dag::Task], current_id: &str, prefix: &str, is_last:
             child_prefix
         };
 
-        print_task_tree(tree, &child.id, &next_prefix, is_last_child);
+        print_task_tree(tree, &child.id, labels, &next_prefix, is_last_child);
     }
 }
 
-fn render_task_tree_lines(tree: &[dag::Task], current_id: &str) -> Vec<String> {
+fn render_task_tree_lines(
+    tree: &[dag::Task],
+    current_id: &str,
+    labels: &std::collections::HashMap<String, Vec<String>>,
+) -> Vec<String> {
     let mut lines = Vec::new();
-    render_task_tree_lines_inner(tree, current_id, "", true, &mut lines);
+    render_task_tree_lines_inner(tree, current_id, labels, "", true, &mut lines);
     lines
 }
 
 fn render_task_tree_lines_inner(
     tree: &[dag::Task],
     current_id: &str,
+    labels: &std::collections::HashMap<String, Vec<String>>,
     prefix: &str,
     is_last: bool,
     out: &mut Vec<String>,
@@ -1359,13 +3587,20 @@ fn render_task_tree_lines_inner(
         None => return,
     };
 
+    let label_display = labels
+        .get(&task.id)
+        .map(|l| format_labels(l))
+        .unwrap_or_default();
     if prefix.is_empty() {
-        out.push(format!("{}  [{}]  {}", task.id, task.status, task.title));
+        out.push(format!(
+            "{}  [{}]  {}{}",
+            task.id, task.status, task.title, label_display
+        ));
     } else {
         let connector = if is_last { "└─" } else { "├─" };
         out.push(format!(
-            "{}{} {}  [{}]  {}",
-            prefix, connector, task.id, task.status, task.title
+            "{}{} {}  [{}]  {}{}",
+            prefix, connector, task.id, task.status, task.title, label_display
         ));
     }
 
@@ -1388,14 +3623,16 @@ fn render_task_tree_lines_inner(
         } else {
             child_prefix
         };
-        render_task_tree_lines_inner(tree, &child.id, &next_prefix, is_last_child, out);
+        render_task_tree_lines_inner(tree, &child.id, labels, &next_prefix, is_last_child, out);
     }
 }
 
 pub(crate) use feature_prompts::{
     build_feature_build_system_prompt, build_feature_plan_system_prompt,
-    build_feature_spec_system_prompt, build_initial_message_plan, build_initial_message_spec,
-    build_initial_message_task_new, build_task_new_system_prompt, gather_project_context,
+    build_feature_reconcile_system_prompt, build_feature_replan_system_prompt,
+    build_feature_spec_system_prompt, build_initial_message_build, build_initial_message_plan,
+    build_initial_message_replan, build_initial_message_spec, build_initial_message_task_new,
+    build_task_new_system_prompt, gather_project_context,
 };
 
 #[cfg(test)]
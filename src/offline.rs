@@ -0,0 +1,140 @@
+//! `ralph run --offline`: runs for restricted, air-gapped environments.
+//!
+//! Offline mode refuses any agent that is not marked `local = true` in
+//! `[agent]` or its `[agents.<name>]` profile, skips webhook notifications,
+//! and refuses terminal commands that look like outbound network access.
+//! Detection is a list of regexes over the command line (`[offline]
+//! network_patterns`); it catches the usual tools, not every way a program
+//! can open a socket, so it complements the agent's own sandbox.
+
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+use crate::project::RalphConfig;
+
+static NETWORK_PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+
+/// `[offline]` section of `.ralph.toml`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OfflineConfig {
+    /// Regexes over terminal command lines that count as network access.
+    /// Setting the list replaces the defaults.
+    #[serde(default = "default_network_patterns")]
+    pub network_patterns: Vec<String>,
+}
+
+impl Default for OfflineConfig {
+    fn default() -> Self {
+        Self {
+            network_patterns: default_network_patterns(),
+        }
+    }
+}
+
+fn default_network_patterns() -> Vec<String> {
+    [
+        r"\b(curl|wget|ssh|scp|sftp|rsync|telnet|ftp|nc|ncat)\b",
+        r"\b(https?|ftp)://",
+        r"\bgit\s+(clone|fetch|pull|push|ls-remote)\b",
+        r"\b(npm|pnpm|yarn)\s+(install|i|add|ci|publish)\b",
+        r"\bpip3?\s+(install|download)\b",
+        r"\bcargo\s+(install|publish|fetch|update)\b",
+        r"\bgo\s+(get|install)\b",
+        r"\bdocker\s+(pull|push|login)\b",
+    ]
+    .iter()
+    .map(|p| p.to_string())
+    .collect()
+}
+
+/// Compile `[offline] network_patterns`, naming the first invalid one.
+pub fn compile(config: &OfflineConfig) -> Result<Vec<Regex>> {
+    config
+        .network_patterns
+        .iter()
+        .map(|p| Regex::new(p).with_context(|| format!("Invalid network pattern '{p}'")))
+        .collect()
+}
+
+/// Turn offline mode on for the rest of the process. Later calls are ignored.
+pub fn enable(config: &OfflineConfig) -> Result<()> {
+    let _ = NETWORK_PATTERNS.set(compile(config)?);
+    Ok(())
+}
+
+/// Whether `--offline` is in effect.
+pub fn is_enabled() -> bool {
+    NETWORK_PATTERNS.get().is_some()
+}
+
+/// The pattern `command` matches when offline mode is on and it looks like
+/// network access; `None` lets the command run.
+pub fn network_access(command: &str) -> Option<&'static str> {
+    NETWORK_PATTERNS
+        .get()?
+        .iter()
+        .find(|p| p.is_match(command))
+        .map(Regex::as_str)
+}
+
+/// Fail unless `agent_command` is an agent marked `local = true`: the
+/// `[agent]` command, or the command of an `[agents.<name>]` profile.
+pub fn check_agent(config: &RalphConfig, agent_command: &str) -> Result<()> {
+    let default_local = config.agent.local && config.agent.command == agent_command;
+    // A profile without `command` runs the binary named after it
+    let profile_local = config
+        .agents
+        .iter()
+        .any(|(name, p)| p.local && p.command.as_deref().unwrap_or(name) == agent_command);
+    if !default_local && !profile_local {
+        bail!(
+            "--offline only runs agents marked local: add `local = true` to the [agent] \
+             section or the [agents.<name>] profile for '{agent_command}' in .ralph.toml"
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(command: &str) -> bool {
+        let patterns = compile(&OfflineConfig::default()).unwrap();
+        patterns.iter().any(|p| p.is_match(command))
+    }
+
+    #[test]
+    fn default_patterns_flag_network_commands() {
+        assert!(matches("curl -sSL https://example.com/install.sh"));
+        assert!(matches("git push origin main"));
+        assert!(matches("npm install left-pad"));
+        assert!(matches("pip install requests"));
+        assert!(matches("python fetch.py http://10.0.0.1/data"));
+
+        assert!(!matches("cargo test --workspace"));
+        assert!(!matches("git status"));
+        assert!(!matches("npm test"));
+        assert!(!matches("grep -rn function src"));
+    }
+
+    #[test]
+    fn check_agent_requires_local_flag() {
+        let config: RalphConfig = toml::from_str(
+            "[agent]\ncommand = \"claude-agent-acp\"\n\n\
+             [agents.llama]\ncommand = \"llama-acp --model q4\"\nlocal = true\n\n\
+             [agents.ollama-acp]\nlocal = true\n",
+        )
+        .unwrap();
+        assert!(check_agent(&config, "llama-acp --model q4").is_ok());
+        assert!(check_agent(&config, "ollama-acp").is_ok());
+        assert!(check_agent(&config, "claude-agent-acp").is_err());
+        assert!(check_agent(&config, "other-agent").is_err());
+
+        let config: RalphConfig =
+            toml::from_str("[agent]\ncommand = \"local-agent\"\nlocal = true\n").unwrap();
+        assert!(check_agent(&config, "local-agent").is_ok());
+    }
+}
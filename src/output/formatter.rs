@@ -3,30 +3,43 @@
 use colored::Colorize;
 use std::process::Command;
 
+use crate::ci;
 use crate::config::Config;
 use crate::ui::{self, EventLine, UiEvent};
 
+/// True when human-readable lines should go to the terminal.
+///
+/// False while the TUI owns the screen, and in CI mode where stdout is
+/// reserved for the JSONL event stream.
+fn plain_output() -> bool {
+    !ui::is_active() && !ci::is_enabled()
+}
+
 /// Print a plain info line.
 ///
 /// When the TUI is active these are silently dropped — the dashboard panels
 /// (status line, DAG summary, tool activity, agent stream) carry all the
 /// information the operator needs.
 pub fn print_info(message: &str) {
-    if !ui::is_active() {
+    if plain_output() {
         println!("{message}");
     }
 }
 
 /// Print a warning line.
 pub fn print_warning(message: &str) {
-    if !ui::is_active() {
+    if ci::is_enabled() {
+        ci::emit_event("warning", message, false);
+    } else if plain_output() {
         eprintln!("{}", message.yellow());
     }
 }
 
 /// Print an error line.
 pub fn print_error(message: &str) {
-    if !ui::is_active() {
+    if ci::is_enabled() {
+        ci::emit_event("error", message, true);
+    } else if plain_output() {
         eprintln!("{}", message.red());
     }
 }
@@ -35,6 +48,8 @@ pub fn print_error(message: &str) {
 pub fn print_iteration_info(config: &Config) {
     let line = if config.limit == 1 {
         "Running once only".to_string()
+    } else if config.total == 0 && config.auto_limit.is_some() {
+        format!("Iteration {} (limit: auto)", config.iteration)
     } else if config.total == 0 {
         format!("Iteration {} (unlimited)", config.iteration)
     } else {
@@ -46,7 +61,7 @@ pub fn print_iteration_info(config: &Config) {
             "{line} | model={} | strategy={}",
             config.current_model, config.model_strategy
         )));
-    } else {
+    } else if plain_output() {
         println!("{line}");
     }
 }
@@ -56,11 +71,26 @@ pub fn print_dag_summary(total: usize, ready: usize, done: usize, blocked: usize
     let line = format!("DAG: {total} tasks, {ready} ready, {done} done, {blocked} blocked");
     if ui::is_active() {
         ui::emit(UiEvent::DagSummary(line.clone()));
-    } else {
+    } else if plain_output() {
         println!("{line}");
     }
 }
 
+/// Show burn-down lines in the TUI's Progress panel. Plain output skips
+/// them; `ralph stats` prints the same charts.
+pub fn print_progress(lines: Vec<String>) {
+    if ui::is_active() {
+        ui::emit(UiEvent::Progress(lines));
+    }
+}
+
+/// Point the TUI's History panel at this project's run logs.
+pub fn announce_run_logs(project_root: &std::path::Path) {
+    if ui::is_active() {
+        ui::emit(UiEvent::RunLogs(project_root.to_path_buf()));
+    }
+}
+
 /// Print completion message.
 pub fn print_complete() {
     if ui::is_active() {
@@ -89,7 +119,7 @@ pub fn print_limit_reached() {
 
 /// Print iteration separator.
 pub fn print_separator() {
-    if !ui::is_active() {
+    if plain_output() {
         let width = terminal_width();
         println!("{}", "-".repeat(width).dimmed());
     }
@@ -97,14 +127,14 @@ pub fn print_separator() {
 
 /// Print a clickable file hyperlink.
 pub fn hyperlink(path: &str) {
-    if !ui::is_active() {
+    if plain_output() {
         println!("\x1b]8;;file://{}\x1b\\{}\x1b]8;;\x1b\\", path, path);
     }
 }
 
 /// Print a file location line with label.
 pub fn print_log_location(label: &str, path: &str) {
-    if !ui::is_active() {
+    if plain_output() {
         println!("{label}");
         hyperlink(path);
     }
@@ -112,7 +142,7 @@ pub fn print_log_location(label: &str, path: &str) {
 
 /// Print verification start message.
 pub fn print_verification_start(iteration: u32, task_id: &str) {
-    if !ui::is_active() {
+    if plain_output() {
         println!(
             "[iter {}] {} {}",
             iteration,
@@ -124,7 +154,7 @@ pub fn print_verification_start(iteration: u32, task_id: &str) {
 
 /// Print verification passed message.
 pub fn print_verification_passed(iteration: u32, task_id: &str) {
-    if !ui::is_active() {
+    if plain_output() {
         println!(
             "[iter {}] {} (verified): {}",
             iteration,
@@ -136,7 +166,7 @@ pub fn print_verification_passed(iteration: u32, task_id: &str) {
 
 /// Print verification failed message.
 pub fn print_verification_failed(iteration: u32, task_id: &str, reason: &str) {
-    if !ui::is_active() {
+    if plain_output() {
         println!(
             "[iter {}] {} verification: {} — {}",
             iteration,
@@ -149,7 +179,7 @@ pub fn print_verification_failed(iteration: u32, task_id: &str, reason: &str) {
 
 /// Print retry message.
 pub fn print_retry(iteration: u32, task_id: &str, attempt: i32, max: i32) {
-    if !ui::is_active() {
+    if plain_output() {
         println!(
             "[iter {}] Retrying {} (attempt {}/{})",
             iteration,
@@ -162,7 +192,7 @@ pub fn print_retry(iteration: u32, task_id: &str, attempt: i32, max: i32) {
 
 /// Print max retries exhausted message.
 pub fn print_max_retries_exhausted(iteration: u32, task_id: &str) {
-    if !ui::is_active() {
+    if plain_output() {
         println!(
             "[iter {}] {} (max retries exhausted): {}",
             iteration,
@@ -174,7 +204,7 @@ pub fn print_max_retries_exhausted(iteration: u32, task_id: &str) {
 
 /// Print task done message.
 pub fn print_task_done(iteration: u32, task_id: &str) {
-    if !ui::is_active() {
+    if plain_output() {
         println!(
             "[iter {}] {}: {}",
             iteration,
@@ -186,7 +216,7 @@ pub fn print_task_done(iteration: u32, task_id: &str) {
 
 /// Print task failed message.
 pub fn print_task_failed(iteration: u32, task_id: &str) {
-    if !ui::is_active() {
+    if plain_output() {
         println!(
             "[iter {}] {}: {}",
             iteration,
@@ -198,7 +228,7 @@ pub fn print_task_failed(iteration: u32, task_id: &str) {
 
 /// Print task incomplete (no sigil) message.
 pub fn print_task_incomplete(iteration: u32, task_id: &str) {
-    if !ui::is_active() {
+    if plain_output() {
         println!(
             "[iter {}] Incomplete (no sigil): {}",
             iteration,
@@ -207,6 +237,13 @@ pub fn print_task_incomplete(iteration: u32, task_id: &str) {
     }
 }
 
+/// Print a task queue change requested by the agent's queue sigils.
+pub fn print_queue_change(iteration: u32, summary: &str) {
+    if plain_output() {
+        println!("[iter {}] Queue: {}", iteration, summary);
+    }
+}
+
 /// Emit an iteration divider into the agent stream panel (TUI only).
 ///
 /// In the TUI, this inserts a visual line separator between iterations.
@@ -217,23 +254,27 @@ pub fn emit_iteration_divider(iteration: u32) {
     }
 }
 
-/// Print task working message.
-pub fn print_task_working(iteration: u32, task_id: &str, title: &str) {
+/// Print task working message, with the task's labels as `#label` tags.
+pub fn print_task_working(iteration: u32, task_id: &str, title: &str, labels: &[String]) {
+    let tags: String = labels.iter().map(|l| format!(" #{l}")).collect();
     if ui::is_active() {
-        ui::emit(UiEvent::CurrentTask(format!("Task: {task_id} — {title}")));
-    } else {
+        ui::emit(UiEvent::CurrentTask(format!(
+            "Task: {task_id} — {title}{tags}"
+        )));
+    } else if plain_output() {
         println!(
-            "[iter {}] Working on: {} -- {}",
+            "[iter {}] Working on: {} -- {}{}",
             iteration,
             task_id.cyan(),
-            title
+            title,
+            tags.dimmed()
         );
     }
 }
 
 /// Print review loop start message.
 pub fn print_review_start(kind: &str, feature_name: &str) {
-    if !ui::is_active() {
+    if plain_output() {
         let line = format!("Reviewing {kind} for '{feature_name}'...");
         println!("\n{}", line.cyan());
     }
@@ -241,7 +282,7 @@ pub fn print_review_start(kind: &str, feature_name: &str) {
 
 /// Print review round start message.
 pub fn print_review_round(round: u32, max: u32, kind: &str) {
-    if !ui::is_active() {
+    if plain_output() {
         let line = format!("{kind} review round {round}/{max}");
         println!("  {} {}", line.cyan(), "→".dimmed());
     }
@@ -273,7 +314,7 @@ pub fn print_review_result(round: u32, passed: bool, changes_summary: &str, kind
 
 /// Print review complete message.
 pub fn print_review_complete(kind: &str, feature_name: &str, rounds: u32) {
-    if !ui::is_active() {
+    if plain_output() {
         let rounds_text = if rounds == 1 {
             "1 round".to_string()
         } else {
@@ -287,7 +328,7 @@ pub fn print_review_complete(kind: &str, feature_name: &str, rounds: u32) {
 
 /// Print review max rounds reached message.
 pub fn print_review_max_rounds(kind: &str, feature_name: &str, max: u32) {
-    if !ui::is_active() {
+    if plain_output() {
         let line = format!("Review limit: '{feature_name}' {kind} stabilized after {max} rounds.");
         println!("{}", line.yellow());
     }
@@ -295,7 +336,7 @@ pub fn print_review_max_rounds(kind: &str, feature_name: &str, max: u32) {
 
 /// Print interrupted message.
 pub fn print_interrupted(iteration: u32, task_id: &str, title: &str) {
-    if !ui::is_active() {
+    if plain_output() {
         println!(
             "\n[iter {}] {} {} — \"{}\"",
             iteration,
@@ -306,8 +347,13 @@ pub fn print_interrupted(iteration: u32, task_id: &str, title: &str) {
     }
 }
 
-/// Emit a structured event to the Events panel (TUI) or stderr (plain mode).
+/// Emit a structured event to the Events panel (TUI), stdout as JSONL (CI mode),
+/// or stderr (plain mode).
 pub fn emit_event(category: &str, message: &str, is_error: bool) {
+    if ci::is_enabled() {
+        ci::emit_event(category, message, is_error);
+        return;
+    }
     let timestamp = chrono::Local::now().format("%H:%M:%S").to_string();
     if ui::is_active() {
         ui::emit(UiEvent::Event(EventLine {
@@ -334,18 +380,21 @@ pub fn emit_event_info(category: &str, message: &str) {
 /// Map category names to `colored` crate colors for plain-mode stderr output.
 fn color_category_plain(category: &str) -> colored::ColoredString {
     match category {
-        "task" => category.cyan(),
+        "task" | "queue" => category.cyan(),
         "iter" => category.yellow(),
         "feature" => category.magenta(),
         "verify" | "review" => category.green(),
         "journal" | "knowledge" => category.blue(),
-        "interrupt" => category.red(),
+        "interrupt" | "steer" => category.red(),
         "dag" | "config" => category.dimmed(),
         _ => category.normal(),
     }
 }
 
 fn speak(message: &str) {
+    if ci::is_enabled() {
+        return;
+    }
     if Command::new("which").arg("say").output().is_ok() {
         let msg = message.to_string();
         std::thread::spawn(move || {
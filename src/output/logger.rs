@@ -1,29 +1,207 @@
-//! Raw JSON logging for debugging.
+//! Run logs: the transcript of what the agent said in each iteration (and,
+//! with `capture_thoughts`, what it thought).
+//!
+//! Each run writes to its own directory, `<tmp>/ralph/logs/<project>/<run-id>/`.
+//! Transcripts are appended to `run.log`; once it would grow past
+//! `[logging] rotate_mb` it is renamed to `run.<n>.log` and a fresh file is
+//! started. When the run ends its files are gzipped, and run directories older
+//! than `[logging] retention` days are deleted as the next run starts.
 
-use chrono::Utc;
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
 use std::env;
-use std::path::PathBuf;
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
-/// Set up the log directory and return the log file path.
-pub fn setup_log_file() -> String {
-    let log_dir = log_directory();
-    std::fs::create_dir_all(&log_dir).ok();
+use crate::project::LoggingConfig;
 
-    let timestamp = Utc::now().format("%Y%m%d-%H%M%S").to_string();
-    let log_path = log_dir.join(format!("{}.log", timestamp));
+/// The file a running run appends to.
+const CURRENT_LOG: &str = "run.log";
 
-    log_path.to_string_lossy().to_string()
+/// Log directory of one run.
+pub struct RunLog {
+    dir: PathBuf,
+    rotate_bytes: u64,
 }
 
-/// Get the log directory path.
-pub fn log_directory() -> PathBuf {
+impl RunLog {
+    /// Create the directory for `run_id`, first deleting runs past retention.
+    pub fn start(project_root: &Path, run_id: &str, config: &LoggingConfig) -> Result<Self> {
+        let root = log_directory(project_root);
+        if config.retention > 0 {
+            let max_age = Duration::from_secs(u64::from(config.retention) * 24 * 60 * 60);
+            prune_runs(&root, max_age, SystemTime::now());
+        }
+        let dir = root.join(run_id);
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        Ok(Self {
+            dir,
+            rotate_bytes: config.rotate_mb * 1024 * 1024,
+        })
+    }
+
+    /// The file transcripts are currently appended to.
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(CURRENT_LOG)
+    }
+
+    /// Append an iteration transcript: a heading, the thought stream when
+    /// captured, steering messages the user sent, then the agent's answer.
+    pub fn append_transcript(
+        &self,
+        heading: &str,
+        response: &str,
+        thoughts: Option<&str>,
+        steering: &[String],
+    ) -> Result<()> {
+        let path = self.path();
+        let mut entry = crate::terminal_env::current()
+            .mask(&render_transcript(heading, response, thoughts, steering));
+        let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        if size > 0 {
+            if self.rotate_bytes > 0 && size + entry.len() as u64 > self.rotate_bytes {
+                let rotated = self
+                    .dir
+                    .join(format!("run.{}.log", rotated_count(&self.dir) + 1));
+                fs::rename(&path, &rotated)
+                    .with_context(|| format!("Failed to rotate {}", path.display()))?;
+            } else {
+                entry.insert(0, '\n');
+            }
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(entry.as_bytes()))
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Gzip the run's log files, or remove the directory if nothing was logged.
+    pub fn finish(&self) -> Result<()> {
+        let files = run_files(&self.dir);
+        if files.is_empty() {
+            fs::remove_dir(&self.dir).ok();
+            return Ok(());
+        }
+        for path in files.iter().filter(|p| !is_gzipped(p)) {
+            let gz = PathBuf::from(format!("{}.gz", path.display()));
+            let mut encoder = GzEncoder::new(
+                fs::File::create(&gz)
+                    .with_context(|| format!("Failed to create {}", gz.display()))?,
+                flate2::Compression::default(),
+            );
+            encoder.write_all(&fs::read(path)?)?;
+            encoder.finish()?;
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+/// A run that has a log directory.
+#[derive(Debug)]
+pub struct RunSummary {
+    pub run_id: String,
+    pub modified: SystemTime,
+    /// Bytes on disk, compressed or not.
+    pub bytes: u64,
+    /// `false` once the run's files have been gzipped.
+    pub active: bool,
+}
+
+/// Runs with logs, newest first.
+pub fn list_runs(project_root: &Path) -> Vec<RunSummary> {
+    let Ok(entries) = fs::read_dir(log_directory(project_root)) else {
+        return Vec::new();
+    };
+    let mut runs: Vec<RunSummary> = entries
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .map(|e| {
+            let files = run_files(&e.path());
+            RunSummary {
+                run_id: e.file_name().to_string_lossy().into_owned(),
+                modified: modified(&e.path()),
+                bytes: files
+                    .iter()
+                    .filter_map(|f| fs::metadata(f).ok())
+                    .map(|m| m.len())
+                    .sum(),
+                active: files.iter().any(|f| !is_gzipped(f)),
+            }
+        })
+        .collect();
+    runs.sort_by_key(|run| std::cmp::Reverse(run.modified));
+    runs
+}
+
+/// Directory holding the logs of `run_id`.
+pub fn run_directory(project_root: &Path, run_id: &str) -> PathBuf {
+    log_directory(project_root).join(run_id)
+}
+
+/// Everything a run logged, oldest first, decompressing finished files.
+pub fn read_run(dir: &Path) -> Result<String> {
+    let mut text = String::new();
+    for path in run_files(dir) {
+        let file =
+            fs::File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+        if is_gzipped(&path) {
+            GzDecoder::new(file).read_to_string(&mut text)?;
+        } else {
+            std::io::BufReader::new(file).read_to_string(&mut text)?;
+        }
+    }
+    Ok(text)
+}
+
+/// Copy what is appended to a run's current log to `out` until the run
+/// finishes (its log is gzipped). `offset` is how much was already shown.
+pub fn follow(dir: &Path, mut offset: u64, out: &mut dyn Write) -> Result<()> {
+    let path = dir.join(CURRENT_LOG);
+    while path.exists() {
+        let mut file = match fs::File::open(&path) {
+            Ok(file) => file,
+            Err(_) => break,
+        };
+        let len = file.metadata()?.len();
+        if len < offset {
+            // Rotated: the new file starts from scratch
+            offset = 0;
+        }
+        if len > offset {
+            std::io::Seek::seek(&mut file, std::io::SeekFrom::Start(offset))?;
+            let mut chunk = Vec::new();
+            file.read_to_end(&mut chunk)?;
+            out.write_all(&chunk)?;
+            out.flush()?;
+            offset += chunk.len() as u64;
+        }
+        std::thread::sleep(Duration::from_millis(500));
+    }
+    Ok(())
+}
+
+/// Size of the current (uncompressed) log, or 0.
+pub fn current_len(dir: &Path) -> u64 {
+    fs::metadata(dir.join(CURRENT_LOG))
+        .map(|m| m.len())
+        .unwrap_or(0)
+}
+
+/// Root of this project's run logs.
+pub fn log_directory(project_root: &Path) -> PathBuf {
     let base_dir = env::var("TMPDIR")
         .or_else(|_| env::var("XDG_RUNTIME_DIR"))
         .unwrap_or_else(|_| "/tmp".to_string());
 
-    let project_name = env::current_dir()
-        .ok()
-        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+    let project_name = project_root
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
         .unwrap_or_else(|| "unknown".to_string());
 
     PathBuf::from(base_dir)
@@ -31,3 +209,165 @@ pub fn log_directory() -> PathBuf {
         .join("logs")
         .join(project_name)
 }
+
+/// `run.1.log … run.<n>.log`, then `run.log`, each possibly gzipped.
+fn run_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<(u32, PathBuf)> = entries
+        .flatten()
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            let stem = name.strip_suffix(".gz").unwrap_or(&name);
+            let order = if stem == CURRENT_LOG {
+                u32::MAX
+            } else {
+                stem.strip_prefix("run.")?
+                    .strip_suffix(".log")?
+                    .parse()
+                    .ok()?
+            };
+            Some((order, e.path()))
+        })
+        .collect();
+    files.sort();
+    files.into_iter().map(|(_, path)| path).collect()
+}
+
+fn rotated_count(dir: &Path) -> usize {
+    run_files(dir)
+        .iter()
+        .filter(|p| !p.ends_with(CURRENT_LOG))
+        .count()
+}
+
+fn is_gzipped(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "gz")
+}
+
+fn modified(path: &Path) -> SystemTime {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+/// Delete run directories (and loose files from older ralph versions) not
+/// modified within `max_age`.
+fn prune_runs(root: &Path, max_age: Duration, now: SystemTime) {
+    let Ok(entries) = fs::read_dir(root) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let age = now.duration_since(modified(&path)).unwrap_or_default();
+        if age <= max_age {
+            continue;
+        }
+        if path.is_dir() {
+            fs::remove_dir_all(&path).ok();
+        } else {
+            fs::remove_file(&path).ok();
+        }
+    }
+}
+
+fn render_transcript(
+    heading: &str,
+    response: &str,
+    thoughts: Option<&str>,
+    steering: &[String],
+) -> String {
+    let mut transcript = format!("# {heading}\n");
+    if let Some(thoughts) = thoughts.map(str::trim).filter(|t| !t.is_empty()) {
+        transcript.push_str(&format!("\n## Thoughts\n\n{thoughts}\n"));
+    }
+    if !steering.is_empty() {
+        transcript.push_str("\n## Steering\n\n");
+        for message in steering {
+            transcript.push_str(&format!("- {}\n", message.replace('\n', "\n  ")));
+        }
+    }
+    transcript.push_str(&format!("\n## Response\n\n{}\n", response.trim()));
+    transcript
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn transcript_includes_thoughts_only_when_captured() {
+        let with = render_transcript("t-1 Task", "Done.\n", Some("Check the tests.\n"), &[]);
+        assert_eq!(
+            with,
+            "# t-1 Task\n\n## Thoughts\n\nCheck the tests.\n\n## Response\n\nDone.\n"
+        );
+        let without = render_transcript("t-1 Task", "Done.", None, &[]);
+        assert!(!without.contains("## Thoughts"));
+        assert_eq!(
+            render_transcript("t-1 Task", "Done.", Some(" "), &[]),
+            without
+        );
+    }
+
+    #[test]
+    fn transcript_lists_steering_messages() {
+        let transcript = render_transcript(
+            "t-1 Task",
+            "Done.",
+            None,
+            &["Use the retry helper".to_string()],
+        );
+        assert_eq!(
+            transcript,
+            "# t-1 Task\n\n## Steering\n\n- Use the retry helper\n\n## Response\n\nDone.\n"
+        );
+    }
+
+    #[test]
+    fn run_log_rotates_gzips_and_reads_back_in_order() {
+        let dir = TempDir::new().unwrap();
+        let log = RunLog {
+            dir: dir.path().join("run-00000001"),
+            rotate_bytes: 100,
+        };
+        fs::create_dir_all(&log.dir).unwrap();
+        for i in 1..=3 {
+            log.append_transcript(&format!("iteration {i}"), &"x".repeat(40), None, &[])
+                .unwrap();
+        }
+        let names = |dir: &Path| -> Vec<String> {
+            run_files(dir)
+                .iter()
+                .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+                .collect()
+        };
+        assert_eq!(names(&log.dir), vec!["run.1.log", "run.2.log", "run.log"]);
+
+        log.finish().unwrap();
+        assert_eq!(
+            names(&log.dir),
+            vec!["run.1.log.gz", "run.2.log.gz", "run.log.gz"]
+        );
+        let text = read_run(&log.dir).unwrap();
+        let headings: Vec<&str> = text.lines().filter(|l| l.starts_with("# ")).collect();
+        assert_eq!(
+            headings,
+            vec!["# iteration 1", "# iteration 2", "# iteration 3"]
+        );
+    }
+
+    #[test]
+    fn prune_removes_only_expired_runs() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("run-old")).unwrap();
+        let day = Duration::from_secs(24 * 60 * 60);
+
+        prune_runs(dir.path(), day, SystemTime::now());
+        assert!(dir.path().join("run-old").exists());
+        prune_runs(dir.path(), day, SystemTime::now() + day * 2);
+        assert!(!dir.path().join("run-old").exists());
+    }
+}
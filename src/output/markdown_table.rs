@@ -0,0 +1,153 @@
+//! Markdown table detection and column layout for agent output.
+//!
+//! A table is a header row, a delimiter row (`|---|:--:|`) and any number of
+//! body rows, each starting with `|`. Body rows are cut or padded to the
+//! header's column count, as GitHub renders them. Widths count characters of
+//! the cell source, so inline markup that renderers keep (`**bold**`,
+//! `` `code` ``) stays aligned.
+
+/// Horizontal alignment of a column, from its delimiter cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// A parsed markdown table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub header: Vec<String>,
+    pub align: Vec<Align>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Width of each column: its widest cell, at least 3 like `---`.
+    pub fn widths(&self) -> Vec<usize> {
+        let mut widths = vec![3; self.header.len()];
+        for row in std::iter::once(&self.header).chain(&self.rows) {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        widths
+    }
+}
+
+/// Whether `line` can be a table row: it starts with `|` after indentation.
+pub fn is_row(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.len() > 1 && trimmed.starts_with('|')
+}
+
+/// Whether `line` is a delimiter row such as `| --- | :---: | --: |`.
+pub fn is_delimiter(line: &str) -> bool {
+    is_row(line)
+        && split_row(line).iter().all(|cell| {
+            let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+            !dashes.is_empty() && dashes.chars().all(|c| c == '-')
+        })
+}
+
+/// The trimmed cells of a row. `\|` is a literal pipe inside a cell.
+pub fn split_row(line: &str) -> Vec<String> {
+    let trimmed = line.trim();
+    let inner = trimmed.strip_prefix('|').unwrap_or(trimmed);
+    let inner = match inner.strip_suffix('|') {
+        Some(rest) if !rest.ends_with('\\') => rest,
+        _ => inner,
+    };
+
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push('|');
+                chars.next();
+            }
+            '|' => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+/// Parse `lines` as one table, or `None` if they do not form one.
+pub fn parse(lines: &[&str]) -> Option<Table> {
+    let (header, rest) = lines.split_first()?;
+    let (delimiter, body) = rest.split_first()?;
+    if !is_row(header) || !is_delimiter(delimiter) {
+        return None;
+    }
+    let header = split_row(header);
+    let align: Vec<Align> = split_row(delimiter)
+        .iter()
+        .map(|cell| match (cell.starts_with(':'), cell.ends_with(':')) {
+            (true, true) => Align::Center,
+            (false, true) => Align::Right,
+            _ => Align::Left,
+        })
+        .collect();
+    if align.len() != header.len() {
+        return None;
+    }
+    let rows = body
+        .iter()
+        .map(|line| {
+            let mut cells = split_row(line);
+            cells.resize(header.len(), String::new());
+            cells
+        })
+        .collect();
+    Some(Table {
+        header,
+        align,
+        rows,
+    })
+}
+
+/// Spaces before and after `cell` to fill `width` with `align`.
+pub fn padding(cell: &str, width: usize, align: Align) -> (usize, usize) {
+    let fill = width.saturating_sub(cell.chars().count());
+    match align {
+        Align::Left => (0, fill),
+        Align::Right => (fill, 0),
+        Align::Center => (fill / 2, fill - fill / 2),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_table() {
+        let lines = [
+            "| Name | Count | Note |",
+            "|:-----|------:|:----:|",
+            "| `a\\|b` | 12 |",
+            "| long name | 3 | x | extra |",
+        ];
+        let table = parse(&lines).unwrap();
+        assert_eq!(table.header, vec!["Name", "Count", "Note"]);
+        assert_eq!(table.align, vec![Align::Left, Align::Right, Align::Center]);
+        assert_eq!(table.rows[0], vec!["`a|b`", "12", ""]);
+        assert_eq!(table.rows[1], vec!["long name", "3", "x"]);
+        assert_eq!(table.widths(), vec![9, 5, 4]);
+        assert_eq!(padding("12", 5, Align::Right), (3, 0));
+        assert_eq!(padding("x", 4, Align::Center), (1, 2));
+    }
+
+    #[test]
+    fn test_parse_rejects_non_tables() {
+        assert!(parse(&["| a | b |", "| c | d |"]).is_none());
+        assert!(parse(&["| a | b |", "|---|"]).is_none());
+        assert!(parse(&["| a |"]).is_none());
+        assert!(!is_row("|"));
+        assert!(!is_delimiter("| - | x |"));
+    }
+}
@@ -2,3 +2,5 @@
 
 pub mod formatter;
 pub mod logger;
+pub mod markdown_table;
+pub mod notify;
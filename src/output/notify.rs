@@ -0,0 +1,275 @@
+//! External notifications for key run events (`[notifications]` in `.ralph.toml`).
+//!
+//! Two channels are built in: desktop notifications (notify-rust) and a JSON
+//! webhook POST. Both are off until configured. Delivery is best-effort: a
+//! failure prints a warning and never stops the run.
+
+use std::collections::BTreeMap;
+use std::sync::mpsc;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::output::formatter;
+
+/// How long a webhook POST may take before it is abandoned.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+static CONFIG: OnceLock<NotificationsConfig> = OnceLock::new();
+
+/// Queue of the background thread that posts webhooks.
+static WEBHOOKS: OnceLock<mpsc::Sender<Delivery>> = OnceLock::new();
+
+/// Work for the webhook thread.
+enum Delivery {
+    Post {
+        url: String,
+        body: serde_json::Value,
+    },
+    /// Reply once everything queued before it has been sent.
+    Flush(mpsc::Sender<()>),
+}
+
+/// Events that can trigger a notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyEvent {
+    /// The run finished with every targeted task resolved.
+    RunComplete,
+    /// A task failed with no retries left.
+    TaskFailed,
+    /// The run stopped because no remaining task is ready.
+    Blocked,
+    /// The run stopped on its iteration limit or wall-clock timeout.
+    BudgetExceeded,
+}
+
+impl NotifyEvent {
+    fn label(self) -> &'static str {
+        match self {
+            NotifyEvent::RunComplete => "Run complete",
+            NotifyEvent::TaskFailed => "Task failed",
+            NotifyEvent::Blocked => "Run blocked",
+            NotifyEvent::BudgetExceeded => "Budget exceeded",
+        }
+    }
+}
+
+/// A delivery channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Channel {
+    Desktop,
+    Webhook,
+}
+
+/// `[notifications]` section.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct NotificationsConfig {
+    /// Show desktop notifications.
+    #[serde(default)]
+    pub desktop: bool,
+    /// URL that receives a JSON POST per event.
+    #[serde(default)]
+    pub webhook: Option<String>,
+    /// Per-event channel lists (`[notifications.events]`). Events not listed
+    /// use every enabled channel; an empty list silences the event.
+    #[serde(default)]
+    pub events: BTreeMap<NotifyEvent, Vec<Channel>>,
+}
+
+impl NotificationsConfig {
+    /// Channels that fire for `event`, limited to the enabled ones.
+    pub fn channels_for(&self, event: NotifyEvent) -> Vec<Channel> {
+        let enabled = |c: &Channel| match c {
+            Channel::Desktop => self.desktop,
+            Channel::Webhook => self.webhook.is_some(),
+        };
+        match self.events.get(&event) {
+            Some(channels) => channels.iter().copied().filter(enabled).collect(),
+            None => [Channel::Desktop, Channel::Webhook]
+                .into_iter()
+                .filter(enabled)
+                .collect(),
+        }
+    }
+}
+
+/// Install the project's notification settings. Later calls are ignored.
+pub fn configure(config: &NotificationsConfig) {
+    let _ = CONFIG.set(config.clone());
+}
+
+/// Send `event` on its configured channels.
+///
+/// Webhooks are queued to a background thread so the caller (usually the
+/// async run loop) never waits on the network; [`flush`] waits for them.
+pub fn notify(event: NotifyEvent, target: &str, message: &str) {
+    let Some(config) = CONFIG.get() else {
+        return;
+    };
+    for channel in config.channels_for(event) {
+        // `ralph run --offline` makes no network calls
+        if channel == Channel::Webhook && crate::offline::is_enabled() {
+            continue;
+        }
+        match channel {
+            Channel::Desktop => {
+                if let Err(e) = send_desktop(event, target, message) {
+                    warn_failed(channel, &e);
+                }
+            }
+            Channel::Webhook => {
+                if let Some(ref url) = config.webhook {
+                    queue_webhook(url, payload(event, target, message));
+                }
+            }
+        }
+    }
+}
+
+/// Wait until every queued webhook has been sent or has timed out. Called
+/// before the process exits, so the final run event is not lost.
+pub fn flush() {
+    let Some(queue) = WEBHOOKS.get() else {
+        return;
+    };
+    let (done_tx, done_rx) = mpsc::channel();
+    if queue.send(Delivery::Flush(done_tx)).is_ok() {
+        let _ = done_rx.recv();
+    }
+}
+
+fn queue_webhook(url: &str, body: serde_json::Value) {
+    let queue = WEBHOOKS.get_or_init(|| {
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for delivery in rx {
+                match delivery {
+                    Delivery::Post { url, body } => {
+                        if let Err(e) = send_webhook(&url, &body) {
+                            warn_failed(Channel::Webhook, &e);
+                        }
+                    }
+                    Delivery::Flush(done) => {
+                        let _ = done.send(());
+                    }
+                }
+            }
+        });
+        tx
+    });
+    let _ = queue.send(Delivery::Post {
+        url: url.to_string(),
+        body,
+    });
+}
+
+fn warn_failed(channel: Channel, e: &anyhow::Error) {
+    formatter::print_warning(&format!("ralph: {:?} notification failed: {e}", channel));
+}
+
+/// JSON body posted to the webhook.
+fn payload(event: NotifyEvent, target: &str, message: &str) -> serde_json::Value {
+    serde_json::json!({
+        "event": event,
+        "title": event.label(),
+        "target": target,
+        "message": message,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+fn send_desktop(event: NotifyEvent, target: &str, message: &str) -> anyhow::Result<()> {
+    notify_rust::Notification::new()
+        .appname("ralph")
+        .summary(&format!("ralph: {} ({target})", event.label()))
+        .body(message)
+        .show()?;
+    Ok(())
+}
+
+fn send_webhook(url: &str, body: &serde_json::Value) -> anyhow::Result<()> {
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(WEBHOOK_TIMEOUT))
+        .build()
+        .into();
+    agent.post(url).send_json(body)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    #[test]
+    fn channels_default_to_enabled_and_respect_overrides() {
+        let config: NotificationsConfig = toml::from_str(
+            r#"
+desktop = true
+webhook = "http://localhost/hook"
+
+[events]
+blocked = ["webhook"]
+budget_exceeded = []
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.channels_for(NotifyEvent::RunComplete),
+            vec![Channel::Desktop, Channel::Webhook]
+        );
+        assert_eq!(
+            config.channels_for(NotifyEvent::Blocked),
+            vec![Channel::Webhook]
+        );
+        assert!(config.channels_for(NotifyEvent::BudgetExceeded).is_empty());
+
+        assert!(NotificationsConfig::default()
+            .channels_for(NotifyEvent::TaskFailed)
+            .is_empty());
+        assert!(toml::from_str::<NotificationsConfig>("[events]\nlunch = []").is_err());
+    }
+
+    #[test]
+    fn webhook_posts_json_payload() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+        let (body_tx, body_rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some(v) = line.to_lowercase().strip_prefix("content-length:") {
+                    length = v.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            body_tx.send(String::from_utf8(body).unwrap()).unwrap();
+            (&stream)
+                .write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+        });
+
+        queue_webhook(
+            &url,
+            payload(NotifyEvent::TaskFailed, "t-abc123", "retries exhausted"),
+        );
+        flush();
+        let body = body_rx.try_recv().expect("flush should wait for the POST");
+        let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["event"], "task_failed");
+        assert_eq!(body["target"], "t-abc123");
+        assert_eq!(body["message"], "retries exhausted");
+    }
+}
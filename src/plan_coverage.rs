@@ -0,0 +1,266 @@
+//! Plan-to-task traceability (`ralph feature coverage`).
+//!
+//! A plan's sections are its `##`–`####` headings, each with a GitHub-style
+//! anchor (`## Phase 2: Storage` → `phase-2-storage`). The DAG-building agent
+//! records the anchor a task was derived from as the task's `plan_ref`, and
+//! coverage compares the two: plan sections no task traces back to, and
+//! executable tasks that trace back to no section.
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::dag::Task;
+
+/// One heading of a plan.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlanSection {
+    pub anchor: String,
+    pub title: String,
+    /// Heading level, 2 for `##`.
+    pub level: usize,
+    /// 1-based line of the heading in the plan.
+    pub line: usize,
+}
+
+/// Gaps between a plan and the tasks built from it.
+#[derive(Debug, Default, Serialize)]
+pub struct Coverage {
+    /// Sections no task references, directly or through an enclosing or
+    /// nested section.
+    pub uncovered: Vec<PlanSection>,
+    /// Leaf tasks with no `plan_ref` on themselves or any parent.
+    pub unanchored: Vec<Task>,
+    /// Tasks whose `plan_ref` names no section of the current plan.
+    pub unknown: Vec<Task>,
+}
+
+impl Coverage {
+    pub fn is_complete(&self) -> bool {
+        self.uncovered.is_empty() && self.unanchored.is_empty() && self.unknown.is_empty()
+    }
+}
+
+/// The `##` to `####` headings of `plan`, outside code fences. Repeated
+/// anchors get `-1`, `-2`, ... suffixes, as GitHub numbers them.
+pub fn plan_sections(plan: &str) -> Vec<PlanSection> {
+    let mut sections = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut in_fence = false;
+    for (i, line) in plan.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        let Some(title) = trimmed[level..].strip_prefix(' ') else {
+            continue;
+        };
+        if !(2..=4).contains(&level) {
+            continue;
+        }
+        let title = title.trim().trim_end_matches('#').trim().to_string();
+        let base = anchor(&title);
+        if base.is_empty() {
+            continue;
+        }
+        let count = seen.entry(base.clone()).or_insert(0);
+        let anchor = match *count {
+            0 => base,
+            n => format!("{base}-{n}"),
+        };
+        *count += 1;
+        sections.push(PlanSection {
+            anchor,
+            title,
+            level,
+            line: i + 1,
+        });
+    }
+    sections
+}
+
+/// GitHub-style anchor of a heading: lowercase, punctuation dropped, spaces
+/// as hyphens. A leading `#` is ignored, so `#phase-2` and `Phase 2` both
+/// give `phase-2`.
+pub fn anchor(text: &str) -> String {
+    text.trim()
+        .trim_start_matches('#')
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The sections as an indented list of anchors and titles, for the
+/// DAG-building prompt.
+pub fn render_sections(sections: &[PlanSection]) -> String {
+    if sections.is_empty() {
+        return "(the plan has no `##` sections)".to_string();
+    }
+    sections
+        .iter()
+        .map(|s| format!("{}- `{}`: {}", "  ".repeat(s.level - 2), s.anchor, s.title))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Compare `plan` with `tasks`, the DAG under the feature's root task
+/// without the root itself.
+pub fn coverage(plan: &str, tasks: &[Task]) -> Coverage {
+    let sections = plan_sections(plan);
+    let anchors: HashSet<&str> = sections.iter().map(|s| s.anchor.as_str()).collect();
+    let by_id: HashMap<&str, &Task> = tasks.iter().map(|t| (t.id.as_str(), t)).collect();
+
+    let referenced: HashSet<String> = tasks
+        .iter()
+        .filter_map(|t| t.plan_ref.as_deref())
+        .map(anchor)
+        .collect();
+    let mut report = Coverage::default();
+
+    // A section is covered when it, a section enclosing it, or a section
+    // nested in it is referenced
+    let mut enclosing: Vec<usize> = Vec::new();
+    let mut covered = vec![false; sections.len()];
+    for (i, section) in sections.iter().enumerate() {
+        enclosing.retain(|&j| sections[j].level < section.level);
+        if referenced.contains(&section.anchor) {
+            covered[i] = true;
+            for &j in &enclosing {
+                covered[j] = true;
+            }
+        } else if enclosing
+            .iter()
+            .any(|&j| referenced.contains(&sections[j].anchor))
+        {
+            covered[i] = true;
+        }
+        enclosing.push(i);
+    }
+    report.uncovered = sections
+        .iter()
+        .zip(&covered)
+        .filter(|(_, covered)| !**covered)
+        .map(|(s, _)| s.clone())
+        .collect();
+
+    let parents: HashSet<&str> = tasks
+        .iter()
+        .filter_map(|t| t.parent_id.as_deref())
+        .collect();
+    for task in tasks {
+        if let Some(plan_ref) = task.plan_ref.as_deref() {
+            if !anchors.contains(anchor(plan_ref).as_str()) {
+                report.unknown.push(task.clone());
+            }
+        }
+        if parents.contains(task.id.as_str()) {
+            continue;
+        }
+        let mut current = Some(task);
+        let mut anchored = false;
+        while let Some(t) = current {
+            if t.plan_ref.is_some() {
+                anchored = true;
+                break;
+            }
+            current = t.parent_id.as_deref().and_then(|p| by_id.get(p).copied());
+        }
+        if !anchored {
+            report.unanchored.push(task.clone());
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAN: &str = "# Plan: auth\n\n## Phase 1: Storage\n\n### 1.1 Users table\n\n\
+        ### 1.2 Sessions\n\n```md\n## Not a heading\n```\n\n## Phase 2: API\n\n## Risks\n\n\
+        ## Phase 2: API\n";
+
+    fn task(id: &str, parent: Option<&str>, plan_ref: Option<&str>) -> Task {
+        Task {
+            id: id.to_string(),
+            title: id.to_string(),
+            description: String::new(),
+            status: "pending".to_string(),
+            parent_id: parent.map(str::to_string),
+            feature_id: None,
+            task_type: "feature".to_string(),
+            priority: 0,
+            retry_count: 0,
+            max_retries: 3,
+            verification_status: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+            claimed_by: None,
+            discovered_by: None,
+            source_ref: None,
+            cwd: None,
+            created_by: None,
+            completed_by: None,
+            plan_ref: plan_ref.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_plan_sections_anchors() {
+        let anchors: Vec<String> = plan_sections(PLAN).into_iter().map(|s| s.anchor).collect();
+        assert_eq!(
+            anchors,
+            vec![
+                "phase-1-storage",
+                "11-users-table",
+                "12-sessions",
+                "phase-2-api",
+                "risks",
+                "phase-2-api-1",
+            ]
+        );
+        assert_eq!(anchor("#Phase 2: API"), "phase-2-api");
+        assert!(render_sections(&plan_sections(PLAN)).contains("  - `12-sessions`: 1.2 Sessions"));
+    }
+
+    #[test]
+    fn test_coverage_reports_gaps() {
+        let tasks = vec![
+            task("storage", Some("root"), Some("phase-1-storage")),
+            task("users", Some("storage"), None),
+            task("api", Some("root"), Some("#Phase 2: API")),
+            task("docs", Some("root"), None),
+            task("stale", Some("root"), Some("phase-3")),
+        ];
+        let report = coverage(PLAN, &tasks);
+        let uncovered: Vec<&str> = report.uncovered.iter().map(|s| s.anchor.as_str()).collect();
+        // 1.1 and 1.2 are covered by the enclosing Phase 1 reference
+        assert_eq!(uncovered, vec!["risks", "phase-2-api-1"]);
+        let ids = |tasks: &[Task]| tasks.iter().map(|t| t.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&report.unanchored), vec!["docs"]);
+        assert_eq!(ids(&report.unknown), vec!["stale"]);
+        assert!(!report.is_complete());
+
+        // A nested reference covers the enclosing section
+        let tasks = vec![task("s", Some("root"), Some("12-sessions"))];
+        let report = coverage(PLAN, &tasks);
+        assert!(!report
+            .uncovered
+            .iter()
+            .any(|s| s.anchor == "phase-1-storage" || s.anchor == "12-sessions"));
+        assert!(report
+            .uncovered
+            .iter()
+            .any(|s| s.anchor == "11-users-table"));
+    }
+}
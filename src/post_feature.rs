@@ -0,0 +1,250 @@
+//! Follow-up tasks for finished features (`[post_feature]`).
+//!
+//! A feature is done when its last task completes, and the documentation work
+//! around it is easy to skip. With `[post_feature] enabled = true`, finishing a
+//! feature adds a standard set of follow-up tasks under its root task (by
+//! default: update the CHANGELOG, document it for users, add examples). That
+//! reopens the feature, so the run that finished it carries on with the
+//! follow-ups. Each feature gets them once; completing them finishes it.
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::dag::{self, Db, Task};
+use crate::feature;
+use crate::output::formatter;
+use crate::project::RalphConfig;
+use crate::replan;
+use crate::task_proposal;
+
+/// Replaced with the feature's name in template titles, descriptions and
+/// criteria.
+const FEATURE_PLACEHOLDER: &str = "{feature}";
+
+/// Follow-up task settings (`[post_feature]`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct PostFeatureConfig {
+    /// Create follow-up tasks when a feature completes.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Tasks to create (`[[post_feature.tasks]]`); replaces the defaults.
+    #[serde(default = "default_tasks")]
+    pub tasks: Vec<FollowUpTemplate>,
+}
+
+impl Default for PostFeatureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tasks: default_tasks(),
+        }
+    }
+}
+
+/// One follow-up task; `{feature}` expands to the feature's name.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct FollowUpTemplate {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    /// Acceptance criteria checklist.
+    #[serde(default)]
+    pub criteria: Vec<String>,
+}
+
+fn default_tasks() -> Vec<FollowUpTemplate> {
+    let template = |title: &str, description: &str, criteria: &[&str]| FollowUpTemplate {
+        title: title.to_string(),
+        description: description.to_string(),
+        criteria: criteria.iter().map(|c| c.to_string()).collect(),
+    };
+    vec![
+        template(
+            "Update CHANGELOG for {feature}",
+            "Add an entry for the {feature} feature to the project's CHANGELOG, \
+             following its existing format. Create CHANGELOG.md if the project has none.",
+            &["The CHANGELOG describes the user-visible changes of {feature}"],
+        ),
+        template(
+            "Document {feature} for users",
+            "Update the user documentation (README, docs/, --help text) to cover \
+             what the {feature} feature adds or changes.",
+            &["Every user-facing option or behavior {feature} added is documented"],
+        ),
+        template(
+            "Add examples for {feature}",
+            "Add a short usage example for the {feature} feature where the project \
+             keeps its examples.",
+            &["At least one example shows {feature} in use"],
+        ),
+    ]
+}
+
+/// Add the configured follow-up tasks to the finished feature
+/// `feature_name`, all or nothing, and reopen it.
+///
+/// Returns no tasks when follow-ups are disabled, the feature has no task
+/// DAG, or it already got its follow-ups.
+pub fn create_follow_ups(
+    db: &Db,
+    config: &RalphConfig,
+    feature_name: &str,
+    created_by: Option<&str>,
+) -> Result<Vec<Task>> {
+    let post = &config.post_feature;
+    if !post.enabled || post.tasks.is_empty() {
+        return Ok(Vec::new());
+    }
+    let feat = feature::get_feature(db, feature_name)?;
+    let Some(root) = replan::root_task(db, &feat)? else {
+        return Ok(Vec::new());
+    };
+
+    let tx = db.conn().unchecked_transaction()?;
+    let claimed = tx.execute(
+        "UPDATE features SET post_feature_at = datetime('now')
+         WHERE id = ? AND post_feature_at IS NULL",
+        [&feat.id],
+    )?;
+    if claimed == 0 {
+        return Ok(Vec::new());
+    }
+    let expand = |text: &str| text.replace(FEATURE_PLACEHOLDER, feature_name);
+    let mut created = Vec::new();
+    for template in &post.tasks {
+        let description = expand(&template.description);
+        let criteria: Vec<String> = template.criteria.iter().map(|c| expand(c)).collect();
+        created.push(dag::create_task_with_feature(
+            db,
+            dag::CreateTaskParams {
+                title: &expand(&template.title),
+                description: (!description.trim().is_empty()).then_some(description.as_str()),
+                parent_id: Some(&root.id),
+                priority: 0,
+                feature_id: Some(&feat.id),
+                task_type: "feature",
+                max_retries: config.execution.max_retries as i32,
+                criteria: &criteria,
+                discovered_by: None,
+                cwd: None,
+                created_by,
+            },
+        )?);
+    }
+    task_proposal::refresh_parent_statuses(db, &root.id)?;
+    feature::update_feature_status(db, &feat.id, "ready")?;
+    tx.commit()?;
+    Ok(created)
+}
+
+/// Create follow-ups for every feature `transitions` finished, reporting
+/// each created task.
+pub fn after_transitions(
+    db: &Db,
+    config: &RalphConfig,
+    transitions: &[dag::AutoTransition],
+    created_by: Option<&str>,
+) -> Result<Vec<Task>> {
+    let mut created = Vec::new();
+    for t in transitions {
+        let dag::AutoTransition::FeatureDone { feature_name } = t else {
+            continue;
+        };
+        let tasks = create_follow_ups(db, config, feature_name, created_by)?;
+        if tasks.is_empty() {
+            continue;
+        }
+        formatter::emit_event_info(
+            "feature",
+            &format!(
+                "feature \"{feature_name}\" reopened for {} follow-up task(s)",
+                tasks.len()
+            ),
+        );
+        for task in &tasks {
+            formatter::emit_event_info("dag", &format!("{} created: {}", task.id, task.title));
+        }
+        created.extend(tasks);
+    }
+    Ok(created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag::init_db;
+    use tempfile::TempDir;
+
+    fn feature_with_one_task(db: &Db) -> (Task, Task) {
+        let feat = feature::create_feature(db, "auth").unwrap();
+        let task =
+            |title: &str, parent: Option<&str>| dag::create_test_task(db, &feat.id, title, parent);
+        let root = task("Feature: auth", None);
+        let login = task("Login form", Some(&root.id));
+        (root, login)
+    }
+
+    #[test]
+    fn finished_feature_is_reopened_with_follow_ups_once() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let (root, login) = feature_with_one_task(&db);
+        let mut config = RalphConfig::default();
+        config.post_feature.enabled = true;
+
+        dag::claim_task(&db, &login.id, "agent-test").unwrap();
+        let transitions = dag::complete_task(&db, &login.id).unwrap();
+        let created = after_transitions(&db, &config, &transitions, None).unwrap();
+        assert_eq!(
+            created.iter().map(|t| t.title.as_str()).collect::<Vec<_>>(),
+            vec![
+                "Update CHANGELOG for auth",
+                "Document auth for users",
+                "Add examples for auth",
+            ]
+        );
+        assert!(created[0].description.contains("the auth feature"));
+        let criteria = dag::get_task_criteria(&db, &created[2].id).unwrap();
+        assert_eq!(
+            criteria[0].description,
+            "At least one example shows auth in use"
+        );
+        assert_eq!(dag::get_task(&db, &root.id).unwrap().status, "pending");
+        assert_eq!(feature::get_feature(&db, "auth").unwrap().status, "ready");
+
+        let mut transitions = Vec::new();
+        for task in &created {
+            dag::claim_task(&db, &task.id, "agent-test").unwrap();
+            transitions.extend(dag::complete_task(&db, &task.id).unwrap());
+        }
+        assert!(transitions
+            .iter()
+            .any(|t| matches!(t, dag::AutoTransition::FeatureDone { .. })));
+        assert!(after_transitions(&db, &config, &transitions, None)
+            .unwrap()
+            .is_empty());
+        assert_eq!(feature::get_feature(&db, "auth").unwrap().status, "done");
+    }
+
+    #[test]
+    fn disabled_by_default_and_templates_are_configurable() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        feature_with_one_task(&db);
+        assert!(
+            create_follow_ups(&db, &RalphConfig::default(), "auth", None)
+                .unwrap()
+                .is_empty()
+        );
+
+        let config: RalphConfig = toml::from_str(
+            "[post_feature]\nenabled = true\n\n[[post_feature.tasks]]\n\
+             title = \"Announce {feature} in #releases\"\n",
+        )
+        .unwrap();
+        let created = create_follow_ups(&db, &config, "auth", None).unwrap();
+        assert_eq!(created.len(), 1);
+        assert_eq!(created[0].title, "Announce auth in #releases");
+        assert!(created[0].description.is_empty());
+    }
+}
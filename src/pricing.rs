@@ -0,0 +1,138 @@
+//! Per-model token pricing for journal cost accounting.
+//!
+//! Agents report token counts (and sometimes a cost) over ACP. When no cost is
+//! reported, ralph prices the counts with a built-in table that `[pricing.<model>]`
+//! sections in `.ralph.toml` can extend or override.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::acp::types::TokenUsage;
+
+/// USD prices per million tokens for one model.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct ModelPrice {
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+    /// Cache-read price; defaults to 10% of the input price.
+    #[serde(default)]
+    pub cache_read_per_mtok: Option<f64>,
+    /// Cache-write price; defaults to 125% of the input price.
+    #[serde(default)]
+    pub cache_write_per_mtok: Option<f64>,
+}
+
+impl ModelPrice {
+    const fn new(input_per_mtok: f64, output_per_mtok: f64) -> Self {
+        Self {
+            input_per_mtok,
+            output_per_mtok,
+            cache_read_per_mtok: None,
+            cache_write_per_mtok: None,
+        }
+    }
+
+    /// Price a usage record in USD.
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        let cache_read = self
+            .cache_read_per_mtok
+            .unwrap_or(self.input_per_mtok * 0.1);
+        let cache_write = self
+            .cache_write_per_mtok
+            .unwrap_or(self.input_per_mtok * 1.25);
+        (usage.input_tokens as f64 * self.input_per_mtok
+            + usage.output_tokens as f64 * self.output_per_mtok
+            + usage.cached_read_tokens as f64 * cache_read
+            + usage.cached_write_tokens as f64 * cache_write)
+            / 1_000_000.0
+    }
+}
+
+/// Built-in prices, matched by model family substring.
+const DEFAULT_PRICES: &[(&str, ModelPrice)] = &[
+    ("opus", ModelPrice::new(5.0, 25.0)),
+    ("sonnet", ModelPrice::new(3.0, 15.0)),
+    ("haiku", ModelPrice::new(1.0, 5.0)),
+];
+
+/// Look up the price for a model.
+///
+/// An exact key in `overrides` wins, then an override key contained in the
+/// model name (so `[pricing.sonnet]` covers `claude-sonnet-4-5`), then the
+/// built-in family table.
+pub fn price_for(model: &str, overrides: &BTreeMap<String, ModelPrice>) -> Option<ModelPrice> {
+    let model = model.to_lowercase();
+    if let Some(price) = overrides.get(&model) {
+        return Some(*price);
+    }
+    overrides
+        .iter()
+        .find(|(key, _)| model.contains(&key.to_lowercase()))
+        .map(|(_, price)| *price)
+        .or_else(|| {
+            DEFAULT_PRICES
+                .iter()
+                .find(|(family, _)| model.contains(family))
+                .map(|(_, price)| *price)
+        })
+}
+
+/// Cost of one iteration in USD.
+///
+/// Uses the agent-reported cost when present; otherwise prices the token
+/// counts. Unknown models cost 0.0.
+pub fn cost_usd(model: &str, usage: &TokenUsage, overrides: &BTreeMap<String, ModelPrice>) -> f64 {
+    if let Some(cost) = usage.reported_cost_usd {
+        return cost;
+    }
+    price_for(model, overrides)
+        .map(|price| price.cost(usage))
+        .unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(input: u64, output: u64) -> TokenUsage {
+        TokenUsage {
+            input_tokens: input,
+            output_tokens: output,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn prices_tokens_with_builtin_table() {
+        let overrides = BTreeMap::new();
+        let cost = cost_usd("claude-sonnet-4-5", &usage(1_000_000, 100_000), &overrides);
+        assert!((cost - 4.5).abs() < 1e-9);
+        assert_eq!(cost_usd("gpt-unknown", &usage(1000, 1000), &overrides), 0.0);
+    }
+
+    #[test]
+    fn overrides_and_reported_cost_take_precedence() {
+        let mut overrides = BTreeMap::new();
+        overrides.insert("sonnet".to_string(), ModelPrice::new(1.0, 2.0));
+        let cost = cost_usd("sonnet", &usage(1_000_000, 1_000_000), &overrides);
+        assert!((cost - 3.0).abs() < 1e-9);
+
+        let reported = TokenUsage {
+            reported_cost_usd: Some(0.42),
+            ..usage(1_000_000, 1_000_000)
+        };
+        assert_eq!(cost_usd("sonnet", &reported, &overrides), 0.42);
+    }
+
+    #[test]
+    fn cache_tokens_default_to_input_multiples() {
+        let price = ModelPrice::new(10.0, 0.0);
+        let u = TokenUsage {
+            cached_read_tokens: 1_000_000,
+            cached_write_tokens: 1_000_000,
+            ..Default::default()
+        };
+        assert!((price.cost(&u) - 13.5).abs() < 1e-9);
+    }
+}
@@ -6,10 +6,12 @@
 
 use anyhow::{bail, Context, Result};
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::{env, fs};
 
 use crate::dag;
+use crate::diff_context::DiffScope;
 use crate::ui::theme::ColorOverrides;
 
 /// Project configuration loaded from `.ralph.toml`.
@@ -27,10 +29,265 @@ pub struct ProjectConfig {
 pub struct RalphConfig {
     #[serde(default)]
     pub execution: ExecutionConfig,
+    /// When a run stops besides completion and the iteration limit (`[run]`).
+    #[serde(default)]
+    pub run: RunConfig,
     #[serde(default)]
     pub agent: AgentConfig,
+    /// Named agent profiles (`[agents.<name>]`), selectable with `--agent <name>`.
+    #[serde(default)]
+    pub agents: BTreeMap<String, AgentProfile>,
     #[serde(default)]
     pub ui: UiConfig,
+    /// Per-model token prices (`[pricing.<model>]`), layered over the built-in table.
+    #[serde(default)]
+    pub pricing: BTreeMap<String, crate::pricing::ModelPrice>,
+    #[serde(default)]
+    pub sigils: SigilsConfig,
+    /// Desktop and webhook notifications for key run events.
+    #[serde(default)]
+    pub notifications: crate::output::notify::NotificationsConfig,
+    #[serde(default)]
+    pub strategy: StrategyConfig,
+    #[serde(default)]
+    pub security: SecurityConfig,
+    /// Issue tracker connections for `ralph sync`.
+    #[serde(default)]
+    pub sync: crate::sync::SyncConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    /// Variables set for every command the agent runs in a terminal (`[env]`).
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub secrets: SecretsConfig,
+    /// Spec/plan review loop (`[review]`).
+    #[serde(default)]
+    pub review: crate::review::ReviewConfig,
+    /// Follow-up tasks created when a feature completes (`[post_feature]`).
+    #[serde(default)]
+    pub post_feature: crate::post_feature::PostFeatureConfig,
+    #[serde(default)]
+    pub user: UserConfig,
+    /// Per-task-type knowledge filters and prompt cap (`[knowledge]`).
+    #[serde(default)]
+    pub knowledge: crate::knowledge::KnowledgeConfig,
+    /// Regexes over agent output that pause for approval (`[guardrails]`).
+    #[serde(default)]
+    pub guardrails: crate::guardrails::GuardrailsConfig,
+    /// Network detection for `ralph run --offline` (`[offline]`).
+    #[serde(default)]
+    pub offline: crate::offline::OfflineConfig,
+    /// Allow, deny or ask per tool kind on agent permission requests (`[permissions]`).
+    #[serde(default)]
+    pub permissions: PermissionsConfig,
+    /// Verification providers, per project or task type (`[verification]`).
+    #[serde(default)]
+    pub verification: crate::verification::VerificationConfig,
+}
+
+/// Run-wide stop conditions (`[run]`).
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RunConfig {
+    /// Stop once this expression over the run's stats holds, e.g.
+    /// `"feature.done_ratio >= 0.8 or budget.cost > 5"` (see exit_when.rs).
+    #[serde(default)]
+    pub exit_when: Option<String>,
+}
+
+/// Secret environment variables for agent terminals (`[secrets]`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct SecretsConfig {
+    /// Dotenv-style file of secrets, relative to the project root.
+    #[serde(default = "default_secrets_file")]
+    pub file: String,
+}
+
+impl Default for SecretsConfig {
+    fn default() -> Self {
+        Self {
+            file: default_secrets_file(),
+        }
+    }
+}
+
+fn default_secrets_file() -> String {
+    ".ralph/secrets.env".to_string()
+}
+
+/// Who is running ralph (`[user]`), recorded on the tasks they create and complete.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct UserConfig {
+    /// Name recorded in `created_by`/`completed_by`; defaults to git's `user.name`.
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// Run log lifecycle (`[logging]`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct LoggingConfig {
+    /// Days to keep run logs; 0 keeps them forever.
+    #[serde(default = "default_log_retention")]
+    pub retention: u32,
+    /// Size in MB at which a run's log is rotated; 0 never rotates.
+    #[serde(default = "default_rotate_mb")]
+    pub rotate_mb: u64,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            retention: default_log_retention(),
+            rotate_mb: default_rotate_mb(),
+        }
+    }
+}
+
+fn default_log_retention() -> u32 {
+    30
+}
+
+fn default_rotate_mb() -> u64 {
+    10
+}
+
+/// Where the agent may read and write files (`[security]`).
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct SecurityConfig {
+    /// Let the agent read and write anywhere the user can.
+    #[serde(default)]
+    pub allow_outside_root: bool,
+    /// Let the agent read (but not write) outside the project root.
+    #[serde(default)]
+    pub allow_reads_outside_root: bool,
+    /// Extra files or directories the agent may use, e.g. `~/.config/gh`.
+    /// Relative entries are resolved against the project root.
+    #[serde(default)]
+    pub allowed_paths: Vec<String>,
+}
+
+/// How ralph answers an agent's permission request for one tool kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// Approve without asking (the default).
+    #[default]
+    Allow,
+    /// Refuse without asking.
+    Deny,
+    /// Ask the user each time; only an explicit yes approves.
+    Ask,
+}
+
+/// Permission per ACP tool kind (`[permissions]`). Read-only sessions deny
+/// edits, deletes and moves whatever is configured here.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct PermissionsConfig {
+    #[serde(default)]
+    pub edit: Permission,
+    #[serde(default)]
+    pub delete: Permission,
+    #[serde(default)]
+    pub r#move: Permission,
+    #[serde(default)]
+    pub execute: Permission,
+    #[serde(default)]
+    pub fetch: Permission,
+}
+
+/// Model strategy tuning (`[strategy]`).
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct StrategyConfig {
+    #[serde(default)]
+    pub adaptive: AdaptiveConfig,
+}
+
+/// Settings for `--model-strategy=adaptive` (`[strategy.adaptive]`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdaptiveConfig {
+    /// Candidate models, in tie-break order.
+    #[serde(default = "default_adaptive_models")]
+    pub models: Vec<String>,
+    /// Per-model weight multipliers for tasks whose title or description
+    /// mentions the key as a word (`[strategy.adaptive.task_weights.design]`).
+    #[serde(default)]
+    pub task_weights: BTreeMap<String, BTreeMap<String, f64>>,
+}
+
+impl Default for AdaptiveConfig {
+    fn default() -> Self {
+        Self {
+            models: default_adaptive_models(),
+            task_weights: BTreeMap::new(),
+        }
+    }
+}
+
+fn default_adaptive_models() -> Vec<String> {
+    ["haiku", "sonnet", "opus"].map(String::from).to_vec()
+}
+
+impl ProjectConfig {
+    /// Path of the progress database.
+    pub fn db_path(&self) -> PathBuf {
+        self.root.join(".ralph/progress.db")
+    }
+
+    /// The person running ralph: `[user] name`, else git's `user.name` for
+    /// the project, else `None`.
+    pub fn user_name(&self) -> Option<String> {
+        if let Some(name) = self.config.user.name.as_deref().map(str::trim) {
+            if !name.is_empty() {
+                return Some(name.to_string());
+            }
+        }
+        let out = std::process::Command::new("git")
+            .arg("-C")
+            .arg(&self.root)
+            .args(["config", "user.name"])
+            .stderr(std::process::Stdio::null())
+            .output()
+            .ok()?;
+        let name = String::from_utf8_lossy(&out.stdout).trim().to_string();
+        (out.status.success() && !name.is_empty()).then_some(name)
+    }
+}
+
+/// Sigils configuration section.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct SigilsConfig {
+    /// Project-defined sigils (`[sigils.custom.<tag>]`), keyed by tag name.
+    #[serde(default)]
+    pub custom: BTreeMap<String, CustomSigil>,
+}
+
+/// A project-defined sigil and what ralph does when the agent emits it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CustomSigil {
+    #[serde(flatten)]
+    pub action: CustomSigilAction,
+    /// When the agent should emit the sigil; shown in the iteration prompt.
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Action run for each occurrence of a custom sigil.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub enum CustomSigilAction {
+    /// Append the body to a project-relative file. `template` may use `{body}`.
+    AppendToFile {
+        path: String,
+        #[serde(default)]
+        template: Option<String>,
+    },
+    /// Run a shell command in the project root with the body on stdin.
+    RunCommand { command: String },
+    /// Create a task: first line of the body is the title, the rest the description.
+    CreateTask {
+        #[serde(default)]
+        priority: Option<i32>,
+    },
 }
 
 /// UI configuration section.
@@ -63,16 +320,44 @@ pub struct AgentConfig {
     /// Command to spawn the ACP agent (default: "claude-agent-acp").
     #[serde(default = "default_agent_command")]
     pub command: String,
+    /// Command `ralph auth` runs for the default agent (default: "claude auth login").
+    #[serde(default)]
+    pub auth_command: Option<String>,
+    /// Runs without network access, so `ralph run --offline` accepts it.
+    #[serde(default)]
+    pub local: bool,
+    /// The agent's model IDs by tier or alias (`strong = "..."`), see
+    /// [`crate::agent_models`].
+    #[serde(default)]
+    pub models: BTreeMap<String, String>,
 }
 
 impl Default for AgentConfig {
     fn default() -> Self {
         Self {
             command: default_agent_command(),
+            auth_command: None,
+            local: false,
+            models: BTreeMap::new(),
         }
     }
 }
 
+/// A named agent profile from an `[agents.<name>]` section.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct AgentProfile {
+    /// Command to spawn this agent over ACP.
+    pub command: Option<String>,
+    /// Command `ralph auth --agent <name>` runs to log in.
+    pub auth_command: Option<String>,
+    /// Runs without network access, so `ralph run --offline` accepts it.
+    #[serde(default)]
+    pub local: bool,
+    /// This agent's model IDs by tier or alias, see [`crate::agent_models`].
+    #[serde(default)]
+    pub models: BTreeMap<String, String>,
+}
+
 fn default_agent_command() -> String {
     "claude-agent-acp".to_string()
 }
@@ -89,6 +374,87 @@ pub struct ExecutionConfig {
     /// compatibility with existing .ralph.toml files and is ignored at runtime.
     #[serde(default = "default_true")]
     pub learn: bool,
+    /// Ready-queue ordering: "fifo", "priority" (default), or "aged".
+    #[serde(default = "default_scheduler")]
+    pub scheduler: String,
+    /// Seconds of waiting per priority level gained under the "aged" scheduler.
+    #[serde(default = "default_aging_secs")]
+    pub aging_secs: u64,
+    /// Hard limit on one agent iteration in seconds; 0 disables it.
+    #[serde(default)]
+    pub iteration_timeout_secs: u64,
+    /// Retries of an iteration whose agent failed transiently (crashed,
+    /// broken pipe, API overloaded) before the run fails; 0 disables them.
+    #[serde(default = "default_agent_retries")]
+    pub agent_retries: u32,
+    /// Wait before the first agent retry in seconds; doubled for each one after.
+    #[serde(default = "default_agent_retry_backoff_secs")]
+    pub agent_retry_backoff_secs: u64,
+    /// Resume a retried task's previous ACP session when the agent supports
+    /// `session/load`.
+    #[serde(default = "default_true")]
+    pub resume_sessions: bool,
+    /// Keep `.ralph/scratch/<feature>.md` and show its tail to the next iteration.
+    #[serde(default = "default_true")]
+    pub scratchpad: bool,
+    /// Show the changes made so far (`git diff` summary) in each prompt:
+    /// "off", "run" (since the run started) or "feature" (since the plan).
+    #[serde(default)]
+    pub diff_context: DiffScope,
+    /// Characters of diff hunks in the `diff_context` section.
+    #[serde(default = "default_diff_context_chars")]
+    pub diff_context_chars: usize,
+    /// Reuse a cached verdict when the task's files are unchanged since the
+    /// last verification.
+    #[serde(default = "default_true")]
+    pub verification_cache: bool,
+    /// Tool calls allowed in one iteration before it is cancelled; 0 = unlimited.
+    #[serde(default)]
+    pub max_tool_calls: u32,
+    /// Back-to-back runs of one terminal command (no file write in between)
+    /// before the agent is told to stop; 0 = unlimited.
+    #[serde(default = "default_max_repeated_commands")]
+    pub max_repeated_commands: u32,
+    /// Estimated prompt tokens above which the spec and plan are summarized;
+    /// 0 = half the model's context window.
+    #[serde(default)]
+    pub prompt_token_budget: u32,
+    /// Write the agent's thought stream into the iteration transcript.
+    #[serde(default)]
+    pub capture_thoughts: bool,
+    /// Give a retry the condensed thoughts of the attempt before it.
+    #[serde(default)]
+    pub thoughts_in_retry: bool,
+    /// Verify a finished task in the background while the next task runs.
+    /// Its dependents wait for the verdict.
+    #[serde(default)]
+    pub pipeline_verification: bool,
+    /// Point verification at the tests the task's files can affect rather
+    /// than the full suite. The final attempt always runs the full suite.
+    #[serde(default = "default_true")]
+    pub targeted_tests: bool,
+    /// Condense each iteration's transcript into a structured journal note
+    /// with `journal_summary_model`.
+    #[serde(default)]
+    pub summarize_journal: bool,
+    /// Model that writes journal notes under `summarize_journal`, the rolling
+    /// run summary, and spec/plan summaries over `prompt_token_budget`.
+    #[serde(default = "default_journal_summary_model")]
+    pub journal_summary_model: String,
+    /// Journal entries between regenerations of the rolling run summary that
+    /// heads the journal context; 0 disables it.
+    #[serde(default = "default_run_summary_every")]
+    pub run_summary_every: u32,
+    /// Iterations budgeted per unresolved leaf task under `--limit auto`.
+    #[serde(default = "default_auto_limit_factor")]
+    pub auto_limit_factor: f64,
+    /// While a task is verified, explore the next ready task in a read-only
+    /// session and give its analysis to that task's iteration.
+    #[serde(default)]
+    pub speculative_exploration: bool,
+    /// Extra retries for a task touching files whose verification has flaked.
+    #[serde(default = "default_flaky_retry_bonus")]
+    pub flaky_retry_bonus: u32,
 }
 
 impl Default for ExecutionConfig {
@@ -97,10 +463,73 @@ impl Default for ExecutionConfig {
             max_retries: default_max_retries(),
             verify: true,
             learn: true,
+            scheduler: default_scheduler(),
+            aging_secs: default_aging_secs(),
+            iteration_timeout_secs: 0,
+            agent_retries: default_agent_retries(),
+            agent_retry_backoff_secs: default_agent_retry_backoff_secs(),
+            resume_sessions: true,
+            scratchpad: true,
+            diff_context: DiffScope::Off,
+            diff_context_chars: default_diff_context_chars(),
+            verification_cache: true,
+            max_tool_calls: 0,
+            max_repeated_commands: default_max_repeated_commands(),
+            prompt_token_budget: 0,
+            capture_thoughts: false,
+            thoughts_in_retry: false,
+            pipeline_verification: false,
+            targeted_tests: true,
+            summarize_journal: false,
+            journal_summary_model: default_journal_summary_model(),
+            run_summary_every: default_run_summary_every(),
+            auto_limit_factor: default_auto_limit_factor(),
+            speculative_exploration: false,
+            flaky_retry_bonus: default_flaky_retry_bonus(),
         }
     }
 }
 
+fn default_agent_retries() -> u32 {
+    3
+}
+
+fn default_agent_retry_backoff_secs() -> u64 {
+    2
+}
+
+fn default_diff_context_chars() -> usize {
+    6000
+}
+
+fn default_journal_summary_model() -> String {
+    "haiku".to_string()
+}
+
+fn default_run_summary_every() -> u32 {
+    10
+}
+
+fn default_auto_limit_factor() -> f64 {
+    2.0
+}
+
+fn default_flaky_retry_bonus() -> u32 {
+    2
+}
+
+fn default_max_repeated_commands() -> u32 {
+    5
+}
+
+fn default_scheduler() -> String {
+    "priority".to_string()
+}
+
+fn default_aging_secs() -> u64 {
+    dag::DEFAULT_AGING_SECS
+}
+
 fn default_max_retries() -> u32 {
     3
 }
@@ -127,16 +556,30 @@ pub fn discover() -> Result<ProjectConfig> {
 /// This is the internal implementation that allows testing with arbitrary
 /// starting directories.
 fn discover_from(start: &Path) -> Result<ProjectConfig> {
+    let config_path = find_config_from(start)?;
+    let config = load_config(&config_path)?;
+    Ok(ProjectConfig {
+        root: config_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default(),
+        config,
+    })
+}
+
+/// Path of the nearest `.ralph.toml`, without loading it. `ralph config
+/// check` uses this so it can report on files that fail to load.
+pub fn find_config() -> Result<PathBuf> {
+    find_config_from(&env::current_dir()?)
+}
+
+fn find_config_from(start: &Path) -> Result<PathBuf> {
     let mut current = start;
 
     loop {
         let config_path = current.join(".ralph.toml");
         if config_path.exists() && config_path.is_file() {
-            let config = load_config(&config_path)?;
-            return Ok(ProjectConfig {
-                root: current.to_path_buf(),
-                config,
-            });
+            return Ok(config_path);
         }
 
         // Move up to parent directory
@@ -155,6 +598,8 @@ fn load_config(path: &Path) -> Result<RalphConfig> {
     let config: RalphConfig = toml::from_str(&content)?;
     // Validate color overrides at load time for clear error messages.
     config.ui.colors.validate()?;
+    config.review.validate()?;
+    config.verification.validate()?;
     Ok(config)
 }
 
@@ -164,7 +609,8 @@ fn load_config(path: &Path) -> Result<RalphConfig> {
 /// - `.ralph.toml` with commented defaults (if it doesn't exist)
 /// - `.ralph/` directory
 /// - `.ralph/progress.db` SQLite database (initialized schema)
-/// - `.gitignore` entry for `.ralph/progress.db`
+/// - `.gitignore` entries for `.ralph/progress.db`, `.ralph/scratch/`, `.ralph/cache/`,
+///   `.ralph/secrets.env`, `.ralph/transcripts/` and `.ralph/ralph.sock`
 ///
 /// This function is idempotent: running it multiple times won't overwrite
 /// existing files or produce errors.
@@ -228,28 +674,37 @@ fn init_in_dir(cwd: &Path) -> Result<()> {
 
     // 5. Update .gitignore
     let gitignore_path = cwd.join(".gitignore");
-    let gitignore_entry = ".ralph/progress.db\n";
+    let gitignore_entries = [
+        ".ralph/progress.db",
+        ".ralph/scratch/",
+        ".ralph/cache/",
+        ".ralph/secrets.env",
+        ".ralph/transcripts/",
+        ".ralph/ralph.sock",
+    ];
 
     if gitignore_path.exists() {
         let content = fs::read_to_string(&gitignore_path).context("Failed to read .gitignore")?;
 
-        // Check if entry already exists
-        if !content
-            .lines()
-            .any(|line| line.trim() == ".ralph/progress.db")
-        {
-            let mut new_content = content;
-            if !new_content.ends_with('\n') {
+        // Append only the entries that are missing
+        let mut new_content = content.clone();
+        for entry in gitignore_entries {
+            if !content.lines().any(|line| line.trim() == entry) {
+                if !new_content.is_empty() && !new_content.ends_with('\n') {
+                    new_content.push('\n');
+                }
+                new_content.push_str(entry);
                 new_content.push('\n');
+                println!("Added {} to .gitignore", entry);
             }
-            new_content.push_str(gitignore_entry);
-
+        }
+        if new_content != content {
             fs::write(&gitignore_path, new_content).context("Failed to update .gitignore")?;
-            println!("Added .ralph/progress.db to .gitignore");
         }
     } else {
-        fs::write(&gitignore_path, gitignore_entry).context("Failed to create .gitignore")?;
-        println!("Created .gitignore with .ralph/progress.db");
+        fs::write(&gitignore_path, gitignore_entries.join("\n") + "\n")
+            .context("Failed to create .gitignore")?;
+        println!("Created .gitignore with {}", gitignore_entries.join(", "));
     }
 
     println!("\nRalph project initialized successfully!");
@@ -401,6 +856,8 @@ mod tests {
         assert!(gitignore.contains("*.log"));
         assert!(gitignore.contains("target/"));
         assert!(gitignore.contains(".ralph/progress.db"));
+        assert!(gitignore.contains(".ralph/scratch/"));
+        assert!(gitignore.contains(".ralph/secrets.env"));
 
         // Verify no duplicate entries if run again
         super::init_in_dir(tmp.path()).unwrap();
@@ -489,6 +946,48 @@ mod tests {
         assert!(tmp.path().join(".claude/settings.json").exists());
     }
 
+    #[test]
+    fn custom_sigils_parse_each_action() {
+        let toml_content = r#"
+[sigils.custom.migration]
+action = "append-to-file"
+path = "docs/migrations.md"
+template = "- [ ] {body}"
+description = "A schema change that needs a migration"
+
+[sigils.custom.notify]
+action = "run-command"
+command = "scripts/notify.sh"
+
+[sigils.custom.followup]
+action = "create-task"
+priority = 5
+"#;
+        let config: RalphConfig = toml::from_str(toml_content).unwrap();
+        let custom = &config.sigils.custom;
+        assert_eq!(
+            custom["migration"].action,
+            CustomSigilAction::AppendToFile {
+                path: "docs/migrations.md".to_string(),
+                template: Some("- [ ] {body}".to_string()),
+            }
+        );
+        assert!(custom["migration"].description.is_some());
+        assert_eq!(
+            custom["notify"].action,
+            CustomSigilAction::RunCommand {
+                command: "scripts/notify.sh".to_string()
+            }
+        );
+        assert_eq!(
+            custom["followup"].action,
+            CustomSigilAction::CreateTask { priority: Some(5) }
+        );
+
+        let bad = "[sigils.custom.x]\naction = \"explode\"\n";
+        assert!(toml::from_str::<RalphConfig>(bad).is_err());
+    }
+
     #[test]
     fn ui_config_defaults_to_light_theme() {
         let config = RalphConfig::default();
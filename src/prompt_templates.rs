@@ -0,0 +1,250 @@
+//! Project overrides for built-in prompts (`.ralph/prompts/<name>.md`).
+//!
+//! Each template replaces one prompt, or one section of the iteration prompt,
+//! and may use `{{variable}}` placeholders. `{{default}}` expands to the
+//! built-in text, so a template can extend it (coding standards, language,
+//! tone) instead of restating it. Unknown placeholders are left as they are;
+//! `ralph config check` reports them, and template files it does not know.
+
+use std::path::Path;
+
+use crate::output::formatter;
+
+/// Where project templates live, relative to the project root.
+pub const PROMPTS_DIR: &str = ".ralph/prompts";
+
+/// A prompt that can be overridden, and the variables its template can use
+/// besides `default`.
+pub struct Template {
+    pub name: &'static str,
+    pub vars: &'static [&'static str],
+}
+
+/// Variables of every section of the iteration prompt.
+const ITERATION_VARS: &[&str] = &[
+    "task_id",
+    "task_title",
+    "task_description",
+    "spec",
+    "plan",
+    "journal",
+    "knowledge",
+];
+
+pub const TEMPLATES: &[Template] = &[
+    Template {
+        name: "system",
+        vars: ITERATION_VARS,
+    },
+    Template {
+        name: "task",
+        vars: ITERATION_VARS,
+    },
+    Template {
+        name: "memory",
+        vars: ITERATION_VARS,
+    },
+    Template {
+        name: "feature_spec",
+        vars: &["name", "spec_path", "context"],
+    },
+    Template {
+        name: "feature_plan",
+        vars: &["name", "spec", "plan_path", "context"],
+    },
+    Template {
+        name: "feature_build",
+        vars: &["spec", "plan", "plan_sections"],
+    },
+    Template {
+        name: "feature_replan",
+        vars: &["name", "spec", "plan", "plan_path", "drift", "context"],
+    },
+    Template {
+        name: "feature_reconcile",
+        vars: &["spec", "plan", "existing_tasks"],
+    },
+    Template {
+        name: "dag_review",
+        vars: &["spec", "plan", "tasks"],
+    },
+    Template {
+        name: "unblock",
+        vars: &["failures"],
+    },
+    Template {
+        name: "task_new",
+        vars: &["context"],
+    },
+];
+
+/// The prompt `name`: the project's template rendered with `vars`, or
+/// `default` when there is no template. An unreadable template is reported
+/// and the default used.
+pub fn apply(project_root: &Path, name: &str, default: String, vars: &[(&str, &str)]) -> String {
+    let path = project_root.join(PROMPTS_DIR).join(format!("{name}.md"));
+    let template = match std::fs::read_to_string(&path) {
+        Ok(template) => template,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return default,
+        Err(e) => {
+            formatter::print_warning(&format!("Ignoring prompt template {}: {e}", path.display()));
+            return default;
+        }
+    };
+    let mut all = vec![("default", default.as_str())];
+    all.extend_from_slice(vars);
+    interpolate(&template, &all)
+}
+
+/// `template` with each `{{name}}` in `vars` replaced by its value. Values
+/// are not expanded again.
+pub fn interpolate(template: &str, vars: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let value = after.find("}}").and_then(|end| {
+            let key = after[..end].trim();
+            vars.iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| (*value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                out.push_str(value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                out.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Problems with the project's templates: files that override nothing and
+/// placeholders the template cannot use.
+pub fn check(project_root: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(project_root.join(PROMPTS_DIR)) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "md"))
+        .collect();
+    paths.sort();
+
+    let mut problems = Vec::new();
+    for path in paths {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        let file = format!("{PROMPTS_DIR}/{name}.md");
+        let Some(template) = TEMPLATES.iter().find(|t| t.name == name) else {
+            problems.push(format!(
+                "{file} overrides no prompt (expected one of: {})",
+                TEMPLATES
+                    .iter()
+                    .map(|t| t.name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+            continue;
+        };
+        let text = std::fs::read_to_string(&path).unwrap_or_default();
+        for var in placeholders(&text) {
+            if var != "default" && !template.vars.contains(&var) {
+                problems.push(format!(
+                    "{file}: unknown variable {{{{{var}}}}} (available: default, {})",
+                    template.vars.join(", ")
+                ));
+            }
+        }
+    }
+    problems
+}
+
+/// Names of the `{{name}}` placeholders in `text`, in order, once each.
+fn placeholders(text: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        let name = after[..end].trim();
+        if !name.is_empty()
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !names.contains(&name)
+        {
+            names.push(name);
+        }
+        rest = &after[end + 2..];
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn interpolates_known_variables_once() {
+        assert_eq!(
+            interpolate(
+                "{{ task_title }}: {{spec}} {{unknown}} {{",
+                &[("task_title", "Add login"), ("spec", "{{task_title}}")]
+            ),
+            "Add login: {{task_title}} {{unknown}} {{"
+        );
+    }
+
+    #[test]
+    fn template_overrides_the_default_and_can_extend_it() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        assert_eq!(apply(root, "system", "built-in".into(), &[]), "built-in");
+
+        std::fs::create_dir_all(root.join(PROMPTS_DIR)).unwrap();
+        std::fs::write(
+            root.join(PROMPTS_DIR).join("system.md"),
+            "{{default}}\n\nAnswer in German. Current task: {{task_title}}",
+        )
+        .unwrap();
+        assert_eq!(
+            apply(
+                root,
+                "system",
+                "built-in".into(),
+                &[("task_title", "Add login")]
+            ),
+            "built-in\n\nAnswer in German. Current task: Add login"
+        );
+    }
+
+    #[test]
+    fn check_reports_unknown_templates_and_variables() {
+        let dir = TempDir::new().unwrap();
+        let prompts = dir.path().join(PROMPTS_DIR);
+        assert!(check(dir.path()).is_empty());
+
+        std::fs::create_dir_all(&prompts).unwrap();
+        std::fs::write(prompts.join("sytem.md"), "x").unwrap();
+        std::fs::write(
+            prompts.join("feature_build.md"),
+            "{{default}} {{spec}} {{knowledge}}",
+        )
+        .unwrap();
+        std::fs::write(prompts.join("notes.txt"), "{{ignored}}").unwrap();
+        let problems = check(dir.path());
+        assert_eq!(problems.len(), 2, "{problems:?}");
+        assert_eq!(
+            problems[0],
+            ".ralph/prompts/feature_build.md: unknown variable {{knowledge}} (available: default, spec, plan, plan_sections)"
+        );
+        assert!(problems[1].starts_with(".ralph/prompts/sytem.md overrides no prompt"));
+    }
+}
@@ -0,0 +1,306 @@
+//! `ralph db prune`: move old run history out of the progress database.
+//!
+//! Journal entries older than the age cutoff that do not belong to one of the
+//! most recent runs, and task logs older than the cutoff on finished or
+//! archived tasks, are written to `.ralph/archive/history-<timestamp>.jsonl.gz`
+//! (one JSON object per row, tagged with its table) and then deleted, along
+//! with the rolling summaries of runs whose journal is gone. The
+//! database is vacuumed afterwards to return the space to the filesystem.
+
+use anyhow::{Context, Result};
+use flate2::write::GzEncoder;
+use rusqlite::types::ValueRef;
+use rusqlite::TransactionBehavior;
+use serde_json::{Map, Value};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::dag::Db;
+
+/// Journal rows to prune. `?1` is the cutoff timestamp, `?2` how many of the
+/// most recent runs to keep whole.
+const JOURNAL_WHERE: &str = "datetime(created_at) < datetime(?1)
+    AND run_id NOT IN (
+        SELECT run_id FROM journal GROUP BY run_id ORDER BY MAX(id) DESC LIMIT ?2
+    )";
+
+/// Task logs to prune. `?1` is the cutoff timestamp. Logs of tasks that may
+/// still run are kept whatever their age.
+const TASK_LOGS_WHERE: &str = "datetime(timestamp) < datetime(?1)
+    AND task_id IN (
+        SELECT id FROM tasks WHERE status IN ('done', 'failed') OR archived_at IS NOT NULL
+    )";
+
+/// What to keep.
+#[derive(Debug, Clone, Copy)]
+pub struct PruneOptions {
+    /// Rows newer than this many days are kept.
+    pub keep_days: u32,
+    /// Journal entries of this many most recent runs are kept whatever their age.
+    pub keep_runs: u32,
+    /// Count what would be pruned without writing anything.
+    pub dry_run: bool,
+}
+
+/// What a prune did (or, with `dry_run`, would do).
+#[derive(Debug, Default)]
+pub struct PruneReport {
+    pub journal_entries: usize,
+    pub runs: usize,
+    pub task_logs: usize,
+    /// Archive written; `None` on a dry run or when nothing was pruned.
+    pub archive: Option<PathBuf>,
+    /// Database file size before and after `VACUUM`.
+    pub size_before: u64,
+    pub size_after: u64,
+}
+
+/// Archive and delete old history, then vacuum.
+pub fn prune(
+    db: &Db,
+    db_path: &Path,
+    project_root: &Path,
+    options: PruneOptions,
+) -> Result<PruneReport> {
+    let cutoff =
+        (chrono::Utc::now() - chrono::Duration::days(options.keep_days.into())).to_rfc3339();
+    let keep_runs = i64::from(options.keep_runs);
+    let size_before = file_size(db_path);
+    let mut report = PruneReport {
+        size_before,
+        size_after: size_before,
+        ..Default::default()
+    };
+
+    // Immediate, so a run finishing meanwhile cannot shift which rows match
+    // between archiving and deleting.
+    let tx = rusqlite::Transaction::new_unchecked(db.conn(), TransactionBehavior::Immediate)?;
+    report.journal_entries = tx.query_row(
+        &format!("SELECT count(*) FROM journal WHERE {JOURNAL_WHERE}"),
+        rusqlite::params![cutoff, keep_runs],
+        |row| row.get(0),
+    )?;
+    report.runs = tx.query_row(
+        &format!("SELECT count(DISTINCT run_id) FROM journal WHERE {JOURNAL_WHERE}"),
+        rusqlite::params![cutoff, keep_runs],
+        |row| row.get(0),
+    )?;
+    report.task_logs = tx.query_row(
+        &format!("SELECT count(*) FROM task_logs WHERE {TASK_LOGS_WHERE}"),
+        [&cutoff],
+        |row| row.get(0),
+    )?;
+    if options.dry_run || report.journal_entries + report.task_logs == 0 {
+        return Ok(report);
+    }
+
+    let archive = project_root.join(".ralph/archive").join(format!(
+        "history-{}.jsonl.gz",
+        chrono::Utc::now().format("%Y%m%dT%H%M%SZ")
+    ));
+    std::fs::create_dir_all(archive.parent().unwrap())
+        .context("Failed to create .ralph/archive")?;
+    let file = std::fs::File::create(&archive)
+        .with_context(|| format!("Failed to create {}", archive.display()))?;
+    let mut encoder = GzEncoder::new(file, flate2::Compression::default());
+    write_rows(
+        &tx,
+        "journal",
+        &format!("SELECT * FROM journal WHERE {JOURNAL_WHERE} ORDER BY id"),
+        rusqlite::params![cutoff, keep_runs],
+        &mut encoder,
+    )?;
+    // Rolling summaries go with the last journal entry of their run
+    let summaries_where =
+        format!("run_id NOT IN (SELECT run_id FROM journal WHERE NOT ({JOURNAL_WHERE}))");
+    write_rows(
+        &tx,
+        "run_summaries",
+        &format!("SELECT * FROM run_summaries WHERE {summaries_where} ORDER BY id"),
+        rusqlite::params![cutoff, keep_runs],
+        &mut encoder,
+    )?;
+    write_rows(
+        &tx,
+        "task_logs",
+        &format!("SELECT * FROM task_logs WHERE {TASK_LOGS_WHERE} ORDER BY id"),
+        rusqlite::params![cutoff],
+        &mut encoder,
+    )?;
+    encoder
+        .finish()
+        .and_then(|file| file.sync_all())
+        .with_context(|| format!("Failed to write {}", archive.display()))?;
+
+    tx.execute(
+        &format!("DELETE FROM run_summaries WHERE {summaries_where}"),
+        rusqlite::params![cutoff, keep_runs],
+    )?;
+    tx.execute(
+        &format!("DELETE FROM journal WHERE {JOURNAL_WHERE}"),
+        rusqlite::params![cutoff, keep_runs],
+    )?;
+    tx.execute(
+        &format!("DELETE FROM task_logs WHERE {TASK_LOGS_WHERE}"),
+        [&cutoff],
+    )?;
+    tx.commit().context("Failed to delete pruned rows")?;
+    report.archive = Some(archive);
+
+    db.conn()
+        .execute_batch("VACUUM")
+        .context("Failed to vacuum the database")?;
+    report.size_after = file_size(db_path);
+    Ok(report)
+}
+
+/// Write each row of `sql` as a JSON line, keyed by column name.
+fn write_rows(
+    conn: &rusqlite::Connection,
+    table: &str,
+    sql: &str,
+    params: impl rusqlite::Params,
+    out: &mut impl Write,
+) -> Result<()> {
+    let mut stmt = conn.prepare(sql)?;
+    let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    let mut rows = stmt.query(params)?;
+    while let Some(row) = rows.next()? {
+        let mut object = Map::new();
+        object.insert("table".to_string(), Value::from(table));
+        for (i, column) in columns.iter().enumerate() {
+            let value = match row.get_ref(i)? {
+                ValueRef::Null | ValueRef::Blob(_) => Value::Null,
+                ValueRef::Integer(n) => Value::from(n),
+                ValueRef::Real(x) => Value::from(x),
+                ValueRef::Text(text) => Value::from(String::from_utf8_lossy(text)),
+            };
+            object.insert(column.clone(), value);
+        }
+        writeln!(out, "{}", Value::Object(object))?;
+    }
+    Ok(())
+}
+
+fn file_size(path: &Path) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag::{self, init_db};
+    use crate::journal::{self, JournalEntry};
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    fn entry(run_id: &str, task_id: &str, notes: &str, created_at: &str) -> JournalEntry {
+        JournalEntry {
+            id: 0,
+            run_id: run_id.to_string(),
+            iteration: 1,
+            task_id: Some(task_id.to_string()),
+            feature_id: None,
+            outcome: "done".to_string(),
+            model: None,
+            duration_secs: 1.0,
+            cost_usd: 0.0,
+            input_tokens: 0,
+            output_tokens: 0,
+            files_modified: Vec::new(),
+            files_read: Vec::new(),
+            notes: Some(notes.to_string()),
+            created_at: created_at.to_string(),
+        }
+    }
+
+    #[test]
+    fn archives_and_deletes_old_history_only() {
+        let dir = TempDir::new().unwrap();
+        let db_path = dir.path().join("progress.db");
+        let db = init_db(db_path.to_str().unwrap()).unwrap();
+        let done = dag::create_task(&db, "Old work", None, None, 0).unwrap();
+        let pending = dag::create_task(&db, "Still open", None, None, 0).unwrap();
+        dag::force_complete_task(db.conn(), &done.id).unwrap();
+        let old = "2020-01-01T00:00:00+00:00";
+        let now = chrono::Utc::now().to_rfc3339();
+        for (run, notes, at) in [
+            ("run-1", "ancient parser notes", old),
+            ("run-2", "old but recent run", old),
+            ("run-3", "fresh notes", now.as_str()),
+        ] {
+            journal::insert_journal_entry(&db, &entry(run, &done.id, notes, at)).unwrap();
+        }
+        for run in ["run-1", "run-3"] {
+            let summary = journal::RollingSummary {
+                run_id: run.to_string(),
+                iteration: 1,
+                summary: format!("{run} so far"),
+                model: None,
+                cost_usd: 0.0,
+            };
+            journal::insert_rolling_summary(&db, &summary).unwrap();
+        }
+        for task_id in [&done.id, &pending.id] {
+            db.conn()
+                .execute(
+                    "INSERT INTO task_logs (task_id, message, timestamp) VALUES (?, 'old', ?)",
+                    rusqlite::params![task_id, old],
+                )
+                .unwrap();
+        }
+        let options = PruneOptions {
+            keep_days: 90,
+            keep_runs: 2,
+            dry_run: true,
+        };
+
+        let dry = prune(&db, &db_path, dir.path(), options).unwrap();
+        assert_eq!((dry.journal_entries, dry.runs, dry.task_logs), (1, 1, 1));
+        assert!(dry.archive.is_none());
+        let count = |sql: &str| -> i64 { db.conn().query_row(sql, [], |r| r.get(0)).unwrap() };
+        assert_eq!(count("SELECT count(*) FROM journal"), 3);
+
+        let report = prune(
+            &db,
+            &db_path,
+            dir.path(),
+            PruneOptions {
+                dry_run: false,
+                ..options
+            },
+        )
+        .unwrap();
+        assert_eq!((report.journal_entries, report.task_logs), (1, 1));
+        assert_eq!(count("SELECT count(*) FROM journal"), 2);
+        assert_eq!(count("SELECT count(*) FROM task_logs"), 1);
+        assert_eq!(
+            journal::latest_rolling_summary(&db, "run-1").unwrap(),
+            None,
+            "summaries go with their run's journal"
+        );
+        assert!(journal::latest_rolling_summary(&db, "run-3")
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            count("SELECT count(*) FROM journal_fts WHERE journal_fts MATCH 'ancient'"),
+            0
+        );
+
+        let mut lines = String::new();
+        flate2::read::GzDecoder::new(std::fs::File::open(report.archive.unwrap()).unwrap())
+            .read_to_string(&mut lines)
+            .unwrap();
+        let rows: Vec<Value> = lines
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0]["table"], "journal");
+        assert_eq!(rows[0]["notes"], "ancient parser notes");
+        assert_eq!(rows[1]["table"], "run_summaries");
+        assert_eq!(rows[1]["summary"], "run-1 so far");
+        assert_eq!(rows[2]["table"], "task_logs");
+        assert_eq!(rows[2]["task_id"], done.id.as_str());
+    }
+}
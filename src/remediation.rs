@@ -0,0 +1,743 @@
+//! Escalating a blocked run to a planning session.
+//!
+//! A run ends blocked when no task is ready but work remains. When failed
+//! tasks are the cause, a short planning session reads their failure logs,
+//! journal notes and criteria and proposes edits to the DAG in a
+//! `<dag-edits>` sigil: remediation tasks, dependency changes and retries.
+//! Ralph previews the edits and applies them, all or nothing, once the user
+//! accepts.
+//!
+//! A blocked `ralph run` offers the session; `--auto-replan` starts it
+//! without asking.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::acp;
+use crate::config::RunTarget;
+use crate::dag::{self, Db, Task};
+use crate::feature;
+use crate::feature_prompts::{
+    build_initial_message_unblock, build_unblock_system_prompt, truncate_context,
+};
+use crate::input;
+use crate::journal;
+use crate::output::formatter;
+use crate::project::RalphConfig;
+use crate::prompt_templates;
+use crate::task_proposal::{self, Review};
+
+const START_TAG: &str = "<dag-edits>";
+const END_TAG: &str = "</dag-edits>";
+
+/// Model for the planning session.
+const PLANNING_MODEL: &str = "opus";
+
+/// Log messages and journal notes shown per failed task.
+const MAX_FAILURE_ENTRIES: usize = 3;
+
+/// Characters of each log message or journal note in the prompt.
+const MAX_NOTE_CHARS: usize = 2_000;
+
+/// A pending task that cannot start because work it waits on failed.
+#[derive(Debug)]
+pub struct StuckTask {
+    pub task: Task,
+    /// Failed tasks it waits on, directly or through unfinished blockers.
+    pub failed: Vec<Task>,
+}
+
+/// One remediation task to create.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewTask {
+    /// Short name edits use to refer to the task.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub criteria: Vec<String>,
+    /// Existing task to nest it under; defaults to the first stuck task's parent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    /// Keys or task IDs that must finish first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+}
+
+/// A dependency edge; either end is a task ID or a new task's key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Edge {
+    pub blocker: String,
+    pub blocked: String,
+}
+
+/// The DAG edits a planning session proposed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DagEdits {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tasks: Vec<NewTask>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub add_dependencies: Vec<Edge>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove_dependencies: Vec<Edge>,
+    /// Failed tasks to reset to pending.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub retry: Vec<String>,
+}
+
+/// What [`apply`] changed.
+#[derive(Debug, Default, PartialEq)]
+pub struct ApplyReport {
+    pub created: Vec<String>,
+    pub added: usize,
+    pub removed: usize,
+    pub retried: usize,
+}
+
+impl DagEdits {
+    /// The edits as pretty JSON, for handing back to the agent.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// One line per edit for the explorer or the terminal.
+    pub fn render_lines(&self) -> Vec<String> {
+        let mut lines = vec!["Proposed DAG edits".to_string()];
+        for task in &self.tasks {
+            let mut line = format!("+ task  {}", task.title);
+            if let Some(key) = &task.key {
+                line.push_str(&format!("  [{key}]"));
+            }
+            if let Some(parent) = &task.parent {
+                line.push_str(&format!("  under {parent}"));
+            }
+            if !task.depends_on.is_empty() {
+                line.push_str(&format!("  after: {}", task.depends_on.join(", ")));
+            }
+            lines.push(line);
+            for criterion in &task.criteria {
+                lines.push(format!("        · {criterion}"));
+            }
+        }
+        for edge in &self.add_dependencies {
+            lines.push(format!(
+                "+ dep   {} \u{2192} {}",
+                edge.blocker, edge.blocked
+            ));
+        }
+        for edge in &self.remove_dependencies {
+            lines.push(format!(
+                "- dep   {} \u{2192} {}",
+                edge.blocker, edge.blocked
+            ));
+        }
+        for id in &self.retry {
+            lines.push(format!("\u{21bb} retry {id}"));
+        }
+        lines
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+            && self.add_dependencies.is_empty()
+            && self.remove_dependencies.is_empty()
+            && self.retry.is_empty()
+    }
+
+    /// Check every reference against the DAG, then apply the edits in a
+    /// transaction that is rolled back, so cycles and other database errors
+    /// surface before the user is asked.
+    pub fn validate(&self, db: &Db, default_parent: Option<&str>) -> Result<()> {
+        if self.is_empty() {
+            bail!("The proposal contains no edits");
+        }
+        let mut keys = HashSet::new();
+        for task in &self.tasks {
+            if task.title.trim().is_empty() {
+                bail!("A proposed task has an empty title");
+            }
+            if let Some(key) = &task.key {
+                if key.starts_with("t-") {
+                    bail!("Task key '{key}' looks like a task ID; pick another");
+                }
+                if !keys.insert(key.as_str()) {
+                    bail!("Duplicate task key '{key}'");
+                }
+            }
+            if let Some(parent) = &task.parent {
+                let parent = existing(db, parent)?;
+                if parent.status == "done" {
+                    bail!(
+                        "'{}' cannot go under {}, which is done",
+                        task.title,
+                        parent.id
+                    );
+                }
+            }
+        }
+        let check_ref = |reference: &str| -> Result<()> {
+            if !keys.contains(reference) {
+                existing(db, reference)?;
+            }
+            Ok(())
+        };
+        for task in &self.tasks {
+            for dep in &task.depends_on {
+                check_ref(dep)?;
+            }
+        }
+        for edge in &self.add_dependencies {
+            check_ref(&edge.blocker)?;
+            check_ref(&edge.blocked)?;
+        }
+        for edge in &self.remove_dependencies {
+            let found: i64 = db.conn().query_row(
+                "SELECT COUNT(*) FROM dependencies WHERE blocker_id = ? AND blocked_id = ?",
+                [&edge.blocker, &edge.blocked],
+                |row| row.get(0),
+            )?;
+            if found == 0 {
+                bail!(
+                    "There is no dependency {} \u{2192} {} to remove",
+                    edge.blocker,
+                    edge.blocked
+                );
+            }
+        }
+        for id in &self.retry {
+            let task = existing(db, id)?;
+            if task.status != "failed" {
+                bail!("Only failed tasks can be retried; {id} is {}", task.status);
+            }
+        }
+
+        let _tx = db.conn().unchecked_transaction()?;
+        apply_edits(db, self, default_parent, 0, None)?;
+        Ok(())
+    }
+}
+
+fn existing(db: &Db, id: &str) -> Result<Task> {
+    dag::get_task(db, id)
+        .with_context(|| format!("'{id}' is neither a new task key nor an existing task"))
+}
+
+/// The pending tasks in scope of `target` (all tasks without one) that wait
+/// on a failed task.
+pub fn find_stuck(db: &Db, target: Option<&RunTarget>) -> Result<Vec<StuckTask>> {
+    let tasks = dag::get_all_tasks(db)?;
+    let scope: Option<HashSet<String>> = match target {
+        Some(RunTarget::Task(id)) => Some(
+            dag::get_task_tree(db, id)?
+                .into_iter()
+                .map(|t| t.id)
+                .collect(),
+        ),
+        Some(RunTarget::Features { names, .. }) => {
+            let mut feature_ids = HashSet::new();
+            for name in names {
+                feature_ids.insert(feature::get_feature(db, name)?.id);
+            }
+            Some(
+                tasks
+                    .iter()
+                    .filter(|t| {
+                        t.feature_id
+                            .as_ref()
+                            .is_some_and(|f| feature_ids.contains(f))
+                    })
+                    .map(|t| t.id.clone())
+                    .collect(),
+            )
+        }
+        None => None,
+    };
+    let parents: HashSet<&str> = tasks
+        .iter()
+        .filter_map(|t| t.parent_id.as_deref())
+        .collect();
+
+    let mut stuck = Vec::new();
+    for task in &tasks {
+        if task.status != "pending"
+            || parents.contains(task.id.as_str())
+            || scope.as_ref().is_some_and(|s| !s.contains(&task.id))
+        {
+            continue;
+        }
+        let failed = failed_blockers(db, &task.id)?;
+        if !failed.is_empty() {
+            stuck.push(StuckTask {
+                task: task.clone(),
+                failed,
+            });
+        }
+    }
+    Ok(stuck)
+}
+
+/// Failed tasks `task_id` waits on, following blockers that are not done.
+fn failed_blockers(db: &Db, task_id: &str) -> Result<Vec<Task>> {
+    let mut failed = Vec::new();
+    let mut seen = HashSet::new();
+    let mut stack = vec![task_id.to_string()];
+    while let Some(id) = stack.pop() {
+        for blocker in dag::get_task_blockers(db, &id)? {
+            if !seen.insert(blocker.id.clone()) {
+                continue;
+            }
+            match blocker.status.as_str() {
+                "failed" => failed.push(blocker),
+                "done" => {}
+                _ => stack.push(blocker.id),
+            }
+        }
+    }
+    Ok(failed)
+}
+
+/// The blocked tasks and, for each failed task, its description, criteria,
+/// latest failure log messages and journal notes, for the planning prompt.
+pub fn render_failures(db: &Db, stuck: &[StuckTask]) -> Result<String> {
+    let mut out = String::from("## Blocked Tasks\n\n");
+    let mut failed: Vec<&Task> = Vec::new();
+    for s in stuck {
+        let ids: Vec<String> = s.failed.iter().map(|t| format!("`{}`", t.id)).collect();
+        out.push_str(&format!(
+            "- `{}` {} \u{2014} waits on failed {}\n",
+            s.task.id,
+            s.task.title,
+            ids.join(", ")
+        ));
+        for task in &s.failed {
+            if !failed.iter().any(|t| t.id == task.id) {
+                failed.push(task);
+            }
+        }
+    }
+
+    out.push_str("\n## Failed Tasks\n");
+    for task in failed {
+        out.push_str(&format!(
+            "\n### `{}` {}\n\nAttempts: {} of {}\n",
+            task.id,
+            task.title,
+            task.retry_count + 1,
+            task.max_retries + 1
+        ));
+        if !task.description.trim().is_empty() {
+            out.push_str(&format!("\n{}\n", task.description.trim()));
+        }
+        let criteria = dag::get_task_criteria(db, &task.id)?;
+        if !criteria.is_empty() {
+            out.push_str("\nAcceptance criteria:\n");
+            for c in criteria {
+                out.push_str(&format!("- [{}] {}", c.status, c.description));
+                if let Some(note) = c.note {
+                    out.push_str(&format!(" \u{2014} {note}"));
+                }
+                out.push('\n');
+            }
+        }
+        let logs = dag::get_task_logs(db, &task.id)?;
+        if !logs.is_empty() {
+            out.push_str("\nFailure log (latest last):\n");
+            for log in logs
+                .iter()
+                .skip(logs.len().saturating_sub(MAX_FAILURE_ENTRIES))
+            {
+                let message = truncate_context(&log.message, MAX_NOTE_CHARS, "the task log");
+                out.push_str(&format!("> {}\n", message.replace('\n', "\n> ")));
+            }
+        }
+        let entries = journal::query_journal_for_task(db, &task.id, MAX_FAILURE_ENTRIES as u32)?;
+        let notes: Vec<_> = entries
+            .iter()
+            .filter_map(|e| Some((e, e.notes.as_deref()?.trim())))
+            .filter(|(_, notes)| !notes.is_empty())
+            .collect();
+        if !notes.is_empty() {
+            out.push_str("\nJournal notes (newest first):\n");
+            for (entry, notes) in notes {
+                let notes = truncate_context(notes, MAX_NOTE_CHARS, "the journal");
+                out.push_str(&format!(
+                    "- {} iteration {} ({}): {}\n",
+                    entry.run_id,
+                    entry.iteration,
+                    entry.outcome,
+                    notes.replace('\n', "\n  ")
+                ));
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Extract and validate the last `<dag-edits>` sigil in `text`.
+pub fn parse(text: &str, db: &Db, default_parent: Option<&str>) -> Result<DagEdits> {
+    let start = text
+        .rfind(START_TAG)
+        .context("The agent did not emit a <dag-edits> sigil")?
+        + START_TAG.len();
+    let end = text[start..]
+        .find(END_TAG)
+        .context("The <dag-edits> sigil is not closed")?;
+    let body = text[start..start + end].trim();
+    let body = body
+        .strip_prefix("```json")
+        .or_else(|| body.strip_prefix("```"))
+        .and_then(|b| b.trim_end().strip_suffix("```"))
+        .unwrap_or(body);
+    let edits: DagEdits =
+        serde_json::from_str(body).context("The <dag-edits> sigil is not valid JSON")?;
+    edits.validate(db, default_parent)?;
+    Ok(edits)
+}
+
+/// Apply accepted edits, all or nothing. New tasks without a `parent` go
+/// under `default_parent`, and take its feature.
+pub fn apply(
+    db: &Db,
+    edits: &DagEdits,
+    default_parent: Option<&str>,
+    max_retries: i32,
+    created_by: Option<&str>,
+) -> Result<ApplyReport> {
+    let tx = db.conn().unchecked_transaction()?;
+    let report = apply_edits(db, edits, default_parent, max_retries, created_by)?;
+    tx.commit()?;
+    Ok(report)
+}
+
+fn apply_edits(
+    db: &Db,
+    edits: &DagEdits,
+    default_parent: Option<&str>,
+    max_retries: i32,
+    created_by: Option<&str>,
+) -> Result<ApplyReport> {
+    let mut report = ApplyReport::default();
+    let mut keys: HashMap<&str, String> = HashMap::new();
+    let mut touched: Vec<String> = Vec::new();
+    for new in &edits.tasks {
+        let parent = new
+            .parent
+            .as_deref()
+            .or(default_parent)
+            .map(|id| dag::get_task(db, id))
+            .transpose()?;
+        let description = (!new.description.trim().is_empty()).then_some(new.description.as_str());
+        let task = dag::create_task_with_feature(
+            db,
+            dag::CreateTaskParams {
+                title: new.title.trim(),
+                description,
+                parent_id: parent.as_ref().map(|p| p.id.as_str()),
+                priority: 0,
+                feature_id: parent.as_ref().and_then(|p| p.feature_id.as_deref()),
+                task_type: parent
+                    .as_ref()
+                    .map_or("standalone", |p| p.task_type.as_str()),
+                max_retries,
+                criteria: &new.criteria,
+                discovered_by: None,
+                cwd: None,
+                created_by,
+            },
+        )?;
+        if let Some(key) = &new.key {
+            keys.insert(key, task.id.clone());
+        }
+        touched.push(task.id.clone());
+        report.created.push(task.id);
+    }
+    let resolve = |reference: &str| {
+        keys.get(reference)
+            .cloned()
+            .unwrap_or_else(|| reference.to_string())
+    };
+
+    for (new, id) in edits.tasks.iter().zip(&report.created) {
+        for dep in &new.depends_on {
+            dag::add_dependency(db, &resolve(dep), id)?;
+        }
+    }
+    for edge in &edits.add_dependencies {
+        dag::add_dependency(db, &resolve(&edge.blocker), &resolve(&edge.blocked))?;
+        report.added += 1;
+    }
+    for edge in &edits.remove_dependencies {
+        dag::remove_dependency(db, &edge.blocker, &edge.blocked)?;
+        report.removed += 1;
+    }
+    for id in &edits.retry {
+        dag::force_reset_task(db.conn(), id)?;
+        touched.push(id.clone());
+        report.retried += 1;
+    }
+
+    // Parents that failed with a retried child, or finished before gaining a
+    // new one, are re-derived; so is a failed feature.
+    let mut roots = HashSet::new();
+    for id in &touched {
+        let mut task = dag::get_task(db, id)?;
+        while let Some(parent) = &task.parent_id {
+            task = dag::get_task(db, parent)?;
+        }
+        if let Some(feature_id) = &task.feature_id {
+            db.conn().execute(
+                "UPDATE features SET status = 'ready', updated_at = datetime('now')
+                 WHERE id = ? AND status IN ('failed', 'done')",
+                [feature_id],
+            )?;
+        }
+        if roots.insert(task.id.clone()) {
+            task_proposal::refresh_parent_statuses(db, &task.id)?;
+        }
+    }
+    Ok(report)
+}
+
+/// After a blocked run, offer a planning session for the tasks that failed
+/// blockers hold up (`auto` skips the question) and apply the edits the user
+/// accepts. Returns whether the DAG changed.
+pub async fn escalate(
+    db: &Db,
+    project_root: &Path,
+    config: &RalphConfig,
+    target: Option<&RunTarget>,
+    agent_command: &str,
+    user: Option<&str>,
+    auto: bool,
+) -> Result<bool> {
+    let stuck = find_stuck(db, target)?;
+    if stuck.is_empty() {
+        return Ok(false);
+    }
+    for s in &stuck {
+        let ids: Vec<&str> = s.failed.iter().map(|t| t.id.as_str()).collect();
+        formatter::print_warning(&format!("{} waits on failed {}", s.task.id, ids.join(", ")));
+    }
+    if !auto {
+        let question = "Start a planning session to propose fixes for the failed tasks?";
+        let start = if input::assume_yes() {
+            true
+        } else if input::no_input() {
+            false
+        } else if crate::ui::is_active() {
+            crate::ui::prompt_confirm("Run Blocked", question, false).unwrap_or(false)
+        } else {
+            input::confirm(question, false)
+        };
+        if !start {
+            formatter::print_info(
+                "Pass --auto-replan to let a planning session propose fixes when a run is blocked.",
+            );
+            return Ok(false);
+        }
+    }
+
+    let default_parent = stuck[0].task.parent_id.clone();
+    let default_parent = default_parent.as_deref();
+    let max_retries = config.execution.max_retries as i32;
+    let failures = render_failures(db, &stuck)?;
+    let system_prompt = prompt_templates::apply(
+        project_root,
+        "unblock",
+        build_unblock_system_prompt(&failures),
+        &[("failures", &failures)],
+    );
+    let mut message = build_initial_message_unblock(None);
+    let mut invalid_proposals = 0;
+    let edits = loop {
+        formatter::print_info("Planning session: diagnosing the failed tasks");
+        let agent_text = acp::interactive::run_streaming(
+            agent_command,
+            &system_prompt,
+            &message,
+            project_root,
+            Some(PLANNING_MODEL),
+        )
+        .await?;
+        let edits = match parse(&agent_text, db, default_parent) {
+            Ok(edits) => edits,
+            Err(e) if invalid_proposals < task_proposal::MAX_INVALID_PROPOSALS => {
+                invalid_proposals += 1;
+                formatter::print_warning(&format!(
+                    "Invalid DAG edits: {e:#}. Asking the agent to fix them."
+                ));
+                message = format!(
+                    "Your DAG edits were invalid: {e:#}. Emit corrected <dag-edits> and stop."
+                );
+                continue;
+            }
+            Err(e) => {
+                formatter::print_warning(&format!(
+                    "The planning session did not propose valid DAG edits: {e:#}"
+                ));
+                return Ok(false);
+            }
+        };
+        let hint = "Accept to apply these edits, or start typing to ask for changes.";
+        match task_proposal::review_lines(
+            "Proposed DAG Edits",
+            edits.render_lines(),
+            hint,
+            "Apply these edits?",
+        ) {
+            Review::Accept => break edits,
+            Review::Revise(feedback) => {
+                message = build_initial_message_unblock(Some((&edits.to_json(), &feedback)));
+            }
+            Review::Reject => {
+                formatter::print_info("DAG edits rejected; nothing changed.");
+                return Ok(false);
+            }
+        }
+    };
+
+    let report = apply(db, &edits, default_parent, max_retries, user)?;
+    formatter::print_info(&format!(
+        "DAG updated: {} task(s) created, {} dependency(ies) added, {} removed, {} task(s) retried",
+        report.created.len(),
+        report.added,
+        report.removed,
+        report.retried
+    ));
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag::init_db;
+    use tempfile::TempDir;
+
+    /// A feature with two leaves where `api` waits on `schema`, which failed.
+    fn blocked_feature(db: &Db) -> (Task, Task, Task) {
+        let feat = feature::create_feature(db, "users").unwrap();
+        let task = |title: &str, parent: Option<&str>| {
+            let task = dag::create_test_task(db, &feat.id, title, parent);
+            dag::add_criteria(db, &task.id, &["cargo test passes".to_string()]).unwrap();
+            task
+        };
+        let root = task("Feature: users", None);
+        let schema = task("Users table", Some(&root.id));
+        let api = task("User endpoint", Some(&root.id));
+        dag::add_dependency(db, &schema.id, &api.id).unwrap();
+        dag::claim_task(db, &schema.id, "agent-test").unwrap();
+        dag::fail_task(db, &schema.id, "sqlx 0.8 removed query_as!").unwrap();
+        (root, schema, api)
+    }
+
+    #[test]
+    fn finds_tasks_held_up_by_failures_and_renders_them() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let (_, schema, api) = blocked_feature(&db);
+
+        let target = RunTarget::Features {
+            names: vec!["users".to_string()],
+            order: crate::cli::FeatureOrder::Sequential,
+        };
+        let stuck = find_stuck(&db, Some(&target)).unwrap();
+        assert_eq!(stuck.len(), 1);
+        assert_eq!(stuck[0].task.id, api.id);
+        assert_eq!(stuck[0].failed[0].id, schema.id);
+        let other = RunTarget::Task(schema.id.clone());
+        assert!(find_stuck(&db, Some(&other)).unwrap().is_empty());
+
+        let text = render_failures(&db, &stuck).unwrap();
+        assert!(text.contains(&format!(
+            "- `{}` User endpoint \u{2014} waits on failed `{}`",
+            api.id, schema.id
+        )));
+        assert!(text.contains("Attempts: 1 of 4"));
+        assert!(text.contains("- [pending] cargo test passes"));
+        assert!(text.contains("> sqlx 0.8 removed query_as!"));
+    }
+
+    #[test]
+    fn accepted_edits_unblock_the_run() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let (root, schema, _) = blocked_feature(&db);
+        assert_eq!(dag::get_task(&db, &root.id).unwrap().status, "failed");
+
+        let text = format!(
+            "Cause found.\n<dag-edits>\n```json\n{{\"tasks\": [{{\"key\": \"pin\", \
+             \"title\": \"Pin sqlx to 0.7\", \"criteria\": [\"cargo build succeeds\"]}}], \
+             \"add_dependencies\": [{{\"blocker\": \"pin\", \"blocked\": \"{schema}\"}}], \
+             \"retry\": [\"{schema}\"]}}\n```\n</dag-edits>",
+            schema = schema.id
+        );
+        let edits = parse(&text, &db, Some(&root.id)).unwrap();
+        assert_eq!(
+            edits.render_lines()[1..],
+            [
+                "+ task  Pin sqlx to 0.7  [pin]".to_string(),
+                "        · cargo build succeeds".to_string(),
+                format!("+ dep   pin \u{2192} {}", schema.id),
+                format!("\u{21bb} retry {}", schema.id),
+            ]
+        );
+        // Validation rolled its trial run back
+        assert_eq!(dag::get_all_tasks(&db).unwrap().len(), 3);
+
+        let report = apply(&db, &edits, Some(&root.id), 3, None).unwrap();
+        assert_eq!(
+            (report.created.len(), report.added, report.retried),
+            (1, 1, 1)
+        );
+        let pin = dag::get_task(&db, &report.created[0]).unwrap();
+        assert_eq!(pin.parent_id.as_deref(), Some(root.id.as_str()));
+        assert_eq!(pin.feature_id, root.feature_id);
+        assert_eq!(dag::get_task(&db, &schema.id).unwrap().status, "pending");
+        assert_eq!(dag::get_task(&db, &root.id).unwrap().status, "pending");
+        let ready: Vec<String> = dag::get_ready_tasks(&db)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.id)
+            .collect();
+        assert_eq!(ready, vec![pin.id]);
+        assert!(find_stuck(&db, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_edits_that_do_not_fit_the_dag() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let (root, schema, api) = blocked_feature(&db);
+        let err = |json: String| {
+            parse(
+                &format!("<dag-edits>{json}</dag-edits>"),
+                &db,
+                Some(&root.id),
+            )
+            .unwrap_err()
+            .to_string()
+        };
+
+        assert!(err("{}".into()).contains("no edits"));
+        assert!(err(format!("{{\"retry\": [\"{}\"]}}", api.id)).contains("Only failed tasks"));
+        assert!(err(
+            "{\"add_dependencies\": [{\"blocker\": \"fix\", \"blocked\": \"t-nope\"}]}".into()
+        )
+        .contains("'fix' is neither"));
+        assert!(err(format!(
+            "{{\"remove_dependencies\": [{{\"blocker\": \"{}\", \"blocked\": \"{}\"}}]}}",
+            api.id, schema.id
+        ))
+        .contains("no dependency"));
+        assert!(err(format!(
+            "{{\"add_dependencies\": [{{\"blocker\": \"{}\", \"blocked\": \"{}\"}}]}}",
+            api.id, schema.id
+        ))
+        .contains("cycle"));
+        assert!(parse("no sigil", &db, None).is_err());
+    }
+}
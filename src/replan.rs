@@ -0,0 +1,285 @@
+//! Inputs for `ralph feature replan`: what changed in the code since a
+//! feature's plan was written, and the current state of its task DAG.
+//!
+//! The baseline is the commit recorded with the latest plan revision
+//! ([`Feature::plan_commit`]). Plans recorded before that column existed fall
+//! back to the last commit made before the revision file was written.
+
+use anyhow::{bail, Result};
+use rusqlite::OptionalExtension;
+use std::path::Path;
+use std::process::Command;
+
+use crate::dag::{self, Db, Task};
+use crate::feature::{self, Feature};
+use crate::feature_prompts::truncate_context;
+use crate::review::DocumentKind;
+
+/// Characters of raw diff handed to the planning agent; the stat and commit
+/// list above it are always complete.
+const MAX_DIFF_CHARS: usize = 30_000;
+
+/// Changes under `.ralph/` (the plan itself, the progress database) are not
+/// code drift.
+const EXCLUDE_RALPH: &str = ":(exclude).ralph";
+
+/// The commit the feature's current plan was written against.
+pub fn plan_baseline(project_root: &Path, feat: &Feature) -> Result<String> {
+    if git(project_root, &["rev-parse", "--git-dir"]).is_none() {
+        bail!(
+            "{} is not a git repository; replanning needs git history",
+            project_root.display()
+        );
+    }
+    if let Some(commit) = &feat.plan_commit {
+        if git(
+            project_root,
+            &["cat-file", "-e", &format!("{commit}^{{commit}}")],
+        )
+        .is_some()
+        {
+            return Ok(commit.clone());
+        }
+    }
+    let written = (feat.plan_revision > 0)
+        .then(|| {
+            feature::revision_path(
+                project_root,
+                &feat.name,
+                DocumentKind::Plan,
+                feat.plan_revision,
+            )
+        })
+        .and_then(|path| std::fs::metadata(path).ok()?.modified().ok());
+    let Some(written) = written else {
+        bail!(
+            "Feature '{}' has no recorded plan revision to compare against",
+            feat.name
+        );
+    };
+    let before = chrono::DateTime::<chrono::Utc>::from(written).to_rfc3339();
+    match git(
+        project_root,
+        &["rev-list", "-1", &format!("--before={before}"), "HEAD"],
+    ) {
+        Some(commit) if !commit.is_empty() => Ok(commit),
+        _ => bail!("No commit predates the plan of feature '{}'", feat.name),
+    }
+}
+
+/// Markdown summary of the code changes since `base`, committed or not:
+/// commits, a diffstat, new untracked files and the (truncated) diff. `None`
+/// when nothing changed.
+pub fn code_drift(project_root: &Path, base: &str) -> Option<String> {
+    let short = &base[..base.len().min(12)];
+    let commits = git(
+        project_root,
+        &[
+            "log",
+            "--oneline",
+            "--no-decorate",
+            &format!("{base}..HEAD"),
+        ],
+    )
+    .unwrap_or_default();
+    let stat = git(
+        project_root,
+        &["diff", "--stat", base, "--", ".", EXCLUDE_RALPH],
+    )
+    .unwrap_or_default();
+    let untracked = git(
+        project_root,
+        &[
+            "ls-files",
+            "--others",
+            "--exclude-standard",
+            "--",
+            ".",
+            EXCLUDE_RALPH,
+        ],
+    )
+    .unwrap_or_default();
+    if commits.is_empty() && stat.is_empty() && untracked.is_empty() {
+        return None;
+    }
+
+    let mut out = format!("Changes since {short}, when the plan was last written.\n");
+    if !commits.is_empty() {
+        out.push_str(&format!("\n### Commits\n\n```\n{commits}\n```\n"));
+    }
+    if !stat.is_empty() {
+        out.push_str(&format!("\n### Changed Files\n\n```\n{stat}\n```\n"));
+    }
+    if !untracked.is_empty() {
+        out.push_str(&format!(
+            "\n### New Untracked Files\n\n```\n{untracked}\n```\n"
+        ));
+    }
+    let diff = git(project_root, &["diff", base, "--", ".", EXCLUDE_RALPH]).unwrap_or_default();
+    if !diff.is_empty() {
+        let diff = truncate_context(&diff, MAX_DIFF_CHARS, &format!("`git diff {short}`"));
+        out.push_str(&format!("\n### Diff\n\n```diff\n{diff}\n```\n"));
+    }
+    Some(out)
+}
+
+/// The feature's root task (`Feature: <name>`), if its DAG was built.
+pub fn root_task(db: &Db, feat: &Feature) -> Result<Option<Task>> {
+    let id: Option<String> = db
+        .conn()
+        .query_row(
+            "SELECT id FROM tasks
+             WHERE feature_id = ? AND parent_id IS NULL AND archived_at IS NULL
+             ORDER BY created_at DESC LIMIT 1",
+            [&feat.id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    id.map(|id| dag::get_task(db, &id)).transpose()
+}
+
+/// The tasks under `root_id` as an indented list with IDs, statuses,
+/// descriptions and criteria, for the reconciliation prompt.
+pub fn render_existing_tasks(db: &Db, root_id: &str) -> Result<String> {
+    let tree = dag::get_task_tree(db, root_id)?;
+    let mut out = String::new();
+    render_children(db, &tree, root_id, 0, &mut out)?;
+    if out.is_empty() {
+        out.push_str("(none)\n");
+    }
+    Ok(out)
+}
+
+fn render_children(
+    db: &Db,
+    tree: &[Task],
+    parent_id: &str,
+    depth: usize,
+    out: &mut String,
+) -> Result<()> {
+    let indent = "  ".repeat(depth);
+    for task in tree
+        .iter()
+        .filter(|t| t.parent_id.as_deref() == Some(parent_id))
+    {
+        out.push_str(&format!(
+            "{indent}- `{}` [{}] {}",
+            task.id, task.status, task.title
+        ));
+        if let Some(cwd) = &task.cwd {
+            out.push_str(&format!(" (cwd: `{cwd}`)"));
+        }
+        if let Some(plan_ref) = &task.plan_ref {
+            out.push_str(&format!(" (plan_ref: `{plan_ref}`)"));
+        }
+        out.push('\n');
+        let description = task.description.trim();
+        if !description.is_empty() {
+            out.push_str(&format!(
+                "{indent}  {}\n",
+                description.replace('\n', &format!("\n{indent}  "))
+            ));
+        }
+        for criterion in dag::get_task_criteria(db, &task.id)? {
+            out.push_str(&format!("{indent}  · {}\n", criterion.description));
+        }
+        render_children(db, tree, &task.id, depth + 1, out)?;
+    }
+    Ok(())
+}
+
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    out.status
+        .success()
+        .then(|| String::from_utf8_lossy(&out.stdout).trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag::init_db;
+    use tempfile::TempDir;
+
+    fn run_git(dir: &Path, args: &[&str]) {
+        let ok = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status
+            .success();
+        assert!(ok, "git {:?} failed", args);
+    }
+
+    #[test]
+    fn drift_since_the_recorded_plan_commit() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        run_git(root, &["init", "-q"]);
+        std::fs::write(root.join("lib.rs"), "fn a() {}\n").unwrap();
+        run_git(root, &["add", "lib.rs"]);
+        run_git(root, &["commit", "-q", "-m", "init"]);
+
+        feature::ensure_feature_dirs(root, "api").unwrap();
+        let db = init_db(root.join(".ralph/progress.db").to_str().unwrap()).unwrap();
+        let feat = feature::create_feature(&db, "api").unwrap();
+        std::fs::write(root.join(".ralph/features/api/plan.md"), "# Plan\n").unwrap();
+        feature::record_revision(&db, root, &feat, DocumentKind::Plan).unwrap();
+        let feat = feature::get_feature(&db, "api").unwrap();
+        let base = plan_baseline(root, &feat).unwrap();
+        assert_eq!(feat.plan_commit.as_deref(), Some(base.as_str()));
+        assert_eq!(code_drift(root, &base), None);
+
+        std::fs::write(root.join("lib.rs"), "fn b() {}\n").unwrap();
+        run_git(root, &["commit", "-q", "-am", "Rename a to b"]);
+        std::fs::write(root.join("new.rs"), "fn c() {}\n").unwrap();
+        std::fs::write(root.join(".ralph/features/api/plan.md"), "# Plan v2\n").unwrap();
+
+        let drift = code_drift(root, &base).unwrap();
+        assert!(drift.contains("Rename a to b"), "{drift}");
+        assert!(drift.contains("lib.rs | 2 +-"), "{drift}");
+        assert!(drift.contains("### New Untracked Files\n\n```\nnew.rs\n```"));
+        assert!(drift.contains("+fn b() {}"));
+        assert!(!drift.contains("plan.md"));
+    }
+
+    #[test]
+    fn baseline_needs_git_and_a_plan_revision() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let feat = feature::create_feature(&db, "api").unwrap();
+        let err = plan_baseline(dir.path(), &feat).unwrap_err().to_string();
+        assert!(err.contains("not a git repository"), "{err}");
+
+        run_git(dir.path(), &["init", "-q"]);
+        let err = plan_baseline(dir.path(), &feat).unwrap_err().to_string();
+        assert!(err.contains("no recorded plan revision"), "{err}");
+    }
+
+    #[test]
+    fn renders_existing_tasks_as_an_indented_list() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let root = dag::create_task(&db, "Feature: api", None, None, 0).unwrap();
+        let parent = dag::create_task(&db, "Models", None, Some(&root.id), 0).unwrap();
+        let child = dag::create_task(&db, "Schema", Some("Tables"), Some(&parent.id), 0).unwrap();
+        dag::add_criteria(&db, &child.id, &["migration applies".to_string()]).unwrap();
+
+        assert_eq!(
+            render_existing_tasks(&db, &root.id).unwrap(),
+            format!(
+                "- `{}` [pending] Models\n  - `{}` [pending] Schema\n    Tables\n    · migration applies\n",
+                parent.id, child.id
+            )
+        );
+    }
+}
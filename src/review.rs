@@ -1,8 +1,20 @@
 //! Iterative review agent for spec and plan documents.
+//!
+//! Reviewers judge a document against built-in criteria, or against the
+//! project's own rubric when `.ralph/review/<kind>_rubric.md` exists. With a
+//! rubric, each round also scores every criterion; the scores are appended to
+//! `.ralph/features/<name>/review/<kind>-scores.jsonl` for audit.
+//!
+//! With `[review] reviewers` above 1, each round runs that many independent
+//! read-only reviewers. A document is approved once a quorum of them finds no
+//! blocking issues; otherwise an editor session applies their merged feedback
+//! and the next round reviews the result.
 
+use std::io::Write;
 use std::path::{Path, PathBuf};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
 
 use crate::acp;
 use crate::acp::connection::SessionRestrictions;
@@ -11,6 +23,85 @@ use crate::output::formatter;
 /// Maximum number of review rounds before stopping.
 const MAX_REVIEW_ROUNDS: u32 = 5;
 
+/// Project rubrics live here, as `spec_rubric.md` and `plan_rubric.md`.
+const RUBRIC_DIR: &str = ".ralph/review";
+
+/// Highest score a rubric criterion can get; scores run from 1.
+const MAX_SCORE: u8 = 5;
+
+/// Model for reviewers when `[review] models` is empty.
+const DEFAULT_REVIEW_MODEL: &str = "opus";
+
+/// Spec/plan review settings (`[review]`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReviewConfig {
+    /// Independent reviewers per round.
+    #[serde(default = "default_reviewers")]
+    pub reviewers: u32,
+    /// Models assigned to reviewers in turn; empty uses opus for all.
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// Reviewers that must raise no blocking issue; defaults to a majority.
+    #[serde(default)]
+    pub quorum: Option<u32>,
+    /// Review the task DAG against the plan after `feature create` builds it.
+    #[serde(default = "default_true")]
+    pub dag: bool,
+    /// Apply the DAG review's suggested edits without asking.
+    #[serde(default)]
+    pub dag_auto_apply: bool,
+}
+
+impl Default for ReviewConfig {
+    fn default() -> Self {
+        Self {
+            reviewers: default_reviewers(),
+            models: Vec::new(),
+            quorum: None,
+            dag: true,
+            dag_auto_apply: false,
+        }
+    }
+}
+
+fn default_reviewers() -> u32 {
+    1
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl ReviewConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.reviewers == 0 {
+            bail!("review.reviewers must be at least 1");
+        }
+        if let Some(quorum) = self.quorum {
+            if quorum == 0 || quorum > self.reviewers {
+                bail!(
+                    "review.quorum must be between 1 and review.reviewers ({}), got {quorum}",
+                    self.reviewers
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Approvals needed for a document to pass.
+    pub fn quorum(&self) -> u32 {
+        self.quorum.unwrap_or(self.reviewers / 2 + 1)
+    }
+
+    /// Model for reviewer `index` (0-based).
+    fn model(&self, index: u32) -> &str {
+        if self.models.is_empty() {
+            return DEFAULT_REVIEW_MODEL;
+        }
+        &self.models[index as usize % self.models.len()]
+    }
+}
+
 /// What kind of document is being reviewed.
 #[derive(Debug, Clone, Copy)]
 pub enum DocumentKind {
@@ -31,7 +122,9 @@ impl DocumentKind {
 ///
 /// Spawns autonomous ACP agent sessions that review and improve the document
 /// until either a review agent finds no major issues or the maximum number
-/// of rounds is reached.
+/// of rounds is reached. With several reviewers, running out of rounds
+/// without a quorum is an error: the document is not approved.
+#[allow(clippy::too_many_arguments)]
 pub async fn review_document(
     document_path: &str,
     kind: DocumentKind,
@@ -40,14 +133,17 @@ pub async fn review_document(
     project_context: &str,
     agent_command: &str,
     project_root: &Path,
+    config: &ReviewConfig,
 ) -> Result<u32> {
     let label = kind.label();
+    let rubric = load_rubric(project_root, kind)?;
 
     formatter::print_review_start(label, feature_name);
     formatter::emit_event_info(
         "review",
         &format!("{} review for \"{}\"", label, feature_name),
     );
+    let mut approvals = 0;
 
     for round in 1..=MAX_REVIEW_ROUNDS {
         formatter::print_review_round(round, MAX_REVIEW_ROUNDS, label);
@@ -65,9 +161,18 @@ pub async fn review_document(
             round,
             agent_command,
             project_root,
+            rubric: rubric.as_ref(),
         };
 
-        let result = run_review_agent(&req).await?;
+        let result = if config.reviewers > 1 {
+            run_consensus_round(&req, config).await?
+        } else {
+            run_review_agent(&req, config.model(0)).await?
+        };
+        approvals = result.approvals;
+        if let Some(rubric) = &rubric {
+            record_scores(project_root, feature_name, kind, rubric, round, &result)?;
+        }
 
         if result.passed {
             formatter::print_review_result(round, true, "", label);
@@ -93,6 +198,20 @@ pub async fn review_document(
         );
     }
 
+    if config.reviewers > 1 {
+        formatter::emit_event_info(
+            "review",
+            &format!("\"{}\" {} not approved: no consensus", feature_name, label),
+        );
+        bail!(
+            "{label} review for '{feature_name}' reached no consensus after {MAX_REVIEW_ROUNDS} rounds \
+             ({approvals} of {} reviewers approved, {} required). Revise {document_path} and re-run \
+             'ralph feature create {feature_name}', or lower review.quorum.",
+            config.reviewers,
+            config.quorum()
+        );
+    }
+
     formatter::print_review_max_rounds(label, feature_name, MAX_REVIEW_ROUNDS);
     formatter::emit_event_info(
         "review",
@@ -108,6 +227,126 @@ pub async fn review_document(
 struct ReviewResult {
     passed: bool,
     changes_summary: String,
+    /// Per-criterion scores; empty without a project rubric.
+    scores: Vec<CriterionScore>,
+    /// Reviewers that raised no blocking issue.
+    approvals: u32,
+}
+
+/// A project's review criteria for one document kind.
+#[derive(Debug)]
+pub struct Rubric {
+    /// Path relative to the project root, for prompts and audit records.
+    pub path: String,
+    pub text: String,
+    /// Criterion names, in file order.
+    pub criteria: Vec<String>,
+}
+
+/// The reviewer's score for one rubric criterion.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CriterionScore {
+    pub criterion: String,
+    pub score: u8,
+    #[serde(default)]
+    pub note: String,
+    /// 1-based reviewer that gave the score, with several reviewers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewer: Option<u32>,
+}
+
+/// Load `.ralph/review/<kind>_rubric.md`, if the project has one. A rubric
+/// without any `- Criterion: description` bullets is an error rather than a
+/// silent fallback to the built-in criteria.
+pub fn load_rubric(project_root: &Path, kind: DocumentKind) -> Result<Option<Rubric>> {
+    let path = format!("{RUBRIC_DIR}/{}_rubric.md", kind.label());
+    let text = match std::fs::read_to_string(project_root.join(&path)) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {path}")),
+    };
+    let criteria = parse_criteria(&text);
+    if criteria.is_empty() {
+        bail!("{path} defines no criteria (expected `- Criterion: description` list items)");
+    }
+    Ok(Some(Rubric {
+        path,
+        text: text.trim().to_string(),
+        criteria,
+    }))
+}
+
+/// Criterion names from top-level list items: `- **Name**: ...`,
+/// `- Name: ...` or just `- Name`.
+fn parse_criteria(text: &str) -> Vec<String> {
+    text.lines()
+        .filter_map(|line| line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")))
+        .filter_map(|item| {
+            let item = item.trim();
+            let name = match item.strip_prefix("**") {
+                Some(rest) => rest.split("**").next()?,
+                None => item.split(':').next()?,
+            };
+            let name = name.trim().trim_end_matches(':').trim();
+            (!name.is_empty()).then(|| name.to_string())
+        })
+        .collect()
+}
+
+/// Append one round's scores to the feature's audit file.
+fn record_scores(
+    project_root: &Path,
+    feature_name: &str,
+    kind: DocumentKind,
+    rubric: &Rubric,
+    round: u32,
+    result: &ReviewResult,
+) -> Result<()> {
+    let label = kind.label();
+    let missing: Vec<&str> = rubric
+        .criteria
+        .iter()
+        .filter(|c| !result.scores.iter().any(|s| &s.criterion == *c))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        formatter::emit_event_info(
+            "review",
+            &format!("{label} rubric: no score for {}", missing.join(", ")),
+        );
+    }
+    if !result.scores.is_empty() {
+        let summary: Vec<String> = result
+            .scores
+            .iter()
+            .map(|s| format!("{} {}/{MAX_SCORE}", s.criterion, s.score))
+            .collect();
+        formatter::emit_event_info(
+            "review",
+            &format!("{label} rubric round {round}: {}", summary.join(", ")),
+        );
+    }
+
+    let dir = project_root
+        .join(".ralph/features")
+        .join(feature_name)
+        .join("review");
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let path = dir.join(format!("{label}-scores.jsonl"));
+    let record = serde_json::json!({
+        "round": round,
+        "recorded_at": chrono::Utc::now().to_rfc3339(),
+        "rubric": rubric.path,
+        "passed": result.passed,
+        "scores": result.scores,
+        "unscored": missing,
+    });
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{record}"))
+        .with_context(|| format!("Failed to write {}", path.display()))
 }
 
 /// Inputs required to run one review round.
@@ -120,10 +359,11 @@ struct ReviewRoundRequest<'a> {
     round: u32,
     agent_command: &'a str,
     project_root: &'a Path,
+    rubric: Option<&'a Rubric>,
 }
 
 /// Run a single review agent on the document.
-async fn run_review_agent(req: &ReviewRoundRequest<'_>) -> Result<ReviewResult> {
+async fn run_review_agent(req: &ReviewRoundRequest<'_>, model: &str) -> Result<ReviewResult> {
     let system_prompt = build_review_prompt(
         req.document_path,
         req.kind,
@@ -131,6 +371,7 @@ async fn run_review_agent(req: &ReviewRoundRequest<'_>) -> Result<ReviewResult>
         req.spec_content,
         req.project_context,
         req.round,
+        req.rubric,
     );
 
     let message = format!(
@@ -144,8 +385,8 @@ async fn run_review_agent(req: &ReviewRoundRequest<'_>) -> Result<ReviewResult>
         req.project_root,
         &system_prompt,
         &message,
-        false,        // read_only = false (review agent can write)
-        Some("opus"), // matching current hardcoded --model opus behavior
+        false, // read_only = false (review agent can write)
+        Some(model),
         SessionRestrictions {
             allow_terminal: false, // review is document-only, no bash
             allowed_write_paths: Some(vec![PathBuf::from(req.document_path)]),
@@ -162,17 +403,25 @@ async fn run_review_agent(req: &ReviewRoundRequest<'_>) -> Result<ReviewResult>
     })?;
 
     let text = &result.full_text;
+    let scores = match req.rubric {
+        Some(rubric) => parse_rubric_scores(text, rubric),
+        None => Vec::new(),
+    };
 
     if parse_review_pass(text) {
         return Ok(ReviewResult {
             passed: true,
             changes_summary: String::new(),
+            scores,
+            approvals: 1,
         });
     }
     if let Some(summary) = parse_review_changes(text) {
         return Ok(ReviewResult {
             passed: false,
             changes_summary: summary,
+            scores,
+            approvals: 0,
         });
     }
 
@@ -184,6 +433,242 @@ async fn run_review_agent(req: &ReviewRoundRequest<'_>) -> Result<ReviewResult>
     ))
 }
 
+/// One reviewer's verdict in a consensus round.
+#[derive(Debug, PartialEq)]
+enum Verdict {
+    /// No blocking issues; optional non-blocking suggestions.
+    Approve(Option<String>),
+    Blocking(String),
+}
+
+/// Run `config.reviewers` independent read-only reviewers. Without a quorum
+/// of approvals, an editor session applies their merged feedback.
+async fn run_consensus_round(
+    req: &ReviewRoundRequest<'_>,
+    config: &ReviewConfig,
+) -> Result<ReviewResult> {
+    let label = req.kind.label();
+    let system_prompt = build_critique_prompt(req);
+    let message = format!(
+        "Review the {label} at {} without editing it.",
+        req.document_path
+    );
+
+    let mut verdicts = Vec::new();
+    let mut scores = Vec::new();
+    for index in 0..config.reviewers {
+        let model = config.model(index);
+        let reviewer = index + 1;
+        let result = acp::connection::run_autonomous(
+            req.agent_command,
+            req.project_root,
+            &system_prompt,
+            &message,
+            true, // reviewers only critique; the editor applies changes
+            Some(model),
+            SessionRestrictions {
+                allow_terminal: false,
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(|e| {
+            anyhow!(
+                "reviewer {reviewer} failed on round {} for {label} '{}': {e}",
+                req.round,
+                req.feature_name
+            )
+        })?;
+        let verdict = parse_verdict(&result.full_text).ok_or_else(|| {
+            anyhow!(
+                "reviewer {reviewer} did not emit a verdict sigil on round {} for {label} '{}'",
+                req.round,
+                req.feature_name
+            )
+        })?;
+        formatter::emit_event_info(
+            "review",
+            &format!(
+                "{label} reviewer {reviewer}/{} ({model}): {}",
+                config.reviewers,
+                match verdict {
+                    Verdict::Approve(_) => "no blocking issues",
+                    Verdict::Blocking(_) => "blocking issues",
+                }
+            ),
+        );
+        if let Some(rubric) = req.rubric {
+            scores.extend(
+                parse_rubric_scores(&result.full_text, rubric)
+                    .into_iter()
+                    .map(|score| CriterionScore {
+                        reviewer: Some(reviewer),
+                        ..score
+                    }),
+            );
+        }
+        verdicts.push((reviewer, model.to_string(), verdict));
+    }
+
+    let approvals = verdicts
+        .iter()
+        .filter(|(_, _, v)| matches!(v, Verdict::Approve(_)))
+        .count() as u32;
+    if approvals >= config.quorum() {
+        return Ok(ReviewResult {
+            passed: true,
+            changes_summary: String::new(),
+            scores,
+            approvals,
+        });
+    }
+
+    let feedback = merge_feedback(&verdicts);
+    let result = acp::connection::run_autonomous(
+        req.agent_command,
+        req.project_root,
+        &build_revision_prompt(req, &feedback),
+        &format!(
+            "Revise the {label} at {} to resolve the reviewers' blocking issues.",
+            req.document_path
+        ),
+        false,
+        Some(config.model(0)),
+        SessionRestrictions {
+            allow_terminal: false,
+            allowed_write_paths: Some(vec![PathBuf::from(req.document_path)]),
+        },
+    )
+    .await
+    .map_err(|e| {
+        anyhow!(
+            "review editor failed on round {} for {label} '{}': {e}",
+            req.round,
+            req.feature_name
+        )
+    })?;
+    let changes_summary = parse_review_changes(&result.full_text).unwrap_or_else(|| {
+        format!(
+            "{} of {} reviewers raised blocking issues",
+            config.reviewers - approvals,
+            config.reviewers
+        )
+    });
+    Ok(ReviewResult {
+        passed: false,
+        changes_summary,
+        scores,
+        approvals,
+    })
+}
+
+/// All reviewers' feedback as one document for the editor: blocking issues
+/// first, then suggestions from reviewers who approved.
+fn merge_feedback(verdicts: &[(u32, String, Verdict)]) -> String {
+    let mut blocking = String::new();
+    let mut suggestions = String::new();
+    for (reviewer, model, verdict) in verdicts {
+        match verdict {
+            Verdict::Blocking(issues) => {
+                blocking.push_str(&format!(
+                    "### Reviewer {reviewer} ({model})\n\n{issues}\n\n"
+                ));
+            }
+            Verdict::Approve(Some(notes)) => {
+                suggestions.push_str(&format!("### Reviewer {reviewer} ({model})\n\n{notes}\n\n"));
+            }
+            Verdict::Approve(None) => {}
+        }
+    }
+    let mut feedback = format!("## Blocking Issues\n\n{blocking}");
+    if !suggestions.is_empty() {
+        feedback.push_str(&format!("## Non-Blocking Suggestions\n\n{suggestions}"));
+    }
+    feedback.trim_end().to_string()
+}
+
+/// Prompt for one independent reviewer in a consensus round.
+fn build_critique_prompt(req: &ReviewRoundRequest<'_>) -> String {
+    let label = req.kind.label();
+    let document_path = req.document_path;
+    let spec_section = req
+        .spec_content
+        .map(|spec| format!("## Feature Specification\n\n{spec}\n"))
+        .unwrap_or_default();
+    let criteria = review_criteria(req.kind, req.rubric);
+    let scoring = scoring_instructions(req.rubric, "as it stands");
+    format!(
+        r#"You are one of several independent reviewers of a feature {label} document for Ralph. Other reviewers see the same document; you do not see their reviews. Do NOT edit any file — an editor applies the reviewers' combined feedback afterwards.
+
+## Feature
+
+**Name:** {feature_name}
+**Document:** `{document_path}`
+**Review round:** {round}
+
+{spec_section}
+{project_context}
+
+## Your Task
+
+1. Read the {label} document at `{document_path}`
+2. Read relevant source code to check that the document fits the project's existing patterns and architecture
+3. Decide whether anything in the document would block a correct implementation
+
+{criteria}
+
+## Blocking vs Non-Blocking
+
+An issue is **blocking** if implementing the document as written would likely produce wrong, incomplete or unverifiable work: a missing requirement, a contradiction, an infeasible approach, an untestable acceptance criterion. Wording, formatting and nice-to-have additions are **non-blocking**.
+
+## Sigils
+
+Emit exactly one of:
+
+- `<review-pass/>` — no blocking issues. You may add `<review-notes>non-blocking suggestions</review-notes>`.
+- `<review-blocking>one issue per line, each saying what is wrong, where, and what would fix it</review-blocking>`{scoring}"#,
+        feature_name = req.feature_name,
+        round = req.round,
+        project_context = req.project_context,
+    )
+}
+
+/// Prompt for the editor that applies merged reviewer feedback.
+fn build_revision_prompt(req: &ReviewRoundRequest<'_>, feedback: &str) -> String {
+    let label = req.kind.label();
+    let document_path = req.document_path;
+    let spec_section = req
+        .spec_content
+        .map(|spec| format!("## Feature Specification\n\n{spec}\n"))
+        .unwrap_or_default();
+    format!(
+        r#"You are the editor of a feature {label} document for Ralph. Independent reviewers found blocking issues; revise the document to resolve them.
+
+## Feature
+
+**Name:** {feature_name}
+**Document:** `{document_path}`
+
+{spec_section}
+{project_context}
+
+## Reviewer Feedback
+
+{feedback}
+
+## Rules
+
+- Resolve every blocking issue. Where reviewers disagree, choose the option that best fits the codebase and say so in the document if it matters to implementers
+- Apply non-blocking suggestions only when they clearly help
+- ONLY modify `{document_path}` — do NOT modify any source code, tests, or other files
+- Preserve the author's intent — do not redesign
+
+When done, emit `<review-changes>summary of what you changed</review-changes>`."#,
+        feature_name = req.feature_name,
+        project_context = req.project_context,
+    )
+}
+
 fn build_review_prompt(
     document_path: &str,
     kind: DocumentKind,
@@ -191,36 +676,19 @@ fn build_review_prompt(
     spec_content: Option<&str>,
     project_context: &str,
     round: u32,
+    rubric: Option<&Rubric>,
 ) -> String {
     let label = kind.label();
 
-    let kind_specific_criteria = match kind {
-        DocumentKind::Spec => {
-            r#"## Spec-Specific Criteria
-
-- **Completeness**: Does the spec cover all functional and non-functional requirements?
-- **Testability**: Are acceptance criteria concrete and verifiable?
-- **Precision**: Are data models, APIs, and schemas defined with enough detail for implementation?
-- **Edge cases**: Are error handling, boundary conditions, and failure modes addressed?
-- **Dependencies**: Are external dependencies, integrations, and assumptions documented?"#
-        }
-        DocumentKind::Plan => {
-            r#"## Plan-Specific Criteria
-
-- **Completeness**: Does the plan cover all spec requirements?
-- **Ordering**: Are implementation phases in a logical, dependency-respecting order?
-- **Task granularity**: Are phases broken into right-sized, implementable chunks?
-- **Verification**: Does each phase have clear verification/acceptance criteria?
-- **Risk coverage**: Are risk areas, failure modes, and mitigation strategies identified?
-- **Spec alignment**: Does the plan reference specific spec sections?"#
-        }
-    };
+    let kind_specific_criteria = review_criteria(kind, rubric);
 
     let spec_section = match spec_content {
         Some(spec) => format!("## Feature Specification\n\n{}\n", spec),
         None => String::new(),
     };
 
+    let scoring = scoring_instructions(rubric, "as it stands after your changes");
+
     let round_note = if round > 1 {
         format!(
             "\n**This is review round {}.** Previous rounds made changes. \
@@ -277,7 +745,7 @@ After your review, emit exactly one of these sigils:
 - `<review-pass/>` — The document is comprehensive, clear, and ready for use. No major issues found.
 - `<review-changes>summary of what you changed</review-changes>` — You made substantive improvements. Briefly describe what changed.
 
-You MUST emit one of these sigils. If you made any changes to the file, use `<review-changes>`. If the document was already good, use `<review-pass/>`."#,
+You MUST emit one of these sigils. If you made any changes to the file, use `<review-changes>`. If the document was already good, use `<review-pass/>`.{scoring}"#,
         label = label,
         feature_name = feature_name,
         document_path = document_path,
@@ -285,9 +753,105 @@ You MUST emit one of these sigils. If you made any changes to the file, use `<re
         spec_section = spec_section,
         project_context = project_context,
         kind_specific_criteria = kind_specific_criteria,
+        scoring = scoring,
     )
 }
 
+/// The criteria section of a reviewer prompt: the project rubric when there
+/// is one, else the built-in criteria for `kind`.
+fn review_criteria(kind: DocumentKind, rubric: Option<&Rubric>) -> String {
+    let label = kind.label();
+    match (rubric, kind) {
+        (Some(rubric), _) => format!(
+            "## Project Rubric\n\n\
+             This project reviews {label}s against its own rubric (`{path}`). \
+             Judge the document by these criteria first:\n\n{text}",
+            path = rubric.path,
+            text = rubric.text,
+        ),
+        (None, DocumentKind::Spec) => r#"## Spec-Specific Criteria
+
+- **Completeness**: Does the spec cover all functional and non-functional requirements?
+- **Testability**: Are acceptance criteria concrete and verifiable?
+- **Precision**: Are data models, APIs, and schemas defined with enough detail for implementation?
+- **Edge cases**: Are error handling, boundary conditions, and failure modes addressed?
+- **Dependencies**: Are external dependencies, integrations, and assumptions documented?"#
+            .to_string(),
+        (None, DocumentKind::Plan) => r#"## Plan-Specific Criteria
+
+- **Completeness**: Does the plan cover all spec requirements?
+- **Ordering**: Are implementation phases in a logical, dependency-respecting order?
+- **Task granularity**: Are phases broken into right-sized, implementable chunks?
+- **Verification**: Does each phase have clear verification/acceptance criteria?
+- **Risk coverage**: Are risk areas, failure modes, and mitigation strategies identified?
+- **Spec alignment**: Does the plan reference specific spec sections?"#
+            .to_string(),
+    }
+}
+
+/// Instructions to emit `<rubric-scores>`, or nothing without a rubric.
+/// `state` says which version of the document to score.
+fn scoring_instructions(rubric: Option<&Rubric>, state: &str) -> String {
+    match rubric {
+        Some(rubric) => {
+            let example: Vec<String> = rubric
+                .criteria
+                .iter()
+                .map(|c| {
+                    serde_json::json!({"criterion": c, "score": 4, "note": "one-line reason"})
+                        .to_string()
+                })
+                .collect();
+            format!(
+                "\n\nAlso score the document {state}, 1 (poor) to \
+                 {MAX_SCORE} (excellent), on every rubric criterion, using the exact criterion \
+                 names:\n\n<rubric-scores>[{}]</rubric-scores>",
+                example.join(", ")
+            )
+        }
+        None => String::new(),
+    }
+}
+
+/// Parse the `<rubric-scores>[...]</rubric-scores>` sigil. Scores for
+/// criteria not in the rubric, or outside 1..=MAX_SCORE, are dropped; a
+/// malformed sigil yields no scores.
+fn parse_rubric_scores(text: &str, rubric: &Rubric) -> Vec<CriterionScore> {
+    let start_tag = "<rubric-scores>";
+    let end_tag = "</rubric-scores>";
+    let Some(start_idx) = text.rfind(start_tag) else {
+        return Vec::new();
+    };
+    let content_start = start_idx + start_tag.len();
+    let Some(end_idx) = text[content_start..].find(end_tag) else {
+        return Vec::new();
+    };
+    let json = text[content_start..content_start + end_idx].trim();
+    let scores: Vec<CriterionScore> = serde_json::from_str(json).unwrap_or_default();
+    scores
+        .into_iter()
+        .filter(|s| (1..=MAX_SCORE).contains(&s.score))
+        .filter_map(|mut s| {
+            // Tolerate case and stray markup differences in the name
+            let canonical = rubric
+                .criteria
+                .iter()
+                .find(|c| c.eq_ignore_ascii_case(s.criterion.trim().trim_matches('*')))?;
+            s.criterion = canonical.clone();
+            Some(s)
+        })
+        .collect()
+}
+
+/// A consensus reviewer's verdict: `<review-blocking>` wins over
+/// `<review-pass/>` if both appear.
+fn parse_verdict(text: &str) -> Option<Verdict> {
+    if let Some(issues) = extract_tag(text, "review-blocking") {
+        return Some(Verdict::Blocking(issues));
+    }
+    parse_review_pass(text).then(|| Verdict::Approve(extract_tag(text, "review-notes")))
+}
+
 /// Parse the `<review-pass/>` sigil from result text.
 fn parse_review_pass(text: &str) -> bool {
     text.contains("<review-pass/>")
@@ -295,18 +859,23 @@ fn parse_review_pass(text: &str) -> bool {
 
 /// Parse the `<review-changes>...</review-changes>` sigil from result text.
 fn parse_review_changes(text: &str) -> Option<String> {
-    let start_tag = "<review-changes>";
-    let end_tag = "</review-changes>";
+    extract_tag(text, "review-changes")
+}
+
+/// Trimmed content of the first `<tag>...</tag>`, if present and non-empty.
+fn extract_tag(text: &str, tag: &str) -> Option<String> {
+    let start_tag = format!("<{tag}>");
+    let end_tag = format!("</{tag}>");
 
-    let start_idx = text.find(start_tag)?;
+    let start_idx = text.find(&start_tag)?;
     let content_start = start_idx + start_tag.len();
-    let end_idx = text[content_start..].find(end_tag)?;
-    let summary = text[content_start..content_start + end_idx].trim();
+    let end_idx = text[content_start..].find(&end_tag)?;
+    let content = text[content_start..content_start + end_idx].trim();
 
-    if summary.is_empty() {
+    if content.is_empty() {
         None
     } else {
-        Some(summary.to_string())
+        Some(content.to_string())
     }
 }
 
@@ -359,7 +928,15 @@ mod tests {
 
     #[test]
     fn test_review_prompt_spec_criteria() {
-        let prompt = build_review_prompt("/tmp/spec.md", DocumentKind::Spec, "test", None, "", 1);
+        let prompt = build_review_prompt(
+            "/tmp/spec.md",
+            DocumentKind::Spec,
+            "test",
+            None,
+            "",
+            1,
+            None,
+        );
         assert!(prompt.contains("Completeness"));
         assert!(prompt.contains("Testability"));
         assert!(prompt.contains("<review-pass/>"));
@@ -375,6 +952,7 @@ mod tests {
             Some("Spec content here"),
             "",
             1,
+            None,
         );
         assert!(prompt.contains("Ordering"));
         assert!(prompt.contains("Task granularity"));
@@ -383,17 +961,275 @@ mod tests {
 
     #[test]
     fn test_review_prompt_round_note() {
-        let r1 = build_review_prompt("/tmp/spec.md", DocumentKind::Spec, "test", None, "", 1);
+        let r1 = build_review_prompt(
+            "/tmp/spec.md",
+            DocumentKind::Spec,
+            "test",
+            None,
+            "",
+            1,
+            None,
+        );
         assert!(!r1.contains("review round"));
 
-        let r2 = build_review_prompt("/tmp/spec.md", DocumentKind::Spec, "test", None, "", 2);
+        let r2 = build_review_prompt(
+            "/tmp/spec.md",
+            DocumentKind::Spec,
+            "test",
+            None,
+            "",
+            2,
+            None,
+        );
         assert!(r2.contains("review round 2"));
     }
 
     #[test]
     fn test_review_prompt_includes_context() {
         let ctx = "## Project Context\n\nTest content";
-        let prompt = build_review_prompt("/tmp/spec.md", DocumentKind::Spec, "test", None, ctx, 1);
+        let prompt = build_review_prompt(
+            "/tmp/spec.md",
+            DocumentKind::Spec,
+            "test",
+            None,
+            ctx,
+            1,
+            None,
+        );
         assert!(prompt.contains("Test content"));
     }
+
+    fn write_rubric(root: &Path, kind: &str, text: &str) {
+        std::fs::create_dir_all(root.join(RUBRIC_DIR)).unwrap();
+        std::fs::write(
+            root.join(RUBRIC_DIR).join(format!("{kind}_rubric.md")),
+            text,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_load_rubric_parses_criteria() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert!(load_rubric(dir.path(), DocumentKind::Spec)
+            .unwrap()
+            .is_none());
+
+        write_rubric(
+            dir.path(),
+            "spec",
+            "# Spec rubric\n\n- **Security**: Threats and mitigations are listed.\n\
+             - Rollout: Migration and rollback are described.\n\
+             - Accessibility\n  - nested detail, not a criterion\n",
+        );
+        let rubric = load_rubric(dir.path(), DocumentKind::Spec)
+            .unwrap()
+            .unwrap();
+        assert_eq!(rubric.path, ".ralph/review/spec_rubric.md");
+        assert_eq!(
+            rubric.criteria,
+            vec!["Security", "Rollout", "Accessibility"]
+        );
+        assert!(load_rubric(dir.path(), DocumentKind::Plan)
+            .unwrap()
+            .is_none());
+
+        write_rubric(dir.path(), "plan", "Just prose, no criteria.\n");
+        let err = load_rubric(dir.path(), DocumentKind::Plan).unwrap_err();
+        assert!(err.to_string().contains("defines no criteria"));
+    }
+
+    #[test]
+    fn test_review_prompt_uses_project_rubric() {
+        let rubric = Rubric {
+            path: ".ralph/review/spec_rubric.md".to_string(),
+            text: "- **Security**: Threats are listed.".to_string(),
+            criteria: vec!["Security".to_string()],
+        };
+        let prompt = build_review_prompt(
+            "/tmp/spec.md",
+            DocumentKind::Spec,
+            "test",
+            None,
+            "",
+            1,
+            Some(&rubric),
+        );
+        assert!(prompt.contains("## Project Rubric"));
+        assert!(prompt.contains("Threats are listed."));
+        assert!(!prompt.contains("Testability"));
+        assert!(prompt.contains("<rubric-scores>"));
+        assert!(prompt.contains(r#""criterion":"Security""#));
+
+        let default = build_review_prompt(
+            "/tmp/spec.md",
+            DocumentKind::Spec,
+            "test",
+            None,
+            "",
+            1,
+            None,
+        );
+        assert!(!default.contains("<rubric-scores>"));
+    }
+
+    #[test]
+    fn test_parse_rubric_scores() {
+        let rubric = Rubric {
+            path: String::new(),
+            text: String::new(),
+            criteria: vec!["Security".to_string(), "Rollout".to_string()],
+        };
+        let text = r#"<review-pass/>
+<rubric-scores>[
+  {"criterion": "security", "score": 4, "note": "STRIDE table present"},
+  {"criterion": "Rollout", "score": 9},
+  {"criterion": "Style", "score": 3}
+]</rubric-scores>"#;
+        assert_eq!(
+            parse_rubric_scores(text, &rubric),
+            vec![CriterionScore {
+                criterion: "Security".to_string(),
+                score: 4,
+                note: "STRIDE table present".to_string(),
+                reviewer: None,
+            }]
+        );
+        assert!(parse_rubric_scores("<rubric-scores>not json</rubric-scores>", &rubric).is_empty());
+        assert!(parse_rubric_scores("<review-pass/>", &rubric).is_empty());
+    }
+
+    #[test]
+    fn test_record_scores_appends_audit_lines() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let rubric = Rubric {
+            path: ".ralph/review/plan_rubric.md".to_string(),
+            text: String::new(),
+            criteria: vec!["Ordering".to_string(), "Rollout".to_string()],
+        };
+        let result = ReviewResult {
+            passed: false,
+            changes_summary: "Reordered phases".to_string(),
+            scores: vec![CriterionScore {
+                criterion: "Ordering".to_string(),
+                score: 3,
+                note: String::new(),
+                reviewer: None,
+            }],
+            approvals: 0,
+        };
+        for round in 1..=2 {
+            record_scores(
+                dir.path(),
+                "auth",
+                DocumentKind::Plan,
+                &rubric,
+                round,
+                &result,
+            )
+            .unwrap();
+        }
+
+        let text = std::fs::read_to_string(
+            dir.path()
+                .join(".ralph/features/auth/review/plan-scores.jsonl"),
+        )
+        .unwrap();
+        let records: Vec<serde_json::Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1]["round"], 2);
+        assert_eq!(records[0]["rubric"], ".ralph/review/plan_rubric.md");
+        assert_eq!(records[0]["scores"][0]["criterion"], "Ordering");
+        assert_eq!(records[0]["scores"][0]["score"], 3);
+        assert_eq!(records[0]["unscored"], serde_json::json!(["Rollout"]));
+    }
+
+    #[test]
+    fn test_review_config_quorum_and_models() {
+        let config: ReviewConfig =
+            toml::from_str("reviewers = 3\nmodels = [\"opus\", \"sonnet\"]\n").unwrap();
+        config.validate().unwrap();
+        assert_eq!(config.quorum(), 2);
+        assert_eq!(
+            (0..3).map(|i| config.model(i)).collect::<Vec<_>>(),
+            vec!["opus", "sonnet", "opus"]
+        );
+
+        let default = ReviewConfig::default();
+        assert_eq!((default.reviewers, default.quorum()), (1, 1));
+        assert_eq!(default.model(0), DEFAULT_REVIEW_MODEL);
+
+        let too_strict: ReviewConfig = toml::from_str("reviewers = 2\nquorum = 3\n").unwrap();
+        assert!(too_strict.validate().is_err());
+        let none: ReviewConfig = toml::from_str("reviewers = 0\n").unwrap();
+        assert!(none.validate().is_err());
+    }
+
+    #[test]
+    fn test_parse_verdict() {
+        assert_eq!(
+            parse_verdict("<review-pass/>"),
+            Some(Verdict::Approve(None))
+        );
+        assert_eq!(
+            parse_verdict("<review-pass/><review-notes>Name the env var.</review-notes>"),
+            Some(Verdict::Approve(Some("Name the env var.".to_string())))
+        );
+        assert_eq!(
+            parse_verdict("<review-pass/>\n<review-blocking>No rollback plan.</review-blocking>"),
+            Some(Verdict::Blocking("No rollback plan.".to_string()))
+        );
+        assert_eq!(parse_verdict("Looks fine to me."), None);
+    }
+
+    #[test]
+    fn test_merge_feedback_lists_blocking_issues_first() {
+        let feedback = merge_feedback(&[
+            (
+                1,
+                "opus".to_string(),
+                Verdict::Approve(Some("Add a diagram.".to_string())),
+            ),
+            (
+                2,
+                "sonnet".to_string(),
+                Verdict::Blocking("No rollback plan.".to_string()),
+            ),
+            (3, "opus".to_string(), Verdict::Approve(None)),
+        ]);
+        assert_eq!(
+            feedback,
+            "## Blocking Issues\n\n### Reviewer 2 (sonnet)\n\nNo rollback plan.\n\n\
+             ## Non-Blocking Suggestions\n\n### Reviewer 1 (opus)\n\nAdd a diagram."
+        );
+    }
+
+    #[test]
+    fn test_critique_prompt_is_read_only_and_asks_for_verdict() {
+        let root = PathBuf::from("/tmp");
+        let req = ReviewRoundRequest {
+            document_path: "/tmp/plan.md",
+            kind: DocumentKind::Plan,
+            feature_name: "auth",
+            spec_content: Some("Spec content here"),
+            project_context: "",
+            round: 2,
+            agent_command: "claude",
+            project_root: &root,
+            rubric: None,
+        };
+        let prompt = build_critique_prompt(&req);
+        assert!(prompt.contains("Do NOT edit any file"));
+        assert!(prompt.contains("Task granularity"));
+        assert!(prompt.contains("Spec content here"));
+        assert!(prompt.contains("<review-blocking>"));
+        assert!(!prompt.contains("<review-changes>"));
+
+        let revision = build_revision_prompt(&req, "## Blocking Issues\n\nNo rollback plan.");
+        assert!(revision.contains("No rollback plan."));
+        assert!(revision.contains("<review-changes>"));
+    }
 }
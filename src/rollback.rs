@@ -0,0 +1,390 @@
+//! Task rollback: revert the working-tree changes attributed to a task.
+//!
+//! Attribution comes from the `files_modified` lists the journal records for
+//! each iteration. A file that exists in `HEAD` is restored to that content; a
+//! file that does not (the task created it) is removed. Changes that were
+//! already committed are left alone, since ralph does not yet record commit
+//! SHAs per task.
+//!
+//! A file other tasks also modified may hold their uncommitted work too, so
+//! it is never removed, and only restored when picked explicitly.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Component, Path};
+use std::process::Command;
+
+use crate::dag::{self, AutoTransition, Db};
+use crate::journal;
+
+/// What rollback does to one file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileAction {
+    /// Check the file out from `HEAD`.
+    Restore,
+    /// Delete the file (not present in `HEAD`).
+    Remove,
+    /// Leave the file untouched, with the reason.
+    Skip(String),
+}
+
+/// A planned revert for one file.
+#[derive(Debug, Clone)]
+pub struct FileRevert {
+    pub path: String,
+    pub action: FileAction,
+    /// Other tasks the journal records modifying the file.
+    pub shared_with: Vec<String>,
+}
+
+impl FileRevert {
+    /// A restore that would also discard other tasks' changes.
+    pub fn is_shared_restore(&self) -> bool {
+        self.action == FileAction::Restore && !self.shared_with.is_empty()
+    }
+}
+
+/// Result of `rollback_task`.
+#[derive(Debug)]
+pub struct RollbackReport {
+    pub reverts: Vec<FileRevert>,
+    pub transitions: Vec<AutoTransition>,
+}
+
+/// Work out how each file attributed to `task_id` would be reverted.
+///
+/// Does not touch the working tree. Fails if the task does not exist or the
+/// project is not a git repository with at least one commit.
+pub fn plan_rollback(db: &Db, project_root: &Path, task_id: &str) -> Result<Vec<FileRevert>> {
+    dag::get_task(db, task_id)?;
+    if !git(project_root, &["rev-parse", "--verify", "--quiet", "HEAD"])? {
+        bail!(
+            "Rollback needs a git repository with at least one commit at {}",
+            project_root.display()
+        );
+    }
+
+    let paths = journal::files_modified_for_task(db, task_id)?;
+    let mut shared = journal::other_tasks_modifying(db, task_id, &paths)?;
+    let mut reverts = Vec::new();
+    for path in paths {
+        let shared_with = shared.remove(&path).unwrap_or_default();
+        let action = if !is_inside_root(&path) {
+            FileAction::Skip("outside the project root".to_string())
+        } else if git(project_root, &["cat-file", "-e", &format!("HEAD:./{path}")])? {
+            FileAction::Restore
+        } else if !project_root.join(&path).exists() {
+            FileAction::Skip("already absent".to_string())
+        } else if !shared_with.is_empty() {
+            FileAction::Skip(format!(
+                "also created or modified by {}",
+                shared_with.join(", ")
+            ))
+        } else {
+            FileAction::Remove
+        };
+        reverts.push(FileRevert {
+            path,
+            action,
+            shared_with,
+        });
+    }
+    Ok(reverts)
+}
+
+/// Revert the task's files, reset it to pending, and record the rollback in
+/// `task_logs` and the journal.
+///
+/// With `only`, just the listed files are reverted; the rest of the plan is
+/// skipped and keeps its changes. Without it, files other tasks also modified
+/// are skipped.
+pub fn rollback_task(
+    db: &Db,
+    project_root: &Path,
+    task_id: &str,
+    only: Option<&[String]>,
+) -> Result<RollbackReport> {
+    let mut reverts = plan_rollback(db, project_root, task_id)?;
+    for revert in &mut reverts {
+        if matches!(revert.action, FileAction::Skip(_)) {
+            continue;
+        }
+        match only {
+            Some(only) if !only.contains(&revert.path) => {
+                revert.action = FileAction::Skip("not selected".to_string());
+            }
+            None if revert.is_shared_restore() => {
+                revert.action = FileAction::Skip(format!(
+                    "also modified by {}",
+                    revert.shared_with.join(", ")
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    for revert in &reverts {
+        match revert.action {
+            FileAction::Restore => {
+                if !git(project_root, &["checkout", "HEAD", "--", &revert.path])? {
+                    bail!("git checkout failed for {}", revert.path);
+                }
+            }
+            FileAction::Remove => std::fs::remove_file(project_root.join(&revert.path))
+                .with_context(|| format!("Failed to remove {}", revert.path))?,
+            FileAction::Skip(_) => {}
+        }
+    }
+
+    let task = dag::get_task(db, task_id)?;
+    let transitions = dag::force_reset_task(db.conn(), task_id)?;
+
+    let reverted: Vec<String> = reverts
+        .iter()
+        .filter(|r| !matches!(r.action, FileAction::Skip(_)))
+        .map(|r| r.path.clone())
+        .collect();
+    let summary = if reverted.is_empty() {
+        "Rolled back: no files to revert".to_string()
+    } else {
+        format!(
+            "Rolled back {} file(s): {}",
+            reverted.len(),
+            reverted.join(", ")
+        )
+    };
+    dag::add_log(db, task_id, &summary)?;
+
+    let now = chrono::Utc::now();
+    journal::insert_journal_entry(
+        db,
+        &journal::JournalEntry {
+            id: 0,
+            run_id: format!("rollback-{}", now.format("%Y%m%d%H%M%S")),
+            iteration: 0,
+            task_id: Some(task_id.to_string()),
+            feature_id: task.feature_id,
+            outcome: "rolled_back".to_string(),
+            model: None,
+            duration_secs: 0.0,
+            cost_usd: 0.0,
+            input_tokens: 0,
+            output_tokens: 0,
+            files_modified: reverted,
+            files_read: Vec::new(),
+            notes: Some(summary),
+            created_at: now.to_rfc3339(),
+        },
+    )?;
+
+    Ok(RollbackReport {
+        reverts,
+        transitions,
+    })
+}
+
+/// Journal paths are project-relative; anything else was written outside the root.
+fn is_inside_root(path: &str) -> bool {
+    Path::new(path)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// Run git in `dir`, returning whether it exited successfully.
+fn git(dir: &Path, args: &[&str]) -> Result<bool> {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .context("Failed to run git")?;
+    Ok(status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag::init_db;
+    use tempfile::TempDir;
+
+    fn run_git(dir: &Path, args: &[&str]) {
+        let ok = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+            .args(args)
+            .output()
+            .unwrap()
+            .status
+            .success();
+        assert!(ok, "git {:?} failed", args);
+    }
+
+    #[test]
+    fn rollback_restores_edits_removes_new_files_and_resets_task() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        run_git(root, &["init", "-q"]);
+        std::fs::write(root.join("lib.rs"), "original\n").unwrap();
+        run_git(root, &["add", "lib.rs"]);
+        run_git(root, &["commit", "-q", "-m", "init"]);
+
+        let db = init_db(root.join("progress.db").to_str().unwrap()).unwrap();
+        let task = dag::create_task(&db, "Edit lib", None, None, 0).unwrap();
+        dag::force_complete_task(db.conn(), &task.id).unwrap();
+
+        std::fs::write(root.join("lib.rs"), "changed\n").unwrap();
+        std::fs::write(root.join("new.rs"), "new\n").unwrap();
+        journal::insert_journal_entry(
+            &db,
+            &journal::JournalEntry {
+                id: 0,
+                run_id: "run-test".to_string(),
+                iteration: 1,
+                task_id: Some(task.id.clone()),
+                feature_id: None,
+                outcome: "done".to_string(),
+                model: None,
+                duration_secs: 1.0,
+                cost_usd: 0.0,
+                input_tokens: 0,
+                output_tokens: 0,
+                files_modified: vec![
+                    "lib.rs".to_string(),
+                    "new.rs".to_string(),
+                    "/etc/passwd".to_string(),
+                ],
+                files_read: Vec::new(),
+                notes: None,
+                created_at: chrono::Utc::now().to_rfc3339(),
+            },
+        )
+        .unwrap();
+
+        let plan = plan_rollback(&db, root, &task.id).unwrap();
+        let actions: Vec<_> = plan.iter().map(|r| r.action.clone()).collect();
+        assert_eq!(actions[0], FileAction::Restore);
+        assert_eq!(actions[1], FileAction::Remove);
+        assert!(matches!(actions[2], FileAction::Skip(_)));
+
+        rollback_task(&db, root, &task.id, None).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(root.join("lib.rs")).unwrap(),
+            "original\n"
+        );
+        assert!(!root.join("new.rs").exists());
+        assert_eq!(dag::get_task(&db, &task.id).unwrap().status, "pending");
+        let logs = dag::get_task_logs(&db, &task.id).unwrap();
+        assert!(logs
+            .iter()
+            .any(|l| l.message.contains("Rolled back 2 file(s)")));
+    }
+
+    #[test]
+    fn rollback_only_reverts_selected_files() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        run_git(root, &["init", "-q"]);
+        std::fs::write(root.join("lib.rs"), "original\n").unwrap();
+        run_git(root, &["add", "lib.rs"]);
+        run_git(root, &["commit", "-q", "-m", "init"]);
+
+        let db = init_db(root.join("progress.db").to_str().unwrap()).unwrap();
+        let task = dag::create_task(&db, "Edit lib", None, None, 0).unwrap();
+        std::fs::write(root.join("lib.rs"), "changed\n").unwrap();
+        std::fs::write(root.join("new.rs"), "new\n").unwrap();
+        journal::insert_journal_entry(
+            &db,
+            &journal::JournalEntry {
+                id: 0,
+                run_id: "run-test".to_string(),
+                iteration: 1,
+                task_id: Some(task.id.clone()),
+                feature_id: None,
+                outcome: "done".to_string(),
+                model: None,
+                duration_secs: 1.0,
+                cost_usd: 0.0,
+                input_tokens: 0,
+                output_tokens: 0,
+                files_modified: vec!["lib.rs".to_string(), "new.rs".to_string()],
+                files_read: Vec::new(),
+                notes: None,
+                created_at: chrono::Utc::now().to_rfc3339(),
+            },
+        )
+        .unwrap();
+
+        let report = rollback_task(&db, root, &task.id, Some(&["new.rs".to_string()])).unwrap();
+        assert_eq!(
+            report.reverts[0].action,
+            FileAction::Skip("not selected".to_string())
+        );
+        assert_eq!(
+            std::fs::read_to_string(root.join("lib.rs")).unwrap(),
+            "changed\n"
+        );
+        assert!(!root.join("new.rs").exists());
+    }
+
+    #[test]
+    fn rollback_keeps_files_other_tasks_modified() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        run_git(root, &["init", "-q"]);
+        std::fs::write(root.join("lib.rs"), "original\n").unwrap();
+        run_git(root, &["add", "lib.rs"]);
+        run_git(root, &["commit", "-q", "-m", "init"]);
+
+        let db = init_db(root.join("progress.db").to_str().unwrap()).unwrap();
+        let task = dag::create_task(&db, "Edit lib", None, None, 0).unwrap();
+        let later = dag::create_task(&db, "Edit lib again", None, None, 0).unwrap();
+        std::fs::write(root.join("lib.rs"), "changed twice\n").unwrap();
+        std::fs::write(root.join("new.rs"), "new, then extended\n").unwrap();
+        for id in [&task.id, &later.id] {
+            journal::insert_journal_entry(
+                &db,
+                &journal::JournalEntry {
+                    id: 0,
+                    run_id: "run-test".to_string(),
+                    iteration: 1,
+                    task_id: Some(id.clone()),
+                    feature_id: None,
+                    outcome: "done".to_string(),
+                    model: None,
+                    duration_secs: 1.0,
+                    cost_usd: 0.0,
+                    input_tokens: 0,
+                    output_tokens: 0,
+                    files_modified: vec!["lib.rs".to_string(), "new.rs".to_string()],
+                    files_read: Vec::new(),
+                    notes: None,
+                    created_at: chrono::Utc::now().to_rfc3339(),
+                },
+            )
+            .unwrap();
+        }
+
+        let plan = plan_rollback(&db, root, &task.id).unwrap();
+        assert!(plan[0].is_shared_restore());
+        assert_eq!(plan[0].shared_with, vec![later.id.clone()]);
+        assert!(matches!(plan[1].action, FileAction::Skip(_)));
+
+        // Neither file changes unless the shared restore is picked
+        rollback_task(&db, root, &task.id, None).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(root.join("lib.rs")).unwrap(),
+            "changed twice\n"
+        );
+        assert!(root.join("new.rs").exists());
+
+        let only = ["lib.rs".to_string(), "new.rs".to_string()];
+        rollback_task(&db, root, &task.id, Some(&only)).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(root.join("lib.rs")).unwrap(),
+            "original\n"
+        );
+        assert!(root.join("new.rs").exists());
+    }
+}
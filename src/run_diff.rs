@@ -0,0 +1,272 @@
+//! `ralph runs diff`: compare two runs from their journal entries.
+//!
+//! Each run is folded into a [`RunSummary`]: totals, outcome and model
+//! counts, the files it modified, and per task the number of attempts, the
+//! last outcome and the time spent. [`render`] lays two summaries side by
+//! side, which is how a model strategy or prompt template change is judged
+//! against a baseline run of the same feature. Tasks are marked `*` when
+//! their outcome differs, `-` or `+` when only one run touched them.
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::dag::{self, Db};
+use crate::journal::{self, JournalEntry};
+
+/// One run, folded from its journal entries.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunSummary {
+    pub run_id: String,
+    pub iterations: u32,
+    pub duration_secs: f64,
+    pub cost_usd: f64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Iterations per journal outcome (`done`, `retried`, ...).
+    pub outcomes: BTreeMap<String, u32>,
+    /// Iterations per model.
+    pub models: BTreeMap<String, u32>,
+    pub files: BTreeSet<String>,
+    pub tasks: BTreeMap<String, TaskRun>,
+}
+
+/// What one run did with one task.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TaskRun {
+    pub title: Option<String>,
+    pub attempts: u32,
+    /// Outcome of the task's last iteration in the run.
+    pub outcome: String,
+    pub duration_secs: f64,
+}
+
+/// Summarize `run_id` from the journal. Fails for a run with no entries.
+pub fn summarize(db: &Db, run_id: &str) -> Result<RunSummary> {
+    let entries = journal::query_journal_recent(db, run_id, u32::MAX)?;
+    if entries.is_empty() {
+        bail!("No journal entries for run {run_id}; `ralph logs` lists recent runs");
+    }
+    let mut summary = fold(run_id, &entries);
+    for (task_id, task) in summary.tasks.iter_mut() {
+        task.title = dag::get_task(db, task_id).ok().map(|t| t.title);
+    }
+    Ok(summary)
+}
+
+fn fold(run_id: &str, entries: &[JournalEntry]) -> RunSummary {
+    let mut summary = RunSummary {
+        run_id: run_id.to_string(),
+        ..Default::default()
+    };
+    for entry in entries {
+        summary.iterations += 1;
+        summary.duration_secs += entry.duration_secs;
+        summary.cost_usd += entry.cost_usd;
+        summary.input_tokens += entry.input_tokens;
+        summary.output_tokens += entry.output_tokens;
+        *summary.outcomes.entry(entry.outcome.clone()).or_default() += 1;
+        let model = entry.model.clone().unwrap_or_else(|| "unknown".to_string());
+        *summary.models.entry(model).or_default() += 1;
+        summary.files.extend(entry.files_modified.iter().cloned());
+        if let Some(task_id) = &entry.task_id {
+            let task = summary.tasks.entry(task_id.clone()).or_default();
+            task.attempts += 1;
+            task.outcome = entry.outcome.clone();
+            task.duration_secs += entry.duration_secs;
+        }
+    }
+    summary
+}
+
+/// Side-by-side comparison of run `a` (the baseline) and run `b`.
+pub fn render(a: &RunSummary, b: &RunSummary) -> Vec<String> {
+    let mut lines = Vec::new();
+    let row = |label: &str, left: String, right: String, delta: String| {
+        format!("{label:<16} {left:<28} {right:<28} {delta}")
+            .trim_end()
+            .to_string()
+    };
+
+    lines.push(row("", a.run_id.clone(), b.run_id.clone(), String::new()));
+    lines.push(row(
+        "Iterations",
+        a.iterations.to_string(),
+        b.iterations.to_string(),
+        signed(b.iterations as f64 - a.iterations as f64, |v| {
+            format!("{v:.0}")
+        }),
+    ));
+    lines.push(row(
+        "Duration",
+        duration(a.duration_secs),
+        duration(b.duration_secs),
+        signed(b.duration_secs - a.duration_secs, duration),
+    ));
+    lines.push(row(
+        "Cost",
+        format!("${:.2}", a.cost_usd),
+        format!("${:.2}", b.cost_usd),
+        signed(b.cost_usd - a.cost_usd, |v| format!("${v:.2}")),
+    ));
+    lines.push(row(
+        "Tokens in/out",
+        format!("{}/{}", a.input_tokens, a.output_tokens),
+        format!("{}/{}", b.input_tokens, b.output_tokens),
+        String::new(),
+    ));
+    lines.push(row(
+        "Outcomes",
+        counts(&a.outcomes),
+        counts(&b.outcomes),
+        String::new(),
+    ));
+    lines.push(row(
+        "Models",
+        counts(&a.models),
+        counts(&b.models),
+        String::new(),
+    ));
+
+    let task_ids: BTreeSet<&String> = a.tasks.keys().chain(b.tasks.keys()).collect();
+    if !task_ids.is_empty() {
+        lines.push(String::new());
+        lines.push("Tasks:".to_string());
+        for id in task_ids {
+            let (left, right) = (a.tasks.get(id), b.tasks.get(id));
+            let title = left
+                .or(right)
+                .and_then(|t| t.title.as_deref())
+                .unwrap_or_default();
+            let marker = match (left, right) {
+                (Some(l), Some(r)) if l.outcome != r.outcome => "*",
+                (Some(_), Some(_)) => " ",
+                (Some(_), None) => "-",
+                _ => "+",
+            };
+            lines.push(format!("{marker} {id}  {title}").trim_end().to_string());
+            lines.push(row("", task_cell(left), task_cell(right), String::new()));
+        }
+    }
+
+    let only_a: Vec<&String> = a.files.difference(&b.files).collect();
+    let only_b: Vec<&String> = b.files.difference(&a.files).collect();
+    let both = a.files.intersection(&b.files).count();
+    lines.push(String::new());
+    lines.push(format!(
+        "Files: {} modified by both, {} only by {}, {} only by {}",
+        both,
+        only_a.len(),
+        a.run_id,
+        only_b.len(),
+        b.run_id
+    ));
+    for file in only_a {
+        lines.push(format!("  - {file}"));
+    }
+    for file in only_b {
+        lines.push(format!("  + {file}"));
+    }
+    lines
+}
+
+fn task_cell(task: Option<&TaskRun>) -> String {
+    match task {
+        Some(t) => format!(
+            "{} ({}x, {})",
+            t.outcome,
+            t.attempts,
+            duration(t.duration_secs)
+        ),
+        None => "\u{2014}".to_string(),
+    }
+}
+
+fn counts(map: &BTreeMap<String, u32>) -> String {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_by(|x, y| y.1.cmp(x.1).then_with(|| x.0.cmp(y.0)));
+    let parts: Vec<String> = entries.iter().map(|(k, v)| format!("{k} {v}")).collect();
+    parts.join(", ")
+}
+
+fn signed(delta: f64, format: impl Fn(f64) -> String) -> String {
+    if delta.abs() < 0.005 {
+        "=".to_string()
+    } else if delta > 0.0 {
+        format!("+{}", format(delta))
+    } else {
+        format!("-{}", format(-delta))
+    }
+}
+
+fn duration(secs: f64) -> String {
+    if secs < 60.0 {
+        format!("{secs:.1}s")
+    } else {
+        let secs = secs.round() as u64;
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(run: &str, task: &str, outcome: &str, model: &str, secs: f64) -> JournalEntry {
+        JournalEntry {
+            id: 0,
+            run_id: run.to_string(),
+            iteration: 1,
+            task_id: Some(task.to_string()),
+            feature_id: None,
+            outcome: outcome.to_string(),
+            model: Some(model.to_string()),
+            duration_secs: secs,
+            cost_usd: 0.5,
+            input_tokens: 100,
+            output_tokens: 10,
+            files_modified: vec![format!("src/{task}.rs")],
+            files_read: Vec::new(),
+            notes: None,
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn compares_totals_tasks_and_files() {
+        let a = fold(
+            "run-a",
+            &[
+                entry("run-a", "t-1", "retried", "opus", 50.0),
+                entry("run-a", "t-1", "done", "opus", 40.0),
+                entry("run-a", "t-2", "failed", "sonnet", 10.0),
+            ],
+        );
+        let b = fold(
+            "run-b",
+            &[
+                entry("run-b", "t-1", "done", "haiku", 30.0),
+                entry("run-b", "t-2", "done", "haiku", 20.0),
+                entry("run-b", "t-3", "done", "haiku", 5.0),
+            ],
+        );
+        assert_eq!(a.tasks["t-1"].attempts, 2);
+        assert_eq!(a.tasks["t-1"].outcome, "done");
+
+        let lines = render(&a, &b);
+        assert!(lines[1].starts_with("Iterations       3"), "{lines:#?}");
+        assert!(lines[1].ends_with(" ="), "{lines:#?}");
+        assert!(lines[2].contains("1m 40s") && lines[2].ends_with("-45.0s"));
+        assert!(lines[5].contains("done 1, failed 1, retried 1"));
+        assert!(lines[6].contains("opus 2, sonnet 1") && lines[6].contains("haiku 3"));
+        assert!(lines.contains(&"  t-1".to_string()));
+        assert!(lines.contains(&"* t-2".to_string()));
+        assert!(lines.contains(&"+ t-3".to_string()));
+        assert!(lines
+            .iter()
+            .any(|l| l.contains("done (2x, 1m 30s)") && l.contains("done (1x, 30.0s)")));
+        assert!(lines
+            .contains(&"Files: 2 modified by both, 0 only by run-a, 1 only by run-b".to_string()));
+        assert_eq!(lines.last().unwrap(), "  + src/t-3.rs");
+    }
+}
@@ -2,20 +2,90 @@
 
 use agent_client_protocol::StopReason;
 use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::future::Future;
 use std::path::Path;
+use tokio::task::{JoinHandle, LocalSet};
 
 use crate::acp;
 use crate::acp::types::{
-    BlockerContext, IterationContext, ParentContext, RetryInfo, RunResult, TaskInfo,
+    ArtifactContext, ArtifactSigil, BlockerContext, IterationContext, ParentContext, RetryInfo,
+    RunResult, StreamingResult, TaskInfo,
 };
+use crate::burndown::{self, Burndown};
+use crate::cli::FeatureOrder;
+use crate::compaction;
 use crate::config::{Config, RunTarget};
-use crate::dag::{self, Db, Task};
+use crate::context_cache::ContextCache;
+use crate::dag::{self, Criterion, Db, Task};
+use crate::diff_context::{self, DiffScope};
+use crate::exit_when::RunStats;
 use crate::feature;
+use crate::flaky;
 use crate::journal;
+use crate::journal_summary;
 use crate::knowledge;
+use crate::lessons;
+use crate::output::notify::{self, NotifyEvent};
 use crate::output::{formatter, logger};
+use crate::post_feature;
+use crate::scratchpad;
+use crate::speculation::Speculation;
+use crate::steering;
 use crate::strategy;
-use crate::verification;
+use crate::verification::{self, VerificationResult};
+
+/// Characters of an attempt's closing output carried into its retry prompt.
+const PREVIOUS_OUTPUT_CHARS: usize = 4000;
+
+/// Characters of an attempt's condensed thoughts carried into its retry prompt.
+const PREVIOUS_THOUGHT_CHARS: usize = 1500;
+
+/// What a task's latest attempt in this run left behind, so a retry can pick
+/// up where it stopped.
+struct PreviousAttempt {
+    session_id: Option<String>,
+    output_tail: String,
+    thought_summary: String,
+}
+
+impl PreviousAttempt {
+    fn from_result(result: &StreamingResult) -> Self {
+        let text = result.full_text.trim();
+        let skip = text.chars().count().saturating_sub(PREVIOUS_OUTPUT_CHARS);
+        Self {
+            session_id: result.session_id.clone(),
+            output_tail: text.chars().skip(skip).collect(),
+            thought_summary: condense_thoughts(&result.thoughts, PREVIOUS_THOUGHT_CHARS),
+        }
+    }
+}
+
+/// Thought text with blank and repeated lines dropped and whitespace collapsed,
+/// keeping the most recent lines that fit in `max_chars`.
+fn condense_thoughts(thoughts: &str, max_chars: usize) -> String {
+    let mut seen = std::collections::HashSet::new();
+    let lines: Vec<String> = thoughts
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty() && seen.insert(line.clone()))
+        .collect();
+    let mut kept = Vec::new();
+    let mut used = 0;
+    for line in lines.iter().rev() {
+        let len = line.chars().count() + 1;
+        if used + len > max_chars {
+            break;
+        }
+        used += len;
+        kept.push(line.as_str());
+    }
+    if kept.len() < lines.len() {
+        kept.push("\u{2026}");
+    }
+    kept.reverse();
+    kept.join("\n")
+}
 
 /// Outcome of the loop execution.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -32,6 +102,59 @@ pub enum Outcome {
     NoPlan,
     /// User interrupted and chose not to continue
     Interrupted,
+    /// `[run] exit_when` held before an iteration
+    ExitCondition,
+}
+
+/// A verification running alongside later iterations
+/// (`[execution] pipeline_verification`). Its task stays claimed and
+/// `in_progress` until the verdict is applied, so dependents stay blocked.
+struct InFlightVerification {
+    task: Task,
+    iteration: u32,
+    criteria: Vec<Criterion>,
+    fingerprint: String,
+    files: Vec<String>,
+    max_retries: u32,
+    /// Journal entry of the iteration that finished the task, written as
+    /// `done` and corrected if verification rejects the work.
+    journal_id: Option<i64>,
+    handle: JoinHandle<Result<VerificationResult>>,
+}
+
+/// How a `<task-done>` is recorded once verification, if any, is over.
+enum DoneVerdict {
+    /// Verification is off: complete the task.
+    Unverified,
+    /// Apply this verdict.
+    Verified {
+        max_retries: u32,
+        criteria: Vec<Criterion>,
+        result: VerificationResult,
+    },
+    /// Verification continues in the background.
+    InFlight(Box<InFlightVerification>),
+}
+
+impl DoneVerdict {
+    /// Journal outcome of the iteration once the verdict is applied; an
+    /// in-flight verification is provisionally `done`.
+    fn outcome(&self, task: &Task) -> &'static str {
+        match self {
+            DoneVerdict::Verified {
+                max_retries,
+                result,
+                ..
+            } if !result.passed => {
+                if task.retry_count < *max_retries as i32 {
+                    "retried"
+                } else {
+                    "failed"
+                }
+            }
+            _ => "done",
+        }
+    }
 }
 
 /// Run the main loop until completion, failure, or limit.
@@ -48,8 +171,14 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
     )
     .context("Failed to open DAG database")?;
 
-    // Resolve feature context (spec + plan content) if targeting a feature
-    let (feature_id, spec_content, plan_content) = resolve_feature_context(&config, &db)?;
+    // Repair task state an earlier crash left half-written
+    for repair in dag::recover_partial_writes(&db).context("Failed to check task state")? {
+        formatter::print_warning(&format!("ralph: recovered {repair}"));
+        formatter::emit_event("dag", &format!("recovered {repair}"), true);
+    }
+
+    // Resolve the features in scope and their spec + plan content
+    let features = resolve_feature_contexts(&config, &db)?;
 
     // Emit iteration 1 start event (iterations 2+ are emitted in advance_iteration_with_model_selection)
     formatter::emit_event_info(
@@ -60,10 +189,113 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
         ),
     );
 
+    // Pipelined verifications are spawned onto this set, so they make progress
+    // while later iterations run.
+    let run_log = logger::RunLog::start(
+        &config.project_root,
+        &config.run_id,
+        &config.ralph_config.logging,
+    )
+    .context("Failed to set up run logs")?;
+    formatter::announce_run_logs(&config.project_root);
+    if config.ralph_config.execution.diff_context != DiffScope::Off {
+        config.run_base = diff_context::head(&config.project_root);
+    }
+
+    let local = LocalSet::new();
+    let agent_id = config.agent_id.clone();
+    let mut in_flight = None;
+    let mut context_cache = ContextCache::default();
+    let outcome = local
+        .run_until(renewing_claims(
+            &db,
+            &agent_id,
+            run_tasks(
+                &mut config,
+                &db,
+                &progress_db,
+                &features,
+                &run_log,
+                &mut in_flight,
+                &mut context_cache,
+            ),
+        ))
+        .await;
+    if let Some(summary) = context_cache.summary() {
+        formatter::emit_event_info("cache", &summary);
+    }
+    if let Err(e) = run_log.finish() {
+        formatter::print_warning(&format!("ralph: could not compress run logs: {e:#}"));
+    }
+
+    // Never leave a task claimed behind a verification nobody waits for.
+    if let Some(pending) = in_flight {
+        local
+            .run_until(renewing_claims(
+                &db,
+                &agent_id,
+                finish_verification(&db, &config, pending),
+            ))
+            .await?;
+        if outcome.as_ref().is_ok_and(|o| *o == Outcome::LimitReached)
+            && scoped_target_resolved(&config, &db, &features)
+                .context("Failed to check if run target is resolved")?
+        {
+            return Ok(Outcome::Complete);
+        }
+    }
+    outcome
+}
+
+/// Drive `fut` while renewing the leases on this agent's claims, so a long
+/// iteration or verification is never mistaken for a crashed one.
+async fn renewing_claims<F: Future>(db: &Db, agent_id: &str, fut: F) -> F::Output {
+    let mut fut = std::pin::pin!(fut);
+    let mut renew = tokio::time::interval(dag::CLAIM_RENEW_INTERVAL);
+    renew.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        tokio::select! {
+            output = &mut fut => return output,
+            _ = renew.tick() => {
+                if let Err(e) = dag::renew_claims(db, agent_id) {
+                    formatter::emit_event("dag", &format!("claim renewal failed \u{2014} {e}"), true);
+                }
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run_tasks(
+    config: &mut Config,
+    db: &Db,
+    progress_db: &Path,
+    features: &[FeatureContext],
+    run_log: &logger::RunLog,
+    in_flight: &mut Option<InFlightVerification>,
+    context_cache: &mut ContextCache,
+) -> Result<Outcome> {
+    // Latest attempt per task, for continuing it when the task is retried.
+    let mut previous_attempts: HashMap<String, PreviousAttempt> = HashMap::new();
+    // Feature the next task comes from when interleaving several features.
+    let mut next_feature = 0;
+    // Read-only exploration of the next task, started while one is verified.
+    let mut speculation: Option<Speculation> = None;
+    // For `run.elapsed_mins` in `[run] exit_when`.
+    let started = std::time::Instant::now();
+
     loop {
+        if in_flight.as_ref().is_some_and(|v| v.handle.is_finished()) {
+            if let Some(pending) = in_flight.take() {
+                finish_verification(db, config, pending).await?;
+            }
+        }
+
         // Get scoped ready tasks
-        let ready_tasks = get_scoped_ready_tasks(&config, &db, feature_id.as_deref())?;
-        let counts = dag::get_task_counts(&db).context("Failed to get task counts")?;
+        let ready_tasks = get_scoped_ready_tasks(config, db, features, next_feature)?;
+        let counts = dag::get_task_counts(db).context("Failed to get task counts")?;
+        update_auto_limit(config, db, features)?;
+        show_progress(db, features)?;
 
         // Print DAG summary at the start of each iteration
         if config.iteration == 1 {
@@ -84,10 +316,15 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
 
         // Check if all tasks are resolved before declaring blocked
         if ready_tasks.is_empty() {
-            if recover_stuck_target_claim(&config, &db)? {
+            // The verdict may unblock dependents or settle the target
+            if let Some(pending) = in_flight.take() {
+                finish_verification(db, config, pending).await?;
+                continue;
+            }
+            if recover_stuck_target_claim(config, db)? {
                 continue;
             }
-            if scoped_target_resolved(&config, &db, feature_id.as_deref())
+            if scoped_target_resolved(config, db, features)
                 .context("Failed to check if run target is resolved")?
             {
                 return Ok(Outcome::Complete);
@@ -95,45 +332,165 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
             return Ok(Outcome::Blocked);
         }
 
+        // `[run] exit_when`: the user's own reason to stop before this iteration
+        if let Some(condition) = &config.exit_when {
+            let stats = run_stats(config, db, features, started)?;
+            if condition.is_met(&stats) {
+                formatter::emit_event_info(
+                    "iter",
+                    &format!(
+                        "exit_when met: {} ({})",
+                        condition.source(),
+                        condition.explain(&stats)
+                    ),
+                );
+                return Ok(Outcome::ExitCondition);
+            }
+        }
+
         // Pick first ready task
         let task = &ready_tasks[0];
         let task_id = task.id.clone();
+        // Spec and plan follow the feature of the claimed task
+        let feature = feature_context(features, task);
+        let spec_content = feature.and_then(|f| f.spec.as_deref());
+        let plan_content = feature.and_then(|f| f.plan.as_deref());
         formatter::emit_event_info(
             "dag",
             &format!("next task: {} \u{2014} \"{}\"", task.id, task.title),
         );
 
-        // Claim the task
-        dag::claim_task(&db, &task_id, &config.agent_id).context("Failed to claim task")?;
+        // Claim the task; an in-progress one is ready only because its lease expired
+        let stale_owner = (task.status == "in_progress").then(|| task.claimed_by.clone());
+        if let Some(chaos) = &config.chaos {
+            chaos
+                .db_lock(&format!("claiming {task_id}"))
+                .context("Failed to claim task")?;
+        }
+        if !dag::claim_task(db, &task_id, &config.agent_id).context("Failed to claim task")? {
+            // Another instance sharing the database got there first
+            formatter::emit_event_info(
+                "dag",
+                &format!("{} was claimed by another agent; picking again", task_id),
+            );
+            continue;
+        }
+        if let Some(i) = feature.and_then(|f| features.iter().position(|c| c.id == f.id)) {
+            next_feature = i + 1;
+        }
+        if let Some(owner) = stale_owner {
+            let message = format!(
+                "Recovered from {} after its claim lease expired",
+                owner.as_deref().unwrap_or("an unknown agent")
+            );
+            formatter::print_warning(&format!("ralph: {task_id}: {message}"));
+            formatter::emit_event(
+                "dag",
+                &format!("{task_id} {}", message.to_lowercase()),
+                true,
+            );
+            dag::add_log(db, &task_id, &message)?;
+        }
+
+        // Adaptive strategy picks the model once the task is known
+        if let Some(model) = strategy::select_for_task(config, db, task) {
+            if model != config.current_model {
+                formatter::emit_event_info(
+                    "config",
+                    &format!("model={} (strategy=adaptive, task {})", model, task_id),
+                );
+            }
+            config.current_model = model;
+        }
 
         // Print iteration info with colors (task ID in cyan)
-        formatter::print_task_working(config.iteration, &task_id, &task.title);
+        let labels = dag::task_labels(task, &dag::get_task_labels(db, &task_id)?);
+        formatter::print_task_working(config.iteration, &task_id, &task.title, &labels);
         formatter::emit_event_info(
             "task",
             &format!("{} claimed \u{2014} \"{}\"", task_id, task.title),
         );
 
         // Set up logging
-        let log_file = logger::setup_log_file();
+        let log_file = run_log.path().to_string_lossy().to_string();
         formatter::print_log_location("Log will be written to:", &log_file);
 
+        // A finished exploration of this task warms up its prompt
+        let speculative_analysis = match speculation.take() {
+            Some(s) if s.task_id == task_id => {
+                let analysis = s.take_if_finished().await;
+                let message = match analysis {
+                    Some(_) => format!("{task_id} starts from a prior analysis"),
+                    None => format!("exploration of {task_id} had not finished; discarded"),
+                };
+                formatter::emit_event_info("speculate", &message);
+                analysis
+            }
+            Some(s) => {
+                formatter::emit_event_info(
+                    "speculate",
+                    &format!(
+                        "exploration of {} discarded; {task_id} was picked",
+                        s.task_id
+                    ),
+                );
+                None
+            }
+            None => None,
+        };
+
+        // Fold the latest journal entries into the rolling run summary when due
+        journal_summary::refresh_run_summary(db, config).await;
+
         // Build iteration context
-        let iteration_context = build_iteration_context(
-            &db,
+        let mut iteration_context = build_iteration_context(
+            db,
             task,
-            spec_content.as_deref(),
-            plan_content.as_deref(),
-            &config,
+            feature,
+            config,
+            previous_attempts.get(&task_id),
+            context_cache,
         )?;
+        iteration_context.speculative_analysis = speculative_analysis;
+        compaction::fit_to_budget(config, &mut iteration_context).await;
 
-        // Run the ACP agent iteration
-        let run_result = match acp::connection::run_iteration(&config, &iteration_context).await {
+        // Run the ACP agent iteration, retrying transient agent failures
+        let run_result = match run_iteration_with_retries(db, config, &mut iteration_context).await
+        {
             Ok(result) => result,
             Err(err) => {
-                try_release_claim(&db, &task_id, "agent run error");
+                try_release_claim(db, &task_id, "agent run error");
                 return Err(err).context("Failed to run agent");
             }
         };
+        let steered = steering::take_delivered();
+        for message in &steered {
+            dag::add_log(
+                db,
+                &task_id,
+                &format!(
+                    "User steering (iteration {}): {}",
+                    config.iteration, message
+                ),
+            )?;
+        }
+        if let RunResult::Completed(ref result)
+        | RunResult::TimedOut(ref result)
+        | RunResult::ToolLimitHit(ref result, _) = run_result
+        {
+            previous_attempts.insert(task_id.clone(), PreviousAttempt::from_result(result));
+            let heading = format!(
+                "{} {} (iteration {}, {})",
+                task_id, task.title, config.iteration, config.current_model
+            );
+            let thoughts = Some(result.thoughts.as_str())
+                .filter(|_| config.ralph_config.execution.capture_thoughts);
+            if let Err(e) =
+                run_log.append_transcript(&heading, &result.full_text, thoughts, &steered)
+            {
+                formatter::print_warning(&format!("ralph: could not write transcript: {e:#}"));
+            }
+        }
 
         // Handle interrupt: prompt for feedback, reset task, optionally continue
         let streaming_result = match run_result {
@@ -145,11 +502,15 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
                 );
 
                 // Release claim first so interrupt follow-up failures do not strand task state.
-                dag::release_claim(&db, &task_id).context("Failed to release task claim")?;
+                dag::release_claim(db, &task_id).context("Failed to release task claim")?;
                 formatter::emit_event_info("task", &format!("{} claim released", task_id));
 
-                // Prompt for feedback
-                let feedback = crate::interrupt::prompt_for_feedback(task)?;
+                // Ask how to proceed (TUI choice pane or terminal prompts)
+                let decision = crate::interrupt::resolve_interrupt(task)?;
+                let (feedback, proceed) = match decision {
+                    crate::interrupt::InterruptDecision::Continue { feedback } => (feedback, true),
+                    crate::interrupt::InterruptDecision::Stop { feedback } => (feedback, false),
+                };
 
                 if let Some(ref fb) = feedback {
                     let new_desc = crate::interrupt::append_feedback_to_description(
@@ -158,7 +519,7 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
                         config.iteration,
                     );
                     dag::update_task(
-                        &db,
+                        db,
                         &task_id,
                         dag::TaskUpdate {
                             description: Some(new_desc),
@@ -166,7 +527,7 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
                         },
                     )?;
                     dag::add_log(
-                        &db,
+                        db,
                         &task_id,
                         &format!("User feedback (iteration {}): {}", config.iteration, fb),
                     )?;
@@ -187,31 +548,136 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
                     model: Some(config.current_model.clone()),
                     duration_secs: 0.0,
                     cost_usd: 0.0,
+                    input_tokens: 0,
+                    output_tokens: 0,
                     files_modified: Vec::new(),
+                    files_read: Vec::new(),
                     notes: feedback.clone(),
                     created_at: chrono::Utc::now().to_rfc3339(),
                 };
-                journal::insert_journal_entry(&db, &journal_entry).ok();
+                journal::insert_journal_entry(db, &journal_entry).ok();
 
                 // Clear flag for next iteration
                 crate::interrupt::clear_interrupt();
 
-                // Ask whether to continue
-                if crate::interrupt::should_continue()? {
+                if proceed {
                     formatter::emit_event_info(
                         "interrupt",
                         "continuing \u{2014} user chose to proceed",
                     );
                     formatter::print_separator();
-                    config = config.next_iteration();
+                    *config = config.next_iteration();
                     formatter::emit_iteration_divider(config.iteration);
-                    formatter::print_iteration_info(&config);
+                    formatter::print_iteration_info(config);
                     continue;
                 } else {
                     formatter::emit_event_info("interrupt", "stopping \u{2014} user chose to halt");
                     return Ok(Outcome::Interrupted);
                 }
             }
+            cancelled @ (RunResult::TimedOut(_) | RunResult::ToolLimitHit(..)) => {
+                let (partial, reason, summary, timed_out) = match cancelled {
+                    RunResult::ToolLimitHit(partial, limit) => (
+                        partial,
+                        format!("Iteration {} stopped: {}", config.iteration, limit),
+                        limit,
+                        false,
+                    ),
+                    RunResult::TimedOut(partial) => {
+                        let secs = config.ralph_config.execution.iteration_timeout_secs;
+                        (
+                            partial,
+                            format!("Iteration {} timed out after {}s", config.iteration, secs),
+                            format!("timed out after {secs}s"),
+                            true,
+                        )
+                    }
+                    _ => unreachable!(),
+                };
+                formatter::print_warning(&format!("ralph: {reason}, agent cancelled"));
+                formatter::emit_event(
+                    "iter",
+                    &format!("{summary} \u{2014} cancelled {task_id}"),
+                    true,
+                );
+                // The requeue or failure, its log and the journal entry commit together
+                let mut announcements = Announcements::default();
+                let tx = db.conn().unchecked_transaction()?;
+                dag::add_log(db, &task_id, &reason)?;
+
+                // A cancelled iteration counts as a failed attempt under the retry policy.
+                let max_retries = config.max_retries as i32;
+                let requeued = task.retry_count < max_retries;
+                if requeued {
+                    dag::requeue_task(db, &task_id).context("Failed to requeue cancelled task")?;
+                    formatter::print_retry(
+                        config.iteration,
+                        &task_id,
+                        task.retry_count + 1,
+                        max_retries,
+                    );
+                    announcements.info(
+                        "task",
+                        format!("{} retry {}/{}", task_id, task.retry_count + 1, max_retries),
+                    );
+                } else {
+                    let fail_reason = format!("{reason}; retries exhausted");
+                    let transitions = dag::fail_task(db, &task_id, &fail_reason)
+                        .context("Failed to fail task")?;
+                    announcements.transitions(&transitions);
+                    formatter::print_max_retries_exhausted(config.iteration, &task_id);
+                    announcements.error(
+                        "task",
+                        format!("{} failed \u{2014} {}", task_id, fail_reason),
+                    );
+                    announcements.notify(NotifyEvent::TaskFailed, &task_id, &fail_reason);
+                }
+
+                let journal_entry = journal::JournalEntry {
+                    id: 0,
+                    run_id: config.run_id.clone(),
+                    iteration: config.iteration,
+                    task_id: Some(task_id.clone()),
+                    feature_id: task.feature_id.clone(),
+                    outcome: match (timed_out, requeued) {
+                        (true, _) => "timeout",
+                        (false, true) => "retried",
+                        (false, false) => "failed",
+                    }
+                    .to_string(),
+                    model: Some(config.current_model.clone()),
+                    duration_secs: partial.duration_ms as f64 / 1000.0,
+                    cost_usd: iteration_cost(config, &partial),
+                    input_tokens: partial.usage.input_tokens,
+                    output_tokens: partial.usage.output_tokens,
+                    files_modified: partial.files_modified.clone(),
+                    files_read: partial.files_read.clone(),
+                    notes: Some(reason),
+                    created_at: chrono::Utc::now().to_rfc3339(),
+                };
+                journal::insert_journal_entry(db, &journal_entry)
+                    .context("Failed to write journal entry")?;
+                tx.commit()
+                    .context("Failed to commit cancelled iteration")?;
+                announcements.send();
+                if scoped_target_resolved(config, db, features)
+                    .context("Failed to check if run target is resolved")?
+                {
+                    return Ok(Outcome::Complete);
+                }
+                if config.limit_reached() {
+                    formatter::emit_event_info(
+                        "iter",
+                        &format!(
+                            "iteration limit reached ({}/{})",
+                            config.iteration, config.limit
+                        ),
+                    );
+                    return Ok(Outcome::LimitReached);
+                }
+                advance_iteration_with_model_selection(config, db, progress_db, None);
+                continue;
+            }
             RunResult::Completed(r) => r,
         };
 
@@ -228,7 +694,14 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
                 // Normal completion — extract sigils from accumulated text.
                 // Sigils are already formatted inline during streaming, so no
                 // separate print_sigils() call needed.
-                acp::sigils::extract_sigils(&streaming_result.full_text)
+                let custom_tags: Vec<&str> = config
+                    .ralph_config
+                    .sigils
+                    .custom
+                    .keys()
+                    .map(String::as_str)
+                    .collect();
+                acp::sigils::extract_sigils(&streaming_result.full_text, &custom_tags)
             }
             StopReason::Cancelled => {
                 // Unexpected: cancellation is normally caught in connection.rs.
@@ -239,7 +712,7 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
                     "iter",
                     &format!("agent stopped: Cancelled \u{2014} releasing {}", task_id),
                 );
-                dag::release_claim(&db, &task_id).context("Failed to release task claim")?;
+                dag::release_claim(db, &task_id).context("Failed to release task claim")?;
                 formatter::print_task_incomplete(config.iteration, &task_id);
                 formatter::emit_event_info("task", &format!("{} incomplete (no sigil)", task_id));
                 let journal_entry = journal::JournalEntry {
@@ -251,13 +724,16 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
                     outcome: "blocked".to_string(),
                     model: Some(config.current_model.clone()),
                     duration_secs: streaming_result.duration_ms as f64 / 1000.0,
-                    cost_usd: 0.0,
+                    cost_usd: iteration_cost(config, &streaming_result),
+                    input_tokens: streaming_result.usage.input_tokens,
+                    output_tokens: streaming_result.usage.output_tokens,
                     files_modified: streaming_result.files_modified.clone(),
+                    files_read: streaming_result.files_read.clone(),
                     notes: None,
                     created_at: chrono::Utc::now().to_rfc3339(),
                 };
-                journal::insert_journal_entry(&db, &journal_entry).ok();
-                if scoped_target_resolved(&config, &db, feature_id.as_deref())
+                journal::insert_journal_entry(db, &journal_entry).ok();
+                if scoped_target_resolved(config, db, features)
                     .context("Failed to check if run target is resolved")?
                 {
                     return Ok(Outcome::Complete);
@@ -272,7 +748,7 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
                     );
                     return Ok(Outcome::LimitReached);
                 }
-                advance_iteration_with_model_selection(&mut config, &db, &progress_db, None);
+                advance_iteration_with_model_selection(config, db, progress_db, None);
                 continue;
             }
             StopReason::MaxTokens | StopReason::MaxTurnRequests => {
@@ -287,7 +763,7 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
                         streaming_result.stop_reason, task_id
                     ),
                 );
-                dag::release_claim(&db, &task_id).context("Failed to release task claim")?;
+                dag::release_claim(db, &task_id).context("Failed to release task claim")?;
                 formatter::print_task_incomplete(config.iteration, &task_id);
                 formatter::emit_event_info("task", &format!("{} incomplete (no sigil)", task_id));
                 let journal_entry = journal::JournalEntry {
@@ -299,13 +775,16 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
                     outcome: "blocked".to_string(),
                     model: Some(config.current_model.clone()),
                     duration_secs: streaming_result.duration_ms as f64 / 1000.0,
-                    cost_usd: 0.0,
+                    cost_usd: iteration_cost(config, &streaming_result),
+                    input_tokens: streaming_result.usage.input_tokens,
+                    output_tokens: streaming_result.usage.output_tokens,
                     files_modified: streaming_result.files_modified.clone(),
+                    files_read: streaming_result.files_read.clone(),
                     notes: None,
                     created_at: chrono::Utc::now().to_rfc3339(),
                 };
-                journal::insert_journal_entry(&db, &journal_entry).ok();
-                if scoped_target_resolved(&config, &db, feature_id.as_deref())
+                journal::insert_journal_entry(db, &journal_entry).ok();
+                if scoped_target_resolved(config, db, features)
                     .context("Failed to check if run target is resolved")?
                 {
                     return Ok(Outcome::Complete);
@@ -320,7 +799,7 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
                     );
                     return Ok(Outcome::LimitReached);
                 }
-                advance_iteration_with_model_selection(&mut config, &db, &progress_db, None);
+                advance_iteration_with_model_selection(config, db, progress_db, None);
                 continue;
             }
             StopReason::Refusal => {
@@ -330,7 +809,7 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
                     &format!("agent stopped: Refusal \u{2014} failing {}", task_id),
                     true,
                 );
-                let transitions = dag::fail_task(&db, &task_id, "Agent refused the request")
+                let transitions = dag::fail_task(db, &task_id, "Agent refused the request")
                     .context("Failed to fail task")?;
                 emit_auto_transitions(&transitions);
                 formatter::print_task_failed(config.iteration, &task_id);
@@ -339,6 +818,11 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
                     &format!("{} failed \u{2014} Agent refused the request", task_id),
                     true,
                 );
+                notify::notify(
+                    NotifyEvent::TaskFailed,
+                    &task_id,
+                    "Agent refused the request",
+                );
                 let journal_entry = journal::JournalEntry {
                     id: 0,
                     run_id: config.run_id.clone(),
@@ -348,13 +832,16 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
                     outcome: "failed".to_string(),
                     model: Some(config.current_model.clone()),
                     duration_secs: streaming_result.duration_ms as f64 / 1000.0,
-                    cost_usd: 0.0,
+                    cost_usd: iteration_cost(config, &streaming_result),
+                    input_tokens: streaming_result.usage.input_tokens,
+                    output_tokens: streaming_result.usage.output_tokens,
                     files_modified: streaming_result.files_modified.clone(),
+                    files_read: streaming_result.files_read.clone(),
                     notes: None,
                     created_at: chrono::Utc::now().to_rfc3339(),
                 };
-                journal::insert_journal_entry(&db, &journal_entry).ok();
-                if scoped_target_resolved(&config, &db, feature_id.as_deref())
+                journal::insert_journal_entry(db, &journal_entry).ok();
+                if scoped_target_resolved(config, db, features)
                     .context("Failed to check if run target is resolved")?
                 {
                     return Ok(Outcome::Complete);
@@ -369,7 +856,7 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
                     );
                     return Ok(Outcome::LimitReached);
                 }
-                advance_iteration_with_model_selection(&mut config, &db, &progress_db, None);
+                advance_iteration_with_model_selection(config, db, progress_db, None);
                 continue;
             }
             _ => {
@@ -385,7 +872,7 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
                         streaming_result.stop_reason, task_id
                     ),
                 );
-                dag::release_claim(&db, &task_id).context("Failed to release task claim")?;
+                dag::release_claim(db, &task_id).context("Failed to release task claim")?;
                 formatter::print_task_incomplete(config.iteration, &task_id);
                 formatter::emit_event_info("task", &format!("{} incomplete (no sigil)", task_id));
                 let journal_entry = journal::JournalEntry {
@@ -397,13 +884,16 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
                     outcome: "blocked".to_string(),
                     model: Some(config.current_model.clone()),
                     duration_secs: streaming_result.duration_ms as f64 / 1000.0,
-                    cost_usd: 0.0,
+                    cost_usd: iteration_cost(config, &streaming_result),
+                    input_tokens: streaming_result.usage.input_tokens,
+                    output_tokens: streaming_result.usage.output_tokens,
                     files_modified: streaming_result.files_modified.clone(),
+                    files_read: streaming_result.files_read.clone(),
                     notes: None,
                     created_at: chrono::Utc::now().to_rfc3339(),
                 };
-                journal::insert_journal_entry(&db, &journal_entry).ok();
-                if scoped_target_resolved(&config, &db, feature_id.as_deref())
+                journal::insert_journal_entry(db, &journal_entry).ok();
+                if scoped_target_resolved(config, db, features)
                     .context("Failed to check if run target is resolved")?
                 {
                     return Ok(Outcome::Complete);
@@ -418,7 +908,7 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
                     );
                     return Ok(Outcome::LimitReached);
                 }
-                advance_iteration_with_model_selection(&mut config, &db, &progress_db, None);
+                advance_iteration_with_model_selection(config, db, progress_db, None);
                 continue;
             }
         };
@@ -430,89 +920,76 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
 
         // Check for FAILURE sigil - this short-circuits before DAG update
         if sigils.is_failure {
-            dag::release_claim(&db, &task_id)
+            dag::release_claim(db, &task_id)
                 .context("Failed to release task claim before failure outcome")?;
             return Ok(Outcome::Failure);
         }
 
-        // Handle task completion/failure sigils
-        if let Some(ref done_id) = sigils.task_done {
-            if done_id == &task_id {
-                if let Err(err) = handle_task_done(
-                    &db,
-                    &config,
+        // Verify the task the agent reports done before anything is recorded
+        let done = match &sigils.task_done {
+            Some(done_id) if done_id == &task_id => {
+                // One verification in flight at a time
+                if let Some(pending) = in_flight.take() {
+                    finish_verification(db, config, pending).await?;
+                }
+                // Explore the next ready task while this one is verified
+                if config.verify
+                    && config.ralph_config.execution.speculative_exploration
+                    && !config.ralph_config.execution.pipeline_verification
+                {
+                    let ready = get_scoped_ready_tasks(config, db, features, next_feature)?;
+                    if let Some(next) = ready.iter().find(|t| t.id != task_id) {
+                        let context = feature_context(features, next);
+                        formatter::emit_event_info(
+                            "speculate",
+                            &format!("exploring {} while {task_id} is verified", next.id),
+                        );
+                        speculation = Some(Speculation::start(
+                            config,
+                            next,
+                            context.and_then(|f| f.spec.as_deref()),
+                            context.and_then(|f| f.plan.as_deref()),
+                        ));
+                    }
+                }
+                match handle_task_done(
+                    db,
+                    config,
                     task,
-                    spec_content.as_deref(),
-                    plan_content.as_deref(),
+                    spec_content,
+                    plan_content,
+                    &streaming_result.files_modified,
                     &log_file,
                 )
                 .await
                 {
-                    try_release_claim(&db, &task_id, "task completion handling error");
-                    return Err(err).context("Failed to handle task completion");
-                }
-            } else {
-                formatter::print_warning(&format!(
-                    "Warning: task-done sigil ID {} does not match assigned task {}",
-                    done_id, task_id
-                ));
-                dag::release_claim(&db, &task_id).context("Failed to release task claim")?;
-                formatter::print_task_incomplete(config.iteration, &task_id);
-                formatter::emit_event_info("task", &format!("{} incomplete (no sigil)", task_id));
-            }
-        } else if let Some(ref failed_id) = sigils.task_failed {
-            if failed_id == &task_id {
-                match dag::fail_task(&db, &task_id, "Task marked failed by Claude") {
-                    Ok(transitions) => emit_auto_transitions(&transitions),
+                    Ok(done) => Some(done),
                     Err(err) => {
-                        try_release_claim(&db, &task_id, "task failure handling error");
-                        return Err(err).context("Failed to fail task");
+                        try_release_claim(db, &task_id, "task completion handling error");
+                        return Err(err).context("Failed to handle task completion");
                     }
                 }
-                formatter::print_task_failed(config.iteration, &task_id);
-                formatter::emit_event(
-                    "task",
-                    &format!("{} failed \u{2014} Task marked failed by Claude", task_id),
-                    true,
-                );
-            } else {
-                formatter::print_warning(&format!(
-                    "Warning: task-failed sigil ID {} does not match assigned task {}",
-                    failed_id, task_id
-                ));
-                dag::release_claim(&db, &task_id).context("Failed to release task claim")?;
-                formatter::print_task_incomplete(config.iteration, &task_id);
-                formatter::emit_event_info("task", &format!("{} incomplete (no sigil)", task_id));
             }
-        } else {
-            // No sigil - release the claim and treat as incomplete
-            dag::release_claim(&db, &task_id).context("Failed to release task claim")?;
-            formatter::print_task_incomplete(config.iteration, &task_id);
-            formatter::emit_event_info("task", &format!("{} incomplete (no sigil)", task_id));
-        }
+            _ => None,
+        };
 
         // Post-iteration: write journal entry and knowledge files
         {
-            // Determine outcome by comparing retry_count before/after handle_task_done
-            let updated_task = dag::get_task(&db, &task_id).ok();
-            let outcome = if let Some(ref t) = updated_task {
-                if t.retry_count > task.retry_count {
-                    "retried"
-                } else if t.status == "done" {
-                    "done"
-                } else if t.status == "failed" {
-                    "failed"
-                } else {
-                    "blocked"
-                }
-            } else if sigils.task_done.is_some() {
-                "done"
-            } else if sigils.task_failed.is_some() {
-                "failed"
-            } else {
-                "blocked"
+            let outcome = match (&done, &sigils.task_done, &sigils.task_failed) {
+                (Some(done), _, _) => done.outcome(task),
+                (None, None, Some(failed_id)) if failed_id == &task_id => "failed",
+                _ => "blocked",
             };
 
+            // Optionally a cheap model rewrites the agent's note from the transcript
+            let (notes, summary_cost) = journal_summary::notes(
+                config,
+                task,
+                &streaming_result,
+                sigils.journal_notes.as_deref(),
+                outcome,
+            )
+            .await;
             let journal_entry = journal::JournalEntry {
                 id: 0, // ignored on insert (AUTOINCREMENT)
                 run_id: config.run_id.clone(),
@@ -521,39 +998,48 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
                 feature_id: task.feature_id.clone(),
                 outcome: outcome.to_string(),
                 model: Some(config.current_model.clone()),
-                // ACP does not report cost — always 0.0 (NFR-5.1)
                 duration_secs: streaming_result.duration_ms as f64 / 1000.0,
-                cost_usd: 0.0,
+                cost_usd: iteration_cost(config, &streaming_result) + summary_cost,
+                input_tokens: streaming_result.usage.input_tokens,
+                output_tokens: streaming_result.usage.output_tokens,
                 files_modified: streaming_result.files_modified.clone(),
-                notes: sigils.journal_notes.clone(),
+                files_read: streaming_result.files_read.clone(),
+                notes,
                 created_at: chrono::Utc::now().to_rfc3339(),
             };
-            match journal::insert_journal_entry(&db, &journal_entry) {
-                Ok(_) => {
-                    formatter::emit_event_info(
-                        "journal",
-                        &format!(
-                            "entry written \u{2014} iteration {}, outcome={}",
-                            config.iteration, outcome
-                        ),
-                    );
-                }
-                Err(e) => {
-                    formatter::print_warning(&format!(
-                        "Warning: failed to write journal entry: {}",
-                        e
-                    ));
-                    formatter::emit_event("journal", &format!("write failed \u{2014} {}", e), true);
+            let transitions = match record_iteration(
+                db,
+                config,
+                task,
+                &sigils,
+                done,
+                &journal_entry,
+                in_flight,
+            ) {
+                Ok(transitions) => transitions,
+                Err(err) => {
+                    try_release_claim(db, &task_id, "iteration bookkeeping error");
+                    return Err(err).context("Failed to record iteration");
                 }
-            }
+            };
+            formatter::emit_event_info(
+                "journal",
+                &format!(
+                    "entry written \u{2014} iteration {}, outcome={}",
+                    config.iteration, outcome
+                ),
+            );
+            create_follow_ups(db, config, &transitions)?;
 
             // Write knowledge entries emitted by the agent
             for sigil in &sigils.knowledge_entries {
-                let feature_name = match &config.run_target {
-                    Some(RunTarget::Feature(name)) => Some(name.as_str()),
-                    _ => None,
-                };
-                match knowledge::write_knowledge_entry(&config.project_root, sigil, feature_name) {
+                let feature_name = feature.map(|f| f.name.as_str());
+                match knowledge::write_knowledge_entry(
+                    &config.project_root,
+                    sigil,
+                    feature_name,
+                    config.ralph_config.knowledge.scope,
+                ) {
                     Ok(path) => {
                         formatter::print_info(&format!(
                             "  Knowledge entry written: {}",
@@ -577,10 +1063,70 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
                     }
                 }
             }
+
+            // Record files the agent registered for dependent tasks
+            register_artifacts(
+                db,
+                &config.project_root,
+                &task_id,
+                iteration_context.task.cwd.as_deref(),
+                &sigils.artifacts,
+            );
+
+            // Run project-defined sigil actions
+            for sigil in &sigils.custom {
+                let Some(custom) = config.ralph_config.sigils.custom.get(&sigil.tag) else {
+                    continue;
+                };
+                match crate::sigil_actions::apply(
+                    db,
+                    &config.project_root,
+                    task,
+                    sigil,
+                    &custom.action,
+                    Some(&config.attribution()),
+                )
+                .await
+                {
+                    Ok(summary) => {
+                        formatter::emit_event_info("sigil", &format!("<{}> {}", sigil.tag, summary))
+                    }
+                    Err(e) => {
+                        formatter::print_warning(&format!(
+                            "  Warning: <{}> sigil action failed: {}",
+                            sigil.tag, e
+                        ));
+                        formatter::emit_event(
+                            "sigil",
+                            &format!("<{}> failed \u{2014} {}", sigil.tag, e),
+                            true,
+                        );
+                    }
+                }
+            }
+
+            // Apply follow-up work the agent queued with <task-add>, <task-block>, <dep-add>
+            for sigil in &sigils.queue {
+                match crate::sigil_actions::apply_queue(
+                    db,
+                    task,
+                    sigil,
+                    Some(&config.attribution()),
+                ) {
+                    Ok(summary) => {
+                        formatter::print_queue_change(config.iteration, &summary);
+                        formatter::emit_event_info("queue", &summary);
+                    }
+                    Err(e) => {
+                        formatter::print_warning(&format!("  Warning: queue sigil ignored: {}", e));
+                        formatter::emit_event("queue", &format!("ignored \u{2014} {}", e), true);
+                    }
+                }
+            }
         }
 
         // Check if all tasks are resolved
-        if scoped_target_resolved(&config, &db, feature_id.as_deref())
+        if scoped_target_resolved(config, db, features)
             .context("Failed to check if run target is resolved")?
         {
             return Ok(Outcome::Complete);
@@ -599,53 +1145,215 @@ pub async fn run(mut config: Config) -> Result<Outcome> {
         }
 
         // Continue to next iteration
-        advance_iteration_with_model_selection(
-            &mut config,
-            &db,
-            &progress_db,
-            next_model_hint.as_deref(),
-        );
+        advance_iteration_with_model_selection(config, db, progress_db, next_model_hint.as_deref());
     }
 }
 
-fn emit_auto_transitions(transitions: &[dag::AutoTransition]) {
-    for t in transitions {
-        match t {
-            dag::AutoTransition::Unblocked {
-                blocked_id,
-                blocker_id,
-            } => formatter::emit_event_info(
-                "dag",
-                &format!("{blocked_id} unblocked (blocker {blocker_id} done)"),
-            ),
-            dag::AutoTransition::ParentCompleted { parent_id } => formatter::emit_event_info(
-                "dag",
-                &format!("{parent_id} auto-completed (all children done)"),
-            ),
-            dag::AutoTransition::ParentFailed {
-                parent_id,
-                child_id,
-            } => formatter::emit_event_info(
-                "dag",
-                &format!("{parent_id} auto-failed (child {child_id} failed)"),
-            ),
-            dag::AutoTransition::FeatureDone { feature_name } => formatter::emit_event_info(
-                "feature",
-                &format!("feature \"{feature_name}\" \u{2192} done (all tasks resolved)"),
-            ),
-            dag::AutoTransition::FeatureFailed { feature_name } => formatter::emit_event(
-                "feature",
-                &format!(
-                    "feature \"{feature_name}\" \u{2192} failed (tasks resolved with failures)"
-                ),
-                true,
-            ),
+/// Apply the task's end-of-iteration state (completion, failure or a
+/// released claim) and write the iteration's journal entry in one
+/// transaction, so a crash leaves either all of it or none. Returns the
+/// auto-transitions; a verification still in flight lands in `in_flight`.
+fn record_iteration(
+    db: &Db,
+    config: &Config,
+    task: &Task,
+    sigils: &acp::types::SigilResult,
+    done: Option<DoneVerdict>,
+    journal_entry: &journal::JournalEntry,
+    in_flight: &mut Option<InFlightVerification>,
+) -> Result<Vec<dag::AutoTransition>> {
+    let task_id = &task.id;
+    let mut announcements = Announcements::default();
+    let tx = db.conn().unchecked_transaction()?;
+    let (transitions, pending) = match (done, &sigils.task_done, &sigils.task_failed) {
+        (Some(done), _, _) => record_done(db, config, task, done, &mut announcements)?,
+        (None, None, Some(failed_id)) if failed_id == task_id => {
+            let transitions = dag::fail_task(db, task_id, "Task marked failed by Claude")
+                .context("Failed to fail task")?;
+            announcements.transitions(&transitions);
+            formatter::print_task_failed(config.iteration, task_id);
+            announcements.error(
+                "task",
+                format!("{} failed \u{2014} Task marked failed by Claude", task_id),
+            );
+            announcements.notify(
+                NotifyEvent::TaskFailed,
+                task_id,
+                "Task marked failed by Claude",
+            );
+            (transitions, None)
         }
-    }
-}
-
-fn try_release_claim(db: &Db, task_id: &str, context: &str) {
-    if let Err(err) = dag::release_claim(db, task_id) {
+        (None, done_id, failed_id) => {
+            // No sigil, or one naming another task: treat as incomplete
+            if let Some(id) = done_id {
+                formatter::print_warning(&format!(
+                    "Warning: task-done sigil ID {} does not match assigned task {}",
+                    id, task_id
+                ));
+            } else if let Some(id) = failed_id {
+                formatter::print_warning(&format!(
+                    "Warning: task-failed sigil ID {} does not match assigned task {}",
+                    id, task_id
+                ));
+            }
+            dag::release_claim(db, task_id).context("Failed to release task claim")?;
+            formatter::print_task_incomplete(config.iteration, task_id);
+            announcements.info("task", format!("{} incomplete (no sigil)", task_id));
+            (Vec::new(), None)
+        }
+    };
+    let journal_id = journal::insert_journal_entry(db, journal_entry)
+        .context("Failed to write journal entry")?;
+    tx.commit().context("Failed to commit iteration")?;
+    announcements.send();
+    if let Some(mut pending) = pending {
+        pending.journal_id = Some(journal_id);
+        *in_flight = Some(pending);
+    }
+    Ok(transitions)
+}
+
+fn emit_auto_transitions(transitions: &[dag::AutoTransition]) {
+    let mut announcements = Announcements::default();
+    announcements.transitions(transitions);
+    announcements.send();
+}
+
+/// Events and notifications raised while a transaction is open. They go out
+/// once it commits, so a rolled-back change is never announced and a slow
+/// webhook never holds the database lock.
+#[derive(Default)]
+struct Announcements {
+    events: Vec<(&'static str, String, bool)>,
+    notifications: Vec<(NotifyEvent, String, String)>,
+}
+
+impl Announcements {
+    fn info(&mut self, category: &'static str, message: String) {
+        self.events.push((category, message, false));
+    }
+
+    fn error(&mut self, category: &'static str, message: String) {
+        self.events.push((category, message, true));
+    }
+
+    fn notify(&mut self, event: NotifyEvent, task_id: &str, detail: &str) {
+        self.notifications
+            .push((event, task_id.to_string(), detail.to_string()));
+    }
+
+    fn transitions(&mut self, transitions: &[dag::AutoTransition]) {
+        for t in transitions {
+            match t {
+                dag::AutoTransition::Unblocked {
+                    blocked_id,
+                    blocker_id,
+                } => self.info(
+                    "dag",
+                    format!("{blocked_id} unblocked (blocker {blocker_id} done)"),
+                ),
+                dag::AutoTransition::ParentCompleted { parent_id } => self.info(
+                    "dag",
+                    format!("{parent_id} auto-completed (all children done)"),
+                ),
+                dag::AutoTransition::ParentFailed {
+                    parent_id,
+                    child_id,
+                } => self.info(
+                    "dag",
+                    format!("{parent_id} auto-failed (child {child_id} failed)"),
+                ),
+                dag::AutoTransition::FeatureDone { feature_name } => self.info(
+                    "feature",
+                    format!("feature \"{feature_name}\" \u{2192} done (all tasks resolved)"),
+                ),
+                dag::AutoTransition::FeatureFailed { feature_name } => self.error(
+                    "feature",
+                    format!(
+                        "feature \"{feature_name}\" \u{2192} failed (tasks resolved with failures)"
+                    ),
+                ),
+            }
+        }
+    }
+
+    /// Emit the events, then send the notifications.
+    fn send(self) {
+        for (category, message, is_error) in &self.events {
+            formatter::emit_event(category, message, *is_error);
+        }
+        for (event, task_id, detail) in &self.notifications {
+            notify::notify(*event, task_id, detail);
+        }
+    }
+}
+
+/// Journal cost for an iteration: agent-reported cost, else priced token counts.
+fn iteration_cost(config: &Config, result: &StreamingResult) -> f64 {
+    crate::pricing::cost_usd(
+        &crate::agent_models::resolve(&config.agent_command, &config.current_model),
+        &result.usage,
+        &config.ralph_config.pricing,
+    )
+}
+
+/// Run one iteration, retrying with exponential backoff while the agent fails
+/// transiently (see [`acp::connection::TransientAgentError`]), up to
+/// `execution.agent_retries` times. A retry resumes the failed attempt's
+/// session when `resume_sessions` is on. Ctrl+C during the backoff counts as
+/// an interrupt of the iteration.
+async fn run_iteration_with_retries(
+    db: &Db,
+    config: &Config,
+    context: &mut IterationContext,
+) -> Result<RunResult> {
+    let max_retries = config.ralph_config.execution.agent_retries;
+    let mut attempt = 0;
+    loop {
+        let attempt_result = match config.chaos.as_ref().and_then(|c| c.agent_crash()) {
+            Some(crash) => Err(crash),
+            None => acp::connection::run_iteration(config, context).await,
+        };
+        let err = match attempt_result {
+            Ok(mut result) => {
+                if let Some(chaos) = &config.chaos {
+                    chaos.mangle(&mut result);
+                }
+                return Ok(result);
+            }
+            Err(err) => err,
+        };
+        let Some(transient) = err.downcast_ref::<acp::connection::TransientAgentError>() else {
+            return Err(err);
+        };
+        if attempt >= max_retries {
+            return Err(err);
+        }
+        attempt += 1;
+        let delay = config.agent_retry_delay(attempt);
+        let message = format!(
+            "Agent failed ({transient}); retry {attempt}/{max_retries} in {}s",
+            delay.as_secs()
+        );
+        formatter::print_warning(&format!("ralph: {}: {message}", context.task.task_id));
+        formatter::emit_event("iter", &message, true);
+        dag::add_log(db, &context.task.task_id, &message)?;
+        if config.ralph_config.execution.resume_sessions && transient.session_id.is_some() {
+            context.resume_session_id = transient.session_id.clone();
+        }
+
+        let deadline = std::time::Instant::now() + delay;
+        while std::time::Instant::now() < deadline {
+            if crate::interrupt::is_interrupted() {
+                return Ok(RunResult::Interrupted);
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+    }
+}
+
+fn try_release_claim(db: &Db, task_id: &str, context: &str) {
+    if let Err(err) = dag::release_claim(db, task_id) {
         formatter::print_warning(&format!(
             "Warning: failed to release task claim for {task_id} after {context}: {err}"
         ));
@@ -711,6 +1419,7 @@ fn advance_iteration_with_model_selection(
         );
     }
 
+    config.model_hinted = selection.hint.is_some();
     config.current_model = selection.model;
     formatter::print_iteration_info(config);
     formatter::emit_event_info(
@@ -722,34 +1431,92 @@ fn advance_iteration_with_model_selection(
     );
 }
 
-/// Resolve feature context: returns (feature_id, spec_content, plan_content).
-fn resolve_feature_context(
-    config: &Config,
-    db: &Db,
-) -> Result<(Option<String>, Option<String>, Option<String>)> {
-    match &config.run_target {
-        Some(RunTarget::Feature(name)) => {
-            let feat = feature::get_feature(db, name)?;
-            let spec = feature::read_spec(&config.project_root, name).ok();
-            let plan = feature::read_plan(&config.project_root, name).ok();
-            Ok((Some(feat.id), spec, plan))
+/// A feature in the run's scope, with the documents its tasks are given.
+/// Refresh the TUI's Progress panel: a burn-down per feature in the run (or
+/// one for all tasks) and the daily throughput across them.
+fn show_progress(db: &Db, features: &[FeatureContext]) -> Result<()> {
+    if !crate::ui::is_active() {
+        return Ok(());
+    }
+    let mut burndowns = Vec::new();
+    for feature in features {
+        burndowns.push(Burndown {
+            feature: Some(feature.name.clone()),
+            days: burndown::compute(db, Some(&feature.id), burndown::WINDOW_DAYS)?,
+        });
+    }
+    if burndowns.is_empty() {
+        burndowns.push(Burndown {
+            feature: None,
+            days: burndown::compute(db, None, burndown::WINDOW_DAYS)?,
+        });
+    }
+
+    let mut throughput = vec![0; burndown::WINDOW_DAYS as usize];
+    for b in &burndowns {
+        for (total, day) in throughput.iter_mut().zip(&b.days) {
+            *total += day.completed;
+        }
+    }
+    formatter::print_progress(burndown::render(&burndowns, &throughput));
+    Ok(())
+}
+
+struct FeatureContext {
+    id: String,
+    name: String,
+    spec: Option<String>,
+    plan: Option<String>,
+    /// Commit the plan was written against (`diff_context = "feature"`).
+    plan_commit: Option<String>,
+}
+
+impl FeatureContext {
+    fn load(config: &Config, feat: feature::Feature) -> Self {
+        Self {
+            spec: feature::read_spec(&config.project_root, &feat.name).ok(),
+            plan: feature::read_plan(&config.project_root, &feat.name).ok(),
+            plan_commit: feat.plan_commit,
+            id: feat.id,
+            name: feat.name,
         }
+    }
+}
+
+/// The features the run targets, in the order given; for a task target, the
+/// task's feature (if any).
+fn resolve_feature_contexts(config: &Config, db: &Db) -> Result<Vec<FeatureContext>> {
+    match &config.run_target {
+        Some(RunTarget::Features { names, .. }) => names
+            .iter()
+            .map(|name| {
+                Ok(FeatureContext::load(
+                    config,
+                    feature::get_feature(db, name)?,
+                ))
+            })
+            .collect(),
         Some(RunTarget::Task(task_id)) => {
             // Standalone task — check if it has a feature_id
             let task = dag::get_task(db, task_id)?;
-            if let Some(ref fid) = task.feature_id {
-                let feat = feature::get_feature_by_id(db, fid)?;
-                let spec = feature::read_spec(&config.project_root, &feat.name).ok();
-                let plan = feature::read_plan(&config.project_root, &feat.name).ok();
-                Ok((Some(fid.clone()), spec, plan))
-            } else {
-                Ok((None, None, None))
+            match task.feature_id {
+                Some(ref fid) => Ok(vec![FeatureContext::load(
+                    config,
+                    feature::get_feature_by_id(db, fid)?,
+                )]),
+                None => Ok(Vec::new()),
             }
         }
-        None => Ok((None, None, None)),
+        None => Ok(Vec::new()),
     }
 }
 
+/// The context of the feature `task` belongs to, if it is in scope.
+fn feature_context<'a>(features: &'a [FeatureContext], task: &Task) -> Option<&'a FeatureContext> {
+    let fid = task.feature_id.as_deref()?;
+    features.iter().find(|f| f.id == fid)
+}
+
 /// If a task-target run has no ready tasks because this run left its own claim
 /// in `in_progress`, release it and continue the loop.
 fn recover_stuck_target_claim(config: &Config, db: &Db) -> Result<bool> {
@@ -778,150 +1545,553 @@ fn recover_stuck_target_claim(config: &Config, db: &Db) -> Result<bool> {
     Ok(true)
 }
 
-/// Get ready tasks scoped to the run target.
-fn get_scoped_ready_tasks(config: &Config, db: &Db, feature_id: Option<&str>) -> Result<Vec<Task>> {
-    match &config.run_target {
-        Some(RunTarget::Feature(_)) => {
-            if let Some(fid) = feature_id {
-                dag::get_ready_tasks_for_feature(db, fid)
-                    .context("Failed to get ready tasks for feature")
-            } else {
-                dag::get_ready_tasks(db).context("Failed to get ready tasks")
+/// Get ready tasks scoped to the run target, ordered by the configured scheduler.
+///
+/// Several features are ordered feature by feature, each by the scheduler:
+/// in the order given (`sequential`), or starting from the feature at index
+/// `next_feature` and wrapping around (`interleaved`).
+fn get_scoped_ready_tasks(
+    config: &Config,
+    db: &Db,
+    features: &[FeatureContext],
+    next_feature: usize,
+) -> Result<Vec<Task>> {
+    let mut ready = match &config.run_target {
+        Some(RunTarget::Features { order, .. }) => {
+            let mut groups = Vec::with_capacity(features.len());
+            for feature in features {
+                let mut ready = dag::get_ready_tasks_for_feature(db, &feature.id)
+                    .context("Failed to get ready tasks for feature")?;
+                ready.retain(|t| config.selects(&t.id));
+                config.scheduler.order(&mut ready, chrono::Utc::now());
+                groups.push(ready);
+            }
+            if *order == FeatureOrder::Interleaved && !groups.is_empty() {
+                let start = next_feature % groups.len();
+                groups.rotate_left(start);
             }
+            return Ok(groups.into_iter().flatten().collect());
         }
         Some(RunTarget::Task(task_id)) => {
             // For a standalone task target, only return that task if it's ready
             let ready = dag::get_ready_tasks(db).context("Failed to get ready tasks")?;
-            Ok(ready.into_iter().filter(|t| t.id == *task_id).collect())
+            ready.into_iter().filter(|t| t.id == *task_id).collect()
         }
-        None => dag::get_ready_tasks(db).context("Failed to get ready tasks"),
-    }
+        None => dag::get_ready_tasks(db).context("Failed to get ready tasks")?,
+    };
+    ready.retain(|t| config.selects(&t.id));
+    config.scheduler.order(&mut ready, chrono::Utc::now());
+    Ok(ready)
 }
 
 /// Check whether the current run target is resolved.
 ///
 /// - `run <task-id>` resolves when that task is `done` or `failed`, regardless
 ///   of unrelated tasks elsewhere in the DAG.
-/// - `run <feature-name>` resolves when all tasks for that feature are `done`/`failed`.
+/// - `run <feature-name>...` resolves when all tasks of every named feature
+///   are `done`/`failed`.
 /// - Unscoped runs resolve when the full DAG is resolved.
-fn scoped_target_resolved(config: &Config, db: &Db, feature_id: Option<&str>) -> Result<bool> {
+fn scoped_target_resolved(config: &Config, db: &Db, features: &[FeatureContext]) -> Result<bool> {
+    // `--only`: done once every selected task is
+    if !config.only.is_empty() {
+        for id in config.only.iter().filter(|id| config.selects(id)) {
+            let task = dag::get_task(db, id)?;
+            if !matches!(task.status.as_str(), "done" | "failed") {
+                return Ok(false);
+            }
+        }
+        return Ok(true);
+    }
+    // `--skip`: skipped leaves (and the parents they keep open) don't count
+    if !config.skip.is_empty() {
+        let feature_ids: Vec<Option<&str>> = match &config.run_target {
+            Some(RunTarget::Features { .. }) => {
+                features.iter().map(|f| Some(f.id.as_str())).collect()
+            }
+            _ => vec![None],
+        };
+        for feature_id in feature_ids {
+            let mut stmt = db.conn().prepare(
+                "SELECT t.id FROM tasks t
+                 WHERE t.status NOT IN ('done', 'failed') AND t.archived_at IS NULL
+                   AND NOT EXISTS (SELECT 1 FROM tasks c WHERE c.parent_id = t.id)
+                   AND (?1 IS NULL OR t.feature_id = ?1)",
+            )?;
+            let unresolved = stmt
+                .query_map([feature_id], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+            if unresolved.iter().any(|id| config.selects(id)) {
+                return Ok(false);
+            }
+        }
+        return Ok(true);
+    }
     match &config.run_target {
         Some(RunTarget::Task(task_id)) => {
             let task = dag::get_task(db, task_id)?;
             Ok(matches!(task.status.as_str(), "done" | "failed"))
         }
-        Some(RunTarget::Feature(_)) => {
-            if let Some(fid) = feature_id {
+        Some(RunTarget::Features { .. }) => {
+            for feature in features {
                 let unresolved: i64 = db.conn().query_row(
                     "SELECT COUNT(*) FROM tasks WHERE feature_id = ? AND status NOT IN ('done', 'failed')",
-                    [fid],
+                    [&feature.id],
                     |row| row.get(0),
                 )?;
-                Ok(unresolved == 0)
-            } else {
-                dag::all_resolved(db)
+                if unresolved > 0 {
+                    return Ok(false);
+                }
             }
+            Ok(true)
         }
         None => dag::all_resolved(db),
     }
 }
 
+/// Tasks in the run's scope and how many of them are unresolved leaves.
+fn scoped_work(config: &Config, db: &Db, features: &[FeatureContext]) -> Result<(usize, usize)> {
+    const COUNTS: &str = "SELECT COUNT(*), COALESCE(SUM(status NOT IN ('done', 'failed') \
+         AND NOT EXISTS (SELECT 1 FROM tasks c WHERE c.parent_id = t.id)), 0) FROM tasks t";
+    let count = |filter: &str, param: Option<&str>| -> Result<(usize, usize)> {
+        let sql = format!("{COUNTS} WHERE {filter}");
+        let row = |row: &rusqlite::Row| Ok((row.get(0)?, row.get(1)?));
+        Ok(match param {
+            Some(param) => db.conn().query_row(&sql, [param], row)?,
+            None => db.conn().query_row(&sql, [], row)?,
+        })
+    };
+    match &config.run_target {
+        Some(RunTarget::Task(task_id)) => count("t.id = ?", Some(task_id)),
+        Some(RunTarget::Features { .. }) => features.iter().try_fold((0, 0), |acc, feature| {
+            let (tasks, leaves) = count("t.feature_id = ?", Some(&feature.id))?;
+            Ok((acc.0 + tasks, acc.1 + leaves))
+        }),
+        None => count("t.archived_at IS NULL", None),
+    }
+}
+
+/// The run's stats for `[run] exit_when`: leaf tasks in scope by status,
+/// and this run's journal entries.
+fn run_stats(
+    config: &Config,
+    db: &Db,
+    features: &[FeatureContext],
+    started: std::time::Instant,
+) -> Result<RunStats> {
+    const LEAVES: &str = "SELECT COUNT(*), COALESCE(SUM(t.status = 'done'), 0), \
+         COALESCE(SUM(t.status = 'failed'), 0) FROM tasks t \
+         WHERE NOT EXISTS (SELECT 1 FROM tasks c WHERE c.parent_id = t.id) \
+           AND t.archived_at IS NULL";
+    let count = |filter: &str, param: Option<&str>| -> Result<(usize, usize, usize)> {
+        let sql = format!("{LEAVES} AND {filter}");
+        let row = |row: &rusqlite::Row| Ok((row.get(0)?, row.get(1)?, row.get(2)?));
+        Ok(match param {
+            Some(param) => db.conn().query_row(&sql, [param], row)?,
+            None => db.conn().query_row(&sql, [], row)?,
+        })
+    };
+    let (total, done, failed) = match &config.run_target {
+        Some(RunTarget::Task(task_id)) => {
+            let tree = dag::get_task_tree(db, task_id)?;
+            let leaves: Vec<&Task> = tree
+                .iter()
+                .filter(|t| !tree.iter().any(|c| c.parent_id.as_ref() == Some(&t.id)))
+                .collect();
+            let with = |status: &str| leaves.iter().filter(|t| t.status == status).count();
+            (leaves.len(), with("done"), with("failed"))
+        }
+        Some(RunTarget::Features { .. }) => {
+            features.iter().try_fold((0, 0, 0), |acc, feature| {
+                let (t, d, f) = count("t.feature_id = ?", Some(&feature.id))?;
+                Ok::<_, anyhow::Error>((acc.0 + t, acc.1 + d, acc.2 + f))
+            })?
+        }
+        None => count("1", None)?,
+    };
+    let (iterations, cost, tokens, failed_iterations) = db.conn().query_row(
+        "SELECT COUNT(*), COALESCE(SUM(cost_usd), 0), \
+                COALESCE(SUM(input_tokens + output_tokens), 0), \
+                COALESCE(SUM(outcome IN ('failed', 'timeout')), 0)
+         FROM journal WHERE run_id = ?",
+        [&config.run_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+    )?;
+    Ok(RunStats {
+        total,
+        done,
+        failed,
+        cost,
+        tokens,
+        iterations,
+        failed_iterations,
+        elapsed_mins: started.elapsed().as_secs_f64() / 60.0,
+    })
+}
+
+/// Under `--limit auto`, recompute the limit whenever the scope has grown
+/// (the first pass, `<task-add>` and other task-creating sigils).
+fn update_auto_limit(config: &mut Config, db: &Db, features: &[FeatureContext]) -> Result<()> {
+    let Some(auto) = config.auto_limit else {
+        return Ok(());
+    };
+    let (tasks, leaves) = scoped_work(config, db, features).context("Failed to count work")?;
+    let first = auto.tasks_seen == 0;
+    if let Some(limit) = config.update_auto_limit(tasks, leaves) {
+        let verb = if first { "set to" } else { "raised to" };
+        formatter::emit_event_info(
+            "iter",
+            &format!(
+                "iteration limit {verb} {limit} ({leaves} unresolved leaf tasks \u{d7} {})",
+                auto.factor
+            ),
+        );
+    }
+    Ok(())
+}
+
+/// Where one feature of a multi-feature run ended up.
+#[derive(Debug, PartialEq)]
+pub struct FeatureSummary {
+    pub name: String,
+    /// Leaf tasks; parents only group them.
+    pub total: usize,
+    pub done: usize,
+    pub failed: usize,
+}
+
+impl std::fmt::Display for FeatureSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = if self.done == self.total {
+            "complete"
+        } else if self.done + self.failed == self.total {
+            "failed"
+        } else {
+            "incomplete"
+        };
+        write!(
+            f,
+            "{}: {state} ({}/{} tasks done",
+            self.name, self.done, self.total
+        )?;
+        if self.failed > 0 {
+            write!(f, ", {} failed", self.failed)?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// Leaf task counts of each named feature, for the end-of-run summary.
+pub fn summarize_features(db: &Db, names: &[String]) -> Result<Vec<FeatureSummary>> {
+    names
+        .iter()
+        .map(|name| {
+            let feat = feature::get_feature(db, name)?;
+            let (total, done, failed) = db.conn().query_row(
+                "SELECT COUNT(*),
+                        COALESCE(SUM(t.status = 'done'), 0),
+                        COALESCE(SUM(t.status = 'failed'), 0)
+                 FROM tasks t
+                 WHERE t.feature_id = ? AND t.archived_at IS NULL
+                   AND NOT EXISTS (SELECT 1 FROM tasks c WHERE c.parent_id = t.id)",
+                [&feat.id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )?;
+            Ok(FeatureSummary {
+                name: feat.name,
+                total,
+                done,
+                failed,
+            })
+        })
+        .collect()
+}
+
+/// The context a run would give an agent working `task`, for people working
+/// it by hand (`ralph task next`).
+pub fn task_context(db: &Db, config: &Config, task: &Task) -> Result<IterationContext> {
+    let feature = match task.feature_id {
+        Some(ref id) => Some(FeatureContext::load(
+            config,
+            feature::get_feature_by_id(db, id)?,
+        )),
+        None => None,
+    };
+    build_iteration_context(
+        db,
+        task,
+        feature.as_ref(),
+        config,
+        None,
+        &mut ContextCache::default(),
+    )
+}
+
 /// Build the full iteration context for the assigned task.
 fn build_iteration_context(
     db: &Db,
     task: &Task,
-    spec_content: Option<&str>,
-    plan_content: Option<&str>,
+    feature: Option<&FeatureContext>,
     config: &Config,
+    previous: Option<&PreviousAttempt>,
+    context_cache: &mut ContextCache,
 ) -> Result<IterationContext> {
     // Build parent context
     let parent = if let Some(ref pid) = task.parent_id {
         let parent_task = dag::get_task(db, pid).ok();
         parent_task.map(|p| ParentContext {
+            description: p.frontmatter().1.to_string(),
             title: p.title,
-            description: p.description,
         })
     } else {
         None
     };
 
     // Build completed blockers context
-    let completed_blockers = get_completed_blockers(db, &task.id)?;
+    let completed_blockers = get_completed_blockers(db, &config.project_root, &task.id)?;
 
     // Build retry info if this is a retry
+    let previous = previous.filter(|_| task.retry_count > 0);
     let retry_info = if task.retry_count > 0 {
         let failure_reason = get_last_failure_reason(db, &task.id)?;
         Some(RetryInfo {
             attempt: task.retry_count + 1,
             max_retries: config.max_retries as i32,
             previous_failure_reason: failure_reason,
+            previous_output: previous
+                .map(|p| p.output_tail.clone())
+                .filter(|t| !t.is_empty()),
+            previous_thoughts: previous
+                .filter(|_| config.ralph_config.execution.thoughts_in_retry)
+                .map(|p| p.thought_summary.clone())
+                .filter(|t| !t.is_empty()),
         })
     } else {
         None
     };
+    let resume_session_id = previous
+        .filter(|_| config.ralph_config.execution.resume_sessions)
+        .and_then(|p| p.session_id.clone());
+
+    let cwd = task_cwd(db, task).filter(|dir| {
+        let exists = config.project_root.join(dir).is_dir();
+        if !exists {
+            formatter::print_warning(&format!(
+                "  Warning: task directory '{}' does not exist; running in the project root",
+                dir
+            ));
+        }
+        exists
+    });
 
+    let (mut frontmatter, description) = task.frontmatter();
+    frontmatter.labels = dag::task_labels(task, &dag::get_task_labels(db, &task.id)?);
     let task_info = TaskInfo {
         task_id: task.id.clone(),
         title: task.title.clone(),
-        description: task.description.clone(),
+        description: description.to_string(),
+        frontmatter,
+        cwd,
         parent,
         completed_blockers,
     };
 
     // Journal: smart-select entries for system prompt context (FR-5.1, FR-5.2)
-    let journal_entries = journal::select_journal_entries(
-        db,
-        &config.run_id,
-        &task.title,
-        &task.description,
-        5, // recent_limit
-        5, // fts_limit
-    )
-    .unwrap_or_default();
-    let journal_context = journal::render_journal_context(&journal_entries);
+    let journal_entries = context_cache
+        .select_journal_entries(
+            db,
+            &config.run_id,
+            &task.title,
+            &task.description,
+            5, // recent_limit
+            5, // fts_limit
+        )
+        .unwrap_or_default();
+    let run_summary = journal::latest_rolling_summary(db, &config.run_id).unwrap_or_default();
+    let journal_context = journal::render_journal_context(&journal_entries, run_summary.as_ref());
 
-    // Knowledge: discover, tag-match, and expand via link graph (FR-6.1-FR-6.4)
-    let all_knowledge = knowledge::discover_knowledge(&config.project_root);
+    // Knowledge: discover, tag-match, and expand via link graph (FR-6.1-FR-6.4);
+    // unchanged files and their link graph come from the cache
+    let (all_knowledge, link_graph) = context_cache.knowledge(&config.project_root);
     let last_files: Vec<String> = journal_entries
         .last()
         .map(|e| e.files_modified.clone())
         .unwrap_or_default();
-    let feature_name = match &config.run_target {
-        Some(RunTarget::Feature(name)) => Some(name.as_str()),
-        _ => None,
-    };
+    // What the agent read: last iteration, plus earlier attempts at this task
+    let mut read_files: Vec<String> = journal_entries
+        .last()
+        .map(|e| e.files_read.clone())
+        .unwrap_or_default();
+    read_files.extend(journal::files_read_for_task(db, &task.id).unwrap_or_default());
+    let feature_name = feature.map(|f| f.name.as_str());
     let mut matched_knowledge = knowledge::match_knowledge_entries(
-        &all_knowledge,
+        all_knowledge,
         &task.title,
         &task.description,
         feature_name,
         &last_files,
+        &read_files,
+        &task_info.frontmatter.labels,
     );
 
-    // Build link graph and expand matched set via bidirectional links
-    let link_graph = knowledge::build_link_graph(&all_knowledge);
+    // Expand matched set via bidirectional links
     let linked_entries =
-        knowledge::expand_via_links(&all_knowledge, &matched_knowledge, &link_graph, 2, 2);
+        knowledge::expand_via_links(all_knowledge, &matched_knowledge, link_graph, 2, 2);
     matched_knowledge.extend(linked_entries);
-    // Re-sort by score after expansion
-    matched_knowledge.sort_by(|a, b| b.1.cmp(&a.1));
+    // Drop other features' entries and re-sort, preferring this feature's
+    let matched_knowledge = knowledge::apply_scope(
+        matched_knowledge,
+        &config.ralph_config.knowledge,
+        feature_name,
+    );
+    let matched_knowledge = knowledge::filter_for_task(
+        matched_knowledge,
+        &config.ralph_config.knowledge,
+        &task.title,
+        &task.description,
+    );
 
     let knowledge_context =
-        knowledge::render_knowledge_context_with_graph(&matched_knowledge, Some(&link_graph));
+        knowledge::render_knowledge_context_with_graph(&matched_knowledge, Some(link_graph));
+
+    // Scratchpad: free-form notes carried over from the previous iteration
+    let scratchpad_context = if config.ralph_config.execution.scratchpad {
+        let path = scratchpad::scratch_path(&config.project_root, feature_name);
+        match scratchpad::prepare(&path) {
+            Ok(tail) => {
+                let relative = path.strip_prefix(&config.project_root).unwrap_or(&path);
+                scratchpad::render_scratchpad_context(&relative.display().to_string(), &tail)
+            }
+            Err(e) => {
+                formatter::print_warning(&format!("  Warning: scratchpad unavailable: {}", e));
+                String::new()
+            }
+        }
+    } else {
+        String::new()
+    };
+
+    // Changes So Far: what earlier iterations already changed in the tree
+    let execution = &config.ralph_config.execution;
+    let base = match execution.diff_context {
+        DiffScope::Off => None,
+        DiffScope::Run => config.run_base.clone().map(|b| (b, "start of this run")),
+        DiffScope::Feature => feature
+            .and_then(|f| f.plan_commit.clone())
+            .map(|b| (b, "when the feature's plan was written"))
+            .or_else(|| config.run_base.clone().map(|b| (b, "start of this run"))),
+    };
+    let diff_context = base
+        .map(|(base, since)| {
+            diff_context::render_diff_context(
+                &config.project_root,
+                &base,
+                since,
+                &task_info.frontmatter.files,
+                execution.diff_context_chars,
+            )
+        })
+        .unwrap_or_default();
 
     Ok(IterationContext {
         task: task_info,
-        spec_content: spec_content.map(|s| s.to_string()),
-        plan_content: plan_content.map(|s| s.to_string()),
+        spec_content: feature.and_then(|f| f.spec.clone()),
+        plan_content: feature.and_then(|f| f.plan.clone()),
         retry_info,
         run_id: config.run_id.clone(),
         journal_context,
         knowledge_context,
+        scratchpad_context,
+        diff_context,
+        speculative_analysis: None,
+        resume_session_id,
     })
 }
 
-/// Get completed blockers (dependencies) for a task.
-fn get_completed_blockers(db: &Db, task_id: &str) -> Result<Vec<BlockerContext>> {
+/// The task's working directory, inherited from the nearest ancestor that
+/// sets one.
+fn task_cwd(db: &Db, task: &Task) -> Option<String> {
+    let mut current = task.clone();
+    loop {
+        if current.cwd.is_some() {
+            return current.cwd;
+        }
+        current = dag::get_task(db, current.parent_id.as_deref()?).ok()?;
+    }
+}
+
+/// Lines of a blocker's artifact shown to the dependent task.
+const ARTIFACT_EXCERPT_LINES: usize = 20;
+/// Longest artifact excerpt, in characters.
+const ARTIFACT_EXCERPT_CHARS: usize = 1500;
+
+/// Store the files named by `<artifact>` sigils as outputs of `task_id`.
+///
+/// Paths resolve against the task's working directory and are stored
+/// relative to the project root. Files that do not exist, or lie outside the
+/// project, are skipped with a warning.
+fn register_artifacts(
+    db: &Db,
+    project_root: &Path,
+    task_id: &str,
+    cwd: Option<&str>,
+    artifacts: &[ArtifactSigil],
+) {
+    let base = project_root.join(cwd.unwrap_or(""));
+    let root = project_root
+        .canonicalize()
+        .unwrap_or_else(|_| project_root.to_path_buf());
+    for artifact in artifacts {
+        let relative = base
+            .join(&artifact.path)
+            .canonicalize()
+            .ok()
+            .filter(|path| path.is_file())
+            .and_then(|path| {
+                path.strip_prefix(&root)
+                    .ok()
+                    .map(|rel| rel.to_string_lossy().into_owned())
+            });
+        let result = match relative {
+            Some(path) => dag::add_artifact(db, task_id, &path, &artifact.description)
+                .map(|_| format!("{task_id} registered {path}")),
+            None => Err(anyhow::anyhow!(
+                "'{}' is not a file in the project",
+                artifact.path
+            )),
+        };
+        match result {
+            Ok(summary) => formatter::emit_event_info("artifact", &summary),
+            Err(e) => {
+                formatter::print_warning(&format!("  Warning: artifact ignored: {e}"));
+                formatter::emit_event("artifact", &format!("ignored \u{2014} {e}"), true);
+            }
+        }
+    }
+}
+
+/// Opening lines of an artifact, or nothing when it cannot be read as text.
+fn artifact_excerpt(path: &Path) -> String {
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return String::new();
+    };
+    let excerpt: String = text
+        .lines()
+        .take(ARTIFACT_EXCERPT_LINES)
+        .collect::<Vec<_>>()
+        .join("\n");
+    if excerpt.chars().count() <= ARTIFACT_EXCERPT_CHARS {
+        return excerpt;
+    }
+    let cut: String = excerpt.chars().take(ARTIFACT_EXCERPT_CHARS).collect();
+    format!("{cut}\u{2026}")
+}
+
+/// Get completed blockers (dependencies) for a task, with their artifacts.
+fn get_completed_blockers(
+    db: &Db,
+    project_root: &Path,
+    task_id: &str,
+) -> Result<Vec<BlockerContext>> {
     let mut stmt = db.conn().prepare(
         r#"
         SELECT t.id, t.title, COALESCE(
@@ -934,16 +2104,28 @@ fn get_completed_blockers(db: &Db, task_id: &str) -> Result<Vec<BlockerContext>>
         "#,
     )?;
 
-    let blockers = stmt
+    let mut blockers = stmt
         .query_map([task_id], |row| {
             Ok(BlockerContext {
                 task_id: row.get(0)?,
                 title: row.get(1)?,
                 summary: row.get(2)?,
+                artifacts: Vec::new(),
             })
         })?
         .collect::<Result<Vec<_>, _>>()?;
 
+    for blocker in &mut blockers {
+        blocker.artifacts = dag::get_task_artifacts(db, &blocker.task_id)?
+            .into_iter()
+            .map(|artifact| ArtifactContext {
+                excerpt: artifact_excerpt(&project_root.join(&artifact.path)),
+                path: artifact.path,
+                description: artifact.description,
+            })
+            .collect();
+    }
+
     Ok(blockers)
 }
 
@@ -961,14 +2143,19 @@ fn get_last_failure_reason(db: &Db, task_id: &str) -> Result<String> {
 }
 
 /// Handle a task-done sigil: verify (if enabled) and complete or retry.
+///
+/// With `pipeline_verification`, a verification that cannot reuse a cached
+/// verdict is spawned instead of awaited and handed back for the caller to
+/// reconcile later.
 async fn handle_task_done(
     db: &Db,
     config: &Config,
     task: &Task,
     spec_content: Option<&str>,
     plan_content: Option<&str>,
+    files_modified: &[String],
     log_file: &str,
-) -> Result<()> {
+) -> Result<DoneVerdict> {
     let task_id = &task.id;
 
     if config.verify {
@@ -976,78 +2163,413 @@ async fn handle_task_done(
         formatter::print_verification_start(config.iteration, task_id);
         formatter::emit_event_info("verify", &format!("verifying {}", task.id));
 
-        let v_result =
-            verification::verify_task(config, task, spec_content, plan_content, log_file).await?;
+        let criteria = dag::get_task_criteria(db, task_id)?;
 
-        if v_result.passed {
-            // Verification passed — complete the task
-            let transitions = dag::complete_task(db, task_id).context("Failed to complete task")?;
-            emit_auto_transitions(&transitions);
-            db.conn().execute(
-                "UPDATE tasks SET verification_status = 'passed' WHERE id = ?",
-                [task_id.as_str()],
-            )?;
-            formatter::print_verification_passed(config.iteration, task_id);
-            formatter::emit_event_info("verify", &format!("{} passed", task.id));
-            formatter::emit_event_info("task", &format!("{} done", task_id));
+        // Every file the task touched, across all of its attempts
+        let mut files = journal::files_modified_for_task(db, task_id)?;
+        files.extend(files_modified.iter().cloned());
+
+        // Known-flaky files earn extra retries. A failure is re-checked rather
+        // than reused from the cache for them and on the last attempt, so a
+        // flaky check gets a second run before it fails the task.
+        let flaky_area = flaky::flaky_area(db, task_id, &files)?;
+        let max_retries = flaky::max_retries(
+            config.max_retries,
+            config.ralph_config.execution.flaky_retry_bonus,
+            &flaky_area,
+        );
+
+        // Reuse the last verdict if none of the task's files (nor the
+        // integration target) changed since
+        let onto = verification::integration::resolve_onto(config)?;
+        let fingerprint = verification::fingerprint(
+            &config.project_root,
+            task,
+            &criteria,
+            &files,
+            onto.as_deref(),
+        );
+        let cached = if config.ralph_config.execution.verification_cache {
+            verification::cached_result(db, task_id, &fingerprint)?.filter(|result| {
+                result.passed || (flaky_area.is_empty() && task.retry_count < max_retries as i32)
+            })
         } else {
-            // Verification failed
-            formatter::print_verification_failed(config.iteration, task_id, &v_result.reason);
-            formatter::emit_event(
+            None
+        };
+        let v_result = match cached {
+            Some(result) => {
+                formatter::emit_event_info(
+                    "verify",
+                    &format!(
+                        "{} unchanged since last verification, reusing verdict",
+                        task.id
+                    ),
+                );
+                result
+            }
+            None if config.ralph_config.execution.pipeline_verification => {
+                formatter::emit_event_info(
+                    "verify",
+                    &format!("{} verifying in the background", task.id),
+                );
+                let handle = tokio::task::spawn_local({
+                    let (config, task, criteria) = (config.clone(), task.clone(), criteria.clone());
+                    let spec = spec_content.map(str::to_string);
+                    let plan = plan_content.map(str::to_string);
+                    let log_file = log_file.to_string();
+                    let files = files.clone();
+                    async move {
+                        verification::verify_task(
+                            &config,
+                            &task,
+                            &criteria,
+                            spec.as_deref(),
+                            plan.as_deref(),
+                            &files,
+                            &flaky_area,
+                            onto.as_deref(),
+                            &log_file,
+                        )
+                        .await
+                    }
+                });
+                return Ok(DoneVerdict::InFlight(Box::new(InFlightVerification {
+                    task: task.clone(),
+                    iteration: config.iteration,
+                    criteria,
+                    fingerprint,
+                    files,
+                    max_retries,
+                    journal_id: None,
+                    handle,
+                })));
+            }
+            None => {
+                let result = verification::verify_task(
+                    config,
+                    task,
+                    &criteria,
+                    spec_content,
+                    plan_content,
+                    &files,
+                    &flaky_area,
+                    onto.as_deref(),
+                    log_file,
+                )
+                .await?;
+                record_verdict(db, config, task_id, &fingerprint, &files, &result)?;
+                result
+            }
+        };
+        Ok(DoneVerdict::Verified {
+            max_retries,
+            criteria,
+            result: v_result,
+        })
+    } else {
+        Ok(DoneVerdict::Unverified)
+    }
+}
+
+/// Apply a `<task-done>` verdict. A verification still in flight is
+/// returned to the caller, which records it once the iteration is committed.
+fn record_done(
+    db: &Db,
+    config: &Config,
+    task: &Task,
+    done: DoneVerdict,
+    announcements: &mut Announcements,
+) -> Result<(Vec<dag::AutoTransition>, Option<InFlightVerification>)> {
+    match done {
+        DoneVerdict::Unverified => {
+            let transitions = complete_done(db, config, &task.id, false, announcements)?;
+            formatter::print_task_done(config.iteration, &task.id);
+            announcements.info("task", format!("{} done", task.id));
+            Ok((transitions, None))
+        }
+        DoneVerdict::Verified {
+            max_retries,
+            criteria,
+            result,
+        } => {
+            let transitions = apply_verdict(
+                db,
+                config,
+                task,
+                config.iteration,
+                max_retries,
+                &criteria,
+                &result,
+                announcements,
+            )?;
+            Ok((transitions, None))
+        }
+        DoneVerdict::InFlight(pending) => Ok((Vec::new(), Some(*pending))),
+    }
+}
+
+/// Mark a task done and attribute it; `verified` also records the passed
+/// verification. Follow-up tasks for finished features are left to the
+/// caller, after its transaction commits.
+fn complete_done(
+    db: &Db,
+    config: &Config,
+    task_id: &str,
+    verified: bool,
+    announcements: &mut Announcements,
+) -> Result<Vec<dag::AutoTransition>> {
+    if let Some(chaos) = &config.chaos {
+        chaos
+            .db_lock(&format!("completing {task_id}"))
+            .context("Failed to complete task")?;
+    }
+    let transitions = dag::complete_task(db, task_id).context("Failed to complete task")?;
+    dag::set_completed_by(db, task_id, Some(&config.attribution()))?;
+    if verified {
+        db.conn().execute(
+            "UPDATE tasks SET verification_status = 'passed' WHERE id = ?",
+            [task_id],
+        )?;
+    }
+    announcements.transitions(&transitions);
+    Ok(transitions)
+}
+
+/// Create the `[post_feature]` follow-ups of any feature `transitions`
+/// finished.
+fn create_follow_ups(db: &Db, config: &Config, transitions: &[dag::AutoTransition]) -> Result<()> {
+    post_feature::after_transitions(
+        db,
+        &config.ralph_config,
+        transitions,
+        Some(&config.attribution()),
+    )
+    .context("Failed to create follow-up tasks")?;
+    Ok(())
+}
+
+/// Wait for a pipelined verification and apply its verdict, correcting the
+/// provisional `done` journal entry if the work was rejected.
+///
+/// A verification that errors (agent crash, Ctrl+C) releases the claim, so
+/// the task is picked up again rather than stranded.
+async fn finish_verification(
+    db: &Db,
+    config: &Config,
+    pending: InFlightVerification,
+) -> Result<()> {
+    let task = &pending.task;
+    let result = match pending.handle.await {
+        Ok(result) => result,
+        Err(err) => Err(anyhow::anyhow!("verification task panicked: {err}")),
+    };
+    if let Ok(v_result) = &result {
+        record_verdict(
+            db,
+            config,
+            &task.id,
+            &pending.fingerprint,
+            &pending.files,
+            v_result,
+        )?;
+    }
+
+    // The verdict and the corrected journal outcome commit together
+    let mut announcements = Announcements::default();
+    let tx = db.conn().unchecked_transaction()?;
+    let (outcome, transitions) = match result {
+        Ok(v_result) => {
+            let transitions = apply_verdict(
+                db,
+                config,
+                task,
+                pending.iteration,
+                pending.max_retries,
+                &pending.criteria,
+                &v_result,
+                &mut announcements,
+            )?;
+            (
+                verdict_outcome(task, &dag::get_task(db, &task.id)?),
+                transitions,
+            )
+        }
+        Err(err) => {
+            formatter::print_warning(&format!(
+                "ralph: verification of {} did not finish: {err:#}",
+                task.id
+            ));
+            announcements.error(
                 "verify",
-                &format!("{} failed \u{2014} {}", task.id, v_result.reason),
-                true,
+                format!("{} not verified \u{2014} {err:#}", task.id),
             );
-
-            // Log the failure
             dag::add_log(
                 db,
-                task_id,
-                &format!("Verification failed: {}", v_result.reason),
+                &task.id,
+                &format!("Verification did not finish: {err:#}"),
             )?;
+            try_release_claim(db, &task.id, "verification error");
+            ("blocked", Vec::new())
+        }
+    };
+    if let Some(id) = pending.journal_id.filter(|_| outcome != "done") {
+        journal::set_outcome(db, id, outcome)?;
+    }
+    tx.commit()
+        .context("Failed to commit verification verdict")?;
+    announcements.send();
+    create_follow_ups(db, config, &transitions)
+}
 
-            let max_retries = config.max_retries as i32;
-            if task.retry_count < max_retries {
-                // Retry: transition failed → pending, increment retry_count
-                let transitions = dag::retry_task(db, task_id).context("Failed to retry task")?;
-                emit_auto_transitions(&transitions);
-                formatter::print_retry(
-                    config.iteration,
-                    task_id,
-                    task.retry_count + 1,
-                    max_retries,
-                );
-                formatter::emit_event_info(
-                    "task",
-                    &format!("{} retry {}/{}", task_id, task.retry_count + 1, max_retries),
-                );
-            } else {
-                // Max retries exhausted — fail the task
-                let fail_reason = format!(
-                    "Verification failed after {} retries: {}",
-                    max_retries, v_result.reason
-                );
-                let transitions =
-                    dag::fail_task(db, task_id, &fail_reason).context("Failed to fail task")?;
-                emit_auto_transitions(&transitions);
-                formatter::print_max_retries_exhausted(config.iteration, task_id);
-                formatter::emit_event("task", &format!("{} max retries exhausted", task_id), true);
-                formatter::emit_event(
-                    "task",
-                    &format!("{} failed \u{2014} {}", task_id, fail_reason),
-                    true,
-                );
+/// Store a fresh verdict: in the cache (if enabled) and in the history that
+/// flake detection reads. A pass on unchanged files after a failure is
+/// reported as flaky; a failure with an actionable reason is offered as a
+/// knowledge entry.
+fn record_verdict(
+    db: &Db,
+    config: &Config,
+    task_id: &str,
+    fingerprint: &str,
+    files: &[String],
+    v_result: &VerificationResult,
+) -> Result<()> {
+    if config.ralph_config.execution.verification_cache {
+        verification::record_result(db, task_id, fingerprint, v_result)?;
+    }
+    if flaky::record(db, task_id, fingerprint, files, v_result.passed)? {
+        formatter::emit_event_info(
+            "verify",
+            &format!("{task_id} flaky \u{2014} passed with no changes since it failed"),
+        );
+        dag::add_log(
+            db,
+            task_id,
+            "Flaky verification: passed with no file changes since the last failure",
+        )?;
+    }
+    if !v_result.passed && config.ralph_config.knowledge.learn_from_failures {
+        if let Some(sigil) = lessons::candidate(&v_result.reason, task_id, files) {
+            let feature = dag::get_task(db, task_id)?
+                .feature_id
+                .and_then(|id| feature::get_feature_by_id(db, &id).ok())
+                .map(|f| f.name);
+            match lessons::offer(
+                &config.project_root,
+                &sigil,
+                feature.as_deref(),
+                config.ralph_config.knowledge.scope,
+            ) {
+                Ok(Some(path)) => formatter::emit_event_info(
+                    "knowledge",
+                    &format!("written from {task_id} failure: {}", path.display()),
+                ),
+                Ok(None) => {}
+                Err(e) => {
+                    formatter::emit_event("knowledge", &format!("write failed \u{2014} {e}"), true)
+                }
             }
         }
+    }
+    Ok(())
+}
+
+/// Journal outcome of a verified task, from its state before and after the
+/// verdict was applied.
+fn verdict_outcome(before: &Task, after: &Task) -> &'static str {
+    if after.retry_count > before.retry_count {
+        "retried"
+    } else if after.status == "failed" {
+        "failed"
     } else {
-        // No verification — complete immediately
-        let transitions = dag::complete_task(db, task_id).context("Failed to complete task")?;
-        emit_auto_transitions(&transitions);
-        formatter::print_task_done(config.iteration, task_id);
-        formatter::emit_event_info("task", &format!("{} done", task_id));
+        "done"
     }
+}
 
-    Ok(())
+/// Record a verdict: complete the task on a pass, otherwise retry it until
+/// it has been retried `max_retries` times, then fail it. Returns the
+/// auto-transitions the status change triggered.
+#[allow(clippy::too_many_arguments)]
+fn apply_verdict(
+    db: &Db,
+    config: &Config,
+    task: &Task,
+    iteration: u32,
+    max_retries: u32,
+    criteria: &[Criterion],
+    v_result: &VerificationResult,
+    announcements: &mut Announcements,
+) -> Result<Vec<dag::AutoTransition>> {
+    let task_id = &task.id;
+
+    // Record per-criterion verdicts; criteria the agent skipped stay pending
+    if !criteria.is_empty() {
+        dag::reset_criteria(db, task_id)?;
+        for verdict in &v_result.criteria {
+            let status = if verdict.passed { "passed" } else { "failed" };
+            if let Err(e) = dag::set_criterion_status(
+                db,
+                task_id,
+                verdict.position,
+                status,
+                verdict.note.as_deref(),
+            ) {
+                announcements.error("verify", format!("{}: {}", task_id, e));
+            }
+        }
+    }
+
+    if v_result.passed {
+        // Verification passed — complete the task
+        let transitions = complete_done(db, config, task_id, true, announcements)?;
+        formatter::print_verification_passed(iteration, task_id);
+        announcements.info("verify", format!("{} passed", task.id));
+        announcements.info("task", format!("{} done", task_id));
+        Ok(transitions)
+    } else {
+        // Verification failed
+        formatter::print_verification_failed(iteration, task_id, &v_result.reason);
+        announcements.error(
+            "verify",
+            format!("{} failed \u{2014} {}", task.id, v_result.reason),
+        );
+
+        // Log the failure
+        dag::add_log(
+            db,
+            task_id,
+            &format!("Verification failed: {}", v_result.reason),
+        )?;
+
+        let max_retries = max_retries as i32;
+        if task.retry_count < max_retries {
+            // Retry: transition failed → pending, increment retry_count
+            let transitions = dag::retry_task(db, task_id).context("Failed to retry task")?;
+            announcements.transitions(&transitions);
+            formatter::print_retry(iteration, task_id, task.retry_count + 1, max_retries);
+            announcements.info(
+                "task",
+                format!("{} retry {}/{}", task_id, task.retry_count + 1, max_retries),
+            );
+            Ok(transitions)
+        } else {
+            // Max retries exhausted — fail the task
+            let fail_reason = format!(
+                "Verification failed after {} retries: {}",
+                max_retries, v_result.reason
+            );
+            let transitions =
+                dag::fail_task(db, task_id, &fail_reason).context("Failed to fail task")?;
+            announcements.transitions(&transitions);
+            formatter::print_max_retries_exhausted(iteration, task_id);
+            announcements.error("task", format!("{} max retries exhausted", task_id));
+            announcements.error(
+                "task",
+                format!("{} failed \u{2014} {}", task_id, fail_reason),
+            );
+            announcements.notify(NotifyEvent::TaskFailed, task_id, &fail_reason);
+            Ok(transitions)
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1072,6 +2594,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn task_cwd_is_inherited_from_the_nearest_ancestor() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let db = dag::open_db(temp_file.path().to_str().unwrap()).unwrap();
+
+        let root = dag::create_task(&db, "Feature: api", None, None, 0).unwrap();
+        let group = dag::create_task(&db, "Endpoints", None, Some(&root.id), 0).unwrap();
+        let leaf = dag::create_task(&db, "GET /users", None, Some(&group.id), 0).unwrap();
+        assert_eq!(task_cwd(&db, &leaf), None);
+
+        dag::set_task_cwd(&db, &root.id, Some("packages")).unwrap();
+        dag::set_task_cwd(&db, &group.id, Some("packages/api")).unwrap();
+        assert_eq!(task_cwd(&db, &leaf).as_deref(), Some("packages/api"));
+
+        dag::set_task_cwd(&db, &leaf.id, Some("packages/api/src")).unwrap();
+        let leaf = dag::get_task(&db, &leaf.id).unwrap();
+        assert_eq!(task_cwd(&db, &leaf).as_deref(), Some("packages/api/src"));
+    }
+
     #[test]
     fn ready_tasks_are_picked_in_order() {
         let temp_file = tempfile::NamedTempFile::new().unwrap();
@@ -1213,10 +2754,33 @@ mod tests {
             )
             .unwrap();
 
-        let blockers = get_completed_blockers(&db, "t-b").unwrap();
+        let root = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(root.path().join("api/db")).unwrap();
+        std::fs::write(
+            root.path().join("api/db/schema.sql"),
+            "CREATE TABLE t (id);\n",
+        )
+        .unwrap();
+        let artifacts = [
+            ArtifactSigil {
+                path: "db/schema.sql".to_string(),
+                description: "Schema".to_string(),
+            },
+            ArtifactSigil {
+                path: "../../etc/passwd".to_string(),
+                description: String::new(),
+            },
+        ];
+        register_artifacts(&db, root.path(), "t-a", Some("api"), &artifacts);
+
+        let blockers = get_completed_blockers(&db, root.path(), "t-b").unwrap();
         assert_eq!(blockers.len(), 1);
         assert_eq!(blockers[0].task_id, "t-a");
         assert_eq!(blockers[0].title, "Task A");
+        assert_eq!(blockers[0].artifacts.len(), 1);
+        assert_eq!(blockers[0].artifacts[0].path, "api/db/schema.sql");
+        assert_eq!(blockers[0].artifacts[0].description, "Schema");
+        assert_eq!(blockers[0].artifacts[0].excerpt, "CREATE TABLE t (id);");
     }
 
     #[test]
@@ -1266,6 +2830,7 @@ mod tests {
             None,
             false,
             None,
+            None,
         )
         .unwrap();
         config.agent_id = "agent-test".to_string();
@@ -1297,6 +2862,7 @@ mod tests {
             None,
             false,
             None,
+            None,
         )
         .unwrap();
         config.agent_id = "agent-test".to_string();
@@ -1323,6 +2889,99 @@ mod tests {
         assert_eq!(updated.claimed_by, None);
     }
 
+    #[test]
+    fn scoped_work_counts_unresolved_leaves() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let db = dag::open_db(temp_file.path().to_str().unwrap()).unwrap();
+
+        let parent = dag::create_task(&db, "parent", None, None, 0).unwrap();
+        let _a = dag::create_task(&db, "a", None, Some(&parent.id), 0).unwrap();
+        let b = dag::create_task(&db, "b", None, Some(&parent.id), 0).unwrap();
+        let _c = dag::create_task(&db, "c", None, None, 0).unwrap();
+        dag::claim_task(&db, &b.id, "agent-test").unwrap();
+        dag::complete_task(&db, &b.id).unwrap();
+
+        let config = Config::from_run_args(
+            None,
+            None,
+            None,
+            crate::project::ProjectConfig {
+                root: std::path::PathBuf::from("."),
+                config: crate::project::RalphConfig::default(),
+            },
+            None,
+            None,
+            false,
+            None,
+            None,
+        )
+        .unwrap();
+        // The parent only groups its children and `b` is done
+        assert_eq!(scoped_work(&config, &db, &[]).unwrap(), (4, 2));
+
+        let config = Config {
+            run_target: Some(RunTarget::Task(b.id.clone())),
+            ..config
+        };
+        assert_eq!(scoped_work(&config, &db, &[]).unwrap(), (1, 0));
+    }
+
+    #[test]
+    fn run_stats_feed_exit_when() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let db = dag::open_db(temp_file.path().to_str().unwrap()).unwrap();
+
+        let parent = dag::create_task(&db, "parent", None, None, 0).unwrap();
+        let a = dag::create_task(&db, "a", None, Some(&parent.id), 0).unwrap();
+        let _b = dag::create_task(&db, "b", None, Some(&parent.id), 0).unwrap();
+        dag::claim_task(&db, &a.id, "agent-test").unwrap();
+        dag::complete_task(&db, &a.id).unwrap();
+
+        let mut project = crate::project::RalphConfig::default();
+        project.run.exit_when = Some("feature.done_ratio >= 0.5 and budget.cost > 1".to_string());
+        let config = Config::from_run_args(
+            None,
+            None,
+            None,
+            crate::project::ProjectConfig {
+                root: std::path::PathBuf::from("."),
+                config: project,
+            },
+            None,
+            None,
+            false,
+            None,
+            None,
+        )
+        .unwrap();
+        for (outcome, cost) in [("done", 0.75), ("failed", 0.5)] {
+            let entry = journal::JournalEntry {
+                id: 0,
+                run_id: config.run_id.clone(),
+                iteration: 1,
+                task_id: Some(a.id.clone()),
+                feature_id: None,
+                outcome: outcome.to_string(),
+                model: None,
+                duration_secs: 1.0,
+                cost_usd: cost,
+                input_tokens: 100,
+                output_tokens: 20,
+                files_modified: vec![],
+                files_read: Vec::new(),
+                notes: None,
+                created_at: chrono::Utc::now().to_rfc3339(),
+            };
+            journal::insert_journal_entry(&db, &entry).unwrap();
+        }
+
+        let stats = run_stats(&config, &db, &[], std::time::Instant::now()).unwrap();
+        assert_eq!((stats.total, stats.done, stats.failed), (2, 1, 0));
+        assert_eq!((stats.iterations, stats.failed_iterations), (2, 1));
+        assert_eq!((stats.cost, stats.tokens), (1.25, 240));
+        assert!(config.exit_when.as_ref().unwrap().is_met(&stats));
+    }
+
     #[test]
     fn scoped_target_resolved_task_ignores_unrelated_pending_tasks() {
         let temp_file = tempfile::NamedTempFile::new().unwrap();
@@ -1345,13 +3004,70 @@ mod tests {
             None,
             false,
             None,
+            None,
         )
         .unwrap();
 
-        assert!(scoped_target_resolved(&config, &db, None).unwrap());
+        assert!(scoped_target_resolved(&config, &db, &[]).unwrap());
         assert!(!dag::all_resolved(&db).unwrap());
     }
 
+    #[test]
+    fn only_and_skip_restrict_ready_tasks_and_resolution() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let db = dag::open_db(temp_file.path().to_str().unwrap()).unwrap();
+        let feat = feature::create_feature(&db, "feat").unwrap();
+        let task = |title: &str| dag::create_test_task(&db, &feat.id, title, None);
+        let (a, b, c) = (task("a"), task("b"), task("c"));
+        dag::add_dependency(&db, &a.id, &b.id).unwrap();
+
+        let mut config = Config::from_run_args(
+            None,
+            None,
+            None,
+            crate::project::ProjectConfig {
+                root: std::path::PathBuf::from("."),
+                config: crate::project::RalphConfig::default(),
+            },
+            Some(RunTarget::Features {
+                names: vec!["feat".to_string()],
+                order: FeatureOrder::Sequential,
+            }),
+            None,
+            false,
+            None,
+            None,
+        )
+        .unwrap();
+        config.only = vec![b.id.clone(), a.id.clone()];
+        let features = resolve_feature_contexts(&config, &db).unwrap();
+        let ready_ids = |config: &Config| -> Vec<String> {
+            get_scoped_ready_tasks(config, &db, &features, 0)
+                .unwrap()
+                .into_iter()
+                .map(|t| t.id)
+                .collect()
+        };
+
+        // b waits on a; c is not selected
+        assert_eq!(ready_ids(&config), vec![a.id.clone()]);
+        dag::claim_task(&db, &a.id, "agent-test").unwrap();
+        dag::complete_task(&db, &a.id).unwrap();
+        assert_eq!(ready_ids(&config), vec![b.id.clone()]);
+        assert!(!scoped_target_resolved(&config, &db, &features).unwrap());
+        dag::claim_task(&db, &b.id, "agent-test").unwrap();
+        dag::complete_task(&db, &b.id).unwrap();
+        assert!(scoped_target_resolved(&config, &db, &features).unwrap());
+
+        // --skip alone: resolved with only the skipped task left
+        config.only.clear();
+        config.skip = vec![c.id.clone()];
+        assert!(ready_ids(&config).is_empty());
+        assert!(scoped_target_resolved(&config, &db, &features).unwrap());
+        config.skip.clear();
+        assert!(!scoped_target_resolved(&config, &db, &features).unwrap());
+    }
+
     #[test]
     fn scoped_target_resolved_feature_ignores_unrelated_pending_tasks() {
         let temp_file = tempfile::NamedTempFile::new().unwrap();
@@ -1360,36 +3076,173 @@ mod tests {
         let feat_target = feature::create_feature(&db, "feat-target").unwrap();
         let feat_other = feature::create_feature(&db, "feat-other").unwrap();
 
-        let target_task = dag::create_task_with_feature(
-            &db,
-            dag::CreateTaskParams {
-                title: "target feature task",
-                description: None,
-                parent_id: None,
-                priority: 0,
-                feature_id: Some(&feat_target.id),
-                task_type: "feature",
-                max_retries: 3,
+        let target_task = dag::create_test_task(&db, &feat_target.id, "target feature task", None);
+        dag::claim_task(&db, &target_task.id, "agent-test").unwrap();
+        dag::complete_task(&db, &target_task.id).unwrap();
+
+        let _other_task =
+            dag::create_test_task(&db, &feat_other.id, "other feature pending task", None);
+
+        let config = Config::from_run_args(
+            None,
+            None,
+            None,
+            crate::project::ProjectConfig {
+                root: std::path::PathBuf::from("."),
+                config: crate::project::RalphConfig::default(),
             },
+            Some(RunTarget::Features {
+                names: vec!["feat-target".to_string()],
+                order: FeatureOrder::Sequential,
+            }),
+            None,
+            false,
+            None,
+            None,
         )
         .unwrap();
-        dag::claim_task(&db, &target_task.id, "agent-test").unwrap();
-        dag::complete_task(&db, &target_task.id).unwrap();
 
-        let _other_task = dag::create_task_with_feature(
+        let features = resolve_feature_contexts(&config, &db).unwrap();
+        assert_eq!(features.len(), 1);
+        assert_eq!(features[0].id, feat_target.id);
+        assert!(scoped_target_resolved(&config, &db, &features).unwrap());
+        assert!(!dag::all_resolved(&db).unwrap());
+    }
+
+    #[test]
+    fn several_features_are_ordered_and_summarized_per_feature() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let db = dag::open_db(temp_file.path().to_str().unwrap()).unwrap();
+
+        let mut ids = HashMap::new();
+        for name in ["auth", "billing"] {
+            let feat = feature::create_feature(&db, name).unwrap();
+            for n in 1..=2 {
+                let title = format!("{name} {n}");
+                let task = dag::create_test_task(&db, &feat.id, &title, None);
+                ids.insert(title, task.id);
+            }
+        }
+
+        let config_for = |order| {
+            Config::from_run_args(
+                None,
+                None,
+                None,
+                crate::project::ProjectConfig {
+                    root: std::path::PathBuf::from("."),
+                    config: crate::project::RalphConfig::default(),
+                },
+                Some(RunTarget::Features {
+                    names: vec!["auth".to_string(), "billing".to_string()],
+                    order,
+                }),
+                None,
+                false,
+                None,
+                Some("fifo".to_string()),
+            )
+            .unwrap()
+        };
+        let titles = |config: &Config, next: usize| -> Vec<String> {
+            let features = resolve_feature_contexts(config, &db).unwrap();
+            get_scoped_ready_tasks(config, &db, &features, next)
+                .unwrap()
+                .into_iter()
+                .map(|t| t.title)
+                .collect()
+        };
+
+        let sequential = config_for(FeatureOrder::Sequential);
+        assert_eq!(
+            titles(&sequential, 1),
+            vec!["auth 1", "auth 2", "billing 1", "billing 2"]
+        );
+        let interleaved = config_for(FeatureOrder::Interleaved);
+        assert_eq!(titles(&interleaved, 0)[0], "auth 1");
+        assert_eq!(titles(&interleaved, 1)[0], "billing 1");
+        assert_eq!(titles(&interleaved, 2)[0], "auth 1");
+
+        for title in ["auth 1", "auth 2", "billing 1"] {
+            dag::claim_task(&db, &ids[title], "agent-test").unwrap();
+            dag::complete_task(&db, &ids[title]).unwrap();
+        }
+        let features = resolve_feature_contexts(&sequential, &db).unwrap();
+        assert!(!scoped_target_resolved(&sequential, &db, &features).unwrap());
+
+        let summaries =
+            summarize_features(&db, &["auth".to_string(), "billing".to_string()]).unwrap();
+        let lines: Vec<String> = summaries.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "auth: complete (2/2 tasks done)",
+                "billing: incomplete (1/2 tasks done)",
+            ]
+        );
+    }
+
+    #[test]
+    fn previous_attempt_keeps_output_tail_and_session() {
+        let result = StreamingResult {
+            full_text: format!("{}end of attempt\n", "x".repeat(PREVIOUS_OUTPUT_CHARS)),
+            thoughts: "Check the lexer.\n\nCheck   the lexer.\nRun tests.\n".to_string(),
+            files_modified: vec![],
+            files_read: Vec::new(),
+            duration_ms: 0,
+            stop_reason: StopReason::EndTurn,
+            usage: Default::default(),
+            session_id: Some("sess-1".to_string()),
+        };
+        let attempt = PreviousAttempt::from_result(&result);
+        assert_eq!(attempt.output_tail.chars().count(), PREVIOUS_OUTPUT_CHARS);
+        assert!(attempt.output_tail.ends_with("end of attempt"));
+        assert_eq!(attempt.session_id.as_deref(), Some("sess-1"));
+        assert_eq!(attempt.thought_summary, "Check the lexer.\nRun tests.");
+    }
+
+    #[test]
+    fn condensed_thoughts_keep_the_most_recent_lines() {
+        let thoughts = "first idea\nsecond idea\nthird idea\n";
+        assert_eq!(
+            condense_thoughts(thoughts, 100),
+            "first idea\nsecond idea\nthird idea"
+        );
+        assert_eq!(
+            condense_thoughts(thoughts, 24),
+            "\u{2026}\nsecond idea\nthird idea"
+        );
+        assert_eq!(condense_thoughts("  \n", 100), "");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn rejected_pipelined_verification_retries_task_and_corrects_journal() {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let db = dag::open_db(temp_file.path().to_str().unwrap()).unwrap();
+        let task = dag::create_task(&db, "verify later", None, None, 0).unwrap();
+        dag::claim_task(&db, &task.id, "agent-test").unwrap();
+        let task = dag::get_task(&db, &task.id).unwrap();
+        let journal_id = journal::insert_journal_entry(
             &db,
-            dag::CreateTaskParams {
-                title: "other feature pending task",
-                description: None,
-                parent_id: None,
-                priority: 0,
-                feature_id: Some(&feat_other.id),
-                task_type: "feature",
-                max_retries: 3,
+            &journal::JournalEntry {
+                id: 0,
+                run_id: "run-00000001".to_string(),
+                iteration: 1,
+                task_id: Some(task.id.clone()),
+                feature_id: None,
+                outcome: "done".to_string(),
+                model: None,
+                duration_secs: 1.0,
+                cost_usd: 0.0,
+                input_tokens: 0,
+                output_tokens: 0,
+                files_modified: Vec::new(),
+                files_read: Vec::new(),
+                notes: None,
+                created_at: chrono::Utc::now().to_rfc3339(),
             },
         )
         .unwrap();
-
         let config = Config::from_run_args(
             None,
             None,
@@ -1398,14 +3251,48 @@ mod tests {
                 root: std::path::PathBuf::from("."),
                 config: crate::project::RalphConfig::default(),
             },
-            Some(RunTarget::Feature("feat-target".to_string())),
+            None,
             None,
             false,
             None,
+            None,
         )
         .unwrap();
 
-        assert!(scoped_target_resolved(&config, &db, Some(&feat_target.id)).unwrap());
-        assert!(!dag::all_resolved(&db).unwrap());
+        let local = LocalSet::new();
+        local
+            .run_until(async {
+                let pending = InFlightVerification {
+                    task: task.clone(),
+                    iteration: 1,
+                    criteria: Vec::new(),
+                    fingerprint: "fp".to_string(),
+                    files: Vec::new(),
+                    max_retries: 3,
+                    journal_id: Some(journal_id),
+                    handle: tokio::task::spawn_local(async {
+                        Ok(VerificationResult {
+                            passed: false,
+                            reason: "tests fail".to_string(),
+                            criteria: Vec::new(),
+                        })
+                    }),
+                };
+                finish_verification(&db, &config, pending).await.unwrap();
+            })
+            .await;
+
+        let updated = dag::get_task(&db, &task.id).unwrap();
+        assert_eq!(updated.status, "pending");
+        assert_eq!(updated.retry_count, 1);
+        let outcome: String = db
+            .conn()
+            .query_row(
+                "SELECT outcome FROM journal WHERE id = ?",
+                [journal_id],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(outcome, "retried");
     }
 }
@@ -0,0 +1,320 @@
+//! End-of-run summary: what the run did and what to run next.
+//!
+//! Printed after every outcome of `ralph run` (and written to `result.json`
+//! as `next_steps` in CI). The counts come from the journal rows recorded
+//! under the run ID, new knowledge from entries created since the run
+//! started, and the suggested commands from the final state of the tasks in
+//! the run's scope: failed tasks to reset, work left for another run, a
+//! replan when failures block the rest.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use crate::config::RunTarget;
+use crate::dag::{self, Db, Task};
+use crate::feature;
+use crate::journal;
+use crate::knowledge;
+
+/// Most task IDs, files and knowledge titles listed per line.
+const MAX_LISTED: usize = 5;
+
+/// Most failed tasks given their own `task reset` suggestion.
+const MAX_RESETS: usize = 3;
+
+#[derive(Debug, Default)]
+pub struct RunSummary {
+    pub iterations: usize,
+    pub completed: Vec<String>,
+    pub failed: Vec<String>,
+    pub files: Vec<String>,
+    pub knowledge: Vec<String>,
+    pub cost_usd: f64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Suggested commands, each with a short reason.
+    pub next_steps: Vec<(String, String)>,
+}
+
+impl RunSummary {
+    /// Summarize run `run_id`, which started at `started` and ended with
+    /// `outcome` (the `result.json` outcome name).
+    pub fn collect(
+        db: &Db,
+        project_root: &Path,
+        run_id: &str,
+        started: DateTime<Utc>,
+        target: Option<&RunTarget>,
+        outcome: &str,
+    ) -> Result<Self> {
+        let mut summary = RunSummary::default();
+        let mut files = BTreeSet::new();
+        let mut entries = journal::query_journal_recent(db, run_id, u32::MAX)?;
+        entries.reverse();
+        for entry in &entries {
+            summary.cost_usd += entry.cost_usd;
+            summary.input_tokens += entry.input_tokens;
+            summary.output_tokens += entry.output_tokens;
+            files.extend(entry.files_modified.iter().cloned());
+            let Some(task_id) = &entry.task_id else {
+                continue;
+            };
+            let bucket = match entry.outcome.as_str() {
+                "done" => &mut summary.completed,
+                "failed" => &mut summary.failed,
+                _ => continue,
+            };
+            if !bucket.contains(task_id) {
+                bucket.push(task_id.clone());
+            }
+        }
+        summary.iterations = entries.len();
+        summary.files = files.into_iter().collect();
+        summary.knowledge = knowledge::discover_knowledge(project_root)
+            .into_iter()
+            .filter(|entry| {
+                DateTime::parse_from_rfc3339(&entry.created_at)
+                    .is_ok_and(|created| created >= started)
+            })
+            .map(|entry| entry.title)
+            .collect();
+        summary.next_steps = next_steps(db, target, outcome)?;
+        Ok(summary)
+    }
+
+    /// The summary block, one line per entry.
+    pub fn render_lines(&self) -> Vec<String> {
+        let mut lines = vec!["Run summary".to_string()];
+        lines.push(format!(
+            "  {} iteration(s), ${:.2}, {} input / {} output tokens",
+            self.iterations,
+            self.cost_usd,
+            compact(self.input_tokens),
+            compact(self.output_tokens)
+        ));
+        for (label, items) in [
+            ("Completed", &self.completed),
+            ("Failed", &self.failed),
+            ("Files touched", &self.files),
+            ("New knowledge", &self.knowledge),
+        ] {
+            if !items.is_empty() {
+                lines.push(format!("  {label} ({}): {}", items.len(), listing(items)));
+            }
+        }
+        if !self.next_steps.is_empty() {
+            lines.push("Next steps".to_string());
+            let width = self.next_steps.iter().map(|(c, _)| c.len()).max();
+            for (command, reason) in &self.next_steps {
+                lines.push(format!(
+                    "  {command:<width$}  # {reason}",
+                    width = width.unwrap_or_default()
+                ));
+            }
+        }
+        lines
+    }
+}
+
+/// The first few `items`, then how many more there are.
+fn listing(items: &[String]) -> String {
+    let mut out = items
+        .iter()
+        .take(MAX_LISTED)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if items.len() > MAX_LISTED {
+        out.push_str(&format!(" (+{} more)", items.len() - MAX_LISTED));
+    }
+    out
+}
+
+fn compact(tokens: u64) -> String {
+    match tokens {
+        0..=9_999 => tokens.to_string(),
+        10_000..=999_999 => format!("{}k", tokens / 1_000),
+        _ => format!("{:.1}M", tokens as f64 / 1_000_000.0),
+    }
+}
+
+/// Commands worth running next, given the tasks in the run's scope.
+fn next_steps(db: &Db, target: Option<&RunTarget>, outcome: &str) -> Result<Vec<(String, String)>> {
+    let Some(target) = target else {
+        return Ok(Vec::new());
+    };
+    let (run_args, features) = match target {
+        RunTarget::Task(id) => (id.clone(), Vec::new()),
+        RunTarget::Features { names, .. } => (names.join(" "), names.clone()),
+    };
+    let mut steps = Vec::new();
+    if outcome == "no_plan" {
+        for name in &features {
+            steps.push((
+                format!("ralph feature create {name} --phase build"),
+                "build the task DAG from the plan".to_string(),
+            ));
+        }
+        return Ok(steps);
+    }
+
+    let tasks = scope_tasks(db, target)?;
+    let leaves: Vec<&Task> = tasks
+        .iter()
+        .filter(|t| !tasks.iter().any(|c| c.parent_id.as_deref() == Some(&t.id)))
+        .collect();
+    let failed: Vec<&&Task> = leaves.iter().filter(|t| t.status == "failed").collect();
+    let left = leaves
+        .iter()
+        .filter(|t| t.status != "done" && t.status != "failed")
+        .count();
+
+    for task in failed.iter().take(MAX_RESETS) {
+        steps.push((
+            format!("ralph task reset {}", task.id),
+            format!("retry \"{}\"", task.title),
+        ));
+    }
+    if failed.len() > MAX_RESETS {
+        steps.push((
+            "ralph task list --status failed --all".to_string(),
+            format!("{} more failed task(s)", failed.len() - MAX_RESETS),
+        ));
+    }
+    if outcome == "blocked" {
+        for name in &features {
+            steps.push((
+                format!("ralph feature replan {name}"),
+                "rework the plan around the failures".to_string(),
+            ));
+        }
+    } else if left > 0 {
+        steps.push((
+            format!("ralph run {run_args} --limit {left}"),
+            format!("{left} task(s) left"),
+        ));
+    } else if failed.is_empty() {
+        steps.push(("ralph stats".to_string(), "review the results".to_string()));
+        for name in &features {
+            steps.push((
+                format!("ralph feature archive {name}"),
+                "every task is done".to_string(),
+            ));
+        }
+    }
+    Ok(steps)
+}
+
+fn scope_tasks(db: &Db, target: &RunTarget) -> Result<Vec<Task>> {
+    match target {
+        RunTarget::Task(id) => dag::get_task_tree(db, id),
+        RunTarget::Features { names, .. } => {
+            let mut tasks = Vec::new();
+            for name in names {
+                let feat = feature::get_feature(db, name)?;
+                tasks.extend(dag::get_all_tasks_for_feature(db, &feat.id)?);
+            }
+            Ok(tasks)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::acp::types::KnowledgeSigil;
+    use crate::cli::FeatureOrder;
+    use crate::dag::init_db;
+    use crate::journal::JournalEntry;
+    use tempfile::TempDir;
+
+    #[test]
+    fn summarizes_the_run_and_suggests_next_commands() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let feat = feature::create_feature(&db, "auth").unwrap();
+        let task = |title: &str| dag::create_test_task(&db, &feat.id, title, None);
+        let done = task("Login form");
+        let broken = task("Session store");
+        task("Logout");
+        dag::claim_task(&db, &done.id, "agent-test").unwrap();
+        dag::complete_task(&db, &done.id).unwrap();
+        dag::claim_task(&db, &broken.id, "agent-test").unwrap();
+        dag::fail_task(&db, &broken.id, "no pool").unwrap();
+        for (iteration, (id, outcome)) in [(&done.id, "done"), (&broken.id, "failed")]
+            .into_iter()
+            .enumerate()
+        {
+            journal::insert_journal_entry(
+                &db,
+                &JournalEntry {
+                    id: 0,
+                    run_id: "run-1".to_string(),
+                    iteration: iteration as u32 + 1,
+                    task_id: Some(id.clone()),
+                    feature_id: Some(feat.id.clone()),
+                    outcome: outcome.to_string(),
+                    model: Some("sonnet".to_string()),
+                    duration_secs: 1.0,
+                    cost_usd: 0.5,
+                    input_tokens: 12_000,
+                    output_tokens: 800,
+                    files_modified: vec!["src/auth.rs".to_string()],
+                    files_read: Vec::new(),
+                    notes: None,
+                    created_at: String::new(),
+                },
+            )
+            .unwrap();
+        }
+        let started = Utc::now() - chrono::Duration::minutes(1);
+        knowledge::write_knowledge_entry(
+            dir.path(),
+            &KnowledgeSigil {
+                title: "Sessions live in redis".to_string(),
+                tags: vec!["auth".to_string()],
+                body: "Use the session pool.".to_string(),
+            },
+            None,
+            knowledge::KnowledgeScope::Global,
+        )
+        .unwrap();
+
+        let target = RunTarget::Features {
+            names: vec!["auth".to_string()],
+            order: FeatureOrder::Sequential,
+        };
+        let summary = RunSummary::collect(
+            &db,
+            dir.path(),
+            "run-1",
+            started,
+            Some(&target),
+            "limit_reached",
+        )
+        .unwrap();
+        assert_eq!(summary.completed, [done.id.as_str()]);
+        assert_eq!(summary.failed, [broken.id.as_str()]);
+        assert_eq!(summary.knowledge, ["Sessions live in redis"]);
+        let lines = summary.render_lines();
+        assert!(
+            lines.contains(&"  2 iteration(s), $1.00, 24k input / 1600 output tokens".to_string())
+        );
+        assert!(lines.contains(&"  Files touched (1): src/auth.rs".to_string()));
+        let commands: Vec<&str> = summary.next_steps.iter().map(|(c, _)| c.as_str()).collect();
+        assert_eq!(
+            commands,
+            [
+                format!("ralph task reset {}", broken.id).as_str(),
+                "ralph run auth --limit 1"
+            ]
+        );
+
+        let blocked =
+            RunSummary::collect(&db, dir.path(), "run-1", started, Some(&target), "blocked")
+                .unwrap();
+        assert_eq!(blocked.next_steps[1].0, "ralph feature replan auth");
+    }
+}
@@ -0,0 +1,136 @@
+//! Per-feature scratchpad handed from one iteration to the next.
+//!
+//! `.ralph/scratch/<feature>.md` is a free-form file the agent may write to
+//! during an iteration. Unlike knowledge entries it has no frontmatter, tags or
+//! deduplication: before each iteration ralph creates it if needed, trims it to
+//! [`MAX_CHARS`], and injects its last [`TAIL_CHARS`] into the prompt.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Size the file is trimmed to before each iteration.
+pub const MAX_CHARS: usize = 16_000;
+
+/// How much of the end of the file goes into the prompt.
+pub const TAIL_CHARS: usize = 4_000;
+
+/// Scratchpad file for a feature, or `standalone.md` outside a feature run.
+///
+/// Characters other than ASCII alphanumerics, `-` and `_` become `-`, so a
+/// feature name cannot point the file outside `.ralph/scratch/`.
+pub fn scratch_path(project_root: &Path, feature: Option<&str>) -> PathBuf {
+    let name: String = feature
+        .unwrap_or("standalone")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    project_root
+        .join(".ralph/scratch")
+        .join(format!("{name}.md"))
+}
+
+/// Create the scratchpad if missing, trim it, and return its tail.
+pub fn prepare(path: &Path) -> Result<String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let kept = tail(&content, MAX_CHARS);
+    if kept.len() != content.len() || !path.exists() {
+        std::fs::write(path, kept)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(tail(kept, TAIL_CHARS).to_string())
+}
+
+/// The last `max_chars` characters of `text`, starting at a line boundary when
+/// the text had to be cut.
+fn tail(text: &str, max_chars: usize) -> &str {
+    let Some((cut, _)) = text.char_indices().rev().nth(max_chars.saturating_sub(1)) else {
+        return text;
+    };
+    if cut == 0 {
+        return text;
+    }
+    let rest = &text[cut..];
+    match rest.find('\n') {
+        Some(nl) if nl + 1 < rest.len() => &rest[nl + 1..],
+        _ => rest,
+    }
+}
+
+/// Render the scratchpad prompt section: where the file is and what it holds.
+pub fn render_scratchpad_context(relative_path: &str, tail: &str) -> String {
+    let mut section = String::from("## Scratchpad\n\n");
+    section.push_str(&format!(
+        "`{relative_path}` carries notes between iterations. Write to it freely: \
+         open questions, half-finished plans, commands that worked. \
+         Ralph keeps the most recent part and shows it to the next iteration.\n\n"
+    ));
+    if tail.trim().is_empty() {
+        section.push_str("It is currently empty.\n");
+    } else {
+        section.push_str("Current contents (most recent part):\n\n");
+        section.push_str(&format!("```markdown\n{}\n```\n", tail.trim_end()));
+    }
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn path_is_per_feature_and_sanitized() {
+        let root = Path::new("/p");
+        assert_eq!(
+            scratch_path(root, Some("auth")),
+            PathBuf::from("/p/.ralph/scratch/auth.md")
+        );
+        assert_eq!(
+            scratch_path(root, None),
+            PathBuf::from("/p/.ralph/scratch/standalone.md")
+        );
+        assert_eq!(
+            scratch_path(root, Some("../x")),
+            PathBuf::from("/p/.ralph/scratch/---x.md")
+        );
+    }
+
+    #[test]
+    fn prepare_creates_trims_and_returns_tail() {
+        let dir = TempDir::new().unwrap();
+        let path = scratch_path(dir.path(), Some("auth"));
+        assert_eq!(prepare(&path).unwrap(), "");
+        assert!(path.exists());
+
+        let line = "x".repeat(99) + "\n";
+        std::fs::write(&path, line.repeat(300)).unwrap();
+        let tail = prepare(&path).unwrap();
+        let kept = std::fs::read_to_string(&path).unwrap();
+        assert!(kept.len() <= MAX_CHARS && kept.starts_with('x'));
+        assert!(tail.len() <= TAIL_CHARS && tail.starts_with('x'));
+        assert!(kept.ends_with(&tail));
+    }
+
+    #[test]
+    fn context_mentions_path_and_contents() {
+        let empty = render_scratchpad_context(".ralph/scratch/auth.md", "");
+        assert!(empty.contains("`.ralph/scratch/auth.md`"));
+        assert!(empty.contains("currently empty"));
+        let full = render_scratchpad_context(".ralph/scratch/auth.md", "- try cargo nextest\n");
+        assert!(full.contains("- try cargo nextest"));
+    }
+}
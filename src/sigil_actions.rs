@@ -0,0 +1,524 @@
+//! Actions for project-defined sigils (`[sigils.custom]` in `.ralph.toml`)
+//! and the built-in queue sigils (`<task-add>`, `<task-block>`, `<dep-add>`).
+//!
+//! `acp::sigils::extract_sigils` finds each occurrence; the run loop then calls
+//! [`apply`] or [`apply_queue`] once per occurrence, in output order.
+
+use anyhow::{bail, Context, Result};
+use std::io::Write;
+use std::path::{Component, Path};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+use crate::acp::types::{CustomSigilMatch, QueueSigil};
+use crate::dag::{self, CreateTaskParams, Db, Task};
+use crate::project::{CustomSigil, CustomSigilAction};
+
+/// How long a `run-command` sigil action may run before it is killed.
+const RUN_COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Lines of a `run-command` action's stderr kept in its summary.
+const RUN_COMMAND_STDERR_LINES: usize = 5;
+
+/// Run the configured action for one custom sigil emitted while working on `task`.
+///
+/// Returns a short description of what was done, for the event log.
+pub async fn apply(
+    db: &Db,
+    project_root: &Path,
+    task: &Task,
+    sigil: &CustomSigilMatch,
+    action: &CustomSigilAction,
+    created_by: Option<&str>,
+) -> Result<String> {
+    match action {
+        CustomSigilAction::AppendToFile { path, template } => {
+            if !Path::new(path)
+                .components()
+                .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+            {
+                bail!("append-to-file path '{}' must be inside the project", path);
+            }
+            let target = project_root.join(path);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            let line = template
+                .as_deref()
+                .unwrap_or("{body}")
+                .replace("{body}", &sigil.body);
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&target)
+                .with_context(|| format!("Failed to open {}", target.display()))?;
+            writeln!(file, "{line}")?;
+            Ok(format!("appended to {path}"))
+        }
+        CustomSigilAction::RunCommand { command } => {
+            let parts = shlex::split(command)
+                .filter(|p| !p.is_empty())
+                .with_context(|| format!("Invalid run-command '{}'", command))?;
+            let mut child = tokio::process::Command::new(&parts[0])
+                .args(&parts[1..])
+                .current_dir(project_root)
+                .env("RALPH_SIGIL_TAG", &sigil.tag)
+                .env("RALPH_SIGIL_BODY", &sigil.body)
+                .env("RALPH_TASK_ID", &task.id)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::piped())
+                .kill_on_drop(true)
+                .spawn()
+                .with_context(|| format!("Failed to run '{}'", command))?;
+            // Feed stdin while collecting stderr, both under the timeout, so a
+            // command that never reads its input cannot hang the run.
+            let stdin = child.stdin.take();
+            let feed = async {
+                if let Some(mut stdin) = stdin {
+                    let _ = stdin.write_all(sigil.body.as_bytes()).await;
+                }
+            };
+            let run = async {
+                let ((), output) = tokio::join!(feed, child.wait_with_output());
+                output
+            };
+            let output = tokio::time::timeout(RUN_COMMAND_TIMEOUT, run)
+                .await
+                .map_err(|_| {
+                    anyhow::anyhow!(
+                        "'{}' timed out after {}s",
+                        command,
+                        RUN_COMMAND_TIMEOUT.as_secs()
+                    )
+                })??;
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let lines: Vec<&str> = stderr.trim_end().lines().collect();
+            let tail = lines[lines.len().saturating_sub(RUN_COMMAND_STDERR_LINES)..].join("\n");
+            let summary = match output.status.success() {
+                true => format!("ran {command}"),
+                false => format!("'{}' exited with {}", command, output.status),
+            };
+            let summary = match tail.is_empty() {
+                true => summary,
+                false => format!("{summary}:\n{tail}"),
+            };
+            if !output.status.success() {
+                bail!(summary);
+            }
+            Ok(summary)
+        }
+        CustomSigilAction::CreateTask { priority } => {
+            let (title, description) = match sigil.body.split_once('\n') {
+                Some((title, rest)) => (title.trim(), Some(rest.trim())),
+                None => (sigil.body.trim(), None),
+            };
+            let parent_id = feature_root(db, task)?;
+            let created = dag::create_task_with_feature(
+                db,
+                CreateTaskParams {
+                    title,
+                    description: description.filter(|d| !d.is_empty()),
+                    parent_id: parent_id.as_deref(),
+                    priority: priority.unwrap_or(task.priority),
+                    feature_id: task.feature_id.as_deref(),
+                    task_type: if task.feature_id.is_some() {
+                        "feature"
+                    } else {
+                        "standalone"
+                    },
+                    max_retries: task.max_retries,
+                    criteria: &[],
+                    discovered_by: Some(&task.id),
+                    cwd: task.cwd.as_deref(),
+                    created_by,
+                },
+            )?;
+            Ok(format!("created {}", created.id))
+        }
+    }
+}
+
+/// The root of the feature DAG `task` belongs to, under which tasks it
+/// discovers are created. `None` for standalone tasks.
+fn feature_root(db: &Db, task: &Task) -> Result<Option<String>> {
+    if task.feature_id.is_none() {
+        return Ok(None);
+    }
+    let mut current = task.clone();
+    while let Some(parent_id) = current.parent_id.as_deref() {
+        current = dag::get_task(db, parent_id)?;
+    }
+    Ok(Some(current.id))
+}
+
+/// Apply one queue sigil emitted while working on `task`.
+///
+/// Every referenced task must exist. New tasks join the current task's feature
+/// under its root and record it in `discovered_by`. Returns a short summary for the iteration
+/// output.
+pub fn apply_queue(
+    db: &Db,
+    task: &Task,
+    sigil: &QueueSigil,
+    created_by: Option<&str>,
+) -> Result<String> {
+    match sigil {
+        QueueSigil::AddTask {
+            title,
+            description,
+            priority,
+            depends_on,
+        } => {
+            for id in depends_on {
+                dag::get_task(db, id).with_context(|| format!("depends-on task '{}'", id))?;
+            }
+            let parent_id = feature_root(db, task)?;
+            let created = dag::create_task_with_feature(
+                db,
+                CreateTaskParams {
+                    title,
+                    description: Some(description.as_str()).filter(|d| !d.is_empty()),
+                    parent_id: parent_id.as_deref(),
+                    priority: priority.unwrap_or(task.priority),
+                    feature_id: task.feature_id.as_deref(),
+                    task_type: if task.feature_id.is_some() {
+                        "feature"
+                    } else {
+                        "standalone"
+                    },
+                    max_retries: task.max_retries,
+                    criteria: &[],
+                    discovered_by: Some(&task.id),
+                    cwd: task.cwd.as_deref(),
+                    created_by,
+                },
+            )?;
+            for id in depends_on {
+                dag::add_dependency(db, id, &created.id)?;
+            }
+            if depends_on.is_empty() {
+                Ok(format!("added {} \"{}\"", created.id, created.title))
+            } else {
+                Ok(format!(
+                    "added {} \"{}\" after {}",
+                    created.id,
+                    created.title,
+                    depends_on.join(", ")
+                ))
+            }
+        }
+        QueueSigil::BlockTask { task_id, reason } => {
+            if *task_id == task.id {
+                bail!("cannot block the task being worked on; use <task-failed> instead");
+            }
+            let target = dag::get_task(db, task_id)?;
+            if target.status != "pending" {
+                bail!(
+                    "only pending tasks can be blocked ({} is {})",
+                    task_id,
+                    target.status
+                );
+            }
+            dag::set_task_status(db.conn(), task_id, "blocked")?;
+            let reason = reason.as_deref().unwrap_or("no reason given");
+            dag::add_log(db, task_id, &format!("Blocked by {}: {}", task.id, reason))?;
+            Ok(format!("blocked {task_id} ({reason})"))
+        }
+        QueueSigil::AddDependency { blocker, blocked } => {
+            dag::get_task(db, blocker)?;
+            dag::get_task(db, blocked)?;
+            dag::add_dependency(db, blocker, blocked)?;
+            Ok(format!("{blocked} now depends on {blocker}"))
+        }
+    }
+}
+
+/// Render the prompt section describing the project's custom sigils.
+///
+/// Empty when none are configured.
+pub fn render_prompt_section<'a>(
+    custom: impl IntoIterator<Item = (&'a String, &'a CustomSigil)>,
+) -> String {
+    let mut section = String::new();
+    for (tag, sigil) in custom {
+        if !crate::acp::sigils::is_valid_custom_tag(tag) {
+            continue;
+        }
+        let effect = match &sigil.action {
+            CustomSigilAction::AppendToFile { path, .. } => format!("appended to `{path}`"),
+            CustomSigilAction::RunCommand { .. } => "passed to a project script".to_string(),
+            CustomSigilAction::CreateTask { .. } => {
+                "turned into a new task (first line = title)".to_string()
+            }
+        };
+        section.push_str(&format!("- `<{tag}>...</{tag}>` — {effect}"));
+        if let Some(ref description) = sigil.description {
+            section.push_str(&format!(". Use for: {description}"));
+        }
+        section.push('\n');
+    }
+    if section.is_empty() {
+        return section;
+    }
+    format!(
+        "### Project Sigils\n\
+         This project defines extra sigils. Each occurrence is processed after your turn:\n\n\
+         {section}\n"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag::init_db;
+    use tempfile::TempDir;
+
+    fn matched(tag: &str, body: &str) -> CustomSigilMatch {
+        CustomSigilMatch {
+            tag: tag.to_string(),
+            body: body.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn append_and_create_task_actions() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let task = dag::create_task(&db, "Current", None, None, 2).unwrap();
+
+        let append = CustomSigilAction::AppendToFile {
+            path: "docs/migrations.md".to_string(),
+            template: Some("- [ ] {body}".to_string()),
+        };
+        apply(
+            &db,
+            dir.path(),
+            &task,
+            &matched("migration", "add email"),
+            &append,
+            None,
+        )
+        .await
+        .unwrap();
+        apply(
+            &db,
+            dir.path(),
+            &task,
+            &matched("migration", "drop legacy"),
+            &append,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("docs/migrations.md")).unwrap(),
+            "- [ ] add email\n- [ ] drop legacy\n"
+        );
+
+        let escape = CustomSigilAction::AppendToFile {
+            path: "../outside.md".to_string(),
+            template: None,
+        };
+        assert!(
+            apply(&db, dir.path(), &task, &matched("m", "x"), &escape, None)
+                .await
+                .is_err()
+        );
+
+        let create = CustomSigilAction::CreateTask { priority: None };
+        let summary = apply(
+            &db,
+            dir.path(),
+            &task,
+            &matched("followup", "Write docs\nCover the new flag."),
+            &create,
+            Some("alice via claude-agent-acp"),
+        )
+        .await
+        .unwrap();
+        let new_id = summary.strip_prefix("created ").unwrap();
+        let created = dag::get_task(&db, new_id).unwrap();
+        assert_eq!(created.title, "Write docs");
+        assert_eq!(created.description, "Cover the new flag.");
+        assert_eq!(created.priority, 2);
+        assert_eq!(
+            created.created_by.as_deref(),
+            Some("alice via claude-agent-acp")
+        );
+        assert_eq!(created.discovered_by.as_deref(), Some(task.id.as_str()));
+    }
+
+    #[tokio::test]
+    async fn create_task_joins_feature_root_and_cwd() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let feature = crate::feature::create_feature(&db, "auth").unwrap();
+        let params = |title, parent_id| CreateTaskParams {
+            title,
+            description: None,
+            parent_id,
+            priority: 1,
+            feature_id: Some(&feature.id),
+            task_type: "feature",
+            max_retries: 3,
+            criteria: &[],
+            discovered_by: None,
+            cwd: Some("crates/api"),
+            created_by: None,
+        };
+        let root = dag::create_task_with_feature(&db, params("Feature: auth", None)).unwrap();
+        let group = dag::create_task_with_feature(&db, params("Storage", Some(&root.id))).unwrap();
+        let task = dag::create_task_with_feature(&db, params("Users", Some(&group.id))).unwrap();
+
+        let create = CustomSigilAction::CreateTask { priority: None };
+        let summary = apply(
+            &db,
+            dir.path(),
+            &task,
+            &matched("followup", "Add index"),
+            &create,
+            None,
+        )
+        .await
+        .unwrap();
+        let created = dag::get_task(&db, summary.strip_prefix("created ").unwrap()).unwrap();
+        assert_eq!(created.parent_id.as_deref(), Some(root.id.as_str()));
+        assert_eq!(created.feature_id.as_deref(), Some(feature.id.as_str()));
+        assert_eq!(created.cwd.as_deref(), Some("crates/api"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn run_command_captures_stderr() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let task = dag::create_task(&db, "Current", None, None, 2).unwrap();
+
+        let run = |command: &str| CustomSigilAction::RunCommand {
+            command: command.to_string(),
+        };
+        let summary = apply(
+            &db,
+            dir.path(),
+            &task,
+            &matched("notify", "hi"),
+            &run("sh -c 'cat >&2'"),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(summary, "ran sh -c 'cat >&2':\nhi");
+
+        let err = apply(
+            &db,
+            dir.path(),
+            &task,
+            &matched("notify", "x"),
+            &run("sh -c 'echo boom >&2; exit 3'"),
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("boom"), "{err}");
+
+        // A large body must not deadlock against a command busy writing stderr.
+        let body = "x".repeat(100 * 1024);
+        let applied = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            apply(
+                &db,
+                dir.path(),
+                &task,
+                &matched("notify", &body),
+                &run("sh -c 'head -c 200000 /dev/zero >&2; cat >/dev/null'"),
+                None,
+            ),
+        )
+        .await
+        .expect("run-command should not hang on a large body");
+        assert!(applied.is_ok(), "{applied:?}");
+    }
+
+    #[test]
+    fn queue_sigils_add_block_and_link_tasks() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let task = dag::create_task(&db, "Current", None, None, 2).unwrap();
+        let other = dag::create_task(&db, "Other", None, None, 2).unwrap();
+
+        let summary = apply_queue(
+            &db,
+            &task,
+            &QueueSigil::AddTask {
+                title: "Add index".to_string(),
+                description: "Lookups are slow.".to_string(),
+                priority: None,
+                depends_on: vec![other.id.clone()],
+            },
+            None,
+        )
+        .unwrap();
+        let new_id = summary.split_whitespace().nth(1).unwrap();
+        let created = dag::get_task(&db, new_id).unwrap();
+        assert_eq!(created.discovered_by.as_deref(), Some(task.id.as_str()));
+        assert_eq!(created.priority, 2);
+        let add_missing_dep = QueueSigil::AddTask {
+            title: "Orphan".to_string(),
+            description: String::new(),
+            priority: None,
+            depends_on: vec!["t-missing".to_string()],
+        };
+        assert!(apply_queue(&db, &task, &add_missing_dep, None).is_err());
+
+        // other -> created exists, so created -> other would close a loop.
+        let cycle = QueueSigil::AddDependency {
+            blocker: created.id.clone(),
+            blocked: other.id.clone(),
+        };
+        assert!(apply_queue(&db, &task, &cycle, None).is_err());
+
+        let block_self = QueueSigil::BlockTask {
+            task_id: task.id.clone(),
+            reason: None,
+        };
+        assert!(apply_queue(&db, &task, &block_self, None).is_err());
+        let block = QueueSigil::BlockTask {
+            task_id: other.id.clone(),
+            reason: Some("needs API key".to_string()),
+        };
+        apply_queue(&db, &task, &block, None).unwrap();
+        assert_eq!(dag::get_task(&db, &other.id).unwrap().status, "blocked");
+        assert!(dag::get_task_logs(&db, &other.id)
+            .unwrap()
+            .iter()
+            .any(|l| l.message.contains("needs API key")));
+    }
+
+    #[test]
+    fn prompt_section_lists_valid_tags_only() {
+        let mut custom = std::collections::BTreeMap::new();
+        assert_eq!(render_prompt_section(&custom), "");
+
+        custom.insert(
+            "migration".to_string(),
+            CustomSigil {
+                action: CustomSigilAction::CreateTask { priority: None },
+                description: Some("schema changes".to_string()),
+            },
+        );
+        custom.insert(
+            "journal".to_string(),
+            CustomSigil {
+                action: CustomSigilAction::CreateTask { priority: None },
+                description: None,
+            },
+        );
+        let section = render_prompt_section(&custom);
+        assert!(section.contains("`<migration>...</migration>`"));
+        assert!(section.contains("Use for: schema changes"));
+        assert!(!section.contains("<journal>"));
+    }
+}
@@ -0,0 +1,161 @@
+//! Speculative exploration of the next task while a verification runs.
+//!
+//! With `[execution] speculative_exploration`, finishing a task also starts
+//! a read-only ACP session on the next ready task: no file writes and no
+//! terminal. It reads the code the task will touch and reports an
+//! `<analysis>` block. When the loop then picks that task, a finished
+//! analysis goes into its prompt under "Prior Analysis". A session that has
+//! not finished by then, or that explored a different task, is dropped,
+//! which kills the agent. The next iteration never waits for it.
+
+use anyhow::Result;
+use tokio::task::JoinHandle;
+
+use crate::acp;
+use crate::config::Config;
+use crate::dag::Task;
+
+/// Longest analysis carried into a prompt, in characters.
+const ANALYSIS_MAX_CHARS: usize = 4000;
+
+/// An exploration session running alongside a verification. Dropping it
+/// aborts the session.
+pub struct Speculation {
+    pub task_id: String,
+    handle: Option<JoinHandle<Result<String>>>,
+}
+
+impl Speculation {
+    /// Start exploring `task` on a `LocalSet`-spawned read-only session.
+    pub fn start(
+        config: &Config,
+        task: &Task,
+        spec_content: Option<&str>,
+        plan_content: Option<&str>,
+    ) -> Self {
+        let instructions = build_exploration_prompt(task, spec_content, plan_content);
+        let agent_command = config.agent_command.clone();
+        let project_root = config.project_root.clone();
+        let model = config.current_model.clone();
+        let handle = tokio::task::spawn_local(async move {
+            let result = acp::connection::run_autonomous(
+                &agent_command,
+                &project_root,
+                &instructions,
+                "Explore the task.",
+                true, // read_only
+                Some(&model),
+                acp::connection::SessionRestrictions {
+                    allow_terminal: false,
+                    ..Default::default()
+                },
+            )
+            .await?;
+            Ok(parse_analysis(&result.full_text))
+        });
+        Self {
+            task_id: task.id.clone(),
+            handle: Some(handle),
+        }
+    }
+
+    /// The analysis, if the session has already finished with one.
+    pub async fn take_if_finished(mut self) -> Option<String> {
+        let handle = self.handle.take()?;
+        if !handle.is_finished() {
+            handle.abort();
+            return None;
+        }
+        match handle.await {
+            Ok(Ok(analysis)) if !analysis.is_empty() => Some(analysis),
+            _ => None,
+        }
+    }
+}
+
+impl Drop for Speculation {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.abort();
+        }
+    }
+}
+
+fn build_exploration_prompt(
+    task: &Task,
+    spec_content: Option<&str>,
+    plan_content: Option<&str>,
+) -> String {
+    let mut prompt = String::new();
+    prompt.push_str("You are preparing the next task for a Ralph iteration. Another agent will implement it; your job is to explore so that agent can start immediately.\n\n");
+    prompt.push_str("## Task\n\n");
+    prompt.push_str(&format!("**ID:** {}\n", task.id));
+    prompt.push_str(&format!("**Title:** {}\n", task.title));
+    prompt.push_str(&format!("**Description:** {}\n\n", task.description));
+
+    if let Some(spec) = spec_content {
+        prompt.push_str("## Specification\n\n");
+        prompt.push_str(spec);
+        prompt.push_str("\n\n");
+    }
+    if let Some(plan) = plan_content {
+        prompt.push_str("## Plan\n\n");
+        prompt.push_str(plan);
+        prompt.push_str("\n\n");
+    }
+
+    prompt.push_str(
+        r#"## Instructions
+
+1. Read the code the task will change and the code it depends on
+2. Do NOT modify any files and do not run commands — you are read-only
+3. Another task is being verified right now, so files may still change
+
+Finish with one `<analysis>` block, under 400 words: the files and functions
+involved, the approach you would take, and pitfalls you noticed.
+
+```
+<analysis>
+...
+</analysis>
+```
+"#,
+    );
+    prompt
+}
+
+/// Content of the last `<analysis>` block, or the tail of `text` when the
+/// agent emitted none, capped at [`ANALYSIS_MAX_CHARS`].
+fn parse_analysis(text: &str) -> String {
+    let analysis = text
+        .rfind("<analysis>")
+        .map(|start| {
+            let body = &text[start + "<analysis>".len()..];
+            body.find("</analysis>").map_or(body, |end| &body[..end])
+        })
+        .unwrap_or(text)
+        .trim();
+    let skip = analysis.chars().count().saturating_sub(ANALYSIS_MAX_CHARS);
+    match analysis.char_indices().nth(skip) {
+        Some((offset, _)) if skip > 0 => analysis[offset..].to_string(),
+        _ => analysis.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_last_analysis_block_or_falls_back_to_tail() {
+        let text = "Reading files...\n<analysis>draft</analysis>\nMore.\n<analysis>\nEdit src/api.rs\n</analysis>";
+        assert_eq!(parse_analysis(text), "Edit src/api.rs");
+        assert_eq!(parse_analysis("  no block here \n"), "no block here");
+        assert_eq!(parse_analysis("<analysis>unterminated"), "unterminated");
+
+        let long = "x".repeat(ANALYSIS_MAX_CHARS + 10) + "end";
+        let parsed = parse_analysis(&long);
+        assert_eq!(parsed.chars().count(), ANALYSIS_MAX_CHARS);
+        assert!(parsed.ends_with("end"));
+    }
+}
@@ -0,0 +1,91 @@
+//! Steering messages: notes the user types into the TUI (`m`) while an agent
+//! works, without interrupting the run.
+//!
+//! The UI thread queues a message; the live ACP session picks it up at the
+//! next safe point (no tool call running, or the end of the agent's turn) and
+//! sends it as a user message in the same session. Messages queued between
+//! sessions go out with the next session's first prompt. The run loop drains
+//! the delivered messages after each iteration to log them.
+
+use std::sync::Mutex;
+
+/// Queued and not yet sent to an agent.
+static PENDING: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Sent to an agent, not yet logged.
+static DELIVERED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Serializes tests that use the global queues.
+#[cfg(test)]
+pub(crate) static TEST_LOCK: Mutex<()> = Mutex::new(());
+
+/// Queue `message` for the live (or next) agent session. Blank messages are
+/// ignored.
+pub fn queue(message: &str) {
+    let message = message.trim();
+    if !message.is_empty() {
+        lock(&PENDING).push(message.to_string());
+    }
+}
+
+/// Whether a message is waiting to be delivered.
+pub fn has_pending() -> bool {
+    !lock(&PENDING).is_empty()
+}
+
+/// Take the queued messages for delivery, remembering them for the log.
+pub fn deliver() -> Vec<String> {
+    let messages = std::mem::take(&mut *lock(&PENDING));
+    lock(&DELIVERED).extend(messages.iter().cloned());
+    messages
+}
+
+/// Messages delivered since the last call, oldest first.
+pub fn take_delivered() -> Vec<String> {
+    std::mem::take(&mut *lock(&DELIVERED))
+}
+
+/// The user message that carries `messages` to the agent.
+pub fn render(messages: &[String]) -> String {
+    let mut text = String::from(
+        "## Message from the user\n\n\
+         The user sent this while you were working. Take it into account and \
+         continue the current task; do not start over.\n",
+    );
+    for message in messages {
+        text.push_str(&format!("\n> {}\n", message.replace('\n', "\n> ")));
+    }
+    text
+}
+
+fn lock(queue: &Mutex<Vec<String>>) -> std::sync::MutexGuard<'_, Vec<String>> {
+    queue.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queued_messages_are_delivered_once_and_logged_once() {
+        let _guard = TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        deliver();
+        take_delivered();
+        queue("  ");
+        queue("Use the existing retry helper\nin net.rs");
+        queue("Skip the docs for now");
+        assert!(has_pending());
+
+        let messages = deliver();
+        assert_eq!(messages.len(), 2);
+        assert!(!has_pending());
+        assert!(deliver().is_empty());
+
+        let text = render(&messages);
+        assert!(text.contains("> Use the existing retry helper\n> in net.rs\n"));
+        assert!(text.contains("> Skip the docs for now\n"));
+
+        assert_eq!(take_delivered(), messages);
+        assert!(take_delivered().is_empty());
+    }
+}
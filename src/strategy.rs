@@ -3,9 +3,12 @@
 //! Determines which Claude model to use for each iteration based on the
 //! configured strategy.
 
-use crate::dag::{self, Db};
+use std::collections::BTreeMap;
+
+use crate::dag::{self, Db, Task};
 
 use crate::config::{Config, ModelStrategy};
+use crate::project::AdaptiveConfig;
 
 /// Result of model selection, including override information for logging.
 #[derive(Debug, Clone, PartialEq)]
@@ -84,6 +87,8 @@ pub fn select_model_with_db(
         ModelStrategy::CostOptimized => select_cost_optimized(config, db),
         ModelStrategy::Escalate => select_escalate(config, db),
         ModelStrategy::PlanThenExecute => select_plan_then_execute(config),
+        // Chosen once the task is claimed; see `select_for_task`.
+        ModelStrategy::Adaptive => config.current_model.clone(),
     };
 
     // Claude hint always wins if provided.
@@ -173,6 +178,7 @@ fn analyze_progress(content: &str, _iteration: u32) -> String {
         "bug",
         "broken",
         "regression",
+        "timeout",
         "FAILURE",
     ];
     let has_errors = error_signals
@@ -250,6 +256,109 @@ fn select_plan_then_execute(config: &Config) -> String {
     }
 }
 
+/// Journal outcomes and how many times each model produced them.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct ModelStats {
+    attempts: u32,
+    successes: u32,
+}
+
+/// Per-model attempt and success counts from the journal.
+///
+/// Only iterations that worked on a task count; `done` is a success and
+/// `failed`, `retried`, `blocked` and `timeout` are not.
+fn load_model_stats(db: &Db) -> BTreeMap<String, ModelStats> {
+    let mut stats = BTreeMap::new();
+    let Ok(mut stmt) = db.conn().prepare(
+        "SELECT model, COUNT(*), SUM(outcome = 'done')
+         FROM journal
+         WHERE model IS NOT NULL
+           AND outcome IN ('done', 'failed', 'retried', 'blocked', 'timeout')
+         GROUP BY model",
+    ) else {
+        return stats;
+    };
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            ModelStats {
+                attempts: row.get(1)?,
+                successes: row.get(2)?,
+            },
+        ))
+    });
+    if let Ok(rows) = rows {
+        stats.extend(rows.flatten());
+    }
+    stats
+}
+
+/// Selection weight per candidate model for a task.
+///
+/// The base weight is the Laplace-smoothed success rate, so untried models
+/// start at 0.5 and keep being sampled. Each `task_weights` entry whose key
+/// appears as a word in `task_text` then multiplies the models it lists.
+fn adaptive_weights(
+    adaptive: &AdaptiveConfig,
+    stats: &BTreeMap<String, ModelStats>,
+    task_text: &str,
+) -> Vec<(String, f64)> {
+    let lower = task_text.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_alphanumeric() && c != '-' && c != '_')
+        .collect();
+    adaptive
+        .models
+        .iter()
+        .map(|model| {
+            let s = stats.get(model).copied().unwrap_or_default();
+            let mut weight = (s.successes as f64 + 1.0) / (s.attempts as f64 + 2.0);
+            for (kind, multipliers) in &adaptive.task_weights {
+                if words.contains(&kind.to_lowercase().as_str()) {
+                    weight *= multipliers.get(model).copied().unwrap_or(1.0);
+                }
+            }
+            (model.clone(), weight)
+        })
+        .collect()
+}
+
+/// Smooth weighted round-robin: each model earns its weight in credit, the
+/// richest is picked and pays back the total. Over many picks each model's
+/// share matches its weight, without the bursts of plain round-robin.
+fn weighted_round_robin(
+    credits: &mut BTreeMap<String, f64>,
+    weights: &[(String, f64)],
+) -> Option<String> {
+    let weights: Vec<&(String, f64)> = weights.iter().filter(|(_, w)| *w > 0.0).collect();
+    let total: f64 = weights.iter().map(|(_, w)| w).sum();
+    let mut best: Option<(&str, f64)> = None;
+    for (model, weight) in &weights {
+        let credit = credits.entry(model.clone()).or_insert(0.0);
+        *credit += weight;
+        if best.is_none_or(|(_, c)| *credit > c) {
+            best = Some((model, *credit));
+        }
+    }
+    let (model, _) = best?;
+    *credits.get_mut(model)? -= total;
+    Some(model.to_string())
+}
+
+/// Adaptive strategy: pick the model for a just-claimed task.
+///
+/// Returns `None` for other strategies and when a `<next-model>` hint already
+/// chose this iteration's model.
+pub fn select_for_task(config: &mut Config, db: &Db, task: &Task) -> Option<String> {
+    if config.model_strategy != ModelStrategy::Adaptive || config.model_hinted {
+        return None;
+    }
+    let stats = load_model_stats(db);
+    let text = format!("{}\n{}", task.title, task.description);
+    let weights = adaptive_weights(&config.ralph_config.strategy.adaptive, &stats, &text);
+    weighted_round_robin(&mut config.adaptive_credits, &weights)
+}
+
 /// Assess what escalation level the recent progress text warrants.
 ///
 /// Returns 0 (haiku) when everything looks fine, 1 (sonnet) for moderate
@@ -275,7 +384,7 @@ fn assess_escalation_need(content: &str) -> u8 {
     }
 
     // Moderate distress → sonnet (level 1)
-    let moderate_signals = ["error", "failure", "failed", "bug"];
+    let moderate_signals = ["error", "failure", "failed", "bug", "timeout"];
     if moderate_signals
         .iter()
         .any(|s| lower.contains(&s.to_lowercase()))
@@ -314,6 +423,7 @@ mod tests {
             None,
             false,
             None,
+            None,
         )
         .unwrap()
     }
@@ -454,6 +564,7 @@ mod tests {
             None,
             false,
             None,
+            None,
         )
         .unwrap();
         assert_eq!(select_model(&mut config, Some("haiku")).model, "haiku");
@@ -476,6 +587,7 @@ mod tests {
             None,
             false,
             None,
+            None,
         )
         .unwrap()
     }
@@ -606,6 +718,7 @@ mod tests {
             None,
             false,
             None,
+            None,
         )
         .unwrap()
     }
@@ -858,4 +971,101 @@ mod tests {
         assert_eq!(selection.strategy_choice, "haiku");
         assert_eq!(selection.model, "opus");
     }
+
+    // --- adaptive strategy tests ---
+
+    fn adaptive_config(task_weights: &str) -> Config {
+        let toml = format!("[strategy.adaptive.task_weights]\n{task_weights}");
+        let project = ProjectConfig {
+            root: PathBuf::from("/test"),
+            config: toml::from_str(&toml).unwrap(),
+        };
+        Config::from_run_args(
+            None,
+            Some("adaptive".to_string()),
+            None,
+            project,
+            None,
+            None,
+            false,
+            None,
+            None,
+        )
+        .unwrap()
+    }
+
+    fn stats(attempts: u32, successes: u32) -> ModelStats {
+        ModelStats {
+            attempts,
+            successes,
+        }
+    }
+
+    #[test]
+    fn adaptive_weights_follow_success_rate_and_task_type() {
+        let config = adaptive_config("design = { opus = 4.0 }");
+        let adaptive = &config.ralph_config.strategy.adaptive;
+        let mut history = BTreeMap::new();
+        history.insert("haiku".to_string(), stats(8, 0));
+        history.insert("sonnet".to_string(), stats(8, 8));
+
+        let weights = adaptive_weights(adaptive, &history, "Fix the parser");
+        assert_eq!(weights[0], ("haiku".to_string(), 0.1));
+        assert_eq!(weights[1], ("sonnet".to_string(), 0.9));
+        assert_eq!(weights[2], ("opus".to_string(), 0.5));
+
+        let weights = adaptive_weights(adaptive, &history, "Design the storage API");
+        assert_eq!(weights[2], ("opus".to_string(), 2.0));
+        let weights = adaptive_weights(adaptive, &history, "Redesign nothing");
+        assert_eq!(weights[2], ("opus".to_string(), 0.5));
+    }
+
+    #[test]
+    fn weighted_round_robin_shares_picks_by_weight() {
+        let weights = vec![
+            ("haiku".to_string(), 0.0),
+            ("sonnet".to_string(), 3.0),
+            ("opus".to_string(), 1.0),
+        ];
+        let mut credits = BTreeMap::new();
+        let picks: Vec<String> = (0..8)
+            .map(|_| weighted_round_robin(&mut credits, &weights).unwrap())
+            .collect();
+        assert_eq!(picks.iter().filter(|m| *m == "sonnet").count(), 6);
+        assert_eq!(picks.iter().filter(|m| *m == "opus").count(), 2);
+        assert_ne!(picks[0..2], picks[2..4], "picks should interleave");
+        assert!(weighted_round_robin(&mut credits, &weights[..1]).is_none());
+    }
+
+    #[test]
+    fn adaptive_select_for_task_reads_journal_and_respects_hints() {
+        let progress_str = temp_progress_db("adaptive");
+        let db = dag::open_db(&progress_str).unwrap();
+        for (model, outcome) in [("opus", "done"), ("opus", "done"), ("haiku", "failed")] {
+            db.conn()
+                .execute(
+                    "INSERT INTO journal (run_id, iteration, outcome, model, created_at)
+                     VALUES ('run-1', 1, ?1, ?2, '2026-01-01T00:00:00Z')",
+                    rusqlite::params![outcome, model],
+                )
+                .unwrap();
+        }
+        let loaded = load_model_stats(&db);
+        assert_eq!(loaded["opus"], stats(2, 2));
+        assert_eq!(loaded["haiku"], stats(1, 0));
+
+        let task = dag::create_task(&db, "Design cache", None, None, 0).unwrap();
+        let mut config = adaptive_config("design = { opus = 4.0 }");
+        assert_eq!(
+            select_for_task(&mut config, &db, &task).as_deref(),
+            Some("opus")
+        );
+
+        config.model_hinted = true;
+        assert_eq!(select_for_task(&mut config, &db, &task), None);
+        let mut fixed = fixed_config("haiku");
+        assert_eq!(select_for_task(&mut fixed, &db, &task), None);
+
+        let _ = std::fs::remove_file(&progress_str);
+    }
 }
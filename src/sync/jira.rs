@@ -0,0 +1,206 @@
+//! Jira Cloud tracker (`[sync.jira]`), over the REST v3 API.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::{ExternalIssue, StatusMap, Tracker};
+
+/// `[sync.jira]` section.
+#[derive(Debug, Clone, Deserialize)]
+pub struct JiraConfig {
+    /// Site URL, e.g. `https://acme.atlassian.net`.
+    pub base_url: String,
+    /// Account email the API token belongs to.
+    pub email: String,
+    /// Issues to import, e.g. `project = ENG AND statusCategory != Done`.
+    pub jql: String,
+    /// Environment variable holding the API token.
+    #[serde(default = "default_api_token_env")]
+    pub api_token_env: String,
+    /// Transition (or target status) names to apply.
+    #[serde(default)]
+    pub transitions: StatusMap,
+}
+
+fn default_api_token_env() -> String {
+    "JIRA_API_TOKEN".to_string()
+}
+
+pub struct Jira {
+    config: JiraConfig,
+    auth: String,
+    agent: ureq::Agent,
+}
+
+impl Jira {
+    pub fn new(config: &JiraConfig) -> Result<Self> {
+        let token = super::token_from_env(&config.api_token_env)?;
+        Ok(Self {
+            auth: format!(
+                "Basic {}",
+                crate::base64::encode(format!("{}:{}", config.email, token).as_bytes())
+            ),
+            config: config.clone(),
+            agent: super::http_agent(),
+        })
+    }
+
+    /// Every issue matching `jql`, following pagination.
+    fn search(&self, jql: &str) -> Result<Vec<ExternalIssue>> {
+        let mut issues = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut request = self
+                .agent
+                .get(self.url("search/jql"))
+                .header("Authorization", &self.auth)
+                .header("Accept", "application/json")
+                .query("jql", jql)
+                .query("fields", "summary,description,priority")
+                .query("maxResults", "100");
+            if let Some(ref token) = page_token {
+                request = request.query("nextPageToken", token);
+            }
+            let page: Value = request
+                .call()
+                .context("Jira search failed")?
+                .body_mut()
+                .read_json()?;
+            for issue in page["issues"].as_array().into_iter().flatten() {
+                let key = issue["key"].as_str().unwrap_or_default().to_string();
+                let fields = &issue["fields"];
+                issues.push(ExternalIssue {
+                    url: Some(format!(
+                        "{}/browse/{}",
+                        self.config.base_url.trim_end_matches('/'),
+                        key
+                    )),
+                    key,
+                    title: fields["summary"].as_str().unwrap_or_default().to_string(),
+                    description: adf_text(&fields["description"]).trim().to_string(),
+                    priority: priority(fields["priority"]["name"].as_str().unwrap_or_default()),
+                });
+            }
+            match page["nextPageToken"].as_str() {
+                Some(token) if page["isLast"].as_bool() != Some(true) => {
+                    page_token = Some(token.to_string())
+                }
+                _ => break,
+            }
+        }
+        Ok(issues)
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!(
+            "{}/rest/api/3/{}",
+            self.config.base_url.trim_end_matches('/'),
+            path
+        )
+    }
+}
+
+impl Tracker for Jira {
+    fn name(&self) -> &'static str {
+        "jira"
+    }
+
+    fn fetch_issues(&self) -> Result<Vec<ExternalIssue>> {
+        self.search(&self.config.jql)
+    }
+
+    fn closed_issues(&self, keys: &[String]) -> Result<Vec<String>> {
+        let mut closed = Vec::new();
+        for chunk in keys.chunks(100) {
+            let jql = format!("key in ({}) AND statusCategory = Done", chunk.join(", "));
+            closed.extend(self.search(&jql)?.into_iter().map(|issue| issue.key));
+        }
+        Ok(closed)
+    }
+
+    fn set_state(&self, key: &str, state: &str) -> Result<()> {
+        let url = self.url(&format!("issue/{key}/transitions"));
+        let available: Value = self
+            .agent
+            .get(&url)
+            .header("Authorization", &self.auth)
+            .header("Accept", "application/json")
+            .call()
+            .with_context(|| format!("Failed to list transitions for {key}"))?
+            .body_mut()
+            .read_json()?;
+        let matches = |v: &Value| {
+            v.as_str()
+                .is_some_and(|name| name.eq_ignore_ascii_case(state))
+        };
+        let transition_id = available["transitions"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|t| matches(&t["name"]) || matches(&t["to"]["name"]))
+            .and_then(|t| t["id"].as_str())
+            .with_context(|| format!("{key} has no transition to '{state}'"))?
+            .to_string();
+        self.agent
+            .post(&url)
+            .header("Authorization", &self.auth)
+            .send_json(json!({ "transition": { "id": transition_id } }))
+            .with_context(|| format!("Failed to transition {key}"))?;
+        Ok(())
+    }
+}
+
+/// Map Jira's default priority names to ralph's (lower = sooner).
+fn priority(name: &str) -> i32 {
+    match name.to_ascii_lowercase().as_str() {
+        "highest" | "blocker" => 0,
+        "high" | "critical" => 1,
+        "low" | "minor" => 3,
+        "lowest" | "trivial" => 4,
+        _ => 2,
+    }
+}
+
+/// Plain text of an Atlassian Document Format value: text nodes joined, with
+/// a newline after each block.
+fn adf_text(node: &Value) -> String {
+    let mut out = String::new();
+    if let Some(text) = node["text"].as_str() {
+        out.push_str(text);
+    }
+    for child in node["content"].as_array().into_iter().flatten() {
+        out.push_str(&adf_text(child));
+    }
+    if matches!(
+        node["type"].as_str(),
+        Some("paragraph" | "heading" | "listItem" | "codeBlock" | "hardBreak")
+    ) {
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adf_description_and_priority() {
+        let doc = json!({
+            "type": "doc",
+            "content": [
+                { "type": "paragraph", "content": [
+                    { "type": "text", "text": "Sessions " },
+                    { "type": "text", "text": "expire." }
+                ]},
+                { "type": "paragraph", "content": [{ "type": "text", "text": "Repro below." }] }
+            ]
+        });
+        assert_eq!(adf_text(&doc), "Sessions expire.\nRepro below.\n");
+        assert_eq!(adf_text(&Value::Null), "");
+        assert_eq!(priority("Highest"), 0);
+        assert_eq!(priority("Medium"), 2);
+        assert_eq!(priority("custom"), 2);
+    }
+}
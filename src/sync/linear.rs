@@ -0,0 +1,228 @@
+//! Linear tracker (`[sync.linear]`), over the GraphQL API.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::cell::RefCell;
+
+use super::{ExternalIssue, StatusMap, Tracker};
+
+const API_URL: &str = "https://api.linear.app/graphql";
+
+/// `[sync.linear]` section.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LinearConfig {
+    /// Team key whose issues are imported, e.g. `ENG`.
+    pub team: String,
+    /// Only import issues in this project.
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Only import issues carrying this label.
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Environment variable holding the personal API key.
+    #[serde(default = "default_api_key_env")]
+    pub api_key_env: String,
+    /// Workflow state names to move issues to.
+    #[serde(default)]
+    pub states: StatusMap,
+}
+
+fn default_api_key_env() -> String {
+    "LINEAR_API_KEY".to_string()
+}
+
+pub struct Linear {
+    config: LinearConfig,
+    api_key: String,
+    agent: ureq::Agent,
+    /// The team's workflow states as `(name, id)`, fetched on first push.
+    states: RefCell<Option<Vec<(String, String)>>>,
+}
+
+impl Linear {
+    pub fn new(config: &LinearConfig) -> Result<Self> {
+        Ok(Self {
+            api_key: super::token_from_env(&config.api_key_env)?,
+            config: config.clone(),
+            agent: super::http_agent(),
+            states: RefCell::new(None),
+        })
+    }
+
+    /// Run a GraphQL request and return its `data`.
+    fn query(&self, query: &str, variables: Value) -> Result<Value> {
+        let mut response: Value = self
+            .agent
+            .post(API_URL)
+            .header("Authorization", &self.api_key)
+            .send_json(json!({ "query": query, "variables": variables }))
+            .context("Linear API request failed")?
+            .body_mut()
+            .read_json()?;
+        if let Some(errors) = response.get("errors").and_then(Value::as_array) {
+            let messages: Vec<&str> = errors
+                .iter()
+                .filter_map(|e| e["message"].as_str())
+                .collect();
+            bail!("Linear API error: {}", messages.join("; "));
+        }
+        Ok(response["data"].take())
+    }
+
+    /// Issue filter for the configured team, project and label.
+    fn filter(&self) -> Value {
+        let mut filter = json!({
+            "team": { "key": { "eq": self.config.team } },
+            "state": { "type": { "in": ["backlog", "unstarted", "started"] } },
+        });
+        if let Some(ref project) = self.config.project {
+            filter["project"] = json!({ "name": { "eq": project } });
+        }
+        if let Some(ref label) = self.config.label {
+            filter["labels"] = json!({ "name": { "eq": label } });
+        }
+        filter
+    }
+
+    /// Id of the team workflow state named `state`. The states are fetched
+    /// once per sync.
+    fn state_id(&self, state: &str) -> Result<String> {
+        if self.states.borrow().is_none() {
+            let data = self.query(
+                "query($team: String!) {
+                    workflowStates(filter: { team: { key: { eq: $team } } }) { nodes { id name } }
+                }",
+                json!({ "team": self.config.team }),
+            )?;
+            let states = data["workflowStates"]["nodes"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|n| {
+                    Some((
+                        n["name"].as_str()?.to_string(),
+                        n["id"].as_str()?.to_string(),
+                    ))
+                })
+                .collect();
+            *self.states.borrow_mut() = Some(states);
+        }
+        self.states
+            .borrow()
+            .iter()
+            .flatten()
+            .find(|(name, _)| name.eq_ignore_ascii_case(state))
+            .map(|(_, id)| id.clone())
+            .with_context(|| {
+                format!(
+                    "Team {} has no workflow state '{}'",
+                    self.config.team, state
+                )
+            })
+    }
+}
+
+impl Tracker for Linear {
+    fn name(&self) -> &'static str {
+        "linear"
+    }
+
+    fn fetch_issues(&self) -> Result<Vec<ExternalIssue>> {
+        const QUERY: &str = "query($filter: IssueFilter, $after: String) {
+            issues(first: 100, after: $after, filter: $filter) {
+                nodes { identifier title description priority url }
+                pageInfo { hasNextPage endCursor }
+            }
+        }";
+        let mut issues = Vec::new();
+        let mut after = Value::Null;
+        loop {
+            let data = self.query(QUERY, json!({ "filter": self.filter(), "after": after }))?;
+            let page = &data["issues"];
+            for node in page["nodes"].as_array().into_iter().flatten() {
+                issues.push(ExternalIssue {
+                    key: node["identifier"].as_str().unwrap_or_default().to_string(),
+                    title: node["title"].as_str().unwrap_or_default().to_string(),
+                    description: node["description"].as_str().unwrap_or_default().to_string(),
+                    priority: priority(node["priority"].as_i64().unwrap_or(0)),
+                    url: node["url"].as_str().map(str::to_string),
+                });
+            }
+            if page["pageInfo"]["hasNextPage"].as_bool() != Some(true) {
+                break;
+            }
+            after = page["pageInfo"]["endCursor"].clone();
+        }
+        Ok(issues)
+    }
+
+    fn closed_issues(&self, keys: &[String]) -> Result<Vec<String>> {
+        const QUERY: &str = "query($filter: IssueFilter) {
+            issues(first: 100, filter: $filter) { nodes { identifier } }
+        }";
+        let prefix = format!("{}-", self.config.team);
+        let numbers: Vec<i64> = keys
+            .iter()
+            .filter_map(|k| k.strip_prefix(&prefix)?.parse().ok())
+            .collect();
+        let mut closed = Vec::new();
+        for chunk in numbers.chunks(100) {
+            let filter = json!({
+                "team": { "key": { "eq": self.config.team } },
+                "number": { "in": chunk },
+                "state": { "type": { "in": ["completed", "canceled"] } },
+            });
+            let data = self.query(QUERY, json!({ "filter": filter }))?;
+            closed.extend(
+                data["issues"]["nodes"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|n| n["identifier"].as_str().map(str::to_string)),
+            );
+        }
+        Ok(closed)
+    }
+
+    fn set_state(&self, key: &str, state: &str) -> Result<()> {
+        let state_id = self.state_id(state)?;
+        let data = self.query(
+            "mutation($id: String!, $stateId: String!) {
+                issueUpdate(id: $id, input: { stateId: $stateId }) { success }
+            }",
+            json!({ "id": key, "stateId": state_id }),
+        )?;
+        if data["issueUpdate"]["success"].as_bool() != Some(true) {
+            bail!("Linear did not update {}", key);
+        }
+        Ok(())
+    }
+}
+
+/// Map Linear priority (0 none, 1 urgent .. 4 low) to ralph's (lower = sooner).
+fn priority(linear: i64) -> i32 {
+    match linear {
+        1..=4 => linear as i32 - 1,
+        _ => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_defaults_and_priority_mapping() {
+        let config: LinearConfig =
+            toml::from_str("team = \"ENG\"\n[states]\nfailed = \"Canceled\"").unwrap();
+        assert_eq!(config.api_key_env, "LINEAR_API_KEY");
+        assert_eq!(config.states.state_for("in_progress"), Some("In Progress"));
+        assert_eq!(config.states.state_for("failed"), Some("Canceled"));
+        assert_eq!(config.states.state_for("pending"), None);
+
+        assert_eq!(priority(1), 0);
+        assert_eq!(priority(4), 3);
+        assert_eq!(priority(0), 2);
+    }
+}
@@ -0,0 +1,459 @@
+//! Two-way sync between standalone tasks and an external issue tracker
+//! (`ralph sync linear`, `ralph sync jira`).
+//!
+//! Import creates one standalone task per open issue and stores the issue key
+//! in `tasks.source_ref` (`linear:ENG-42`, `jira:PROJ-7`), so re-running the
+//! sync never duplicates a task. It also archives imported tasks that were not
+//! started yet when their issue was closed on the tracker. Push walks imported tasks whose status moved
+//! to `in_progress`, `done` or `failed` since the last sync and moves the issue
+//! to the state configured for that status. `tasks.source_synced_status`
+//! remembers what was last pushed.
+
+pub mod jira;
+pub mod linear;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::dag::{self, CreateTaskParams, Db};
+
+/// How long one tracker API request may take.
+const HTTP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// `[sync]` section of `.ralph.toml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SyncConfig {
+    #[serde(default)]
+    pub linear: Option<linear::LinearConfig>,
+    #[serde(default)]
+    pub jira: Option<jira::JiraConfig>,
+}
+
+/// Tracker state (Linear) or transition (Jira) to push for each task status.
+/// A status without an entry is not pushed.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StatusMap {
+    #[serde(default = "default_in_progress")]
+    pub in_progress: Option<String>,
+    #[serde(default = "default_done")]
+    pub done: Option<String>,
+    #[serde(default)]
+    pub failed: Option<String>,
+}
+
+impl Default for StatusMap {
+    fn default() -> Self {
+        Self {
+            in_progress: default_in_progress(),
+            done: default_done(),
+            failed: None,
+        }
+    }
+}
+
+impl StatusMap {
+    /// Tracker state for a ralph task status, if it is pushed.
+    pub fn state_for(&self, status: &str) -> Option<&str> {
+        match status {
+            "in_progress" => self.in_progress.as_deref(),
+            "done" => self.done.as_deref(),
+            "failed" => self.failed.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+fn default_in_progress() -> Option<String> {
+    Some("In Progress".to_string())
+}
+
+fn default_done() -> Option<String> {
+    Some("Done".to_string())
+}
+
+/// An open issue fetched from a tracker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalIssue {
+    /// Tracker key, e.g. `ENG-42`.
+    pub key: String,
+    pub title: String,
+    pub description: String,
+    /// Ralph priority (lower = more urgent).
+    pub priority: i32,
+    pub url: Option<String>,
+}
+
+/// A remote issue tracker.
+pub trait Tracker {
+    /// Prefix for `source_ref` values, e.g. `linear`.
+    fn name(&self) -> &'static str;
+    /// Issues that should exist as tasks.
+    fn fetch_issues(&self) -> Result<Vec<ExternalIssue>>;
+    /// The keys among `keys` whose issue is closed (completed or canceled).
+    fn closed_issues(&self, keys: &[String]) -> Result<Vec<String>>;
+    /// Move issue `key` to the named state or transition.
+    fn set_state(&self, key: &str, state: &str) -> Result<()>;
+}
+
+/// Which directions to sync.
+#[derive(Debug, Clone, Copy)]
+pub struct SyncOptions<'a> {
+    pub import: bool,
+    pub push: bool,
+    /// Report what would change without writing to the database or tracker.
+    pub dry_run: bool,
+    /// `max_retries` for imported tasks.
+    pub max_retries: i32,
+    /// `created_by` for imported tasks.
+    pub created_by: Option<&'a str>,
+}
+
+/// What a sync did (or, with `dry_run`, would do).
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    /// `(issue key, task id)`; the task id is empty on a dry run.
+    pub imported: Vec<(String, String)>,
+    /// `(issue key, task id)` of tasks archived because their issue was closed.
+    pub closed: Vec<(String, String)>,
+    /// `(issue key, task status, tracker state)`.
+    pub pushed: Vec<(String, String, String)>,
+    /// Per-issue push failures. They are retried on the next sync.
+    pub errors: Vec<String>,
+}
+
+/// Import open issues and push task status changes for one tracker.
+pub fn sync(
+    db: &Db,
+    tracker: &dyn Tracker,
+    statuses: &StatusMap,
+    options: SyncOptions<'_>,
+) -> Result<SyncReport> {
+    let mut report = SyncReport::default();
+    if options.import {
+        for issue in tracker.fetch_issues()? {
+            let source_ref = format!("{}:{}", tracker.name(), issue.key);
+            if task_for_source_ref(db, &source_ref)?.is_some() {
+                continue;
+            }
+            let task_id = if options.dry_run {
+                String::new()
+            } else {
+                import_issue(db, &issue, &source_ref, options)?
+            };
+            report.imported.push((issue.key, task_id));
+        }
+        close_finished_issues(db, tracker, options, &mut report)?;
+    }
+    if options.push {
+        for (task_id, status, source_ref) in pending_pushes(db, tracker.name())? {
+            let key = issue_key(tracker, &source_ref).to_string();
+            let Some(state) = statuses.state_for(&status) else {
+                // Unmapped statuses are never pushed; record them as synced
+                // so they are not selected again on every run
+                if !options.dry_run {
+                    mark_synced(db, &task_id, &status)?;
+                }
+                continue;
+            };
+            if !options.dry_run {
+                if let Err(e) = tracker.set_state(&key, state) {
+                    report.errors.push(format!("{key}: {e:#}"));
+                    continue;
+                }
+                mark_synced(db, &task_id, &status)?;
+                dag::add_log(
+                    db,
+                    &task_id,
+                    &format!("Pushed {status} to {source_ref} ({state})"),
+                )?;
+            }
+            report.pushed.push((key, status, state.to_string()));
+        }
+    }
+    Ok(report)
+}
+
+/// Archive linked tasks that are still pending or blocked when their issue has
+/// been closed on the tracker. Started and finished tasks are left alone.
+fn close_finished_issues(
+    db: &Db,
+    tracker: &dyn Tracker,
+    options: SyncOptions<'_>,
+    report: &mut SyncReport,
+) -> Result<()> {
+    let mut stmt = db.conn().prepare(
+        "SELECT id, source_ref FROM tasks
+         WHERE source_ref LIKE ? || ':%'
+           AND status IN ('pending', 'blocked')
+           AND archived_at IS NULL",
+    )?;
+    let linked: Vec<(String, String)> = stmt
+        .query_map([tracker.name()], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    if linked.is_empty() {
+        return Ok(());
+    }
+    let keys: Vec<String> = linked
+        .iter()
+        .map(|(_, source_ref)| issue_key(tracker, source_ref).to_string())
+        .collect();
+    let closed = tracker.closed_issues(&keys)?;
+    for ((task_id, source_ref), key) in linked.into_iter().zip(keys) {
+        if !closed.contains(&key) {
+            continue;
+        }
+        if !options.dry_run {
+            let timestamp = chrono::Utc::now().to_rfc3339();
+            db.conn().execute(
+                "UPDATE tasks SET archived_at = ?1, updated_at = ?1 WHERE id = ?2",
+                rusqlite::params![timestamp, task_id],
+            )?;
+            dag::add_log(db, &task_id, &format!("Archived: {source_ref} was closed"))?;
+        }
+        report.closed.push((key, task_id));
+    }
+    Ok(())
+}
+
+/// The tracker key of a `source_ref`, e.g. `ENG-42` for `linear:ENG-42`.
+fn issue_key<'a>(tracker: &dyn Tracker, source_ref: &'a str) -> &'a str {
+    source_ref
+        .strip_prefix(tracker.name())
+        .and_then(|rest| rest.strip_prefix(':'))
+        .unwrap_or(source_ref)
+}
+
+fn mark_synced(db: &Db, task_id: &str, status: &str) -> Result<()> {
+    db.conn().execute(
+        "UPDATE tasks SET source_synced_status = ? WHERE id = ?",
+        rusqlite::params![status, task_id],
+    )?;
+    Ok(())
+}
+
+/// Task already linked to `source_ref`, archived tasks included.
+fn task_for_source_ref(db: &Db, source_ref: &str) -> Result<Option<String>> {
+    let mut stmt = db
+        .conn()
+        .prepare("SELECT id FROM tasks WHERE source_ref = ?")?;
+    let mut rows = stmt.query([source_ref])?;
+    Ok(match rows.next()? {
+        Some(row) => Some(row.get(0)?),
+        None => None,
+    })
+}
+
+fn import_issue(
+    db: &Db,
+    issue: &ExternalIssue,
+    source_ref: &str,
+    options: SyncOptions<'_>,
+) -> Result<String> {
+    let mut description = issue.description.trim().to_string();
+    if let Some(ref url) = issue.url {
+        if !description.is_empty() {
+            description.push_str("\n\n");
+        }
+        description.push_str(&format!("Source: {url}"));
+    }
+    let task = dag::create_task_with_feature(
+        db,
+        CreateTaskParams {
+            title: &issue.title,
+            description: Some(description.as_str()).filter(|d| !d.is_empty()),
+            parent_id: None,
+            priority: issue.priority,
+            feature_id: None,
+            task_type: "standalone",
+            max_retries: options.max_retries,
+            criteria: &[],
+            discovered_by: None,
+            cwd: None,
+            created_by: options.created_by,
+        },
+    )?;
+    // The issue is already open on the tracker, so the initial status is not pushed.
+    db.conn()
+        .execute(
+            "UPDATE tasks SET source_ref = ?, source_synced_status = 'pending' WHERE id = ?",
+            rusqlite::params![source_ref, task.id],
+        )
+        .with_context(|| format!("Failed to link {} to {}", task.id, source_ref))?;
+    dag::add_log(db, &task.id, &format!("Imported from {source_ref}"))?;
+    Ok(task.id)
+}
+
+/// `(task id, status, source_ref)` of linked tasks whose status changed since
+/// the last push.
+fn pending_pushes(db: &Db, tracker: &str) -> Result<Vec<(String, String, String)>> {
+    let mut stmt = db.conn().prepare(
+        "SELECT id, status, source_ref FROM tasks
+         WHERE source_ref LIKE ? || ':%'
+           AND status IN ('in_progress', 'done', 'failed')
+           AND status IS NOT source_synced_status
+         ORDER BY updated_at ASC",
+    )?;
+    let rows = stmt.query_map([tracker], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+/// Read a tracker credential from the environment variable named in config.
+fn token_from_env(var: &str) -> Result<String> {
+    std::env::var(var)
+        .ok()
+        .filter(|t| !t.trim().is_empty())
+        .with_context(|| format!("Set {var} to your API token"))
+}
+
+fn http_agent() -> ureq::Agent {
+    ureq::Agent::config_builder()
+        .timeout_global(Some(HTTP_TIMEOUT))
+        .build()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag::init_db;
+    use std::cell::RefCell;
+    use tempfile::TempDir;
+
+    struct FakeTracker {
+        issues: Vec<ExternalIssue>,
+        closed: Vec<String>,
+        pushed: RefCell<Vec<(String, String)>>,
+    }
+
+    impl Tracker for FakeTracker {
+        fn name(&self) -> &'static str {
+            "fake"
+        }
+        fn fetch_issues(&self) -> Result<Vec<ExternalIssue>> {
+            Ok(self.issues.clone())
+        }
+        fn closed_issues(&self, keys: &[String]) -> Result<Vec<String>> {
+            Ok(keys
+                .iter()
+                .filter(|k| self.closed.contains(k))
+                .cloned()
+                .collect())
+        }
+        fn set_state(&self, key: &str, state: &str) -> Result<()> {
+            self.pushed
+                .borrow_mut()
+                .push((key.to_string(), state.to_string()));
+            Ok(())
+        }
+    }
+
+    fn options(dry_run: bool) -> SyncOptions<'static> {
+        SyncOptions {
+            import: true,
+            push: true,
+            dry_run,
+            max_retries: 3,
+            created_by: Some("alice"),
+        }
+    }
+
+    fn issue(key: &str) -> ExternalIssue {
+        ExternalIssue {
+            key: key.to_string(),
+            title: "Fix login".to_string(),
+            description: "Sessions expire early.".to_string(),
+            priority: 1,
+            url: Some(format!("https://tracker/{key}")),
+        }
+    }
+
+    fn synced_status(db: &Db, task_id: &str) -> Option<String> {
+        db.conn()
+            .query_row(
+                "SELECT source_synced_status FROM tasks WHERE id = ?",
+                [task_id],
+                |row| row.get(0),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn imports_once_and_pushes_each_transition_once() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let tracker = FakeTracker {
+            issues: vec![issue("ENG-1")],
+            closed: Vec::new(),
+            pushed: RefCell::new(Vec::new()),
+        };
+        let statuses = StatusMap::default();
+
+        let dry = sync(&db, &tracker, &statuses, options(true)).unwrap();
+        assert_eq!(dry.imported.len(), 1);
+        assert!(dag::get_all_tasks(&db).unwrap().is_empty());
+
+        let report = sync(&db, &tracker, &statuses, options(false)).unwrap();
+        let task = dag::get_task(&db, &report.imported[0].1).unwrap();
+        assert_eq!(task.source_ref.as_deref(), Some("fake:ENG-1"));
+        assert_eq!(task.task_type, "standalone");
+        assert_eq!(task.priority, 1);
+        assert_eq!(task.created_by.as_deref(), Some("alice"));
+        assert!(task.description.ends_with("Source: https://tracker/ENG-1"));
+        assert!(report.pushed.is_empty());
+
+        let again = sync(&db, &tracker, &statuses, options(false)).unwrap();
+        assert!(again.imported.is_empty());
+
+        dag::set_task_status(db.conn(), &task.id, "in_progress").unwrap();
+        sync(&db, &tracker, &statuses, options(false)).unwrap();
+        sync(&db, &tracker, &statuses, options(false)).unwrap();
+        dag::set_task_status(db.conn(), &task.id, "failed").unwrap();
+        sync(&db, &tracker, &statuses, options(false)).unwrap();
+        assert_eq!(
+            *tracker.pushed.borrow(),
+            vec![("ENG-1".to_string(), "In Progress".to_string())]
+        );
+        // The unmapped `failed` is recorded as synced rather than reselected
+        assert_eq!(synced_status(&db, &task.id).as_deref(), Some("failed"));
+    }
+
+    #[test]
+    fn archives_unstarted_tasks_whose_issue_closed() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let mut tracker = FakeTracker {
+            issues: vec![issue("ENG-1"), issue("ENG-2"), issue("ENG-3")],
+            closed: Vec::new(),
+            pushed: RefCell::new(Vec::new()),
+        };
+        let statuses = StatusMap::default();
+        let report = sync(&db, &tracker, &statuses, options(false)).unwrap();
+        let ids: Vec<String> = report.imported.into_iter().map(|(_, id)| id).collect();
+        dag::set_task_status(db.conn(), &ids[1], "in_progress").unwrap();
+
+        tracker.issues.clear();
+        tracker.closed = vec!["ENG-1".to_string(), "ENG-2".to_string()];
+        let dry = sync(&db, &tracker, &statuses, options(true)).unwrap();
+        assert_eq!(dry.closed, vec![("ENG-1".to_string(), ids[0].clone())]);
+
+        let report = sync(&db, &tracker, &statuses, options(false)).unwrap();
+        assert_eq!(report.closed, vec![("ENG-1".to_string(), ids[0].clone())]);
+        let archived = |id: &str| -> bool {
+            db.conn()
+                .query_row(
+                    "SELECT archived_at IS NOT NULL FROM tasks WHERE id = ?",
+                    [id],
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+        // The started task and the still-open issue keep their tasks
+        assert!(archived(&ids[0]));
+        assert!(!archived(&ids[1]));
+        assert!(!archived(&ids[2]));
+        assert!(sync(&db, &tracker, &statuses, options(false))
+            .unwrap()
+            .closed
+            .is_empty());
+    }
+}
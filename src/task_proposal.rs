@@ -0,0 +1,918 @@
+//! Proposed task DAGs for Phase 3 of `feature create`.
+//!
+//! The planning agent does not write tasks itself. It describes the DAG as
+//! JSON inside a `<task-dag>` sigil; ralph validates it, previews the tree and
+//! asks the user to accept it, request changes, or reject it. Only an accepted
+//! proposal is inserted, in one transaction.
+//!
+//! `feature replan` uses the same format against an existing DAG: proposed
+//! tasks that carry the `id` of an existing task update it, and
+//! [`reconcile`] applies the difference.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+use crate::dag::{self, Db, Task};
+use crate::input;
+
+const START_TAG: &str = "<task-dag>";
+const END_TAG: &str = "</task-dag>";
+
+/// Invalid proposals sent back to the agent before `feature create` gives up.
+pub const MAX_INVALID_PROPOSALS: u32 = 2;
+
+const CHOICE_ACCEPT: &str = "Accept";
+const CHOICE_EDIT: &str = "Request changes";
+const CHOICE_REJECT: &str = "Reject";
+
+/// One proposed task and its subtree.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProposedTask {
+    /// ID of the existing task this one updates (re-planning only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Short name other tasks use in `depends_on`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub criteria: Vec<String>,
+    /// Keys of tasks that must finish before this one starts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// Working directory relative to the project root; children inherit it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Anchor of the plan section the task was derived from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan_ref: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ProposedTask>,
+}
+
+impl ProposedTask {
+    /// The trimmed `plan_ref`, `None` when missing or blank.
+    fn plan_ref(&self) -> Option<&str> {
+        self.plan_ref
+            .as_deref()
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+    }
+}
+
+/// A proposed DAG: the tasks directly under the feature's root task.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProposedDag {
+    pub tasks: Vec<ProposedTask>,
+}
+
+/// What the user decided about a proposal.
+#[derive(Debug, PartialEq)]
+pub enum Review {
+    Accept,
+    /// Ask the agent for a new proposal with this feedback.
+    Revise(String),
+    Reject,
+}
+
+impl ProposedDag {
+    /// Number of proposed tasks, at every depth.
+    pub fn task_count(&self) -> usize {
+        fn count(tasks: &[ProposedTask]) -> usize {
+            tasks.iter().map(|t| 1 + count(&t.children)).sum()
+        }
+        count(&self.tasks)
+    }
+
+    /// The proposal as pretty JSON, for handing back to the agent.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// The tree for the explorer or the terminal: one line per task, followed
+    /// by its acceptance criteria.
+    pub fn render_lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("Proposed task DAG ({} tasks)", self.task_count())];
+        render_tasks(&self.tasks, "", &mut lines);
+        lines
+    }
+
+    /// Reject structural problems the database would only partly catch:
+    /// duplicate or unknown keys, dependency cycles, and dependencies between a
+    /// task and its own ancestor (which could never become ready).
+    fn validate(&self) -> Result<()> {
+        if self.tasks.is_empty() {
+            bail!("The proposal contains no tasks");
+        }
+        let mut flat = Vec::new();
+        flatten(&self.tasks, &[], &mut flat);
+        let mut keys = HashSet::new();
+        let mut ids = HashSet::new();
+        for (task, _) in &flat {
+            if task.title.trim().is_empty() {
+                bail!("A proposed task has an empty title");
+            }
+            if let Some(key) = &task.key {
+                if !keys.insert(key.as_str()) {
+                    bail!("Duplicate task key '{key}'");
+                }
+            }
+            if let Some(id) = &task.id {
+                if !ids.insert(id.as_str()) {
+                    bail!("Task {id} appears more than once");
+                }
+            }
+            if let Some(cwd) = &task.cwd {
+                dag::normalize_cwd(cwd)?;
+            }
+        }
+        let ancestors: HashMap<&str, &Vec<&str>> = flat
+            .iter()
+            .filter_map(|(task, path)| Some((task.key.as_deref()?, path)))
+            .collect();
+        for (task, path) in &flat {
+            for dep in &task.depends_on {
+                if !keys.contains(dep.as_str()) {
+                    bail!("'{}' depends on unknown key '{dep}'", task.title);
+                }
+                let own_key = task.key.as_deref();
+                if own_key == Some(dep.as_str())
+                    || path.contains(&dep.as_str())
+                    || own_key.is_some_and(|k| ancestors[dep.as_str()].contains(&k))
+                {
+                    bail!(
+                        "'{}' depends on '{dep}', which contains it or is inside it",
+                        task.title
+                    );
+                }
+            }
+        }
+        if let Some(cycle) = find_cycle(&flat) {
+            bail!("Dependency cycle: {}", cycle.join(" -> "));
+        }
+        Ok(())
+    }
+}
+
+/// Parse and validate the last `<task-dag>` sigil in the agent's output.
+pub fn parse(text: &str) -> Result<ProposedDag> {
+    let start = text
+        .rfind(START_TAG)
+        .context("The agent did not emit a <task-dag> sigil")?
+        + START_TAG.len();
+    let end = text[start..]
+        .find(END_TAG)
+        .context("The <task-dag> sigil is not closed")?;
+    let body = text[start..start + end].trim();
+    // Agents like to wrap JSON in a code fence
+    let body = body
+        .strip_prefix("```json")
+        .or_else(|| body.strip_prefix("```"))
+        .and_then(|b| b.trim_end().strip_suffix("```"))
+        .unwrap_or(body);
+    let dag: ProposedDag =
+        serde_json::from_str(body).context("The <task-dag> sigil is not valid JSON")?;
+    dag.validate()?;
+    Ok(dag)
+}
+
+/// Insert an accepted proposal under `root_id`, all or nothing. Returns the
+/// number of tasks created.
+pub fn insert(
+    db: &Db,
+    dag: &ProposedDag,
+    root_id: &str,
+    feature_id: &str,
+    max_retries: i32,
+    created_by: Option<&str>,
+) -> Result<usize> {
+    let tx = db.conn().unchecked_transaction()?;
+    let mut ids: HashMap<String, String> = HashMap::new();
+    let mut created = Vec::new();
+    insert_tasks(
+        db,
+        &dag.tasks,
+        root_id,
+        feature_id,
+        max_retries,
+        created_by,
+        &mut ids,
+        &mut created,
+    )?;
+    for (task, id) in &created {
+        for dep in &task.depends_on {
+            dag::add_dependency(db, &ids[dep], id)?;
+        }
+    }
+    tx.commit().context("Failed to create the task DAG")?;
+    Ok(created.len())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn insert_tasks<'a>(
+    db: &Db,
+    tasks: &'a [ProposedTask],
+    parent_id: &str,
+    feature_id: &str,
+    max_retries: i32,
+    created_by: Option<&str>,
+    ids: &mut HashMap<String, String>,
+    created: &mut Vec<(&'a ProposedTask, String)>,
+) -> Result<()> {
+    for task in tasks {
+        if let Some(id) = &task.id {
+            bail!(
+                "'{}' refers to existing task {id}; only `feature replan` updates tasks",
+                task.title
+            );
+        }
+        let row = create_proposed(db, task, parent_id, feature_id, max_retries, created_by)?;
+        if let Some(key) = &task.key {
+            ids.insert(key.clone(), row.id.clone());
+        }
+        insert_tasks(
+            db,
+            &task.children,
+            &row.id,
+            feature_id,
+            max_retries,
+            created_by,
+            ids,
+            created,
+        )?;
+        created.push((task, row.id));
+    }
+    Ok(())
+}
+
+fn create_proposed(
+    db: &Db,
+    task: &ProposedTask,
+    parent_id: &str,
+    feature_id: &str,
+    max_retries: i32,
+    created_by: Option<&str>,
+) -> Result<Task> {
+    let description = (!task.description.trim().is_empty()).then_some(task.description.as_str());
+    let mut created = dag::create_task_with_feature(
+        db,
+        dag::CreateTaskParams {
+            title: task.title.trim(),
+            description,
+            parent_id: Some(parent_id),
+            priority: 0,
+            feature_id: Some(feature_id),
+            task_type: "feature",
+            max_retries,
+            criteria: &task.criteria,
+            discovered_by: None,
+            cwd: task.cwd.as_deref(),
+            created_by,
+        },
+    )?;
+    if let Some(plan_ref) = task.plan_ref() {
+        dag::set_task_plan_ref(db, &created.id, Some(plan_ref))?;
+        created.plan_ref = Some(plan_ref.to_string());
+    }
+    Ok(created)
+}
+
+/// What [`reconcile`] did to a feature's DAG.
+#[derive(Debug, Default, PartialEq)]
+pub struct ReconcileReport {
+    /// Done tasks, and in-progress tasks the proposal left out, left exactly
+    /// as they were.
+    pub kept: usize,
+    /// Unfinished tasks rewritten from the proposal.
+    pub updated: usize,
+    pub created: usize,
+    /// Unfinished tasks the proposal dropped, deleted.
+    pub removed: usize,
+}
+
+/// Apply a re-planned proposal to the DAG under `root_id`, all or nothing.
+///
+/// Done tasks never change. A proposed task with the `id` of an unfinished
+/// task updates its title, description, criteria, working directory and plan
+/// section in place (a failed task
+/// goes back to pending) and moves it under the task it is nested in; one
+/// without an `id` is created. Unfinished tasks the proposal leaves out are
+/// deleted, unless they are in progress or a task that stays is below them.
+/// Existing dependencies are kept and the proposed ones added.
+pub fn reconcile(
+    db: &Db,
+    dag: &ProposedDag,
+    root_id: &str,
+    feature_id: &str,
+    max_retries: i32,
+    created_by: Option<&str>,
+) -> Result<ReconcileReport> {
+    let existing: HashMap<String, Task> = dag::get_task_tree(db, root_id)?
+        .into_iter()
+        .filter(|t| t.id != root_id)
+        .map(|t| (t.id.clone(), t))
+        .collect();
+    let mut flat = Vec::new();
+    flatten(&dag.tasks, &[], &mut flat);
+    for (task, _) in &flat {
+        if let Some(id) = &task.id {
+            if !existing.contains_key(id) {
+                bail!(
+                    "'{}' refers to task {id}, which is not in this feature's DAG",
+                    task.title
+                );
+            }
+        }
+    }
+
+    let tx = db.conn().unchecked_transaction()?;
+    let mut report = ReconcileReport::default();
+    let mut ids: HashMap<String, String> = HashMap::new();
+    let mut placed = Vec::new();
+    apply_tasks(
+        db,
+        &dag.tasks,
+        root_id,
+        feature_id,
+        max_retries,
+        created_by,
+        &existing,
+        &mut ids,
+        &mut placed,
+        &mut report,
+    )?;
+    for (task, id) in &placed {
+        if task.depends_on.is_empty() {
+            continue;
+        }
+        let blockers: HashSet<String> = dag::get_task_blockers(db, id)?
+            .into_iter()
+            .map(|t| t.id)
+            .collect();
+        for dep in &task.depends_on {
+            if !blockers.contains(&ids[dep]) {
+                dag::add_dependency(db, &ids[dep], id)?;
+            }
+        }
+    }
+
+    // Parents after the moves; a task moved into its own subtree is cut off
+    // from the root
+    let parents: HashMap<String, Option<String>> = dag::get_task_tree(db, root_id)?
+        .into_iter()
+        .map(|t| (t.id, t.parent_id))
+        .collect();
+    if let Some((task, _)) = placed.iter().find(|(_, id)| !parents.contains_key(id)) {
+        bail!("'{}' would be moved inside its own subtree", task.title);
+    }
+    let parent_of = |id: &str| parents.get(id).cloned().flatten();
+
+    // A task stays if it is done, in progress, was proposed, or has such a
+    // task below it
+    let mut staying: HashSet<String> = placed.iter().map(|(_, id)| id.clone()).collect();
+    for task in existing.values() {
+        if task.status == "done" || (task.status == "in_progress" && !staying.contains(&task.id)) {
+            report.kept += 1;
+            staying.insert(task.id.clone());
+        }
+    }
+    for id in staying.clone() {
+        let mut parent = parent_of(&id);
+        while let Some(p) = parent.filter(|p| p != root_id) {
+            parent = parent_of(&p);
+            staying.insert(p);
+        }
+    }
+    let dropped: Vec<&Task> = existing
+        .values()
+        .filter(|t| !staying.contains(&t.id))
+        .collect();
+    report.removed = dropped.len();
+    for task in &dropped {
+        db.conn().execute(
+            "DELETE FROM dependencies WHERE blocker_id = ? OR blocked_id = ?",
+            [&task.id, &task.id],
+        )?;
+    }
+    // Deleting the top of each dropped subtree takes its children with it
+    for task in dropped.iter().filter(|t| {
+        parent_of(&t.id)
+            .as_deref()
+            .is_none_or(|p| staying.contains(p) || p == root_id)
+    }) {
+        dag::delete_task(db, &task.id)?;
+    }
+
+    refresh_parent_statuses(db, root_id)?;
+    tx.commit().context("Failed to update the task DAG")?;
+    Ok(report)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn apply_tasks<'a>(
+    db: &Db,
+    tasks: &'a [ProposedTask],
+    parent_id: &str,
+    feature_id: &str,
+    max_retries: i32,
+    created_by: Option<&str>,
+    existing: &HashMap<String, Task>,
+    ids: &mut HashMap<String, String>,
+    placed: &mut Vec<(&'a ProposedTask, String)>,
+    report: &mut ReconcileReport,
+) -> Result<()> {
+    for task in tasks {
+        let id = match task.id.as_ref().map(|id| &existing[id]) {
+            Some(current) if current.status == "done" => {
+                if task.children.iter().any(|c| c.id.is_none()) {
+                    bail!(
+                        "'{}' is done; new tasks cannot be added under it",
+                        current.title
+                    );
+                }
+                current.id.clone()
+            }
+            Some(current) => {
+                dag::update_task(
+                    db,
+                    &current.id,
+                    dag::TaskUpdate {
+                        title: Some(task.title.trim().to_string()),
+                        description: Some(task.description.trim().to_string()),
+                        priority: None,
+                    },
+                )?;
+                let criteria: Vec<String> = dag::get_task_criteria(db, &current.id)?
+                    .into_iter()
+                    .map(|c| c.description)
+                    .collect();
+                if criteria != task.criteria {
+                    dag::replace_criteria(db, &current.id, &task.criteria)?;
+                }
+                if dag::normalize_cwd(task.cwd.as_deref().unwrap_or(""))? != current.cwd {
+                    dag::set_task_cwd(db, &current.id, task.cwd.as_deref())?;
+                }
+                if task.plan_ref() != current.plan_ref.as_deref() {
+                    dag::set_task_plan_ref(db, &current.id, task.plan_ref())?;
+                }
+                if current.status == "failed" {
+                    dag::force_reset_task(db.conn(), &current.id)?;
+                }
+                if current.parent_id.as_deref() != Some(parent_id) {
+                    if let Some(parent) = existing.get(parent_id).filter(|p| p.status == "done") {
+                        bail!(
+                            "'{}' is done; '{}' cannot be moved under it",
+                            parent.title,
+                            current.title
+                        );
+                    }
+                    dag::set_task_parent(db, &current.id, parent_id)?;
+                }
+                report.updated += 1;
+                current.id.clone()
+            }
+            None => {
+                report.created += 1;
+                create_proposed(db, task, parent_id, feature_id, max_retries, created_by)?.id
+            }
+        };
+        if let Some(key) = &task.key {
+            ids.insert(key.clone(), id.clone());
+        }
+        apply_tasks(
+            db,
+            &task.children,
+            &id,
+            feature_id,
+            max_retries,
+            created_by,
+            existing,
+            ids,
+            placed,
+            report,
+        )?;
+        placed.push((task, id));
+    }
+    Ok(())
+}
+
+/// Re-derive the stored status of every parent under (and including)
+/// `root_id`, e.g. a done parent that gained a pending child.
+pub(crate) fn refresh_parent_statuses(db: &Db, root_id: &str) -> Result<()> {
+    let tree = dag::get_task_tree(db, root_id)?;
+    for task in &tree {
+        if !tree
+            .iter()
+            .any(|t| t.parent_id.as_deref() == Some(&task.id))
+        {
+            continue;
+        }
+        let derived = dag::compute_parent_status(db.conn(), &task.id)?;
+        if derived != task.status {
+            db.conn().execute(
+                "UPDATE tasks SET status = ?, updated_at = datetime('now') WHERE id = ?",
+                [&derived, &task.id],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Show the proposal and ask what to do with it, in the TUI when it is
+/// running and on the plain terminal otherwise.
+///
+/// `--yes` and `--no-input` accept without asking, so scripted
+/// `feature create` runs still end with a DAG.
+pub fn review(dag: &ProposedDag) -> Review {
+    let hint = format!(
+        "{} tasks proposed. Accept to create them, or start typing to ask for changes.",
+        dag.task_count()
+    );
+    review_lines(
+        "Proposed Task DAG",
+        dag.render_lines(),
+        &hint,
+        "Create these tasks?",
+    )
+}
+
+/// Show `lines` under `title` and ask to accept, request changes or reject,
+/// as [`review`] does for task DAGs. `hint` is shown in the TUI, `question`
+/// on the plain terminal.
+pub fn review_lines(title: &str, lines: Vec<String>, hint: &str, question: &str) -> Review {
+    if input::assume_yes() || input::no_input() {
+        return Review::Accept;
+    }
+
+    if crate::ui::is_active() {
+        crate::ui::show_explorer(title, lines);
+        let choice =
+            crate::ui::prompt_choice(title, hint, &[CHOICE_ACCEPT, CHOICE_EDIT, CHOICE_REJECT]);
+        return match choice {
+            Some(crate::ui::UiPromptResult::Input(text)) => match text.as_str() {
+                CHOICE_ACCEPT => Review::Accept,
+                CHOICE_REJECT => Review::Reject,
+                CHOICE_EDIT => {
+                    match crate::ui::prompt_multiline(
+                        "Request changes",
+                        "Describe what the agent should change in the DAG.",
+                    ) {
+                        Some(crate::ui::UiPromptResult::Input(text)) => revise_or_reject(text),
+                        _ => Review::Reject,
+                    }
+                }
+                _ => revise_or_reject(text),
+            },
+            _ => Review::Reject,
+        };
+    }
+
+    println!();
+    for line in &lines {
+        println!("{line}");
+    }
+    eprint!("{question} [a]ccept, [e]dit (request changes), [r]eject: ");
+    let _ = std::io::stderr().flush();
+    loop {
+        let Some(answer) = input::read_line() else {
+            eprintln!();
+            return Review::Reject;
+        };
+        match answer.trim().to_lowercase().as_str() {
+            "a" | "accept" | "y" | "yes" => return Review::Accept,
+            "r" | "reject" | "n" | "no" => return Review::Reject,
+            "e" | "edit" => {
+                eprint!("Describe the changes: ");
+                let _ = std::io::stderr().flush();
+                return input::read_line().map_or(Review::Reject, revise_or_reject);
+            }
+            _ => {
+                eprint!("Please answer a, e or r: ");
+                let _ = std::io::stderr().flush();
+            }
+        }
+    }
+}
+
+fn revise_or_reject(feedback: String) -> Review {
+    if feedback.trim().is_empty() {
+        Review::Reject
+    } else {
+        Review::Revise(feedback.trim().to_string())
+    }
+}
+
+fn render_tasks(tasks: &[ProposedTask], prefix: &str, out: &mut Vec<String>) {
+    for (i, task) in tasks.iter().enumerate() {
+        let last = i == tasks.len() - 1;
+        let connector = if last { "└─" } else { "├─" };
+        let mut line = format!("{prefix}{connector} {}", task.title);
+        if let Some(key) = &task.key {
+            line.push_str(&format!("  [{key}]"));
+        }
+        if let Some(id) = &task.id {
+            line.push_str(&format!("  ({id})"));
+        }
+        if let Some(cwd) = &task.cwd {
+            line.push_str(&format!("  in {cwd}/"));
+        }
+        if let Some(plan_ref) = &task.plan_ref {
+            line.push_str(&format!("  §{plan_ref}"));
+        }
+        if !task.depends_on.is_empty() {
+            line.push_str(&format!("  after: {}", task.depends_on.join(", ")));
+        }
+        out.push(line);
+        let child_prefix = format!("{prefix}{}", if last { "   " } else { "│  " });
+        for criterion in &task.criteria {
+            out.push(format!("{child_prefix}  · {criterion}"));
+        }
+        render_tasks(&task.children, &child_prefix, out);
+    }
+}
+
+/// Every task with the keys of its ancestors.
+fn flatten<'a>(
+    tasks: &'a [ProposedTask],
+    path: &[&'a str],
+    out: &mut Vec<(&'a ProposedTask, Vec<&'a str>)>,
+) {
+    for task in tasks {
+        out.push((task, path.to_vec()));
+        let mut child_path = path.to_vec();
+        child_path.extend(task.key.as_deref());
+        flatten(&task.children, &child_path, out);
+    }
+}
+
+/// A dependency cycle among keyed tasks, as the keys along it.
+fn find_cycle(flat: &[(&ProposedTask, Vec<&str>)]) -> Option<Vec<String>> {
+    let deps: HashMap<&str, Vec<&str>> = flat
+        .iter()
+        .filter_map(|(task, _)| {
+            let key = task.key.as_deref()?;
+            Some((key, task.depends_on.iter().map(String::as_str).collect()))
+        })
+        .collect();
+
+    fn visit<'a>(
+        key: &'a str,
+        deps: &HashMap<&'a str, Vec<&'a str>>,
+        stack: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+    ) -> Option<Vec<String>> {
+        if let Some(pos) = stack.iter().position(|k| *k == key) {
+            let mut cycle: Vec<String> = stack[pos..].iter().map(|k| k.to_string()).collect();
+            cycle.push(key.to_string());
+            return Some(cycle);
+        }
+        if !done.insert(key) {
+            return None;
+        }
+        stack.push(key);
+        for dep in deps.get(key).into_iter().flatten() {
+            if let Some(cycle) = visit(dep, deps, stack, done) {
+                return Some(cycle);
+            }
+        }
+        stack.pop();
+        None
+    }
+
+    let mut done = HashSet::new();
+    let mut keys: Vec<&str> = deps.keys().copied().collect();
+    keys.sort();
+    keys.into_iter()
+        .find_map(|key| visit(key, &deps, &mut Vec::new(), &mut done))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag::init_db;
+    use crate::feature;
+    use tempfile::TempDir;
+
+    const PROPOSAL: &str = r#"Here is the DAG.
+<task-dag>
+```json
+{"tasks": [
+  {"key": "models", "title": "Data models", "cwd": "crates/db", "plan_ref": "2-storage", "children": [
+    {"key": "schema", "title": "Add schema", "description": "Tables", "criteria": ["migration applies"]},
+    {"key": "types", "title": "Add types", "depends_on": ["schema"]}
+  ]},
+  {"title": "Wire up API", "depends_on": ["models"], "criteria": ["cargo test passes"]}
+]}
+```
+</task-dag>"#;
+
+    #[test]
+    fn parses_fenced_proposal_and_renders_tree() {
+        let dag = parse(PROPOSAL).unwrap();
+        assert_eq!(dag.task_count(), 4);
+        assert_eq!(
+            dag.render_lines(),
+            vec![
+                "Proposed task DAG (4 tasks)",
+                "├─ Data models  [models]  in crates/db/  §2-storage",
+                "│  ├─ Add schema  [schema]",
+                "│  │    · migration applies",
+                "│  └─ Add types  [types]  after: schema",
+                "└─ Wire up API  after: models",
+                "     · cargo test passes",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_proposals() {
+        let err = |json: &str| {
+            parse(&format!("<task-dag>{json}</task-dag>"))
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(parse("no sigil").is_err());
+        assert!(err(r#"{"tasks": []}"#).contains("no tasks"));
+        assert!(err(r#"{"tasks": [{"title": "A", "depends_on": ["x"]}]}"#).contains("unknown key"));
+        assert!(err(
+            r#"{"tasks": [{"key": "a", "title": "A", "depends_on": ["b"]},
+                          {"key": "b", "title": "B", "depends_on": ["a"]}]}"#
+        )
+        .contains("a -> b -> a"));
+        assert!(err(r#"{"tasks": [{"key": "p", "title": "P", "children": [
+                {"key": "c", "title": "C", "depends_on": ["p"]}]}]}"#)
+        .contains("contains it"));
+        assert!(
+            err(r#"{"tasks": [{"key": "a", "title": "A"}, {"key": "a", "title": "B"}]}"#)
+                .contains("Duplicate")
+        );
+        assert!(err(r#"{"tasks": [{"title": "A", "cwd": "../other"}]}"#)
+            .contains("relative to the project root"));
+    }
+
+    #[test]
+    fn insert_creates_tree_with_criteria_and_dependencies() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let feat = feature::create_feature(&db, "api").unwrap();
+        let root = dag::create_task(&db, "Feature: api", None, None, 0).unwrap();
+
+        let created = insert(
+            &db,
+            &parse(PROPOSAL).unwrap(),
+            &root.id,
+            &feat.id,
+            3,
+            Some("alice"),
+        )
+        .unwrap();
+        assert_eq!(created, 4);
+
+        let tree = dag::get_task_tree(&db, &root.id).unwrap();
+        let id = |title: &str| tree.iter().find(|t| t.title == title).unwrap().id.clone();
+        let schema = tree.iter().find(|t| t.title == "Add schema").unwrap();
+        assert_eq!(
+            schema.parent_id.as_deref(),
+            Some(id("Data models").as_str())
+        );
+        assert_eq!(schema.feature_id.as_deref(), Some(feat.id.as_str()));
+        assert_eq!(schema.created_by.as_deref(), Some("alice"));
+        let models = tree.iter().find(|t| t.title == "Data models").unwrap();
+        assert_eq!(models.cwd.as_deref(), Some("crates/db"));
+        assert_eq!(models.plan_ref.as_deref(), Some("2-storage"));
+        assert_eq!(schema.plan_ref, None);
+        assert_eq!(dag::get_task_criteria(&db, &schema.id).unwrap().len(), 1);
+        let blockers = |title: &str| -> Vec<String> {
+            db.conn()
+                .prepare("SELECT blocker_id FROM dependencies WHERE blocked_id = ?")
+                .unwrap()
+                .query_map([id(title)], |r| r.get(0))
+                .unwrap()
+                .collect::<rusqlite::Result<_>>()
+                .unwrap()
+        };
+        assert_eq!(blockers("Add types"), vec![id("Add schema")]);
+        assert_eq!(blockers("Wire up API"), vec![id("Data models")]);
+    }
+
+    #[test]
+    fn reconcile_keeps_done_updates_pending_and_drops_the_rest() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let feat = feature::create_feature(&db, "api").unwrap();
+        let root = dag::create_task(&db, "Feature: api", None, None, 0).unwrap();
+        let task = |title: &str, parent: &str| {
+            let task = dag::create_test_task(&db, &feat.id, title, Some(parent));
+            dag::add_criteria(&db, &task.id, &["old criterion".to_string()]).unwrap();
+            task
+        };
+        let models = task("Models", &root.id);
+        let schema = task("Schema", &models.id);
+        let types = task("Types", &models.id);
+        let api = task("API", &root.id);
+        let docs = task("Docs", &root.id);
+        let search = task("Search", &root.id);
+        dag::set_task_status(db.conn(), &search.id, "in_progress").unwrap();
+        dag::add_dependency(&db, &schema.id, &types.id).unwrap();
+        dag::add_dependency(&db, &docs.id, &api.id).unwrap();
+        dag::force_complete_task(db.conn(), &schema.id).unwrap();
+        dag::force_fail_task(db.conn(), &api.id).unwrap();
+        // An attempted task the proposal drops has journal rows pointing at it
+        dag::force_fail_task(db.conn(), &docs.id).unwrap();
+        db.conn()
+            .execute(
+                "INSERT INTO journal (run_id, iteration, task_id, outcome) VALUES ('run-1', 1, ?, 'failed')",
+                [&docs.id],
+            )
+            .unwrap();
+
+        let proposal = parse(&format!(
+            r#"<task-dag>{{"tasks": [
+              {{"id": "{schema}", "key": "schema", "title": "ignored"}},
+              {{"id": "{types}", "key": "types", "title": "Types v2", "criteria": ["new criterion"], "plan_ref": " 21-types "}},
+              {{"id": "{api}", "title": "API", "criteria": ["old criterion"]}},
+              {{"title": "Cache", "depends_on": ["types", "schema"]}}
+            ]}}</task-dag>"#,
+            schema = schema.id,
+            types = types.id,
+            api = api.id,
+        ))
+        .unwrap();
+        let report = reconcile(&db, &proposal, &root.id, &feat.id, 3, None).unwrap();
+        assert_eq!(
+            report,
+            ReconcileReport {
+                kept: 2,
+                updated: 2,
+                created: 1,
+                removed: 1,
+            }
+        );
+
+        let reread = |id: &str| dag::get_task(&db, id).unwrap();
+        assert_eq!(reread(&schema.id).title, "Schema");
+        assert_eq!(reread(&schema.id).status, "done");
+        assert_eq!(reread(&types.id).title, "Types v2");
+        assert_eq!(reread(&types.id).plan_ref.as_deref(), Some("21-types"));
+        // Types was nested at the top level, so it moved out of Models
+        assert_eq!(
+            reread(&types.id).parent_id.as_deref(),
+            Some(root.id.as_str())
+        );
+        assert_eq!(
+            reread(&schema.id).parent_id.as_deref(),
+            Some(models.id.as_str())
+        );
+        assert_eq!(reread(&search.id).status, "in_progress");
+        let criteria = dag::get_task_criteria(&db, &types.id).unwrap();
+        assert_eq!(criteria.len(), 1);
+        assert_eq!(criteria[0].description, "new criterion");
+        assert_eq!(reread(&api.id).status, "pending");
+        assert!(dag::get_task(&db, &docs.id).is_err());
+        let orphaned: i64 = db
+            .conn()
+            .query_row(
+                "SELECT COUNT(*) FROM journal WHERE run_id = 'run-1' AND task_id IS NULL",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(orphaned, 1);
+        assert!(dag::get_task_blockers(&db, &api.id).unwrap().is_empty());
+
+        let tree = dag::get_task_tree(&db, &root.id).unwrap();
+        let cache = tree.iter().find(|t| t.title == "Cache").unwrap();
+        assert_eq!(cache.parent_id.as_deref(), Some(root.id.as_str()));
+        let mut blockers: Vec<String> = dag::get_task_blockers(&db, &cache.id)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.id)
+            .collect();
+        blockers.sort();
+        let mut expected = vec![schema.id.clone(), types.id.clone()];
+        expected.sort();
+        assert_eq!(blockers, expected);
+
+        let under_done = parse(&format!(
+            r#"<task-dag>{{"tasks": [
+              {{"id": "{schema}", "title": "Schema", "children": [{{"id": "{api}", "title": "API"}}]}}
+            ]}}</task-dag>"#,
+            schema = schema.id,
+            api = api.id,
+        ))
+        .unwrap();
+        let err = reconcile(&db, &under_done, &root.id, &feat.id, 3, None).unwrap_err();
+        assert!(err.to_string().contains("cannot be moved"), "{err}");
+        assert_eq!(reread(&api.id).parent_id.as_deref(), Some(root.id.as_str()));
+
+        let unknown =
+            parse(r#"<task-dag>{"tasks": [{"id": "t-nope", "title": "X"}]}</task-dag>"#).unwrap();
+        let err = reconcile(&db, &unknown, &root.id, &feat.id, 3, None).unwrap_err();
+        assert!(err.to_string().contains("not in this feature's DAG"));
+        assert!(insert(&db, &unknown, &root.id, &feat.id, 3, None).is_err());
+    }
+}
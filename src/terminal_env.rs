@@ -0,0 +1,195 @@
+//! Environment for the commands agents run in terminals.
+//!
+//! `[env]` variables from `.ralph.toml` are passed to every terminal command
+//! as they are. Secrets come from a dotenv-style file (`[secrets] file`,
+//! `.ralph/secrets.env` by default, which `ralph init` gitignores) and are
+//! passed the same way, but their values are masked in the terminal output
+//! returned to the agent and in run-log transcripts. Agents cannot read the
+//! secrets file through the file tools either.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::project::RalphConfig;
+
+/// Replacement for a secret value in captured output.
+pub const MASK: &str = "********";
+
+/// Secret values shorter than this are passed but not masked: replacing a
+/// one- or two-character string everywhere would garble all output.
+const MIN_MASKED_LEN: usize = 4;
+
+static TERMINAL_ENV: OnceLock<TerminalEnv> = OnceLock::new();
+
+/// Variables injected into agent terminals, and which values to mask.
+#[derive(Debug, Clone, Default)]
+pub struct TerminalEnv {
+    vars: Vec<(String, String)>,
+    /// Secret values to mask, longest first so a secret containing another
+    /// is replaced whole.
+    masked: Vec<String>,
+    secrets_file: Option<PathBuf>,
+}
+
+impl TerminalEnv {
+    /// Build from `[env]` and the secrets file. A missing secrets file is
+    /// fine; a malformed one is an error.
+    pub fn load(project_root: &Path, config: &RalphConfig) -> Result<Self> {
+        let mut vars: Vec<(String, String)> = config
+            .env
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        let secrets_file = project_root.join(&config.secrets.file);
+        let secrets = match std::fs::read_to_string(&secrets_file) {
+            Ok(text) => parse_dotenv(&text)
+                .with_context(|| format!("Failed to parse {}", secrets_file.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", secrets_file.display()))
+            }
+        };
+        let mut masked: Vec<String> = secrets
+            .iter()
+            .map(|(_, value)| value.clone())
+            .filter(|value| value.chars().count() >= MIN_MASKED_LEN)
+            .collect();
+        masked.sort_by_key(|value| std::cmp::Reverse(value.len()));
+        masked.dedup();
+        // Secrets win over `[env]` entries of the same name
+        vars.retain(|(name, _)| !secrets.iter().any(|(secret, _)| secret == name));
+        vars.extend(secrets);
+        Ok(Self {
+            vars,
+            masked,
+            secrets_file: Some(secrets_file),
+        })
+    }
+
+    /// `(name, value)` pairs to set on each terminal command.
+    pub fn vars(&self) -> &[(String, String)] {
+        &self.vars
+    }
+
+    /// `text` with every secret value replaced by [`MASK`].
+    pub fn mask(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for value in &self.masked {
+            if text.contains(value.as_str()) {
+                text = text.replace(value.as_str(), MASK);
+            }
+        }
+        text
+    }
+
+    /// Whether `path` (canonical) is the secrets file.
+    pub fn is_secrets_file(&self, path: &Path) -> bool {
+        self.secrets_file
+            .as_ref()
+            .and_then(|file| file.canonicalize().ok())
+            .is_some_and(|file| file == path)
+    }
+}
+
+/// Load the project's terminal environment for every session started
+/// afterwards. Later calls are ignored.
+pub fn configure(project_root: &Path, config: &RalphConfig) -> Result<()> {
+    if TERMINAL_ENV.get().is_none() {
+        let _ = TERMINAL_ENV.set(TerminalEnv::load(project_root, config)?);
+    }
+    Ok(())
+}
+
+/// The configured environment, or an empty one outside a project.
+pub fn current() -> &'static TerminalEnv {
+    static EMPTY: OnceLock<TerminalEnv> = OnceLock::new();
+    TERMINAL_ENV
+        .get()
+        .unwrap_or_else(|| EMPTY.get_or_init(TerminalEnv::default))
+}
+
+/// Parse `NAME=value` lines. Blank lines, `#` comments and a leading `export`
+/// are allowed; values may be wrapped in single or double quotes.
+fn parse_dotenv(text: &str) -> Result<Vec<(String, String)>> {
+    let mut vars = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((name, value)) = line.split_once('=') else {
+            bail!("line {}: expected NAME=value", n + 1);
+        };
+        let name = name.trim();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            bail!("line {}: invalid variable name '{name}'", n + 1);
+        }
+        let value = value.trim();
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|q| value.strip_prefix(*q)?.strip_suffix(*q))
+            .unwrap_or(value);
+        vars.push((name.to_string(), value.to_string()));
+    }
+    Ok(vars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn loads_env_and_secrets_and_masks_secret_values() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir_all(dir.path().join(".ralph")).unwrap();
+        std::fs::write(
+            dir.path().join(".ralph/secrets.env"),
+            "# test keys\nexport API_KEY=\"sk-live-abc123\"\nDATABASE_URL='postgres://u:hunter22@db/app'\nPIN=42\n",
+        )
+        .unwrap();
+        let config: RalphConfig = toml::from_str(
+            "[env]\nRUST_LOG = \"debug\"\nDATABASE_URL = \"postgres://localhost/test\"\n",
+        )
+        .unwrap();
+
+        let env = TerminalEnv::load(dir.path(), &config).unwrap();
+        let mut vars = env.vars().to_vec();
+        vars.sort();
+        assert_eq!(
+            vars,
+            vec![
+                ("API_KEY".into(), "sk-live-abc123".into()),
+                ("DATABASE_URL".into(), "postgres://u:hunter22@db/app".into()),
+                ("PIN".into(), "42".into()),
+                ("RUST_LOG".into(), "debug".into()),
+            ]
+        );
+        assert_eq!(
+            env.mask("key=sk-live-abc123 url=postgres://u:hunter22@db/app pin=42"),
+            format!("key={MASK} url={MASK} pin=42")
+        );
+        let secrets_file = dir
+            .path()
+            .join(".ralph/secrets.env")
+            .canonicalize()
+            .unwrap();
+        assert!(env.is_secrets_file(&secrets_file));
+    }
+
+    #[test]
+    fn missing_secrets_file_is_fine_but_malformed_is_not() {
+        let dir = TempDir::new().unwrap();
+        let config = RalphConfig::default();
+        let env = TerminalEnv::load(dir.path(), &config).unwrap();
+        assert!(env.vars().is_empty());
+        assert_eq!(env.mask("nothing to hide"), "nothing to hide");
+
+        std::fs::create_dir_all(dir.path().join(".ralph")).unwrap();
+        std::fs::write(dir.path().join(".ralph/secrets.env"), "JUST_A_NAME\n").unwrap();
+        let err = TerminalEnv::load(dir.path(), &config).unwrap_err();
+        assert!(format!("{err:#}").contains("line 1"));
+    }
+}
@@ -0,0 +1,273 @@
+//! Test-impact analysis: which tests a task's changes can affect.
+//!
+//! Verification normally runs the project's whole test suite. When the files
+//! a task modified map cleanly onto test targets, the verification agent is
+//! pointed at just those instead:
+//!
+//! - **Rust**: each `.rs` file belongs to the package of its nearest
+//!   `Cargo.toml`. `tests/<name>.rs` selects `--test <name>`; `src/a/b.rs`
+//!   (or `src/a/b/mod.rs`) selects tests whose path contains `a::b`. A crate
+//!   root, `build.rs` or manifest change selects the whole package.
+//! - **JS/TS**: with Jest configured (a `jest.config.*` file or a `jest` key
+//!   or dependency in `package.json`), source and test files are handed to
+//!   `jest --findRelatedTests`, which follows the import graph itself.
+//!
+//! A modified file that fits neither (a shell script, a Cargo workspace
+//! manifest, a JS file without Jest) makes the analysis give up, so the full
+//! suite runs. Documentation and `.ralph/` files are ignored.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Component, Path};
+
+/// Extensions that never affect test outcomes.
+const IGNORED_EXTENSIONS: &[&str] = &["md", "txt", "rst", "png", "jpg", "svg"];
+const JS_EXTENSIONS: &[&str] = &["js", "jsx", "ts", "tsx", "mjs", "cjs"];
+const JEST_CONFIGS: &[&str] = &[
+    "jest.config.js",
+    "jest.config.ts",
+    "jest.config.mjs",
+    "jest.config.cjs",
+    "jest.config.json",
+];
+
+/// Tests to run for one Cargo package.
+#[derive(Debug, Default, PartialEq)]
+struct PackageTests {
+    /// Run everything in the package; overrides the fields below.
+    all: bool,
+    /// `tests/<name>.rs` integration targets.
+    test_targets: BTreeSet<String>,
+    /// Module paths used as libtest name filters.
+    filters: BTreeSet<String>,
+}
+
+/// Shell commands that run the tests affected by `files` (paths relative to
+/// `project_root`), or `None` when the full suite should run instead.
+pub fn targeted_test_commands(project_root: &Path, files: &[String]) -> Option<Vec<String>> {
+    let mut packages: BTreeMap<String, PackageTests> = BTreeMap::new();
+    let mut js_files: BTreeSet<String> = BTreeSet::new();
+
+    for file in files {
+        let path = Path::new(file);
+        if path.starts_with(".ralph") || path.is_absolute() {
+            continue;
+        }
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        if IGNORED_EXTENSIONS.contains(&ext) {
+            continue;
+        }
+        if JS_EXTENSIONS.contains(&ext) {
+            js_files.insert(file.clone());
+            continue;
+        }
+        let (package, rest) = owning_package(project_root, path)?;
+        let tests = packages.entry(package).or_default();
+        match rust_selection(&rest)? {
+            RustSelection::Package => tests.all = true,
+            RustSelection::Target(name) => {
+                tests.test_targets.insert(name);
+            }
+            RustSelection::Module(filter) => {
+                tests.filters.insert(filter);
+            }
+        }
+    }
+
+    let mut commands: Vec<String> = packages
+        .iter()
+        .flat_map(|(package, tests)| cargo_commands(package, tests))
+        .collect();
+    if !js_files.is_empty() {
+        if !uses_jest(project_root) {
+            return None;
+        }
+        let files: Vec<&str> = js_files.iter().map(String::as_str).collect();
+        commands.push(format!(
+            "npx jest --findRelatedTests {}",
+            shlex::try_join(files).ok()?
+        ));
+    }
+    (!commands.is_empty()).then_some(commands)
+}
+
+enum RustSelection {
+    Package,
+    Target(String),
+    Module(String),
+}
+
+/// What to run for a file, given its path relative to its package root.
+fn rust_selection(rest: &Path) -> Option<RustSelection> {
+    let parts: Vec<&str> = rest
+        .components()
+        .map(|c| match c {
+            Component::Normal(s) => s.to_str(),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    match parts.as_slice() {
+        ["Cargo.toml"] | ["build.rs"] => Some(RustSelection::Package),
+        ["tests", file] => Some(RustSelection::Target(file.strip_suffix(".rs")?.to_string())),
+        // Helpers shared between integration tests, e.g. tests/common/mod.rs
+        ["tests", ..] => Some(RustSelection::Package),
+        ["src", "lib.rs"] | ["src", "main.rs"] => Some(RustSelection::Package),
+        ["src", "bin", ..] => Some(RustSelection::Package),
+        ["src", modules @ ..] => {
+            let mut modules: Vec<&str> = modules.to_vec();
+            let last = modules.pop()?.strip_suffix(".rs")?;
+            if last != "mod" {
+                modules.push(last);
+            }
+            (!modules.is_empty()).then(|| RustSelection::Module(modules.join("::")))
+        }
+        _ => None,
+    }
+}
+
+fn cargo_commands(package: &str, tests: &PackageTests) -> Vec<String> {
+    if tests.all {
+        return vec![format!("cargo test -p {package}")];
+    }
+    let mut commands = Vec::new();
+    if !tests.filters.is_empty() {
+        // Unit tests live in the crate's own targets; `--lib --bins` skips
+        // integration tests that the filters would not match anyway.
+        let filters: Vec<&str> = tests.filters.iter().map(String::as_str).collect();
+        commands.push(format!(
+            "cargo test -p {package} --lib --bins -- {}",
+            filters.join(" ")
+        ));
+    }
+    for target in &tests.test_targets {
+        commands.push(format!("cargo test -p {package} --test {target}"));
+    }
+    commands
+}
+
+/// The package owning `file` and the file's path relative to that package,
+/// from the nearest `Cargo.toml` with a `[package]` table. `None` if the file
+/// is not inside a package.
+fn owning_package(project_root: &Path, file: &Path) -> Option<(String, std::path::PathBuf)> {
+    let mut dir = file.parent();
+    while let Some(current) = dir {
+        let manifest = project_root.join(current).join("Cargo.toml");
+        if let Some(name) = package_name(&manifest) {
+            return Some((name, file.strip_prefix(current).ok()?.to_path_buf()));
+        }
+        if current.as_os_str().is_empty() {
+            break;
+        }
+        dir = current.parent();
+    }
+    None
+}
+
+fn package_name(manifest: &Path) -> Option<String> {
+    let text = std::fs::read_to_string(manifest).ok()?;
+    let value: toml::Value = toml::from_str(&text).ok()?;
+    Some(value.get("package")?.get("name")?.as_str()?.to_string())
+}
+
+fn uses_jest(project_root: &Path) -> bool {
+    if JEST_CONFIGS
+        .iter()
+        .any(|name| project_root.join(name).exists())
+    {
+        return true;
+    }
+    let Ok(text) = std::fs::read_to_string(project_root.join("package.json")) else {
+        return false;
+    };
+    let Ok(package) = serde_json::from_str::<serde_json::Value>(&text) else {
+        return false;
+    };
+    package.get("jest").is_some()
+        || ["dependencies", "devDependencies"]
+            .iter()
+            .any(|deps| package[deps].get("jest").is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn files(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|p| p.to_string()).collect()
+    }
+
+    fn workspace() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"app\"\n\n[workspace]\nmembers = [\"crates/util\"]\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(dir.path().join("crates/util")).unwrap();
+        std::fs::write(
+            dir.path().join("crates/util/Cargo.toml"),
+            "[package]\nname = \"util\"\n",
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn rust_files_map_to_module_filters_and_test_targets() {
+        let dir = workspace();
+        let commands = targeted_test_commands(
+            dir.path(),
+            &files(&[
+                "src/dag/mod.rs",
+                "src/dag/db.rs",
+                "tests/cli.rs",
+                "crates/util/src/lib.rs",
+                "README.md",
+                ".ralph/progress.db",
+            ]),
+        )
+        .unwrap();
+        assert_eq!(
+            commands,
+            vec![
+                "cargo test -p app --lib --bins -- dag dag::db",
+                "cargo test -p app --test cli",
+                "cargo test -p util",
+            ]
+        );
+    }
+
+    #[test]
+    fn unmapped_files_fall_back_to_the_full_suite() {
+        let dir = workspace();
+        assert_eq!(
+            targeted_test_commands(dir.path(), &files(&["scripts/release.sh"])),
+            None
+        );
+        assert_eq!(
+            targeted_test_commands(dir.path(), &files(&["README.md"])),
+            None
+        );
+        // JS without Jest cannot be narrowed down
+        assert_eq!(
+            targeted_test_commands(dir.path(), &files(&["web/app.ts"])),
+            None
+        );
+    }
+
+    #[test]
+    fn js_files_use_jest_related_tests() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("package.json"),
+            r#"{"devDependencies": {"jest": "^29"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            targeted_test_commands(dir.path(), &files(&["src/a b.ts", "src/util.test.js"])),
+            Some(vec![
+                "npx jest --findRelatedTests 'src/a b.ts' src/util.test.js".to_string()
+            ])
+        );
+    }
+}
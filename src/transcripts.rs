@@ -0,0 +1,185 @@
+//! Terminal session recordings per task (`.ralph/transcripts/`).
+//!
+//! The output the agent reads from a terminal is drained as it goes, so once
+//! an iteration ends nothing shows what its commands printed. While a task
+//! iteration runs, every terminal the agent creates is recorded when it is
+//! released (or killed at the end of the session): the command, working
+//! directory, exit code, duration and full stdout/stderr. Records are
+//! appended as JSON lines to `.ralph/transcripts/<task-id>.jsonl`, with
+//! secrets masked like the run log. `ralph logs --task <id> --terminals`
+//! prints them.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::terminal_env;
+
+/// Transcript directory, relative to the project root.
+pub const TRANSCRIPTS_DIR: &str = ".ralph/transcripts";
+
+/// One terminal command the agent ran.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TerminalRecord {
+    pub iteration: u32,
+    pub command: String,
+    pub cwd: String,
+    /// RFC 3339 start time.
+    pub started_at: String,
+    pub duration_ms: u64,
+    /// `None` when the command was killed or still running at release.
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Where one iteration's terminals are recorded.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    path: PathBuf,
+    iteration: u32,
+}
+
+impl Recorder {
+    pub fn for_task(project_root: &Path, task_id: &str, iteration: u32) -> Self {
+        Self {
+            path: transcript_path(project_root, task_id),
+            iteration,
+        }
+    }
+
+    pub fn iteration(&self) -> u32 {
+        self.iteration
+    }
+
+    /// Append `record`, masking secret values in its command and output.
+    pub fn append(&self, record: &TerminalRecord) -> Result<()> {
+        let env = terminal_env::current();
+        let record = TerminalRecord {
+            command: env.mask(&record.command),
+            stdout: env.mask(&record.stdout),
+            stderr: env.mask(&record.stderr),
+            ..record.clone()
+        };
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
+
+pub fn transcript_path(project_root: &Path, task_id: &str) -> PathBuf {
+    project_root
+        .join(TRANSCRIPTS_DIR)
+        .join(format!("{task_id}.jsonl"))
+}
+
+/// Every recorded terminal of `task_id`, oldest first. A task that never ran
+/// a terminal has none.
+pub fn load(project_root: &Path, task_id: &str) -> Result<Vec<TerminalRecord>> {
+    let path = transcript_path(project_root, task_id);
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("{}: line {} is not a record", path.display(), i + 1))
+        })
+        .collect()
+}
+
+/// Plain-text view of `records`: a header per command, then its output.
+pub fn render(records: &[TerminalRecord]) -> String {
+    let mut out = String::new();
+    for (i, record) in records.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let exit = match record.exit_code {
+            Some(code) => format!("exit {code}"),
+            None => "no exit code".to_string(),
+        };
+        let _ = writeln!(
+            out,
+            "── iteration {} · {} · {exit} · {:.1}s",
+            record.iteration,
+            record.started_at,
+            record.duration_ms as f64 / 1000.0
+        );
+        let _ = writeln!(out, "$ {}", record.command);
+        let _ = writeln!(out, "  (in {})", record.cwd);
+        for (label, text) in [("stdout", &record.stdout), ("stderr", &record.stderr)] {
+            if text.is_empty() {
+                continue;
+            }
+            let _ = writeln!(out, "[{label}]");
+            out.push_str(text);
+            if !text.ends_with('\n') {
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn record(command: &str, exit_code: Option<i32>) -> TerminalRecord {
+        TerminalRecord {
+            iteration: 2,
+            command: command.to_string(),
+            cwd: ".".to_string(),
+            started_at: "2026-10-17T10:00:00+00:00".to_string(),
+            duration_ms: 1500,
+            exit_code,
+            stdout: "ok\n".to_string(),
+            stderr: String::new(),
+        }
+    }
+
+    #[test]
+    fn records_round_trip_in_order() {
+        let tmp = TempDir::new().unwrap();
+        assert!(load(tmp.path(), "t-1").unwrap().is_empty());
+
+        let recorder = Recorder::for_task(tmp.path(), "t-1", 2);
+        recorder.append(&record("cargo test", Some(0))).unwrap();
+        recorder.append(&record("sleep 60", None)).unwrap();
+
+        let records = load(tmp.path(), "t-1").unwrap();
+        assert_eq!(
+            records,
+            vec![record("cargo test", Some(0)), record("sleep 60", None)]
+        );
+        assert!(load(tmp.path(), "t-2").unwrap().is_empty());
+    }
+
+    #[test]
+    fn render_shows_command_exit_and_output() {
+        let mut failed = record("cargo test", Some(101));
+        failed.stderr = "test failed".to_string();
+        let text = render(&[record("ls", Some(0)), failed]);
+        assert!(text.contains("── iteration 2 · 2026-10-17T10:00:00+00:00 · exit 0 · 1.5s"));
+        assert!(text.contains("$ cargo test\n  (in .)\n[stdout]\nok\n[stderr]\ntest failed\n"));
+        assert!(!text.contains("[stderr]\n\n"));
+    }
+}
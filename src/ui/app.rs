@@ -2,7 +2,7 @@
 
 use std::io;
 use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use crossterm::cursor::{Hide, Show};
 use crossterm::event::{
@@ -18,22 +18,27 @@ use ratatui::Terminal;
 
 use crate::ui::state::{AppState, FrameAreas};
 use crate::ui::view;
-use crate::ui::{UiCommand, UiPromptResult};
+use crate::ui::{ConfirmReply, UiCommand, UiPromptResult};
 
 const EVENT_POLL_INTERVAL: Duration = Duration::from_millis(20);
 
+/// How often the Run header meter's clock is redrawn while a session runs.
+const METER_TICK: Duration = Duration::from_secs(1);
+
 enum Interaction {
     None,
     Multiline {
         reply: Sender<UiPromptResult>,
     },
     Confirm {
-        reply: Sender<bool>,
+        reply: ConfirmReply,
         default_yes: bool,
     },
     Explorer {
         reply: Sender<()>,
     },
+    /// Composing a steering message for the running agent (`m`).
+    Steering,
 }
 
 /// Execute the UI loop until a shutdown command is received.
@@ -53,6 +58,7 @@ pub(super) fn run(rx: Receiver<UiCommand>) -> io::Result<()> {
     let mut should_exit = false;
     let mut needs_draw = true;
     let mut cursor_shown = false;
+    let mut last_draw = Instant::now();
 
     while !should_exit {
         match rx.recv_timeout(EVENT_POLL_INTERVAL) {
@@ -74,6 +80,11 @@ pub(super) fn run(rx: Receiver<UiCommand>) -> io::Result<()> {
         if handle_terminal_events(&mut state, &mut interaction, &areas) {
             needs_draw = true;
         }
+        if state.meter.as_ref().is_some_and(|m| m.finished.is_none())
+            && last_draw.elapsed() >= METER_TICK
+        {
+            needs_draw = true;
+        }
 
         // Show terminal cursor when input pane is active in free-text mode,
         // hide it otherwise so it doesn't flicker over the dashboard.
@@ -92,6 +103,7 @@ pub(super) fn run(rx: Receiver<UiCommand>) -> io::Result<()> {
                 view::render_with_cache(frame, &state, &mut areas, &mut render_cache)
             })?;
             needs_draw = false;
+            last_draw = Instant::now();
         }
     }
 
@@ -116,14 +128,23 @@ fn apply_command(state: &mut AppState, interaction: &mut Interaction, cmd: UiCom
             title,
             hint,
             choices,
+            checked,
             reply,
         } => {
             // Defensive: if a Multiline is already active, deactivate first.
             // The old reply channel is dropped, causing recv() Err on the caller.
-            if matches!(interaction, Interaction::Multiline { .. }) {
+            if matches!(
+                interaction,
+                Interaction::Multiline { .. } | Interaction::Steering
+            ) {
                 state.deactivate_input();
             }
-            state.activate_input(title, hint, choices);
+            match (choices, checked) {
+                (Some(choices), Some(checked)) => {
+                    state.activate_multi_select(title, hint, choices, checked)
+                }
+                (choices, _) => state.activate_input(title, hint, choices),
+            }
             *interaction = Interaction::Multiline { reply };
             false
         }
@@ -135,7 +156,10 @@ fn apply_command(state: &mut AppState, interaction: &mut Interaction, cmd: UiCom
         } => {
             // Defensive: if a Multiline is active, deactivate input first.
             // The old reply channel is dropped, causing recv() Err on the caller.
-            if matches!(interaction, Interaction::Multiline { .. }) {
+            if matches!(
+                interaction,
+                Interaction::Multiline { .. } | Interaction::Steering
+            ) {
                 state.deactivate_input();
             }
             state.modal = Some(crate::ui::state::UiModal::Confirm {
@@ -149,9 +173,10 @@ fn apply_command(state: &mut AppState, interaction: &mut Interaction, cmd: UiCom
         UiCommand::ShowExplorer {
             title,
             lines,
+            markdown,
             reply,
         } => {
-            state.show_explorer(title, lines);
+            state.show_explorer(title, lines, markdown);
             state.modal = None;
             *interaction = Interaction::Explorer { reply };
             false
@@ -202,6 +227,64 @@ fn process_key(
     key: crossterm::event::KeyEvent,
 ) {
     match interaction {
+        Interaction::Multiline { .. } if state.input_checked.is_some() => {
+            // Multi-select key handling: toggle items, Enter submits the set.
+            let count = state.input_choices.as_ref().map_or(0, Vec::len);
+            match key.code {
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    let old = std::mem::replace(interaction, Interaction::None);
+                    if let Interaction::Multiline { reply } = old {
+                        let _ = reply.send(UiPromptResult::Interrupted);
+                    }
+                    state.deactivate_input();
+                }
+                KeyCode::Esc => {
+                    let old = std::mem::replace(interaction, Interaction::None);
+                    if let Interaction::Multiline { reply } = old {
+                        let _ = reply.send(UiPromptResult::Exit);
+                    }
+                    state.deactivate_input();
+                }
+                KeyCode::Up => {
+                    state.input_choice_cursor = state.input_choice_cursor.saturating_sub(1);
+                }
+                KeyCode::Down if state.input_choice_cursor + 1 < count => {
+                    state.input_choice_cursor += 1;
+                }
+                KeyCode::Char(' ') => state.toggle_checked(state.input_choice_cursor),
+                KeyCode::Char(ch @ '1'..='9') => {
+                    let index = (ch as usize) - ('1' as usize);
+                    if index < count {
+                        state.input_choice_cursor = index;
+                        state.toggle_checked(index);
+                    }
+                }
+                KeyCode::Char('a') => {
+                    let all = state.checked_indices().len() == count;
+                    if let Some(checked) = state.input_checked.as_mut() {
+                        checked.iter_mut().for_each(|c| *c = !all);
+                    }
+                }
+                KeyCode::Enter => {
+                    let selected = state.checked_indices();
+                    let old = std::mem::replace(interaction, Interaction::None);
+                    if let Interaction::Multiline { reply } = old {
+                        let _ = reply.send(UiPromptResult::Selected(selected));
+                    }
+                    state.deactivate_input();
+                }
+                KeyCode::PageUp => {
+                    state.agent_scroll_up(20);
+                }
+                KeyCode::PageDown => {
+                    state.agent_scroll_down(20, state.agent_line_count);
+                }
+                KeyCode::End => {
+                    state.agent_scroll_to_bottom();
+                }
+                _ => {}
+            }
+        }
         Interaction::Multiline { .. } if state.input_choices.is_some() => {
             // Choice mode key handling.
             match key.code {
@@ -290,13 +373,8 @@ fn process_key(
                 }
                 state.deactivate_input();
             }
-            KeyCode::Enter if key.modifiers.contains(KeyModifiers::SHIFT) => {
-                // Shift+Enter inserts a newline.
-                state.input_text.insert(state.input_cursor, '\n');
-                state.input_cursor += 1;
-            }
-            KeyCode::Enter => {
-                // Enter submits the input.
+            KeyCode::Enter if !key.modifiers.contains(KeyModifiers::SHIFT) => {
+                // Enter submits the input; Shift+Enter inserts a newline.
                 let old = std::mem::replace(interaction, Interaction::None);
                 if let Interaction::Multiline { reply } = old {
                     let text = state.input_text.trim_end().to_string();
@@ -308,101 +386,46 @@ fn process_key(
                 }
                 state.deactivate_input();
             }
-            KeyCode::Backspace => {
-                if state.input_cursor > 0 {
-                    // Find the previous char boundary.
-                    let prev = state.input_text[..state.input_cursor]
-                        .char_indices()
-                        .next_back()
-                        .map(|(i, _)| i)
-                        .unwrap_or(0);
-                    state.input_text.drain(prev..state.input_cursor);
-                    state.input_cursor = prev;
-                }
-            }
-            KeyCode::Delete => {
-                if state.input_cursor < state.input_text.len() {
-                    let next = state.input_cursor
-                        + state.input_text[state.input_cursor..]
-                            .chars()
-                            .next()
-                            .map(|c| c.len_utf8())
-                            .unwrap_or(0);
-                    state.input_text.drain(state.input_cursor..next);
-                }
-            }
-            KeyCode::Left => {
-                if state.input_cursor > 0 {
-                    state.input_cursor = state.input_text[..state.input_cursor]
-                        .char_indices()
-                        .next_back()
-                        .map(|(i, _)| i)
-                        .unwrap_or(0);
-                }
-            }
-            KeyCode::Right => {
-                if state.input_cursor < state.input_text.len() {
-                    state.input_cursor += state.input_text[state.input_cursor..]
-                        .chars()
-                        .next()
-                        .map(|c| c.len_utf8())
-                        .unwrap_or(0);
-                }
-            }
-            KeyCode::Up => {
-                // Move cursor to the same column on the previous line.
-                input_cursor_up(state);
-            }
-            KeyCode::Down => {
-                // Move cursor to the same column on the next line.
-                input_cursor_down(state);
-            }
-            KeyCode::Home => {
-                // Move cursor to start of current line.
-                let before = &state.input_text[..state.input_cursor];
-                state.input_cursor = match before.rfind('\n') {
-                    Some(pos) => pos + 1,
-                    None => 0,
-                };
-            }
-            KeyCode::End => {
-                // Move cursor to end of current line.
-                let after = &state.input_text[state.input_cursor..];
-                state.input_cursor += match after.find('\n') {
-                    Some(pos) => pos,
-                    None => after.len(),
-                };
-            }
-            KeyCode::Char(ch) => {
-                state.input_text.insert(state.input_cursor, ch);
-                state.input_cursor += ch.len_utf8();
-            }
-            KeyCode::PageUp => {
-                state.agent_scroll_up(20);
-            }
-            KeyCode::PageDown => {
-                state.agent_scroll_down(20, state.agent_line_count);
-            }
-            _ => {}
+            _ => edit_input(state, key),
         },
-        Interaction::Confirm { reply, default_yes } => match key.code {
-            KeyCode::Char('y') | KeyCode::Char('Y') => {
-                let _ = reply.send(true);
+        Interaction::Steering => match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                 *interaction = Interaction::None;
-                state.modal = None;
+                state.deactivate_input();
+                crate::interrupt::request_interrupt();
             }
-            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
-                let _ = reply.send(false);
+            KeyCode::Esc => {
                 *interaction = Interaction::None;
-                state.modal = None;
+                state.deactivate_input();
             }
-            KeyCode::Enter => {
-                let _ = reply.send(*default_yes);
+            KeyCode::Enter if !key.modifiers.contains(KeyModifiers::SHIFT) => {
+                let message = state.input_text.trim().to_string();
+                if !message.is_empty() {
+                    crate::steering::queue(&message);
+                    let first_line = message.lines().next().unwrap_or_default();
+                    crate::output::formatter::emit_event_info(
+                        "steer",
+                        &format!("queued: {first_line}"),
+                    );
+                }
                 *interaction = Interaction::None;
-                state.modal = None;
+                state.deactivate_input();
             }
-            _ => {}
+            _ => edit_input(state, key),
         },
+        Interaction::Confirm { default_yes, .. } => {
+            let answer = match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => true,
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => false,
+                KeyCode::Enter => *default_yes,
+                _ => return,
+            };
+            let old = std::mem::replace(interaction, Interaction::None);
+            if let Interaction::Confirm { reply, .. } = old {
+                reply.send(answer);
+            }
+            state.modal = None;
+        }
         Interaction::Explorer { reply } => match key.code {
             KeyCode::Char('q') | KeyCode::Esc | KeyCode::Enter => {
                 state.hide_explorer();
@@ -425,14 +448,83 @@ fn process_key(
                     state.explorer_scroll_down();
                 }
             }
+            KeyCode::Char('y') => export_explorer(state, false),
+            KeyCode::Char('s') => export_explorer(state, true),
+            _ => {}
+        },
+        Interaction::None if state.event_search_editing => match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                crate::interrupt::request_interrupt();
+            }
+            KeyCode::Char(ch) => state.edit_event_search(Some(ch)),
+            KeyCode::Backspace => state.edit_event_search(None),
+            KeyCode::Enter => state.finish_event_search(true),
+            KeyCode::Esc => state.finish_event_search(false),
             _ => {}
         },
+        Interaction::None if state.history.is_some() => {
+            // History panel: arrows scroll, left/right jump between iterations.
+            let Some(history) = state.history.as_mut() else {
+                return;
+            };
+            match key.code {
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    crate::interrupt::request_interrupt();
+                }
+                KeyCode::Char('h') | KeyCode::Esc => state.toggle_history(),
+                KeyCode::Left | KeyCode::Char('[') => history.jump(false),
+                KeyCode::Right | KeyCode::Char(']') => history.jump(true),
+                KeyCode::Up | KeyCode::Char('k') => history.scroll_by(-1),
+                KeyCode::Down | KeyCode::Char('j') => history.scroll_by(1),
+                KeyCode::PageUp => history.scroll_by(-20),
+                KeyCode::PageDown => history.scroll_by(20),
+                KeyCode::Home => history.scroll = 0,
+                KeyCode::End => history.scroll_by(isize::MAX),
+                _ => {}
+            }
+        }
+        Interaction::None if state.diff_panel => {
+            // Diff panel: arrows scroll the diff, left/right switch files.
+            match key.code {
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    crate::interrupt::request_interrupt();
+                }
+                KeyCode::Char('d') | KeyCode::Esc => state.toggle_diff_panel(),
+                KeyCode::Char('m') => {
+                    state.start_steering();
+                    *interaction = Interaction::Steering;
+                }
+                KeyCode::Char('f') => state.cycle_event_filter(),
+                KeyCode::Char('/') => state.start_event_search(),
+                KeyCode::Char('T') => state.cycle_theme(),
+                KeyCode::Left | KeyCode::Char('h') => state.diff_select(false),
+                KeyCode::Right | KeyCode::Char('l') | KeyCode::Tab => state.diff_select(true),
+                KeyCode::Up | KeyCode::Char('k') => state.diff_scroll_by(-1),
+                KeyCode::Down | KeyCode::Char('j') => state.diff_scroll_by(1),
+                KeyCode::PageUp => state.diff_scroll_by(-20),
+                KeyCode::PageDown => state.diff_scroll_by(20),
+                _ => {}
+            }
+        }
         Interaction::None => {
             // Dashboard mode: arrow keys scroll the Agent Stream panel.
             match key.code {
                 KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                     crate::interrupt::request_interrupt();
                 }
+                KeyCode::Char('d') => state.toggle_diff_panel(),
+                KeyCode::Char('h') => state.toggle_history(),
+                KeyCode::Char('t') => state.toggle_thoughts(),
+                KeyCode::Char('m') => {
+                    state.start_steering();
+                    *interaction = Interaction::Steering;
+                }
+                KeyCode::Char('f') => state.cycle_event_filter(),
+                KeyCode::Char('/') => state.start_event_search(),
+                KeyCode::Char('T') => state.cycle_theme(),
+                KeyCode::Esc if !state.event_search.is_empty() => {
+                    state.finish_event_search(false);
+                }
                 KeyCode::Up | KeyCode::Char('k') => {
                     state.agent_scroll_up(1);
                 }
@@ -456,6 +548,106 @@ fn process_key(
     }
 }
 
+/// Text editing keys shared by the prompt and steering input panes.
+/// Copy the open explorer view to the clipboard, or save it to a file, and
+/// report the result in the explorer footer.
+fn export_explorer(state: &mut AppState, save: bool) {
+    let Some((title, lines)) = state.explorer_view() else {
+        return;
+    };
+    let notice = if save {
+        let path = super::export::default_path(title);
+        match super::export::write_lines(&path, lines) {
+            Ok(()) => format!("Saved {} line(s) to {}", lines.len(), path.display()),
+            Err(e) => format!("Save failed: {e:#}"),
+        }
+    } else {
+        match super::export::copy_to_clipboard(lines) {
+            Ok(()) => format!("Copied {} line(s) to the clipboard", lines.len()),
+            Err(e) => format!("Copy failed: {e:#}"),
+        }
+    };
+    state.set_explorer_notice(notice);
+}
+
+fn edit_input(state: &mut AppState, key: crossterm::event::KeyEvent) {
+    match key.code {
+        KeyCode::Enter if key.modifiers.contains(KeyModifiers::SHIFT) => {
+            // Shift+Enter inserts a newline.
+            state.input_text.insert(state.input_cursor, '\n');
+            state.input_cursor += 1;
+        }
+        KeyCode::Backspace if state.input_cursor > 0 => {
+            // Find the previous char boundary.
+            let prev = state.input_text[..state.input_cursor]
+                .char_indices()
+                .next_back()
+                .map(|(i, _)| i)
+                .unwrap_or(0);
+            state.input_text.drain(prev..state.input_cursor);
+            state.input_cursor = prev;
+        }
+        KeyCode::Delete if state.input_cursor < state.input_text.len() => {
+            let next = state.input_cursor
+                + state.input_text[state.input_cursor..]
+                    .chars()
+                    .next()
+                    .map(|c| c.len_utf8())
+                    .unwrap_or(0);
+            state.input_text.drain(state.input_cursor..next);
+        }
+        KeyCode::Left if state.input_cursor > 0 => {
+            state.input_cursor = state.input_text[..state.input_cursor]
+                .char_indices()
+                .next_back()
+                .map(|(i, _)| i)
+                .unwrap_or(0);
+        }
+        KeyCode::Right if state.input_cursor < state.input_text.len() => {
+            state.input_cursor += state.input_text[state.input_cursor..]
+                .chars()
+                .next()
+                .map(|c| c.len_utf8())
+                .unwrap_or(0);
+        }
+        KeyCode::Up => {
+            // Move cursor to the same column on the previous line.
+            input_cursor_up(state);
+        }
+        KeyCode::Down => {
+            // Move cursor to the same column on the next line.
+            input_cursor_down(state);
+        }
+        KeyCode::Home => {
+            // Move cursor to start of current line.
+            let before = &state.input_text[..state.input_cursor];
+            state.input_cursor = match before.rfind('\n') {
+                Some(pos) => pos + 1,
+                None => 0,
+            };
+        }
+        KeyCode::End => {
+            // Move cursor to end of current line.
+            let after = &state.input_text[state.input_cursor..];
+            state.input_cursor += match after.find('\n') {
+                Some(pos) => pos,
+                None => after.len(),
+            };
+        }
+        KeyCode::Char(ch) => {
+            state.input_text.insert(state.input_cursor, ch);
+            state.input_cursor += ch.len_utf8();
+        }
+        KeyCode::PageUp => {
+            state.agent_scroll_up(20);
+        }
+        KeyCode::PageDown => {
+            state.agent_scroll_down(20, state.agent_line_count);
+        }
+        _ => {}
+    }
+}
+
 fn process_mouse(
     state: &mut AppState,
     _interaction: &mut Interaction,
@@ -477,6 +669,14 @@ fn process_mouse(
     };
 
     if let Some(ref r) = areas.agent {
+        if let Some(history) = state.history.as_mut().filter(|_| in_rect(r)) {
+            history.scroll_by(scroll_lines as isize);
+            return;
+        }
+        if in_rect(r) && state.diff_panel {
+            state.diff_scroll_by(scroll_lines as isize);
+            return;
+        }
         if in_rect(r) {
             let inner_h = r.height.saturating_sub(2) as usize;
             let max_offset = state.agent_line_count.saturating_sub(inner_h);
@@ -492,7 +692,7 @@ fn process_mouse(
     if let Some(ref r) = areas.events {
         if in_rect(r) {
             let inner_h = r.height.saturating_sub(2) as usize;
-            let total = state.events.len();
+            let total = state.visible_events().len();
             let max_offset = total.saturating_sub(inner_h);
             if scroll_lines < 0 {
                 state.events_scroll_up((-scroll_lines) as usize);
@@ -592,6 +792,38 @@ mod tests {
         KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)
     }
 
+    #[test]
+    fn multi_select_toggles_and_submits_checked_indices() {
+        let mut state = AppState::default();
+        let (tx, rx) = std::sync::mpsc::channel();
+        state.activate_multi_select(
+            "Pick".to_string(),
+            "hint".to_string(),
+            vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            vec![true, false, false],
+        );
+        let mut interaction = Interaction::Multiline { reply: tx };
+
+        process_key(&mut state, &mut interaction, key(KeyCode::Down));
+        process_key(&mut state, &mut interaction, key(KeyCode::Char(' ')));
+        process_key(&mut state, &mut interaction, key(KeyCode::Char('1')));
+        process_key(&mut state, &mut interaction, key(KeyCode::Char('3')));
+        // Typing does not switch a checklist to free text.
+        process_key(&mut state, &mut interaction, key(KeyCode::Char('x')));
+        assert_eq!(state.checked_indices(), vec![1, 2]);
+
+        process_key(&mut state, &mut interaction, key(KeyCode::Char('a')));
+        assert_eq!(state.checked_indices(), vec![0, 1, 2]);
+        process_key(&mut state, &mut interaction, key(KeyCode::Char('a')));
+        assert!(state.checked_indices().is_empty());
+        process_key(&mut state, &mut interaction, key(KeyCode::Char('2')));
+
+        process_key(&mut state, &mut interaction, key(KeyCode::Enter));
+        assert_eq!(rx.recv().unwrap(), UiPromptResult::Selected(vec![1]));
+        assert!(!state.input_active);
+        assert!(state.input_checked.is_none());
+    }
+
     #[test]
     fn freetext_typing_and_backspace() {
         let mut state = AppState::default();
@@ -929,4 +1161,101 @@ mod tests {
         // Input should still be active
         assert!(state.input_active);
     }
+
+    #[test]
+    fn slash_search_captures_keys_until_enter_or_esc() {
+        let mut state = AppState::default();
+        let mut interaction = Interaction::None;
+
+        process_key(&mut state, &mut interaction, key(KeyCode::Char('/')));
+        assert!(state.event_search_editing);
+        // `d` and `f` are search text here, not shortcuts
+        for ch in "dfx".chars() {
+            process_key(&mut state, &mut interaction, key(KeyCode::Char(ch)));
+        }
+        process_key(&mut state, &mut interaction, key(KeyCode::Backspace));
+        assert_eq!(state.event_search, "df");
+        assert!(!state.diff_panel);
+        process_key(&mut state, &mut interaction, key(KeyCode::Enter));
+        assert!(!state.event_search_editing);
+        assert_eq!(state.event_search, "df");
+
+        process_key(&mut state, &mut interaction, key(KeyCode::Char('f')));
+        assert_eq!(state.event_filter.label(), Some("iter"));
+        process_key(&mut state, &mut interaction, key(KeyCode::Esc));
+        assert!(state.event_search.is_empty());
+    }
+
+    #[test]
+    fn h_opens_history_and_arrows_jump_between_iterations() {
+        let mut state = AppState::default();
+        let mut interaction = Interaction::None;
+
+        process_key(&mut state, &mut interaction, key(KeyCode::Char('h')));
+        assert!(state.history.as_ref().unwrap().lines.is_empty());
+        process_key(&mut state, &mut interaction, key(KeyCode::Esc));
+        assert!(state.history.is_none());
+
+        state.history = Some(crate::ui::state::RunHistory::from_runs(&[
+            (
+                "run-1".to_string(),
+                "# t-1 A (iteration 1, m)\nx\n".to_string(),
+            ),
+            (
+                "run-2".to_string(),
+                "# t-2 B (iteration 1, m)\ny\n".to_string(),
+            ),
+        ]));
+        let scroll = |state: &AppState| state.history.as_ref().unwrap().scroll;
+        assert_eq!(scroll(&state), 5);
+        process_key(&mut state, &mut interaction, key(KeyCode::Left));
+        assert_eq!(scroll(&state), 4);
+        process_key(&mut state, &mut interaction, key(KeyCode::Left));
+        assert_eq!(scroll(&state), 1);
+        process_key(&mut state, &mut interaction, key(KeyCode::Down));
+        assert_eq!(scroll(&state), 2);
+        process_key(&mut state, &mut interaction, key(KeyCode::Right));
+        assert_eq!(scroll(&state), 4);
+        // `d` doesn't open the Diff panel underneath
+        process_key(&mut state, &mut interaction, key(KeyCode::Char('d')));
+        assert!(!state.diff_panel);
+        process_key(&mut state, &mut interaction, key(KeyCode::Char('h')));
+        assert!(state.history.is_none());
+    }
+
+    #[test]
+    fn m_composes_a_steering_message_and_enter_queues_it() {
+        let _guard = crate::steering::TEST_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        crate::steering::deliver();
+        let mut state = AppState::default();
+        let mut interaction = Interaction::None;
+
+        process_key(&mut state, &mut interaction, key(KeyCode::Char('m')));
+        assert!(state.input_active && state.input_steering);
+        // `d` is text here, not the diff shortcut
+        process_key(&mut state, &mut interaction, key(KeyCode::Char('d')));
+        process_key(
+            &mut state,
+            &mut interaction,
+            KeyEvent::new(KeyCode::Enter, KeyModifiers::SHIFT),
+        );
+        process_key(&mut state, &mut interaction, key(KeyCode::Char('x')));
+        assert_eq!(state.input_text, "d\nx");
+        assert!(!state.diff_panel);
+
+        process_key(&mut state, &mut interaction, key(KeyCode::Enter));
+        assert!(matches!(interaction, Interaction::None));
+        assert!(!state.input_active && !state.input_steering);
+        assert_eq!(crate::steering::deliver(), vec!["d\nx".to_string()]);
+
+        // Esc discards the draft
+        process_key(&mut state, &mut interaction, key(KeyCode::Char('m')));
+        process_key(&mut state, &mut interaction, key(KeyCode::Char('y')));
+        process_key(&mut state, &mut interaction, key(KeyCode::Esc));
+        assert!(!state.input_active);
+        assert!(!crate::steering::has_pending());
+        crate::steering::take_delivered();
+    }
 }
@@ -1,5 +1,7 @@
 //! Events emitted by core modules and consumed by the TUI runtime.
 
+use std::time::{Duration, Instant};
+
 /// A structured tool activity entry for the TUI.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ToolLine {
@@ -23,11 +25,84 @@ pub struct EventLine {
     pub is_error: bool,
 }
 
+/// Cumulative unified diff of one file written during the current iteration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDiff {
+    /// Project-relative path.
+    pub path: String,
+    /// Unified diff from the file's content before the iteration's first write.
+    pub diff: String,
+}
+
+/// Live counters for the Run header meter, kept by
+/// `streaming::render_session_update` for the current iteration's session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamMeter {
+    /// When the session started.
+    pub started: Instant,
+    /// When it ended; freezes the elapsed time and rate.
+    pub finished: Option<Instant>,
+    /// The iteration timeout, if any.
+    pub timeout: Option<Duration>,
+    /// Characters of text, thoughts and tool input streamed so far.
+    pub chars: u64,
+    /// Estimated cost of the prompt plus the streamed output, in millionths
+    /// of a USD; `None` when the model has no price.
+    pub cost_micro_usd: Option<u64>,
+}
+
+impl StreamMeter {
+    pub fn elapsed(&self, now: Instant) -> Duration {
+        self.finished
+            .unwrap_or(now)
+            .saturating_duration_since(self.started)
+    }
+
+    /// Streamed tokens, at four characters per token.
+    pub fn tokens(&self) -> u64 {
+        self.chars.div_ceil(4)
+    }
+
+    /// Whether at least 80% of the timeout has passed.
+    pub fn near_timeout(&self, now: Instant) -> bool {
+        self.timeout
+            .is_some_and(|t| self.elapsed(now).as_secs_f64() >= t.as_secs_f64() * 0.8)
+    }
+
+    /// `1.2k tok  85 ch/s  2:14/15:00  ~$0.42`
+    pub fn render(&self, now: Instant) -> String {
+        let elapsed = self.elapsed(now);
+        let tokens = match self.tokens() {
+            n if n >= 1000 => format!("{:.1}k", n as f64 / 1000.0),
+            n => n.to_string(),
+        };
+        let rate = self.chars as f64 / elapsed.as_secs_f64().max(1.0);
+        let mut line = format!("{tokens} tok  {rate:.0} ch/s  {}", clock(elapsed.as_secs()));
+        if let Some(timeout) = self.timeout {
+            line.push_str(&format!("/{}", clock(timeout.as_secs())));
+        }
+        if let Some(micros) = self.cost_micro_usd {
+            line.push_str(&format!("  ~${:.2}", micros as f64 / 1_000_000.0));
+        }
+        line
+    }
+}
+
+/// `m:ss`, or `h:mm:ss` from an hour.
+fn clock(secs: u64) -> String {
+    match secs {
+        s if s >= 3600 => format!("{}:{:02}:{:02}", s / 3600, s / 60 % 60, s % 60),
+        s => format!("{}:{:02}", s / 60, s % 60),
+    }
+}
+
 /// Event payload rendered by the TUI.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum UiEvent {
     StatusLine(String),
     DagSummary(String),
+    /// Burn-down and velocity lines for the Progress panel.
+    Progress(Vec<String>),
     CurrentTask(String),
     AgentText(String),
     /// Thinking text from the agent, rendered indented in the agent stream.
@@ -41,6 +116,12 @@ pub enum UiEvent {
     },
     /// Structured orchestration event for the Events panel.
     Event(EventLine),
+    /// A file write by the agent, for the Diff panel.
+    FileDiff(FileDiff),
+    /// Project root whose run logs back the History panel.
+    RunLogs(std::path::PathBuf),
+    /// Updated counters for the Run header meter.
+    Meter(StreamMeter),
 }
 
 #[cfg(test)]
@@ -66,4 +147,29 @@ mod tests {
         };
         assert_ne!(a, c);
     }
+
+    #[test]
+    fn stream_meter_renders_rate_elapsed_and_cost() {
+        let started = Instant::now();
+        let meter = StreamMeter {
+            started,
+            finished: None,
+            timeout: Some(Duration::from_secs(900)),
+            chars: 6_000,
+            cost_micro_usd: Some(420_000),
+        };
+        let now = started + Duration::from_secs(134);
+        assert_eq!(meter.render(now), "1.5k tok  45 ch/s  2:14/15:00  ~$0.42");
+        assert!(!meter.near_timeout(now));
+        assert!(meter.near_timeout(started + Duration::from_secs(720)));
+
+        let finished = StreamMeter {
+            finished: Some(started + Duration::from_secs(3)),
+            timeout: None,
+            chars: 30,
+            cost_micro_usd: None,
+            ..meter
+        };
+        assert_eq!(finished.render(now), "8 tok  10 ch/s  0:03");
+    }
 }
@@ -0,0 +1,106 @@
+//! Export of explorer views: `y` copies the view to the clipboard, `s`
+//! saves it under `.ralph/exports/`, and `--out FILE` on the commands behind
+//! the views writes the same lines without the TUI.
+//!
+//! The clipboard is reached with the OSC 52 escape sequence, which the
+//! terminal (or tmux with `set-clipboard on`) forwards to the system
+//! clipboard, so it also works over SSH and needs no platform tools.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+/// The view as plain text, one line per entry.
+pub fn to_text(lines: &[String]) -> String {
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text
+}
+
+/// Write `lines` to `path`, creating its parent directories.
+pub fn write_lines(path: &Path, lines: &[String]) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::write(path, to_text(lines))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// The OSC 52 sequence that puts `text` on the system clipboard.
+fn osc52(text: &str) -> String {
+    let encoded = crate::base64::encode(text.as_bytes());
+    // tmux and screen only pass the sequence through when wrapped.
+    if std::env::var_os("TMUX").is_some() {
+        format!("\x1bPtmux;\x1b\x1b]52;c;{encoded}\x07\x1b\\")
+    } else {
+        format!("\x1b]52;c;{encoded}\x07")
+    }
+}
+
+/// Copy `lines` to the clipboard through the terminal.
+pub fn copy_to_clipboard(lines: &[String]) -> Result<()> {
+    let mut stdout = std::io::stdout();
+    stdout.write_all(osc52(&to_text(lines)).as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Where `s` saves the view titled `title`: a timestamped file under the
+/// project's `.ralph/exports/`, or the working directory outside a project.
+pub fn default_path(title: &str) -> PathBuf {
+    let root = crate::project::find_config()
+        .ok()
+        .and_then(|config| config.parent().map(Path::to_path_buf))
+        .unwrap_or_default();
+    let dir = if root.as_os_str().is_empty() {
+        PathBuf::new()
+    } else {
+        root.join(".ralph").join("exports")
+    };
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    dir.join(format!("{}-{stamp}.txt", slug(title)))
+}
+
+/// Lowercase ASCII words of `title` joined by dashes.
+fn slug(title: &str) -> String {
+    let slug = title
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        "view".to_string()
+    } else {
+        slug
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn views_export_as_plain_text() {
+        let lines = vec!["t-1  [done]  A".to_string(), "  └─ t-2".to_string()];
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("nested/tree.txt");
+        write_lines(&path, &lines).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "t-1  [done]  A\n  └─ t-2\n"
+        );
+
+        assert_eq!(slug("Dependencies for t-1a2b"), "dependencies-for-t-1a2b");
+        assert_eq!(slug("──"), "view");
+        let name = default_path("Task Explorer");
+        let name = name.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("task-explorer-") && name.ends_with(".txt"));
+
+        let seq = osc52("hi\n");
+        assert!(seq.contains("]52;c;aGkK\x07"));
+    }
+}
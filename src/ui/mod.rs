@@ -2,6 +2,7 @@
 
 pub mod app;
 pub mod event;
+pub mod export;
 pub mod state;
 pub mod theme;
 pub mod view;
@@ -46,6 +47,8 @@ impl UiMode {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum UiPromptResult {
     Input(String),
+    /// Indices of the checked items from a multi-select prompt, in list order.
+    Selected(Vec<usize>),
     Exit,
     Interrupted,
 }
@@ -56,17 +59,20 @@ pub(super) enum UiCommand {
         title: String,
         hint: String,
         choices: Option<Vec<String>>,
+        /// Initial check marks; `Some` turns the choice list into a multi-select.
+        checked: Option<Vec<bool>>,
         reply: Sender<UiPromptResult>,
     },
     Confirm {
         title: String,
         prompt: String,
         default_yes: bool,
-        reply: Sender<bool>,
+        reply: ConfirmReply,
     },
     ShowExplorer {
         title: String,
         lines: Vec<String>,
+        markdown: bool,
         reply: Sender<()>,
     },
     Shutdown,
@@ -139,12 +145,79 @@ pub fn prompt_multiline(title: &str, hint: &str) -> Option<UiPromptResult> {
         title: title.to_string(),
         hint: hint.to_string(),
         choices: None,
+        checked: None,
         reply: reply_tx,
     })
     .ok()?;
     reply_rx.recv().ok()
 }
 
+/// Show the input pane in choice mode on the active UI.
+///
+/// The user picks one of `choices` (arrow keys or 1-9), or starts typing to
+/// switch to free text; either way the reply arrives as `UiPromptResult::Input`.
+pub fn prompt_choice(title: &str, hint: &str, choices: &[&str]) -> Option<UiPromptResult> {
+    let tx = sender()?;
+    let (reply_tx, reply_rx) = mpsc::channel();
+    tx.send(UiCommand::PromptMultiline {
+        title: title.to_string(),
+        hint: hint.to_string(),
+        choices: Some(choices.iter().map(|c| c.to_string()).collect()),
+        checked: None,
+        reply: reply_tx,
+    })
+    .ok()?;
+    reply_rx.recv().ok()
+}
+
+/// Show the input pane as a checklist on the active UI.
+///
+/// Space (or 1-9) toggles an item, `a` toggles all, Enter submits. The reply is
+/// `UiPromptResult::Selected` with the checked indices. `checked` gives the
+/// initial marks; missing entries start unchecked.
+pub fn prompt_multi_select(
+    title: &str,
+    hint: &str,
+    choices: &[&str],
+    checked: &[bool],
+) -> Option<UiPromptResult> {
+    let tx = sender()?;
+    let (reply_tx, reply_rx) = mpsc::channel();
+    tx.send(UiCommand::PromptMultiline {
+        title: title.to_string(),
+        hint: hint.to_string(),
+        choices: Some(choices.iter().map(|c| c.to_string()).collect()),
+        checked: Some(
+            (0..choices.len())
+                .map(|i| checked.get(i).copied().unwrap_or(false))
+                .collect(),
+        ),
+        reply: reply_tx,
+    })
+    .ok()?;
+    reply_rx.recv().ok()
+}
+
+/// Where the answer to a confirmation modal goes: the channel of a blocking
+/// caller, or the oneshot of a caller awaiting it on the async runtime.
+pub(super) enum ConfirmReply {
+    Blocking(Sender<bool>),
+    Async(tokio::sync::oneshot::Sender<bool>),
+}
+
+impl ConfirmReply {
+    pub(super) fn send(self, answer: bool) {
+        match self {
+            ConfirmReply::Blocking(tx) => {
+                let _ = tx.send(answer);
+            }
+            ConfirmReply::Async(tx) => {
+                let _ = tx.send(answer);
+            }
+        }
+    }
+}
+
 /// Show a yes/no confirmation modal on the active UI.
 pub fn prompt_confirm(title: &str, prompt: &str, default_yes: bool) -> Option<bool> {
     let tx = sender()?;
@@ -153,14 +226,39 @@ pub fn prompt_confirm(title: &str, prompt: &str, default_yes: bool) -> Option<bo
         title: title.to_string(),
         prompt: prompt.to_string(),
         default_yes,
-        reply: reply_tx,
+        reply: ConfirmReply::Blocking(reply_tx),
     })
     .ok()?;
     reply_rx.recv().ok()
 }
 
+/// Like [`prompt_confirm`], for callers on the async runtime: the answer is
+/// awaited, so the ACP connection keeps being served while the modal is open.
+pub async fn prompt_confirm_async(title: &str, prompt: &str, default_yes: bool) -> Option<bool> {
+    let tx = sender()?;
+    let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
+    tx.send(UiCommand::Confirm {
+        title: title.to_string(),
+        prompt: prompt.to_string(),
+        default_yes,
+        reply: ConfirmReply::Async(reply_tx),
+    })
+    .ok()?;
+    reply_rx.await.ok()
+}
+
 /// Show a full-screen explorer view and wait for user dismissal.
 pub fn show_explorer(title: &str, lines: Vec<String>) -> bool {
+    open_explorer(title, lines, false)
+}
+
+/// Like [`show_explorer`], with the lines styled as markdown: headings,
+/// code blocks, lists and inline formatting, as in the agent stream.
+pub fn show_markdown_explorer(title: &str, lines: Vec<String>) -> bool {
+    open_explorer(title, lines, true)
+}
+
+fn open_explorer(title: &str, lines: Vec<String>, markdown: bool) -> bool {
     let Some(tx) = sender() else {
         return false;
     };
@@ -169,6 +267,7 @@ pub fn show_explorer(title: &str, lines: Vec<String>) -> bool {
         .send(UiCommand::ShowExplorer {
             title: title.to_string(),
             lines,
+            markdown,
             reply: reply_tx,
         })
         .is_err()
@@ -244,6 +343,8 @@ mod tests {
     #[test]
     fn prompt_calls_fallback_when_ui_not_running() {
         assert!(super::prompt_multiline("T", "H").is_none());
+        assert!(super::prompt_choice("T", "H", &["a", "b"]).is_none());
+        assert!(super::prompt_multi_select("T", "H", &["a", "b"], &[true]).is_none());
         assert!(super::prompt_confirm("T", "P", true).is_none());
         assert!(!super::show_explorer("X", vec!["a".to_string()]));
     }
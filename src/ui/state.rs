@@ -1,12 +1,21 @@
 //! Mutable app state for the TUI renderer.
 
 use std::collections::VecDeque;
+use std::path::PathBuf;
 
-use crate::ui::event::{EventLine, ToolLine, UiEvent};
+use crate::output::logger;
+use crate::ui::event::{EventLine, FileDiff, StreamMeter, ToolLine, UiEvent};
+use crate::ui::theme;
 
 const MAX_TOOL_LINES: usize = 200;
-const MAX_EVENT_LINES: usize = 200;
+/// Events kept for scrollback; the panel only renders the visible window.
+const MAX_EVENT_LINES: usize = 5_000;
+const MAX_DIFF_LINES: usize = 5_000;
 const MAX_AGENT_CHARS: usize = 60_000;
+/// Most recent runs loaded into the History panel.
+const MAX_HISTORY_RUNS: usize = 5;
+/// Transcript lines kept in the History panel; the oldest are dropped.
+const MAX_HISTORY_LINES: usize = 20_000;
 
 /// Cached rectangle positions of dashboard frames from the last render pass.
 /// Used by the event loop to route mouse scroll events to the correct panel.
@@ -18,6 +27,128 @@ pub struct FrameAreas {
     pub events: Option<ratatui::layout::Rect>,
 }
 
+/// Which events the Events panel shows, cycled with `f`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EventFilter {
+    #[default]
+    All,
+    Category(&'static str),
+    /// Only events rendered as errors.
+    Errors,
+}
+
+const EVENT_FILTER_CYCLE: [EventFilter; 6] = [
+    EventFilter::All,
+    EventFilter::Category("iter"),
+    EventFilter::Category("task"),
+    EventFilter::Category("verify"),
+    EventFilter::Category("dag"),
+    EventFilter::Errors,
+];
+
+impl EventFilter {
+    fn matches(self, event: &EventLine) -> bool {
+        match self {
+            EventFilter::All => true,
+            EventFilter::Category(category) => event.category == category,
+            EventFilter::Errors => event.is_error,
+        }
+    }
+
+    /// Label for the Events panel title; `None` when showing everything.
+    pub fn label(self) -> Option<&'static str> {
+        match self {
+            EventFilter::All => None,
+            EventFilter::Category(category) => Some(category),
+            EventFilter::Errors => Some("errors"),
+        }
+    }
+}
+
+/// Past Agent Stream transcripts from the run logs, for the History panel.
+#[derive(Debug, Clone, Default)]
+pub struct RunHistory {
+    pub lines: Vec<String>,
+    /// Indices of run headers and iteration headings in `lines`.
+    pub markers: Vec<usize>,
+    pub scroll: usize,
+}
+
+impl RunHistory {
+    /// Build from `(run_id, transcript)` pairs, oldest run first. Each run
+    /// gets a header line; scrolling starts at the last iteration.
+    pub fn from_runs(runs: &[(String, String)]) -> Self {
+        let mut lines = Vec::new();
+        for (run_id, transcript) in runs {
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.push(format!("═════ run {run_id} ═════"));
+            lines.extend(transcript.lines().map(str::to_string));
+        }
+        if lines.len() > MAX_HISTORY_LINES {
+            lines.drain(..lines.len() - MAX_HISTORY_LINES);
+        }
+        let markers: Vec<usize> = lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| is_history_marker(line))
+            .map(|(i, _)| i)
+            .collect();
+        let scroll = markers.last().copied().unwrap_or(0);
+        Self {
+            lines,
+            markers,
+            scroll,
+        }
+    }
+
+    /// Read the newest runs' transcripts for `project_root`.
+    pub fn load(project_root: &std::path::Path) -> Self {
+        let mut runs: Vec<(String, String)> = logger::list_runs(project_root)
+            .into_iter()
+            .take(MAX_HISTORY_RUNS)
+            .filter_map(|run| {
+                let dir = logger::run_directory(project_root, &run.run_id);
+                let text = logger::read_run(&dir).ok()?;
+                (!text.trim().is_empty()).then_some((run.run_id, text))
+            })
+            .collect();
+        runs.reverse();
+        Self::from_runs(&runs)
+    }
+
+    /// Scroll by `delta` lines, clamped to the transcript.
+    pub fn scroll_by(&mut self, delta: isize) {
+        let max = self.lines.len().saturating_sub(1);
+        self.scroll = self.scroll.saturating_add_signed(delta).min(max);
+    }
+
+    /// Jump to the next (`forward`) or previous run or iteration marker.
+    pub fn jump(&mut self, forward: bool) {
+        let target = if forward {
+            self.markers.iter().find(|&&m| m > self.scroll)
+        } else {
+            self.markers.iter().rev().find(|&&m| m < self.scroll)
+        };
+        if let Some(&m) = target {
+            self.scroll = m;
+        }
+    }
+
+    /// 1-based position of the marker at or above the scroll offset, and the
+    /// marker count.
+    pub fn position(&self) -> (usize, usize) {
+        let current = self.markers.iter().filter(|&&m| m <= self.scroll).count();
+        (current, self.markers.len())
+    }
+}
+
+/// Run header (`═════ run …`) or iteration heading (`# <task> …`).
+pub fn is_history_marker(line: &str) -> bool {
+    line.starts_with("═════ run ") || line.starts_with("# ")
+}
+
 /// Optional modal rendered above the base screen.
 #[derive(Debug, Clone)]
 pub enum UiModal {
@@ -36,6 +167,10 @@ pub enum UiScreen {
         title: String,
         lines: Vec<String>,
         scroll: usize,
+        /// Result of the last copy or save, shown in place of the key hints.
+        notice: Option<String>,
+        /// Style the lines as markdown (task descriptions, specs, plans).
+        markdown: bool,
     },
 }
 
@@ -44,10 +179,18 @@ pub enum UiScreen {
 pub struct AppState {
     pub status_line: String,
     pub dag_summary: String,
+    /// Burn-down lines; the Progress panel is hidden while empty.
+    pub progress: Vec<String>,
     pub current_task: String,
+    /// Live token/rate/time/cost meter for the Run header, once a session streams.
+    pub meter: Option<StreamMeter>,
     pub tools: VecDeque<ToolLine>,
     pub agent_text: String,
-    /// Cached line count for `agent_text` to avoid repeated scans in hot paths.
+    /// `agent_text` without thought lines, shown while thoughts are hidden.
+    pub answer_text: String,
+    /// Whether the Agent Stream includes thought lines (toggled with `t`).
+    pub show_thoughts: bool,
+    /// Cached line count of the visible agent text to avoid repeated scans in hot paths.
     pub agent_line_count: usize,
     /// Monotonic revision for agent text updates; used by renderer caches.
     pub agent_revision: u64,
@@ -75,11 +218,33 @@ pub struct AppState {
     pub input_choices: Option<Vec<String>>,
     /// Which choice is highlighted in choice mode.
     pub input_choice_cursor: usize,
+    /// Check marks per choice; `Some` makes choice mode a multi-select.
+    pub input_checked: Option<Vec<bool>>,
+    /// Whether the Input pane holds a steering message (opened with `m`)
+    /// rather than answering a prompt.
+    pub input_steering: bool,
     /// Ring buffer of structured orchestration events for the Events panel.
     pub events: VecDeque<EventLine>,
     /// When `None`, Events panel auto-scrolls to the bottom.
     /// When `Some(offset)`, the user has pinned the scroll position.
     pub events_scroll: Option<usize>,
+    pub event_filter: EventFilter,
+    /// Case-insensitive text the shown events must contain; empty = no search.
+    pub event_search: String,
+    /// Whether keystrokes are going to `event_search` (after `/`).
+    pub event_search_editing: bool,
+    /// Per-file diffs written this iteration, in first-write order.
+    pub diffs: Vec<FileDiff>,
+    /// Whether the Diff panel replaces the Agent Stream (toggled with `d`).
+    pub diff_panel: bool,
+    /// Index into `diffs` of the file shown in the Diff panel.
+    pub diff_selected: usize,
+    /// Scroll offset within the selected diff.
+    pub diff_scroll: usize,
+    /// Project whose run logs the History panel reads.
+    pub history_root: Option<PathBuf>,
+    /// When `Some`, the History panel replaces the Agent Stream (`h`).
+    pub history: Option<RunHistory>,
 }
 
 impl Default for AppState {
@@ -87,9 +252,13 @@ impl Default for AppState {
         Self {
             status_line: "Starting".to_string(),
             dag_summary: "DAG: n/a".to_string(),
+            progress: Vec::new(),
             current_task: "Task: idle".to_string(),
+            meter: None,
             tools: VecDeque::new(),
             agent_text: String::new(),
+            answer_text: String::new(),
+            show_thoughts: true,
             agent_line_count: 0,
             agent_revision: 0,
             agent_scroll: None,
@@ -104,8 +273,19 @@ impl Default for AppState {
             input_cursor: 0,
             input_choices: None,
             input_choice_cursor: 0,
+            input_checked: None,
+            input_steering: false,
             events: VecDeque::new(),
             events_scroll: None,
+            event_filter: EventFilter::All,
+            event_search: String::new(),
+            event_search_editing: false,
+            diffs: Vec::new(),
+            diff_panel: false,
+            diff_selected: 0,
+            diff_scroll: 0,
+            history_root: None,
+            history: None,
         }
     }
 }
@@ -119,6 +299,9 @@ impl AppState {
             UiEvent::DagSummary(line) => {
                 self.dag_summary = line;
             }
+            UiEvent::Progress(lines) => {
+                self.progress = lines;
+            }
             UiEvent::CurrentTask(line) => {
                 self.current_task = line;
             }
@@ -134,6 +317,7 @@ impl AppState {
                 }
 
                 append_collapsed_text(&mut self.agent_text, &text);
+                append_collapsed_text(&mut self.answer_text, &text);
                 self.cap_agent_text();
             }
             UiEvent::AgentThinking(text) => {
@@ -157,9 +341,16 @@ impl AppState {
                 if !self.agent_text.is_empty() && !self.agent_text.ends_with('\n') {
                     self.agent_text.push('\n');
                 }
-                self.agent_text
-                    .push_str(&format!("\n───── iteration {iteration} ─────\n\n"));
+                if !self.answer_text.is_empty() && !self.answer_text.ends_with('\n') {
+                    self.answer_text.push('\n');
+                }
+                let divider = format!("\n───── iteration {iteration} ─────\n\n");
+                self.agent_text.push_str(&divider);
+                self.answer_text.push_str(&divider);
                 self.cap_agent_text();
+                self.diffs.clear();
+                self.diff_selected = 0;
+                self.diff_scroll = 0;
             }
             UiEvent::ToolActivity(tool_line) => {
                 self.tools.push_back(tool_line);
@@ -190,33 +381,122 @@ impl AppState {
                     }
                 }
             }
+            UiEvent::FileDiff(mut file_diff) => {
+                let line_count = file_diff.diff.lines().count();
+                if line_count > MAX_DIFF_LINES {
+                    let kept: Vec<&str> = file_diff.diff.lines().take(MAX_DIFF_LINES).collect();
+                    file_diff.diff = format!(
+                        "{}\n… {} more lines",
+                        kept.join("\n"),
+                        line_count - MAX_DIFF_LINES
+                    );
+                }
+                // A rewrite of a file already shown replaces its diff in place.
+                match self.diffs.iter_mut().find(|d| d.path == file_diff.path) {
+                    Some(existing) => *existing = file_diff,
+                    None => self.diffs.push(file_diff),
+                }
+            }
+            UiEvent::RunLogs(project_root) => {
+                self.history_root = Some(project_root);
+            }
+            UiEvent::Meter(meter) => {
+                self.meter = Some(meter);
+            }
         }
     }
 
-    /// Cap `agent_text` to `MAX_AGENT_CHARS`, update line count and revision.
-    fn cap_agent_text(&mut self) {
-        if self.agent_text.len() > MAX_AGENT_CHARS {
-            let mut split = self.agent_text.len() - MAX_AGENT_CHARS;
-            while split < self.agent_text.len() && !self.agent_text.is_char_boundary(split) {
-                split += 1;
-            }
-            if split < self.agent_text.len() {
-                self.agent_text.drain(..split);
-            }
+    /// Show or hide thought lines in the Agent Stream.
+    pub fn toggle_thoughts(&mut self) {
+        self.show_thoughts = !self.show_thoughts;
+        self.agent_scroll = None;
+        self.cap_agent_text();
+    }
+
+    /// Switch to the next theme (`T`) and note the result in the Events panel.
+    pub fn cycle_theme(&mut self) {
+        let (message, is_error) = match theme::cycle() {
+            Ok(name) => (format!("theme: {name}"), false),
+            Err(e) => (format!("theme not loaded: {e:#}"), true),
+        };
+        // Cached markdown lines carry the old theme's styles.
+        self.agent_revision = self.agent_revision.wrapping_add(1);
+        self.apply(UiEvent::Event(EventLine {
+            category: "config".to_string(),
+            message,
+            timestamp: chrono::Local::now().format("%H:%M:%S").to_string(),
+            is_error,
+        }));
+    }
+
+    /// Agent Stream text as currently shown: with or without thoughts.
+    pub fn visible_agent_text(&self) -> &str {
+        if self.show_thoughts {
+            &self.agent_text
+        } else {
+            &self.answer_text
         }
-        self.agent_line_count = if self.agent_text.is_empty() {
+    }
+
+    /// Show or hide the Diff panel.
+    pub fn toggle_diff_panel(&mut self) {
+        self.diff_panel = !self.diff_panel;
+    }
+
+    /// Open the History panel, reading the run logs afresh, or close it.
+    pub fn toggle_history(&mut self) {
+        self.history = match (&self.history, &self.history_root) {
+            (Some(_), _) => None,
+            (None, Some(root)) => Some(RunHistory::load(root)),
+            (None, None) => Some(RunHistory::default()),
+        };
+    }
+
+    /// Select the next (`forward`) or previous file in the Diff panel, wrapping.
+    pub fn diff_select(&mut self, forward: bool) {
+        let n = self.diffs.len();
+        if n == 0 {
+            return;
+        }
+        self.diff_selected = if forward {
+            (self.diff_selected + 1) % n
+        } else {
+            (self.diff_selected + n - 1) % n
+        };
+        self.diff_scroll = 0;
+    }
+
+    /// Scroll the selected diff by `delta` lines, clamped to its length.
+    pub fn diff_scroll_by(&mut self, delta: isize) {
+        let max = self
+            .diffs
+            .get(self.diff_selected)
+            .map(|d| d.diff.lines().count().saturating_sub(1))
+            .unwrap_or(0);
+        self.diff_scroll = self.diff_scroll.saturating_add_signed(delta).min(max);
+    }
+
+    /// Cap `agent_text` and `answer_text` to `MAX_AGENT_CHARS`, update line
+    /// count and revision.
+    fn cap_agent_text(&mut self) {
+        cap_text(&mut self.agent_text);
+        cap_text(&mut self.answer_text);
+        let visible = self.visible_agent_text();
+        self.agent_line_count = if visible.is_empty() {
             0
         } else {
-            self.agent_text.lines().count()
+            visible.lines().count()
         };
         self.agent_revision = self.agent_revision.wrapping_add(1);
     }
 
-    pub fn show_explorer(&mut self, title: String, lines: Vec<String>) {
+    pub fn show_explorer(&mut self, title: String, lines: Vec<String>, markdown: bool) {
         self.screen = UiScreen::Explorer {
             title,
             lines,
             scroll: 0,
+            notice: None,
+            markdown,
         };
     }
 
@@ -253,9 +533,63 @@ impl AppState {
         self.agent_scroll = None;
     }
 
+    /// Events that pass the category filter and the search, oldest first.
+    pub fn visible_events(&self) -> Vec<&EventLine> {
+        let needle = self.event_search.to_lowercase();
+        self.events
+            .iter()
+            .filter(|e| self.event_filter.matches(e))
+            .filter(|e| {
+                needle.is_empty()
+                    || e.message.to_lowercase().contains(&needle)
+                    || e.category.contains(&needle)
+            })
+            .collect()
+    }
+
+    /// Show the next category in the Events panel, returning to all events
+    /// after the last.
+    pub fn cycle_event_filter(&mut self) {
+        let pos = EVENT_FILTER_CYCLE
+            .iter()
+            .position(|f| *f == self.event_filter)
+            .unwrap_or(0);
+        self.event_filter = EVENT_FILTER_CYCLE[(pos + 1) % EVENT_FILTER_CYCLE.len()];
+        self.events_scroll = None;
+    }
+
+    /// Start typing a new event search (`/`).
+    pub fn start_event_search(&mut self) {
+        self.event_search.clear();
+        self.event_search_editing = true;
+        self.events_scroll = None;
+    }
+
+    /// Extend (`Some`) or shorten (`None`) the search being typed.
+    pub fn edit_event_search(&mut self, ch: Option<char>) {
+        match ch {
+            Some(ch) => self.event_search.push(ch),
+            None => {
+                self.event_search.pop();
+            }
+        }
+        self.events_scroll = None;
+    }
+
+    /// Stop typing the search; `keep` leaves the events filtered by it.
+    pub fn finish_event_search(&mut self, keep: bool) {
+        self.event_search_editing = false;
+        if !keep {
+            self.event_search.clear();
+        }
+        self.events_scroll = None;
+    }
+
     /// Scroll the Events panel up by `n` lines. Activates pinned scroll mode.
     pub fn events_scroll_up(&mut self, n: usize) {
-        let current = self.events_scroll.unwrap_or(self.events.len());
+        let current = self
+            .events_scroll
+            .unwrap_or_else(|| self.visible_events().len());
         self.events_scroll = Some(current.saturating_sub(n));
     }
 
@@ -324,18 +658,66 @@ impl AppState {
         self.input_cursor = 0;
         self.input_choices = choices;
         self.input_choice_cursor = 0;
+        self.input_checked = None;
+        self.input_steering = false;
         self.input_scroll = 0;
     }
 
+    /// Open the input pane for a steering message to the running agent.
+    pub fn start_steering(&mut self) {
+        self.activate_input(
+            "Message the agent".to_string(),
+            "Delivered to the running agent between tool calls; it keeps working.".to_string(),
+            None,
+        );
+        self.input_steering = true;
+    }
+
+    /// Activate the input pane as a checklist over `choices`.
+    pub fn activate_multi_select(
+        &mut self,
+        title: String,
+        hint: String,
+        choices: Vec<String>,
+        checked: Vec<bool>,
+    ) {
+        self.activate_input(title, hint, Some(choices));
+        self.input_checked = Some(checked);
+    }
+
+    /// Flip the check mark on choice `index`, if it exists.
+    pub fn toggle_checked(&mut self, index: usize) {
+        if let Some(mark) = self
+            .input_checked
+            .as_mut()
+            .and_then(|checked| checked.get_mut(index))
+        {
+            *mark = !*mark;
+        }
+    }
+
+    /// Indices of the checked choices, in order.
+    pub fn checked_indices(&self) -> Vec<usize> {
+        self.input_checked
+            .iter()
+            .flatten()
+            .enumerate()
+            .filter(|(_, &checked)| checked)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
     /// Deactivate the input pane (return to idle).
     pub fn deactivate_input(&mut self) {
         self.input_active = false;
+        self.input_steering = false;
         self.input_title = "Input".to_string();
         self.input_hint = "Waiting for agent...".to_string();
         self.input_text.clear();
         self.input_cursor = 0;
         self.input_choices = None;
         self.input_choice_cursor = 0;
+        self.input_checked = None;
         self.input_scroll = 0;
     }
 
@@ -352,6 +734,20 @@ impl AppState {
             }
         }
     }
+
+    /// Title and lines of the open explorer view.
+    pub fn explorer_view(&self) -> Option<(&str, &[String])> {
+        match &self.screen {
+            UiScreen::Explorer { title, lines, .. } => Some((title, lines)),
+            UiScreen::Dashboard => None,
+        }
+    }
+
+    pub fn set_explorer_notice(&mut self, text: String) {
+        if let UiScreen::Explorer { notice, .. } = &mut self.screen {
+            *notice = Some(text);
+        }
+    }
 }
 
 /// Append text while collapsing newline runs to at most two `\n` chars.
@@ -371,6 +767,19 @@ fn append_collapsed_text(dst: &mut String, src: &str) {
     }
 }
 
+/// Drop the oldest text beyond `MAX_AGENT_CHARS`, on a char boundary.
+fn cap_text(text: &mut String) {
+    if text.len() > MAX_AGENT_CHARS {
+        let mut split = text.len() - MAX_AGENT_CHARS;
+        while split < text.len() && !text.is_char_boundary(split) {
+            split += 1;
+        }
+        if split < text.len() {
+            text.drain(..split);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -382,6 +791,24 @@ mod tests {
         assert_eq!(state.status_line, "iter 2");
     }
 
+    #[test]
+    fn thoughts_can_be_hidden_from_the_agent_stream() {
+        let mut state = AppState::default();
+        state.apply(UiEvent::AgentThinking("weighing options".to_string()));
+        state.apply(UiEvent::AgentText("Done.".to_string()));
+        assert!(state.visible_agent_text().contains("  weighing options"));
+        let shown_lines = state.agent_line_count;
+
+        state.toggle_thoughts();
+        assert_eq!(state.visible_agent_text(), "Done.");
+        assert!(state.agent_line_count < shown_lines);
+        state.apply(UiEvent::AgentThinking("more".to_string()));
+        assert_eq!(state.visible_agent_text(), "Done.");
+
+        state.toggle_thoughts();
+        assert!(state.visible_agent_text().ends_with("  more\n"));
+    }
+
     #[test]
     fn agent_text_is_capped_and_utf8_safe() {
         let mut state = AppState::default();
@@ -472,28 +899,31 @@ mod tests {
     #[test]
     fn events_ring_buffer_caps_at_max() {
         let mut state = AppState::default();
-        for i in 0..201 {
+        for i in 0..=MAX_EVENT_LINES {
             state.apply(UiEvent::Event(make_event("iter", &format!("event {i}"))));
         }
-        assert_eq!(state.events.len(), 200);
+        assert_eq!(state.events.len(), MAX_EVENT_LINES);
         // First event (index 0) should have been dropped; front is now event 1.
         assert_eq!(state.events[0].message, "event 1");
-        assert_eq!(state.events[199].message, "event 200");
+        assert_eq!(
+            state.events[MAX_EVENT_LINES - 1].message,
+            format!("event {MAX_EVENT_LINES}")
+        );
     }
 
     #[test]
     fn events_ring_buffer_adjusts_scroll_offset() {
         let mut state = AppState::default();
         // Fill to capacity.
-        for i in 0..200 {
+        for i in 0..MAX_EVENT_LINES {
             state.apply(UiEvent::Event(make_event("dag", &format!("event {i}"))));
         }
         // Pin scroll at offset 10.
         state.events_scroll = Some(10);
 
         // Push one more — drops one from front, offset should decrease by 1.
-        state.apply(UiEvent::Event(make_event("dag", "event 200")));
-        assert_eq!(state.events.len(), 200);
+        state.apply(UiEvent::Event(make_event("dag", "event new")));
+        assert_eq!(state.events.len(), MAX_EVENT_LINES);
         assert_eq!(state.events_scroll, Some(9));
 
         // Pin scroll at 0, push another — offset stays at 0 (saturating_sub).
@@ -548,9 +978,59 @@ mod tests {
     }
 
     #[test]
-    fn events_scroll_to_bottom() {
+    fn events_filter_by_category_and_search() {
         let mut state = AppState::default();
-        state.events_scroll = Some(15);
+        state.apply(UiEvent::Event(make_event("iter", "iteration 1 started")));
+        state.apply(UiEvent::Event(make_event("task", "t-abc12345 claimed")));
+        state.apply(UiEvent::Event(make_event("verify", "t-abc12345 passed")));
+        let mut failed = make_event("task", "t-def67890 failed");
+        failed.is_error = true;
+        state.apply(UiEvent::Event(failed));
+        let messages = |state: &AppState| -> Vec<String> {
+            state
+                .visible_events()
+                .iter()
+                .map(|e| e.message.clone())
+                .collect()
+        };
+
+        state.cycle_event_filter(); // iter
+        assert_eq!(messages(&state), vec!["iteration 1 started"]);
+        state.cycle_event_filter(); // task
+        assert_eq!(state.event_filter.label(), Some("task"));
+        assert_eq!(
+            messages(&state),
+            vec!["t-abc12345 claimed", "t-def67890 failed"]
+        );
+        for _ in 0..3 {
+            state.cycle_event_filter(); // verify, dag, errors
+        }
+        assert_eq!(messages(&state), vec!["t-def67890 failed"]);
+        state.cycle_event_filter();
+        assert_eq!(state.event_filter, EventFilter::All);
+
+        state.start_event_search();
+        for ch in "ABC1".chars() {
+            state.edit_event_search(Some(ch));
+        }
+        assert_eq!(
+            messages(&state),
+            vec!["t-abc12345 claimed", "t-abc12345 passed"]
+        );
+        state.edit_event_search(None);
+        state.finish_event_search(true);
+        assert!(!state.event_search_editing);
+        assert_eq!(state.event_search, "ABC");
+        state.finish_event_search(false);
+        assert_eq!(messages(&state).len(), 4);
+    }
+
+    #[test]
+    fn events_scroll_to_bottom() {
+        let mut state = AppState {
+            events_scroll: Some(15),
+            ..Default::default()
+        };
         state.events_scroll_to_bottom();
         assert_eq!(state.events_scroll, None);
     }
@@ -561,6 +1041,7 @@ mod tests {
         state.show_explorer(
             "Tasks".to_string(),
             vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            false,
         );
         state.explorer_scroll_up();
         if let UiScreen::Explorer { scroll, .. } = &state.screen {
@@ -576,5 +1057,39 @@ mod tests {
         } else {
             panic!("expected explorer");
         }
+        assert_eq!(state.explorer_view().unwrap().0, "Tasks");
+        state.set_explorer_notice("Copied 3 line(s)".to_string());
+        assert!(matches!(
+            &state.screen,
+            UiScreen::Explorer { notice: Some(n), .. } if n == "Copied 3 line(s)"
+        ));
+    }
+
+    #[test]
+    fn file_diffs_replace_by_path_and_reset_each_iteration() {
+        let diff = |path: &str, body: &str| {
+            UiEvent::FileDiff(FileDiff {
+                path: path.to_string(),
+                diff: body.to_string(),
+            })
+        };
+        let mut state = AppState::default();
+        state.apply(diff("a.rs", "+one"));
+        state.apply(diff("b.rs", "+two"));
+        state.apply(diff("a.rs", "+one\n+three"));
+        assert_eq!(state.diffs.len(), 2);
+        assert_eq!(state.diffs[0].diff, "+one\n+three");
+
+        state.diff_select(false);
+        assert_eq!(state.diff_selected, 1);
+        state.diff_select(true);
+        assert_eq!(state.diff_selected, 0);
+        state.diff_scroll_by(10);
+        assert_eq!(state.diff_scroll, 1);
+        state.diff_scroll_by(-5);
+        assert_eq!(state.diff_scroll, 0);
+
+        state.apply(UiEvent::IterationDivider { iteration: 2 });
+        assert!(state.diffs.is_empty());
     }
 }
@@ -1,17 +1,22 @@
 //! Color and style tokens for the ratatui dashboard.
 //!
-//! Supports light and dark themes with optional per-token color overrides.
-//! The active theme is resolved at startup from `RALPH_THEME` env var,
-//! `[ui].theme` in `.ralph.toml`, or defaults to `light`. Users can set
-//! individual color overrides in `[ui.colors]` that layer on top of the
-//! base theme.
+//! Supports the built-in light and dark themes plus theme files from
+//! `.ralph/themes/<name>.toml` (project) and `$XDG_CONFIG_HOME/ralph/themes/`
+//! (user), with optional per-token color overrides. The active theme is
+//! resolved at startup from `RALPH_THEME` env var, `[ui].theme` in
+//! `.ralph.toml`, or defaults to `light`. Users can set individual color
+//! overrides in `[ui.colors]` that layer on top of whichever theme is active.
+//! The dashboard switches between the available themes with `T`.
 //!
 //! All rendering code reads from the resolved theme via the public
 //! `theme::*()` accessor functions.
 
-use std::sync::OnceLock;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock, RwLock, RwLockReadGuard};
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use ratatui::style::{Color, Modifier, Style};
 use serde::Deserialize;
 
@@ -62,6 +67,9 @@ pub struct Theme {
     pub event_interrupt_fg: Color,
     pub event_dag_fg: Color,
     pub event_config_fg: Color,
+    // Diff panel tokens
+    pub diff_added_fg: Color,
+    pub diff_removed_fg: Color,
 }
 
 impl Theme {
@@ -106,6 +114,8 @@ impl Theme {
             event_interrupt_fg: Color::Red,
             event_dag_fg: Color::Rgb(180, 180, 180),
             event_config_fg: Color::Rgb(180, 180, 180),
+            diff_added_fg: Color::Green,
+            diff_removed_fg: Color::Red,
         }
     }
 
@@ -150,6 +160,8 @@ impl Theme {
             event_interrupt_fg: Color::Red,
             event_dag_fg: Color::Rgb(80, 80, 80),
             event_config_fg: Color::Rgb(80, 80, 80),
+            diff_added_fg: Color::Rgb(0, 140, 0),
+            diff_removed_fg: Color::Rgb(190, 0, 0),
         }
     }
 }
@@ -244,11 +256,18 @@ pub struct ColorOverrides {
     pub event_interrupt: Option<String>,
     pub event_dag: Option<String>,
     pub event_config: Option<String>,
+    pub diff_added: Option<String>,
+    pub diff_removed: Option<String>,
 }
 
 impl ColorOverrides {
     /// Validate all color values, returning an error for the first invalid one.
     pub fn validate(&self) -> Result<()> {
+        self.validate_section("ui.colors")
+    }
+
+    /// `validate`, naming invalid keys as `<section>.<key>`.
+    fn validate_section(&self, section: &str) -> Result<()> {
         let fields: &[(&str, &Option<String>)] = &[
             ("background", &self.background),
             ("border", &self.border),
@@ -285,11 +304,13 @@ impl ColorOverrides {
             ("event_interrupt", &self.event_interrupt),
             ("event_dag", &self.event_dag),
             ("event_config", &self.event_config),
+            ("diff_added", &self.diff_added),
+            ("diff_removed", &self.diff_removed),
         ];
         for (name, value) in fields {
             if let Some(v) = value {
                 parse_color(v)
-                    .map_err(|e| anyhow::anyhow!("invalid color for ui.colors.{name}: {e}"))?;
+                    .map_err(|e| anyhow::anyhow!("invalid color for {section}.{name}: {e}"))?;
             }
         }
         Ok(())
@@ -340,11 +361,13 @@ impl ColorOverrides {
         set(&mut theme.event_interrupt_fg, &self.event_interrupt);
         set(&mut theme.event_dag_fg, &self.event_dag);
         set(&mut theme.event_config_fg, &self.event_config);
+        set(&mut theme.diff_added_fg, &self.diff_added);
+        set(&mut theme.diff_removed_fg, &self.diff_removed);
         theme
     }
 }
 
-/// Name of the active theme.
+/// Name of a built-in theme.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ThemeName {
     Light,
@@ -360,49 +383,225 @@ impl ThemeName {
             _ => None,
         }
     }
+
+    fn theme(self) -> Theme {
+        match self {
+            Self::Light => Theme::light(),
+            Self::Dark => Theme::dark(),
+        }
+    }
+}
+
+/// Built-in theme names, in switching order.
+pub const BUILTIN_THEMES: [&str; 2] = ["light", "dark"];
+
+/// Project theme directory, relative to the project root.
+pub const THEMES_DIR: &str = ".ralph/themes";
+
+/// A theme file: a built-in base plus colors for any token, using the same
+/// keys as `[ui.colors]`.
+///
+/// ```toml
+/// base = "dark"
+/// description = "Solarized dark"
+///
+/// [colors]
+/// background = "#002b36"
+/// heading = "#268bd2"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ThemeFile {
+    /// Built-in theme the file starts from (default: dark).
+    #[serde(default)]
+    pub base: Option<String>,
+    /// One line shown by `ralph ui themes`.
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub colors: ColorOverrides,
+}
+
+impl ThemeFile {
+    /// Read and validate a theme file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read theme file {}", path.display()))?;
+        let file: Self = toml::from_str(&text)
+            .with_context(|| format!("Invalid theme file {}", path.display()))?;
+        if let Some(base) = &file.base {
+            if ThemeName::parse(base).is_none() {
+                bail!(
+                    "{}: invalid base '{base}' (expected light or dark)",
+                    path.display()
+                );
+            }
+        }
+        file.colors
+            .validate_section("colors")
+            .with_context(|| format!("Invalid theme file {}", path.display()))?;
+        Ok(file)
+    }
+
+    fn theme(&self) -> Theme {
+        let base = self
+            .base
+            .as_deref()
+            .and_then(ThemeName::parse)
+            .unwrap_or(ThemeName::Dark);
+        self.colors.apply_to(base.theme())
+    }
 }
 
-static ACTIVE_THEME: OnceLock<Theme> = OnceLock::new();
+/// A theme that can be activated: built-in, or loaded from a file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThemeEntry {
+    pub name: String,
+    /// Theme file, or `None` for a built-in theme.
+    pub path: Option<PathBuf>,
+}
+
+impl ThemeEntry {
+    /// Build the theme's tokens, reading its file if it has one.
+    pub fn load(&self) -> Result<Theme> {
+        match (&self.path, ThemeName::parse(&self.name)) {
+            (Some(path), _) => Ok(ThemeFile::load(path)?.theme()),
+            (None, Some(name)) => Ok(name.theme()),
+            (None, None) => bail!("unknown theme '{}'", self.name),
+        }
+    }
+}
+
+/// User theme directory: `$XDG_CONFIG_HOME/ralph/themes`, else
+/// `~/.config/ralph/themes`.
+pub fn user_themes_dir() -> Option<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config.join("ralph").join("themes"))
+}
 
-/// Resolve the theme from `RALPH_THEME` env var (overrides config), then
-/// the config file value. Falls back to `Light` if neither is set.
-pub fn resolve_theme_name(config_theme: &str) -> ThemeName {
+/// Every theme available to a project: the built-ins, then theme files by
+/// name. A project file shadows a user file of the same name, and a file
+/// named `light` or `dark` replaces that built-in.
+pub fn available(project_root: &Path) -> Vec<ThemeEntry> {
+    let mut dirs: Vec<PathBuf> = user_themes_dir().into_iter().collect();
+    dirs.push(project_root.join(THEMES_DIR));
+    available_in(&dirs)
+}
+
+/// `available`, reading theme files from `dirs` (later directories win).
+fn available_in(dirs: &[PathBuf]) -> Vec<ThemeEntry> {
+    let mut files = BTreeMap::new();
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("toml") {
+                continue;
+            }
+            if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                files.insert(stem.to_string(), path);
+            }
+        }
+    }
+    let mut themes: Vec<ThemeEntry> = BUILTIN_THEMES
+        .iter()
+        .map(|name| ThemeEntry {
+            name: name.to_string(),
+            path: None,
+        })
+        .collect();
+    for (name, path) in files {
+        match themes.iter_mut().find(|t| t.name == name) {
+            Some(builtin) => builtin.path = Some(path),
+            None => themes.push(ThemeEntry {
+                name,
+                path: Some(path),
+            }),
+        }
+    }
+    themes
+}
+
+/// Index in `themes` of the theme named by `RALPH_THEME` (when it names an
+/// available theme), else by the config file value. Falls back to `dark` if
+/// neither matches.
+pub fn resolve_theme(config_theme: &str, themes: &[ThemeEntry]) -> usize {
+    let find = |name: &str| {
+        themes
+            .iter()
+            .position(|t| t.name.eq_ignore_ascii_case(name.trim()))
+    };
     // Env var takes priority.
     if let Ok(val) = std::env::var("RALPH_THEME") {
-        if let Some(name) = ThemeName::parse(&val) {
-            return name;
+        if let Some(i) = find(&val) {
+            return i;
         }
     }
     // Config file value.
-    ThemeName::parse(config_theme).unwrap_or(ThemeName::Dark)
+    find(config_theme).or_else(|| find("dark")).unwrap_or(0)
 }
 
-/// Initialize the active theme with optional color overrides.
+static ACTIVE_THEME: OnceLock<RwLock<Theme>> = OnceLock::new();
+
+/// The themes `cycle()` moves through, and the overrides applied to each.
+struct Switcher {
+    themes: Vec<ThemeEntry>,
+    overrides: ColorOverrides,
+    current: usize,
+}
+
+static SWITCHER: OnceLock<Mutex<Switcher>> = OnceLock::new();
+
+/// Initialize the active theme for a project, with color overrides on top.
 ///
 /// Call once at startup before any rendering. If called multiple times,
 /// subsequent calls are ignored (first write wins). Color overrides should
 /// be validated with `ColorOverrides::validate()` before calling this.
-pub fn init_with_overrides(name: ThemeName, overrides: Option<&ColorOverrides>) {
-    let mut theme = match name {
-        ThemeName::Light => Theme::light(),
-        ThemeName::Dark => Theme::dark(),
-    };
-    if let Some(ov) = overrides {
-        theme = ov.apply_to(theme);
+/// Fails when the selected theme file cannot be loaded.
+pub fn init_for_project(
+    project_root: &Path,
+    config_theme: &str,
+    overrides: &ColorOverrides,
+) -> Result<()> {
+    let themes = available(project_root);
+    let current = resolve_theme(config_theme, &themes);
+    let theme = overrides.apply_to(themes[current].load()?);
+    if ACTIVE_THEME.set(RwLock::new(theme)).is_ok() {
+        let _ = SWITCHER.set(Mutex::new(Switcher {
+            themes,
+            overrides: overrides.clone(),
+            current,
+        }));
     }
-    let _ = ACTIVE_THEME.set(theme);
+    Ok(())
 }
 
-/// Initialize the active theme without color overrides.
-/// Convenience wrapper around `init_with_overrides`.
-#[allow(dead_code)]
-pub fn init(name: ThemeName) {
-    init_with_overrides(name, None);
+/// Switch to the next available theme and return its name. A theme file
+/// that fails to load is reported and skipped over on the next call.
+pub fn cycle() -> Result<String> {
+    let Some(switcher) = SWITCHER.get() else {
+        bail!("no themes to switch between");
+    };
+    let mut switcher = switcher.lock().unwrap_or_else(|e| e.into_inner());
+    switcher.current = (switcher.current + 1) % switcher.themes.len();
+    let entry = &switcher.themes[switcher.current];
+    let theme = switcher.overrides.apply_to(entry.load()?);
+    let name = entry.name.clone();
+    *active_lock().write().unwrap_or_else(|e| e.into_inner()) = theme;
+    Ok(name)
+}
+
+fn active_lock() -> &'static RwLock<Theme> {
+    ACTIVE_THEME.get_or_init(|| RwLock::new(Theme::dark()))
 }
 
 /// Get the active theme. Falls back to dark if not initialized.
-fn active() -> &'static Theme {
-    ACTIVE_THEME.get_or_init(Theme::dark)
+fn active() -> RwLockReadGuard<'static, Theme> {
+    active_lock().read().unwrap_or_else(|e| e.into_inner())
 }
 
 pub fn background() -> Color {
@@ -546,6 +745,18 @@ pub fn error() -> Style {
     Style::default().fg(t.error_fg).bg(t.background)
 }
 
+/// Style for added lines in the diff panel.
+pub fn diff_added() -> Style {
+    let t = active();
+    Style::default().fg(t.diff_added_fg).bg(t.background)
+}
+
+/// Style for removed lines in the diff panel.
+pub fn diff_removed() -> Style {
+    let t = active();
+    Style::default().fg(t.diff_removed_fg).bg(t.background)
+}
+
 #[allow(dead_code)]
 pub fn info() -> Style {
     let t = active();
@@ -642,7 +853,7 @@ pub fn event_category_style(category: &str) -> Style {
         "review" => event_review(),
         "journal" => event_journal(),
         "knowledge" => event_knowledge(),
-        "interrupt" => event_interrupt(),
+        "interrupt" | "steer" | "guard" | "offline" => event_interrupt(),
         "dag" => event_dag(),
         "config" => event_config(),
         _ => subdued(),
@@ -687,32 +898,103 @@ mod tests {
         assert_eq!(t.title_fg, Color::Blue);
     }
 
+    fn builtins() -> Vec<ThemeEntry> {
+        available_in(&[])
+    }
+
     #[test]
     fn resolve_falls_back_to_dark() {
         // With no env var set, unknown config value falls back to dark.
         std::env::remove_var("RALPH_THEME");
-        assert_eq!(resolve_theme_name("invalid"), ThemeName::Dark);
+        let themes = builtins();
+        assert_eq!(themes[resolve_theme("invalid", &themes)].name, "dark");
     }
 
     #[test]
     fn resolve_uses_config_value() {
         std::env::remove_var("RALPH_THEME");
-        assert_eq!(resolve_theme_name("dark"), ThemeName::Dark);
-        assert_eq!(resolve_theme_name("light"), ThemeName::Light);
+        let themes = builtins();
+        assert_eq!(themes[resolve_theme("dark", &themes)].name, "dark");
+        assert_eq!(themes[resolve_theme("light", &themes)].name, "light");
     }
 
     #[test]
     fn resolve_env_overrides_config() {
         std::env::set_var("RALPH_THEME", "dark");
-        assert_eq!(resolve_theme_name("light"), ThemeName::Dark);
+        let themes = builtins();
+        assert_eq!(themes[resolve_theme("light", &themes)].name, "dark");
         std::env::remove_var("RALPH_THEME");
     }
 
     #[test]
     fn resolve_env_invalid_falls_through_to_config() {
         std::env::set_var("RALPH_THEME", "nope");
-        assert_eq!(resolve_theme_name("dark"), ThemeName::Dark);
+        let themes = builtins();
+        assert_eq!(themes[resolve_theme("dark", &themes)].name, "dark");
+        std::env::remove_var("RALPH_THEME");
+    }
+
+    #[test]
+    fn theme_files_are_listed_after_builtins_and_project_wins() {
+        let user = tempfile::TempDir::new().unwrap();
+        let project = tempfile::TempDir::new().unwrap();
+        fs::write(user.path().join("solarized.toml"), "base = \"light\"\n").unwrap();
+        fs::write(user.path().join("nord.toml"), "").unwrap();
+        fs::write(user.path().join("notes.txt"), "").unwrap();
+        fs::write(project.path().join("solarized.toml"), "").unwrap();
+        fs::write(project.path().join("dark.toml"), "").unwrap();
+
+        let themes = available_in(&[user.path().to_path_buf(), project.path().to_path_buf()]);
+        let names: Vec<&str> = themes.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["light", "dark", "nord", "solarized"]);
+        assert_eq!(themes[0].path, None);
+        assert_eq!(themes[1].path, Some(project.path().join("dark.toml")));
+        assert_eq!(themes[3].path, Some(project.path().join("solarized.toml")));
+
         std::env::remove_var("RALPH_THEME");
+        assert_eq!(themes[resolve_theme("Nord", &themes)].name, "nord");
+    }
+
+    #[test]
+    fn theme_file_applies_colors_over_its_base() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("paper.toml");
+        fs::write(
+            &path,
+            r##"base = "light"
+description = "High contrast"
+
+[colors]
+heading = "#ff0000"
+event_task = "magenta"
+"##,
+        )
+        .unwrap();
+        let file = ThemeFile::load(&path).unwrap();
+        assert_eq!(file.description.as_deref(), Some("High contrast"));
+
+        let entry = ThemeEntry {
+            name: "paper".to_string(),
+            path: Some(path),
+        };
+        let theme = entry.load().unwrap();
+        assert_eq!(theme.heading_fg, Color::Rgb(255, 0, 0));
+        assert_eq!(theme.event_task_fg, Color::Magenta);
+        assert_eq!(theme.title_fg, Theme::light().title_fg);
+    }
+
+    #[test]
+    fn invalid_theme_files_are_rejected() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("bad.toml");
+        fs::write(&path, "[colors]\nborder = \"neon\"\n").unwrap();
+        let err = format!("{:#}", ThemeFile::load(&path).unwrap_err());
+        assert!(err.contains("colors.border"), "got: {err}");
+        assert!(!err.contains("ui.colors"), "got: {err}");
+
+        fs::write(&path, "base = \"sepia\"\n").unwrap();
+        let err = ThemeFile::load(&path).unwrap_err().to_string();
+        assert!(err.contains("invalid base 'sepia'"), "got: {err}");
     }
 
     #[test]
@@ -841,6 +1123,8 @@ mod tests {
             event_interrupt: Some("#2299aa".to_string()),
             event_dag: Some("#33aabb".to_string()),
             event_config: Some("#44bbcc".to_string()),
+            diff_added: Some("#55ccdd".to_string()),
+            diff_removed: Some("#66ddee".to_string()),
         };
         assert!(ov.validate().is_ok());
         let themed = ov.apply_to(Theme::dark());
@@ -849,12 +1133,13 @@ mod tests {
         assert_eq!(themed.cursor_bg, Color::Rgb(0xee, 0xee, 0xee));
         assert_eq!(themed.event_task_fg, Color::Rgb(0xbb, 0x22, 0x33));
         assert_eq!(themed.event_config_fg, Color::Rgb(0x44, 0xbb, 0xcc));
+        assert_eq!(themed.diff_removed_fg, Color::Rgb(0x66, 0xdd, 0xee));
     }
 
     #[test]
     fn event_category_style_returns_non_default_for_all_categories() {
         // Ensure the OnceLock is initialized for this test.
-        let _ = ACTIVE_THEME.set(Theme::dark());
+        let _ = ACTIVE_THEME.set(RwLock::new(Theme::dark()));
 
         let default = Style::default();
         let categories = [
@@ -4,7 +4,8 @@ use ratatui::prelude::*;
 use ratatui::style::Modifier;
 use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
 
-use crate::ui::state::{AppState, FrameAreas, UiModal, UiScreen};
+use crate::output::markdown_table::{self, Table};
+use crate::ui::state::{is_history_marker, AppState, FrameAreas, RunHistory, UiModal, UiScreen};
 use crate::ui::theme;
 
 /// Persistent cache for expensive Agent Stream rendering work.
@@ -64,9 +65,11 @@ pub fn render_with_cache(
             title,
             lines,
             scroll,
+            notice,
+            markdown,
         } => {
             *areas = FrameAreas::default();
-            render_explorer(frame, title, lines, *scroll);
+            render_explorer(frame, title, lines, *scroll, notice.as_deref(), *markdown);
         }
     }
 
@@ -105,6 +108,7 @@ fn render_dashboard(
     .block(
         Block::default()
             .title("Run")
+            .title(run_meter(state))
             .borders(Borders::ALL)
             .border_style(theme::border()),
     );
@@ -115,16 +119,48 @@ fn render_dashboard(
         .constraints([Constraint::Percentage(42), Constraint::Percentage(58)])
         .split(root[1]);
 
-    // Split left column into Events (top) and Tool Activity (bottom).
+    // Split left column into Events (top) and Tool Activity (bottom), with
+    // the Progress panel under them once there are burn-down lines.
+    let progress_height = match state.progress.len() {
+        0 => 0,
+        n => n as u16 + 2,
+    };
+    let left_column = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(progress_height)])
+        .split(body[0]);
     let left_panels = Layout::default()
         .direction(Direction::Vertical)
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-        .split(body[0]);
+        .split(left_column[0]);
+    if progress_height > 0 {
+        let lines: Vec<Line<'_>> = state
+            .progress
+            .iter()
+            .map(|line| Line::from(Span::styled(line.as_str(), theme::accent())))
+            .collect();
+        let progress_panel = Paragraph::new(lines).block(
+            Block::default()
+                .title("Progress")
+                .borders(Borders::ALL)
+                .border_style(theme::border()),
+        );
+        frame.render_widget(progress_panel, left_column[1]);
+    }
 
-    // Top-left: Events panel.
-    let event_lines: Vec<Line<'_>> = state
-        .events
+    // Top-left: Events panel. Only the visible window of the (possibly
+    // filtered) scrollback is turned into lines.
+    let visible_events = state.visible_events();
+    let events_inner_h = left_panels[0].height.saturating_sub(2) as usize;
+    let events_max_offset = visible_events.len().saturating_sub(events_inner_h);
+    let events_scroll_offset = match state.events_scroll {
+        Some(pinned) => pinned.min(events_max_offset),
+        None => events_max_offset, // auto-scroll to bottom
+    };
+    let event_lines: Vec<Line<'_>> = visible_events
         .iter()
+        .skip(events_scroll_offset)
+        .take(events_inner_h)
         .map(|el| {
             Line::from(vec![
                 Span::styled(el.timestamp.clone(), theme::subdued()),
@@ -145,28 +181,28 @@ fn render_dashboard(
             ])
         })
         .collect();
-    let events_inner_h = left_panels[0].height.saturating_sub(2) as usize;
-    let events_max_offset = event_lines.len().saturating_sub(events_inner_h);
-    let events_scroll_offset = match state.events_scroll {
-        Some(pinned) => pinned.min(events_max_offset),
-        None => events_max_offset, // auto-scroll to bottom
-    };
-    let events_title = if state.events_scroll.is_some() {
-        format!(
-            "Events [scroll {}/{}]",
+    let mut events_title = "Events".to_string();
+    if let Some(label) = state.event_filter.label() {
+        events_title.push_str(&format!(" [{label}]"));
+    }
+    if state.event_search_editing || !state.event_search.is_empty() {
+        events_title.push_str(&format!(" /{}", state.event_search));
+        if state.event_search_editing {
+            events_title.push('_');
+        }
+    }
+    if state.events_scroll.is_some() {
+        events_title.push_str(&format!(
+            " [scroll {}/{}]",
             events_scroll_offset, events_max_offset
-        )
-    } else {
-        "Events".to_string()
-    };
-    let events_panel = Paragraph::new(event_lines)
-        .block(
-            Block::default()
-                .title(events_title)
-                .borders(Borders::ALL)
-                .border_style(theme::border()),
-        )
-        .scroll((events_scroll_offset as u16, 0));
+        ));
+    }
+    let events_panel = Paragraph::new(event_lines).block(
+        Block::default()
+            .title(events_title)
+            .borders(Borders::ALL)
+            .border_style(theme::border()),
+    );
     frame.render_widget(events_panel, left_panels[0]);
     areas.events = Some(left_panels[0]);
 
@@ -243,21 +279,83 @@ fn render_dashboard(
         .constraints([Constraint::Min(0), Constraint::Length(input_height)])
         .split(body[1]);
 
-    // Agent Stream: render markdown-styled lines with scroll support.
-    let inner_height = right[0].height.saturating_sub(2) as usize; // subtract border
-    let inner_width = right[0].width.saturating_sub(2).max(1) as usize; // subtract border
+    if let Some(history) = &state.history {
+        render_history_panel(frame, right[0], history);
+    } else if state.diff_panel {
+        render_diff_panel(frame, right[0], state);
+    } else {
+        render_agent_stream(frame, right[0], state, agent_cache);
+    }
+    areas.agent = Some(right[0]);
+
+    render_input_pane(frame, right[1], state);
+    areas.input = Some(right[1]);
+
+    let footer_text = if state.input_active && state.input_checked.is_some() {
+        "PgUp/PgDn scroll agent · ↑/↓ choices · Space/1-9 toggle · a all · Enter submit · Esc exit"
+    } else if state.input_active && state.input_choices.is_some() {
+        "PgUp/PgDn scroll agent · ↑/↓ choices · 1-9 quick-select · Mouse wheel scrolls panels · Esc exit"
+    } else if state.input_steering {
+        "Enter=send to agent · Shift+Enter=newline · ↑/↓/←/→ navigate · Esc cancel"
+    } else if state.input_active {
+        "Enter=submit · Shift+Enter=newline · ↑/↓/←/→ navigate · Mouse wheel scrolls panels"
+    } else if state.event_search_editing {
+        "Type to search events · Backspace delete · Enter keep · Esc clear"
+    } else if state.history.is_some() {
+        "↑/↓ scroll · PgUp/PgDn page · ←/→ previous/next iteration · Home/End first/last · h back to agent stream"
+    } else if state.diff_panel {
+        "←/→ switch file · ↑/↓ scroll diff · d back to agent stream · m message agent · f filter events · / search events · T theme"
+    } else {
+        "↑/↓ scroll agent · End auto-scroll · m message agent · d diffs · h history · t thoughts · f filter events · / search events · T theme · Mouse wheel scrolls panels"
+    };
+    let footer = Paragraph::new(footer_text).style(theme::subdued());
+    frame.render_widget(footer, root[2]);
+}
+
+/// Agent Stream: markdown-styled agent text with scroll support.
+/// The live meter, right-aligned in the Run header's border; shown in the
+/// error style once 80% of the iteration timeout has passed.
+fn run_meter(state: &AppState) -> Line<'static> {
+    let Some(meter) = &state.meter else {
+        return Line::default();
+    };
+    let now = std::time::Instant::now();
+    let style = if meter.near_timeout(now) {
+        theme::error()
+    } else {
+        theme::accent()
+    };
+    Line::from(Span::styled(format!(" {} ", meter.render(now)), style)).right_aligned()
+}
 
-    let (styled_lines, total_lines) =
-        agent_cache.resolve(&state.agent_text, state.agent_revision, inner_width);
+fn render_agent_stream(
+    frame: &mut Frame<'_>,
+    area: Rect,
+    state: &AppState,
+    agent_cache: &mut AgentRenderCache,
+) {
+    let inner_height = area.height.saturating_sub(2) as usize; // subtract border
+    let inner_width = area.width.saturating_sub(2).max(1) as usize; // subtract border
+
+    let (styled_lines, total_lines) = agent_cache.resolve(
+        state.visible_agent_text(),
+        state.agent_revision,
+        inner_width,
+    );
     let max_offset = total_lines.saturating_sub(inner_height);
     let scroll_offset = match state.agent_scroll {
         Some(pinned) => pinned.min(max_offset),
         None => max_offset, // auto-scroll to bottom
     };
+    let title = if state.show_thoughts {
+        "Agent Stream"
+    } else {
+        "Agent Stream (thoughts hidden)"
+    };
     let scroll_indicator = if state.agent_scroll.is_some() {
-        format!("Agent Stream [scroll {}/{}]", scroll_offset, max_offset)
+        format!("{title} [scroll {}/{}]", scroll_offset, max_offset)
     } else {
-        "Agent Stream".to_string()
+        title.to_string()
     };
     let agent = Paragraph::new(styled_lines.to_vec())
         .block(
@@ -268,28 +366,105 @@ fn render_dashboard(
         )
         .wrap(Wrap { trim: false })
         .scroll((scroll_offset as u16, 0));
-    frame.render_widget(agent, right[0]);
-    areas.agent = Some(right[0]);
+    frame.render_widget(agent, area);
+}
 
-    render_input_pane(frame, right[1], state);
-    areas.input = Some(right[1]);
+/// Diff panel: unified diff of one file written this iteration.
+fn render_diff_panel(frame: &mut Frame<'_>, area: Rect, state: &AppState) {
+    let block = |title: String| {
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+    };
+    let Some(file) = state.diffs.get(state.diff_selected) else {
+        let empty = Paragraph::new("No files written this iteration.")
+            .style(theme::subdued())
+            .block(block("Diff".to_string()));
+        frame.render_widget(empty, area);
+        return;
+    };
 
-    let footer_text = if state.input_active && state.input_choices.is_some() {
-        "PgUp/PgDn scroll agent · ↑/↓ choices · 1-9 quick-select · Mouse wheel scrolls panels · Esc exit"
-    } else if state.input_active {
-        "Enter=submit · Shift+Enter=newline · ↑/↓/←/→ navigate · Mouse wheel scrolls panels"
-    } else {
-        "↑/↓ scroll agent · End auto-scroll · Mouse wheel scrolls panels · --no-ui for plain"
+    let lines: Vec<Line<'_>> = file
+        .diff
+        .lines()
+        .map(|line| {
+            let style = if line.starts_with("+++") || line.starts_with("---") {
+                theme::heading()
+            } else if line.starts_with('+') {
+                theme::diff_added()
+            } else if line.starts_with('-') {
+                theme::diff_removed()
+            } else if line.starts_with("@@") {
+                theme::accent()
+            } else {
+                theme::subdued()
+            };
+            Line::from(Span::styled(line, style))
+        })
+        .collect();
+    let title = format!(
+        "Diff {}/{} {}",
+        state.diff_selected + 1,
+        state.diffs.len(),
+        file.path
+    );
+    let panel = Paragraph::new(lines)
+        .block(block(title))
+        .scroll((state.diff_scroll as u16, 0));
+    frame.render_widget(panel, area);
+}
+
+/// History panel: earlier iterations' transcripts from the run logs, with
+/// run headers and iteration headings highlighted.
+fn render_history_panel(frame: &mut Frame<'_>, area: Rect, history: &RunHistory) {
+    let block = |title: String| {
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(theme::border())
     };
-    let footer = Paragraph::new(footer_text).style(theme::subdued());
-    frame.render_widget(footer, root[2]);
+    if history.lines.is_empty() {
+        let empty = Paragraph::new("No run logs yet.")
+            .style(theme::subdued())
+            .block(block("History".to_string()));
+        frame.render_widget(empty, area);
+        return;
+    }
+
+    let lines: Vec<Line<'_>> = history
+        .lines
+        .iter()
+        .map(|line| {
+            let style = if line.starts_with("═════ run ") {
+                theme::title()
+            } else if is_history_marker(line) {
+                theme::accent()
+            } else if line.starts_with("## ") {
+                theme::subdued()
+            } else {
+                Style::default()
+            };
+            Line::from(Span::styled(line.as_str(), style))
+        })
+        .collect();
+    let (current, total) = history.position();
+    let title = format!(
+        "History [iteration {current}/{total} · line {}/{}]",
+        history.scroll + 1,
+        history.lines.len()
+    );
+    let panel = Paragraph::new(lines)
+        .block(block(title))
+        .scroll((history.scroll.min(u16::MAX as usize) as u16, 0));
+    frame.render_widget(panel, area);
 }
 
 fn compute_total_lines(styled_lines: &[Line<'_>], inner_width: usize, text: &str) -> usize {
     styled_lines
         .iter()
         .map(|line| {
-            let char_count: usize = line.spans.iter().map(|s| s.content.len()).sum();
+            let char_count: usize = line.spans.iter().map(|s| s.content.chars().count()).sum();
             if char_count == 0 {
                 1
             } else {
@@ -316,8 +491,11 @@ fn render_agent_markdown(text: &str) -> Vec<Line<'static>> {
     let mut in_code_block = false;
     // Tracks which sigil tag we're inside for multi-line sigils.
     let mut in_sigil: Option<&str> = None;
+    let source: Vec<&str> = text.split('\n').collect();
+    let mut next = 0;
 
-    for raw_line in text.split('\n') {
+    while let Some(&raw_line) = source.get(next) {
+        next += 1;
         let trimmed = raw_line.trim_start();
 
         // Inside a multi-line sigil: check for closing tag.
@@ -348,6 +526,23 @@ fn render_agent_markdown(text: &str) -> Vec<Line<'static>> {
             continue;
         }
 
+        // Tables: a header row followed by a delimiter row, then body rows.
+        if markdown_table::is_row(raw_line)
+            && source
+                .get(next)
+                .is_some_and(|line| markdown_table::is_delimiter(line))
+        {
+            let end = source[next..]
+                .iter()
+                .position(|line| !markdown_table::is_row(line))
+                .map_or(source.len(), |n| next + n);
+            if let Some(table) = markdown_table::parse(&source[next - 1..end]) {
+                lines.extend(render_table(&table));
+                next = end;
+                continue;
+            }
+        }
+
         // Iteration divider lines.
         if trimmed.starts_with("───── iteration ") && trimmed.ends_with(" ─────")
         {
@@ -432,6 +627,36 @@ fn render_agent_markdown(text: &str) -> Vec<Line<'static>> {
     lines
 }
 
+/// Render a markdown table with box-drawing rules. It yields one line per
+/// source line (no top or bottom border), so pager scroll positions still
+/// match the source.
+fn render_table(table: &Table) -> Vec<Line<'static>> {
+    let widths = table.widths();
+    let row = |cells: &[String], header: bool| {
+        let mut spans = vec![Span::styled("│", theme::hr())];
+        for ((cell, width), align) in cells.iter().zip(&widths).zip(&table.align) {
+            let (before, after) = markdown_table::padding(cell, *width, *align);
+            spans.push(Span::styled(" ".repeat(before + 1), theme::subdued()));
+            if header {
+                spans.push(Span::styled(cell.clone(), theme::heading()));
+            } else {
+                spans.extend(parse_inline_markdown(cell));
+            }
+            spans.push(Span::styled(" ".repeat(after + 1), theme::subdued()));
+            spans.push(Span::styled("│", theme::hr()));
+        }
+        Line::from(spans)
+    };
+    let rule: Vec<String> = widths.iter().map(|w| "─".repeat(w + 2)).collect();
+
+    let mut lines = vec![
+        row(&table.header, true),
+        Line::styled(format!("├{}┤", rule.join("┼")), theme::hr()),
+    ];
+    lines.extend(table.rows.iter().map(|cells| row(cells, false)));
+    lines
+}
+
 /// Detect if a line opens a multi-line sigil (has opening tag but no matching close on same line).
 ///
 /// Returns the sigil tag name if a multi-line sigil begins.
@@ -828,7 +1053,11 @@ fn render_input_pane(frame: &mut Frame<'_>, area: Rect, state: &AppState) {
         // Hint bar.
         lines.push(Line::from(""));
         lines.push(Line::styled(
-            "Enter=submit  Shift+Enter=newline  Esc=exit  Ctrl+C=interrupt",
+            if state.input_steering {
+                "Enter=send  Shift+Enter=newline  Esc=cancel  Ctrl+C=interrupt"
+            } else {
+                "Enter=submit  Shift+Enter=newline  Esc=exit  Ctrl+C=interrupt"
+            },
             theme::subdued(),
         ));
 
@@ -888,14 +1117,19 @@ fn render_input_pane(frame: &mut Frame<'_>, area: Rect, state: &AppState) {
 
         for (i, choice) in choices.iter().enumerate() {
             let number = i + 1;
+            let mark = match state.input_checked.as_ref() {
+                Some(checked) if checked.get(i) == Some(&true) => "[x] ",
+                Some(_) => "[ ] ",
+                None => "",
+            };
             if i == state.input_choice_cursor {
                 lines.push(Line::styled(
-                    format!("> {number}. {choice}"),
+                    format!("> {mark}{number}. {choice}"),
                     theme::title(),
                 ));
             } else {
                 lines.push(Line::styled(
-                    format!("  {number}. {choice}"),
+                    format!("  {mark}{number}. {choice}"),
                     theme::subdued(),
                 ));
             }
@@ -904,7 +1138,11 @@ fn render_input_pane(frame: &mut Frame<'_>, area: Rect, state: &AppState) {
         // Bottom hint bar.
         lines.push(Line::from(""));
         lines.push(Line::styled(
-            "1-9 select · ↑/↓ navigate · Enter confirm · type for custom",
+            if state.input_checked.is_some() {
+                "Space/1-9 toggle · a all · ↑/↓ navigate · Enter submit"
+            } else {
+                "1-9 select · ↑/↓ navigate · Enter confirm · type for custom"
+            },
             theme::subdued(),
         ));
 
@@ -938,20 +1176,32 @@ fn count_wrapped_lines(text: &str, width: usize) -> usize {
         .sum()
 }
 
-fn render_explorer(frame: &mut Frame<'_>, title: &str, lines: &[String], scroll: usize) {
+fn render_explorer(
+    frame: &mut Frame<'_>,
+    title: &str,
+    lines: &[String],
+    scroll: usize,
+    notice: Option<&str>,
+    markdown: bool,
+) {
     let root = Layout::default()
         .direction(Direction::Vertical)
         .constraints([Constraint::Min(1), Constraint::Length(1)])
         .split(frame.area());
 
     let body_height = root[0].height.saturating_sub(2) as usize;
-    let slice = lines
-        .iter()
+    // Markdown is styled as a whole so code blocks above the scroll position
+    // still apply; it yields one line per source line.
+    let styled: Vec<Line<'static>> = if markdown {
+        render_agent_markdown(&lines.join("\n"))
+    } else {
+        lines.iter().map(|l| Line::raw(l.clone())).collect()
+    };
+    let slice: Vec<Line<'static>> = styled
+        .into_iter()
         .skip(scroll)
         .take(body_height.max(1))
-        .cloned()
-        .collect::<Vec<_>>()
-        .join("\n");
+        .collect();
 
     let body = Paragraph::new(slice)
         .block(
@@ -964,8 +1214,10 @@ fn render_explorer(frame: &mut Frame<'_>, title: &str, lines: &[String], scroll:
         .wrap(Wrap { trim: false });
     frame.render_widget(body, root[0]);
 
-    let footer = Paragraph::new("Explorer: \u{2191}/\u{2193} scroll, q/Esc/Enter close")
-        .style(theme::subdued());
+    let footer = Paragraph::new(
+        notice.unwrap_or("Explorer: \u{2191}/\u{2193} scroll, y copy, s save, q/Esc/Enter close"),
+    )
+    .style(theme::subdued());
     frame.render_widget(footer, root[1]);
 }
 
@@ -1059,6 +1311,42 @@ mod tests {
         assert!(text.contains("Agent Stream"));
         assert!(text.contains("Tool Activity"));
         assert!(text.contains("Input"));
+        assert!(!text.contains("Progress"));
+
+        state.apply(UiEvent::Progress(vec![
+            "auth      ▆█▃▃  1 left".to_string(),
+            "done/day  ▄_█_".to_string(),
+        ]));
+        terminal
+            .draw(|f| {
+                let mut areas = FrameAreas::default();
+                render(f, &state, &mut areas);
+            })
+            .unwrap();
+        let text = buffer_text(terminal.backend().buffer());
+        assert!(text.contains("Progress"));
+        assert!(text.contains("1 left"));
+
+        state.apply(UiEvent::Meter(crate::ui::event::StreamMeter {
+            started: std::time::Instant::now(),
+            finished: None,
+            timeout: Some(std::time::Duration::from_secs(900)),
+            chars: 4_000,
+            cost_micro_usd: Some(120_000),
+        }));
+        terminal
+            .draw(|f| {
+                let mut areas = FrameAreas::default();
+                render(f, &state, &mut areas);
+            })
+            .unwrap();
+        let header = buffer_text(terminal.backend().buffer())
+            .lines()
+            .next()
+            .unwrap()
+            .to_string();
+        assert!(header.contains("1.0k tok"), "{header}");
+        assert!(header.contains("0:00/15:00  ~$0.12"), "{header}");
     }
 
     #[test]
@@ -1069,6 +1357,7 @@ mod tests {
         state.show_explorer(
             "Task Explorer".to_string(),
             vec!["t-1 done foo".to_string(), "t-2 pending bar".to_string()],
+            false,
         );
         terminal
             .draw(|f| {
@@ -1081,6 +1370,49 @@ mod tests {
         assert!(text.contains("Explorer:"));
     }
 
+    #[test]
+    fn markdown_explorer_styles_and_wraps_descriptions() {
+        let backend = TestBackend::new(40, 20);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut state = AppState::default();
+        state.show_explorer(
+            "Task t-1".to_string(),
+            vec![
+                "  Description:".to_string(),
+                "    ## Approach".to_string(),
+                "    ```".to_string(),
+                "    - not a list".to_string(),
+                "    ```".to_string(),
+                "    - a list item that is long enough to wrap at the panel".to_string(),
+            ],
+            true,
+        );
+        terminal
+            .draw(|f| {
+                let mut areas = FrameAreas::default();
+                render(f, &state, &mut areas);
+            })
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let text = buffer_text(buffer);
+        assert!(
+            text.contains("panel"),
+            "long line wraps instead of clipping"
+        );
+        let style_of = |needle: &str| {
+            let rows: Vec<String> = text.lines().map(str::to_string).collect();
+            let (y, row) = rows
+                .iter()
+                .enumerate()
+                .find(|(_, r)| r.contains(needle))
+                .unwrap();
+            let x = row[..row.find(needle).unwrap()].chars().count();
+            buffer[(x as u16, y as u16)].fg
+        };
+        assert_eq!(Some(style_of("## Approach")), theme::heading().fg);
+        assert_eq!(Some(style_of("- not a list")), theme::code_block().fg);
+    }
+
     #[test]
     fn modal_renders_over_base_screen() {
         let backend = TestBackend::new(80, 24);
@@ -1164,6 +1496,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn dashboard_renders_multi_select_check_marks() {
+        let backend = TestBackend::new(100, 30);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let state = AppState {
+            input_active: true,
+            input_title: "Accept tasks".to_string(),
+            input_choices: Some(vec!["Add index".to_string(), "Write docs".to_string()]),
+            input_checked: Some(vec![true, false]),
+            ..Default::default()
+        };
+        terminal
+            .draw(|f| {
+                let mut areas = FrameAreas::default();
+                render(f, &state, &mut areas);
+            })
+            .unwrap();
+        let text = buffer_text(terminal.backend().buffer());
+        assert!(text.contains("> [x] 1. Add index"), "{text}");
+        assert!(text.contains("  [ ] 2. Write docs"), "{text}");
+        assert!(text.contains("Space/1-9 toggle"), "{text}");
+    }
+
     #[test]
     fn dashboard_renders_input_pane_active_choices() {
         let backend = TestBackend::new(100, 30);
@@ -1281,4 +1636,72 @@ mod tests {
             "Tool Activity title at x={x} should be within left 42% (max {left_column_max})"
         );
     }
+
+    #[test]
+    fn diff_panel_replaces_agent_stream() {
+        let backend = TestBackend::new(100, 30);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let mut state = AppState::default();
+        state.apply(UiEvent::FileDiff(crate::ui::event::FileDiff {
+            path: "src/lib.rs".to_string(),
+            diff: "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1 @@\n-old\n+new\n".to_string(),
+        }));
+        state.toggle_diff_panel();
+        terminal
+            .draw(|f| {
+                let mut areas = FrameAreas::default();
+                render(f, &state, &mut areas);
+            })
+            .unwrap();
+        let text = buffer_text(terminal.backend().buffer());
+        assert!(text.contains("Diff 1/1 src/lib.rs"));
+        assert!(text.contains("+new"));
+        assert!(!text.contains("Agent Stream"));
+    }
+
+    #[test]
+    fn history_panel_replaces_agent_stream() {
+        let backend = TestBackend::new(100, 30);
+        let mut terminal = Terminal::new(backend).unwrap();
+        let state = AppState {
+            history: Some(RunHistory::from_runs(&[(
+                "run-1".to_string(),
+                "# t-abc Fix login (iteration 1, sonnet)\n\n## Response\n\nDone.\n".to_string(),
+            )])),
+            ..AppState::default()
+        };
+        terminal
+            .draw(|f| {
+                let mut areas = FrameAreas::default();
+                render(f, &state, &mut areas);
+            })
+            .unwrap();
+        let text = buffer_text(terminal.backend().buffer());
+        assert!(text.contains("History [iteration 2/2"));
+        assert!(text.contains("# t-abc Fix login"));
+        assert!(!text.contains("Agent Stream"));
+    }
+
+    #[test]
+    fn agent_markdown_renders_tables_with_box_rules() {
+        let text = "Summary:\n| Task | Cost |\n|------|-----:|\n| `t-1` | $0.4 |\n| ab |";
+        let lines: Vec<String> = render_agent_markdown(text)
+            .iter()
+            .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "Summary:",
+                "│ Task  │ Cost │",
+                "├───────┼──────┤",
+                "│ `t-1` │ $0.4 │",
+                "│ ab    │      │",
+            ]
+        );
+
+        // Pipes without a delimiter row are left alone
+        let plain = render_agent_markdown("| not | a table |");
+        assert_eq!(plain.len(), 1);
+    }
 }
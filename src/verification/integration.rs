@@ -0,0 +1,324 @@
+//! Verification of the merged state (`[verification] integrate_onto`).
+//!
+//! A task is normally verified in the shared working tree, which says nothing
+//! about whether its changes still work combined with tasks other ralph
+//! instances completed meanwhile. With `integrate_onto = "origin/main"`, the
+//! working tree is snapshotted into a commit and merged onto that ref under
+//! `refs/ralph/integration/<task-id>`, and the verifier runs in a temporary
+//! detached worktree of the merge. A merge conflict fails verification
+//! without starting the verifier, so the task is retried instead of marked
+//! done.
+//!
+//! The snapshot and merge commits never touch the working tree, the index or
+//! any branch; the ref and worktree are removed once the verdict is in.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use crate::config::Config;
+use crate::output::formatter;
+
+/// Namespace of the temporary integration refs.
+pub const REF_PREFIX: &str = "refs/ralph/integration/";
+
+/// Identity of the temporary commits, so they work without `user.email`.
+const COMMIT_IDENTITY: [(&str, &str); 4] = [
+    ("GIT_AUTHOR_NAME", "ralph"),
+    ("GIT_AUTHOR_EMAIL", "ralph@localhost"),
+    ("GIT_COMMITTER_NAME", "ralph"),
+    ("GIT_COMMITTER_EMAIL", "ralph@localhost"),
+];
+
+/// Outcome of merging a task's working tree onto the integration target.
+pub enum Integration {
+    /// Merged cleanly; verify in this worktree.
+    Merged(IntegrationTree),
+    /// Paths that conflict with the target.
+    Conflicts(Vec<String>),
+}
+
+/// A temporary worktree of the merged state, removed with its ref on drop.
+pub struct IntegrationTree {
+    repo: PathBuf,
+    dir: PathBuf,
+    /// Subdirectory of the repository the project lives in.
+    prefix: String,
+    reference: String,
+}
+
+impl IntegrationTree {
+    /// The project root inside the worktree.
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(&self.prefix)
+    }
+}
+
+impl Drop for IntegrationTree {
+    fn drop(&mut self) {
+        let dir = self.dir.to_string_lossy().to_string();
+        let _ = git(&self.repo, &["worktree", "remove", "--force", &dir]);
+        let _ = git(&self.repo, &["update-ref", "-d", &self.reference]);
+    }
+}
+
+/// The commit `[verification] integrate_onto` names, or `None` when unset.
+///
+/// A remote-tracking ref (`origin/main`) is fetched first so tasks other
+/// instances pushed meanwhile are included; if the fetch fails, or the run is
+/// `--offline`, the last fetched state is used.
+pub fn resolve_onto(config: &Config) -> Result<Option<String>> {
+    let Some(target) = config.ralph_config.verification.integrate_onto.as_deref() else {
+        return Ok(None);
+    };
+    let root = &config.project_root;
+    let fetch_target = target
+        .split_once('/')
+        .filter(|_| !crate::offline::is_enabled());
+    if let Some((remote, branch)) = fetch_target {
+        let remotes = git(root, &["remote"])?;
+        if String::from_utf8_lossy(&remotes.stdout)
+            .lines()
+            .any(|r| r == remote)
+        {
+            let fetch = git(root, &["fetch", "--quiet", remote, branch])?;
+            if !fetch.status.success() {
+                formatter::print_warning(&format!(
+                    "Could not fetch {target}, verifying against the last fetched state: {}",
+                    stderr(&fetch)
+                ));
+            }
+        }
+    }
+    let spec = format!("{target}^{{commit}}");
+    let commit = git_stdout(root, &["rev-parse", "--verify", "--quiet", &spec])
+        .with_context(|| format!("verification.integrate_onto: unknown ref '{target}'"))?;
+    Ok(Some(commit))
+}
+
+/// Merge the working tree of `project_root` onto commit `onto`, as a
+/// worktree to verify `task_id` in.
+///
+/// Conflicts in `.ralph/` are ignored, since ralph itself writes there.
+pub fn integrate(project_root: &Path, task_id: &str, onto: &str) -> Result<Integration> {
+    let snapshot = snapshot(project_root, task_id)?;
+
+    let merge = git(
+        project_root,
+        &[
+            "merge-tree",
+            "--write-tree",
+            "--name-only",
+            "--no-messages",
+            onto,
+            &snapshot,
+        ],
+    )?;
+    let out = String::from_utf8_lossy(&merge.stdout).to_string();
+    let mut lines = out.lines();
+    let tree = match merge.status.code() {
+        Some(0) | Some(1) => lines.next().unwrap_or_default().to_string(),
+        _ => bail!(
+            "git merge-tree failed (git 2.38 or newer is needed): {}",
+            stderr(&merge)
+        ),
+    };
+    let conflicts: Vec<String> = lines
+        .take_while(|l| !l.is_empty())
+        .filter(|l| !l.starts_with(".ralph/"))
+        .map(str::to_string)
+        .collect();
+    if !conflicts.is_empty() {
+        return Ok(Integration::Conflicts(conflicts));
+    }
+
+    let message = format!("ralph: integrate {task_id}");
+    let merged = commit_tree(project_root, &tree, &[onto, &snapshot], &message)?;
+    let reference = format!("{REF_PREFIX}{task_id}");
+    git_stdout(project_root, &["update-ref", &reference, &merged])?;
+
+    let dir = std::env::temp_dir().join(format!(
+        "ralph-integration-{}-{}",
+        task_id,
+        std::process::id()
+    ));
+    let tree = IntegrationTree {
+        repo: project_root.to_path_buf(),
+        dir,
+        prefix: git_stdout(project_root, &["rev-parse", "--show-prefix"])?,
+        reference,
+    };
+    let dir = tree.dir.to_string_lossy().to_string();
+    git_stdout(
+        project_root,
+        &["worktree", "add", "--detach", "--quiet", &dir, &merged],
+    )
+    .context("Failed to create the integration worktree")?;
+    Ok(Integration::Merged(tree))
+}
+
+/// Verdict reason for a task whose changes conflict with `target`.
+pub fn conflict_reason(target: &str, conflicts: &[String]) -> String {
+    format!(
+        "Changes conflict with {target} in: {}. Merge {target} into the working tree \
+         and resolve the conflicts.",
+        conflicts.join(", ")
+    )
+}
+
+/// Commit the whole working tree, untracked files included, on top of
+/// `HEAD` without touching the real index.
+fn snapshot(project_root: &Path, task_id: &str) -> Result<String> {
+    let index = std::env::temp_dir().join(format!(
+        "ralph-integration-{}-{}.index",
+        task_id,
+        std::process::id()
+    ));
+    let result = (|| {
+        let with_index = |args: &[&str]| {
+            let out = Command::new("git")
+                .arg("-C")
+                .arg(project_root)
+                .args(args)
+                .env("GIT_INDEX_FILE", &index)
+                .output()
+                .context("Failed to run git")?;
+            if !out.status.success() {
+                bail!("git {} failed: {}", args.join(" "), stderr(&out));
+            }
+            Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+        };
+        with_index(&["read-tree", "HEAD"])?;
+        with_index(&["add", "--all"])?;
+        let tree = with_index(&["write-tree"])?;
+        let head = git_stdout(project_root, &["rev-parse", "HEAD"])?;
+        commit_tree(
+            project_root,
+            &tree,
+            &[&head],
+            &format!("ralph: working tree of {task_id}"),
+        )
+    })();
+    let _ = std::fs::remove_file(&index);
+    result.context("Failed to snapshot the working tree")
+}
+
+fn commit_tree(project_root: &Path, tree: &str, parents: &[&str], message: &str) -> Result<String> {
+    let mut args = vec!["commit-tree", tree, "-m", message];
+    for parent in parents {
+        args.extend(["-p", parent]);
+    }
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(project_root)
+        .args(&args)
+        .envs(COMMIT_IDENTITY)
+        .output()
+        .context("Failed to run git")?;
+    if !out.status.success() {
+        bail!("git commit-tree failed: {}", stderr(&out));
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
+/// Run git in `dir`. Credential prompts are disabled, so a fetch from a remote
+/// that needs a login fails instead of waiting on the terminal.
+fn git(dir: &Path, args: &[&str]) -> Result<Output> {
+    Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .context("Failed to run git")
+}
+
+fn git_stdout(dir: &Path, args: &[&str]) -> Result<String> {
+    let out = git(dir, args)?;
+    if !out.status.success() {
+        return Err(anyhow!("git {} failed: {}", args.join(" "), stderr(&out)));
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
+fn stderr(out: &Output) -> String {
+    String::from_utf8_lossy(&out.stderr).trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(dir: &Path, args: &[&str]) {
+        let out = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .envs(COMMIT_IDENTITY)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "git {:?}: {}", args, stderr(&out));
+    }
+
+    /// A repo whose `main` gained a change to `shared.txt` after `HEAD`.
+    fn repo_with_concurrent_change() -> (tempfile::TempDir, String) {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        run(root, &["init", "--quiet", "--initial-branch=main"]);
+        std::fs::write(root.join("shared.txt"), "a\nb\nc\n").unwrap();
+        run(root, &["add", "."]);
+        run(root, &["commit", "--quiet", "-m", "base"]);
+        run(root, &["checkout", "--quiet", "-b", "work"]);
+
+        run(root, &["checkout", "--quiet", "main"]);
+        std::fs::write(root.join("shared.txt"), "a\nB\nc\n").unwrap();
+        run(root, &["commit", "--quiet", "-am", "concurrent"]);
+        run(root, &["checkout", "--quiet", "work"]);
+        let onto = git_stdout(root, &["rev-parse", "main"]).unwrap();
+        (dir, onto)
+    }
+
+    #[test]
+    fn test_integrate_merges_working_tree_onto_target() {
+        let (dir, onto) = repo_with_concurrent_change();
+        let root = dir.path();
+        std::fs::write(root.join("new.txt"), "task output\n").unwrap();
+
+        let tree = match integrate(root, "t-abc123", &onto).unwrap() {
+            Integration::Merged(tree) => tree,
+            Integration::Conflicts(c) => panic!("unexpected conflicts: {c:?}"),
+        };
+        let worktree = tree.path();
+        assert_eq!(
+            std::fs::read_to_string(worktree.join("shared.txt")).unwrap(),
+            "a\nB\nc\n"
+        );
+        assert!(worktree.join("new.txt").exists());
+        // The real index and branch are untouched
+        assert_eq!(
+            git_stdout(root, &["status", "--porcelain"]).unwrap(),
+            "?? new.txt"
+        );
+
+        drop(tree);
+        assert!(!worktree.exists());
+        assert!(git_stdout(root, &["for-each-ref", REF_PREFIX])
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_integrate_reports_conflicts() {
+        let (dir, onto) = repo_with_concurrent_change();
+        let root = dir.path();
+        std::fs::write(root.join("shared.txt"), "a\nX\nc\n").unwrap();
+
+        match integrate(root, "t-abc123", &onto).unwrap() {
+            Integration::Conflicts(c) => assert_eq!(c, vec!["shared.txt"]),
+            Integration::Merged(_) => panic!("expected a conflict"),
+        }
+        assert!(
+            conflict_reason("main", &["shared.txt".to_string()]).contains("main in: shared.txt")
+        );
+    }
+}
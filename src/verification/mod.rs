@@ -0,0 +1,778 @@
+//! Verification of finished tasks.
+//!
+//! A [`Verifier`] decides whether a task marked done really is. The default
+//! is the verification agent ([`AgentVerifier`]); `[verification]` can swap
+//! in a shell command, an HTTP service or a composite of several, per
+//! project or per task type (see [`providers`]).
+//!
+//! Verdicts are cached in the `verifications` table under a fingerprint of the
+//! task's files, so a retry that changed nothing reuses the previous verdict
+//! instead of starting another agent session.
+//!
+//! With `[verification] integrate_onto`, the verifier judges the task's
+//! changes merged onto that ref rather than the working tree as it is (see
+//! [`integration`]).
+
+pub mod integration;
+pub mod providers;
+
+pub use providers::VerificationConfig;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::process::Command;
+
+use crate::acp;
+use crate::config::Config;
+use crate::dag::{Criterion, Db, Task};
+use crate::flaky;
+use crate::output::formatter;
+use crate::test_impact;
+
+/// Result of task verification.
+#[derive(Debug)]
+pub struct VerificationResult {
+    pub passed: bool,
+    pub reason: String,
+    /// Per-criterion verdicts reported by the verification agent.
+    pub criteria: Vec<CriterionVerdict>,
+}
+
+/// Verdict for a single acceptance criterion, from a `<criterion>` sigil.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CriterionVerdict {
+    pub position: i32,
+    pub passed: bool,
+    pub note: Option<String>,
+}
+
+/// Everything a [`Verifier`] gets to judge a task by.
+pub struct VerificationRequest<'a> {
+    pub config: &'a Config,
+    pub task: &'a Task,
+    pub criteria: &'a [Criterion],
+    pub spec_content: Option<&'a str>,
+    pub plan_content: Option<&'a str>,
+    /// Every file the task touched, across all of its attempts.
+    pub files: &'a [String],
+    /// Known-flaky files among `files` (see [`crate::flaky`]).
+    pub flaky_area: &'a [String],
+}
+
+/// A way of deciding whether a finished task passes.
+///
+/// Operational failures (the agent or service could not be reached) are
+/// errors; a task that does not pass is an `Ok` result with `passed: false`.
+#[async_trait::async_trait(?Send)]
+pub trait Verifier {
+    /// Provider name, for events and composite verdicts.
+    fn name(&self) -> &str;
+    async fn verify(&self, request: &VerificationRequest<'_>) -> Result<VerificationResult>;
+}
+
+/// Verify a completed task against its spec and plan with the provider
+/// `[verification]` selects for it.
+///
+/// With `onto` (from [`integration::resolve_onto`]), the working tree is
+/// merged onto that commit first and verified there; a conflict fails the
+/// task without running the verifier.
+#[allow(clippy::too_many_arguments)]
+pub async fn verify_task(
+    config: &Config,
+    task: &Task,
+    criteria: &[Criterion],
+    spec_content: Option<&str>,
+    plan_content: Option<&str>,
+    files: &[String],
+    flaky_area: &[String],
+    onto: Option<&str>,
+    _log_file: &str,
+) -> Result<VerificationResult> {
+    let verifier = providers::for_task(config, task)?;
+    let merged = match onto {
+        None => None,
+        Some(onto) => match integration::integrate(&config.project_root, &task.id, onto)? {
+            integration::Integration::Conflicts(conflicts) => {
+                let target = config
+                    .ralph_config
+                    .verification
+                    .integrate_onto
+                    .as_deref()
+                    .unwrap_or(onto);
+                return Ok(VerificationResult {
+                    passed: false,
+                    reason: integration::conflict_reason(target, &conflicts),
+                    criteria: Vec::new(),
+                });
+            }
+            integration::Integration::Merged(tree) => {
+                formatter::emit_event_info(
+                    "verify",
+                    &format!(
+                        "{} verifying merged state in {}",
+                        task.id,
+                        tree.path().display()
+                    ),
+                );
+                let config = Config {
+                    project_root: tree.path(),
+                    ..config.clone()
+                };
+                Some((tree, config))
+            }
+        },
+    };
+    let config = merged.as_ref().map_or(config, |(_, config)| config);
+    if verifier.name() != providers::AGENT {
+        formatter::emit_event_info(
+            "verify",
+            &format!("{} verifying with {}", task.id, verifier.name()),
+        );
+    }
+    let request = VerificationRequest {
+        config,
+        task,
+        criteria,
+        spec_content,
+        plan_content,
+        files,
+        flaky_area,
+    };
+    verifier.verify(&request).await
+}
+
+/// The verification agent.
+///
+/// Spawns a read-only ACP session that can run tests and inspect code
+/// but cannot modify the codebase (write_text_file is rejected).
+///
+/// With `targeted_tests`, the agent is told to run only the tests the task's
+/// files can affect, unless this is the task's last attempt. Known-flaky
+/// files earn extra attempts and a note asking the agent to re-run a failing
+/// check.
+pub struct AgentVerifier {
+    /// Provider name under `[verification.providers]`.
+    pub name: String,
+    /// Model to verify with instead of the iteration's.
+    pub model: Option<String>,
+}
+
+#[async_trait::async_trait(?Send)]
+impl Verifier for AgentVerifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn verify(&self, request: &VerificationRequest<'_>) -> Result<VerificationResult> {
+        let VerificationRequest {
+            config,
+            task,
+            files,
+            flaky_area,
+            ..
+        } = *request;
+        let max_retries = flaky::max_retries(
+            config.max_retries,
+            config.ralph_config.execution.flaky_retry_bonus,
+            flaky_area,
+        );
+        let final_attempt = task.retry_count >= max_retries as i32;
+        let targeted = if config.ralph_config.execution.targeted_tests && !final_attempt {
+            test_impact::targeted_test_commands(&config.project_root, files)
+        } else {
+            None
+        };
+        let system_prompt = build_verification_prompt(
+            task,
+            request.criteria,
+            request.spec_content,
+            request.plan_content,
+            targeted.as_deref(),
+            flaky_area,
+        );
+
+        let result = acp::connection::run_autonomous(
+            &config.agent_command,
+            &config.project_root,
+            &system_prompt,
+            "Verify the task.",
+            true, // read_only = true
+            Some(self.model.as_deref().unwrap_or(&config.current_model)),
+            acp::connection::SessionRestrictions {
+                allow_terminal: true, // verification needs to run tests
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        Ok(verdict_from_text(&result.full_text, request.criteria))
+    }
+}
+
+/// The verdict in a verification agent's output: `<verify-pass>`,
+/// `<verify-fail>` and per-criterion `<criterion>` sigils. A pass needs a
+/// `pass` verdict on every one of `criteria`; one left unmarked or skipped
+/// is not met.
+fn verdict_from_text(text: &str, criteria: &[Criterion]) -> VerificationResult {
+    let verdicts = parse_criterion_verdicts(text);
+    let unchecked: Vec<String> = criteria
+        .iter()
+        .filter(|c| !verdicts.iter().any(|v| v.position == c.position))
+        .map(|c| format!("#{}", c.position))
+        .collect();
+
+    // Any failed criterion overrides an overall pass
+    let failed: Vec<String> = verdicts
+        .iter()
+        .filter(|v| !v.passed)
+        .map(|v| match v.note {
+            Some(ref note) => format!("#{} ({})", v.position, note),
+            None => format!("#{}", v.position),
+        })
+        .collect();
+
+    // Parse verification sigils from the accumulated agent text
+    if parse_verify_pass(text) && failed.is_empty() && unchecked.is_empty() {
+        return VerificationResult {
+            passed: true,
+            reason: "Verification passed".to_string(),
+            criteria: verdicts,
+        };
+    }
+    if let Some(reason) = parse_verify_fail(text) {
+        return VerificationResult {
+            passed: false,
+            reason,
+            criteria: verdicts,
+        };
+    }
+    if !failed.is_empty() {
+        return VerificationResult {
+            passed: false,
+            reason: format!("Acceptance criteria failed: {}", failed.join(", ")),
+            criteria: verdicts,
+        };
+    }
+    if parse_verify_pass(text) {
+        return VerificationResult {
+            passed: false,
+            reason: format!("Acceptance criteria not checked: {}", unchecked.join(", ")),
+            criteria: verdicts,
+        };
+    }
+
+    // No sigil found — treat as failure
+    VerificationResult {
+        passed: false,
+        reason: "Verification agent did not emit a verification sigil".to_string(),
+        criteria: verdicts,
+    }
+}
+
+/// Fingerprint of the state a verdict for `task` depends on.
+///
+/// Covers the task ID, its acceptance criteria, git `HEAD`, the integration
+/// commit `onto` if any, and the content hash of every file in `files` plus
+/// every file git reports as changed or untracked. Paths under `.ralph/` are
+/// ignored, since ralph itself writes there between iterations.
+pub fn fingerprint(
+    project_root: &Path,
+    task: &Task,
+    criteria: &[Criterion],
+    files: &[String],
+    onto: Option<&str>,
+) -> String {
+    let mut paths: BTreeSet<String> = files.iter().cloned().collect();
+    paths.extend(git_changed_files(project_root));
+
+    let mut hasher = Sha256::new();
+    hasher.update(format!("task:{}\n", task.id));
+    for c in criteria {
+        hasher.update(format!("criterion:{}:{}\n", c.position, c.description));
+    }
+    hasher.update(format!(
+        "head:{}\n",
+        git_output(project_root, &["rev-parse", "HEAD"]).unwrap_or_default()
+    ));
+    if let Some(onto) = onto {
+        hasher.update(format!("onto:{}\n", onto));
+    }
+    for path in paths.iter().filter(|p| !p.starts_with(".ralph/")) {
+        let content_hash = match std::fs::read(project_root.join(path)) {
+            Ok(bytes) => hex(&Sha256::digest(&bytes)),
+            Err(_) => "-".to_string(),
+        };
+        hasher.update(format!("file:{}:{}\n", path, content_hash));
+    }
+    hex(&hasher.finalize())
+}
+
+/// Most recent cached verdict for `task_id` under `fingerprint`, if any.
+pub fn cached_result(
+    db: &Db,
+    task_id: &str,
+    fingerprint: &str,
+) -> Result<Option<VerificationResult>> {
+    let row = db.conn().query_row(
+        "SELECT passed, reason, criteria FROM verifications
+         WHERE task_id = ? AND fingerprint = ?
+         ORDER BY id DESC LIMIT 1",
+        [task_id, fingerprint],
+        |row| {
+            Ok((
+                row.get::<_, bool>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        },
+    );
+    match row {
+        Ok((passed, reason, criteria)) => Ok(Some(VerificationResult {
+            passed,
+            reason,
+            criteria: serde_json::from_str(&criteria).unwrap_or_default(),
+        })),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e).context("Failed to read cached verification"),
+    }
+}
+
+/// Store a verdict so a later verification with the same fingerprint can reuse it.
+pub fn record_result(
+    db: &Db,
+    task_id: &str,
+    fingerprint: &str,
+    result: &VerificationResult,
+) -> Result<()> {
+    db.conn()
+        .execute(
+            "INSERT INTO verifications (task_id, fingerprint, passed, reason, criteria)
+             VALUES (?, ?, ?, ?, ?)",
+            rusqlite::params![
+                task_id,
+                fingerprint,
+                result.passed,
+                result.reason,
+                serde_json::to_string(&result.criteria)?
+            ],
+        )
+        .context("Failed to cache verification result")?;
+    Ok(())
+}
+
+/// Files git reports as modified, added or untracked, relative to the root.
+///
+/// Empty outside a git repository.
+/// Changed and untracked files under `project_root`, relative to it. Git
+/// reports paths from the repository root, which differs when the project
+/// lives in a subdirectory.
+fn git_changed_files(project_root: &Path) -> Vec<String> {
+    let Some(out) = git_output(
+        project_root,
+        &[
+            "status",
+            "--porcelain",
+            "-z",
+            "--untracked-files=all",
+            "--",
+            ".",
+        ],
+    ) else {
+        return Vec::new();
+    };
+    let prefix = git_output(project_root, &["rev-parse", "--show-prefix"]).unwrap_or_default();
+    let mut files = Vec::new();
+    let mut entries = out.split('\0').filter(|e| e.len() > 3);
+    while let Some(entry) = entries.next() {
+        let (status, path) = entry.split_at(3);
+        files.push(path.strip_prefix(&prefix).unwrap_or(path).to_string());
+        // Renames and copies are followed by the original path.
+        if status.starts_with('R') || status.starts_with('C') {
+            entries.next();
+        }
+    }
+    files
+}
+
+fn git_output(dir: &Path, args: &[&str]) -> Option<String> {
+    let out = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    out.status
+        .success()
+        .then(|| String::from_utf8_lossy(&out.stdout).trim_end().to_string())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn build_verification_prompt(
+    task: &Task,
+    criteria: &[Criterion],
+    spec_content: Option<&str>,
+    plan_content: Option<&str>,
+    targeted_tests: Option<&[String]>,
+    flaky_area: &[String],
+) -> String {
+    let mut prompt = String::new();
+
+    prompt.push_str("You are a verification agent for Ralph. Your job is to verify that a task was completed correctly.\n\n");
+    prompt.push_str("## Task to Verify\n\n");
+    prompt.push_str(&format!("**ID:** {}\n", task.id));
+    prompt.push_str(&format!("**Title:** {}\n", task.title));
+    prompt.push_str(&format!("**Description:** {}\n\n", task.description));
+
+    if !criteria.is_empty() {
+        prompt.push_str("## Acceptance Criteria\n\n");
+        for c in criteria {
+            prompt.push_str(&format!("{}. {}\n", c.position, c.description));
+        }
+        prompt.push('\n');
+    }
+
+    if let Some(spec) = spec_content {
+        prompt.push_str("## Specification\n\n");
+        prompt.push_str(spec);
+        prompt.push_str("\n\n");
+    }
+
+    if let Some(plan) = plan_content {
+        prompt.push_str("## Plan\n\n");
+        prompt.push_str(plan);
+        prompt.push_str("\n\n");
+    }
+
+    prompt.push_str("## Instructions\n\n");
+    prompt.push_str(
+        "1. Read the relevant source files to check if the task was implemented correctly\n",
+    );
+    match targeted_tests {
+        Some(commands) => {
+            prompt.push_str(
+                "2. Run the tests the task's changes can affect (the full suite is not needed):\n",
+            );
+            for command in commands {
+                prompt.push_str(&format!("   - `{command}`\n"));
+            }
+        }
+        None => prompt.push_str("2. Run any applicable tests (cargo test, etc.)\n"),
+    }
+    if !flaky_area.is_empty() {
+        prompt.push_str(
+            "   Checks covering these files have failed before and then passed unchanged; \
+             re-run a failing check once before rejecting the work:\n",
+        );
+        for file in flaky_area {
+            prompt.push_str(&format!("   - `{file}`\n"));
+        }
+    }
+    prompt.push_str(
+        r#"3. Check that acceptance criteria from the task description are met
+4. Do NOT modify any files — you are read-only
+
+## Sigils
+
+After verification, emit exactly one of these sigils:
+
+- `<verify-pass/>` — The task was implemented correctly
+- `<verify-fail>reason</verify-fail>` — The task has issues (explain why)
+"#,
+    );
+
+    if !criteria.is_empty() {
+        prompt.push_str(
+            r#"
+Before the overall verdict, mark every acceptance criterion by number:
+
+- `<criterion n="1" status="pass"/>` — The criterion is met
+- `<criterion n="2" status="fail">reason</criterion>` — The criterion is not met (explain why)
+
+Any failed criterion fails verification, and so does one you leave unmarked:
+there is no skip, so mark a criterion you cannot check as `fail` and say why.
+"#,
+        );
+    }
+
+    prompt
+}
+
+/// Parse the `<verify-pass/>` sigil from result text.
+pub fn parse_verify_pass(text: &str) -> bool {
+    text.contains("<verify-pass/>")
+}
+
+/// Parse the `<verify-fail>...</verify-fail>` sigil from result text.
+pub fn parse_verify_fail(text: &str) -> Option<String> {
+    let start_tag = "<verify-fail>";
+    let end_tag = "</verify-fail>";
+
+    let start_idx = text.find(start_tag)?;
+    let content_start = start_idx + start_tag.len();
+    let end_idx = text[content_start..].find(end_tag)?;
+    let reason = text[content_start..content_start + end_idx].trim();
+
+    if reason.is_empty() {
+        None
+    } else {
+        Some(reason.to_string())
+    }
+}
+
+/// Parse all `<criterion n="N" status="pass|fail">note</criterion>` sigils from result text.
+///
+/// Both self-closing and note-bearing forms are accepted. Entries with a missing
+/// or non-numeric `n`, or a status other than `pass`/`fail`, are skipped.
+pub fn parse_criterion_verdicts(text: &str) -> Vec<CriterionVerdict> {
+    let mut verdicts = Vec::new();
+    let mut search_from = 0;
+
+    while let Some(start_idx) = text[search_from..].find("<criterion ") {
+        let abs_start = search_from + start_idx;
+        let tag_end = match text[abs_start..].find('>') {
+            Some(idx) => abs_start + idx,
+            None => break,
+        };
+        let self_closing = text[..tag_end].ends_with('/');
+        let attr_end = if self_closing { tag_end - 1 } else { tag_end };
+        let tag_content = &text[abs_start + 11..attr_end];
+
+        let (note, next) = if self_closing {
+            (None, tag_end + 1)
+        } else {
+            let end_tag = "</criterion>";
+            match text[tag_end + 1..].find(end_tag) {
+                Some(idx) => {
+                    let body = text[tag_end + 1..tag_end + 1 + idx].trim();
+                    let note = (!body.is_empty()).then(|| body.to_string());
+                    (note, tag_end + 1 + idx + end_tag.len())
+                }
+                None => (None, tag_end + 1),
+            }
+        };
+
+        let position = acp::sigils::extract_attribute(tag_content, "n")
+            .and_then(|n| n.trim().parse::<i32>().ok());
+        let status = acp::sigils::extract_attribute(tag_content, "status");
+        let passed = match status.as_deref() {
+            Some("pass") => Some(true),
+            Some("fail") => Some(false),
+            _ => None,
+        };
+        if let (Some(position), Some(passed)) = (position, passed) {
+            verdicts.push(CriterionVerdict {
+                position,
+                passed,
+                note,
+            });
+        }
+        search_from = next;
+    }
+    verdicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_verify_pass() {
+        assert!(parse_verify_pass("All looks good <verify-pass/> done"));
+        assert!(!parse_verify_pass("No pass sigil here"));
+    }
+
+    #[test]
+    fn test_parse_verify_fail() {
+        let text = "<verify-fail>Tests are failing in module X</verify-fail>";
+        assert_eq!(
+            parse_verify_fail(text),
+            Some("Tests are failing in module X".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_verify_fail_empty() {
+        assert_eq!(parse_verify_fail("<verify-fail></verify-fail>"), None);
+    }
+
+    #[test]
+    fn test_parse_verify_fail_absent() {
+        assert_eq!(parse_verify_fail("no sigil"), None);
+    }
+
+    #[test]
+    fn test_parse_criterion_verdicts() {
+        let text = r#"Checked.
+<criterion n="1" status="pass"/>
+<criterion n="2" status="fail">no test for the error path</criterion>
+<criterion n="x" status="pass"/>
+<criterion n="3" status="maybe"/>
+<verify-fail>criterion 2</verify-fail>"#;
+        let verdicts = parse_criterion_verdicts(text);
+        assert_eq!(
+            verdicts,
+            vec![
+                CriterionVerdict {
+                    position: 1,
+                    passed: true,
+                    note: None,
+                },
+                CriterionVerdict {
+                    position: 2,
+                    passed: false,
+                    note: Some("no test for the error path".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify_pass_needs_every_criterion_checked() {
+        let criteria: Vec<Criterion> = (1..=2)
+            .map(|position| Criterion {
+                position,
+                description: format!("criterion {position}"),
+                status: "pending".to_string(),
+                note: None,
+            })
+            .collect();
+        let skipped = r#"<criterion n="1" status="pass"/>
+<criterion n="2" status="skip">could not run the e2e suite</criterion>
+<verify-pass/>"#;
+        let result = verdict_from_text(skipped, &criteria);
+        assert!(!result.passed);
+        assert_eq!(result.reason, "Acceptance criteria not checked: #2");
+
+        let checked = r#"<criterion n="1" status="pass"/>
+<criterion n="2" status="pass"/>
+<verify-pass/>"#;
+        assert!(verdict_from_text(checked, &criteria).passed);
+        assert!(verdict_from_text("<verify-pass/>", &[]).passed);
+    }
+
+    #[test]
+    fn test_parse_criterion_verdicts_absent() {
+        assert!(parse_criterion_verdicts("<verify-pass/>").is_empty());
+    }
+
+    #[test]
+    fn test_verification_prompt_lists_criteria() {
+        let task = Task {
+            id: "t-abc123".to_string(),
+            title: "Title".to_string(),
+            description: "Desc".to_string(),
+            status: "in_progress".to_string(),
+            parent_id: None,
+            feature_id: None,
+            task_type: "standalone".to_string(),
+            priority: 0,
+            retry_count: 0,
+            max_retries: 3,
+            verification_status: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+            claimed_by: None,
+            discovered_by: None,
+            source_ref: None,
+            cwd: None,
+            created_by: None,
+            completed_by: None,
+            plan_ref: None,
+        };
+        let criteria = vec![Criterion {
+            position: 1,
+            description: "cargo test passes".to_string(),
+            status: "pending".to_string(),
+            note: None,
+        }];
+
+        let prompt = build_verification_prompt(&task, &criteria, None, None, None, &[]);
+        assert!(prompt.contains("## Acceptance Criteria"));
+        assert!(prompt.contains("1. cargo test passes"));
+        assert!(prompt.contains("<criterion n=\"1\" status=\"pass\"/>"));
+
+        let prompt = build_verification_prompt(&task, &[], None, None, None, &[]);
+        assert!(!prompt.contains("## Acceptance Criteria"));
+        assert!(prompt.contains("Run any applicable tests"));
+
+        let commands = vec!["cargo test -p app --test cli".to_string()];
+        let prompt = build_verification_prompt(&task, &[], None, None, Some(&commands), &[]);
+        assert!(prompt.contains("   - `cargo test -p app --test cli`\n"));
+        assert!(!prompt.contains("Run any applicable tests"));
+        assert!(!prompt.contains("re-run a failing check"));
+
+        let flaky = vec!["src/net.rs".to_string()];
+        let prompt = build_verification_prompt(&task, &[], None, None, None, &flaky);
+        assert!(prompt.contains("re-run a failing check once"));
+        assert!(prompt.contains("   - `src/net.rs`\n"));
+    }
+
+    #[test]
+    fn test_fingerprint_tracks_file_content_and_cache_roundtrip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = crate::dag::init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let task = crate::dag::create_task(&db, "Edit lib", None, None, 0).unwrap();
+        std::fs::write(dir.path().join("lib.rs"), "v1").unwrap();
+        let files = vec!["lib.rs".to_string(), ".ralph/scratch/x.md".to_string()];
+
+        let first = fingerprint(dir.path(), &task, &[], &files, None);
+        assert_eq!(first, fingerprint(dir.path(), &task, &[], &files, None));
+        assert_ne!(
+            first,
+            fingerprint(dir.path(), &task, &[], &files, Some("abc"))
+        );
+        std::fs::write(dir.path().join("lib.rs"), "v2").unwrap();
+        let second = fingerprint(dir.path(), &task, &[], &files, None);
+        assert_ne!(first, second);
+
+        assert!(cached_result(&db, &task.id, &second).unwrap().is_none());
+        let verdict = VerificationResult {
+            passed: false,
+            reason: "tests fail".to_string(),
+            criteria: vec![CriterionVerdict {
+                position: 1,
+                passed: false,
+                note: Some("red".to_string()),
+            }],
+        };
+        record_result(&db, &task.id, &second, &verdict).unwrap();
+        let cached = cached_result(&db, &task.id, &second).unwrap().unwrap();
+        assert!(!cached.passed);
+        assert_eq!(cached.reason, "tests fail");
+        assert_eq!(cached.criteria, verdict.criteria);
+        assert!(cached_result(&db, &task.id, &first).unwrap().is_none());
+
+        crate::dag::delete_task(&db, &task.id).unwrap();
+    }
+
+    #[test]
+    fn test_git_changed_files_are_relative_to_a_subdirectory_project() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(dir.path())
+                .args(args)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {args:?}");
+        };
+        git(&["init", "-q"]);
+        std::fs::create_dir_all(dir.path().join("app/src")).unwrap();
+        std::fs::write(dir.path().join("app/src/lib.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.path().join("README.md"), "outside the project").unwrap();
+
+        assert_eq!(
+            git_changed_files(&dir.path().join("app")),
+            vec!["src/lib.rs".to_string()]
+        );
+    }
+}
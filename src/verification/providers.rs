@@ -0,0 +1,677 @@
+//! Verification providers (`[verification]`).
+//!
+//! A provider is a named [`Verifier`] configuration. `agent` is built in;
+//! `[verification.providers.<name>]` adds more:
+//!
+//! ```toml
+//! [verification]
+//! provider = "gate"            # project default
+//!
+//! [verification.task_types]    # per task type (see [knowledge.task_types])
+//! frontend = "agent"
+//!
+//! [verification.providers.lint]
+//! type = "command"
+//! command = "cargo clippy -- -D warnings"
+//!
+//! [verification.providers.qa]
+//! type = "http"
+//! url = "https://qa.example.com/verify"
+//! token_env = "QA_TOKEN"
+//!
+//! [verification.providers.gate]
+//! type = "composite"
+//! providers = ["lint", "qa", "agent"]
+//! ```
+//!
+//! `integrate_onto = "<ref>"` verifies the merged state rather than the
+//! working tree, whichever provider runs (see [`super::integration`]).
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::time::Duration;
+
+use super::{AgentVerifier, CriterionVerdict, VerificationRequest, VerificationResult, Verifier};
+use crate::config::Config;
+use crate::dag::Task;
+use crate::knowledge;
+use crate::terminal_env;
+
+/// Name of the built-in verification agent provider.
+pub const AGENT: &str = "agent";
+
+/// Output lines of a failing command kept in its verdict.
+const COMMAND_OUTPUT_LINES: usize = 20;
+
+/// How tasks are verified (`[verification]`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct VerificationConfig {
+    /// Provider for tasks no `task_types` entry matches.
+    #[serde(default = "default_provider")]
+    pub provider: String,
+    /// Providers by name (`[verification.providers.<name>]`).
+    #[serde(default)]
+    pub providers: BTreeMap<String, ProviderConfig>,
+    /// Provider per task type from `[knowledge.task_types]`; the first
+    /// matching type with an entry wins.
+    #[serde(default)]
+    pub task_types: BTreeMap<String, String>,
+    /// Verify the working tree merged onto this ref instead of as it is
+    /// (see [`super::integration`]).
+    #[serde(default)]
+    pub integrate_onto: Option<String>,
+}
+
+impl Default for VerificationConfig {
+    fn default() -> Self {
+        Self {
+            provider: default_provider(),
+            providers: BTreeMap::new(),
+            task_types: BTreeMap::new(),
+            integrate_onto: None,
+        }
+    }
+}
+
+fn default_provider() -> String {
+    AGENT.to_string()
+}
+
+/// One `[verification.providers.<name>]` table.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ProviderConfig {
+    /// The verification agent, optionally on its own model.
+    Agent {
+        #[serde(default)]
+        model: Option<String>,
+    },
+    /// A shell command run in the project root; exit status 0 passes.
+    Command {
+        command: String,
+        /// Seconds before the command is killed and the task fails (0 = none).
+        #[serde(default)]
+        timeout_secs: u64,
+    },
+    /// An external service that receives the task as JSON and answers with
+    /// `{"passed": bool, "reason": "...", "criteria": [...]}`.
+    Http {
+        url: String,
+        /// Environment variable holding a bearer token.
+        #[serde(default)]
+        token_env: Option<String>,
+        #[serde(default = "default_http_timeout_secs")]
+        timeout_secs: u64,
+    },
+    /// Other providers in order; the first failure fails the task.
+    Composite { providers: Vec<String> },
+}
+
+fn default_http_timeout_secs() -> u64 {
+    300
+}
+
+impl VerificationConfig {
+    /// Reject references to unknown providers, empty commands, URLs and
+    /// composites, and composites that include themselves.
+    pub fn validate(&self) -> Result<()> {
+        if self
+            .integrate_onto
+            .as_deref()
+            .is_some_and(|r| r.trim().is_empty())
+        {
+            bail!("verification.integrate_onto must name a ref");
+        }
+        build(self, &self.provider, &mut Vec::new())
+            .with_context(|| "verification.provider".to_string())?;
+        for (task_type, provider) in &self.task_types {
+            build(self, provider, &mut Vec::new())
+                .with_context(|| format!("verification.task_types.{task_type}"))?;
+        }
+        for name in self.providers.keys() {
+            build(self, name, &mut Vec::new())
+                .with_context(|| format!("verification.providers.{name}"))?;
+        }
+        Ok(())
+    }
+
+    /// Name of the provider that verifies `task`.
+    pub fn provider_for<'a>(
+        &'a self,
+        knowledge: &knowledge::KnowledgeConfig,
+        task: &Task,
+    ) -> &'a str {
+        knowledge::task_types(knowledge, &task.title, &task.description)
+            .into_iter()
+            .find_map(|t| self.task_types.get(t))
+            .unwrap_or(&self.provider)
+    }
+
+    fn provider(&self, name: &str) -> Option<ProviderConfig> {
+        match self.providers.get(name) {
+            Some(provider) => Some(provider.clone()),
+            None if name == AGENT => Some(ProviderConfig::Agent { model: None }),
+            None => None,
+        }
+    }
+}
+
+/// The verifier `[verification]` selects for `task`.
+pub fn for_task(config: &Config, task: &Task) -> Result<Box<dyn Verifier>> {
+    let verification = &config.ralph_config.verification;
+    let name = verification.provider_for(&config.ralph_config.knowledge, task);
+    build(verification, name, &mut Vec::new())
+}
+
+/// Build provider `name`. `path` holds the composites being built, to catch cycles.
+fn build(
+    config: &VerificationConfig,
+    name: &str,
+    path: &mut Vec<String>,
+) -> Result<Box<dyn Verifier>> {
+    let Some(provider) = config.provider(name) else {
+        bail!("unknown verification provider '{name}'");
+    };
+    let name = name.to_string();
+    Ok(match provider {
+        ProviderConfig::Agent { model } => Box::new(AgentVerifier { name, model }),
+        ProviderConfig::Command {
+            command,
+            timeout_secs,
+        } => {
+            if command.trim().is_empty() {
+                bail!("verification provider '{name}' has an empty command");
+            }
+            Box::new(CommandVerifier {
+                name,
+                command,
+                timeout: (timeout_secs > 0).then(|| Duration::from_secs(timeout_secs)),
+            })
+        }
+        ProviderConfig::Http {
+            url,
+            token_env,
+            timeout_secs,
+        } => {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                bail!("verification provider '{name}' needs an http(s) url, got '{url}'");
+            }
+            if crate::offline::is_enabled() {
+                bail!(
+                    "verification provider '{name}' posts to {url}, which --offline does not \
+                     allow; select a command or agent provider for offline runs"
+                );
+            }
+            Box::new(HttpVerifier {
+                name,
+                url,
+                token_env,
+                timeout: Duration::from_secs(timeout_secs),
+            })
+        }
+        ProviderConfig::Composite { providers } => {
+            if providers.is_empty() {
+                bail!("composite verification provider '{name}' lists no providers");
+            }
+            if path.contains(&name) {
+                bail!(
+                    "verification provider '{name}' includes itself ({} -> {name})",
+                    path.join(" -> ")
+                );
+            }
+            path.push(name.clone());
+            let members = providers
+                .iter()
+                .map(|member| build(config, member, path))
+                .collect::<Result<Vec<_>>>()?;
+            path.pop();
+            Box::new(CompositeVerifier { name, members })
+        }
+    })
+}
+
+/// Runs a shell command in the project root with the agent terminal
+/// environment (`[env]` and secrets) plus `RALPH_TASK_ID`, `RALPH_TASK_TITLE`
+/// and `RALPH_TASK_FILES` (one path per line). Exit status 0 passes; anything else fails with the output's tail.
+pub struct CommandVerifier {
+    name: String,
+    command: String,
+    timeout: Option<Duration>,
+}
+
+#[async_trait::async_trait(?Send)]
+impl Verifier for CommandVerifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn verify(&self, request: &VerificationRequest<'_>) -> Result<VerificationResult> {
+        let env = terminal_env::current();
+        let mut command = tokio::process::Command::new("sh");
+        command
+            .arg("-c")
+            .arg(&self.command)
+            .current_dir(&request.config.project_root)
+            .envs(env.vars().iter().map(|(k, v)| (k, v)))
+            .env("RALPH_TASK_ID", &request.task.id)
+            .env("RALPH_TASK_TITLE", &request.task.title)
+            .env("RALPH_TASK_FILES", request.files.join("\n"))
+            .stdin(Stdio::null())
+            .kill_on_drop(true);
+        let run = command.output();
+        let output = match self.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, run).await {
+                Ok(output) => output,
+                Err(_) => {
+                    return Ok(VerificationResult {
+                        passed: false,
+                        reason: format!(
+                            "`{}` timed out after {}s",
+                            self.command,
+                            timeout.as_secs()
+                        ),
+                        criteria: Vec::new(),
+                    })
+                }
+            },
+            None => run.await,
+        }
+        .with_context(|| format!("Failed to run verification command `{}`", self.command))?;
+
+        if output.status.success() {
+            return Ok(VerificationResult {
+                passed: true,
+                reason: format!("`{}` passed", self.command),
+                criteria: Vec::new(),
+            });
+        }
+        let text = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let lines: Vec<&str> = text.trim_end().lines().collect();
+        let tail = lines[lines.len().saturating_sub(COMMAND_OUTPUT_LINES)..].join("\n");
+        let mut reason = format!("`{}` failed ({})", self.command, output.status);
+        if !tail.is_empty() {
+            reason.push_str(":\n");
+            reason.push_str(&env.mask(&tail));
+        }
+        Ok(VerificationResult {
+            passed: false,
+            reason,
+            criteria: Vec::new(),
+        })
+    }
+}
+
+/// POSTs the task, its criteria, files, spec and plan as JSON to an external
+/// service. An unreachable service or a non-2xx answer is an error, not a
+/// failed verdict.
+pub struct HttpVerifier {
+    name: String,
+    url: String,
+    token_env: Option<String>,
+    timeout: Duration,
+}
+
+/// Body an HTTP provider answers with.
+#[derive(Debug, Deserialize)]
+struct HttpVerdict {
+    passed: bool,
+    #[serde(default)]
+    reason: Option<String>,
+    #[serde(default)]
+    criteria: Vec<CriterionVerdict>,
+}
+
+#[async_trait::async_trait(?Send)]
+impl Verifier for HttpVerifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn verify(&self, request: &VerificationRequest<'_>) -> Result<VerificationResult> {
+        let token = match &self.token_env {
+            Some(var) => Some(std::env::var(var).with_context(|| {
+                format!("{var} is not set (verification provider '{}')", self.name)
+            })?),
+            None => None,
+        };
+        let body = json!({
+            "task": {
+                "id": request.task.id,
+                "title": request.task.title,
+                "description": request.task.description,
+            },
+            "criteria": request
+                .criteria
+                .iter()
+                .map(|c| json!({ "position": c.position, "description": c.description }))
+                .collect::<Vec<_>>(),
+            "files": request.files,
+            "spec": request.spec_content,
+            "plan": request.plan_content,
+        });
+        let url = self.url.clone();
+        let timeout = self.timeout;
+        let verdict = tokio::task::spawn_blocking(move || -> Result<HttpVerdict> {
+            let agent: ureq::Agent = ureq::Agent::config_builder()
+                .timeout_global(Some(timeout))
+                .build()
+                .into();
+            let mut post = agent.post(&url);
+            if let Some(token) = token {
+                post = post.header("Authorization", &format!("Bearer {token}"));
+            }
+            Ok(post.send_json(&body)?.body_mut().read_json()?)
+        })
+        .await?
+        .with_context(|| format!("Verification service {} failed", self.url))?;
+
+        // Any failed criterion overrides an overall pass, as with the agent
+        let failed: Vec<String> = verdict
+            .criteria
+            .iter()
+            .filter(|v| !v.passed)
+            .map(|v| format!("#{}", v.position))
+            .collect();
+        let passed = verdict.passed && failed.is_empty();
+        let reason = match verdict.reason {
+            Some(reason) if !reason.trim().is_empty() => reason,
+            _ if passed => "Verification passed".to_string(),
+            _ if !failed.is_empty() => {
+                format!("Acceptance criteria failed: {}", failed.join(", "))
+            }
+            _ => format!("{} rejected the task", self.name),
+        };
+        Ok(VerificationResult {
+            passed,
+            reason,
+            criteria: verdict.criteria,
+        })
+    }
+}
+
+/// Runs its members in order and stops at the first that fails, so cheap
+/// checks can go before the verification agent.
+pub struct CompositeVerifier {
+    name: String,
+    members: Vec<Box<dyn Verifier>>,
+}
+
+#[async_trait::async_trait(?Send)]
+impl Verifier for CompositeVerifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn verify(&self, request: &VerificationRequest<'_>) -> Result<VerificationResult> {
+        let mut criteria: BTreeMap<i32, CriterionVerdict> = BTreeMap::new();
+        let mut passed_by = Vec::new();
+        for member in &self.members {
+            let result = member.verify(request).await?;
+            for verdict in result.criteria {
+                // A criterion one member failed stays failed
+                match criteria.get(&verdict.position) {
+                    Some(existing) if !existing.passed => {}
+                    _ => {
+                        criteria.insert(verdict.position, verdict);
+                    }
+                }
+            }
+            if !result.passed {
+                return Ok(VerificationResult {
+                    passed: false,
+                    reason: format!("{}: {}", member.name(), result.reason),
+                    criteria: criteria.into_values().collect(),
+                });
+            }
+            passed_by.push(member.name().to_string());
+        }
+        Ok(VerificationResult {
+            passed: true,
+            reason: format!("Verification passed ({})", passed_by.join(", ")),
+            criteria: criteria.into_values().collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag::{self, init_db};
+    use crate::project::{ProjectConfig, RalphConfig};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use tempfile::TempDir;
+
+    fn parse(toml: &str) -> VerificationConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn config_with(dir: &TempDir, verification: VerificationConfig) -> Config {
+        let ralph_config = RalphConfig {
+            verification,
+            knowledge: toml::from_str(
+                "[task_types.frontend]\nkeywords = [\"css\"]\n[task_types.docs]\nkeywords = [\"readme\"]",
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        let project = ProjectConfig {
+            root: dir.path().to_path_buf(),
+            config: ralph_config,
+        };
+        Config::from_run_args(None, None, None, project, None, None, false, None, None).unwrap()
+    }
+
+    async fn run(config: &Config, task: &Task, files: &[String]) -> Result<VerificationResult> {
+        let verifier = for_task(config, task)?;
+        let request = VerificationRequest {
+            config,
+            task,
+            criteria: &[],
+            spec_content: None,
+            plan_content: Some("the plan"),
+            files,
+            flaky_area: &[],
+        };
+        verifier.verify(&request).await
+    }
+
+    #[test]
+    fn providers_parse_and_validate() {
+        let config = parse(
+            r#"
+provider = "gate"
+[task_types]
+docs = "lint"
+[providers.lint]
+type = "command"
+command = "make lint"
+[providers.qa]
+type = "http"
+url = "https://qa.example.com/verify"
+token_env = "QA_TOKEN"
+[providers.gate]
+type = "composite"
+providers = ["lint", "qa", "agent"]
+"#,
+        );
+        config.validate().unwrap();
+        assert_eq!(
+            config.providers["qa"],
+            ProviderConfig::Http {
+                url: "https://qa.example.com/verify".to_string(),
+                token_env: Some("QA_TOKEN".to_string()),
+                timeout_secs: 300,
+            }
+        );
+        VerificationConfig::default().validate().unwrap();
+
+        let unknown = parse("provider = \"nope\"");
+        assert!(format!("{:#}", unknown.validate().unwrap_err()).contains("unknown"));
+        let unknown_member = parse("[providers.g]\ntype = \"composite\"\nproviders = [\"x\"]");
+        assert!(unknown_member.validate().is_err());
+        let empty = parse("[providers.g]\ntype = \"composite\"\nproviders = []");
+        assert!(empty.validate().is_err());
+        let blank = parse("[providers.c]\ntype = \"command\"\ncommand = \" \"");
+        assert!(blank.validate().is_err());
+        let bad_url = parse("[providers.h]\ntype = \"http\"\nurl = \"qa.example.com\"");
+        assert!(bad_url.validate().is_err());
+        let cycle = parse(
+            "[providers.a]\ntype = \"composite\"\nproviders = [\"b\"]\n\
+             [providers.b]\ntype = \"composite\"\nproviders = [\"agent\", \"a\"]",
+        );
+        assert!(format!("{:#}", cycle.validate().unwrap_err()).contains("includes itself"));
+        assert!(toml::from_str::<VerificationConfig>("[providers.x]\ntype = \"ftp\"").is_err());
+    }
+
+    #[test]
+    fn provider_is_chosen_by_task_type() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let config = config_with(
+            &dir,
+            parse(
+                "provider = \"lint\"\n[task_types]\nfrontend = \"agent\"\n\
+                 [providers.lint]\ntype = \"command\"\ncommand = \"true\"",
+            ),
+        );
+        let knowledge = &config.ralph_config.knowledge;
+        let verification = &config.ralph_config.verification;
+        let styling = dag::create_task(&db, "Fix css spacing", None, None, 0).unwrap();
+        let docs = dag::create_task(&db, "Update readme", None, None, 0).unwrap();
+        assert_eq!(verification.provider_for(knowledge, &styling), AGENT);
+        assert_eq!(verification.provider_for(knowledge, &docs), "lint");
+        assert_eq!(for_task(&config, &docs).unwrap().name(), "lint");
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn command_provider_passes_on_zero_exit_and_reports_output_otherwise() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let task = dag::create_task(&db, "Add login", None, None, 0).unwrap();
+        let files = vec!["src/a.rs".to_string(), "src/b.rs".to_string()];
+
+        let check = "test \"$RALPH_TASK_ID\" = \"$EXPECTED\" && \
+                     test \"$(echo \"$RALPH_TASK_FILES\" | wc -l)\" -eq 2";
+        let config = config_with(
+            &dir,
+            parse(&format!(
+                "provider = \"check\"\n[providers.check]\ntype = \"command\"\ncommand = '{}'",
+                check.replace("$EXPECTED", &task.id)
+            )),
+        );
+        let result = run(&config, &task, &files).await.unwrap();
+        assert!(result.passed, "{}", result.reason);
+
+        let config = config_with(
+            &dir,
+            parse(
+                "provider = \"check\"\n[providers.check]\ntype = \"command\"\n\
+                 command = \"echo 2 tests failed; exit 3\"",
+            ),
+        );
+        let result = run(&config, &task, &files).await.unwrap();
+        assert!(!result.passed);
+        assert!(
+            result.reason.contains("2 tests failed"),
+            "{}",
+            result.reason
+        );
+
+        let config = config_with(
+            &dir,
+            parse(
+                "provider = \"check\"\n[providers.check]\ntype = \"command\"\n\
+                 command = \"sleep 5\"\ntimeout_secs = 1",
+            ),
+        );
+        let result = run(&config, &task, &files).await.unwrap();
+        assert!(!result.passed);
+        assert!(result.reason.contains("timed out"), "{}", result.reason);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn composite_stops_at_first_failure() {
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let task = dag::create_task(&db, "Add login", None, None, 0).unwrap();
+        let config = config_with(
+            &dir,
+            parse(
+                "provider = \"gate\"\n\
+                 [providers.ok]\ntype = \"command\"\ncommand = \"true\"\n\
+                 [providers.lint]\ntype = \"command\"\ncommand = \"exit 1\"\n\
+                 [providers.marker]\ntype = \"command\"\ncommand = \"touch ran\"\n\
+                 [providers.gate]\ntype = \"composite\"\nproviders = [\"ok\", \"lint\", \"marker\"]",
+            ),
+        );
+        let result = run(&config, &task, &[]).await.unwrap();
+        assert!(!result.passed);
+        assert!(result.reason.starts_with("lint: "), "{}", result.reason);
+        assert!(!dir.path().join("ran").exists());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn http_provider_posts_task_and_reads_verdict() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/verify", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some(v) = line.to_lowercase().strip_prefix("content-length:") {
+                    length = v.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let answer =
+                r#"{"passed":true,"criteria":[{"position":1,"passed":false,"note":"no test"}]}"#;
+            (&stream)
+                .write_all(
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                        answer.len(),
+                        answer
+                    )
+                    .as_bytes(),
+                )
+                .unwrap();
+            String::from_utf8(body).unwrap()
+        });
+
+        let dir = TempDir::new().unwrap();
+        let db = init_db(dir.path().join("progress.db").to_str().unwrap()).unwrap();
+        let task = dag::create_task(&db, "Add login", None, None, 0).unwrap();
+        let config = config_with(
+            &dir,
+            parse(&format!(
+                "provider = \"qa\"\n[providers.qa]\ntype = \"http\"\nurl = \"{url}\""
+            )),
+        );
+        let result = run(&config, &task, &["src/login.rs".to_string()])
+            .await
+            .unwrap();
+        assert!(!result.passed);
+        assert_eq!(result.reason, "Acceptance criteria failed: #1");
+        assert_eq!(result.criteria.len(), 1);
+
+        let body: serde_json::Value = serde_json::from_str(&server.join().unwrap()).unwrap();
+        assert_eq!(body["task"]["id"], task.id.as_str());
+        assert_eq!(body["files"][0], "src/login.rs");
+        assert_eq!(body["plan"], "the plan");
+        assert!(body["spec"].is_null());
+    }
+}
@@ -135,7 +135,7 @@ async fn test_iteration_with_mock_agent() {
     );
 
     // Sigil extraction should find task_done.
-    let sigils = extract_sigils(&result.full_text);
+    let sigils = extract_sigils(&result.full_text, &[]);
     assert_eq!(
         sigils.task_done,
         Some("t-123".to_string()),
@@ -166,7 +166,7 @@ async fn test_iteration_task_failed_sigil() {
     .await
     .expect("run_autonomous should succeed");
 
-    let sigils = extract_sigils(&result.full_text);
+    let sigils = extract_sigils(&result.full_text, &[]);
     assert!(
         sigils.task_done.is_none(),
         "task_done should be None, got: {:?}",
@@ -205,7 +205,7 @@ async fn test_iteration_no_sigil() {
         result.full_text
     );
 
-    let sigils = extract_sigils(&result.full_text);
+    let sigils = extract_sigils(&result.full_text, &[]);
     assert!(sigils.task_done.is_none(), "task_done should be None");
     assert!(sigils.task_failed.is_none(), "task_failed should be None");
     assert!(
@@ -247,7 +247,7 @@ async fn test_iteration_journal_and_knowledge() {
     .await
     .expect("run_autonomous should succeed");
 
-    let sigils = extract_sigils(&result.full_text);
+    let sigils = extract_sigils(&result.full_text, &[]);
 
     assert_eq!(
         sigils.journal_notes,
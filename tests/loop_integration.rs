@@ -0,0 +1,219 @@
+//! End-to-end tests of `ralph run` driven by the scripted mock agent.
+//!
+//! Each test initialises a project in a temp directory, adds a task, and runs
+//! the real `ralph` binary with `ralph-mock-agent` replaying a fixture from
+//! `tests/fixtures/scripts/`. This covers the whole loop: claiming, prompt
+//! building, tool calls, sigil handling, retries and task state.
+//!
+//! **Requires the `test-mock-agents` feature to build the mock binary.**
+//! Run with:
+//!   cargo test --features test-mock-agents --test loop_integration
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use tempfile::TempDir;
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Path to the compiled `ralph-mock-agent` example binary.
+///
+/// See acp_integration.rs for how the path is derived from `current_exe()`.
+fn mock_agent_path() -> PathBuf {
+    let exe = std::env::current_exe().expect("could not read current_exe path");
+    exe.parent()
+        .and_then(|deps| deps.parent())
+        .map(|d| d.join("examples").join("ralph-mock-agent"))
+        .expect("could not navigate to target directory from current_exe")
+}
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/scripts")
+        .join(name)
+}
+
+fn sh_quote(s: &str) -> String {
+    shlex::try_quote(s)
+        .expect("no nul bytes in test string")
+        .to_string()
+}
+
+/// A freshly initialised project with verification off and no retry backoff.
+struct Project {
+    dir: TempDir,
+}
+
+impl Project {
+    fn new() -> Self {
+        let project = Self {
+            dir: TempDir::new().unwrap(),
+        };
+        std::fs::create_dir_all(project.path().join(".tmp")).unwrap();
+        let out = project.ralph(&["--no-ui", "init"]);
+        assert!(out.status.success(), "init failed: {}", stderr(&out));
+        std::fs::write(
+            project.path().join(".ralph.toml"),
+            "[execution]\nverify = false\nagent_retry_backoff_secs = 0\n",
+        )
+        .unwrap();
+        project
+    }
+
+    fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    fn ralph(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_ralph"))
+            .args(args)
+            .current_dir(self.path())
+            .env("RALPH_NO_INPUT", "1")
+            .env("TMPDIR", self.path().join(".tmp"))
+            .env_remove("CI")
+            .output()
+            .expect("failed to run ralph")
+    }
+
+    fn add_task(&self, title: &str) -> String {
+        let out = self.ralph(&["--no-ui", "task", "add", title]);
+        assert!(out.status.success(), "task add failed: {}", stderr(&out));
+        String::from_utf8(out.stdout).unwrap().trim().to_string()
+    }
+
+    /// Run `target` with the mock agent replaying `script`. The script is
+    /// copied into the project so its turn state stays with this test.
+    fn run(&self, target: &str, script: &str) -> Output {
+        let copy = self.path().join(script);
+        std::fs::copy(fixture(script), &copy).unwrap();
+        let agent = format!(
+            "env MOCK_SCRIPT={} {}",
+            sh_quote(copy.to_str().unwrap()),
+            sh_quote(mock_agent_path().to_str().unwrap())
+        );
+        self.ralph(&["--no-ui", "run", target, "--agent", &agent])
+    }
+
+    /// Turns of `script` the agent consumed, in order.
+    fn turns_used(&self, script: &str) -> Vec<usize> {
+        std::fs::read_to_string(self.path().join(format!("{script}.state")))
+            .unwrap_or_default()
+            .lines()
+            .map(|l| l.parse().unwrap())
+            .collect()
+    }
+
+    /// `(input, output)` tokens the journal recorded for `task_id`, summed.
+    fn journal_tokens(&self, task_id: &str) -> (i64, i64) {
+        let db = rusqlite::Connection::open(self.path().join(".ralph/progress.db")).unwrap();
+        db.query_row(
+            "SELECT TOTAL(input_tokens), TOTAL(output_tokens) FROM journal WHERE task_id = ?",
+            [task_id],
+            |row| Ok((row.get::<_, f64>(0)? as i64, row.get::<_, f64>(1)? as i64)),
+        )
+        .unwrap()
+    }
+
+    fn task(&self, id: &str) -> serde_json::Value {
+        let out = self.ralph(&["--no-ui", "task", "show", id, "--json"]);
+        assert!(out.status.success(), "task show failed: {}", stderr(&out));
+        serde_json::from_slice(&out.stdout).unwrap()
+    }
+}
+
+fn stderr(out: &Output) -> String {
+    String::from_utf8_lossy(&out.stderr).into_owned()
+}
+
+// ============================================================================
+// Full loop
+// ============================================================================
+
+/// One iteration writes a file through the tool provider, runs a terminal
+/// command, and completes the task with journal and knowledge sigils.
+#[test]
+fn run_completes_task_and_applies_sigils() {
+    let project = Project::new();
+    let id = project.add_task("Write the notes file");
+
+    let out = project.run(&id, "complete_with_sigils.yaml");
+    assert!(out.status.success(), "run failed: {}", stderr(&out));
+
+    assert_eq!(project.task(&id)["status"], "done");
+    assert_eq!(project.turns_used("complete_with_sigils.yaml"), [0]);
+    assert_eq!(
+        std::fs::read_to_string(project.path().join("notes.txt")).unwrap(),
+        "written by the mock agent\n"
+    );
+    let knowledge: Vec<String> = std::fs::read_dir(project.path().join(".ralph/knowledge"))
+        .unwrap()
+        .flatten()
+        .map(|e| std::fs::read_to_string(e.path()).unwrap())
+        .collect();
+    assert!(
+        knowledge
+            .iter()
+            .any(|entry| entry.contains("Notes live in notes.txt")),
+        "knowledge entry not written: {knowledge:?}"
+    );
+}
+
+/// A transient agent error is retried within the iteration, and the retry's
+/// completion counts.
+#[test]
+fn run_retries_transient_agent_failures() {
+    let project = Project::new();
+    let id = project.add_task("Survive an overloaded agent");
+
+    let out = project.run(&id, "transient_then_done.yaml");
+    assert!(out.status.success(), "run failed: {}", stderr(&out));
+
+    assert_eq!(project.task(&id)["status"], "done");
+    assert_eq!(project.turns_used("transient_then_done.yaml"), [0, 1]);
+    assert!(stderr(&out).contains("retry 1/"), "{}", stderr(&out));
+}
+
+/// An iteration without a completion sigil releases the task, and the next
+/// iteration picks it up again.
+#[test]
+fn run_picks_up_incomplete_task_again() {
+    let project = Project::new();
+    let id = project.add_task("Finish on the second try");
+
+    let out = project.run(&id, "incomplete_then_done.yaml");
+    assert!(out.status.success(), "run failed: {}", stderr(&out));
+
+    assert_eq!(project.task(&id)["status"], "done");
+    assert_eq!(project.turns_used("incomplete_then_done.yaml"), [0, 1]);
+    assert!(stderr(&out).contains("incomplete (no sigil)"));
+}
+
+/// `<task-failed>` fails the task and ends the run without another prompt.
+#[test]
+fn run_stops_on_failed_task() {
+    let project = Project::new();
+    let id = project.add_task("Give up");
+
+    project.run(&id, "failed.yaml");
+
+    assert_eq!(project.task(&id)["status"], "failed");
+    assert_eq!(project.turns_used("failed.yaml"), [0]);
+}
+
+/// An agent without terminal support asks Ralph to run a command, and gets
+/// its output back as the next prompt of the same session.
+#[test]
+fn run_runs_commands_for_agent_without_terminal() {
+    let project = Project::new();
+    let id = project.add_task("Run a command through the harness");
+
+    let out = project.run(&id, "harness_commands.yaml");
+    assert!(out.status.success(), "run failed: {}", stderr(&out));
+
+    assert_eq!(project.task(&id)["status"], "done");
+    assert_eq!(project.turns_used("harness_commands.yaml"), [0, 1]);
+    // Each round's usage is counted exactly once
+    assert_eq!(project.journal_tokens(&id), (300, 30));
+}
@@ -0,0 +1,340 @@
+//! Scripted mock ACP agent for end-to-end tests of the run loop.
+//!
+//! Where `mock-agent` answers every prompt the same way, this agent replays a
+//! YAML fixture (path in `MOCK_SCRIPT`) of turns. Each prompt consumes the
+//! first unused turn whose `when` substring appears in the prompt (or that has
+//! no `when`), runs its steps and ends with its stop reason:
+//!
+//! ```yaml
+//! terminal: false                  # optional: advertise no terminal support
+//! turns:
+//!   - when: "**ID:**"              # optional prompt substring
+//!     steps:
+//!       - thought: Reading the task
+//!       - read: src/lib.rs         # fs/read_text_file
+//!       - write: notes.txt         # fs/write_text_file
+//!         content: hello
+//!       - terminal: echo hello     # terminal/create_terminal, waits for exit
+//!       - text: "<task-done>{task_id}</task-done>"
+//!     stop: end_turn               # or max_tokens, max_turn_requests, refusal, cancelled
+//!     usage: { input_tokens: 100, output_tokens: 20 }  # optional, sent as `_meta.usage`
+//!   - error: Overloaded            # fail the prompt with this message
+//!   - exit: 1                      # kill the process mid-prompt
+//! ```
+//!
+//! `{task_id}` in text and thoughts is replaced with the task ID from the
+//! prompt's `**ID:**` line, or from the last prompt that had one. Ralph spawns a fresh agent per iteration, so
+//! consumed turns are recorded in a state file (`MOCK_STATE`, default
+//! `<script>.state`) that persists across processes. A prompt with no turn
+//! left fails with "mock script exhausted".
+//!
+//! Build: `cargo build --features test-mock-agents --examples`
+
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+use agent_client_protocol::{
+    Agent, AgentCapabilities, AgentSideConnection, AuthenticateRequest, AuthenticateResponse,
+    CancelNotification, Client, ContentBlock, ContentChunk, CreateTerminalRequest, Implementation,
+    InitializeRequest, InitializeResponse, NewSessionRequest, NewSessionResponse, PromptRequest,
+    PromptResponse, ReadTextFileRequest, SessionId, SessionNotification, SessionUpdate, StopReason,
+    TextContent, WaitForTerminalExitRequest, WriteTextFileRequest,
+};
+use async_trait::async_trait;
+use serde::Deserialize;
+use tokio::task::LocalSet;
+use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
+
+use agent_client_protocol::Error as AcpError;
+type Result<T> = std::result::Result<T, AcpError>;
+
+// ============================================================================
+// Script format
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Script {
+    /// Whether the agent can use the client's terminals; `false` is sent as
+    /// `_meta.terminal` in the initialize capabilities.
+    #[serde(default = "default_true")]
+    terminal: bool,
+    turns: Vec<Turn>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Turn {
+    /// Only prompts containing this text consume the turn.
+    #[serde(default)]
+    when: Option<String>,
+    #[serde(default)]
+    steps: Vec<Step>,
+    #[serde(default = "default_stop")]
+    stop: StopReason,
+    /// Fail the prompt request with this message after the steps.
+    #[serde(default)]
+    error: Option<String>,
+    /// Exit the agent process with this code after the steps.
+    #[serde(default)]
+    exit: Option<i32>,
+    /// Token usage reported for the turn, as `_meta.usage`.
+    #[serde(default)]
+    usage: Option<serde_json::Value>,
+}
+
+fn default_stop() -> StopReason {
+    StopReason::EndTurn
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Step {
+    Text { text: String },
+    Thought { thought: String },
+    Read { read: PathBuf },
+    Write { write: PathBuf, content: String },
+    Terminal { terminal: String },
+    Sleep { delay_ms: u64 },
+}
+
+fn load_script() -> (Script, PathBuf) {
+    let path = PathBuf::from(std::env::var("MOCK_SCRIPT").expect("MOCK_SCRIPT must be set"));
+    let text = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("cannot read {}: {e}", path.display()));
+    let script: Script = serde_yaml::from_str(&text)
+        .unwrap_or_else(|e| panic!("invalid script {}: {e}", path.display()));
+    let state = std::env::var("MOCK_STATE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(format!("{}.state", path.display())));
+    (script, state)
+}
+
+/// Indices of turns consumed by earlier prompts, in any agent process.
+fn consumed(state: &PathBuf) -> Vec<usize> {
+    std::fs::read_to_string(state)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| l.trim().parse().ok())
+        .collect()
+}
+
+fn mark_consumed(state: &PathBuf, index: usize) {
+    use std::io::Write;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(state)
+        .unwrap_or_else(|e| panic!("cannot write {}: {e}", state.display()));
+    writeln!(file, "{index}").expect("write state file");
+}
+
+/// The task ID from the prompt's `**ID:** t-…` line, or empty.
+fn task_id(prompt: &str) -> String {
+    prompt
+        .split("**ID:** ")
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next())
+        .unwrap_or_default()
+        .to_string()
+}
+
+// ============================================================================
+// Mock agent implementation
+// ============================================================================
+
+struct ScriptedAgent {
+    /// Shared slot for the AgentSideConnection.
+    /// See mock_agent.rs for the design rationale.
+    conn: Rc<RefCell<Option<Rc<AgentSideConnection>>>>,
+    /// Task ID of the last prompt that named one, for follow-up prompts.
+    task_id: RefCell<String>,
+}
+
+impl ScriptedAgent {
+    async fn update(&self, session_id: &SessionId, update: SessionUpdate) -> Result<()> {
+        let conn = { self.conn.borrow().as_ref().cloned() };
+        match conn {
+            Some(conn) => {
+                conn.session_notification(SessionNotification::new(session_id.clone(), update))
+                    .await
+            }
+            None => Ok(()),
+        }
+    }
+
+    async fn run_step(&self, session_id: &SessionId, step: &Step, task_id: &str) -> Result<()> {
+        let conn = self
+            .conn
+            .borrow()
+            .as_ref()
+            .cloned()
+            .expect("AgentSideConnection not yet initialised");
+        let chunk = |text: &str| {
+            ContentChunk::new(ContentBlock::Text(TextContent::new(
+                text.replace("{task_id}", task_id),
+            )))
+        };
+        match step {
+            Step::Text { text } => {
+                self.update(session_id, SessionUpdate::AgentMessageChunk(chunk(text)))
+                    .await?;
+            }
+            Step::Thought { thought } => {
+                self.update(session_id, SessionUpdate::AgentThoughtChunk(chunk(thought)))
+                    .await?;
+            }
+            // Tool errors are part of the scenario (e.g. a denied write), so
+            // they don't fail the turn.
+            Step::Read { read } => {
+                let _ = conn
+                    .read_text_file(ReadTextFileRequest::new(session_id.clone(), read.clone()))
+                    .await;
+            }
+            Step::Write { write, content } => {
+                let _ = conn
+                    .write_text_file(WriteTextFileRequest::new(
+                        session_id.clone(),
+                        write.clone(),
+                        content.clone(),
+                    ))
+                    .await;
+            }
+            Step::Terminal { terminal } => {
+                if let Ok(resp) = conn
+                    .create_terminal(CreateTerminalRequest::new(
+                        session_id.clone(),
+                        terminal.clone(),
+                    ))
+                    .await
+                {
+                    let _ = conn
+                        .wait_for_terminal_exit(WaitForTerminalExitRequest::new(
+                            session_id.clone(),
+                            resp.terminal_id,
+                        ))
+                        .await;
+                }
+            }
+            Step::Sleep { delay_ms } => {
+                tokio::time::sleep(Duration::from_millis(*delay_ms)).await;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl Agent for ScriptedAgent {
+    async fn initialize(&self, args: InitializeRequest) -> Result<InitializeResponse> {
+        let (script, _) = load_script();
+        let mut caps = AgentCapabilities::new();
+        if !script.terminal {
+            let mut meta = serde_json::Map::new();
+            meta.insert("terminal".to_string(), serde_json::Value::Bool(false));
+            caps = caps.meta(meta);
+        }
+        Ok(InitializeResponse::new(args.protocol_version)
+            .agent_capabilities(caps)
+            .agent_info(Implementation::new("ralph-mock-agent", "0.1.0")))
+    }
+
+    async fn authenticate(&self, _args: AuthenticateRequest) -> Result<AuthenticateResponse> {
+        Ok(AuthenticateResponse::default())
+    }
+
+    async fn new_session(&self, _args: NewSessionRequest) -> Result<NewSessionResponse> {
+        Ok(NewSessionResponse::new(SessionId::new(
+            "mock-session-script-1",
+        )))
+    }
+
+    async fn prompt(&self, args: PromptRequest) -> Result<PromptResponse> {
+        let prompt: String = args
+            .prompt
+            .iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text(text) => Some(text.text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let (script, state) = load_script();
+        let used = consumed(&state);
+        let Some((index, turn)) = script.turns.iter().enumerate().find(|(i, turn)| {
+            !used.contains(i)
+                && turn
+                    .when
+                    .as_ref()
+                    .is_none_or(|w| prompt.contains(w.as_str()))
+        }) else {
+            return Err(AcpError::new(-32603, "mock script exhausted"));
+        };
+        mark_consumed(&state, index);
+
+        let named = task_id(&prompt);
+        if !named.is_empty() {
+            *self.task_id.borrow_mut() = named;
+        }
+        let task_id = self.task_id.borrow().clone();
+        for step in &turn.steps {
+            self.run_step(&args.session_id, step, &task_id).await?;
+        }
+        if let Some(code) = turn.exit {
+            std::process::exit(code);
+        }
+        if let Some(message) = &turn.error {
+            return Err(AcpError::new(-32603, message.clone()));
+        }
+        let mut response = PromptResponse::new(turn.stop);
+        if let Some(usage) = &turn.usage {
+            let mut meta = serde_json::Map::new();
+            meta.insert("usage".to_string(), usage.clone());
+            response = response.meta(meta);
+        }
+        Ok(response)
+    }
+
+    async fn cancel(&self, _args: CancelNotification) -> Result<()> {
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Entry point
+// ============================================================================
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let local = LocalSet::new();
+    local
+        .run_until(async {
+            let conn_slot: Rc<RefCell<Option<Rc<AgentSideConnection>>>> =
+                Rc::new(RefCell::new(None));
+
+            let agent = ScriptedAgent {
+                conn: conn_slot.clone(),
+                task_id: RefCell::new(String::new()),
+            };
+
+            let stdin = tokio::io::stdin();
+            let stdout = tokio::io::stdout();
+
+            let (conn, io_task) =
+                AgentSideConnection::new(agent, stdout.compat_write(), stdin.compat(), |fut| {
+                    tokio::task::spawn_local(fut);
+                });
+
+            *conn_slot.borrow_mut() = Some(Rc::new(conn));
+
+            let _ = io_task.await;
+        })
+        .await;
+}